@@ -7,6 +7,8 @@ import argparse
 import subprocess
 import re
 import shlex
+import shutil
+import tempfile
 from pathlib import Path
 from copy import copy
 import itertools
@@ -170,12 +172,16 @@ class TestCase(object):
         # Check for a `FAIL` directive.
         self.should_fail = self.regex_fail.search(self.content) is not None
 
-        # Check for a `RUN` directive.
-        self.run = self.regex_run.search(self.content)
-        if self.run:
-            self.run = self.run.group(1)
-        else:
-            self.run = "moore --syntax %s"
+        # Check for `RUN` directives. Multiple commands are executed one after
+        # the other, and their output is checked as a whole. `%t` refers to a
+        # temporary directory that is shared by the commands of the test.
+        self.runs = self.regex_run.findall(self.content)
+        if not self.runs:
+            self.runs = ["moore --syntax-only %s"]
+        self.run = self.runs[0]
+        self.tmpdir = None
+        if not self.ignore and any("%t" in run for run in self.runs):
+            self.tmpdir = tempfile.mkdtemp(prefix="moore-test-")
 
         # Execution results.
         self.timeout = False
@@ -184,31 +190,38 @@ class TestCase(object):
         self.stdout = ""
         self.stderr = ""
 
-        # Process the run command.
-        self.cmd = shlex.split(self.run)
-        if self.cmd[0] == "moore":
-            self.cmd[0] = "{}{}".format(prefix, self.cmd[0])
-        self.cmd = [path if x == "%s" else x for x in self.cmd]
-        cmd = list()
-        for x in self.cmd:
+        # Process the run commands.
+        self.cmds = [self.process(run) for run in self.runs]
+        self.cmd = self.cmds[0]
+
+    def process(self, run):
+        cmd = shlex.split(run)
+        if cmd[0] == "moore":
+            cmd[0] = "{}{}".format(prefix, cmd[0])
+        if self.tmpdir:
+            cmd = [x.replace("%t", self.tmpdir) for x in cmd]
+        cmd = [self.path if x == "%s" else x for x in cmd]
+        expanded = list()
+        for x in cmd:
             if "*" in x.__str__():
                 self.info += "Arg: `{}` is a glob pattern\n".format(x)
-                cmd += self.dir.glob(x)
+                expanded += self.dir.glob(x)
             elif (self.dir/x).exists():
                 self.info += "Arg: `{}` is a path\n".format(x)
-                cmd.append(self.dir/x)
+                expanded.append(self.dir/x)
             else:
                 self.info += "Arg: `{}` is a plain argument\n".format(x)
-                cmd.append(x)
-        self.cmd = list([x.__str__() for x in cmd])
+                expanded.append(x)
+        return list([x.__str__() for x in expanded])
 
-    def launch(self):
+    def launch(self, cmd=None):
         if self.ignore:
             return
+        cmd = cmd or self.cmd
         try:
-            self.info += "Command: {}\n".format(self.cmd)
+            self.info += "Command: {}\n".format(cmd)
             self.proc = subprocess.Popen(
-                [x.__str__() for x in self.cmd],
+                [x.__str__() for x in cmd],
                 universal_newlines=True,
                 stdin=subprocess.DEVNULL,
                 stdout=subprocess.PIPE,
@@ -222,19 +235,31 @@ class TestCase(object):
     def finish(self):
         if self.ignore or self.failed:
             return
-        try:
-            self.stdout, self.stderr = self.proc.communicate(timeout=10)
-        except subprocess.TimeoutExpired as e:
-            self.proc.kill()
-            self.stdout, self.stderr = self.proc.communicate()
-            self.timeout = True
-            self.failed = True
-            self.info += "Timeout"
+        self.failed = False
+        for i, cmd in enumerate(self.cmds):
+            if i > 0:
+                self.launch(cmd)
+                if self.failed:
+                    break
+            try:
+                stdout, stderr = self.proc.communicate(timeout=10)
+                self.stdout += stdout
+                self.stderr += stderr
+            except subprocess.TimeoutExpired as e:
+                self.proc.kill()
+                self.proc.communicate()
+                self.timeout = True
+                self.failed = True
+                self.info += "Timeout"
+                break
+
+            # Check the return code.
+            self.failed = self.failed or (self.proc.returncode != 0)
+        if self.tmpdir:
+            shutil.rmtree(self.tmpdir, ignore_errors=True)
+        if self.timeout:
             return
 
-        # Check the return code.
-        self.failed = (self.proc.returncode != 0)
-
         # Check if we were supposed to fail, but didn't.
         if self.failed != self.should_fail:
             self.info += "Failed: {}\nShould Fail: {}\n".format(self.failed, self.should_fail)
@@ -5,16 +5,23 @@
 #[macro_use]
 extern crate log;
 
-use clap::{App, Arg, ArgMatches};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use llhd;
 use llhd::opt::{Pass, PassContext};
+use moore::cache::Cache;
 use moore::common::score::NodeRef;
+use moore::common::source::{display_path, Span};
+use moore::common::timing::Stopwatch;
+use moore::config::Config;
 use moore::errors::*;
+use moore::file_list::FileList;
 use moore::name::Name;
 use moore::score::{ScoreBoard, ScoreContext};
 use moore::svlog::{hir::Visitor as _, QueryDatabase as _};
 use moore::*;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug)]
 enum Language {
@@ -35,11 +42,31 @@ fn main() {
     );
     builder.try_init().unwrap();
 
-    // Parse the command-line arguments.
-    let matches = App::new(env!("CARGO_PKG_NAME"))
+    // Describe the available warning categories in the help text.
+    let mut warnings_help = String::from(
+        "Configure how warnings are reported. `-W<category>` enables a category of \
+         warnings, `-Wno-<category>` disables it, and `-Werror=<category>` reports its \
+         warnings as errors. `-Wall` enables all categories, `-Werror` reports all warnings \
+         as errors. The categories are:\n",
+    );
+    for lint in lints::ALL {
+        warnings_help.push_str(&format!("\n    {:20}{}", lint.name, lint.desc));
+    }
+
+    // Describe the command-line arguments.
+    let mut app = App::new(env!("CARGO_PKG_NAME"))
         .version(clap::crate_version!())
         .author(clap::crate_authors!())
         .about(clap::crate_description!())
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .after_help(
+            "EXIT STATUS:\n    \
+             0      Success\n    \
+             1      Invalid options, findings of `lint`, or other failures\n    \
+             2      The input files could not be read or parsed\n    \
+             3      The design could not be checked, elaborated, or lowered\n    \
+             101    Internal compiler error",
+        )
         .arg(
             Arg::with_name("trace_scoreboard")
                 .long("trace-scoreboard")
@@ -66,28 +93,168 @@ fn main() {
                 .global(true),
         )
         .arg(
-            Arg::with_name("inc")
-                .short("I")
-                .value_name("DIR")
-                .help("Add a search path for SystemVerilog includes")
-                .multiple(true)
+            Arg::with_name("no-config")
+                .long("no-config")
+                .help("Ignore the project configuration file")
+                .long_help(
+                    "Ignore the project configuration file. Otherwise the options in the \
+                     first `moore.toml` found in the working directory or its parents are \
+                     merged with the ones given on the command line.",
+                )
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("color")
+                .long("color")
+                .value_name("WHEN")
+                .help("When to color diagnostics")
+                .long_help(
+                    "When to color diagnostics. With `auto`, diagnostics are colored if they \
+                     are printed to a terminal and the `NO_COLOR` environment variable is not \
+                     set.",
+                )
+                .possible_values(&["auto", "always", "never"])
+                .default_value("auto")
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("tab-width")
+                .long("tab-width")
+                .value_name("N")
+                .help("Number of columns between tab stops in diagnostics")
+                .long_help(
+                    "Number of columns between tab stops. Tabs in source excerpts are expanded \
+                     accordingly, and the column numbers in diagnostics count tabs such that \
+                     they match the columns an editor with the same setting displays.",
+                )
+                .default_value("4")
+                .takes_value(true)
+                .validator(|v| match v.parse::<usize>() {
+                    Ok(0) => Err("tab width must be at least 1".to_string()),
+                    Ok(_) => Ok(()),
+                    Err(e) => Err(e.to_string()),
+                })
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("diagnostics-format")
+                .long("diagnostics-format")
+                .value_name("FORMAT")
+                .help("Format in which diagnostics are printed")
+                .long_help(
+                    "Format in which diagnostics are printed. With `json`, every diagnostic is \
+                     printed to stderr as a JSON object on a single line.",
+                )
+                .possible_values(&["human", "json"])
+                .default_value("human")
+                .takes_value(true)
+                .number_of_values(1)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("sarif")
+                .long("sarif")
+                .value_name("FILE")
+                .help("Write the emitted diagnostics to a SARIF log")
                 .takes_value(true)
                 .number_of_values(1),
         )
         .arg(
-            Arg::with_name("def")
-                .short("D")
-                .value_name("DEFINE")
-                .help("Define a preprocesor macro")
+            Arg::with_name("manifest")
+                .long("manifest")
+                .value_name("FILE")
+                .help("Write a JSON summary of the invocation for build systems")
+                .long_help(
+                    "Write a JSON summary of the invocation for build systems, listing the \
+                     exit code, the input files, the elaborated modules, the outputs \
+                     produced, and the number of diagnostics.",
+                )
+                .takes_value(true)
+                .number_of_values(1)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("lint-baseline")
+                .long("lint-baseline")
+                .value_name("FILE")
+                .help("Only report warnings that are not in a baseline file")
+                .long_help(
+                    "Only report warnings that are not in a baseline file. If the file does \
+                     not exist, the current warnings are recorded in it instead of being \
+                     reported. Delete the file to record a new baseline.",
+                )
+                .takes_value(true)
+                .number_of_values(1)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("error-limit")
+                .long("error-limit")
+                .value_name("N")
+                .help("Stop after N errors")
+                .takes_value(true)
+                .number_of_values(1)
+                .validator(|v| v.parse::<usize>().map(|_| ()).map_err(|e| e.to_string()))
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("stream-diagnostics")
+                .long("stream-diagnostics")
+                .help("Print diagnostics as they are emitted instead of sorted by location at the end")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("time-passes")
+                .long("time-passes")
+                .help("Report the time spent in each pass")
+                .long_help(
+                    "Report the time spent in each pass, such as parsing, name checking, and \
+                     code generation of every module, followed by the queries that took the \
+                     most time. Query times exclude the time spent in the queries they call.",
+                )
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("memory-report")
+                .long("memory-report")
+                .help("Report the memory used by the compiler's data structures")
+                .long_help(
+                    "Report the number and size of the tokens, AST and HIR nodes, interned \
+                     types, and parameter environments created during compilation, broken down \
+                     by the kind of node. This shows which parts of a design are heavy to \
+                     process.",
+                )
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("warnings")
+                .short("W")
+                .value_name("WARNING")
+                .help("Configure how warnings are reported")
+                .long_help(&warnings_help)
                 .multiple(true)
                 .takes_value(true)
-                .number_of_values(1),
+                .number_of_values(1)
+                .global(true),
         )
+        .args(&input_args())
         .arg(
             Arg::with_name("preproc")
                 .short("E")
                 .help("Write preprocessed input files to stdout"),
         )
+        .arg(
+            Arg::with_name("line-markers")
+                .long("line-markers")
+                .requires("preproc")
+                .help("Insert `line directives into the preprocessed output")
+                .long_help(
+                    "Insert `line directives into the output of `-E` wherever it does not \
+                     continue with the next line of the same input file, such that other \
+                     tools can map the output back to the input files.",
+                ),
+        )
         .arg(
             Arg::with_name("dump-ast")
                 .long("dump-ast")
@@ -95,9 +262,40 @@ fn main() {
         )
         .arg(
             Arg::with_name("check-syntax")
-                .long("syntax")
+                .long("syntax-only")
+                .alias("syntax")
                 .help("Preprocess and check the input for syntax errors"),
         )
+        .arg(
+            Arg::with_name("elaborate-only")
+                .long("elaborate-only")
+                .conflicts_with_all(&["check-syntax", "preproc"])
+                .help("Elaborate the design and report errors without emitting LLHD")
+                .long_help(
+                    "Elaborate the design and report errors without emitting LLHD. The \
+                     modules given with `-e` are elaborated, or the ones which are not \
+                     instantiated anywhere if none are given. Other outputs requested with \
+                     `--emit`, `--port-map`, or `--dpi-header` are still produced.",
+                ),
+        )
+        .arg(
+            Arg::with_name("cache-dir")
+                .long("cache-dir")
+                .value_name("DIR")
+                .help("Skip unchanged files and designs that checked cleanly before")
+                .long_help(
+                    "Remember in DIR which input files parsed without any diagnostics. With \
+                     `--syntax-only`, files whose content, included files, macro definitions, \
+                     and include paths are unchanged since then are not parsed again. Prints \
+                     how many files were found in the cache. When only LLHD is emitted, the \
+                     output of a design that elaborated without any diagnostics is remembered \
+                     as well, and reused as long as the files defining the modules reachable \
+                     from the top-level modules, and any packages and interfaces, are \
+                     unchanged.",
+                )
+                .takes_value(true)
+                .number_of_values(1),
+        )
         .arg(
             Arg::with_name("emit_pkgs")
                 .long("emit-pkgs")
@@ -125,19 +323,247 @@ fn main() {
             Arg::with_name("elaborate")
                 .short("e")
                 .long("elaborate")
+                .visible_alias("top")
                 .value_name("ENTITY")
                 .help("Elaborate an entity or module")
+                .long_help(
+                    "Elaborate an entity or module. May be given multiple times to elaborate \
+                     several top-level modules into one design. If no module is given but an \
+                     output is requested with `--emit`, the modules which are not instantiated \
+                     in any other module are elaborated. Otherwise all modules are checked.",
+                )
                 .multiple(true)
                 .takes_value(true)
                 .number_of_values(1),
         )
         .arg(
-            Arg::with_name("INPUT")
-                .help("The input files to compile")
+            Arg::with_name("emit")
+                .long("emit")
+                .value_name("WHAT")
+                .help("Output to produce for the elaborated design")
+                .possible_values(&[
+                    "llhd",
+                    "fsm",
+                    "fsm-json",
+                    "clocks",
+                    "reachability",
+                    "ast-json",
+                    "hir",
+                    "mir",
+                    "hierarchy",
+                    "hierarchy-json",
+                    "hierarchy-dot",
+                ])
+                .default_value("llhd")
                 .multiple(true)
-                .required(true),
+                .takes_value(true)
+                .number_of_values(1),
         )
-        .get_matches();
+        .arg(
+            Arg::with_name("port-map")
+                .long("port-map")
+                .value_name("FILE")
+                .help("Write a map from LLHD entity ports to source ports")
+                .takes_value(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("dpi-header")
+                .long("dpi-header")
+                .value_name("FILE")
+                .help("Write a C header declaring the DPI functions and tasks")
+                .takes_value(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("entity-policy")
+                .long("entity-policy")
+                .value_name("POLICY")
+                .help("How module specializations are mapped to LLHD entities")
+                .long_help(
+                    "How module specializations are mapped to LLHD entities. With \
+                     `per-instance`, one entity is emitted for every set of parameters a \
+                     module is instantiated with. With `shared`, specializations which \
+                     produce the same code share one entity.",
+                )
+                .possible_values(&["per-instance", "shared"])
+                .default_value("per-instance")
+                .takes_value(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("coverage")
+                .long("coverage")
+                .help("Instrument cover statements with hit counters"),
+        )
+        .subcommand(
+            SubCommand::with_name("scan")
+                .about("Print the order in which the input files have to be compiled")
+                .long_about(
+                    "Print the order in which the input files have to be compiled. A file is \
+                     compiled after the files that define the modules and interfaces it \
+                     instantiates and the packages it refers to. Otherwise the files keep \
+                     the order in which they are given.",
+                )
+                .args(&input_args())
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Format in which the compile order is printed")
+                        .long_help(
+                            "Format in which the compile order is printed. With `text`, the \
+                             files are printed one per line. With `json`, the units each file \
+                             defines and uses and the files it depends on are printed as well.",
+                        )
+                        .possible_values(&["text", "json"])
+                        .default_value("text")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("tags")
+                .about("Print an index of the definitions in the input files")
+                .long_about(
+                    "Print an index of the definitions in the input files, such as modules, \
+                     ports, signals, parameters, and typedefs, for editors and code browsers \
+                     to navigate by.",
+                )
+                .args(&input_args())
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Format in which the index is printed")
+                        .long_help(
+                            "Format in which the index is printed. With `ctags`, the \
+                             definitions are printed as a sorted tags file. With `json`, the \
+                             names which refer to each definition are printed as well.",
+                        )
+                        .possible_values(&["ctags", "json"])
+                        .default_value("ctags")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("lsp")
+                .about("Run a language server on standard input and output")
+                .long_about(
+                    "Run a language server on standard input and output. The server \
+                     publishes the diagnostics for the open SystemVerilog documents, finds \
+                     the definition of names, and describes them when hovering.",
+                )
+                .args(&preproc_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("fmt")
+                .about("Format SystemVerilog files")
+                .long_about(
+                    "Format SystemVerilog files. Lines are indented according to the nesting \
+                     of blocks and brackets, and lines longer than the line width are broken \
+                     after commas. Comments and the spacing within lines are kept. The \
+                     formatted files are printed to stdout, unless `--in-place` or `--check` \
+                     is given.",
+                )
+                .arg(
+                    Arg::with_name("indent")
+                        .long("indent")
+                        .value_name("N")
+                        .help("Number of spaces per level of indentation")
+                        .default_value("2")
+                        .takes_value(true)
+                        .validator(|v| v.parse::<usize>().map(|_| ()).map_err(|e| e.to_string())),
+                )
+                .arg(
+                    Arg::with_name("line-width")
+                        .long("line-width")
+                        .value_name("N")
+                        .help("Number of columns after which lines are broken")
+                        .default_value("100")
+                        .takes_value(true)
+                        .validator(|v| v.parse::<usize>().map(|_| ()).map_err(|e| e.to_string())),
+                )
+                .arg(
+                    Arg::with_name("in-place")
+                        .short("i")
+                        .long("in-place")
+                        .help("Overwrite the files with their formatted version"),
+                )
+                .arg(
+                    Arg::with_name("check")
+                        .long("check")
+                        .conflicts_with("in-place")
+                        .help(
+                            "Report the files which are not formatted, and fail if there are any",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("INPUT")
+                        .help("The files to format")
+                        .multiple(true)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("lint")
+                .about("Check the input files for problems without generating code")
+                .long_about(
+                    "Check the input files for problems without generating code. The input \
+                     files are parsed and the names and types in every module are checked. \
+                     The rules which are checked are the warning categories listed in `moore \
+                     --help`, whose levels can be configured with `-W` and a configuration \
+                     file. Fails if any warnings \
+                     or errors are reported.",
+                )
+                .args(&input_args())
+                .arg(
+                    Arg::with_name("config")
+                        .long("config")
+                        .value_name("FILE")
+                        .help("Read the levels of the rules from a file")
+                        .long_help(
+                            "Read the levels of the rules from a file. Each line of the file \
+                             assigns a level to a rule, as in `trailing-comma = deny`, where \
+                             the level is `allow`, `warn`, or `deny`. The rule `all` sets the \
+                             level of every rule. Lines starting with `#` are ignored. `-W` \
+                             options take precedence over the file.",
+                        )
+                        .takes_value(true)
+                        .number_of_values(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("explain")
+                .about("Describe a diagnostic code in detail")
+                .arg(
+                    Arg::with_name("CODE")
+                        .help("The code to describe, e.g. `SV0001`")
+                        .required(true),
+                ),
+        );
+
+    // Parse the command-line arguments, and merge them with the options of the
+    // project configuration file.
+    let args: Vec<_> = std::env::args_os().collect();
+    let matches = app
+        .get_matches_from_safe_borrow(&args)
+        .unwrap_or_else(|e| e.exit());
+    let config = match std::env::current_dir()
+        .ok()
+        .and_then(|dir| Config::find(&dir))
+    {
+        Some(path) if !matches.is_present("no-config") => {
+            let config = Config::open(&path);
+            Some((path, config))
+        }
+        _ => None,
+    };
+    let matches = match config {
+        Some((_, Ok(ref config))) => app
+            .get_matches_from_safe_borrow(merge_config(args, &matches, config))
+            .unwrap_or_else(|e| e.exit()),
+        _ => matches,
+    };
 
     // Configure the session.
     let mut session = Session::new();
@@ -161,114 +587,531 @@ fn main() {
         };
     }
     session.opts.opt_level = matches.value_of("opt-level").unwrap().parse().unwrap();
+    session.opts.coverage = matches.is_present("coverage");
+    session.opts.keep_diagnostics = matches.is_present("sarif");
+    session.opts.error_limit = matches.value_of("error-limit").map(|v| v.parse().unwrap());
+    session.opts.stream_diagnostics = matches.is_present("stream-diagnostics");
+    session.opts.time_passes = matches.is_present("time-passes");
+    session.opts.memory_report = matches.is_present("memory-report");
+    if let Some((path, config)) = config {
+        let applied = config.and_then(|config| config.apply_lints(&mut session.opts.lint_levels));
+        if let Err(msg) = applied {
+            session.emit(DiagBuilder2::error(format!(
+                "invalid project configuration `{}`: {}",
+                path.display(),
+                msg
+            )));
+            session.flush_diagnostics();
+            std::process::exit(1);
+        }
+    }
+    if let Some(path) = matches
+        .subcommand_matches("lint")
+        .and_then(|m| m.value_of("config"))
+    {
+        let applied = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| session.opts.lint_levels.apply_config(&text));
+        if let Err(msg) = applied {
+            session.emit(DiagBuilder2::error(format!(
+                "invalid lint configuration `{}`: {}",
+                path, msg
+            )));
+            session.flush_diagnostics();
+            std::process::exit(1);
+        }
+    }
+    for flag in matches.values_of("warnings").into_iter().flatten() {
+        if let Err(msg) = session.opts.lint_levels.apply_flag(flag) {
+            session.emit(
+                DiagBuilder2::error(msg).add_note("See `moore --help` for a list of categories"),
+            );
+            session.flush_diagnostics();
+            std::process::exit(1);
+        }
+    }
+    if let Some(path) = matches.value_of("lint-baseline") {
+        let baseline = match std::fs::read_to_string(path) {
+            Ok(text) => lints::LintBaseline::parse(&text),
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Ok(lints::LintBaseline::record())
+            }
+            Err(e) => Err(e.to_string()),
+        };
+        match baseline {
+            Ok(baseline) => *session.lint_baseline.borrow_mut() = Some(baseline),
+            Err(e) => {
+                session.emit(DiagBuilder2::error(format!(
+                    "unable to read lint baseline `{}`: {}",
+                    path, e
+                )));
+                session.flush_diagnostics();
+                std::process::exit(1);
+            }
+        }
+    }
+    session.opts.diag_format = match matches.value_of("diagnostics-format").unwrap() {
+        "human" => DiagFormat::Human,
+        "json" => DiagFormat::Json,
+        _ => unreachable!(),
+    };
 
-    // Invoke the compiler.
-    score(&session, &matches);
-}
+    session.opts.diag_style = DiagStyle {
+        color: match matches.value_of("color").unwrap() {
+            "always" => true,
+            "never" => false,
+            _ => {
+                std::env::var_os("NO_COLOR").map_or(true, |v| v.is_empty())
+                    && atty::is(atty::Stream::Stderr)
+            }
+        },
+        width: terminal_width(),
+        tab_width: matches.value_of("tab-width").unwrap().parse().unwrap(),
+    };
 
-fn score(sess: &Session, matches: &ArgMatches) {
-    use crate::name::get_name_table;
-    let svlog_arenas = svlog::GlobalArenas::default();
+    // Describe a diagnostic code if so requested.
+    if let Some(matches) = matches.subcommand_matches("explain") {
+        explain(&session, matches.value_of("CODE").unwrap());
+        return;
+    }
 
-    // Prepare a list of include paths.
-    let include_paths: Vec<_> = match matches.values_of("inc") {
-        Some(args) => args.map(|x| std::path::Path::new(x)).collect(),
-        None => Vec::new(),
+    // Invoke the compiler, reporting panics as internal compiler errors.
+    let mut manifest = Manifest::default();
+    let mut result = match ice::catch(|| match matches.subcommand() {
+        ("scan", Some(matches)) => scan(&session, matches),
+        ("tags", Some(matches)) => tags(&session, matches),
+        ("lsp", Some(matches)) => lsp(&session, matches).map_err(|()| Failure::Other),
+        ("fmt", Some(matches)) => format_files(&session, matches).map_err(|()| Failure::Other),
+        ("lint", Some(matches)) => lint(&session, matches),
+        _ => score(&session, &matches, &mut manifest),
+    }) {
+        Ok(result) => result,
+        Err(panic) if panic.fatal => Err(Failure::Elaboration),
+        Err(panic) => {
+            session.flush_diagnostics();
+            let context = session.crash_context.borrow();
+            session.emit(panic.to_diag(env!("CARGO_PKG_VERSION"), &context));
+            Err(Failure::Ice)
+        }
     };
+    if result != Err(Failure::Ice) {
+        session.flush_diagnostics();
+        session.print_timings();
+        session.print_memory_report();
+        let reports = write_reports(&session, &matches, &mut manifest);
+        if result.is_ok() {
+            result = reports;
+        }
+    }
 
-    let defines: Vec<_> = match matches.values_of("def") {
-        Some(args) => args
-            .map(|x| {
-                let mut iter = x.split("=");
-                (iter.next().unwrap(), iter.next())
-            })
-            .collect(),
-        None => Vec::new(),
+    // Write the manifest if so requested.
+    let exit_code = match result {
+        Ok(()) => 0,
+        Err(failure) => failure.exit_code(),
     };
+    if let Some(path) = matches.value_of("manifest") {
+        session.flush_diagnostics();
+        let json = serde_json::json!({
+            "version": 1,
+            "exit-code": exit_code,
+            "inputs": manifest.inputs,
+            "tops": manifest.tops,
+            "outputs": manifest
+                .outputs
+                .iter()
+                .map(|(kind, path)| serde_json::json!({ "kind": kind, "path": path }))
+                .collect::<Vec<_>>(),
+            "diagnostics": session.summary(),
+        });
+        if let Err(e) = std::fs::write(path, format!("{:#}\n", json)) {
+            session.emit(DiagBuilder2::error(format!(
+                "unable to write manifest to `{}`: {}",
+                path, e
+            )));
+            session.print_summary();
+            std::process::exit(Failure::Other.exit_code());
+        }
+    }
+    session.print_summary();
+    std::process::exit(exit_code);
+}
 
-    // Establish into which library the entities will be compiled. Later on this
-    // should be made configurable per entity.
-    let lib = get_name_table().intern(matches.value_of("lib").unwrap_or("work"), true);
+/// Why an invocation failed, which determines the exit code.
+///
+/// Errors in the command line or the configuration also exit with code 1.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Failure {
+    /// The input files could not be read, preprocessed, or parsed.
+    Syntax,
+    /// The design could not be checked, elaborated, or lowered.
+    Elaboration,
+    /// The compiler crashed.
+    Ice,
+    /// Any other failure, such as a report that could not be written or a
+    /// finding of `lint`.
+    Other,
+}
 
-    // Parse the input files.
-    let mut failed = false;
-    let mut asts = Vec::new();
-    for filename in matches.values_of("INPUT").unwrap() {
-        // Detect the file type.
-        let language = match Path::new(&filename).extension().and_then(|s| s.to_str()) {
-            Some("sv") | Some("svh") => Language::SystemVerilog,
-            Some("v") | Some("vh") => Language::Verilog,
-            Some("vhd") | Some("vhdl") => Language::Vhdl,
-            Some(ext) => {
-                sess.emit(
-                    DiagBuilder2::warning(format!("ignoring `{}`", filename)).add_note(format!(
-                        "Cannot determine language from extension `.{}`",
-                        ext
-                    )),
-                );
-                continue;
-            }
-            None => {
-                sess.emit(
-                    DiagBuilder2::warning(format!("ignoring `{}`", filename)).add_note(format!(
-                        "No file extension that can be used to guess language"
-                    )),
-                );
-                continue;
+impl Failure {
+    fn exit_code(self) -> i32 {
+        match self {
+            Failure::Other => 1,
+            Failure::Syntax => 2,
+            Failure::Elaboration => 3,
+            Failure::Ice => ice::ICE_EXIT_CODE,
+        }
+    }
+}
+
+/// What an invocation produced, as recorded in the manifest.
+#[derive(Default, Debug)]
+struct Manifest {
+    /// The input files that were parsed.
+    inputs: Vec<String>,
+    /// The modules that were elaborated.
+    tops: Vec<String>,
+    /// The outputs that were produced, together with the file they were
+    /// written to, or `None` if they were printed to stdout.
+    outputs: Vec<(String, Option<String>)>,
+}
+
+/// Write the SARIF log and the lint baseline if so requested.
+fn write_reports(
+    session: &Session,
+    matches: &ArgMatches,
+    manifest: &mut Manifest,
+) -> Result<(), Failure> {
+    // Write the emitted diagnostics to a SARIF log if so requested.
+    if let Some(path) = matches.value_of("sarif") {
+        let sarif = sarif::to_sarif(
+            &session.diagnostics.borrow(),
+            &session.suppressed_diagnostics.borrow(),
+        );
+        let written = std::fs::File::create(path).and_then(|f| {
+            serde_json::to_writer_pretty(std::io::BufWriter::new(f), &sarif).map_err(Into::into)
+        });
+        if let Err(e) = written {
+            session.emit(DiagBuilder2::error(format!(
+                "unable to write SARIF log to `{}`: {}",
+                path, e
+            )));
+            return Err(Failure::Other);
+        }
+        manifest
+            .outputs
+            .push(("sarif".to_string(), Some(path.to_string())));
+    }
+
+    // Write the recorded warnings to the baseline file if it did not exist.
+    if let Some(path) = matches.value_of("lint-baseline") {
+        let baseline = session.lint_baseline.borrow_mut().take().unwrap();
+        if baseline.recording {
+            let written = std::fs::write(path, format!("{:#}\n", baseline.to_json()));
+            if let Err(e) = written {
+                session.emit(DiagBuilder2::error(format!(
+                    "unable to write lint baseline `{}`: {}",
+                    path, e
+                )));
+                return Err(Failure::Other);
             }
-        };
+            session.emit(DiagBuilder2::note(format!(
+                "recorded {} warning(s) in lint baseline `{}`",
+                baseline.len(),
+                path
+            )));
+            manifest
+                .outputs
+                .push(("lint-baseline".to_string(), Some(path.to_string())));
+        }
+    }
+    Ok(())
+}
 
-        // Add the file to the source manager.
-        let sm = source::get_source_manager();
-        let source = match sm.open(&filename) {
-            Some(s) => s,
-            None => panic!("Unable to open input file '{}'", filename),
-        };
+/// Insert the options of a project configuration file into the command-line
+/// arguments.
+///
+/// The options are inserted before the ones given on the command line, right
+/// after the name of the subcommand if there is one, such that the options on
+/// the command line take precedence. Only the options which the subcommand
+/// accepts are inserted.
+fn merge_config(
+    mut args: Vec<std::ffi::OsString>,
+    matches: &ArgMatches,
+    config: &Config,
+) -> Vec<std::ffi::OsString> {
+    let (subcommand, sub_matches) = matches.subcommand();
+    let mut extra = vec![];
+    let option = |extra: &mut Vec<String>, flag: &str, values: &[String]| {
+        for value in values {
+            extra.push(flag.to_string());
+            extra.push(value.clone());
+        }
+    };
+    if ["", "scan", "tags", "lint", "lsp"].contains(&subcommand) {
+        option(&mut extra, "-I", &config.include_dirs);
+        option(&mut extra, "-D", &config.defines);
+    }
+    if ["", "scan", "tags", "lint"].contains(&subcommand) {
+        option(&mut extra, "-F", &config.file_lists);
+        option(&mut extra, "-y", &config.library_dirs);
+        option(&mut extra, "-v", &config.library_files);
+        extra.extend(config.files.iter().cloned());
+    }
+    if subcommand.is_empty() {
+        if matches.occurrences_of("elaborate") == 0 {
+            option(&mut extra, "-e", &config.tops);
+        }
+        if matches.occurrences_of("emit") == 0 {
+            option(&mut extra, "--emit", &config.emit);
+        }
+        if let (0, Some(level)) = (matches.occurrences_of("opt-level"), config.opt_level) {
+            option(&mut extra, "-O", &[level.to_string()]);
+        }
+    }
+    let index = match sub_matches {
+        Some(_) => args.iter().position(|a| a == subcommand).unwrap() + 1,
+        None => 1,
+    };
+    args.splice(index..index, extra.into_iter().map(Into::into));
+    args
+}
 
-        // Parse the file.
-        match language {
-            Language::SystemVerilog | Language::Verilog => {
-                let preproc = svlog::preproc::Preprocessor::new(source, &include_paths, &defines);
-                if matches.is_present("preproc") {
-                    for token in preproc {
-                        print!(
-                            "{}",
-                            match token {
-                                Ok((_token, span)) => span.extract(),
-                                Err(diag) => {
-                                    sess.emit(diag);
-                                    failed = true;
-                                    continue;
-                                }
-                            }
-                        );
-                    }
-                    continue;
-                }
+/// The arguments that configure the preprocessor, shared by the compiler and
+/// the `scan` and `lsp` subcommands.
+fn preproc_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("inc")
+            .short("I")
+            .value_name("DIR")
+            .help("Add a search path for SystemVerilog includes")
+            .multiple(true)
+            .takes_value(true)
+            .number_of_values(1),
+        Arg::with_name("def")
+            .short("D")
+            .value_name("DEFINE")
+            .help("Define a preprocesor macro")
+            .multiple(true)
+            .takes_value(true)
+            .number_of_values(1),
+    ]
+}
+
+/// The arguments that select the input files and how they are preprocessed,
+/// shared by the compiler and the `scan` subcommand.
+fn input_args() -> Vec<Arg<'static, 'static>> {
+    let mut args = preproc_args();
+    args.extend(vec![
+        Arg::with_name("file-list")
+            .short("f")
+            .value_name("FILE")
+            .help("Read input files and options from a file list")
+            .long_help(
+                "Read input files and options from a file list. The list may contain \
+                 source files, `+incdir+`, `+define+`, `+libext+`, `-I`, `-D`, `-y`, and \
+                 `-v` options, references \
+                 to environment variables such as `$HOME` or `${HOME}`, and nested `-f` \
+                 and `-F` file lists. Relative paths are relative to the working \
+                 directory.",
+            )
+            .multiple(true)
+            .takes_value(true)
+            .number_of_values(1),
+        Arg::with_name("file-list-relative")
+            .short("F")
+            .value_name("FILE")
+            .help("Read input files and options from a file list, relative to its location")
+            .long_help(
+                "Read input files and options from a file list, like `-f`, but with \
+                 relative paths being relative to the directory that contains the file \
+                 list.",
+            )
+            .multiple(true)
+            .takes_value(true)
+            .number_of_values(1),
+        Arg::with_name("library-dir")
+            .short("y")
+            .value_name("DIR")
+            .help("Search a directory for modules that are not defined in the input files")
+            .long_help(
+                "Search a directory for modules that are instantiated but not defined in \
+                 the input files. A module `foo` is looked up in a file `foo` with one of \
+                 the extensions given with `+libext+`, by default `.sv` and `.v`.",
+            )
+            .multiple(true)
+            .takes_value(true)
+            .number_of_values(1),
+        Arg::with_name("library-file")
+            .short("v")
+            .value_name("FILE")
+            .help("Search a file for modules that are not defined in the input files")
+            .long_help(
+                "Search a file for modules that are instantiated but not defined in the \
+                 input files. The file is only compiled if it defines one of them.",
+            )
+            .multiple(true)
+            .takes_value(true)
+            .number_of_values(1),
+        Arg::with_name("INPUT")
+            .help("The input files to compile")
+            .long_help(
+                "The input files to compile. For compatibility with other compilers, \
+                 `+incdir+<dir>`, `+define+<name>=<value>`, and `+libext+<ext>` options \
+                 are accepted among the input files.",
+            )
+            .multiple(true),
+    ]);
+    args
+}
 
-                let lexer = svlog::lexer::Lexer::new(preproc);
-                match svlog::parser::parse(lexer, &svlog_arenas.ast) {
-                    Ok(x) => asts.push(score::Ast::Svlog(x)),
-                    Err(()) => failed = true,
+/// Format SystemVerilog files.
+fn format_files(sess: &Session, matches: &ArgMatches) -> Result<(), ()> {
+    let options = svlog::format::FormatOptions {
+        indent: matches.value_of("indent").unwrap().parse().unwrap(),
+        line_width: matches.value_of("line-width").unwrap().parse().unwrap(),
+    };
+    let mut failed = false;
+    for path in matches.values_of("INPUT").unwrap() {
+        let input = match std::fs::read_to_string(path) {
+            Ok(input) => input,
+            Err(e) => {
+                sess.emit(DiagBuilder2::error(format!(
+                    "unable to read `{}`: {}",
+                    path, e
+                )));
+                failed = true;
+                continue;
+            }
+        };
+        let output = svlog::format::format(&input, &options);
+        if matches.is_present("check") {
+            if output != input {
+                sess.emit(DiagBuilder2::error(format!("`{}` is not formatted", path)));
+                failed = true;
+            }
+        } else if matches.is_present("in-place") {
+            if output != input {
+                if let Err(e) = std::fs::write(path, output) {
+                    sess.emit(DiagBuilder2::error(format!(
+                        "unable to write `{}`: {}",
+                        path, e
+                    )));
+                    failed = true;
                 }
             }
-            Language::Vhdl => match vhdl::syntax::parse(source) {
-                Ok(x) => asts.push(score::Ast::Vhdl(x)),
-                Err(()) => failed = true,
-            },
+        } else {
+            print!("{}", output);
         }
     }
-    if failed || sess.failed() {
-        std::process::exit(1);
+    if failed {
+        Err(())
+    } else {
+        Ok(())
+    }
+}
+
+/// Run a language server on standard input and output.
+fn lsp(sess: &Session, matches: &ArgMatches) -> Result<(), ()> {
+    let include_dirs = matches
+        .values_of("inc")
+        .into_iter()
+        .flatten()
+        .map(String::from)
+        .collect();
+    let defines = matches
+        .values_of("def")
+        .into_iter()
+        .flatten()
+        .map(|x| {
+            let mut iter = x.split("=");
+            (
+                iter.next().unwrap().to_string(),
+                iter.next().map(String::from),
+            )
+        })
+        .collect();
+    let mut server = moore::lsp::Server::new(include_dirs, defines);
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    let mut input = stdin.lock();
+    let mut output = stdout.lock();
+    server
+        .run(&mut input, &mut output)
+        .map_err(|e| sess.emit(DiagBuilder2::error(format!("language server: {}", e))))
+}
+
+/// Determine the width of the terminal that diagnostics are printed to.
+fn terminal_width() -> Option<usize> {
+    if let Some(columns) = std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return Some(columns);
     }
+    #[cfg(unix)]
+    unsafe {
+        let mut size: libc::winsize = std::mem::zeroed();
+        if libc::ioctl(libc::STDERR_FILENO, libc::TIOCGWINSZ, &mut size) == 0 && size.ws_col > 0 {
+            return Some(size.ws_col as usize);
+        }
+    }
+    None
+}
+
+/// Print the extended description of a diagnostic code.
+fn explain(sess: &Session, code: &str) {
+    match codes::lookup(code) {
+        Some(code) => println!("{}: {}\n\n{}", code, code.summary, code.explanation),
+        None => {
+            sess.emit(
+                DiagBuilder2::error(format!("`{}` is not a known diagnostic code", code))
+                    .add_note("Codes look like `SV0001` and are printed next to diagnostics"),
+            );
+            sess.flush_diagnostics();
+            std::process::exit(1);
+        }
+    }
+}
+
+fn score(sess: &Session, matches: &ArgMatches, manifest: &mut Manifest) -> Result<(), Failure> {
+    use crate::name::get_name_table;
+    let svlog_arenas = svlog::GlobalArenas::default();
+
+    let (asts, mut cache) =
+        parse_inputs_cached(sess, matches, &svlog_arenas.ast).map_err(|()| Failure::Syntax)?;
+    let asts: Vec<_> = asts
+        .into_iter()
+        .map(|(name, ast)| {
+            manifest.inputs.push(name);
+            ast
+        })
+        .collect();
     if matches.is_present("preproc") {
-        return;
+        return Ok(());
     }
+    let mut failed = false;
+
+    // Establish into which library the entities will be compiled. Later on this
+    // should be made configurable per entity.
+    let lib = get_name_table().intern(matches.value_of("lib").unwrap_or("work"), true);
+    let ieee = parse_ieee(sess, &asts).map_err(|()| Failure::Syntax)?;
 
     // Dump the AST if so requested.
     if matches.is_present("dump-ast") {
         println!("{:#99?}", asts);
     }
+    let emit: Vec<_> = matches.values_of("emit").unwrap().collect();
+    if emit.contains(&"ast-json") {
+        let files: Vec<_> = asts
+            .iter()
+            .filter_map(|ast| match *ast {
+                score::Ast::Svlog(ref x) => Some(x),
+                _ => None,
+            })
+            .collect();
+        emit_ast_json(&files);
+        manifest.outputs.push(("ast-json".to_string(), None));
+        if emit.iter().all(|&e| e == "ast-json") {
+            return Ok(());
+        }
+    }
 
     if matches.is_present("emit_pkgs") {
         vhdl::debug::emit_pkgs(
@@ -284,7 +1127,7 @@ fn score(sess: &Session, matches: &ArgMatches) {
 
     // Stop processing if requested.
     if matches.is_present("check-syntax") {
-        std::process::exit(0);
+        return Ok(());
     }
 
     // Create the scoreboard and add the initial map of libraries.
@@ -303,41 +1146,709 @@ fn score(sess: &Session, matches: &ArgMatches) {
             vhdl_phases: &vhdl_phases,
             svlog: &svlog_sb,
         };
-        let lib_id = ctx.add_library(lib, &asts);
-        if let Some(names) = matches.values_of("elaborate") {
+        let lib_id = {
+            let _timer = sess.time_pass("index");
+            add_ieee_library(&ctx, lib, &ieee);
+            ctx.add_library(lib, &asts)
+        };
+        let tops = if let Some(names) = matches.values_of("elaborate") {
             debug!("lib_id = {:?}", lib_id);
             debug!("{:?}", sb);
+            let mut tops = vec![];
             for name in names {
-                match elaborate_name(&ctx, lib_id, name) {
-                    Ok(_) => (),
+                let _timer = sess.time_pass(format!("elaborate `{}`", name));
+                match elaborate_name(&ctx, lib_id, name, &mut tops) {
+                    Ok(_) => manifest.tops.push(name.to_string()),
                     Err(_) => failed = true,
                 };
             }
-            if sess.failed() {
+            tops
+        } else if matches.occurrences_of("emit") > 0 || matches.is_present("elaborate-only") {
+            // Without any modules to elaborate but an output or elaboration
+            // requested, use the ones which are not instantiated anywhere.
+            let tops = svlog::reachability::uninstantiated_modules(ctx.svlog);
+            let names: HashMap<_, _> = ctx.svlog.modules().map(|(name, id)| (id, name)).collect();
+            manifest
+                .tops
+                .extend(tops.iter().map(|id| names[id].to_string()));
+            let names: Vec<_> = tops.iter().map(|id| format!("`{}`", names[id])).collect();
+            if names.is_empty() {
+                sess.emit(DiagBuilder2::error(
+                    "no top module given, and no module that is not instantiated elsewhere",
+                ));
                 failed = true;
+            } else {
+                sess.emit(DiagBuilder2::note(format!(
+                    "no top module given; elaborating {}",
+                    names.join(", ")
+                )));
+            }
+            tops
+        } else {
+            // Without any modules to elaborate, check all of them.
+            check_modules(sess, ctx.svlog);
+            vec![]
+        };
+        if !failed && !tops.is_empty() {
+            let _timer = sess.time_pass("elaborate");
+            failed |=
+                elaborate_modules(&ctx, &asts, &tops, matches, manifest, cache.as_mut()).is_err();
+        }
+        if matches
+            .values_of("emit")
+            .unwrap()
+            .any(|e| e == "reachability")
+        {
+            emit_reachability(ctx.svlog, &tops);
+            manifest.outputs.push(("reachability".to_string(), None));
+        }
+    }
+    if sess.measure_memory() {
+        for (category, stats) in svlog_arenas.statistics() {
+            for stats in stats {
+                sess.timings
+                    .add_memory(category, stats.name, stats.count, stats.bytes);
             }
         }
     }
     if failed || sess.failed() {
-        std::process::exit(1);
+        return Err(Failure::Elaboration);
     }
 
-    // Extract the populated LLHD modules from the scoreboards and link them
-    // together.
-    let _vhdl_module = vhdl_sb.llmod.into_inner();
+    // Emit the code generated for the elaborated VHDL units.
+    vhdl_sb.write_llhd(&mut std::io::stdout().lock());
 
-    // Emit the module.
-    // TODO: Re-enable this once the VHDL crate has been moved over to llhd v0.8.
-    // llhd::assembly::write_module(&mut std::io::stdout().lock(), &vhdl_module);
+    if sess.failed() {
+        return Err(Failure::Elaboration);
+    }
+    Ok(())
+}
 
+/// Check the names and types in every SystemVerilog module.
+fn check_modules(sess: &Session, cx: &svlog::GlobalContext) {
+    let mut modules: Vec<_> = cx.modules().collect();
+    modules.sort_by_key(|&(_, id)| id);
+    for (name, id) in modules {
+        let _timer = sess.time_pass(format!("check `{}`", name));
+        cx.check_module_names(id);
+    }
+}
+
+/// Check the input files for problems, failing if any are reported.
+fn lint(sess: &Session, matches: &ArgMatches) -> Result<(), Failure> {
+    use crate::name::get_name_table;
+    let svlog_arenas = svlog::GlobalArenas::default();
+    let asts: Vec<_> = parse_inputs(sess, matches, &svlog_arenas.ast)
+        .map_err(|()| Failure::Syntax)?
+        .into_iter()
+        .map(|(_, ast)| ast)
+        .collect();
+    let ieee = parse_ieee(sess, &asts).map_err(|()| Failure::Syntax)?;
+    let arenas = score::Arenas::new();
+    let sb = ScoreBoard::new(&arenas);
+    let vhdl_sb = vhdl::score::ScoreBoard::new(&arenas.vhdl);
+    let svlog_sb = svlog::GlobalContext::new(&sess, &svlog_arenas);
+    let vhdl_phases = vhdl::lazy::LazyPhaseTable::new(&vhdl_sb);
+    let ctx = ScoreContext {
+        sess,
+        sb: &sb,
+        vhdl: &vhdl_sb,
+        vhdl_phases: &vhdl_phases,
+        svlog: &svlog_sb,
+    };
+    let lib = get_name_table().intern(matches.value_of("lib").unwrap_or("work"), true);
+    {
+        let _timer = sess.time_pass("index");
+        add_ieee_library(&ctx, lib, &ieee);
+        ctx.add_library(lib, &asts);
+    }
+    check_modules(sess, ctx.svlog);
     if sess.failed() {
-        std::process::exit(1);
+        return Err(Failure::Elaboration);
+    }
+    {
+        let _timer = sess.time_pass("lints");
+        for ast in &asts {
+            if let score::Ast::Svlog(ref file) = *ast {
+                svlog::unused::check_unused(ctx.svlog, file);
+                svlog::drivers::check_drivers(ctx.svlog, file);
+            }
+        }
+    }
+    if sess.num_emitted() > 0 {
+        return Err(Failure::Other);
+    }
+    Ok(())
+}
+
+/// Print the order in which the input files have to be compiled.
+fn scan(sess: &Session, matches: &ArgMatches) -> Result<(), Failure> {
+    let svlog_arenas = svlog::GlobalArenas::default();
+    let asts = parse_inputs(sess, matches, &svlog_arenas.ast).map_err(|()| Failure::Syntax)?;
+    let files: Vec<_> = asts
+        .iter()
+        .map(|(_, ast)| match *ast {
+            score::Ast::Svlog(ref file) => svlog::deps::FileDeps::scan(file),
+            score::Ast::Vhdl(_) => Default::default(),
+        })
+        .collect();
+    let order = svlog::deps::CompileOrder::new(&files);
+    let paths: Vec<_> = asts.iter().map(|(path, _)| display_path(path)).collect();
+    for &index in &order.cyclic {
+        sess.emit(DiagBuilder2::warning(format!(
+            "`{}` is part of a dependency cycle and compiled before the files it depends on",
+            paths[index]
+        )));
+    }
+
+    if matches.value_of("format") == Some("json") {
+        let names = |names: &[Name]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        let json = serde_json::json!({
+            "files": paths
+                .iter()
+                .zip(&files)
+                .zip(&order.deps)
+                .map(|((path, file), deps)| serde_json::json!({
+                    "path": path,
+                    "defines": names(&file.defined),
+                    "uses": names(&file.used),
+                    "depends": deps.iter().map(|&dep| &paths[dep]).collect::<Vec<_>>(),
+                }))
+                .collect::<Vec<_>>(),
+            "order": order.order.iter().map(|&index| &paths[index]).collect::<Vec<_>>(),
+        });
+        println!("{:#}", json);
+    } else {
+        for &index in &order.order {
+            println!("{}", paths[index]);
+        }
+    }
+    Ok(())
+}
+
+/// Print an index of the definitions in the input files.
+fn tags(sess: &Session, matches: &ArgMatches) -> Result<(), Failure> {
+    let svlog_arenas = svlog::GlobalArenas::default();
+    let asts = parse_inputs(sess, matches, &svlog_arenas.ast).map_err(|()| Failure::Syntax)?;
+    let files: Vec<_> = asts
+        .iter()
+        .filter_map(|(_, ast)| match *ast {
+            score::Ast::Svlog(ref file) => Some(file),
+            score::Ast::Vhdl(_) => None,
+        })
+        .collect();
+    let cx = svlog::GlobalContext::new(sess, &svlog_arenas);
+    cx.add_files(files.iter().cloned());
+
+    // Describe a location as the path and human-readable line and column.
+    let location = |span: Span| {
+        let (line, column, _) = span.begin().human();
+        (span.source.get_path(), line, column)
+    };
+
+    if matches.value_of("format") == Some("json") {
+        let location_json = |span: Span| {
+            let (path, line, column) = location(span);
+            serde_json::json!({ "file": &*path, "line": line, "column": column })
+        };
+        let json = serde_json::json!({
+            "definitions": files
+                .iter()
+                .flat_map(|file| svlog::lookup::definitions(&cx, file))
+                .map(|def| {
+                    let mut json = location_json(def.name.span);
+                    json["name"] = def.name.value.to_string().into();
+                    json["kind"] = tag_kind(def).1.into();
+                    json
+                })
+                .collect::<Vec<_>>(),
+            "references": files
+                .iter()
+                .flat_map(|file| svlog::lookup::references(&cx, file))
+                .map(|reference| {
+                    let mut json = location_json(reference.name.span);
+                    json["name"] = reference.name.value.to_string().into();
+                    json["definition"] = location_json(reference.def.name.span);
+                    json
+                })
+                .collect::<Vec<_>>(),
+        });
+        println!("{:#}", json);
+    } else {
+        let mut tags: Vec<_> = files
+            .iter()
+            .flat_map(|file| svlog::lookup::definitions(&cx, file))
+            .map(|def| {
+                let (path, line, _) = location(def.name.span);
+                let kind = tag_kind(def).0;
+                (def.name.value.to_string(), path, line, kind)
+            })
+            .collect();
+        tags.sort();
+        println!("!_TAG_FILE_FORMAT\t2\t/extended format/");
+        println!("!_TAG_FILE_SORTED\t1\t/0=unsorted, 1=sorted, 2=foldcase/");
+        println!("!_TAG_PROGRAM_NAME\tmoore\t//");
+        for (name, path, line, kind) in tags {
+            println!("{}\t{}\t{};\"\t{}", name, path, line, kind);
+        }
+    }
+    Ok(())
+}
+
+/// The ctags kind letter and name of a definition.
+///
+/// The letters follow the ones Universal Ctags uses for SystemVerilog, such
+/// that editors show the same kinds for both.
+fn tag_kind(def: &svlog::resolver::Def) -> (char, &'static str) {
+    use svlog::lookup::SymbolKind;
+    match SymbolKind::of(def) {
+        SymbolKind::Module => ('m', "module"),
+        SymbolKind::Interface => ('I', "interface"),
+        SymbolKind::Modport => ('M', "modport"),
+        SymbolKind::Package => ('K', "package"),
+        SymbolKind::Net => ('n', "net"),
+        SymbolKind::Variable | SymbolKind::Genvar => ('r', "register"),
+        SymbolKind::ValueParam | SymbolKind::EnumConst => ('c', "constant"),
+        SymbolKind::TypeParam | SymbolKind::Typedef => ('T', "typedef"),
+        SymbolKind::Function => ('f', "function"),
+        SymbolKind::Task => ('t', "task"),
+        SymbolKind::Port => ('p', "port"),
+        SymbolKind::Instance => ('i', "instance"),
+        SymbolKind::Block => ('b', "block"),
+    }
+}
+
+/// Parse the input files, and the library files which define modules that
+/// are missing from them.
+///
+/// Returns the name of each parsed file together with its AST.
+fn parse_inputs<'a>(
+    sess: &Session,
+    matches: &ArgMatches,
+    arena: &'a svlog::ast::Arena<'a>,
+) -> Result<Vec<(String, score::Ast<'a>)>, ()> {
+    parse_inputs_cached(sess, matches, arena).map(|(asts, _)| asts)
+}
+
+/// Parse the input files like `parse_inputs`, and also return the cache given
+/// with `--cache-dir`, which knows the input files that parsed cleanly.
+fn parse_inputs_cached<'a>(
+    sess: &Session,
+    matches: &ArgMatches,
+    arena: &'a svlog::ast::Arena<'a>,
+) -> Result<(Vec<(String, score::Ast<'a>)>, Option<Cache>), ()> {
+    // Collect the input files and the options from the file lists, in the
+    // order they were given on the command line.
+    let mut entries: Vec<_> = vec![];
+    for (arg, relative) in &[
+        ("file-list", Some(false)),
+        ("file-list-relative", Some(true)),
+    ] {
+        if let (Some(indices), Some(values)) = (matches.indices_of(arg), matches.values_of(arg)) {
+            entries.extend(indices.zip(values).map(|(i, v)| (i, v, *relative)));
+        }
+    }
+    if let (Some(indices), Some(values)) = (matches.indices_of("INPUT"), matches.values_of("INPUT"))
+    {
+        entries.extend(indices.zip(values).map(|(i, v)| (i, v, None)));
+    }
+    entries.sort_by_key(|&(i, _, _)| i);
+    let mut file_list = FileList::new();
+    for (_, value, relative) in entries {
+        match relative {
+            Some(relative) => file_list.open(value, relative, None, sess)?,
+            None => file_list.add_arg(value, sess),
+        }
+    }
+
+    if file_list.files.is_empty() {
+        sess.emit(
+            DiagBuilder2::error("no input files")
+                .add_note("Pass the files on the command line, in a file list, or in moore.toml"),
+        );
+        return Err(());
+    }
+
+    // Prepare a list of include paths.
+    let include_paths: Vec<_> = matches
+        .values_of("inc")
+        .into_iter()
+        .flatten()
+        .chain(file_list.include_dirs.iter().map(String::as_str))
+        .map(Path::new)
+        .collect();
+
+    let defines: Vec<_> = matches
+        .values_of("def")
+        .into_iter()
+        .flatten()
+        .map(|x| {
+            let mut iter = x.split("=");
+            (iter.next().unwrap(), iter.next())
+        })
+        .chain(
+            file_list
+                .defines
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_ref().map(String::as_str))),
+        )
+        .collect();
+
+    // Prepare the library directories and files in which missing modules are
+    // looked up.
+    let library = svlog::library::Library {
+        dirs: matches
+            .values_of("library-dir")
+            .into_iter()
+            .flatten()
+            .chain(file_list.library_dirs.iter().map(String::as_str))
+            .map(PathBuf::from)
+            .collect(),
+        extensions: file_list.library_extensions.clone(),
+        files: matches
+            .values_of("library-file")
+            .into_iter()
+            .flatten()
+            .chain(file_list.library_files.iter().map(String::as_str))
+            .map(PathBuf::from)
+            .collect(),
+    };
+
+    // Open the cache of files that checked cleanly before.
+    let mut cache = match matches.value_of("cache-dir") {
+        Some(dir) if !matches.is_present("preproc") => match Cache::open(dir) {
+            Ok(cache) => Some(cache),
+            Err(e) => {
+                sess.emit(DiagBuilder2::error(format!(
+                    "cannot open cache directory `{}`: {}",
+                    dir, e
+                )));
+                return Err(());
+            }
+        },
+        _ => None,
+    };
+
+    // Parse the input files.
+    let mut failed = false;
+    let mut asts = Vec::new();
+    for filename in &file_list.files {
+        // Detect the file type.
+        let language = match Path::new(&filename).extension().and_then(|s| s.to_str()) {
+            Some("sv") | Some("svh") => Language::SystemVerilog,
+            Some("v") | Some("vh") => Language::Verilog,
+            Some("vhd") | Some("vhdl") => Language::Vhdl,
+            Some(ext) => {
+                sess.emit(
+                    DiagBuilder2::lint(lints::INPUT_FILE, format!("ignoring `{}`", filename))
+                        .add_note(format!(
+                            "Cannot determine language from extension `.{}`",
+                            ext
+                        )),
+                );
+                continue;
+            }
+            None => {
+                sess.emit(
+                    DiagBuilder2::lint(lints::INPUT_FILE, format!("ignoring `{}`", filename))
+                        .add_note(format!(
+                            "No file extension that can be used to guess language"
+                        )),
+                );
+                continue;
+            }
+        };
+
+        // Skip files which are unchanged since they last checked cleanly.
+        let key = match (&cache, std::fs::read(filename)) {
+            (Some(_), Ok(content)) => {
+                Some(Cache::key(filename, &content, &defines, &include_paths))
+            }
+            _ => None,
+        };
+        if let (Some(cache), Some(key)) = (&mut cache, key) {
+            if matches.is_present("check-syntax") && cache.lookup(filename, key) {
+                continue;
+            }
+        }
+
+        let include_log = svlog::preproc::IncludeLog::default();
+        let emitted = sess.num_emitted();
+        match parse_file(
+            sess,
+            matches,
+            filename,
+            language,
+            &include_paths,
+            &defines,
+            Some(include_log.clone()),
+            arena,
+        ) {
+            Ok(Some(ast)) => asts.push((filename.clone(), ast)),
+            Ok(None) => (),
+            Err(()) => failed = true,
+        }
+
+        // Remember the files which parsed without any diagnostics.
+        if let (Some(cache), Some(key)) = (&mut cache, key) {
+            if sess.num_emitted() == emitted {
+                let includes: Vec<_> = include_log
+                    .sources()
+                    .into_iter()
+                    .map(|source| source.get_path().to_string())
+                    .collect();
+                if let Err(e) = cache.store(filename, key, &includes) {
+                    sess.emit(DiagBuilder2::warning(format!(
+                        "cannot write to cache directory `{}`: {}",
+                        matches.value_of("cache-dir").unwrap(),
+                        e
+                    )));
+                }
+            }
+        }
+    }
+    if let Some(cache) = &cache {
+        if matches.is_present("check-syntax") {
+            sess.emit(DiagBuilder2::note(format!(
+                "{} of {} files unchanged since they last checked cleanly",
+                cache.hits,
+                cache.hits + cache.misses
+            )));
+        }
+    }
+    if !failed && !library.is_empty() && !matches.is_present("preproc") {
+        failed |= add_library_files(
+            sess,
+            matches,
+            &library,
+            &include_paths,
+            &defines,
+            arena,
+            &mut asts,
+        )
+        .is_err();
+    }
+    if sess.measure_memory() {
+        for stats in arena.statistics() {
+            sess.timings
+                .add_memory("ast", stats.name, stats.count, stats.bytes);
+        }
+    }
+    if failed || sess.failed() {
+        return Err(());
+    }
+    Ok((asts, cache))
+}
+
+/// Parse the bundled IEEE library if any of the inputs are VHDL files.
+fn parse_ieee<'a>(sess: &Session, asts: &[score::Ast]) -> Result<Vec<score::Ast<'a>>, ()> {
+    let any_vhdl = asts.iter().any(|ast| match ast {
+        score::Ast::Vhdl(_) => true,
+        _ => false,
+    });
+    if !any_vhdl {
+        return Ok(vec![]);
+    }
+    let _timer = sess.time_pass("parse ieee");
+    Ok(vec![score::Ast::Vhdl(vhdl::ieee::parse(sess)?)])
+}
+
+/// Add the bundled IEEE library to the scoreboard, unless the inputs are
+/// compiled into a library of that name themselves.
+fn add_ieee_library<'ast>(
+    ctx: &ScoreContext<'_, '_, 'ast, '_>,
+    lib: Name,
+    ieee: &'ast [score::Ast<'ast>],
+) {
+    if ieee.is_empty() || lib.as_str().eq_ignore_ascii_case(vhdl::ieee::LIB_NAME) {
+        return;
+    }
+    ctx.add_library(
+        name::get_name_table().intern(vhdl::ieee::LIB_NAME, false),
+        ieee,
+    );
+}
+
+/// Parse an input file.
+///
+/// Returns `None` if the file does not produce an AST, e.g. because it is only
+/// preprocessed.
+fn parse_file<'a>(
+    sess: &Session,
+    matches: &ArgMatches,
+    filename: &str,
+    language: Language,
+    include_paths: &[&Path],
+    defines: &[(&str, Option<&str>)],
+    include_log: Option<svlog::preproc::IncludeLog>,
+    arena: &'a svlog::ast::Arena<'a>,
+) -> Result<Option<score::Ast<'a>>, ()> {
+    // Add the file to the source manager.
+    let sm = source::get_source_manager();
+    let source = match sm.open(filename) {
+        Some(s) => s,
+        None => {
+            sess.emit(DiagBuilder2::error(format!(
+                "unable to open input file `{}`",
+                filename
+            )));
+            return Err(());
+        }
+    };
+
+    // Parse the file.
+    let _timer = sess.time_pass(format!("parse `{}`", filename));
+    match language {
+        Language::SystemVerilog | Language::Verilog => {
+            let mut preproc = svlog::preproc::Preprocessor::new(source, include_paths, defines);
+            if let Some(include_log) = include_log {
+                preproc = preproc.with_include_log(include_log);
+            }
+            if matches.is_present("preproc") {
+                let stdout = std::io::stdout();
+                return svlog::preproc::write_output(
+                    preproc,
+                    matches.is_present("line-markers"),
+                    &mut stdout.lock(),
+                    sess,
+                )
+                .map(|_| None);
+            }
+
+            let stopwatch = Stopwatch::default();
+            let lexer = svlog::lexer::Lexer::new(preproc).with_stopwatch(stopwatch.clone());
+            let result = svlog::parser::parse(lexer, arena, sess);
+            if sess.opts.time_passes {
+                sess.timings.add("preprocess and lex", stopwatch.elapsed());
+            }
+            if sess.measure_memory() {
+                let count = stopwatch.count();
+                sess.timings.add_memory(
+                    "tokens",
+                    "Token",
+                    count,
+                    count * std::mem::size_of::<svlog::lexer::TokenAndSpan>(),
+                );
+            }
+            result.map(|x| Some(score::Ast::Svlog(x)))
+        }
+        Language::Vhdl => vhdl::syntax::parse(source, sess).map(|x| Some(score::Ast::Vhdl(x))),
+    }
+}
+
+/// Add the files from the library that define the modules and interfaces
+/// which are instantiated but not defined in the parsed files.
+///
+/// Library files given with `-v` are only parsed once a module is missing, and
+/// only added if they define one of the missing modules. Files in library
+/// directories given with `-y` are parsed as soon as the module they are named
+/// after is missing. This repeats until no more missing modules can be found.
+fn add_library_files<'a>(
+    sess: &Session,
+    matches: &ArgMatches,
+    library: &svlog::library::Library,
+    include_paths: &[&Path],
+    defines: &[(&str, Option<&str>)],
+    arena: &'a svlog::ast::Arena<'a>,
+    asts: &mut Vec<(String, score::Ast<'a>)>,
+) -> Result<(), ()> {
+    let _timer = sess.time_pass("library");
+    let mut units = svlog::library::Units::default();
+    for (_, ast) in asts.iter() {
+        if let score::Ast::Svlog(ref file) = *ast {
+            units.add_file(file);
+        }
+    }
+    let mut library_files = None;
+    let mut searched = HashSet::new();
+    loop {
+        let missing: Vec<_> = units
+            .undefined()
+            .into_iter()
+            .filter(|&name| searched.insert(name))
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        // Parse the library files the first time a module is missing.
+        let library_files = library_files.get_or_insert_with(|| {
+            library
+                .files
+                .iter()
+                .map(|path| {
+                    let filename = path.to_string_lossy().into_owned();
+                    let ast = parse_file(
+                        sess,
+                        matches,
+                        &filename,
+                        Language::Verilog,
+                        include_paths,
+                        defines,
+                        None,
+                        arena,
+                    );
+                    (filename, ast)
+                })
+                .collect::<Vec<_>>()
+        });
+        let mut found = vec![];
+        for (filename, file) in library_files.iter_mut() {
+            let defines_missing = match *file {
+                Ok(Some(score::Ast::Svlog(ref file))) => svlog::library::defined_units(file)
+                    .iter()
+                    .any(|name| missing.contains(name)),
+                Err(()) => return Err(()),
+                _ => false,
+            };
+            if defines_missing {
+                let ast = std::mem::replace(file, Ok(None)).unwrap().unwrap();
+                found.push((filename.clone(), ast));
+            }
+        }
+        for (_, ast) in &found {
+            if let score::Ast::Svlog(ref file) = *ast {
+                units.add_file(file);
+            }
+        }
+
+        // Look up the remaining modules in the library directories.
+        for name in missing {
+            if units.defined.contains(&name) {
+                continue;
+            }
+            if let Some(path) = library.find(name) {
+                debug!("Found module `{}` in library file {}", name, path.display());
+                let filename = path.to_string_lossy();
+                if let Some(ast) = parse_file(
+                    sess,
+                    matches,
+                    &filename,
+                    Language::Verilog,
+                    include_paths,
+                    defines,
+                    None,
+                    arena,
+                )? {
+                    if let score::Ast::Svlog(ref file) = ast {
+                        units.add_file(file);
+                    }
+                    found.push((filename.into_owned(), ast));
+                }
+            }
+        }
+        asts.extend(found);
     }
 }
 
 /// Resolve an entity/module specificaiton of the form `[lib.]entity[.arch]` for
 /// elaboration.
-fn elaborate_name(ctx: &ScoreContext, lib_id: score::LibRef, input_name: &str) -> Result<(), ()> {
+fn elaborate_name(
+    ctx: &ScoreContext,
+    lib_id: score::LibRef,
+    input_name: &str,
+    tops: &mut Vec<NodeId>,
+) -> Result<(), ()> {
     let (lib, name, arch) = parse_elaborate_name(input_name)?;
     debug!(
         "parsed `{}` into (lib: {:?}, name: {:?}, arch: {:?})",
@@ -376,6 +1887,7 @@ fn elaborate_name(ctx: &ScoreContext, lib_id: score::LibRef, input_name: &str) -
     enum Elaborate {
         VhdlEntity(vhdl::score::EntityRef, vhdl::score::ArchRef),
         VhdlPkg(vhdl::score::PkgDeclRef),
+        VhdlCfg(vhdl::score::CfgRef),
         Svlog(NodeId), // TODO: handle svlog case
     };
     let defs = ctx.defs(lib.into())?;
@@ -413,6 +1925,7 @@ fn elaborate_name(ctx: &ScoreContext, lib_id: score::LibRef, input_name: &str) -
             Elaborate::VhdlEntity(entity, arch_ref)
         }
         Some(&score::Def::Vhdl(vhdl::score::Def::Pkg(p))) => Elaborate::VhdlPkg(p),
+        Some(&score::Def::Vhdl(vhdl::score::Def::Cfg(c))) => Elaborate::VhdlCfg(c),
         Some(&score::Def::Svlog(e)) => Elaborate::Svlog(e),
         _ => {
             let mut d = DiagBuilder2::error(format!("Item `{}` does not exist", name))
@@ -431,10 +1944,7 @@ fn elaborate_name(ctx: &ScoreContext, lib_id: score::LibRef, input_name: &str) -
     // Generate the LLHD definition for whatever we're elaborating.
     match elab {
         Elaborate::VhdlEntity(_entity, arch) => {
-            // let decl = ctx.vhdl.lldecl(arch);
-            // println!("Architecture declared as {:?}", decl);
-            let def = ctx.vhdl().lldef(arch)?;
-            eprintln!("Architecture declared as {:?}", def);
+            ctx.vhdl().lldef(arch)?;
         }
         Elaborate::VhdlPkg(pkg) => {
             use moore::vhdl::typeck::{Typeck, TypeckContext};
@@ -444,37 +1954,609 @@ fn elaborate_name(ctx: &ScoreContext, lib_id: score::LibRef, input_name: &str) -
             // use moore::vhdl::codegen::Codegen;
             // ctx.vhdl().codegen(pkg, &mut ())?;
         }
-        Elaborate::Svlog(m) => {
-            // Emit the detailed type analysis if requested.
-            if ctx.sess.has_verbosity(Verbosity::TYPES) {
-                use svlog::BaseContext;
-                TypeVerbosityVisitor(ctx.svlog, ctx.svlog.default_param_env())
-                    .visit_node_with_id(m, false);
-            }
+        Elaborate::VhdlCfg(cfg) => {
+            // Elaborate the configured architecture with the configuration in
+            // effect.
+            let sbc = ctx.vhdl();
+            let hir = sbc.hir(cfg)?;
+            let env = sbc.intern_generic_env(vhdl::generic_env::GenericEnvData {
+                parent: None,
+                values: vec![],
+                cfg: Some(&hir.block),
+            });
+            sbc.with_env(env).lldef(hir.block.arch.value)?;
+        }
+        Elaborate::Svlog(m) => tops.push(m),
+    }
+    Ok(())
+}
+
+/// Elaborate the SystemVerilog top-level modules of a design.
+///
+/// The modules reachable from the tops are checked, and the requested outputs
+/// are emitted for the hierarchies below all tops together. If a cache is
+/// given and none of the files the design depends on changed since it was last
+/// elaborated cleanly, the LLHD output of that elaboration is reused.
+fn elaborate_modules(
+    ctx: &ScoreContext,
+    asts: &[score::Ast],
+    tops: &[NodeId],
+    matches: &ArgMatches,
+    manifest: &mut Manifest,
+    mut cache: Option<&mut Cache>,
+) -> Result<(), ()> {
+    let cache_key = cache
+        .as_ref()
+        .and_then(|cache| elaboration_key(ctx, asts, tops, matches, cache));
+    if let (Some(cache), Some(key)) = (cache.as_mut(), cache_key) {
+        if let Some(output) = cache.lookup_output(key) {
+            let _timer = ctx.sess.time_pass("reuse cached output");
+            print!("{}", output);
+            manifest.outputs.push(("llhd".to_string(), None));
+            return Ok(());
+        }
+    }
+    let emitted = ctx.sess.num_emitted();
+
+    // Check the names in all modules reachable from the tops. The other
+    // modules are never looked at.
+    let names: HashMap<_, _> = ctx.svlog.modules().map(|(name, id)| (id, name)).collect();
+    let mut names_ok = true;
+    for id in svlog::reachability::reachable_modules(ctx.svlog, tops) {
+        let _timer = ctx.sess.time_pass(format!("check `{}`", names[&id]));
+        names_ok &= ctx.svlog.check_module_names(id);
+    }
+    if !names_ok {
+        return Err(());
+    }
+
+    // Emit the detailed type analysis if requested.
+    for &m in tops {
+        if ctx.sess.has_verbosity(Verbosity::TYPES) {
+            use svlog::BaseContext;
+            TypeVerbosityVisitor(ctx.svlog, ctx.svlog.default_param_env())
+                .visit_node_with_id(m, false);
+        }
+
+        // Emit the instantiation details if requested.
+        if ctx.sess.has_verbosity(Verbosity::INSTS) {
+            svlog::InstVerbosityVisitor::new(ctx.svlog).visit_node_with_id(m, false);
+        }
+    }
 
-            // Emit the instantiation details if requested.
-            if ctx.sess.has_verbosity(Verbosity::INSTS) {
-                svlog::InstVerbosityVisitor::new(ctx.svlog).visit_node_with_id(m, false);
+    // Emit the extracted state machines if requested.
+    let emit: Vec<_> = matches.values_of("emit").unwrap().collect();
+    manifest.outputs.extend(
+        emit.iter()
+            .filter(|&&e| e != "llhd" && e != "reachability" && e != "ast-json")
+            .map(|&e| (e.to_string(), None)),
+    );
+    let modules = if emit
+        .iter()
+        .any(|&e| ["fsm", "fsm-json", "clocks", "hir", "mir"].contains(&e))
+    {
+        let mut modules = svlog::ModuleCollector::new(ctx.svlog);
+        for &m in tops {
+            modules.visit_node_with_id(m, false);
+        }
+        modules.modules
+    } else {
+        vec![]
+    };
+    if emit.contains(&"fsm") || emit.contains(&"fsm-json") {
+        let mut fsms = vec![];
+        for &(id, env) in &modules {
+            if let Ok(x) = ctx.svlog.extract_fsms(id, env) {
+                fsms.extend(x.iter().cloned());
             }
+        }
+        if emit.contains(&"fsm") {
+            emit_fsms_dot(&fsms);
+        }
+        if emit.contains(&"fsm-json") {
+            emit_fsms_json(&fsms);
+        }
+    }
+    if emit.contains(&"clocks") {
+        emit_clocks(ctx.svlog, &modules);
+    }
+    if emit.iter().any(|e| e.starts_with("hierarchy")) {
+        let trees: Vec<_> = tops
+            .iter()
+            .flat_map(|&top| svlog::hierarchy::instance_tree(ctx.svlog, top))
+            .collect();
+        if emit.contains(&"hierarchy") {
+            for tree in &trees {
+                emit_hierarchy(tree, 0);
+            }
+        }
+        if emit.contains(&"hierarchy-json") {
+            let json: Vec<_> = trees.iter().map(hierarchy_to_json).collect();
+            println!("{:#}", serde_json::Value::Array(json));
+        }
+        if emit.contains(&"hierarchy-dot") {
+            emit_hierarchy_dot(&trees);
+        }
+    }
+    if emit.contains(&"hir") {
+        for &(id, env) in &modules {
+            print!("{}", svlog::dump::dump_hir(ctx.svlog, id, env));
+        }
+    }
+    if emit.contains(&"mir") {
+        for &(id, env) in &modules {
+            print!("{}", svlog::dump::dump_mir(ctx.svlog, id, env));
+        }
+    }
+    if !emit.contains(&"llhd") && !matches.is_present("elaborate-only") {
+        return Ok(());
+    }
 
-            let mut cg = svlog::CodeGenerator::new(ctx.svlog);
-            cg.emit_module(m)?;
-            let mut module = cg.finalize();
-            let pass_ctx = PassContext;
-            if ctx.sess.opts.opt_level > 0 {
-                llhd::pass::ConstFolding::run_on_module(&pass_ctx, &mut module);
-                // llhd::pass::VarToPhiPromotion::run_on_module(&pass_ctx, &mut module); // broken in llhd 0.13
-                llhd::pass::DeadCodeElim::run_on_module(&pass_ctx, &mut module);
-                llhd::pass::GlobalCommonSubexprElim::run_on_module(&pass_ctx, &mut module);
-                llhd::pass::InstSimplification::run_on_module(&pass_ctx, &mut module);
-                llhd::pass::DeadCodeElim::run_on_module(&pass_ctx, &mut module);
+    let mut cg = svlog::CodeGenerator::new(ctx.svlog);
+    cg.set_entity_policy(match matches.value_of("entity-policy").unwrap() {
+        "per-instance" => svlog::EntityPolicy::PerInstance,
+        "shared" => svlog::EntityPolicy::Shared,
+        _ => unreachable!(),
+    });
+    {
+        let _timer = ctx.sess.time_pass("codegen");
+        let mut failed = false;
+        for &m in tops {
+            failed |= cg.emit_module(m).is_err();
+        }
+        if failed {
+            return Err(());
+        }
+    }
+
+    // Look for combinational loops in the modules, which would otherwise only
+    // show up as a simulation that never settles, for inferred latches, for
+    // width mismatches, and for incomplete or overlapping case statements.
+    // This runs after code generation such that the parametrizations are
+    // numbered in the order in which the code generator encounters them.
+    {
+        let _timer = ctx.sess.time_pass("design lints");
+        let mut modules = svlog::ModuleCollector::new(ctx.svlog);
+        for &m in tops {
+            modules.visit_node_with_id(m, false);
+        }
+        for (id, env) in modules.modules {
+            svlog::comb_loop::check_comb_loops(ctx.svlog, id, env);
+            svlog::latch::check_latches(ctx.svlog, id, env);
+            svlog::width::check_widths(ctx.svlog, id, env);
+            svlog::case::check_cases(ctx.svlog, id, env);
+        }
+    }
+    if let Some(path) = matches.value_of("port-map") {
+        let result = std::fs::File::create(path).and_then(|f| {
+            let mut out = std::io::BufWriter::new(f);
+            cg.write_port_map(&mut out)?;
+            out.flush()
+        });
+        if let Err(e) = result {
+            ctx.sess.emit(DiagBuilder2::error(format!(
+                "unable to write port map to `{}`: {}",
+                path, e
+            )));
+            return Err(());
+        }
+        manifest
+            .outputs
+            .push(("port-map".to_string(), Some(path.to_string())));
+    }
+    if let Some(path) = matches.value_of("dpi-header") {
+        let result = std::fs::File::create(path).and_then(|f| {
+            let mut out = std::io::BufWriter::new(f);
+            cg.write_dpi_header(&mut out)?;
+            out.flush()
+        });
+        if let Err(e) = result {
+            ctx.sess.emit(DiagBuilder2::error(format!(
+                "unable to write DPI header to `{}`: {}",
+                path, e
+            )));
+            return Err(());
+        }
+        manifest
+            .outputs
+            .push(("dpi-header".to_string(), Some(path.to_string())));
+    }
+    if matches.is_present("elaborate-only") {
+        return Ok(());
+    }
+    let mut module = cg.finalize();
+    let pass_ctx = PassContext;
+    if ctx.sess.opts.opt_level > 0 {
+        let _timer = ctx.sess.time_pass("optimize");
+        llhd::pass::ConstFolding::run_on_module(&pass_ctx, &mut module);
+        // llhd::pass::VarToPhiPromotion::run_on_module(&pass_ctx, &mut module); // broken in llhd 0.13
+        llhd::pass::DeadCodeElim::run_on_module(&pass_ctx, &mut module);
+        llhd::pass::GlobalCommonSubexprElim::run_on_module(&pass_ctx, &mut module);
+        llhd::pass::InstSimplification::run_on_module(&pass_ctx, &mut module);
+        llhd::pass::DeadCodeElim::run_on_module(&pass_ctx, &mut module);
+    }
+    let _timer = ctx.sess.time_pass("write output");
+    let mut output = vec![];
+    llhd::assembly::write_module(&mut output, &module);
+    let output = String::from_utf8_lossy(&output);
+    print!("{}", output);
+    manifest.outputs.push(("llhd".to_string(), None));
+
+    // Remember the output if the design elaborated without any diagnostics.
+    if let (Some(cache), Some(key)) = (cache, cache_key) {
+        if ctx.sess.num_emitted() == emitted {
+            if let Err(e) = cache.store_output(key, &output) {
+                ctx.sess.emit(DiagBuilder2::warning(format!(
+                    "cannot write to cache directory `{}`: {}",
+                    matches.value_of("cache-dir").unwrap(),
+                    e
+                )));
             }
-            llhd::assembly::write_module(&mut std::io::stdout().lock(), &module);
         }
     }
     Ok(())
 }
 
+/// Determine the key under which the LLHD output of an elaboration is cached.
+///
+/// The key covers the options that affect the output, e.g. the optimization
+/// level, coverage instrumentation, and lint levels, and the input files that
+/// define a module reachable from the tops or anything other than modules,
+/// such as packages, interfaces, or declarations in the compilation unit.
+/// Files which only define unreachable modules do not affect the output.
+/// Returns `None` if the output is not cached, e.g. because other outputs are
+/// requested as well or the design contains VHDL.
+fn elaboration_key(
+    ctx: &ScoreContext,
+    asts: &[score::Ast],
+    tops: &[NodeId],
+    matches: &ArgMatches,
+    cache: &Cache,
+) -> Option<u64> {
+    let emit: Vec<_> = matches.values_of("emit").unwrap().collect();
+    if emit != ["llhd"]
+        || matches.is_present("elaborate-only")
+        || matches.is_present("port-map")
+        || matches.is_present("dpi-header")
+        || !ctx.sess.opts.verbosity.is_empty()
+    {
+        return None;
+    }
+    let reachable: HashSet<_> = svlog::reachability::reachable_modules(ctx.svlog, tops)
+        .into_iter()
+        .collect();
+    let mut paths = vec![];
+    for ast in asts {
+        let file = match ast {
+            score::Ast::Svlog(file) => file,
+            score::Ast::Vhdl(_) => return None,
+        };
+        let relevant = file.items.iter().any(|item| match &item.data {
+            svlog::ast::ItemData::ModuleDecl(m) => ctx
+                .svlog
+                .find_module(m.name.value)
+                .map_or(true, |id| reachable.contains(&id)),
+            _ => true,
+        });
+        if relevant {
+            paths.push(file.span.source.get_path());
+        }
+    }
+    let names: HashMap<_, _> = ctx.svlog.modules().map(|(name, id)| (id, name)).collect();
+    let opts = &ctx.sess.opts;
+    let mut options: Vec<_> = tops.iter().map(|id| names[id].to_string()).collect();
+    options.push(format!("-O{}", opts.opt_level));
+    options.push(format!(
+        "--entity-policy={}",
+        matches.value_of("entity-policy").unwrap()
+    ));
+    options.push(format!("--coverage={}", opts.coverage));
+    options.push(format!(
+        "--ignore-duplicate-defs={}",
+        opts.ignore_duplicate_defs
+    ));
+
+    // The output is only stored if no diagnostics were emitted, which depends
+    // on the levels at which the lints are reported.
+    for &lint in lints::ALL {
+        options.push(format!("-W{}={:?}", lint.name, opts.lint_levels.get(lint)));
+    }
+    cache.elaboration_key(&options, paths.iter().map(|p| &**p))
+}
+
+/// Print which modules are reachable from the elaborated top-level modules,
+/// and which ones have been pruned.
+fn emit_reachability(cx: &svlog::GlobalContext, tops: &[NodeId]) {
+    let reachable = svlog::reachability::reachable_modules(cx, tops);
+    let mut modules: Vec<_> = cx.modules().collect();
+    modules.sort_by_key(|&(_, id)| id);
+    for &id in &reachable {
+        if let Some(&(name, _)) = modules.iter().find(|&&(_, m)| m == id) {
+            println!("reachable {}", name);
+        }
+    }
+    for (name, id) in modules {
+        if !reachable.contains(&id) {
+            println!("pruned {}", name);
+        }
+    }
+}
+
+/// Print the clocks and resets of the modules in a design.
+fn emit_clocks(cx: &svlog::GlobalContext, modules: &[(NodeId, svlog::ParamEnv)]) {
+    use svlog::BaseContext;
+    let edge = |edge| match edge {
+        svlog::ast::EdgeIdent::Posedge => "posedge",
+        svlog::ast::EdgeIdent::Negedge => "negedge",
+        _ => "edge",
+    };
+    for &(id, env) in modules {
+        let clocking = match cx.module_clocking(id, env) {
+            Ok(x) => x,
+            Err(()) => continue,
+        };
+        if clocking.procs.is_empty() {
+            continue;
+        }
+        let name = match cx.hir_of(id) {
+            Ok(svlog::hir::HirNode::Module(m)) => m.name.value,
+            _ => continue,
+        };
+        if env == cx.default_param_env() {
+            println!("module {}:", name);
+        } else {
+            println!("module {} ({:?}):", name, env);
+        }
+        for clock in &clocking.clocks {
+            println!("    clock {} ({})", clock.name, edge(clock.edge));
+        }
+        for reset in &clocking.resets {
+            println!(
+                "    reset {} ({}, active {})",
+                reset.name,
+                edge(reset.edge),
+                if reset.active_high { "high" } else { "low" }
+            );
+        }
+        for prok in &clocking.procs {
+            let span = cx.span(prok.proc);
+            let mut line = format!("    process at line {}:", span.begin().human_line());
+            if let Some(clock) = prok.clock {
+                line.push_str(&format!(" clock {} ({})", clock.name, edge(clock.edge)));
+            }
+            for reset in &prok.resets {
+                line.push_str(&format!(" reset {}", reset.name));
+            }
+            println!("{}", line);
+        }
+    }
+}
+
+/// Print an instance hierarchy as an indented tree.
+fn emit_hierarchy(inst: &svlog::hierarchy::Instance, depth: usize) {
+    if depth == 0 {
+        println!("{}", inst.target_desc());
+    } else {
+        println!(
+            "{:indent$}{}: {}{}",
+            "",
+            inst.name,
+            if inst.interface { "interface " } else { "" },
+            inst.target_desc(),
+            indent = depth * 2
+        );
+    }
+    for child in &inst.children {
+        emit_hierarchy(child, depth + 1);
+    }
+}
+
+/// Convert an instance hierarchy to JSON.
+fn hierarchy_to_json(inst: &svlog::hierarchy::Instance) -> serde_json::Value {
+    serde_json::json!({
+        "name": inst.name.to_string(),
+        "target": inst.target.to_string(),
+        "kind": if inst.interface { "interface" } else { "module" },
+        "params": inst
+            .params
+            .iter()
+            .map(|(name, value)| serde_json::json!({
+                "name": name.to_string(),
+                "value": value,
+            }))
+            .collect::<Vec<_>>(),
+        "children": inst.children.iter().map(hierarchy_to_json).collect::<Vec<_>>(),
+    })
+}
+
+/// Print instance hierarchies as a Graphviz DOT graph.
+fn emit_hierarchy_dot(trees: &[svlog::hierarchy::Instance]) {
+    println!("digraph hierarchy {{");
+    println!("    node [shape=box];");
+    for (path, inst) in trees.iter().flat_map(|tree| tree.iter()) {
+        println!(
+            "    {:?} [label={:?}];",
+            path,
+            format!("{}\n{}", inst.name, inst.target_desc())
+        );
+        for child in &inst.children {
+            println!(
+                "    {:?} -> {:?};",
+                path,
+                format!("{}.{}", path, child.name)
+            );
+        }
+    }
+    println!("}}");
+}
+
+/// Print state machines as a Graphviz DOT graph.
+fn emit_fsms_dot(fsms: &[svlog::fsm::Fsm]) {
+    println!("digraph fsms {{");
+    for (i, fsm) in fsms.iter().enumerate() {
+        println!("    subgraph cluster_{} {{", i);
+        println!(
+            "        label = {:?};",
+            format!("{}.{}", fsm.module_name, fsm.name)
+        );
+        for (j, state) in fsm.states.iter().enumerate() {
+            println!(
+                "        s{}_{} [label={:?}{}];",
+                i,
+                j,
+                state.to_string(),
+                if fsm.reset == Some(j) {
+                    ", shape=doublecircle"
+                } else {
+                    ""
+                }
+            );
+        }
+        for t in &fsm.transitions {
+            println!(
+                "        s{}_{} -> s{}_{} [label={:?}];",
+                i,
+                t.from,
+                i,
+                t.to,
+                t.conds.join(" && ")
+            );
+        }
+        println!("    }}");
+    }
+    println!("}}");
+}
+
+/// Print state machines as JSON.
+fn emit_fsms_json(fsms: &[svlog::fsm::Fsm]) {
+    let json: Vec<_> = fsms
+        .iter()
+        .map(|fsm| {
+            serde_json::json!({
+                "module": fsm.module_name.to_string(),
+                "state": fsm.name.to_string(),
+                "states": fsm.states.iter().map(|s| serde_json::json!({
+                    "name": s.name.map(|n| n.to_string()),
+                    "value": s.value.to_string(),
+                })).collect::<Vec<_>>(),
+                "reset": fsm.reset,
+                "transitions": fsm.transitions.iter().map(|t| serde_json::json!({
+                    "from": t.from,
+                    "to": t.to,
+                    "conds": t.conds,
+                })).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    println!("{}", serde_json::to_string_pretty(&json).unwrap());
+}
+
+/// Print the parsed SystemVerilog files as JSON.
+///
+/// Every node lists its kind, the variant of enum nodes such as expressions,
+/// its name if it has one, its location in the source code, and its children.
+/// Operators, the text of identifiers and literals, and the direction of ports
+/// are listed as well, since they are not nodes of their own.
+fn emit_ast_json<'a>(files: &[&'a svlog::ast::SourceFile<'a>]) {
+    let mut lines = HashMap::new();
+    let json: Vec<_> = files
+        .iter()
+        .map(|file| ast_to_json(svlog::ast::BasicNode::as_any(*file), &mut lines))
+        .collect();
+    println!("{}", serde_json::to_string_pretty(&json).unwrap());
+}
+
+/// Convert an AST node and its children to JSON.
+///
+/// The offsets at which lines start are cached per source file in `lines`.
+fn ast_to_json<'a>(
+    node: &'a dyn svlog::ast::AnyNode<'a>,
+    lines: &mut HashMap<source::Source, Vec<usize>>,
+) -> serde_json::Value {
+    let span = node.span();
+    let location = if span.source == source::INVALID_SOURCE {
+        serde_json::Value::Null
+    } else {
+        let content = span.source.get_content();
+        let starts = lines.entry(span.source).or_insert_with(|| {
+            std::iter::once(0)
+                .chain(
+                    content
+                        .iter()
+                        .filter(|&(_, c)| c == '\n')
+                        .map(|(offset, _)| offset + 1),
+                )
+                .collect()
+        });
+        let line_column = |offset: usize| {
+            let line = match starts.binary_search(&offset) {
+                Ok(index) => index + 1,
+                Err(index) => index,
+            };
+            let start = starts[line - 1];
+            (line, content.extract(start, offset).chars().count() + 1)
+        };
+        let (line, column) = line_column(span.begin);
+        let (end_line, end_column) = line_column(span.end);
+        serde_json::json!({
+            "file": display_path(&span.source.get_path()),
+            "begin": span.begin,
+            "end": span.end,
+            "line": line,
+            "column": column,
+            "end_line": end_line,
+            "end_column": end_column,
+        })
+    };
+    let mut children = vec![];
+    node.for_each_child(&mut |child| children.push(ast_to_json(child, lines)));
+    let mut json = serde_json::json!({
+        "kind": node.type_name(),
+        "variant": node.get_variant(),
+        "name": node.get_name().map(|name| name.value.to_string()),
+        "span": location,
+    });
+    for (key, value) in ast_details(node) {
+        json[key] = value.into();
+    }
+    json["children"] = children.into();
+    json
+}
+
+/// Determine the details of an AST node which are not nodes of their own.
+///
+/// These are the operator of unary, binary, and assignment expressions, the
+/// text of identifiers and literals, and the direction of ports.
+fn ast_details<'a>(node: &'a dyn svlog::ast::AnyNode<'a>) -> Vec<(&'static str, String)> {
+    use svlog::ast::{AllNode, ExprData, PortData};
+    match node.as_all() {
+        AllNode::Expr(expr) => match expr.data {
+            ExprData::UnaryExpr { op, .. } | ExprData::BinaryExpr { op, .. } => {
+                vec![("op", op.to_string())]
+            }
+            ExprData::AssignExpr { op, .. } => vec![("op", op.to_string())],
+            ExprData::IdentExpr(name) | ExprData::SysIdentExpr(name) => {
+                vec![("text", name.value.to_string())]
+            }
+            ExprData::LiteralExpr(_) => vec![("text", expr.span.extract())],
+            _ => vec![],
+        },
+        AllNode::Port(port) => match port.data {
+            PortData::Explicit { dir: Some(dir), .. } | PortData::Named { dir: Some(dir), .. } => {
+                vec![("dir", dir.to_string())]
+            }
+            _ => vec![],
+        },
+        AllNode::PortDecl(decl) => vec![("dir", decl.dir.to_string())],
+        AllNode::SubroutinePort(port) => port
+            .dir
+            .iter()
+            .map(|dir| ("dir", dir.to_string()))
+            .collect(),
+        _ => vec![],
+    }
+}
+
 /// Parse an entity name of the form `(first\.)?second((arch))?` for
 /// elaboration.
 fn parse_elaborate_name<S: AsRef<str>>(name: S) -> Result<(Option<Name>, Name, Option<Name>), ()> {
@@ -550,14 +2632,18 @@ impl<'a, 'gcx> svlog::hir::Visitor<'gcx> for TypeVerbosityVisitor<'a, 'gcx> {
         self.0
     }
 
-    fn visit_expr(&mut self, expr: &'gcx svlog::hir::Expr<'gcx>, lvalue: bool) {
+    fn visit_expr(
+        &mut self,
+        expr: &'gcx svlog::hir::Expr<'gcx>,
+        lvalue: bool,
+    ) -> svlog::hir::Control {
         self.print(expr.id);
-        svlog::hir::walk_expr(self, expr, lvalue);
+        svlog::hir::walk_expr(self, expr, lvalue)
     }
 
-    fn visit_var_decl(&mut self, decl: &'gcx svlog::hir::VarDecl) {
+    fn visit_var_decl(&mut self, decl: &'gcx svlog::hir::VarDecl) -> svlog::hir::Control {
         self.print(decl.id);
-        svlog::hir::walk_var_decl(self, decl);
+        svlog::hir::walk_var_decl(self, decl)
     }
 }
 
@@ -0,0 +1,306 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Remembering which input files check cleanly across invocations.
+//!
+//! A cache directory given with `--cache-dir` holds one entry for every input
+//! file that parsed without any diagnostics. The entry is keyed by a hash of
+//! the file's path and content, the macro definitions and include paths it was
+//! parsed with, and the compiler version, and records the files it included
+//! together with the hash of their content. An invocation with `--syntax-only`
+//! skips the files that have an entry whose included files are unchanged.
+//!
+//! The ASTs themselves are not stored, since they are allocated in arenas and
+//! refer to each other and to the source manager by pointer. Invocations which
+//! elaborate the design parse every file.
+//!
+//! What is stored instead is the LLHD output of an elaboration. It is keyed by
+//! the options that affect it and by the entries of the input files that it
+//! depends on, i.e. the files that define the modules reachable from the
+//! top-level modules and the packages, interfaces, and other declarations
+//! visible to them. A design whose relevant files are unchanged, including the
+//! files they include, is therefore not elaborated again, even if files with
+//! unrelated modules changed.
+
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// A directory of cache entries.
+#[derive(Debug)]
+pub struct Cache {
+    /// The directory that holds the entries.
+    dir: PathBuf,
+    /// The number of files that were found in the cache.
+    pub hits: usize,
+    /// The number of files that were not found in the cache.
+    pub misses: usize,
+    /// For each input file with a valid entry, the hash of its key and the
+    /// content of the files it included.
+    files: HashMap<String, u64>,
+}
+
+impl Cache {
+    /// Open a cache directory, creating it if necessary.
+    pub fn open(dir: impl Into<PathBuf>) -> io::Result<Cache> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Cache {
+            dir,
+            hits: 0,
+            misses: 0,
+            files: Default::default(),
+        })
+    }
+
+    /// Compute the key of a file parsed with a set of macro definitions and
+    /// include paths.
+    pub fn key(
+        path: &str,
+        content: &[u8],
+        defines: &[(&str, Option<&str>)],
+        include_paths: &[&Path],
+    ) -> u64 {
+        let mut hasher = Hasher::default();
+        hasher.write(env!("CARGO_PKG_VERSION").as_bytes());
+        hasher.write(path.as_bytes());
+        hasher.write(content);
+        for (name, value) in defines {
+            hasher.write(name.as_bytes());
+            hasher.write(&[value.is_some() as u8]);
+            hasher.write(value.unwrap_or("").as_bytes());
+        }
+        for path in include_paths {
+            hasher.write(path.to_string_lossy().as_bytes());
+        }
+        hasher.finish()
+    }
+
+    /// Check whether there is an entry for the key of a file whose included
+    /// files are unchanged, and count the hit or miss.
+    pub fn lookup(&mut self, path: &str, key: u64) -> bool {
+        let hit = self.read_entry(path, key).unwrap_or(false);
+        if hit {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+        hit
+    }
+
+    /// Add an entry for the key of a file, recording the current content of
+    /// the files that were included.
+    pub fn store(&mut self, path: &str, key: u64, includes: &[String]) -> io::Result<()> {
+        let includes = includes
+            .iter()
+            .map(|path| {
+                let content = fs::read(path)?;
+                Ok(json!({
+                    "path": path,
+                    "hash": format!("{:016x}", hash(&content)),
+                }))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        let entry = json!({ "includes": includes });
+        fs::write(self.entry_path(key), entry.to_string())?;
+        self.files
+            .insert(path.to_string(), entry_hash(key, &includes));
+        Ok(())
+    }
+
+    fn read_entry(&mut self, path: &str, key: u64) -> Option<bool> {
+        let entry: Value = serde_json::from_slice(&fs::read(self.entry_path(key)).ok()?).ok()?;
+        let includes = entry.get("includes")?.as_array()?;
+        for include in includes {
+            let path = include.get("path")?.as_str()?;
+            let expected = include.get("hash")?.as_str()?;
+            let content = fs::read(path).ok()?;
+            if format!("{:016x}", hash(&content)) != expected {
+                return Some(false);
+            }
+        }
+        self.files
+            .insert(path.to_string(), entry_hash(key, includes));
+        Some(true)
+    }
+
+    /// Compute the key of an elaboration from the options that affect its
+    /// output and the input files that it depends on.
+    ///
+    /// Returns `None` if one of the files has no valid entry, e.g. because it
+    /// produced diagnostics or was not parsed in this invocation, since the
+    /// files it includes are unknown then.
+    pub fn elaboration_key<'a>(
+        &self,
+        options: &[String],
+        paths: impl IntoIterator<Item = &'a str>,
+    ) -> Option<u64> {
+        let mut files = paths
+            .into_iter()
+            .map(|path| self.files.get(path).cloned())
+            .collect::<Option<Vec<_>>>()?;
+        files.sort();
+        files.dedup();
+        let mut hasher = Hasher::default();
+        hasher.write(env!("CARGO_PKG_VERSION").as_bytes());
+        for option in options {
+            hasher.write(option.as_bytes());
+        }
+        for file in files {
+            hasher.write(&file.to_le_bytes());
+        }
+        Some(hasher.finish())
+    }
+
+    /// Find the output of an earlier elaboration, and count the hit or miss.
+    pub fn lookup_output(&mut self, key: u64) -> Option<String> {
+        let output = fs::read_to_string(self.output_path(key)).ok();
+        if output.is_some() {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+        output
+    }
+
+    /// Store the output of an elaboration.
+    pub fn store_output(&self, key: u64, output: &str) -> io::Result<()> {
+        fs::write(self.output_path(key), output)
+    }
+
+    fn output_path(&self, key: u64) -> PathBuf {
+        self.dir.join(format!("{:016x}.llhd", key))
+    }
+
+    fn entry_path(&self, key: u64) -> PathBuf {
+        self.dir.join(format!("{:016x}.json", key))
+    }
+}
+
+/// Combine the key of a file with the hashes of the files it included.
+fn entry_hash(key: u64, includes: &[Value]) -> u64 {
+    let mut hasher = Hasher::default();
+    hasher.write(&key.to_le_bytes());
+    for include in includes {
+        hasher.write(include.to_string().as_bytes());
+    }
+    hasher.finish()
+}
+
+/// Hash a sequence of bytes.
+pub fn hash(bytes: &[u8]) -> u64 {
+    let mut hasher = Hasher::default();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+/// A 64 bit FNV-1a hasher.
+///
+/// The standard library's hasher is not guaranteed to produce the same values
+/// across releases, which would invalidate the cache whenever the compiler is
+/// rebuilt with a different toolchain.
+struct Hasher(u64);
+
+impl Default for Hasher {
+    fn default() -> Hasher {
+        Hasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher {
+    /// Add bytes to the hash, followed by their length such that consecutive
+    /// writes cannot be confused with a single longer one.
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes.iter().chain(&(bytes.len() as u64).to_le_bytes()) {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys() {
+        let key = |content: &str, defines: &[(&str, Option<&str>)]| {
+            Cache::key("a.sv", content.as_bytes(), defines, &[])
+        };
+        assert_eq!(
+            key("module a; endmodule", &[]),
+            key("module a; endmodule", &[])
+        );
+        assert_ne!(
+            key("module a; endmodule", &[]),
+            key("module b; endmodule", &[])
+        );
+        assert_ne!(
+            key("module a; endmodule", &[("FOO", None)]),
+            key("module a; endmodule", &[("FOO", Some("1"))])
+        );
+        assert_ne!(
+            key("module a; endmodule", &[("AB", None)]),
+            key("module a; endmodule", &[("A", Some("B"))])
+        );
+    }
+
+    #[test]
+    fn entries() {
+        let dir = std::env::temp_dir().join(format!("moore-cache-test-{}", std::process::id()));
+        let include = dir.join("defs.svh");
+        let mut cache = Cache::open(dir.join("cache")).unwrap();
+        fs::write(&include, "`define WIDTH 8").unwrap();
+        let includes = vec![include.to_string_lossy().into_owned()];
+
+        assert!(!cache.lookup("a.sv", 1));
+        cache.store("a.sv", 1, &includes).unwrap();
+        assert!(cache.lookup("a.sv", 1));
+        fs::write(&include, "`define WIDTH 16").unwrap();
+        assert!(!cache.lookup("a.sv", 1));
+        assert_eq!((cache.hits, cache.misses), (1, 2));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn outputs() {
+        let dir = std::env::temp_dir().join(format!("moore-output-test-{}", std::process::id()));
+        let include = dir.join("defs.svh");
+        let mut cache = Cache::open(dir.join("cache")).unwrap();
+        fs::write(&include, "`define WIDTH 8").unwrap();
+        let includes = vec![include.to_string_lossy().into_owned()];
+        let options = vec!["top".to_string()];
+
+        // Files without an entry prevent the elaboration from being cached.
+        assert_eq!(cache.elaboration_key(&options, vec!["a.sv"]), None);
+        cache.store("a.sv", 1, &includes).unwrap();
+        cache.store("b.sv", 2, &[]).unwrap();
+        let key = cache.elaboration_key(&options, vec!["a.sv"]).unwrap();
+        assert_ne!(
+            cache.elaboration_key(&options, vec!["a.sv", "b.sv"]),
+            Some(key)
+        );
+        assert_ne!(cache.elaboration_key(&[], vec!["a.sv"]), Some(key));
+
+        assert_eq!(cache.lookup_output(key), None);
+        cache.store_output(key, "entity @top () -> () {}").unwrap();
+        assert_eq!(
+            cache.lookup_output(key).as_ref().map(String::as_str),
+            Some("entity @top () -> () {}")
+        );
+
+        // A change to an included file changes the key.
+        fs::write(&include, "`define WIDTH 16").unwrap();
+        cache.store("a.sv", 1, &includes).unwrap();
+        assert_ne!(cache.elaboration_key(&options, vec!["a.sv"]), Some(key));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
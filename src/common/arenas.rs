@@ -19,6 +19,39 @@ impl<'z, 'a, 'p: 'a, 't, T: 't> Alloc<'z, 't, T> for &'p dyn Alloc<'a, 't, T> {
     }
 }
 
+/// Allocates slices of values.
+pub trait AllocSlice<'a, 't, T: 't> {
+    /// Allocate a slice of values of type `T`.
+    fn alloc_slice(&'a self, values: impl IntoIterator<Item = T>) -> &'t [T];
+}
+
+/// The number and size of the values of one type allocated in an arena.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllocStats {
+    /// The name of the type, without its module path.
+    pub name: &'static str,
+    /// The number of values allocated.
+    pub count: usize,
+    /// The number of bytes taken up by the values.
+    pub bytes: usize,
+}
+
+impl AllocStats {
+    /// Determine the values allocated so far in a typed arena.
+    ///
+    /// The values are named after their type, without its module path and
+    /// generic arguments.
+    pub fn of<T>(arena: &TypedArena<T>) -> AllocStats {
+        let name = std::any::type_name::<T>();
+        let name = name.split('<').next().unwrap_or(name);
+        AllocStats {
+            name: name.rsplit("::").next().unwrap_or(name),
+            count: arena.len(),
+            bytes: arena.len() * std::mem::size_of::<T>(),
+        }
+    }
+}
+
 /// Allocates values into itself.
 ///
 /// This is merely a marker trait that you should not implement yourself. It is
@@ -153,6 +186,12 @@ macro_rules! make_arenas {
                     $($name: $crate::arenas::TypedArena::new(),)*
                 }
             }
+
+            /// The number and size of the values allocated so far, for each
+            /// type.
+            pub fn statistics(&self) -> Vec<$crate::arenas::AllocStats> {
+                vec![$($crate::arenas::AllocStats::of(&self.$name),)*]
+            }
         }
 
         impl<$($lt),*> Default for $arena_name<$($lt),*> {
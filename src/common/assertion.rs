@@ -0,0 +1,121 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Operators of the temporal assertion languages.
+//!
+//! SystemVerilog Assertions (SVA) and the Property Specification Language
+//! (PSL) embedded in VHDL share most of their temporal layer: both describe
+//! sequences of boolean expressions, properties built on top of these, and
+//! directives that assert, assume, or cover properties. The syntax trees of
+//! both frontends use the operators defined here, such that later stages can
+//! treat assertions uniformly regardless of the source language.
+
+/// A verification directive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DirectiveKind {
+    /// `assert`: the property must hold.
+    Assert,
+    /// `assume`: the property is assumed to hold.
+    Assume,
+    /// `cover`: the sequence or property should be observed.
+    Cover,
+    /// `restrict`: the property constrains the stimulus.
+    Restrict,
+    /// `expect`: the property must hold from now on (SVA only).
+    Expect,
+}
+
+impl DirectiveKind {
+    /// Get the keyword that introduces the directive.
+    pub fn keyword(self) -> &'static str {
+        match self {
+            DirectiveKind::Assert => "assert",
+            DirectiveKind::Assume => "assume",
+            DirectiveKind::Cover => "cover",
+            DirectiveKind::Restrict => "restrict",
+            DirectiveKind::Expect => "expect",
+        }
+    }
+}
+
+/// A binary operator on sequences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SeqBinOp {
+    /// Either sequence matches. PSL `|`, SVA `or`.
+    Or,
+    /// Both sequences match, not necessarily with the same length. PSL `&`,
+    /// SVA `and`.
+    And,
+    /// Both sequences match with the same length. PSL `&&`, SVA `intersect`.
+    Intersect,
+    /// The first sequence matches within the second. PSL and SVA `within`.
+    Within,
+    /// The second sequence starts the cycle after the first ends. PSL `;`,
+    /// SVA `##1`.
+    Concat,
+    /// The second sequence starts in the cycle the first ends. PSL `:`, SVA
+    /// `##0`.
+    Fusion,
+}
+
+/// The strength of a sequence used as a property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PropSeqOp {
+    /// No explicit strength.
+    None,
+    /// A weak sequence. SVA `weak(...)`.
+    Weak,
+    /// A strong sequence. PSL `{...}!`, SVA `strong(...)`.
+    Strong,
+}
+
+/// A binary operator between a sequence and a property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PropSeqBinOp {
+    /// Overlapping suffix implication `|->`.
+    ImplOverlap,
+    /// Non-overlapping suffix implication `|=>`.
+    ImplNonoverlap,
+    /// Overlapping followed-by `#-#` (SVA only).
+    FollowOverlap,
+    /// Non-overlapping followed-by `#=#` (SVA only).
+    FollowNonoverlap,
+}
+
+/// A unary temporal operator on properties.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PropUnaryOp {
+    /// The property holds in every cycle.
+    Always,
+    /// The property holds in no cycle (PSL only).
+    Never,
+    /// The property eventually holds. PSL `eventually!`, SVA `s_eventually`.
+    SEventually,
+    /// The property holds in the next cycle, if there is one. PSL `next`, SVA
+    /// `nexttime`.
+    Next,
+    /// There is a next cycle and the property holds in it. PSL `next!`, SVA
+    /// `s_nexttime`.
+    SNext,
+}
+
+/// A binary operator on properties.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PropBinOp {
+    Or,
+    And,
+    Until,
+    SUntil,
+    UntilWith,
+    SUntilWith,
+    /// The first property holds strictly before the second (PSL only).
+    Before,
+    SBefore,
+    BeforeWith,
+    SBeforeWith,
+    Impl,
+    Iff,
+    SeqImplOl,
+    SeqImplNol,
+    SeqFollowOl,
+    SeqFollowNol,
+}
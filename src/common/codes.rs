@@ -0,0 +1,342 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Stable codes identifying diagnostics.
+//!
+//! Diagnostics may carry a code such as `SV0001`, which is printed alongside
+//! the message and does not change across releases even if the wording of the
+//! message does. Each code comes with an extended description and an example,
+//! which `moore explain <CODE>` prints. Codes starting with `SV` are emitted by
+//! the SystemVerilog frontend, codes starting with `VH` by the VHDL frontend.
+//!
+//! Not every diagnostic carries a code yet. Codes have been assigned to the
+//! errors users most commonly run into, such as unresolved or duplicate names,
+//! mismatched instance connections, and misuse of types and constants; all
+//! other diagnostics print without one.
+//!
+//! Codes are never reused. A diagnostic that is removed retires its code.
+
+use std::fmt;
+
+/// A stable code identifying a diagnostic.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DiagCode {
+    /// The code itself, e.g. `SV0001`.
+    pub code: &'static str,
+    /// A one-line summary of the diagnostic.
+    pub summary: &'static str,
+    /// An extended description of the diagnostic, with an example.
+    pub explanation: &'static str,
+}
+
+impl fmt::Display for DiagCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.code)
+    }
+}
+
+/// Find the code with a given name.
+///
+/// The lookup is case-insensitive, such that `sv0001` finds `SV0001`.
+pub fn lookup(code: &str) -> Option<DiagCode> {
+    ALL.iter()
+        .find(|c| c.code.eq_ignore_ascii_case(code))
+        .cloned()
+}
+
+macro_rules! declare_codes {
+    ($($name:ident: $summary:expr, $explanation:expr;)*) => {
+        $(
+            #[doc = $summary]
+            pub const $name: DiagCode = DiagCode {
+                code: stringify!($name),
+                summary: $summary,
+                explanation: $explanation,
+            };
+        )*
+
+        /// All known diagnostic codes, in ascending order.
+        pub const ALL: &[DiagCode] = &[$($name),*];
+    };
+}
+
+declare_codes! {
+    SV0001: "A name could not be resolved.",
+r#"A name was used that does not refer to any declaration visible at this
+point. Names are looked up in the enclosing scopes, in the packages imported
+into them, and among the modules, interfaces, and packages of the design.
+
+Example:
+
+    module foo;
+        initial x = 1;  // `x` is not declared
+    endmodule
+
+Declare the name before using it, import the package that declares it, or
+check the name for typos."#;
+
+    SV0002: "A name could not be found inside a scope.",
+r#"A hierarchical or scoped name refers to a member that the scope does not
+contain. This happens when accessing an item of a package, a signal of an
+instance, or a block inside a module.
+
+Example:
+
+    package pkg;
+        localparam int A = 1;
+    endpackage
+    module foo;
+        int x = pkg::B;  // `pkg` has no `B`
+    endmodule
+
+Check that the member is declared in the scope and spelled correctly."#;
+
+    SV0003: "A name is declared multiple times in the same scope.",
+r#"Every name may only be declared once per scope. A later declaration of the
+same name is rejected, since references to it would be ambiguous.
+
+Example:
+
+    module foo;
+        int a;
+        int a;  // `a` already declared above
+    endmodule
+
+Rename or remove one of the declarations."#;
+
+    SV0004: "An instance connects a port that the module does not have.",
+r#"A named port connection `.name(...)` in a module instantiation refers to a
+port that is not among the ports of the instantiated module.
+
+Example:
+
+    module bar (input logic a);
+    endmodule
+    module foo;
+        bar i_bar (.a(1), .b(0));  // `bar` has no port `b`
+    endmodule
+
+Remove the connection or add the port to the instantiated module."#;
+
+    SV0005: "A port is connected multiple times.",
+r#"Each port of a module may be connected at most once. This may happen when a
+port appears several times in a port list, or when multiple external ports of a
+non-ANSI module refer to the same internal port.
+
+Example:
+
+    module bar (a, a);
+        input logic a;
+    endmodule
+
+Connect every port only once."#;
+
+    SV0006: "An instance assigns a parameter that the module does not have.",
+r#"A named parameter assignment `#(.NAME(...))` in a module instantiation
+refers to a parameter that the instantiated module does not declare.
+
+Example:
+
+    module bar #(parameter int N = 1);
+    endmodule
+    module foo;
+        bar #(.M(2)) i_bar();  // `bar` has no parameter `M`
+    endmodule
+
+Remove the assignment or add the parameter to the instantiated module."#;
+
+    SV0007: "An instance assigns more parameters than the module has.",
+r#"A module instantiation passes more positional parameter assignments than
+the instantiated module declares parameters.
+
+Example:
+
+    module bar #(parameter int N = 1);
+    endmodule
+    module foo;
+        bar #(1, 2) i_bar();  // `bar` only has one parameter
+    endmodule
+
+Remove the superfluous assignments."#;
+
+    SV0008: "A field is accessed that the value does not have.",
+r#"A member access `x.name` refers to a field that is not declared in the
+struct or union type of `x`.
+
+Example:
+
+    module foo;
+        struct packed { logic a; } s;
+        initial s.b = 0;  // the struct has no field `b`
+    endmodule
+
+Check the type of the value and the spelling of the field."#;
+
+    SV0009: "A name is used as a type but does not refer to a type.",
+r#"A type was expected, for example in a declaration or a cast, but the name
+given refers to something else, such as a variable or a parameter.
+
+Example:
+
+    module foo;
+        int a;
+        a b;  // `a` is a variable, not a type
+    endmodule
+
+Use a `typedef` to declare a type name, or `type(a)` to refer to the type of
+a value."#;
+
+    SV0010: "The type of an expression cannot be inferred.",
+r#"Some expressions, such as unsized literals, assignment patterns, and
+concatenations of unsized values, have no self-determined type. Their type is
+derived from the context they appear in. If the context does not provide a
+type either, the expression is rejected.
+
+Example:
+
+    module foo;
+        initial $display('{1, 2});  // no type for the pattern
+    endmodule
+
+Add a cast such as `T'(...)` to give the expression a type."#;
+
+    SV0011: "An expression is assigned to that cannot be assigned to.",
+r#"The left-hand side of an assignment must refer to a variable, a net, a
+port, or a select or member of one. Other expressions, such as constants,
+parameters, or the results of operators, cannot be assigned to.
+
+Example:
+
+    module foo;
+        localparam int N = 1;
+        initial N = 2;  // parameters are constant
+    endmodule
+
+Assign to a variable instead."#;
+
+    SV0012: "A value that must be constant is not.",
+r#"Some values must be known at compile time, for example the values of
+parameters, the bounds of ranges, and the initial values of declarations that
+are folded into constants. Such values may not depend on variables, nets, or
+ports.
+
+Example:
+
+    module foo (input int a);
+        localparam int N = a;  // `a` is a port
+    endmodule
+
+Use a parameter or a literal instead."#;
+
+    SV0013: "An assignment pattern sets an element outside of the array.",
+r#"The index of a keyed array assignment pattern must lie within the bounds of
+the array the pattern is assigned to.
+
+Example:
+
+    module foo;
+        int a [2];
+        initial a = '{0: 1, 2: 3};  // `a` only has elements 0 and 1
+    endmodule
+
+Remove the element or enlarge the array."#;
+
+    SV0014: "A list has a trailing comma.",
+r#"Lists such as port lists, parameter lists, and argument lists do not allow a
+comma after the last element. The comma is ignored.
+
+Example:
+
+    module foo (input a, input b,);  // comma after `b`
+    endmodule
+
+Remove the comma."#;
+
+    SV0015: "A type cannot be passed through the DPI.",
+r#"Functions and tasks imported from or exported to C through the Direct
+Programming Interface may only use the types listed in Annex H of IEEE
+1800-2017 for their arguments. Only a subset of these may be returned from a
+function: `void`, `byte`, `shortint`, `int`, `longint`, `real`, `shortreal`,
+`chandle`, `string`, and scalar `bit` and `logic`.
+
+Example:
+
+    import "DPI-C" function logic [7:0] foo();  // vectors cannot be returned
+
+Pass vectors as `output` arguments instead of returning them."#;
+
+    SV0016: "A DPI export refers to a subroutine not declared in its scope.",
+r#"An `export "DPI-C"` declaration makes a function or task callable from C.
+The subroutine must be declared in the same scope as the export.
+
+Example:
+
+    module foo;
+        export "DPI-C" function bar;  // `bar` is not declared in `foo`
+    endmodule
+
+Declare the subroutine next to the export."#;
+
+    VH0001: "A name could not be resolved.",
+r#"A name was used that does not refer to any declaration visible at this
+point. Names are looked up in the enclosing declarative regions and among the
+declarations made visible by `use` clauses.
+
+Example:
+
+    entity foo is end;
+    architecture bar of foo is
+        signal a : bit := b;  -- `b` is not declared
+    begin end;
+
+Declare the name, add the `use` clause that makes it visible, or check the
+name for typos."#;
+
+    VH0002: "A name refers to multiple declarations.",
+r#"A name that refers to more than one visible declaration can only be used if
+the context decides which of the declarations is meant, for example through
+overload resolution on the argument and return types of a subprogram. If the
+context is not sufficient, the name is rejected.
+
+Example:
+
+    use work.pkg_a.all;  -- declares `c`
+    use work.pkg_b.all;  -- also declares `c`
+    ...
+    x <= c;  -- which `c`?
+
+Use a selected name such as `work.pkg_a.c` to refer to one of the
+declarations."#;
+
+    VH0003: "A name is declared multiple times in the same region.",
+r#"A declarative region may only contain one declaration of a name, unless all
+declarations are overloadable subprograms or enumeration literals with distinct
+signatures.
+
+Example:
+
+    architecture bar of foo is
+        signal a : bit;
+        signal a : bit;  -- `a` already declared above
+    begin end;
+
+Rename or remove one of the declarations."#;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_are_unique_and_sorted() {
+        for pair in ALL.windows(2) {
+            assert!(pair[0].code < pair[1].code, "{} >= {}", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn lookup_ignores_case() {
+        assert_eq!(lookup("SV0001"), Some(SV0001));
+        assert_eq!(lookup("vh0002"), Some(VH0002));
+        assert_eq!(lookup("SV9999"), None);
+    }
+}
@@ -2,7 +2,9 @@
 
 //! Utilities to implement diagnostics and error reporting facilities.
 
-use crate::source::Span;
+use crate::codes::DiagCode;
+use crate::lints::Lint;
+use crate::source::{display_width, Span, BYTE_ORDER_MARK};
 use std::fmt;
 
 /// Print debug information. Omitted in release builds.
@@ -75,12 +77,20 @@ pub struct DiagBuilder2 {
     pub severity: Severity,
     pub message: String,
     pub segments: Vec<DiagSegment>,
+    pub code: Option<DiagCode>,
+    pub lint: Option<Lint>,
 }
 
 #[derive(Clone, Debug)]
 pub enum DiagSegment {
     Span(Span),
     Note(String),
+    /// A suggested fix, replacing the source text in a span.
+    Suggestion {
+        message: String,
+        span: Span,
+        replacement: String,
+    },
 }
 
 /// A diagnostic result type. Either carries the result `T` in the Ok variant,
@@ -93,6 +103,8 @@ impl DiagBuilder2 {
             severity: severity,
             message: message.into(),
             segments: Vec::new(),
+            code: None,
+            lint: None,
         }
     }
 
@@ -116,6 +128,17 @@ impl DiagBuilder2 {
         DiagBuilder2::new(Severity::Note, message)
     }
 
+    /// Create a warning that belongs to a lint.
+    ///
+    /// The level of the lint determines whether the warning is reported, and
+    /// whether it is reported as an error. See the `lints` module.
+    pub fn lint<S: Into<String>>(lint: Lint, message: S) -> DiagBuilder2 {
+        DiagBuilder2 {
+            lint: Some(lint),
+            ..DiagBuilder2::new(Severity::Warning, message)
+        }
+    }
+
     pub fn segment(self, segment: DiagSegment) -> DiagBuilder2 {
         let mut segments = self.segments;
         segments.push(segment);
@@ -133,6 +156,34 @@ impl DiagBuilder2 {
         self.segment(DiagSegment::Note(message.into()))
     }
 
+    /// Suggest a fix which replaces the source text in `span` with
+    /// `replacement`.
+    ///
+    /// The message should describe the fix, e.g. "Remove the comma". An empty
+    /// replacement suggests deleting the text.
+    pub fn span_suggestion<M: Into<String>, R: Into<String>>(
+        self,
+        span: Span,
+        message: M,
+        replacement: R,
+    ) -> DiagBuilder2 {
+        self.segment(DiagSegment::Suggestion {
+            message: message.into(),
+            span,
+            replacement: replacement.into(),
+        })
+    }
+
+    /// Assign a stable code to the diagnostic.
+    ///
+    /// See the `codes` module for a list of codes.
+    pub fn code(self, code: DiagCode) -> DiagBuilder2 {
+        DiagBuilder2 {
+            code: Some(code),
+            ..self
+        }
+    }
+
     pub fn get_severity(&self) -> Severity {
         self.severity
     }
@@ -144,9 +195,76 @@ impl DiagBuilder2 {
     pub fn get_segments(&self) -> &[DiagSegment] {
         &self.segments
     }
+
+    pub fn get_code(&self) -> Option<DiagCode> {
+        self.code
+    }
+
+    pub fn get_lint(&self) -> Option<Lint> {
+        self.lint
+    }
+}
+
+impl DiagBuilder2 {
+    /// Convert the diagnostic to JSON.
+    ///
+    /// The spans of the diagnostic are listed in `spans`, with the first one
+    /// being the primary location. Each span carries the byte offsets into its
+    /// source file, and the 1-based line and column where it begins and ends.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut spans = vec![];
+        let mut notes = vec![];
+        let mut suggestions = vec![];
+        for segment in &self.segments {
+            match *segment {
+                DiagSegment::Span(sp) => spans.push(span_to_json(sp)),
+                DiagSegment::Note(ref message) => notes.push(message.clone()),
+                DiagSegment::Suggestion {
+                    ref message,
+                    span,
+                    ref replacement,
+                } => suggestions.push(serde_json::json!({
+                    "message": message,
+                    "span": span_to_json(span),
+                    "replacement": replacement,
+                })),
+            }
+        }
+        serde_json::json!({
+            "severity": self.get_severity().to_str(),
+            "code": self.get_code().map(|c| c.code),
+            "lint": self.get_lint().map(|l| l.name),
+            "message": self.get_message(),
+            "spans": spans,
+            "notes": notes,
+            "suggestions": suggestions,
+        })
+    }
+}
+
+fn span_to_json(sp: Span) -> serde_json::Value {
+    let snippet = sp.snippet();
+    serde_json::json!({
+        "file": &snippet.path[..],
+        "begin": sp.begin,
+        "end": sp.end,
+        "line": snippet.begin.0,
+        "column": snippet.begin.1,
+        "end_line": snippet.end.0,
+        "end_column": snippet.end.1,
+        "text": snippet.text(),
+        "expansions": sp
+            .expansion_chain()
+            .into_iter()
+            .map(|expansion| serde_json::json!({
+                "macro": &expansion.name[..],
+                "call_site": span_to_json(expansion.call_site),
+            }))
+            .collect::<Vec<_>>(),
+    })
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub enum Severity {
     Note,
     Warning,
@@ -173,88 +291,176 @@ impl fmt::Display for Severity {
     }
 }
 
-impl fmt::Display for DiagBuilder2 {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+/// The way diagnostics are rendered as human-readable text.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DiagStyle {
+    /// Whether to color and underline the output with ANSI escape sequences.
+    pub color: bool,
+    /// The width of the terminal, to which notes are wrapped.
+    pub width: Option<usize>,
+    /// The number of columns between tab stops.
+    pub tab_width: usize,
+}
+
+impl Default for DiagStyle {
+    fn default() -> DiagStyle {
+        DiagStyle {
+            color: true,
+            width: None,
+            tab_width: 4,
+        }
+    }
+}
+
+const RESET: &str = "\x1B[m";
+const BOLD: &str = "\x1B[1m";
+const UNDERLINE: &str = "\x1B[4m";
+const GREEN: &str = "\x1B[32m";
+
+impl DiagBuilder2 {
+    /// Render the diagnostic as human-readable text.
+    pub fn render(&self, style: DiagStyle) -> String {
+        let mut out = String::new();
+        self.write_human(&mut out, style).unwrap();
+        out
+    }
+
+    fn write_human(&self, f: &mut impl fmt::Write, style: DiagStyle) -> fmt::Result {
+        let esc = |code: &'static str| if style.color { code } else { "" };
         let mut colorcode = match self.get_severity() {
             Severity::Bug | Severity::Fatal | Severity::Error => "\x1B[31;1m",
             Severity::Warning => "\x1B[33;1m",
             Severity::Note => "\x1B[36;1m",
         };
+        write!(f, "{}{}", esc(colorcode), self.get_severity())?;
+        if let Some(code) = self.get_code() {
+            write!(f, "[{}]", code)?;
+        }
         write!(
             f,
-            "{}{}:\x1B[m\x1B[1m {}\x1B[m\n",
-            colorcode,
-            self.get_severity(),
-            self.get_message()
+            ":{}{} {}{}",
+            esc(RESET),
+            esc(BOLD),
+            self.get_message(),
+            esc(RESET)
         )?;
+        match self.get_lint() {
+            Some(lint) if self.get_severity() >= Severity::Error => {
+                write!(f, " [-Werror={}]", lint)?
+            }
+            Some(lint) => write!(f, " [-W{}]", lint)?,
+            None => (),
+        }
+        writeln!(f)?;
 
         for segment in &self.segments {
             match *segment {
                 DiagSegment::Span(sp) => {
-                    let c = sp.source.get_content();
-
-                    // Look for the start of the line.
-                    let (line, col, line_offset) = sp.begin().human();
-
-                    // Print the line in question.
-                    let text: String = c
-                        .iter_from(line_offset)
-                        .map(|x| x.1)
-                        .take_while(|c| *c != '\n' && *c != '\r')
-                        .collect();
-                    write!(
-                        f,
-                        "  --> {}:{}:{}-{}:\n",
-                        sp.source.get_path(),
-                        line,
-                        col,
-                        col + sp.extract().len()
-                    )?;
-                    write!(f, "   | \n")?;
+                    let snippet = sp.snippet();
+                    let range = snippet.range.clone();
+
+                    // Determine the columns as displayed by an editor.
+                    let line = snippet.begin.0;
+                    let col = sp.begin().display_column(style.tab_width);
+                    let end_col = if snippet.end.0 == line {
+                        sp.end().display_column(style.tab_width)
+                    } else {
+                        col + snippet.text().chars().count()
+                    };
+
+                    // Print the line in question, with the span underlined.
+                    // Tabs are expanded to the next tab stop.
+                    let text = snippet.line();
+                    writeln!(f, "  --> {}:{}:{}-{}:", snippet.path, line, col, end_col)?;
+                    writeln!(f, "   | ")?;
                     write!(f, "   | ")?;
-                    for (mut i, c) in text.char_indices() {
-                        i += line_offset;
-                        if sp.begin != sp.end {
-                            if i == sp.begin {
-                                write!(f, "{}", colorcode)?;
+                    let mut column = 0;
+                    for (i, c) in text.char_indices() {
+                        if range.start != range.end {
+                            if i == range.start {
+                                write!(f, "{}{}", esc(colorcode), esc(UNDERLINE))?;
                             }
-                            if i == sp.end {
-                                write!(f, "\x1B[m")?;
+                            if i == range.end {
+                                write!(f, "{}", esc(RESET))?;
                             }
                         }
+                        let width = display_width(c, column, style.tab_width);
                         match c {
-                            '\t' => write!(f, "    ")?,
+                            '\t' => write!(f, "{:1$}", "", width)?,
+                            BYTE_ORDER_MARK => (),
                             c => write!(f, "{}", c)?,
                         }
+                        column += width;
                     }
-                    write!(f, "\x1B[m\n")?;
+                    writeln!(f, "{}", esc(RESET))?;
                     write!(f, "   | ")?;
 
                     // Print the caret markers for the line in question.
                     let mut pd = ' ';
-                    for (mut i, c) in text.char_indices() {
-                        i += line_offset;
-                        let d = if (i >= sp.begin && i < sp.end)
-                            || (i == sp.begin && sp.begin == sp.end)
+                    let mut column = 0;
+                    for (i, c) in text.char_indices() {
+                        let d = if range.contains(&i)
+                            || (i == range.start && range.start == range.end)
                         {
                             '^'
                         } else {
                             ' '
                         };
                         if d != pd {
-                            write!(f, "{}", if d == ' ' { "\x1B[m" } else { colorcode })?;
+                            write!(f, "{}", esc(if d == ' ' { RESET } else { colorcode }))?;
                         }
                         pd = d;
-                        match c {
-                            '\t' => write!(f, "{}{}{}{}", d, d, d, d)?,
-                            _ => write!(f, "{}", d)?,
+                        let width = display_width(c, column, style.tab_width);
+                        for _ in 0..width {
+                            write!(f, "{}", d)?;
                         }
+                        column += width;
+                    }
+                    writeln!(f, "{}", esc(RESET))?;
+
+                    // Print the macro invocations that produced the span.
+                    for expansion in sp.expansion_chain() {
+                        let call_site = expansion.call_site.snippet();
+                        write_note(
+                            f,
+                            style,
+                            "note",
+                            &format!(
+                                "in expansion of `{}` at {}:{}:{}",
+                                expansion.name,
+                                call_site.path,
+                                call_site.begin.0,
+                                call_site.begin.1
+                            ),
+                        )?;
                     }
-                    write!(f, "\x1B[m\n")?;
-                    colorcode = "\x1B[1m";
+                    colorcode = BOLD;
                 }
-                DiagSegment::Note(ref message) => {
-                    write!(f, "   = \x1B[1mnote:\x1B[m {}\n", message)?
+                DiagSegment::Note(ref message) => write_note(f, style, "note", message)?,
+                DiagSegment::Suggestion {
+                    ref message,
+                    span,
+                    ref replacement,
+                } => {
+                    write_note(f, style, "help", message)?;
+
+                    // Print the line in question with the fix applied, if
+                    // the fix does not span multiple lines.
+                    let snippet = span.snippet();
+                    let line = snippet.line();
+                    let (begin, end) = (snippet.range.start, snippet.range.end);
+                    if end <= line.len() && !replacement.contains('\n') {
+                        writeln!(f, "   | ")?;
+                        writeln!(
+                            f,
+                            "   | {}{}{}{}{}",
+                            &line[..begin],
+                            esc(GREEN),
+                            replacement,
+                            esc(RESET),
+                            &line[end..]
+                        )?;
+                    }
                 }
             }
         }
@@ -266,9 +472,138 @@ impl fmt::Display for DiagBuilder2 {
                  you open an issue [1] and describe how you triggered the bug, together with a \
                  minimal snippet of code to reproduce it. Thanks!\n"
             )?;
-            write!(f, "[1]: https://github.com/fabianschuiki/moore\n")?;
+            writeln!(f, "[1]: https://github.com/fabianschuiki/moore")?;
         }
 
         Ok(())
     }
 }
+
+/// Print a note or help message, wrapped to the width of the terminal.
+///
+/// Continuation lines are indented to align with the first line.
+fn write_note(f: &mut impl fmt::Write, style: DiagStyle, kind: &str, message: &str) -> fmt::Result {
+    let (bold, reset) = if style.color { (BOLD, RESET) } else { ("", "") };
+    let indent = "   = ".len() + kind.len() + 2;
+    write!(f, "   = {}{}:{} ", bold, kind, reset)?;
+    let width = match style.width {
+        Some(width) if width > indent + 20 => width - indent,
+        _ => return writeln!(f, "{}", message),
+    };
+    let mut column = 0;
+    for word in message.split(' ') {
+        if column > 0 && column + 1 + word.chars().count() > width {
+            write!(f, "\n{:1$}", "", indent)?;
+            column = 0;
+        } else if column > 0 {
+            write!(f, " ")?;
+            column += 1;
+        }
+        write!(f, "{}", word)?;
+        column += word.chars().count();
+    }
+    writeln!(f)
+}
+
+impl fmt::Display for DiagBuilder2 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_human(f, DiagStyle::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::get_source_manager;
+
+    #[test]
+    fn json() {
+        let source = get_source_manager().add("diag_json.sv", "module foo (a,);\n");
+        let comma = Span::new(source, 13, 14);
+        let diag = DiagBuilder2::warning("superfluous trailing comma")
+            .code(crate::codes::SV0014)
+            .span(comma)
+            .add_note("Some note")
+            .span_suggestion(comma, "Remove the comma", "");
+        let json = diag.to_json();
+        assert_eq!(json["severity"], "warning");
+        assert_eq!(json["code"], "SV0014");
+        assert_eq!(json["spans"][0]["file"], "diag_json.sv");
+        assert_eq!(json["spans"][0]["begin"], 13);
+        assert_eq!(json["spans"][0]["end"], 14);
+        assert_eq!(json["spans"][0]["line"], 1);
+        assert_eq!(json["spans"][0]["column"], 14);
+        assert_eq!(json["notes"][0], "Some note");
+        assert_eq!(json["suggestions"][0]["replacement"], "");
+    }
+
+    #[test]
+    fn suggestion_applied_in_output() {
+        let source = get_source_manager().add("diag_suggestion.sv", "bar i (.a(x));\n");
+        let diag = DiagBuilder2::error("requires positional connections").span_suggestion(
+            Span::new(source, 7, 12),
+            "Remove `.a(...)`",
+            "x",
+        );
+        let output = format!("{}", diag);
+        assert!(output.contains("help:\x1B[m Remove `.a(...)`\n"));
+        assert!(output.contains("   | bar i (\x1B[32mx\x1B[m);\n"));
+    }
+
+    #[test]
+    fn plain_and_wrapped_output() {
+        let source = get_source_manager().add("diag_style.sv", "int x = y;\n");
+        let diag = DiagBuilder2::error("`y` not found")
+            .span(Span::new(source, 8, 9))
+            .add_note("Names are looked up in the enclosing scopes and the imported packages");
+        let style = DiagStyle {
+            color: false,
+            width: Some(40),
+            tab_width: 4,
+        };
+        assert_eq!(
+            diag.render(style),
+            "error: `y` not found\n\
+             \x20 --> diag_style.sv:1:9-10:\n\
+             \x20  | \n\
+             \x20  | int x = y;\n\
+             \x20  |         ^ \n\
+             \x20  = note: Names are looked up in the\n\
+             \x20          enclosing scopes and the\n\
+             \x20          imported packages\n"
+        );
+    }
+
+    #[test]
+    fn tabs_and_unicode_columns() {
+        let source = get_source_manager().add("diag_tabs.sv", "\u{FEFF}\tü\tx = y;\n");
+        let diag = DiagBuilder2::error("`x` not found").span(Span::new(source, 7, 8));
+        let style = DiagStyle {
+            color: false,
+            width: None,
+            tab_width: 8,
+        };
+        assert_eq!(
+            diag.render(style),
+            "error: `x` not found\n\
+             \x20 --> diag_tabs.sv:1:17-18:\n\
+             \x20  | \n\
+             \x20  |         ü       x = y;\n\
+             \x20  |                 ^     \n"
+        );
+    }
+
+    #[test]
+    fn expansion_chain_in_output() {
+        let sm = get_source_manager();
+        let source = sm.add("diag_expansion.sv", "`define FOO bar\n`FOO\n");
+        let outer = sm.add_expansion("FOO", Span::new(source, 16, 20));
+        let diag = DiagBuilder2::error("unknown `bar`")
+            .span(Span::new(source, 12, 15).with_expansion(outer));
+        let output = format!("{}", diag);
+        assert!(output.contains("note:\x1B[m in expansion of `FOO` at diag_expansion.sv:2:1\n"));
+        let json = diag.to_json();
+        assert_eq!(json["spans"][0]["expansions"][0]["macro"], "FOO");
+        assert_eq!(json["spans"][0]["expansions"][0]["call_site"]["line"], 2);
+    }
+}
@@ -0,0 +1,224 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Reporting internal compiler errors.
+//!
+//! A panic inside the compiler is a bug, regardless of whether it was preceded
+//! by a compiler bug diagnostic or not. The driver runs the compiler through
+//! `catch`, which intercepts the panic and returns its message and location
+//! instead of printing them. Together with the context that the compiler
+//! records in the session while unwinding, such as the active queries, these
+//! are then reported as an internal compiler error (ICE).
+//!
+//! The compiler may also unwind deliberately through `abort` after it has
+//! reported a fatal error in the input, such as a dependency cycle, from which
+//! it cannot recover. This is not a compiler bug and `catch` marks it as such.
+
+use crate::errors::DiagBuilder2;
+use crate::source::Span;
+use backtrace::Backtrace;
+use std::cell::{Cell, RefCell};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+
+/// The exit code of the compiler after an internal compiler error.
+///
+/// This is the exit code of an uncaught Rust panic, and is distinct from the
+/// exit code 1 that indicates errors in the input.
+pub const ICE_EXIT_CODE: i32 = 101;
+
+/// The number of queries listed in an ICE report.
+const MAX_LISTED_QUERIES: usize = 16;
+
+/// The maximum length of the description of a query in an ICE report.
+const MAX_QUERY_LEN: usize = 120;
+
+/// What the compiler was doing when it panicked.
+#[derive(Debug, Default)]
+pub struct CrashContext {
+    /// The queries that were executing, innermost first.
+    pub queries: Vec<String>,
+    /// The locations in the source code that were being processed, with a
+    /// description of each.
+    pub spans: Vec<(String, Span)>,
+}
+
+/// A panic intercepted by `catch`.
+#[derive(Debug)]
+pub struct Panic {
+    /// The panic message.
+    pub message: String,
+    /// The location in the compiler source code where the panic occurred.
+    pub location: Option<String>,
+    /// The backtrace of the panic, if enabled through `RUST_BACKTRACE`.
+    pub backtrace: Option<Backtrace>,
+    /// Whether the compiler unwound through `abort` after reporting a fatal
+    /// error, rather than panicking due to a bug.
+    pub fatal: bool,
+}
+
+/// The payload of an unwind started by `abort`.
+struct FatalError;
+
+/// Abort the compilation after a fatal error has been reported.
+///
+/// This unwinds to the enclosing `catch` without invoking the panic hook, such
+/// that nothing is printed.
+pub fn abort() -> ! {
+    panic::resume_unwind(Box::new(FatalError))
+}
+
+thread_local! {
+    /// Whether panics are currently intercepted by `catch`.
+    static CATCHING: Cell<bool> = Cell::new(false);
+    /// The last panic intercepted by `catch`.
+    static PANIC: RefCell<Option<Panic>> = RefCell::new(None);
+}
+
+/// Run a function, intercepting any panic that occurs within it.
+///
+/// The panic is not printed, but returned as an error instead. Panics outside
+/// of `catch` are printed as usual.
+pub fn catch<R>(f: impl FnOnce() -> R) -> Result<R, Panic> {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !CATCHING.with(|c| c.get()) {
+                return default_hook(info);
+            }
+            let message = if let Some(s) = info.payload().downcast_ref::<&str>() {
+                s.to_string()
+            } else if let Some(s) = info.payload().downcast_ref::<String>() {
+                s.clone()
+            } else {
+                "unknown panic".to_string()
+            };
+            let panic = Panic {
+                message,
+                location: info
+                    .location()
+                    .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+                backtrace: if backtrace_enabled() {
+                    Some(Backtrace::new())
+                } else {
+                    None
+                },
+                fatal: false,
+            };
+            PANIC.with(|p| *p.borrow_mut() = Some(panic));
+        }));
+    });
+
+    let was_catching = CATCHING.with(|c| c.replace(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    CATCHING.with(|c| c.set(was_catching));
+    result.map_err(|payload| {
+        if payload.is::<FatalError>() {
+            return Panic {
+                message: "aborted after a fatal error".to_string(),
+                location: None,
+                backtrace: None,
+                fatal: true,
+            };
+        }
+        PANIC
+            .with(|p| p.borrow_mut().take())
+            .unwrap_or_else(|| Panic {
+                message: "unknown panic".to_string(),
+                location: None,
+                backtrace: None,
+                fatal: false,
+            })
+    })
+}
+
+/// Check whether backtraces are enabled through `RUST_BACKTRACE`.
+fn backtrace_enabled() -> bool {
+    std::env::var_os("RUST_BACKTRACE").map_or(false, |v| v != "0")
+}
+
+impl Panic {
+    /// Describe the panic as a compiler bug diagnostic.
+    ///
+    /// The diagnostic lists the compiler version, the location of the panic,
+    /// and the context in which it occurred, followed by hints on how to
+    /// report it.
+    pub fn to_diag(&self, version: &str, context: &CrashContext) -> DiagBuilder2 {
+        let mut diag = DiagBuilder2::bug(self.message.clone());
+        diag = match self.location {
+            Some(ref location) => diag.add_note(format!(
+                "Internal compiler error in moore {}, panicked at {}",
+                version, location
+            )),
+            None => diag.add_note(format!("Internal compiler error in moore {}", version)),
+        };
+        if !context.queries.is_empty() {
+            diag = diag.add_note("Query stack, innermost first:");
+            for query in context.queries.iter().take(MAX_LISTED_QUERIES) {
+                let mut query = query.clone();
+                if query.chars().count() > MAX_QUERY_LEN {
+                    query = query.chars().take(MAX_QUERY_LEN).collect();
+                    query.push_str("...");
+                }
+                diag = diag.add_note(format!("  - {}", query));
+            }
+            if context.queries.len() > MAX_LISTED_QUERIES {
+                diag = diag.add_note(format!(
+                    "  and {} more",
+                    context.queries.len() - MAX_LISTED_QUERIES
+                ));
+            }
+        }
+        for (description, span) in &context.spans {
+            let snippet = span.snippet();
+            diag = diag.add_note(format!(
+                "{} {}:{}:{}",
+                description, snippet.path, snippet.begin.0, snippet.begin.1
+            ));
+        }
+        diag = match self.backtrace {
+            Some(ref backtrace) => diag.add_note(format!("Backtrace:\n{:?}", backtrace)),
+            None => diag.add_note("Run with `RUST_BACKTRACE=1` to include a backtrace"),
+        };
+        diag.add_note(
+            "To help reproduce the crash, reduce the input to the smallest design that still \
+             triggers it, e.g. by removing unrelated modules and statements, and attach it to \
+             the issue",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catch_panic() {
+        assert_eq!(catch(|| 42).unwrap(), 42);
+        let panic = catch(|| panic!("oh no {}", 42)).unwrap_err();
+        assert_eq!(panic.message, "oh no 42");
+        assert!(panic.location.unwrap().contains("ice.rs"));
+        assert!(!panic.fatal);
+    }
+
+    #[test]
+    fn catch_abort() {
+        let panic = catch(|| abort()).unwrap_err();
+        assert!(panic.fatal);
+        assert!(panic.location.is_none());
+    }
+
+    #[test]
+    fn report() {
+        let context = CrashContext {
+            queries: vec!["type_of(x)".to_string(), "y".repeat(200)],
+            spans: vec![],
+        };
+        let panic = catch(|| panic!("boom")).unwrap_err();
+        let output = format!("{}", panic.to_diag("1.2.3", &context));
+        assert!(output.contains("compiler bug:\x1B[m\x1B[1m boom"));
+        assert!(output.contains("Internal compiler error in moore 1.2.3, panicked at"));
+        assert!(output.contains("  - type_of(x)\n"));
+        assert!(output.contains(&format!("  - {}...\n", "y".repeat(MAX_QUERY_LEN))));
+    }
+}
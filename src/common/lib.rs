@@ -8,48 +8,438 @@ extern crate bitflags;
 
 #[macro_use]
 pub mod arenas;
+pub mod assertion;
+pub mod codes;
 pub mod errors;
 pub mod grind;
+pub mod ice;
 pub mod id;
 pub mod lexer;
+pub mod lints;
 pub mod name;
+pub mod sarif;
 pub mod score;
 pub mod source;
+pub mod timing;
 pub mod util;
 
 pub use self::id::NodeId;
-use crate::errors::{DiagBuilder2, DiagEmitter, Severity};
-use std::cell::Cell;
+use crate::errors::{DiagBuilder2, DiagEmitter, DiagSegment, DiagStyle, Severity};
+use crate::ice::CrashContext;
+use crate::lints::{LintBaseline, LintLevel, LintLevels};
+use crate::source::{Span, INVALID_SOURCE};
+use crate::timing::{PassTimer, Timings};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
 
 pub struct Session {
     pub opts: SessionOptions,
     /// Whether any error diagnostics were produced.
     pub failed: Cell<bool>,
+    /// The diagnostics emitted so far, if `opts.keep_diagnostics` is set.
+    pub diagnostics: RefCell<Vec<DiagBuilder2>>,
+    /// The number of warnings suppressed by comments in the source code.
+    pub suppressed: Cell<usize>,
+    /// The warnings suppressed so far, if `opts.keep_diagnostics` is set.
+    pub suppressed_diagnostics: RefCell<Vec<DiagBuilder2>>,
+    /// The known warnings which are not reported, if any.
+    pub lint_baseline: RefCell<Option<LintBaseline>>,
+    /// The number of warnings not reported since they are in the baseline.
+    pub baselined: Cell<usize>,
+    /// The diagnostics waiting to be printed, grouped by their location.
+    pending: RefCell<Vec<PendingDiag>>,
+    /// The index into `pending` of each distinct diagnostic.
+    pending_index: RefCell<HashMap<DiagKey, usize>>,
+    /// The stack of instance names leading to the instantiation currently being
+    /// elaborated.
+    inst_path: RefCell<Vec<String>>,
+    /// The number of distinct errors emitted so far.
+    errors: Cell<usize>,
+    /// The number of diagnostics emitted so far, not counting suppressed and
+    /// baselined warnings.
+    emitted: Cell<usize>,
+    /// The number of errors and warnings printed, by severity and category.
+    printed: RefCell<DiagCounts>,
+    /// The time spent in passes and queries, if `opts.time_passes` is set,
+    /// and the memory used, if `measure_memory` returns `true`.
+    pub timings: Timings,
+    /// What the compiler was doing when it panicked, recorded while
+    /// unwinding.
+    pub crash_context: RefCell<CrashContext>,
 }
 
+/// The number of diagnostics printed, by severity and category.
+#[derive(Default)]
+struct DiagCounts {
+    errors: usize,
+    warnings: usize,
+    /// The number of diagnostics per code or warning category.
+    categories: BTreeMap<&'static str, usize>,
+}
+
+/// The fields that identify repeated reports of the same diagnostic.
+type DiagKey = (Severity, Option<&'static str>, String, Option<Span>);
+
+/// A diagnostic waiting to be printed.
+struct PendingDiag {
+    diag: DiagBuilder2,
+    /// The instantiations in which the diagnostic was reported.
+    contexts: Vec<String>,
+}
+
+/// The number of instantiation contexts listed for a repeated diagnostic.
+const MAX_LISTED_CONTEXTS: usize = 3;
+
+/// The number of queries listed in the report of `print_timings`.
+const MAX_LISTED_QUERIES: usize = 10;
+
 impl Session {
     /// Create a new session.
     pub fn new() -> Session {
         Session {
             opts: Default::default(),
             failed: Cell::new(false),
+            diagnostics: Default::default(),
+            suppressed: Cell::new(0),
+            suppressed_diagnostics: Default::default(),
+            lint_baseline: Default::default(),
+            baselined: Cell::new(0),
+            pending: Default::default(),
+            pending_index: Default::default(),
+            inst_path: Default::default(),
+            errors: Cell::new(0),
+            emitted: Cell::new(0),
+            printed: Default::default(),
+            timings: Default::default(),
+            crash_context: Default::default(),
         }
     }
 
     pub fn failed(&self) -> bool {
         self.failed.get()
     }
+
+    /// The number of diagnostics emitted so far.
+    ///
+    /// Warnings which are allowed, suppressed by a comment, or part of the
+    /// lint baseline are not counted.
+    pub fn num_emitted(&self) -> usize {
+        self.emitted.get()
+    }
+
+    /// Enter an instantiation while elaborating a design.
+    ///
+    /// Diagnostics emitted until the matching `leave_inst` are attributed to
+    /// the hierarchical path of the instantiation.
+    pub fn enter_inst(&self, name: &str) {
+        self.inst_path.borrow_mut().push(name.to_string());
+    }
+
+    /// Leave the instantiation entered last.
+    pub fn leave_inst(&self) {
+        self.inst_path.borrow_mut().pop();
+    }
+
+    /// Print the diagnostics emitted so far.
+    ///
+    /// The diagnostics are sorted by file and position, such that the output
+    /// does not depend on the order in which the compiler happened to visit
+    /// the design. A diagnostic that was reported multiple times at the same
+    /// location, e.g. once for every instantiation of a module, is printed
+    /// only once, together with a summary of the instantiations that
+    /// triggered it.
+    pub fn flush_diagnostics(&self) {
+        for PendingDiag { mut diag, contexts } in self.take_pending() {
+            if contexts.len() > 1 {
+                let mut listed = contexts[..contexts.len().min(MAX_LISTED_CONTEXTS)]
+                    .iter()
+                    .map(|c| format!("`{}`", c))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                if contexts.len() > MAX_LISTED_CONTEXTS {
+                    listed.push_str(&format!(
+                        ", and {} more",
+                        contexts.len() - MAX_LISTED_CONTEXTS
+                    ));
+                }
+                diag = diag.add_note(format!(
+                    "Reported for {} instantiations: {}",
+                    contexts.len(),
+                    listed
+                ));
+            }
+            match self.opts.diag_format {
+                DiagFormat::Human => eprintln!("{}", diag.render(self.opts.diag_style)),
+                DiagFormat::Json => eprintln!("{}", diag.to_json()),
+            }
+            let mut printed = self.printed.borrow_mut();
+            match diag.get_severity() {
+                Severity::Warning => printed.warnings += 1,
+                Severity::Note => (),
+                _ => printed.errors += 1,
+            }
+            let category = diag
+                .get_lint()
+                .map(|l| l.name)
+                .or_else(|| diag.get_code().map(|c| c.code));
+            if let Some(category) = category {
+                *printed.categories.entry(category).or_insert(0) += 1;
+            }
+            if self.opts.keep_diagnostics {
+                self.diagnostics.borrow_mut().push(diag);
+            }
+        }
+    }
+
+    /// Take the diagnostics emitted so far without printing them.
+    ///
+    /// This allows tools such as the language server to present the
+    /// diagnostics in their own way. The diagnostics are returned in the
+    /// order in which they were emitted.
+    pub fn take_diagnostics(&self) -> Vec<DiagBuilder2> {
+        self.pending_index.borrow_mut().clear();
+        self.pending
+            .borrow_mut()
+            .drain(..)
+            .map(|pending| pending.diag)
+            .collect()
+    }
+
+    /// Take the diagnostics waiting to be printed, sorted by location.
+    ///
+    /// Diagnostics without a location come last. Diagnostics at the same
+    /// location keep the order in which they were emitted.
+    fn take_pending(&self) -> Vec<PendingDiag> {
+        let mut index = self.pending_index.borrow_mut();
+        if self.opts.stream_diagnostics {
+            // Keep recognizing repeats of the diagnostics printed already.
+            index.values_mut().for_each(|i| *i = std::usize::MAX);
+        } else {
+            index.clear();
+        }
+        let mut pending = std::mem::replace(&mut *self.pending.borrow_mut(), vec![]);
+        pending.sort_by_cached_key(|p| match primary_span(&p.diag) {
+            Some(sp) => (false, sp.source.get_path().to_string(), sp.begin),
+            None => (true, String::new(), 0),
+        });
+        pending
+    }
+
+    /// Start measuring a pass, if `opts.time_passes` is set.
+    ///
+    /// The pass ends when the returned timer is dropped.
+    pub fn time_pass(&self, name: impl Into<String>) -> PassTimer<'_> {
+        if self.opts.time_passes {
+            self.timings.start(name)
+        } else {
+            PassTimer::disabled()
+        }
+    }
+
+    /// Print the time spent in the passes and the hottest queries, if
+    /// `opts.time_passes` is set.
+    pub fn print_timings(&self) {
+        if !self.opts.time_passes {
+            return;
+        }
+        match self.opts.diag_format {
+            DiagFormat::Human => eprint!("{}", self.timings.report(MAX_LISTED_QUERIES)),
+            DiagFormat::Json => eprintln!(
+                "{}",
+                serde_json::json!({ "timings": self.timings.to_json() })
+            ),
+        }
+    }
+
+    /// Whether the memory used by the compiler should be recorded in
+    /// `timings`, i.e. if `opts.time_passes` or `opts.memory_report` is set.
+    pub fn measure_memory(&self) -> bool {
+        self.opts.time_passes || self.opts.memory_report
+    }
+
+    /// Print the memory used, broken down by the kind of node or value, if
+    /// `opts.memory_report` is set.
+    pub fn print_memory_report(&self) {
+        if !self.opts.memory_report {
+            return;
+        }
+        match self.opts.diag_format {
+            DiagFormat::Human => eprint!("{}", self.timings.memory_report()),
+            DiagFormat::Json => eprintln!(
+                "{}",
+                serde_json::json!({ "memory": self.timings.memory_to_json() })
+            ),
+        }
+    }
+
+    /// Print a summary of the diagnostics printed so far.
+    ///
+    /// The summary is a single line of the form `moore: 2 errors, 1 warning
+    /// (SV0001: 2, label: 1)`, listing the number of diagnostics per code and
+    /// warning category, such that it can easily be picked up from CI logs.
+    pub fn print_summary(&self) {
+        self.flush_diagnostics();
+        let printed = self.printed.borrow();
+        match self.opts.diag_format {
+            DiagFormat::Human => {
+                let plural = |n: usize, what: &str| {
+                    format!("{} {}{}", n, what, if n == 1 { "" } else { "s" })
+                };
+                let mut line = format!(
+                    "moore: {}, {}",
+                    plural(printed.errors, "error"),
+                    plural(printed.warnings, "warning")
+                );
+                if self.suppressed.get() > 0 {
+                    line.push_str(&format!(", {} suppressed", self.suppressed.get()));
+                }
+                if self.baselined.get() > 0 {
+                    line.push_str(&format!(", {} baselined", self.baselined.get()));
+                }
+                if !printed.categories.is_empty() {
+                    let categories: Vec<_> = printed
+                        .categories
+                        .iter()
+                        .map(|(c, n)| format!("{}: {}", c, n))
+                        .collect();
+                    line.push_str(&format!(" ({})", categories.join(", ")));
+                }
+                eprintln!("{}", line);
+            }
+            DiagFormat::Json => {
+                eprintln!("{}", serde_json::json!({ "summary": self.summary() }))
+            }
+        }
+    }
+
+    /// The number of diagnostics printed so far, as a JSON object.
+    ///
+    /// Lists the number of errors and warnings, of warnings suppressed and
+    /// baselined, and of diagnostics per code and warning category.
+    pub fn summary(&self) -> serde_json::Value {
+        let printed = self.printed.borrow();
+        serde_json::json!({
+            "errors": printed.errors,
+            "warnings": printed.warnings,
+            "suppressed": self.suppressed.get(),
+            "baselined": self.baselined.get(),
+            "categories": printed.categories,
+        })
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.flush_diagnostics();
+    }
 }
 
 impl DiagEmitter for Session {
-    fn emit(&self, diag: DiagBuilder2) {
+    fn emit(&self, mut diag: DiagBuilder2) {
+        if let Some(lint) = diag.get_lint() {
+            match self.opts.lint_levels.get(lint) {
+                LintLevel::Allow => return,
+                LintLevel::Warn => (),
+                LintLevel::Deny => diag.severity = Severity::Error,
+            }
+
+            // Check whether the warning is suppressed by a comment at its
+            // primary location. Warnings without a location in a source file
+            // cannot be suppressed.
+            let suppressed = diag.get_segments().iter().find_map(|s| match *s {
+                DiagSegment::Span(sp) if sp.source == INVALID_SOURCE => Some(false),
+                DiagSegment::Span(sp) => Some(sp.source.lint_pragmas().suppresses(lint, sp.begin)),
+                _ => None,
+            });
+            if suppressed == Some(true) {
+                self.suppressed.set(self.suppressed.get() + 1);
+                if self.opts.keep_diagnostics {
+                    self.suppressed_diagnostics.borrow_mut().push(diag);
+                }
+                return;
+            }
+
+            // Skip warnings that are known from the baseline.
+            if let Some(ref mut baseline) = *self.lint_baseline.borrow_mut() {
+                if baseline.covers(&diag) {
+                    self.baselined.set(self.baselined.get() + 1);
+                    return;
+                }
+            }
+        }
         if diag.severity >= Severity::Error {
             self.failed.set(true);
         }
-        eprintln!("{}", diag);
+        self.emitted.set(self.emitted.get() + 1);
+
+        // Group the diagnostic with earlier reports at the same location, and
+        // record the instantiation it was reported for.
+        let context = self.inst_path.borrow().join(".");
+        let key = (
+            diag.severity,
+            diag.get_code().map(|c| c.code),
+            diag.get_message().clone(),
+            primary_span(&diag),
+        );
+        let severity = diag.severity;
+        let existing = self.pending_index.borrow().get(&key).cloned();
+        match existing {
+            Some(index) => {
+                if let Some(pending) = self.pending.borrow_mut().get_mut(index) {
+                    if !context.is_empty() && !pending.contexts.contains(&context) {
+                        pending.contexts.push(context);
+                    }
+                }
+            }
+            None => {
+                if severity >= Severity::Error {
+                    self.errors.set(self.errors.get() + 1);
+                }
+                let mut pending = self.pending.borrow_mut();
+                self.pending_index.borrow_mut().insert(key, pending.len());
+                pending.push(PendingDiag {
+                    diag,
+                    contexts: if context.is_empty() {
+                        vec![]
+                    } else {
+                        vec![context]
+                    },
+                });
+            }
+        }
+
+        // Compiler bugs abort the compilation, so print everything right away.
+        // The same goes for streaming mode, where diagnostics are printed as
+        // they come in rather than sorted at the end.
+        if severity == Severity::Bug || self.opts.stream_diagnostics {
+            self.flush_diagnostics();
+        }
+
+        // Stop once the configured number of errors has been reached.
+        if let Some(limit) = self.opts.error_limit {
+            if severity >= Severity::Error && self.errors.get() >= limit {
+                self.flush_diagnostics();
+                let diag = DiagBuilder2::note(format!("too many errors, stopping after {}", limit))
+                    .add_note("Use `--error-limit` to report more errors");
+                match self.opts.diag_format {
+                    DiagFormat::Human => eprintln!("{}", diag.render(self.opts.diag_style)),
+                    DiagFormat::Json => eprintln!("{}", diag.to_json()),
+                }
+                self.print_summary();
+                std::process::exit(1);
+            }
+        }
     }
 }
 
+/// The location a diagnostic primarily refers to.
+fn primary_span(diag: &DiagBuilder2) -> Option<Span> {
+    diag.get_segments().iter().find_map(|s| match *s {
+        DiagSegment::Span(sp) => Some(sp),
+        _ => None,
+    })
+}
+
 impl SessionContext for Session {
     fn has_verbosity(&self, verb: Verbosity) -> bool {
         self.opts.verbosity.contains(verb)
@@ -75,6 +465,41 @@ pub struct SessionOptions {
     pub verbosity: Verbosity,
     /// The optimization level.
     pub opt_level: usize,
+    /// Instrument cover statements with hit counters.
+    pub coverage: bool,
+    /// The format in which diagnostics are printed.
+    pub diag_format: DiagFormat,
+    /// The colors and width of human-readable diagnostics.
+    pub diag_style: DiagStyle,
+    /// Keep a copy of every emitted diagnostic in the session.
+    pub keep_diagnostics: bool,
+    /// The levels at which warnings are reported.
+    pub lint_levels: LintLevels,
+    /// Stop after this many errors.
+    pub error_limit: Option<usize>,
+    /// Print diagnostics as soon as they are emitted, instead of sorted by
+    /// location once the compiler is done.
+    pub stream_diagnostics: bool,
+    /// Measure the time spent in passes and queries.
+    pub time_passes: bool,
+    /// Measure the memory occupied by tokens, AST and HIR nodes, types, and
+    /// parameter environments.
+    pub memory_report: bool,
+}
+
+/// The format in which diagnostics are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagFormat {
+    /// Human-readable text with source excerpts.
+    Human,
+    /// One JSON object per line, for consumption by other tools.
+    Json,
+}
+
+impl Default for DiagFormat {
+    fn default() -> DiagFormat {
+        DiagFormat::Human
+    }
 }
 
 bitflags! {
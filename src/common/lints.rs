@@ -0,0 +1,590 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Warning categories and their levels.
+//!
+//! Every warning belongs to a category, called a lint. The level at which the
+//! warnings of a lint are reported can be changed on the command line:
+//! `-W<lint>` enables a lint, `-Wno-<lint>` disables it, and `-Werror=<lint>`
+//! turns its warnings into errors. Each lint has a default level, such that
+//! noisy checks can be disabled unless explicitly requested.
+//!
+//! Warnings can also be suppressed in the source code through comments:
+//!
+//! ```text
+//! // moore lint_off trailing-comma
+//! ...
+//! // moore lint_on trailing-comma
+//! ```
+//!
+//! disables a lint for the lines in between. `lint_off` and `lint_on` without
+//! a name apply to all lints. `// moore lint_ignore <lint>` suppresses a lint
+//! on the line the comment is on, or on the next line if the comment is on a
+//! line of its own. Lint names are case-insensitive and may use underscores
+//! instead of dashes, such that `TRAILING_COMMA` also works. In VHDL files the
+//! comments start with `--` instead.
+//!
+//! The levels can also be read from a configuration file, which lists one lint
+//! per line together with its level:
+//!
+//! ```text
+//! # Lists must not end in a comma.
+//! trailing-comma = deny
+//! unsupported = allow
+//! ```
+//!
+//! Existing warnings may be recorded in a baseline file, such that only new
+//! warnings are reported. See [`LintBaseline`].
+
+use crate::errors::{DiagBuilder2, DiagSegment};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// A category of warnings.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Lint {
+    /// The name of the lint, as used on the command line.
+    pub name: &'static str,
+    /// A short description of the warnings in this category.
+    pub desc: &'static str,
+    /// The level at which the lint is reported unless configured otherwise.
+    pub default: LintLevel,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// The level at which the warnings of a lint are reported.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum LintLevel {
+    /// The warnings are not reported.
+    Allow,
+    /// The warnings are reported as warnings.
+    Warn,
+    /// The warnings are reported as errors.
+    Deny,
+}
+
+impl LintLevel {
+    /// Find the level with a given name, i.e. `allow`, `warn`, or `deny`.
+    pub fn from_name(name: &str) -> Option<LintLevel> {
+        match name {
+            "allow" => Some(LintLevel::Allow),
+            "warn" => Some(LintLevel::Warn),
+            "deny" => Some(LintLevel::Deny),
+            _ => None,
+        }
+    }
+}
+
+/// Find the lint with a given name.
+pub fn lookup(name: &str) -> Option<Lint> {
+    ALL.iter().find(|l| l.name == name).cloned()
+}
+
+/// The configured levels of the lints.
+///
+/// Lints which have not been configured explicitly are reported at their
+/// default level.
+#[derive(Clone, Default, Debug)]
+pub struct LintLevels {
+    levels: HashMap<&'static str, LintLevel>,
+    /// Report all warnings as errors.
+    pub warnings_as_errors: bool,
+}
+
+impl LintLevels {
+    /// Set the level of a lint.
+    pub fn set(&mut self, lint: Lint, level: LintLevel) {
+        self.levels.insert(lint.name, level);
+    }
+
+    /// Set the level of all lints.
+    pub fn set_all(&mut self, level: LintLevel) {
+        for lint in ALL {
+            self.set(*lint, level);
+        }
+    }
+
+    /// Determine the level at which a lint is reported.
+    pub fn get(&self, lint: Lint) -> LintLevel {
+        let level = self.levels.get(lint.name).cloned().unwrap_or(lint.default);
+        if self.warnings_as_errors && level == LintLevel::Warn {
+            LintLevel::Deny
+        } else {
+            level
+        }
+    }
+
+    /// Apply a `-W` command line option.
+    ///
+    /// Accepts `<lint>`, `no-<lint>`, `error=<lint>`, `no-error=<lint>`,
+    /// `error`, and `all`. Returns an error message if the option is invalid.
+    pub fn apply_flag(&mut self, flag: &str) -> Result<(), String> {
+        let (name, level) = match flag {
+            "all" => {
+                self.set_all(LintLevel::Warn);
+                return Ok(());
+            }
+            "error" => {
+                self.warnings_as_errors = true;
+                return Ok(());
+            }
+            _ if flag.starts_with("error=") => (&flag[6..], LintLevel::Deny),
+            _ if flag.starts_with("no-error=") => (&flag[9..], LintLevel::Warn),
+            _ if flag.starts_with("no-") => (&flag[3..], LintLevel::Allow),
+            _ => (flag, LintLevel::Warn),
+        };
+        match lookup(name) {
+            Some(lint) => {
+                self.set(lint, level);
+                Ok(())
+            }
+            None => Err(format!("unknown warning category `{}`", name)),
+        }
+    }
+
+    /// Apply the contents of a configuration file.
+    ///
+    /// Each line assigns a level to a lint, or to `all` lints, as in
+    /// `trailing-comma = deny`. Empty lines and lines starting with `#` are
+    /// ignored. Returns an error message if a line is invalid.
+    pub fn apply_config(&mut self, text: &str) -> Result<(), String> {
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |msg: String| format!("line {}: {}", index + 1, msg);
+            let (name, level) = match line.find('=') {
+                Some(eq) => (line[..eq].trim(), line[eq + 1..].trim()),
+                None => {
+                    return Err(error(format!(
+                        "expected `<lint> = <level>`, found `{}`",
+                        line
+                    )))
+                }
+            };
+            self.apply_setting(name, level).map_err(error)?;
+        }
+        Ok(())
+    }
+
+    /// Set the level of a lint, or of `all` lints, by name.
+    ///
+    /// Returns an error message if the lint or level is unknown.
+    pub fn apply_setting(&mut self, name: &str, level: &str) -> Result<(), String> {
+        let level = LintLevel::from_name(level).ok_or_else(|| {
+            format!(
+                "unknown level `{}`; expected `allow`, `warn`, or `deny`",
+                level
+            )
+        })?;
+        match name {
+            "all" => self.set_all(level),
+            _ => match lookup(name) {
+                Some(lint) => self.set(lint, level),
+                None => return Err(format!("unknown warning category `{}`", name)),
+            },
+        }
+        Ok(())
+    }
+}
+
+/// The regions of a source file in which lints are suppressed by comments.
+#[derive(Clone, Default, Debug)]
+pub struct LintPragmas {
+    regions: Vec<SuppressedRegion>,
+}
+
+/// A range of bytes in which a lint is suppressed.
+#[derive(Clone, Debug)]
+struct SuppressedRegion {
+    /// The normalized name of the lint, or `None` for all lints.
+    lint: Option<String>,
+    begin: usize,
+    end: usize,
+}
+
+/// The markers that start a comment in SystemVerilog.
+pub const SVLOG_COMMENTS: &[&str] = &["//", "/*"];
+
+/// The markers that start a comment in VHDL.
+pub const VHDL_COMMENTS: &[&str] = &["--"];
+
+impl LintPragmas {
+    /// Find the suppression comments in the text of a source file.
+    ///
+    /// Comments are recognized by the given markers, which depend on the
+    /// language of the file. See `SVLOG_COMMENTS` and `VHDL_COMMENTS`.
+    pub fn parse(text: &str, comment_markers: &[&str]) -> LintPragmas {
+        let mut regions = vec![];
+        let mut open: Vec<(Option<String>, usize)> = vec![];
+        let mut line_begin = 0;
+        let mut lines = text.split('\n').peekable();
+        while let Some(line) = lines.next() {
+            // The line ends after its newline, if it has one.
+            let line_end = (line_begin + line.len() + 1).min(text.len());
+            if let Some((has_code, directive, names)) = parse_pragma(line, comment_markers) {
+                let names = if names.is_empty() {
+                    vec![None]
+                } else {
+                    names.into_iter().map(Some).collect()
+                };
+                match directive {
+                    "lint_off" => open.extend(names.into_iter().map(|n| (n, line_end))),
+                    "lint_on" => {
+                        let closes =
+                            |lint: &Option<String>| names.iter().any(|n| n.is_none() || n == lint);
+                        for (lint, begin) in open.iter().filter(|(l, _)| closes(l)) {
+                            regions.push(SuppressedRegion {
+                                lint: lint.clone(),
+                                begin: *begin,
+                                end: line_begin,
+                            });
+                        }
+                        open.retain(|(l, _)| !closes(l));
+                    }
+                    "lint_ignore" => {
+                        let (begin, end) = if has_code {
+                            (line_begin, line_end)
+                        } else {
+                            let next_len = lines.peek().map(|l| l.len() + 1).unwrap_or(0);
+                            (line_end, (line_end + next_len).min(text.len()))
+                        };
+                        regions.extend(names.into_iter().map(|lint| SuppressedRegion {
+                            lint,
+                            begin,
+                            end,
+                        }));
+                    }
+                    _ => (),
+                }
+            }
+            line_begin = line_end;
+        }
+        regions.extend(open.into_iter().map(|(lint, begin)| SuppressedRegion {
+            lint,
+            begin,
+            end: text.len(),
+        }));
+        LintPragmas { regions }
+    }
+
+    /// Check whether a lint is suppressed at a byte offset.
+    pub fn suppresses(&self, lint: Lint, offset: usize) -> bool {
+        self.regions.iter().any(|r| {
+            offset >= r.begin
+                && offset < r.end
+                && r.lint.as_ref().map(|n| n == lint.name).unwrap_or(true)
+        })
+    }
+}
+
+/// Parse a suppression comment on a line.
+///
+/// Returns whether the comment is preceded by code, the directive, and the
+/// normalized names of the lints it applies to.
+fn parse_pragma<'a>(
+    line: &'a str,
+    comment_markers: &[&str],
+) -> Option<(bool, &'a str, Vec<String>)> {
+    let (index, marker) = comment_markers
+        .iter()
+        .flat_map(|marker| line.find(marker).map(|index| (index, marker)))
+        .min()?;
+    let mut words = line[index + marker.len()..]
+        .trim_end_matches(|c: char| c.is_whitespace() || c == '/' || c == '*')
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|w| !w.is_empty());
+    if words.next()? != "moore" {
+        return None;
+    }
+    let directive = words.next()?;
+    let names = words
+        .map(|w| w.to_ascii_lowercase().replace('_', "-"))
+        .collect();
+    Some((!line[..index].trim().is_empty(), directive, names))
+}
+
+/// A set of known warnings that are not reported.
+///
+/// Large codebases may adopt a lint gradually by recording the warnings it
+/// currently produces in a baseline file, and only reporting warnings that are
+/// not in the baseline. Warnings are identified by their lint, file, message,
+/// and the text of the line they point at, such that edits elsewhere in a file
+/// do not invalidate the baseline.
+#[derive(Clone, Default, Debug)]
+pub struct LintBaseline {
+    /// The number of occurrences of each known warning.
+    warnings: BTreeMap<BaselineEntry, usize>,
+    /// Whether warnings are added to the baseline instead of being looked up.
+    pub recording: bool,
+}
+
+/// The fields identifying a warning in a baseline.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+struct BaselineEntry {
+    lint: String,
+    file: String,
+    message: String,
+    line: String,
+}
+
+impl LintBaseline {
+    /// Create an empty baseline that records the warnings emitted.
+    pub fn record() -> LintBaseline {
+        LintBaseline {
+            warnings: Default::default(),
+            recording: true,
+        }
+    }
+
+    /// Read a baseline from the contents of a baseline file.
+    pub fn parse(text: &str) -> Result<LintBaseline, String> {
+        let json: serde_json::Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        let mut warnings = BTreeMap::new();
+        for entry in json["warnings"]
+            .as_array()
+            .ok_or("missing `warnings` list")?
+        {
+            let field = |name: &str| {
+                entry[name]
+                    .as_str()
+                    .map(String::from)
+                    .ok_or(format!("warning without `{}`", name))
+            };
+            let entry_key = BaselineEntry {
+                lint: field("lint")?,
+                file: field("file")?,
+                message: field("message")?,
+                line: field("line")?,
+            };
+            let count = entry["count"].as_u64().unwrap_or(1) as usize;
+            *warnings.entry(entry_key).or_insert(0) += count;
+        }
+        Ok(LintBaseline {
+            warnings,
+            recording: false,
+        })
+    }
+
+    /// Convert the baseline into the contents of a baseline file.
+    pub fn to_json(&self) -> serde_json::Value {
+        let warnings: Vec<_> = self
+            .warnings
+            .iter()
+            .map(|(entry, count)| {
+                serde_json::json!({
+                    "lint": entry.lint,
+                    "file": entry.file,
+                    "message": entry.message,
+                    "line": entry.line,
+                    "count": count,
+                })
+            })
+            .collect();
+        serde_json::json!({ "version": 1, "warnings": warnings })
+    }
+
+    /// The number of warnings in the baseline.
+    pub fn len(&self) -> usize {
+        self.warnings.values().sum()
+    }
+
+    /// Check whether the baseline contains no warnings.
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    /// Check whether a diagnostic is covered by the baseline.
+    ///
+    /// Each warning in the baseline covers as many diagnostics as it occurred
+    /// when the baseline was recorded. When recording, the diagnostic is added
+    /// to the baseline instead. Diagnostics without a lint are never covered.
+    pub fn covers(&mut self, diag: &DiagBuilder2) -> bool {
+        let lint = match diag.get_lint() {
+            Some(lint) => lint,
+            None => return false,
+        };
+        let (file, line) = match diag.get_segments().iter().find_map(|s| match *s {
+            DiagSegment::Span(sp) => Some(sp),
+            _ => None,
+        }) {
+            Some(sp) => {
+                let snippet = sp.snippet();
+                (snippet.path.to_string(), snippet.line().trim().to_string())
+            }
+            None => (String::new(), String::new()),
+        };
+        let entry = BaselineEntry {
+            lint: lint.name.to_string(),
+            file,
+            message: diag.get_message().clone(),
+            line,
+        };
+        if self.recording {
+            *self.warnings.entry(entry).or_insert(0) += 1;
+            return true;
+        }
+        match self.warnings.get_mut(&entry) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+macro_rules! declare_lints {
+    ($($ident:ident: $name:expr, $default:ident, $desc:expr;)*) => {
+        $(
+            #[doc = $desc]
+            pub const $ident: Lint = Lint {
+                name: $name,
+                desc: $desc,
+                default: LintLevel::$default,
+            };
+        )*
+
+        /// All known lints.
+        pub const ALL: &[Lint] = &[$($ident),*];
+    };
+}
+
+declare_lints! {
+    TRAILING_COMMA: "trailing-comma", Warn,
+        "A list has a superfluous trailing comma.";
+    UNSUPPORTED: "unsupported", Warn,
+        "A construct is not supported by moore and is ignored.";
+    ARG_ORDER: "arg-order", Warn,
+        "Positional parameters or ports appear after named ones.";
+    LITERAL_OVERFLOW: "literal-overflow", Warn,
+        "A literal does not fit into its explicit size and is truncated.";
+    PATTERN_OVERWRITE: "pattern-overwrite", Warn,
+        "An assignment pattern sets the same element more than once.";
+    LABEL: "label", Warn,
+        "A block label is repeated, missing, or does not match.";
+    NOT_CALLABLE: "not-callable", Warn,
+        "An expression is called that is not a function or task.";
+    INPUT_FILE: "input-file", Warn,
+        "An input file is ignored since its language cannot be determined.";
+    GUARD: "guard", Warn,
+        "A VHDL signal assignment is guarded where guards have no effect.";
+    UNSUPPORTED_OPTION: "unsupported-option", Warn,
+        "An option on the command line or in a file list is not supported and ignored.";
+    SENSITIVITY: "sensitivity", Warn,
+        "A process reads a signal that is missing from its sensitivity list.";
+    UNUSED_SIGNAL: "unused-signal", Warn,
+        "A signal is never read.";
+    UNUSED_PARAM: "unused-param", Warn,
+        "A parameter is never used.";
+    UNUSED_PORT: "unused-port", Warn,
+        "A port is never used inside its module.";
+    MULTIPLE_DRIVERS: "multiple-drivers", Warn,
+        "A variable or net is driven from more than one place.";
+    COMB_LOOP: "comb-loop", Warn,
+        "Signals depend on each other combinationally in a loop.";
+    INFERRED_LATCH: "inferred-latch", Warn,
+        "A combinational process does not assign a variable on every path.";
+    WIDTH_MISMATCH: "width-mismatch", Allow,
+        "An assignment or comparison has operands of different widths.";
+    CASE_INCOMPLETE: "case-incomplete", Warn,
+        "A case statement without default does not handle every value.";
+    CASE_OVERLAP: "case-overlap", Warn,
+        "A case item overlaps with an earlier item.";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags() {
+        let mut levels = LintLevels::default();
+        assert_eq!(levels.get(LABEL), LintLevel::Warn);
+        levels.apply_flag("no-label").unwrap();
+        assert_eq!(levels.get(LABEL), LintLevel::Allow);
+        levels.apply_flag("error=label").unwrap();
+        assert_eq!(levels.get(LABEL), LintLevel::Deny);
+        levels.apply_flag("label").unwrap();
+        assert_eq!(levels.get(LABEL), LintLevel::Warn);
+        levels.apply_flag("error").unwrap();
+        assert_eq!(levels.get(LABEL), LintLevel::Deny);
+        assert!(levels.apply_flag("no-such-lint").is_err());
+    }
+
+    #[test]
+    fn config() {
+        let mut levels = LintLevels::default();
+        levels
+            .apply_config("# Some comment\n\nall = deny\n  label = allow  \n")
+            .unwrap();
+        assert_eq!(levels.get(LABEL), LintLevel::Allow);
+        assert_eq!(levels.get(TRAILING_COMMA), LintLevel::Deny);
+        assert!(levels.apply_config("label").is_err());
+        assert!(levels.apply_config("label = error").is_err());
+        assert_eq!(
+            levels.apply_config("\nno-such-lint = warn"),
+            Err("line 2: unknown warning category `no-such-lint`".to_string())
+        );
+    }
+
+    #[test]
+    fn pragmas() {
+        let text = "<a>\n\
+                    // moore lint_off TRAILING_COMMA\n\
+                    <b>\n\
+                    // moore lint_on trailing-comma\n\
+                    <c> // moore lint_ignore label\n\
+                    /* moore lint_ignore */\n\
+                    <d>\n\
+                    <e>\n";
+        let pragmas = LintPragmas::parse(text, SVLOG_COMMENTS);
+        let at = |s: &str| text.find(s).unwrap();
+        assert!(!pragmas.suppresses(TRAILING_COMMA, at("<a>")));
+        assert!(pragmas.suppresses(TRAILING_COMMA, at("<b>")));
+        assert!(!pragmas.suppresses(LABEL, at("<b>")));
+        assert!(!pragmas.suppresses(TRAILING_COMMA, at("<c>")));
+        assert!(pragmas.suppresses(LABEL, at("<c>")));
+        assert!(pragmas.suppresses(LABEL, at("<d>")));
+        assert!(pragmas.suppresses(TRAILING_COMMA, at("<d>")));
+        assert!(!pragmas.suppresses(LABEL, at("<e>")));
+    }
+
+    #[test]
+    fn pragma_languages() {
+        let text = "i--; // moore lint_ignore label\n\
+                    a <= b; -- moore lint_ignore trailing_comma\n";
+        let at = |s: &str| text.find(s).unwrap();
+        let svlog = LintPragmas::parse(text, SVLOG_COMMENTS);
+        assert!(svlog.suppresses(LABEL, at("i--")));
+        assert!(!svlog.suppresses(TRAILING_COMMA, at("a <=")));
+        let vhdl = LintPragmas::parse(text, VHDL_COMMENTS);
+        assert!(!vhdl.suppresses(LABEL, at("i--")));
+        assert!(vhdl.suppresses(TRAILING_COMMA, at("a <=")));
+    }
+
+    #[test]
+    fn baseline() {
+        use crate::source::{get_source_manager, Span};
+        let source = get_source_manager().add("baseline.sv", "foo (a,);\nfoo (b,);\n");
+        let warning = |offset| {
+            DiagBuilder2::lint(TRAILING_COMMA, "superfluous trailing comma").span(Span::new(
+                source,
+                offset,
+                offset + 1,
+            ))
+        };
+        let mut recorded = LintBaseline::record();
+        assert!(recorded.covers(&warning(6)));
+        assert_eq!(recorded.len(), 1);
+        let mut baseline = LintBaseline::parse(&recorded.to_json().to_string()).unwrap();
+        assert!(!baseline.covers(&warning(16)));
+        assert!(baseline.covers(&warning(6)));
+        assert!(!baseline.covers(&warning(6)));
+    }
+}
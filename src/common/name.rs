@@ -3,21 +3,35 @@
 //! A name table that internalizes all names presented to it and allows for them
 //! to be referred to by a lightweight tag. This structure is heavily inspired
 //! by the interner used in the Rust compiler.
+//!
+//! There is a single name table for the entire process, such that names may be
+//! passed between threads. The table is split into shards that are locked
+//! independently, which keeps threads interning different names from waiting
+//! on each other.
 
-use std::borrow::Borrow;
-use std::cell::RefCell;
+use once_cell::sync::Lazy;
+use std::borrow::{Borrow, Cow};
 use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 use std::fmt;
-use std::hash::Hash;
+use std::hash::{Hash, Hasher};
 use std::ops::Deref;
-use std::rc::Rc;
+use std::sync::{
+    atomic::{AtomicU32, Ordering as AtomicOrdering},
+    Arc, RwLock,
+};
 
 /// A name is a lightweight 32 bit tag that refers to a string in a name table.
 /// During parsing, encountered strings are inserted into the name table and
 /// only the corresponding tag is kept in the token. Names which have their most
 /// significant bit set represent case sensitive names, such as for extended
 /// identifiers.
+///
+/// Names are numbered in the order in which they are first interned, such that
+/// sorting names orders them by their first occurrence. Two names are equal if
+/// and only if they were interned from the same string, or from strings that
+/// only differ in case if the names are case insensitive.
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Name(pub u32);
 
@@ -74,20 +88,20 @@ impl<'a> From<&'a str> for Name {
 }
 
 /// A reference-counted string that acts like a regular str slice, hiding the
-/// fact that it is wrapped in Rc<>.
+/// fact that it is wrapped in Arc<>.
 #[derive(Clone, PartialEq, Hash, PartialOrd)]
-pub struct RcStr(Rc<String>);
+pub struct RcStr(Arc<String>);
 
 impl RcStr {
     /// Create a new ref-counted string which is a copy of `value`.
     pub fn new(value: &str) -> RcStr {
-        RcStr(Rc::new(value.to_string()))
+        RcStr(Arc::new(value.to_string()))
     }
 
     /// Create a new ref-counted string that contains `value`, without
     /// allocating any new storage.
     pub fn from(value: String) -> RcStr {
-        RcStr(Rc::new(value))
+        RcStr(Arc::new(value))
     }
 }
 
@@ -130,20 +144,30 @@ impl Into<String> for RcStr {
     }
 }
 
+/// The number of shards the name table is split into. Must be a power of two.
+const NUM_SHARDS: usize = 16;
+
 /// A lookup table of names. Internalizes strings either in a case sensitive or
 /// case insensitive way. Allows for bidirectional lookup, i.e. by string or by
 /// assigned name.
+///
+/// The table may be shared between threads. The lookup by string is sharded by
+/// the hash of the lowercase string, such that all spellings of a case
+/// insensitive name end up in the same shard. The lookup by name is sharded by
+/// the name's index.
 pub struct NameTable {
-    map: RefCell<HashMap<RcStr, Name>>,
-    vect: RefCell<Vec<RcStr>>,
+    map: Vec<RwLock<HashMap<RcStr, Name>>>,
+    vect: Vec<RwLock<Vec<Option<RcStr>>>>,
+    next: AtomicU32,
 }
 
 impl NameTable {
     /// Create a new empty name table.
     pub fn new() -> NameTable {
         NameTable {
-            map: RefCell::new(HashMap::new()),
-            vect: RefCell::new(Vec::new()),
+            map: (0..NUM_SHARDS).map(|_| Default::default()).collect(),
+            vect: (0..NUM_SHARDS).map(|_| Default::default()).collect(),
+            next: AtomicU32::new(0),
         }
     }
 
@@ -151,56 +175,125 @@ impl NameTable {
     /// table and returns the new name, or returns the existing name if the
     /// string already exists in the table.
     pub fn intern(&self, value: &str, case_sensitive: bool) -> Name {
-        let mut map = self.map.borrow_mut();
-        if let Some(&idx) = map.get(value) {
+        // Most names are lowercase already, in which case we can avoid an
+        // allocation.
+        let lower = if value
+            .bytes()
+            .any(|b| !b.is_ascii() || b.is_ascii_uppercase())
+        {
+            Cow::Owned(value.to_lowercase())
+        } else {
+            Cow::Borrowed(value)
+        };
+        let shard = &self.map[shard_of(&lower)];
+        if let Some(&idx) = shard.read().unwrap().get(value) {
             return idx;
         }
 
         // Since the name is not present in the table yet, we allocate a new idx
         // for it. Also, if it is a case-insensitive name, we insert both its
         // original form as well as its lowercase form into the lookup table.
-        let mut vect = self.vect.borrow_mut();
-        if case_sensitive {
-            let new_idx = Name((vect.len() as u32) << 1 | 1);
-            let v = RcStr::new(value);
-            map.insert(v.clone(), new_idx);
-            vect.push(v);
-            new_idx
-        } else {
-            let new_idx = Name((vect.len() as u32) << 1 | 0);
-            let lower = value.to_lowercase();
-            if let Some(&idx) = map.get(lower.as_str()) {
+        // Another thread may have added the name in the meantime, so check
+        // again.
+        let mut map = shard.write().unwrap();
+        if let Some(&idx) = map.get(value) {
+            return idx;
+        }
+        if !case_sensitive {
+            if let Some(&idx) = map.get(lower.as_ref()) {
                 return idx;
             }
-            let v = RcStr::new(value);
-            map.insert(RcStr::from(lower), new_idx);
-            map.insert(v.clone(), new_idx);
-            vect.push(v);
-            new_idx
         }
+        let index = self.next.fetch_add(1, AtomicOrdering::Relaxed);
+        let new_idx = Name(index << 1 | case_sensitive as u32);
+        let v = RcStr::new(value);
+        if !case_sensitive {
+            map.insert(RcStr::from(lower.into_owned()), new_idx);
+        }
+        map.insert(v.clone(), new_idx);
+
+        // Make the string available for lookup by name. The slot may lie
+        // beyond the end of the shard if other threads allocated names in the
+        // meantime, or it may have been reserved by a thread that allocated a
+        // later name first.
+        let mut vect = self.vect[index as usize % NUM_SHARDS].write().unwrap();
+        let slot = index as usize / NUM_SHARDS;
+        if vect.len() <= slot {
+            vect.resize(slot + 1, None);
+        }
+        vect[slot] = Some(v);
+        new_idx
     }
 
     /// Retrieve the string given a name tag.
     pub fn get(&self, idx: Name) -> RcStr {
-        (*self.vect.borrow())[(idx.0 >> 1) as usize].clone()
+        let index = (idx.0 >> 1) as usize;
+        self.vect[index % NUM_SHARDS].read().unwrap()[index / NUM_SHARDS]
+            .clone()
+            .expect("name not in table")
     }
 
     /// Try to find a string.
-    pub fn find<Q: ?Sized>(&self, value: &Q) -> Option<Name>
-    where
-        RcStr: Borrow<Q>,
-        Q: Eq + Hash,
-    {
-        (*self.map.borrow()).get(value).map(|v| *v)
-    }
-}
-
-/// Get this thread's current name table.
-pub fn get_name_table() -> Rc<NameTable> {
-    thread_local!(static TBL: Rc<NameTable> = {
-        let nt = NameTable::new();
-        // token::prefill_name_table(&mut nt);
-        Rc::new(nt)
-    });
-    TBL.with(|x| x.clone())
+    pub fn find(&self, value: &str) -> Option<Name> {
+        let lower = value.to_lowercase();
+        self.map[shard_of(&lower)]
+            .read()
+            .unwrap()
+            .get(value)
+            .cloned()
+    }
+}
+
+/// Determine the shard of the name table a lowercase string belongs to.
+fn shard_of(lower: &str) -> usize {
+    let mut hasher = DefaultHasher::new();
+    lower.hash(&mut hasher);
+    hasher.finish() as usize % NUM_SHARDS
+}
+
+/// Get the name table.
+///
+/// All threads share the same table.
+pub fn get_name_table() -> &'static NameTable {
+    static TBL: Lazy<NameTable> = Lazy::new(NameTable::new);
+    &TBL
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn case() {
+        let tbl = NameTable::new();
+        let a = tbl.intern("Foo", false);
+        assert_eq!(tbl.intern("FOO", false), a);
+        assert_eq!(tbl.intern("foo", false), a);
+        assert_ne!(tbl.intern("Bar", true), tbl.intern("bar", true));
+        assert_eq!(&*tbl.get(a), "Foo");
+        assert_eq!(tbl.find("fOO"), None);
+        assert_eq!(tbl.find("foo"), Some(a));
+    }
+
+    #[test]
+    fn threads() {
+        let tbl = Arc::new(NameTable::new());
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let tbl = tbl.clone();
+                std::thread::spawn(move || {
+                    (0..1000)
+                        .map(|i| tbl.intern(&format!("n{}", i), true))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let names: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        for other in &names[1..] {
+            assert_eq!(&names[0], other);
+        }
+        for (i, &name) in names[0].iter().enumerate() {
+            assert_eq!(*tbl.get(name), format!("n{}", i));
+        }
+    }
 }
@@ -0,0 +1,195 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Export of diagnostics in the Static Analysis Results Interchange Format.
+//!
+//! SARIF is an OASIS standard for the output of static analysis tools, which is
+//! understood by GitHub code scanning and various other dashboards. This module
+//! converts a list of diagnostics into a SARIF 2.1.0 log with a single run. The
+//! diagnostic codes of the `codes` module become the rules of the run.
+
+use crate::codes::DiagCode;
+use crate::errors::{DiagBuilder2, DiagSegment, Severity};
+use crate::source::Span;
+
+/// Convert a list of diagnostics into a SARIF log.
+///
+/// The `suppressed` diagnostics are included as results with an in-source
+/// suppression, such that dashboards can list them separately.
+pub fn to_sarif(diags: &[DiagBuilder2], suppressed: &[DiagBuilder2]) -> serde_json::Value {
+    let mut rules: Vec<DiagCode> = diags
+        .iter()
+        .chain(suppressed)
+        .flat_map(|d| d.get_code())
+        .collect();
+    rules.sort_by_key(|c| c.code);
+    rules.dedup();
+
+    let mut results: Vec<_> = diags.iter().map(|d| result_to_sarif(d, &rules)).collect();
+    results.extend(suppressed.iter().map(|d| {
+        let mut result = result_to_sarif(d, &rules);
+        result["suppressions"] = serde_json::json!([{ "kind": "inSource" }]);
+        result
+    }));
+    let rules: Vec<_> = rules
+        .iter()
+        .map(|c| {
+            serde_json::json!({
+                "id": c.code,
+                "shortDescription": { "text": c.summary },
+                "fullDescription": { "text": c.explanation },
+                "help": { "text": c.explanation },
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "moore",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/fabianschuiki/moore",
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }],
+    })
+}
+
+/// Convert a single diagnostic into a SARIF result.
+///
+/// The first span of the diagnostic becomes the location of the result, all
+/// further spans become related locations. Notes are appended to the message.
+fn result_to_sarif(diag: &DiagBuilder2, rules: &[DiagCode]) -> serde_json::Value {
+    let mut text = diag.get_message().clone();
+    let mut locations = vec![];
+    let mut fixes = vec![];
+    for segment in diag.get_segments() {
+        match *segment {
+            DiagSegment::Span(sp) => locations.push(location_to_sarif(sp)),
+            DiagSegment::Note(ref message) => {
+                text.push('\n');
+                text.push_str(message);
+            }
+            DiagSegment::Suggestion {
+                ref message,
+                span,
+                ref replacement,
+            } => fixes.push(serde_json::json!({
+                "description": { "text": message },
+                "artifactChanges": [{
+                    "artifactLocation": { "uri": uri_of(span) },
+                    "replacements": [{
+                        "deletedRegion": region_of(span),
+                        "insertedContent": { "text": replacement },
+                    }],
+                }],
+            })),
+        }
+    }
+
+    let level = match diag.get_severity() {
+        Severity::Bug | Severity::Fatal | Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Note => "note",
+    };
+    let mut result = serde_json::json!({
+        "level": level,
+        "message": { "text": text },
+    });
+    if let Some(code) = diag.get_code() {
+        result["ruleId"] = code.code.into();
+        result["ruleIndex"] = rules.iter().position(|&c| c == code).unwrap().into();
+    }
+    if !locations.is_empty() {
+        let related = locations.split_off(1);
+        result["locations"] = locations.into();
+        if !related.is_empty() {
+            result["relatedLocations"] = related.into();
+        }
+    }
+    if !fixes.is_empty() {
+        result["fixes"] = fixes.into();
+    }
+    result
+}
+
+/// Convert a span into a SARIF physical location.
+fn location_to_sarif(sp: Span) -> serde_json::Value {
+    serde_json::json!({
+        "physicalLocation": {
+            "artifactLocation": { "uri": uri_of(sp) },
+            "region": region_of(sp),
+        }
+    })
+}
+
+/// Convert a span into a SARIF region.
+fn region_of(sp: Span) -> serde_json::Value {
+    let snippet = sp.snippet();
+    serde_json::json!({
+        "startLine": snippet.begin.0,
+        "startColumn": snippet.begin.1,
+        "endLine": snippet.end.0,
+        "endColumn": snippet.end.1,
+        "byteOffset": sp.begin,
+        "byteLength": sp.end - sp.begin,
+    })
+}
+
+/// Determine the URI of the source file a span points into.
+///
+/// Relative paths are kept as relative references, such that the results can
+/// be matched against the files of a repository.
+fn uri_of(sp: Span) -> String {
+    let path = sp.source.get_path();
+    if path.starts_with('/') {
+        format!("file://{}", &path[..])
+    } else {
+        path[..].to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codes;
+    use crate::source::get_source_manager;
+
+    #[test]
+    fn results_and_rules() {
+        let source = get_source_manager().add("sarif.sv", "module foo (a,);\n");
+        let comma = Span::new(source, 13, 14);
+        let diags = vec![
+            DiagBuilder2::warning("superfluous trailing comma")
+                .code(codes::SV0014)
+                .span(comma)
+                .span_suggestion(comma, "Remove the comma", ""),
+            DiagBuilder2::error("something else").add_note("Some note"),
+        ];
+        let sarif = to_sarif(&diags[..1], &diags[1..]);
+        let run = &sarif["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "SV0014");
+        assert_eq!(run["results"][0]["ruleId"], "SV0014");
+        assert_eq!(run["results"][0]["ruleIndex"], 0);
+        assert_eq!(run["results"][0]["level"], "warning");
+        let location = &run["results"][0]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "sarif.sv");
+        assert_eq!(location["region"]["startColumn"], 14);
+        assert_eq!(location["region"]["byteOffset"], 13);
+        assert_eq!(
+            run["results"][0]["fixes"][0]["description"]["text"],
+            "Remove the comma"
+        );
+        assert_eq!(run["results"][1]["level"], "error");
+        assert_eq!(
+            run["results"][1]["message"]["text"],
+            "something else\nSome note"
+        );
+        assert!(run["results"][1].get("ruleId").is_none());
+        assert_eq!(run["results"][1]["suppressions"][0]["kind"], "inSource");
+    }
+}
@@ -4,6 +4,7 @@
 //! source file. This helps keeping the source location lean and allow for
 //! simple querying of information.
 
+use crate::lints::{self, LintPragmas};
 use crate::name::RcStr;
 use memmap::Mmap;
 use std;
@@ -12,6 +13,7 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt;
 use std::hash::{Hash, Hasher};
+use std::num::NonZeroU32;
 use std::path::Path;
 use std::rc::Rc;
 
@@ -24,6 +26,7 @@ pub const INVALID_SPAN: Span = Span {
     source: INVALID_SOURCE,
     begin: 0,
     end: 0,
+    expansion: None,
 };
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -46,6 +49,16 @@ impl Source {
     pub fn extract(self, begin: usize, end: usize) -> String {
         get_source_manager().with(self, |x| x.extract(begin, end))
     }
+
+    /// Check whether this source file only exists in memory.
+    pub fn is_virtual(self) -> bool {
+        get_source_manager().with(self, |x| x.is_virtual())
+    }
+
+    /// Obtain the lint suppression comments in this source file.
+    pub fn lint_pragmas(self) -> Rc<LintPragmas> {
+        get_source_manager().lint_pragmas(self)
+    }
 }
 
 impl fmt::Debug for Source {
@@ -105,6 +118,11 @@ pub trait SourceFile {
     fn extract(&self, begin: usize, end: usize) -> String {
         self.get_content().extract(begin, end)
     }
+
+    /// Check whether the file only exists in memory.
+    fn is_virtual(&self) -> bool {
+        false
+    }
 }
 
 pub trait SourceContent {
@@ -136,6 +154,8 @@ pub trait SourceContent {
 pub struct SourceManager {
     map: RefCell<HashMap<RcStr, Source>>,
     vect: RefCell<Vec<Box<dyn SourceFile>>>,
+    pragmas: RefCell<HashMap<Source, Rc<LintPragmas>>>,
+    expansions: RefCell<Vec<ExpansionInfo>>,
 }
 
 impl SourceManager {
@@ -143,6 +163,8 @@ impl SourceManager {
         SourceManager {
             map: RefCell::new(HashMap::new()),
             vect: RefCell::new(Vec::new()),
+            pragmas: RefCell::new(HashMap::new()),
+            expansions: RefCell::new(Vec::new()),
         }
     }
 
@@ -162,6 +184,44 @@ impl SourceManager {
         f(&*vect[id.0 as usize - 1])
     }
 
+    /// Obtain the lint suppression comments in a source file.
+    ///
+    /// The file is scanned for comments the first time this is called, and the
+    /// result is cached.
+    pub fn lint_pragmas(&self, id: Source) -> Rc<LintPragmas> {
+        if let Some(pragmas) = self.pragmas.borrow().get(&id) {
+            return pragmas.clone();
+        }
+        let path = id.get_path();
+        let comment_markers = if path.ends_with(".vhd") || path.ends_with(".vhdl") {
+            lints::VHDL_COMMENTS
+        } else {
+            lints::SVLOG_COMMENTS
+        };
+        let content = id.get_content();
+        let pragmas = Rc::new(LintPragmas::parse(
+            &String::from_utf8_lossy(content.bytes()),
+            comment_markers,
+        ));
+        self.pragmas.borrow_mut().insert(id, pragmas.clone());
+        pragmas
+    }
+
+    /// Record an expansion of a macro invoked at a given location.
+    pub fn add_expansion(&self, name: &str, call_site: Span) -> Expansion {
+        let mut expansions = self.expansions.borrow_mut();
+        expansions.push(ExpansionInfo {
+            name: RcStr::new(name),
+            call_site,
+        });
+        Expansion(NonZeroU32::new(expansions.len() as u32).unwrap())
+    }
+
+    /// Obtain the information recorded for a macro expansion.
+    pub fn expansion_info(&self, id: Expansion) -> ExpansionInfo {
+        self.expansions.borrow()[id.0.get() as usize - 1].clone()
+    }
+
     pub fn find<Q: ?Sized>(&self, filename: &Q) -> Option<Source>
     where
         RcStr: Borrow<Q>,
@@ -216,6 +276,27 @@ impl SourceManager {
         new_id
     }
 
+    /// Set the contents of a virtual file, adding it to the source manager if
+    /// it does not exist yet.
+    ///
+    /// This allows in-memory buffers, such as the unsaved contents of a file
+    /// in an editor or code generated on the fly, to be processed like files on
+    /// disk. If a file with the given name has been opened or added before, the
+    /// name is rebound to a new `Source` with the given contents, shadowing any
+    /// file on disk. Spans into the previous contents remain valid.
+    pub fn update(&self, filename: &str, content: &str) -> Source {
+        let mut vect = self.vect.borrow_mut();
+        let new_id = Source(vect.len() as u32 + 1);
+        let v = RcStr::new(filename);
+        self.map.borrow_mut().insert(v.clone(), new_id);
+        vect.push(Box::new(VirtualSourceFile {
+            id: new_id,
+            filename: v,
+            content: Rc::new(VirtualSourceContent(content.to_string())),
+        }));
+        new_id
+    }
+
     /// Create a virtual file from the contents of a string and add it to the
     /// source manager. The file can only be used with the returned `Source`,
     /// since there is no name associated with it by which it could be referred
@@ -265,6 +346,10 @@ impl SourceFile for VirtualSourceFile {
     fn get_content(&self) -> Rc<dyn SourceContent> {
         self.content.clone()
     }
+
+    fn is_virtual(&self) -> bool {
+        true
+    }
 }
 
 impl SourceContent for VirtualSourceContent {
@@ -296,8 +381,26 @@ struct DiskSourceFile {
     content: RefCell<Option<Rc<DiskSourceContent>>>,
 }
 
+/// The content of a source file on disk.
+///
+/// Files are memory-mapped, such that they are paged in as the lexer advances
+/// rather than read into a string up front. Files that cannot be mapped, such
+/// as empty files or pipes, are read into memory instead.
 #[derive(Debug)]
-struct DiskSourceContent(pub Mmap);
+enum DiskSourceContent {
+    Mapped(Mmap),
+    Read(Vec<u8>),
+}
+
+impl DiskSourceContent {
+    fn load(path: &Path) -> std::io::Result<DiskSourceContent> {
+        use memmap::Protection;
+        match Mmap::open_path(path, Protection::Read) {
+            Ok(map) => Ok(DiskSourceContent::Mapped(map)),
+            Err(_) => std::fs::read(path).map(DiskSourceContent::Read),
+        }
+    }
+}
 
 impl SourceFile for DiskSourceFile {
     fn get_id(&self) -> Source {
@@ -309,12 +412,9 @@ impl SourceFile for DiskSourceFile {
     }
 
     fn get_content(&self) -> Rc<dyn SourceContent> {
-        use memmap::Protection;
         let is_none = self.content.borrow().is_none();
         if is_none {
-            let c = Rc::new(DiskSourceContent(
-                Mmap::open_path(Path::new(&*self.filename), Protection::Read).unwrap(),
-            ));
+            let c = Rc::new(DiskSourceContent::load(Path::new(&*self.filename)).unwrap());
             *self.content.borrow_mut() = Some(c.clone());
             c
         } else {
@@ -325,41 +425,135 @@ impl SourceFile for DiskSourceFile {
 
 impl SourceContent for DiskSourceContent {
     fn iter(&self) -> Box<CharIter> {
-        use std::str;
-        Box::new(
-            str::from_utf8(unsafe { self.0.as_slice() })
-                .unwrap()
-                .char_indices(),
-        )
+        Box::new(ChunkedChars::new(self.bytes()))
     }
 
     fn iter_from(&self, offset: usize) -> Box<CharIter> {
-        use std::str;
-        Box::new(
-            str::from_utf8(unsafe { &self.0.as_slice()[offset..] })
-                .unwrap()
-                .char_indices(),
-        )
+        Box::new(ChunkedChars::new(&self.bytes()[offset..]))
     }
 
     fn extract(&self, begin: usize, end: usize) -> String {
-        use std::str;
-        str::from_utf8(unsafe { &self.0.as_slice()[begin..end] })
-            .unwrap()
-            .to_string()
+        String::from_utf8_lossy(&self.bytes()[begin..end]).into_owned()
     }
 
     fn extract_iter(&self, begin: usize, end: usize) -> Box<CharIter> {
-        use std::str;
-        Box::new(
-            str::from_utf8(unsafe { &self.0.as_slice()[begin..end] })
-                .unwrap()
-                .char_indices(),
-        )
+        Box::new(ChunkedChars::new(&self.bytes()[begin..end]))
     }
 
     fn bytes(&self) -> &[u8] {
-        unsafe { self.0.as_slice() }
+        match self {
+            DiskSourceContent::Mapped(map) => unsafe { map.as_slice() },
+            DiskSourceContent::Read(bytes) => bytes,
+        }
+    }
+}
+
+/// The number of bytes decoded at once by `ChunkedChars`.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// An iterator over the characters in a byte slice, decoded as UTF-8 one chunk
+/// at a time.
+///
+/// Unlike `str::char_indices`, this does not require the entire slice to be
+/// validated before the first character is produced. Invalid byte sequences
+/// are yielded as the replacement character `U+FFFD`, at the offset of the
+/// first offending byte.
+struct ChunkedChars<'a> {
+    bytes: &'a [u8],
+    /// The characters in the chunk decoded last.
+    chunk: std::str::CharIndices<'a>,
+    /// The offset of `chunk` in `bytes`.
+    chunk_start: usize,
+    /// The offset of the first byte that has not been decoded yet.
+    front: usize,
+    /// The offset after the last byte that has not been decoded yet.
+    back: usize,
+}
+
+impl<'a> ChunkedChars<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        ChunkedChars {
+            bytes,
+            chunk: "".char_indices(),
+            chunk_start: 0,
+            front: 0,
+            back: bytes.len(),
+        }
+    }
+
+    /// Decode the next chunk of bytes.
+    ///
+    /// Returns the replacement character if the chunk starts with an invalid
+    /// byte sequence.
+    fn refill(&mut self) -> Option<(usize, char)> {
+        let end = self.back.min(self.front + CHUNK_SIZE);
+        let bytes = &self.bytes[self.front..end];
+        let valid = match std::str::from_utf8(bytes) {
+            Ok(valid) => valid,
+            Err(e) if e.valid_up_to() > 0 => {
+                std::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap()
+            }
+            Err(e) => {
+                // A sequence can only be cut short by the end of the slice,
+                // since chunks are longer than any character.
+                let offset = self.front;
+                self.front += e.error_len().unwrap_or(bytes.len());
+                return Some((offset, std::char::REPLACEMENT_CHARACTER));
+            }
+        };
+        self.chunk = valid.char_indices();
+        self.chunk_start = self.front;
+        self.front += valid.len();
+        None
+    }
+}
+
+impl Iterator for ChunkedChars<'_> {
+    type Item = (usize, char);
+
+    fn next(&mut self) -> Option<(usize, char)> {
+        loop {
+            if let Some((i, c)) = self.chunk.next() {
+                return Some((self.chunk_start + i, c));
+            }
+            if self.front >= self.back {
+                return None;
+            }
+            if let Some(replaced) = self.refill() {
+                return Some(replaced);
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.chunk.as_str().len() + self.back - self.front;
+        ((remaining + 3) / 4, Some(remaining))
+    }
+}
+
+impl DoubleEndedIterator for ChunkedChars<'_> {
+    fn next_back(&mut self) -> Option<(usize, char)> {
+        if self.front >= self.back {
+            let chunk_start = self.chunk_start;
+            return self.chunk.next_back().map(|(i, c)| (chunk_start + i, c));
+        }
+
+        // Find the start of the last character, which is preceded by at most
+        // three continuation bytes.
+        let mut start = self.back - 1;
+        while start > self.front && self.back - start < 4 && self.bytes[start] & 0xC0 == 0x80 {
+            start -= 1;
+        }
+        match std::str::from_utf8(&self.bytes[start..self.back]) {
+            Ok(valid) => {
+                self.back = start;
+                valid.chars().next_back().map(|c| (start, c))
+            }
+            Err(_) => {
+                self.back -= 1;
+                Some((self.back, std::char::REPLACEMENT_CHARACTER))
+            }
+        }
     }
 }
 
@@ -406,6 +600,7 @@ impl Location {
                     break;
                 }
                 '\r' => continue,
+                BYTE_ORDER_MARK => line_offset = c.0,
                 _ => {
                     col += 1;
                     line_offset = c.0;
@@ -423,6 +618,20 @@ impl Location {
         (line, col, line_offset)
     }
 
+    /// Determine the column at this location as displayed by an editor.
+    ///
+    /// Tabs advance the column to the next multiple of `tab_width`. Other
+    /// characters count as one column, regardless of their encoded length.
+    pub fn display_column(self, tab_width: usize) -> usize {
+        let (_, _, line_offset) = self.human();
+        let content = self.source.get_content();
+        let mut col = 0;
+        for (_, c) in content.extract_iter(line_offset, self.offset) {
+            col += display_width(c, col, tab_width);
+        }
+        col + 1
+    }
+
     /// Determine the line at this location.
     pub fn human_line(self) -> usize {
         self.human().0
@@ -451,6 +660,39 @@ impl From<Location> for Span {
     }
 }
 
+/// The byte order mark that may appear at the beginning of a file.
+///
+/// The mark is treated as whitespace by the lexers and does not count towards
+/// column numbers.
+pub const BYTE_ORDER_MARK: char = '\u{FEFF}';
+
+/// Determine the number of columns a character occupies when displayed at a
+/// column `col`, counting from zero.
+pub fn display_width(c: char, col: usize, tab_width: usize) -> usize {
+    let tab_width = tab_width.max(1);
+    match c {
+        '\t' => tab_width - col % tab_width,
+        BYTE_ORDER_MARK => 0,
+        _ => 1,
+    }
+}
+
+/// Shorten a path for display to the user.
+///
+/// Paths within the current working directory are made relative to it, such
+/// that the output does not depend on where the tool is run from. Other paths
+/// are returned unchanged.
+pub fn display_path(path: &str) -> String {
+    let cwd = match std::env::current_dir() {
+        Ok(x) => x,
+        Err(_) => return path.to_string(),
+    };
+    match Path::new(path).strip_prefix(&cwd) {
+        Ok(rel) => rel.to_string_lossy().into_owned(),
+        Err(_) => path.to_string(),
+    }
+}
+
 /// A span of locations within a source file, expressed as a half-open interval
 /// of bytes `[begin,end)`.
 #[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash)]
@@ -458,6 +700,9 @@ pub struct Span {
     pub source: Source,
     pub begin: usize,
     pub end: usize,
+    /// The macro expansion that produced the text in this span, if any. The
+    /// span then points into the body of the macro definition.
+    pub expansion: Option<Expansion>,
 }
 
 impl Span {
@@ -467,9 +712,33 @@ impl Span {
             source: source,
             begin: begin,
             end: end,
+            expansion: None,
         }
     }
 
+    /// Mark this span as having been produced by a macro expansion.
+    pub fn with_expansion(self, expansion: Expansion) -> Span {
+        Span {
+            expansion: Some(expansion),
+            ..self
+        }
+    }
+
+    /// Return the chain of macro expansions that produced this span.
+    ///
+    /// The innermost expansion comes first, followed by the expansion in which
+    /// its macro was invoked, and so on.
+    pub fn expansion_chain(&self) -> Vec<ExpansionInfo> {
+        let mut chain = vec![];
+        let mut next = self.expansion;
+        while let Some(id) = next {
+            let info = id.info();
+            next = info.call_site.expansion;
+            chain.push(info);
+        }
+        chain
+    }
+
     /// Create a new span that covers two spans, i.e. represents the smallest
     /// possible span that fully contains both input spans `a` and `b`.
     pub fn union<S: Into<Span>>(a: S, b: S) -> Span {
@@ -484,6 +753,7 @@ impl Span {
             source: sa.source,
             begin: min(sa.begin, sb.begin),
             end: max(sa.end, sb.end),
+            expansion: sa.expansion,
         }
     }
 
@@ -520,6 +790,27 @@ impl Span {
     pub fn iter<'a>(self, content: &'a Rc<dyn SourceContent>) -> Box<CharIter<'a>> {
         content.extract_iter(self.begin, self.end)
     }
+
+    /// Resolve this span into its position in the source file and the lines of
+    /// text it touches.
+    pub fn snippet(&self) -> Snippet {
+        let (line, column, line_offset) = self.begin().human();
+        let (end_line, end_column, _) = self.end().human();
+        let mut lines = String::new();
+        for (offset, c) in self.source.get_content().iter_from(line_offset) {
+            if line_offset + offset >= self.end && (c == '\n' || c == '\r') {
+                break;
+            }
+            lines.push(c);
+        }
+        Snippet {
+            path: self.source.get_path(),
+            begin: (line, column),
+            end: (end_line, end_column),
+            range: self.begin - line_offset..self.end - line_offset,
+            lines,
+        }
+    }
 }
 
 impl fmt::Debug for Span {
@@ -528,6 +819,58 @@ impl fmt::Debug for Span {
     }
 }
 
+/// A span resolved into a position in its source file, together with the text
+/// around it.
+///
+/// Snippets carry everything needed to render a message about a span, such
+/// that tools embedding the compiler need not query the source manager.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snippet {
+    /// The path of the source file.
+    pub path: RcStr,
+    /// The line and column of the first character in the span, counted from 1.
+    pub begin: (usize, usize),
+    /// The line and column just after the last character in the span.
+    pub end: (usize, usize),
+    /// The lines the span touches, from the beginning of its first line up to
+    /// the end of its last line, without the final line break.
+    pub lines: String,
+    /// The byte range of the span within `lines`.
+    pub range: std::ops::Range<usize>,
+}
+
+impl Snippet {
+    /// The text within the span.
+    pub fn text(&self) -> &str {
+        &self.lines[self.range.clone()]
+    }
+
+    /// The first line the span touches.
+    pub fn line(&self) -> &str {
+        self.lines.split(['\n', '\r']).next().unwrap()
+    }
+}
+
+/// An opaque ID identifying a macro expansion.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Expansion(NonZeroU32);
+
+impl Expansion {
+    /// Obtain the macro name and call site of this expansion.
+    pub fn info(self) -> ExpansionInfo {
+        get_source_manager().expansion_info(self)
+    }
+}
+
+/// The provenance of text produced by a macro expansion.
+#[derive(Clone, Debug)]
+pub struct ExpansionInfo {
+    /// The name of the expanded macro.
+    pub name: RcStr,
+    /// The location where the macro was invoked.
+    pub call_site: Span,
+}
+
 /// A wrapper that associates a span with a value.
 #[derive(PartialOrd, Ord, PartialEq, Eq)]
 pub struct Spanned<T> {
@@ -629,12 +972,115 @@ mod tests {
         assert_eq!(source, id);
     }
 
+    #[test]
+    fn update_file() {
+        let sm = get_source_manager();
+        let first = sm.update("buffer.sv", "module foo;\nendmodule\n");
+        let span = Span::new(first, 7, 10);
+        assert_eq!(sm.open("buffer.sv"), Some(first));
+        assert!(first.is_virtual());
+        let second = sm.update("buffer.sv", "module bar;\nendmodule\n");
+        assert_ne!(first, second);
+        assert_eq!(sm.open("buffer.sv"), Some(second));
+        assert_eq!(span.extract(), "foo");
+        assert_eq!(Span::new(second, 7, 10).extract(), "bar");
+        assert_eq!(&second.get_path()[..], "buffer.sv");
+    }
+
+    #[test]
+    fn chunked_chars() {
+        let text = format!("{}老虎.\n", "a".repeat(CHUNK_SIZE - 2));
+        let chars = |bytes| ChunkedChars::new(bytes).collect::<Vec<_>>();
+        let chars_back = |bytes| {
+            let mut chars: Vec<_> = ChunkedChars::new(bytes).rev().collect();
+            chars.reverse();
+            chars
+        };
+        let expected: Vec<_> = text.char_indices().collect();
+        assert_eq!(chars(text.as_bytes()), expected);
+        assert_eq!(chars_back(text.as_bytes()), expected);
+        assert_eq!(
+            ChunkedChars::new(text.as_bytes()).size_hint().1,
+            Some(text.len())
+        );
+
+        let mut iter = ChunkedChars::new("ab老c".as_bytes());
+        assert_eq!(iter.next(), Some((0, 'a')));
+        assert_eq!(iter.next_back(), Some((5, 'c')));
+        assert_eq!(iter.next_back(), Some((2, '老')));
+        assert_eq!(iter.next_back(), Some((1, 'b')));
+        assert_eq!(iter.next(), None);
+
+        let invalid = b"caf\xe9 \xe8\x80";
+        let expected = vec![
+            (0, 'c'),
+            (1, 'a'),
+            (2, 'f'),
+            (3, '\u{FFFD}'),
+            (4, ' '),
+            (5, '\u{FFFD}'),
+        ];
+        assert_eq!(chars(invalid), expected);
+        assert_eq!(chars_back(&invalid[..5]), &expected[..5]);
+    }
+
+    #[test]
+    fn disk_files() {
+        let dir = std::env::temp_dir();
+        let empty = dir.join(format!("moore-empty-{}.sv", std::process::id()));
+        let latin1 = dir.join(format!("moore-latin1-{}.sv", std::process::id()));
+        std::fs::write(&empty, "").unwrap();
+        std::fs::write(&latin1, b"// caf\xe9\nmodule foo;\n").unwrap();
+        let sm = get_source_manager();
+        let empty_source = sm.open(empty.to_str().unwrap()).unwrap();
+        let latin1_source = sm.open(latin1.to_str().unwrap()).unwrap();
+        assert_eq!(empty_source.get_content().iter().next(), None);
+        assert_eq!(Span::new(latin1_source, 3, 8).extract(), "caf\u{FFFD}\n");
+        assert_eq!(Span::new(latin1_source, 15, 18).extract(), "foo");
+        assert_eq!(Location::new(latin1_source, 15).human(), (2, 8, 8));
+        std::fs::remove_file(empty).unwrap();
+        std::fs::remove_file(latin1).unwrap();
+    }
+
     #[test]
     fn inexistent_file() {
         let sm = get_source_manager();
         assert_eq!(sm.open("/this/path/points/nowhere"), None);
     }
 
+    #[test]
+    fn columns() {
+        let sm = get_source_manager();
+        let source = sm.add("columns.sv", "\u{FEFF}a\n\tb老c\n");
+        let at = |offset| Location::new(source, offset);
+        assert_eq!(at(3).human(), (1, 1, 0));
+        assert_eq!(at(4).human(), (1, 2, 0));
+        assert_eq!(at(6).human(), (2, 2, 5));
+        assert_eq!(at(6).display_column(4), 5);
+        assert_eq!(at(10).display_column(4), 7);
+        assert_eq!(at(10).display_column(1), 4);
+    }
+
+    #[test]
+    fn snippet() {
+        let source = get_source_manager().add(
+            "snippet.sv",
+            "module foo;\n  int x;\r\n  int y;\nendmodule\n",
+        );
+        let snippet = Span::new(source, 14, 19).snippet();
+        assert_eq!(&snippet.path[..], "snippet.sv");
+        assert_eq!(snippet.begin, (2, 3));
+        assert_eq!(snippet.end, (2, 8));
+        assert_eq!(snippet.lines, "  int x;");
+        assert_eq!(snippet.text(), "int x");
+        let snippet = Span::new(source, 18, 29).snippet();
+        assert_eq!(snippet.begin, (2, 7));
+        assert_eq!(snippet.end, (3, 8));
+        assert_eq!(snippet.lines, "  int x;\r\n  int y;");
+        assert_eq!(snippet.text(), "x;\r\n  int y");
+        assert_eq!(snippet.line(), "  int x;");
+    }
+
     #[test]
     fn chars() {
         let sm = get_source_manager();
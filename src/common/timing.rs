@@ -0,0 +1,525 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Measuring where compile time goes.
+//!
+//! The compiler records how long its passes and queries take if
+//! `--time-passes` is given. Passes form a tree, since for example the code
+//! generation of a module includes the code generation of its instances.
+//! Passes with the same name and parent are merged into one entry. Queries are
+//! recorded with their self time, i.e. excluding the time spent in the queries
+//! they call, such that the hottest queries stand out. Alongside the times,
+//! the compiler records how much memory large data structures such as the AST
+//! occupy. The totals are part of the time report, and `--memory-report`
+//! breaks them down by the kind of node or value.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// The time spent in the passes and queries of a session.
+pub struct Timings {
+    /// When the timings were created.
+    start: Instant,
+    /// The passes measured so far, in the order they were started.
+    passes: RefCell<Vec<PassTime>>,
+    /// The index of the currently running pass.
+    current: Cell<Option<usize>>,
+    /// The time spent in each query.
+    queries: RefCell<HashMap<&'static str, QueryTime>>,
+    /// The start time and the time spent in nested queries of the currently
+    /// executing queries.
+    query_stack: RefCell<Vec<(Instant, Duration)>>,
+    /// The memory occupied by each kind of measured data structure.
+    memory: RefCell<Vec<MemoryUse>>,
+}
+
+/// The time spent in a pass.
+#[derive(Clone, Debug)]
+pub struct PassTime {
+    /// The name of the pass.
+    pub name: String,
+    /// The index of the enclosing pass.
+    pub parent: Option<usize>,
+    /// The nesting depth of the pass.
+    pub depth: usize,
+    /// The number of times the pass ran.
+    pub count: usize,
+    /// The total time spent in the pass.
+    pub duration: Duration,
+}
+
+/// The time spent in a query.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct QueryTime {
+    /// The number of times the query was executed.
+    pub executed: usize,
+    /// The number of times the query was served from the cache.
+    pub cached: usize,
+    /// The time spent in the query, excluding nested queries.
+    pub self_time: Duration,
+    /// The time spent in the query, including nested queries.
+    pub total_time: Duration,
+}
+
+/// The memory occupied by one kind of value in a data structure.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryUse {
+    /// What the data structure holds, e.g. `ast`.
+    pub category: String,
+    /// The kind of value, e.g. `Expr`.
+    pub kind: String,
+    /// The number of values.
+    pub count: usize,
+    /// The number of bytes occupied by the values.
+    pub bytes: usize,
+}
+
+impl Default for Timings {
+    fn default() -> Timings {
+        Timings {
+            start: Instant::now(),
+            passes: Default::default(),
+            current: Default::default(),
+            queries: Default::default(),
+            query_stack: Default::default(),
+            memory: Default::default(),
+        }
+    }
+}
+
+impl Timings {
+    /// Start measuring a pass.
+    ///
+    /// The pass ends when the returned timer is dropped. Passes started in the
+    /// meantime are nested within this pass.
+    pub fn start(&self, name: impl Into<String>) -> PassTimer<'_> {
+        let parent = self.current.get();
+        let index = self.pass_index(name.into(), parent);
+        self.current.set(Some(index));
+        PassTimer {
+            timings: Some(self),
+            index,
+            parent,
+            start: Instant::now(),
+        }
+    }
+
+    /// Add time measured elsewhere to a pass nested in the current one.
+    pub fn add(&self, name: impl Into<String>, duration: Duration) {
+        let index = self.pass_index(name.into(), self.current.get());
+        self.passes.borrow_mut()[index].duration += duration;
+    }
+
+    /// Record the number of values of a kind in a data structure, and the
+    /// bytes they occupy.
+    ///
+    /// Measurements with the same category and kind are added up.
+    pub fn add_memory(
+        &self,
+        category: impl Into<String>,
+        kind: impl Into<String>,
+        count: usize,
+        bytes: usize,
+    ) {
+        let (category, kind) = (category.into(), kind.into());
+        let mut memory = self.memory.borrow_mut();
+        match memory
+            .iter_mut()
+            .find(|m| m.category == category && m.kind == kind)
+        {
+            Some(entry) => {
+                entry.count += count;
+                entry.bytes += bytes;
+            }
+            None => memory.push(MemoryUse {
+                category,
+                kind,
+                count,
+                bytes,
+            }),
+        }
+    }
+
+    /// Find or create the pass with a given name and parent, and count one
+    /// more run of it.
+    fn pass_index(&self, name: String, parent: Option<usize>) -> usize {
+        let mut passes = self.passes.borrow_mut();
+        let index = match passes
+            .iter()
+            .position(|p| p.parent == parent && p.name == name)
+        {
+            Some(index) => index,
+            None => {
+                let depth = parent.map(|p| passes[p].depth + 1).unwrap_or(0);
+                passes.push(PassTime {
+                    name,
+                    parent,
+                    depth,
+                    count: 0,
+                    duration: Duration::default(),
+                });
+                passes.len() - 1
+            }
+        };
+        passes[index].count += 1;
+        index
+    }
+
+    /// Note that a query is about to be looked up.
+    pub fn enter_query(&self) {
+        self.query_stack
+            .borrow_mut()
+            .push((Instant::now(), Duration::default()));
+    }
+
+    /// Note that a query entered last was served from the cache.
+    pub fn cached_query(&self, name: &'static str) {
+        self.query_stack.borrow_mut().pop();
+        self.queries.borrow_mut().entry(name).or_default().cached += 1;
+    }
+
+    /// Note that a query entered last has finished executing.
+    pub fn leave_query(&self, name: &'static str) {
+        let mut stack = self.query_stack.borrow_mut();
+        let (start, nested) = match stack.pop() {
+            Some(x) => x,
+            None => return,
+        };
+        let elapsed = start.elapsed();
+        if let Some(parent) = stack.last_mut() {
+            parent.1 += elapsed;
+        }
+        let mut queries = self.queries.borrow_mut();
+        let entry = queries.entry(name).or_default();
+        entry.executed += 1;
+        entry.self_time += elapsed.checked_sub(nested).unwrap_or_default();
+        entry.total_time += elapsed;
+    }
+
+    /// The passes measured so far, in the order they were first started.
+    pub fn passes(&self) -> Vec<PassTime> {
+        self.passes.borrow().clone()
+    }
+
+    /// The queries measured so far, starting with the highest self time.
+    pub fn queries(&self) -> Vec<(&'static str, QueryTime)> {
+        let mut queries: Vec<_> = self
+            .queries
+            .borrow()
+            .iter()
+            .map(|(&n, &t)| (n, t))
+            .collect();
+        queries.sort_by(|a, b| b.1.self_time.cmp(&a.1.self_time).then(a.0.cmp(b.0)));
+        queries
+    }
+
+    /// The memory measured so far, in the order it was first recorded.
+    pub fn memory(&self) -> Vec<MemoryUse> {
+        self.memory.borrow().clone()
+    }
+
+    /// The memory measured so far, summed up per category, in the order in
+    /// which the categories were first recorded. Categories without any
+    /// values are left out.
+    pub fn memory_totals(&self) -> Vec<MemoryUse> {
+        let mut totals: Vec<MemoryUse> = vec![];
+        for entry in self.memory.borrow().iter() {
+            match totals.iter_mut().find(|t| t.category == entry.category) {
+                Some(total) => {
+                    total.count += entry.count;
+                    total.bytes += entry.bytes;
+                }
+                None => totals.push(MemoryUse {
+                    kind: "total".to_string(),
+                    ..entry.clone()
+                }),
+            }
+        }
+        totals.retain(|t| t.count > 0);
+        totals
+    }
+
+    /// The time elapsed since the timings were created.
+    pub fn total(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Render a human-readable report of the passes and the `max_queries`
+    /// hottest queries.
+    pub fn report(&self, max_queries: usize) -> String {
+        let mut report = String::new();
+        let seconds = |d: Duration| format!("{:9.3}s", d.as_secs_f64());
+        for pass in self.passes() {
+            report.push_str(&format!(
+                "time: {}  {:indent$}{}",
+                seconds(pass.duration),
+                "",
+                pass.name,
+                indent = pass.depth * 2
+            ));
+            if pass.count > 1 {
+                report.push_str(&format!(" ({}x)", pass.count));
+            }
+            report.push('\n');
+        }
+        report.push_str(&format!("time: {}  total\n", seconds(self.total())));
+        for total in self.memory_totals() {
+            report.push_str(&format!(
+                "memory: {:>10}  {}\n",
+                format_bytes(total.bytes),
+                total.category
+            ));
+        }
+
+        let queries = self.queries();
+        if !queries.is_empty() {
+            report.push_str(&format!(
+                "\nhottest queries:\n{:>10}  {:>10}  {:>8}  {:>8}  query\n",
+                "self", "total", "executed", "cached"
+            ));
+            for (name, time) in queries.into_iter().take(max_queries) {
+                report.push_str(&format!(
+                    "{}  {}  {:8}  {:8}  {}\n",
+                    seconds(time.self_time),
+                    seconds(time.total_time),
+                    time.executed,
+                    time.cached,
+                    name
+                ));
+            }
+        }
+        report
+    }
+
+    /// Render a human-readable report of the memory measured so far.
+    ///
+    /// Each category is listed with its total, followed by its kinds of
+    /// values, starting with the ones that occupy the most memory.
+    pub fn memory_report(&self) -> String {
+        let mut report = format!("{:<24}  {:>10}  {:>12}\n", "memory", "count", "bytes");
+        let memory = self.memory();
+        for total in self.memory_totals() {
+            report.push_str(&format!(
+                "{:<24}  {:>10}  {:>12}\n",
+                total.category,
+                total.count,
+                format_bytes(total.bytes)
+            ));
+            let mut kinds: Vec<_> = memory
+                .iter()
+                .filter(|m| m.category == total.category && m.count > 0)
+                .collect();
+            kinds.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.kind.cmp(&b.kind)));
+            for kind in kinds {
+                report.push_str(&format!(
+                    "  {:<22}  {:>10}  {:>12}\n",
+                    kind.kind,
+                    kind.count,
+                    format_bytes(kind.bytes)
+                ));
+            }
+        }
+        report
+    }
+
+    /// Render the memory measured so far as JSON.
+    pub fn memory_to_json(&self) -> serde_json::Value {
+        serde_json::Value::Array(
+            self.memory()
+                .iter()
+                .map(|m| {
+                    serde_json::json!({
+                        "category": m.category,
+                        "kind": m.kind,
+                        "count": m.count,
+                        "bytes": m.bytes,
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    /// Render the passes, queries, and memory as JSON.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "passes": self
+                .passes()
+                .iter()
+                .map(|p| serde_json::json!({
+                    "name": p.name,
+                    "depth": p.depth,
+                    "count": p.count,
+                    "seconds": p.duration.as_secs_f64(),
+                }))
+                .collect::<Vec<_>>(),
+            "queries": self
+                .queries()
+                .iter()
+                .map(|(name, t)| serde_json::json!({
+                    "name": name,
+                    "executed": t.executed,
+                    "cached": t.cached,
+                    "self_seconds": t.self_time.as_secs_f64(),
+                    "total_seconds": t.total_time.as_secs_f64(),
+                }))
+                .collect::<Vec<_>>(),
+            "memory": self.memory_to_json(),
+            "total_seconds": self.total().as_secs_f64(),
+        })
+    }
+}
+
+/// Format a number of bytes with a binary unit.
+fn format_bytes(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+/// A running measurement of a pass, which ends when the timer is dropped.
+#[must_use]
+pub struct PassTimer<'a> {
+    timings: Option<&'a Timings>,
+    index: usize,
+    parent: Option<usize>,
+    start: Instant,
+}
+
+impl PassTimer<'_> {
+    /// A timer that does not measure anything.
+    pub fn disabled() -> PassTimer<'static> {
+        PassTimer {
+            timings: None,
+            index: 0,
+            parent: None,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for PassTimer<'_> {
+    fn drop(&mut self) {
+        if let Some(timings) = self.timings {
+            timings.passes.borrow_mut()[self.index].duration += self.start.elapsed();
+            timings.current.set(self.parent);
+        }
+    }
+}
+
+/// An accumulator for the time spent in a pass that is interleaved with other
+/// work, such as lexing which is driven token by token by the parser.
+#[derive(Clone, Default)]
+pub struct Stopwatch(Rc<Cell<(Duration, usize)>>);
+
+impl Stopwatch {
+    /// Run a function and add the time it takes to the stopwatch.
+    pub fn measure<R>(&self, f: impl FnOnce() -> R) -> R {
+        let start = Instant::now();
+        let result = f();
+        let (elapsed, count) = self.0.get();
+        self.0.set((elapsed + start.elapsed(), count + 1));
+        result
+    }
+
+    /// The time accumulated so far.
+    pub fn elapsed(&self) -> Duration {
+        self.0.get().0
+    }
+
+    /// The number of measurements taken so far, e.g. the number of tokens
+    /// lexed.
+    pub fn count(&self) -> usize {
+        self.0.get().1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_passes() {
+        let timings = Timings::default();
+        {
+            let _outer = timings.start("codegen");
+            for _ in 0..2 {
+                let _inner = timings.start("module `foo`");
+            }
+            timings.add("lex", Duration::from_millis(3));
+        }
+        let _other = timings.start("optimize");
+        let passes: Vec<_> = timings
+            .passes()
+            .into_iter()
+            .map(|p| (p.name, p.depth, p.count))
+            .collect();
+        assert_eq!(
+            passes,
+            vec![
+                ("codegen".to_string(), 0, 1),
+                ("module `foo`".to_string(), 1, 2),
+                ("lex".to_string(), 1, 1),
+                ("optimize".to_string(), 0, 1),
+            ]
+        );
+        assert_eq!(timings.passes()[2].duration, Duration::from_millis(3));
+    }
+
+    #[test]
+    fn query_self_time() {
+        let timings = Timings::default();
+        timings.enter_query();
+        timings.enter_query();
+        timings.cached_query("type_of");
+        timings.enter_query();
+        timings.leave_query("type_of");
+        timings.leave_query("hir_of");
+        let queries = timings.queries();
+        let hir_of = queries.iter().find(|q| q.0 == "hir_of").unwrap().1;
+        let type_of = queries.iter().find(|q| q.0 == "type_of").unwrap().1;
+        assert_eq!((hir_of.executed, hir_of.cached), (1, 0));
+        assert_eq!((type_of.executed, type_of.cached), (1, 1));
+        assert!(hir_of.total_time >= type_of.total_time);
+        assert!(hir_of.self_time <= hir_of.total_time);
+    }
+
+    #[test]
+    fn memory() {
+        let timings = Timings::default();
+        timings.add_memory("ast", "Expr", 10, 100);
+        timings.add_memory("hir", "Expr", 2, 20);
+        timings.add_memory("ast", "Stmt", 3, 60);
+        timings.add_memory("ast", "Expr", 5, 50);
+        let totals: Vec<_> = timings
+            .memory_totals()
+            .into_iter()
+            .map(|m| (m.category, m.count, m.bytes))
+            .collect();
+        assert_eq!(
+            totals,
+            vec![("ast".to_string(), 18, 210), ("hir".to_string(), 2, 20)]
+        );
+        let report = timings.memory_report();
+        let lines: Vec<_> = report
+            .lines()
+            .map(|l| l.split_whitespace().next())
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                Some("memory"),
+                Some("ast"),
+                Some("Expr"),
+                Some("Stmt"),
+                Some("hir"),
+                Some("Expr")
+            ]
+        );
+        assert!(report.contains("  Expr                            15         150 B\n"));
+    }
+}
@@ -0,0 +1,219 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Reading project configuration files.
+//!
+//! A project may describe how its design is compiled in a `moore.toml` file,
+//! which is found by searching the working directory and its parents:
+//!
+//! ```toml
+//! include-dirs = ["rtl/include"]
+//! defines = ["SYNTHESIS", "WIDTH=8"]
+//! file-lists = ["rtl/files.f"]
+//! files = ["rtl/top.sv"]
+//! library-dirs = ["lib"]
+//! library-files = ["lib/cells.v"]
+//! top = ["top"]
+//!
+//! [lints]
+//! trailing-comma = "deny"
+//!
+//! [output]
+//! emit = ["llhd"]
+//! opt-level = 2
+//! ```
+//!
+//! Every key is optional. Relative paths are relative to the directory that
+//! contains the configuration file. The options are merged with the ones given
+//! on the command line: lists are extended, and the other options only apply
+//! if they are not given on the command line.
+
+use crate::common::lints::LintLevels;
+use std::path::{Path, PathBuf};
+
+/// The name of project configuration files.
+pub const FILE_NAME: &str = "moore.toml";
+
+/// The options read from a project configuration file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// The include search paths.
+    pub include_dirs: Vec<String>,
+    /// The macro definitions, as `<name>` or `<name>=<value>`.
+    pub defines: Vec<String>,
+    /// The file lists to read input files and options from.
+    pub file_lists: Vec<String>,
+    /// The input files.
+    pub files: Vec<String>,
+    /// The library directories searched for missing modules.
+    pub library_dirs: Vec<String>,
+    /// The library files searched for missing modules.
+    pub library_files: Vec<String>,
+    /// The modules to elaborate.
+    pub tops: Vec<String>,
+    /// The levels of the lints, by name.
+    pub lints: Vec<(String, String)>,
+    /// The outputs to produce.
+    pub emit: Vec<String>,
+    /// The optimization level.
+    pub opt_level: Option<u32>,
+}
+
+impl Config {
+    /// Find the configuration file in a directory or its parents.
+    pub fn find(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .map(|dir| dir.join(FILE_NAME))
+            .find(|path| path.is_file())
+    }
+
+    /// Read a configuration file.
+    pub fn open(path: &Path) -> Result<Config, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        Config::parse(&text, dir)
+    }
+
+    /// Parse the contents of a configuration file, resolving relative paths
+    /// against a directory.
+    pub fn parse(text: &str, dir: &Path) -> Result<Config, String> {
+        let value: toml::Value = text.parse().map_err(|e: toml::de::Error| e.to_string())?;
+        let table = value.as_table().unwrap();
+        let path = |p: String| dir.join(p).to_string_lossy().into_owned();
+        let mut config = Config::default();
+        for (key, value) in table {
+            match key.as_str() {
+                "include-dirs" => config.include_dirs = strings(key, value)?.map(path).collect(),
+                "defines" => config.defines = strings(key, value)?.collect(),
+                "file-lists" => config.file_lists = strings(key, value)?.map(path).collect(),
+                "files" => config.files = strings(key, value)?.map(path).collect(),
+                "library-dirs" => config.library_dirs = strings(key, value)?.map(path).collect(),
+                "library-files" => config.library_files = strings(key, value)?.map(path).collect(),
+                "top" => config.tops = strings(key, value)?.collect(),
+                "lints" => {
+                    for (name, level) in section(key, value)? {
+                        let level = level
+                            .as_str()
+                            .ok_or_else(|| format!("`lints.{}` must be a string", name))?;
+                        config.lints.push((name.clone(), level.to_string()));
+                    }
+                }
+                "output" => {
+                    for (name, value) in section(key, value)? {
+                        match name.as_str() {
+                            "emit" => config.emit = strings("output.emit", value)?.collect(),
+                            "opt-level" => {
+                                let level = value
+                                    .as_integer()
+                                    .filter(|&level| level >= 0)
+                                    .ok_or("`output.opt-level` must be a positive integer")?;
+                                config.opt_level = Some(level as u32);
+                            }
+                            _ => return Err(format!("unknown key `output.{}`", name)),
+                        }
+                    }
+                }
+                _ => return Err(format!("unknown key `{}`", key)),
+            }
+        }
+        Ok(config)
+    }
+
+    /// Apply the lint levels to a configuration.
+    pub fn apply_lints(&self, levels: &mut LintLevels) -> Result<(), String> {
+        for (name, level) in &self.lints {
+            levels
+                .apply_setting(name, level)
+                .map_err(|e| format!("`lints.{}`: {}", name, e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Interpret a value as a list of strings.
+fn strings(key: &str, value: &toml::Value) -> Result<impl Iterator<Item = String>, String> {
+    let error = || format!("`{}` must be a list of strings", key);
+    let values = value
+        .as_array()
+        .ok_or_else(error)?
+        .iter()
+        .map(|v| v.as_str().map(String::from).ok_or_else(error))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(values.into_iter())
+}
+
+/// Interpret a value as a table.
+fn section<'a>(key: &str, value: &'a toml::Value) -> Result<&'a toml::value::Table, String> {
+    value
+        .as_table()
+        .ok_or_else(|| format!("`{}` must be a table", key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::lints::{self, LintLevel};
+
+    #[test]
+    fn parse() {
+        let config = Config::parse(
+            "include-dirs = [\"inc\"]\n\
+             defines = [\"A\", \"B=1\"]\n\
+             files = [\"top.sv\"]\n\
+             top = [\"top\"]\n\
+             [lints]\n\
+             label = \"deny\"\n\
+             [output]\n\
+             emit = [\"hierarchy\"]\n\
+             opt-level = 2\n",
+            Path::new("proj"),
+        )
+        .unwrap();
+        assert_eq!(
+            config.include_dirs,
+            vec![Path::new("proj").join("inc").to_string_lossy()]
+        );
+        assert_eq!(config.defines, vec!["A", "B=1"]);
+        assert_eq!(
+            config.files,
+            vec![Path::new("proj").join("top.sv").to_string_lossy()]
+        );
+        assert_eq!(config.tops, vec!["top"]);
+        assert_eq!(config.emit, vec!["hierarchy"]);
+        assert_eq!(config.opt_level, Some(2));
+
+        let mut levels = LintLevels::default();
+        config.apply_lints(&mut levels).unwrap();
+        assert_eq!(levels.get(lints::LABEL), LintLevel::Deny);
+    }
+
+    #[test]
+    fn errors() {
+        let parse = |text| Config::parse(text, Path::new(""));
+        assert_eq!(parse("foo = 1"), Err("unknown key `foo`".to_string()));
+        assert_eq!(
+            parse("files = \"a.sv\""),
+            Err("`files` must be a list of strings".to_string())
+        );
+        assert_eq!(
+            parse("[output]\nopt-level = -1"),
+            Err("`output.opt-level` must be a positive integer".to_string())
+        );
+        assert!(parse("files = [").is_err());
+        let mut levels = LintLevels::default();
+        assert!(parse("[lints]\nlabel = \"loud\"")
+            .unwrap()
+            .apply_lints(&mut levels)
+            .is_err());
+    }
+
+    #[test]
+    fn find() {
+        let dir = std::env::temp_dir().join(format!("moore-config-test-{}", std::process::id()));
+        let nested = dir.join("a").join("b");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(Config::find(&nested).filter(|p| p.starts_with(&dir)), None);
+        std::fs::write(dir.join(FILE_NAME), "").unwrap();
+        assert_eq!(Config::find(&nested), Some(dir.join(FILE_NAME)));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -94,6 +94,7 @@ pub(crate) fn derive_arena(_args: TokenStream) -> TokenStream {
     // Generate the various bits and pieces.
     let mut fields = vec![];
     let mut allocs = vec![];
+    let mut stats = vec![];
 
     for (name, generics) in types {
         // Assemble the generics for the alloc implementation.
@@ -130,6 +131,24 @@ pub(crate) fn derive_arena(_args: TokenStream) -> TokenStream {
                     self.#field_name.alloc(value)
                 }
             }
+
+            impl #alloc_generics
+                moore_common::arenas::AllocSlice<#arena_lt, #arena_lt, #name #generics>
+                for #arena_name #arena_generics #whc
+            {
+                fn alloc_slice(
+                    &#arena_lt self,
+                    values: impl IntoIterator<Item = #name #generics>,
+                ) -> &#arena_lt [#name #generics] {
+                    self.#field_name.alloc_extend(values)
+                }
+            }
+        });
+        stats.push(quote! {
+            moore_common::arenas::AllocStats {
+                name: stringify!(#name),
+                ..moore_common::arenas::AllocStats::of(&self.#field_name)
+            }
         });
     }
 
@@ -141,6 +160,14 @@ pub(crate) fn derive_arena(_args: TokenStream) -> TokenStream {
             #(#fields,)*
         }
 
+        impl #arena_generics #arena_name #arena_generics {
+            /// The number and size of the values allocated so far, for each
+            /// type.
+            pub fn statistics(&self) -> Vec<moore_common::arenas::AllocStats> {
+                vec![#(#stats),*]
+            }
+        }
+
         #(#allocs)*
     };
     // println!("{}", output);
@@ -63,6 +63,25 @@ pub(crate) fn node_data(input: TokenStream) -> TokenStream {
     assert!(!arms.indefinite.is_empty());
     assert!(!arms.definite.is_empty());
 
+    // Generate the names of the variants of an enum.
+    let variant_fn = match &input.data {
+        syn::Data::Enum(inenum) => {
+            let arms_variant = inenum.variants.iter().map(|variant| {
+                let variant_name = &variant.ident;
+                let variant_str = variant_name.to_string();
+                quote! { #name::#variant_name { .. } => Some(#variant_str), }
+            });
+            quote! {
+                fn get_variant(&self) -> Option<&'static str> {
+                    match self {
+                        #(#arms_variant)*
+                    }
+                }
+            }
+        }
+        _ => quote! {},
+    };
+
     // Generate the implementation of the `AnyNodeData` trait.
     let MatchArms {
         name: arms_name,
@@ -77,6 +96,8 @@ pub(crate) fn node_data(input: TokenStream) -> TokenStream {
                 }
             }
 
+            #variant_fn
+
             fn fmt_indefinite(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
                 match self {
                     #(#arms_indefinite)*
@@ -54,6 +54,8 @@ pub(crate) fn derive_query_db(input: TokenStream) -> TokenStream {
     let mut caches = vec![];
     let mut tags = vec![];
     let mut tag_debugs = vec![];
+    let mut tag_names = vec![];
+    let mut tag_evictions = vec![];
     let mut keys = vec![];
 
     for raw_query in &queries {
@@ -181,6 +183,14 @@ pub(crate) fn derive_query_db(input: TokenStream) -> TokenStream {
         tag_debugs.push(quote! {
             QueryTag::#tag_name (x) => write!(f, "{}{:?}", stringify!(#name), x),
         });
+        tag_names.push(quote! {
+            QueryTag::#tag_name (..) => stringify!(#name),
+        });
+        tag_evictions.push(quote! {
+            QueryTag::#tag_name (x) => {
+                storage.#cache_name.borrow_mut().remove(x);
+            }
+        });
 
         // Render the query for the database trait.
         funcs.push(quote! {
@@ -191,9 +201,20 @@ pub(crate) fn derive_query_db(input: TokenStream) -> TokenStream {
                 let query_tag = QueryTag::#tag_name(query_key.clone());
                 self.before_query(&query_tag);
 
+                // Record that the calling query depends on this one.
+                if let Some(caller) = query_storage.stack.borrow().last() {
+                    query_storage
+                        .dependents
+                        .borrow_mut()
+                        .entry(query_tag.clone())
+                        .or_default()
+                        .insert(caller.clone());
+                }
+
                 // Check if we already have a result for this query.
                 if let Some(result) = query_storage.#cache_name.borrow().get(&query_key) {
                     trace!("Serving {}{:?} from cache", stringify!(#name), query_key);
+                    self.cached_query(&query_tag);
                     return Clone::clone(&result);
                 }
                 trace!("Executing {}{:?}", stringify!(#name), query_key);
@@ -265,6 +286,55 @@ pub(crate) fn derive_query_db(input: TokenStream) -> TokenStream {
             /// Called after a query is executed.
             fn after_query(&self, tag: &QueryTag #lts) {}
 
+            /// Called instead of `after_query` if a query is served from the
+            /// cache.
+            fn cached_query(&self, tag: &QueryTag #lts) {}
+
+            /// Record that the currently-executing query reads an input.
+            ///
+            /// Has no effect outside of a query.
+            fn read_input(&self, input: QueryInput) {
+                let storage = self.storage();
+                if let Some(reader) = storage.stack.borrow().last() {
+                    storage
+                        .readers
+                        .borrow_mut()
+                        .entry(input)
+                        .or_default()
+                        .insert(reader.clone());
+                }
+            }
+
+            /// Discard the cached results that depend on an input.
+            ///
+            /// Call this after an input has changed. This drops the results of
+            /// the queries that read the input, and transitively of all queries
+            /// that used those results, such that they are recomputed the next
+            /// time they are needed. All other results remain cached.
+            fn invalidate_input(&self, input: QueryInput) {
+                let storage = self.storage();
+                assert!(
+                    storage.stack.borrow().is_empty(),
+                    "inputs cannot change while a query is executing"
+                );
+                let mut worklist: Vec<_> = storage
+                    .readers
+                    .borrow_mut()
+                    .remove(&input)
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                while let Some(tag) = worklist.pop() {
+                    trace!("Invalidating {:?}", tag);
+                    match &tag {
+                        #(#tag_evictions)*
+                    }
+                    if let Some(dependents) = storage.dependents.borrow_mut().remove(&tag) {
+                        worklist.extend(dependents);
+                    }
+                }
+            }
+
             #(#funcs)*
         }
     });
@@ -278,6 +348,10 @@ pub(crate) fn derive_query_db(input: TokenStream) -> TokenStream {
             pub stack: RefCell<Vec<QueryTag #lts>>,
             /// A set of the currently-executing queries.
             pub inflight: RefCell<HashSet<QueryTag #lts>>,
+            /// For each query, the queries that used its result.
+            pub dependents: RefCell<HashMap<QueryTag #lts, HashSet<QueryTag #lts>>>,
+            /// For each input, the queries that read it.
+            pub readers: RefCell<HashMap<QueryInput, HashSet<QueryTag #lts>>>,
 
             #(#caches)*
         }
@@ -291,6 +365,15 @@ pub(crate) fn derive_query_db(input: TokenStream) -> TokenStream {
             #(#tags)*
         }
 
+        impl #lts QueryTag #lts {
+            /// The name of the query.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    #(#tag_names)*
+                }
+            }
+        }
+
         impl #lts std::fmt::Debug for QueryTag #lts {
             fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
                 match self {
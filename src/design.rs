@@ -0,0 +1,283 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! An interface for embedding the compiler in other tools.
+//!
+//! The entry point is [`parse_files`], which parses a set of SystemVerilog
+//! files and hands the resulting [`Design`] to a closure. The design can then
+//! be elaborated for a top-level module, and asked for the types and values of
+//! the nodes within it:
+//!
+//! ```no_run
+//! let mut diags = vec![];
+//! let result = moore::design::parse_files(
+//!     &["top.sv"],
+//!     &moore::design::Options::default(),
+//!     &mut diags,
+//!     |design| {
+//!         let elab = design.elaborate("top", &[("WIDTH", 16)])?;
+//!         Ok::<_, ()>(elab.hierarchy.children.len())
+//!     },
+//! );
+//! ```
+//!
+//! The design and everything obtained from it only lives for the duration of
+//! the closure, since it is allocated in arenas that are freed afterwards.
+//! Diagnostics are passed to a [`DiagnosticSink`] once the closure returns.
+
+use crate::common::{
+    errors::{DiagBuilder2, DiagEmitter},
+    lints::LintLevels,
+    name::Name,
+    source, NodeId,
+};
+use crate::svlog::{
+    self, ast, hierarchy::Instance, resolver::DefNode, ty::UnpackedType, value::Value, ParamEnv,
+    QueryDatabase,
+};
+use std::path::{Path, PathBuf};
+
+/// The options used to parse a design.
+#[derive(Debug, Default, Clone)]
+pub struct Options {
+    /// The include search paths.
+    pub include_dirs: Vec<PathBuf>,
+    /// The macro definitions, with an optional value.
+    pub defines: Vec<(String, Option<String>)>,
+    /// The levels at which warnings are reported.
+    pub lint_levels: LintLevels,
+}
+
+/// A receiver of the diagnostics produced while processing a design.
+pub trait DiagnosticSink {
+    /// Receive a diagnostic.
+    fn emit(&mut self, diag: DiagBuilder2);
+}
+
+impl DiagnosticSink for Vec<DiagBuilder2> {
+    fn emit(&mut self, diag: DiagBuilder2) {
+        self.push(diag);
+    }
+}
+
+/// A diagnostic sink that prints diagnostics to standard error.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Stderr;
+
+impl DiagnosticSink for Stderr {
+    fn emit(&mut self, diag: DiagBuilder2) {
+        eprintln!("{}", diag);
+    }
+}
+
+/// Parse a set of files and process the resulting design.
+///
+/// Returns an error if a file cannot be opened or parsed, in which case `f` is
+/// not called. In either case the diagnostics are passed to `sink`.
+pub fn parse_files<R>(
+    paths: &[impl AsRef<Path>],
+    options: &Options,
+    sink: &mut impl DiagnosticSink,
+    f: impl for<'gcx> FnOnce(&Design<'gcx>) -> R,
+) -> Result<R, ()> {
+    let mut sess = crate::common::Session::new();
+    sess.opts.lint_levels = options.lint_levels.clone();
+    let include_dirs: Vec<_> = options.include_dirs.iter().map(PathBuf::as_path).collect();
+    let defines: Vec<_> = options
+        .defines
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_ref().map(String::as_str)))
+        .collect();
+    let arenas = svlog::GlobalArenas::default();
+
+    let sm = source::get_source_manager();
+    let mut files = vec![];
+    for path in paths {
+        let path = path.as_ref().to_string_lossy();
+        let source = match sm.open(&path) {
+            Some(x) => x,
+            None => {
+                sess.emit(DiagBuilder2::error(format!(
+                    "unable to open input file `{}`",
+                    path
+                )));
+                continue;
+            }
+        };
+        let preproc = svlog::preproc::Preprocessor::new(source, &include_dirs, &defines);
+        let lexer = svlog::lexer::Lexer::new(preproc);
+        if let Ok(file) = svlog::parser::parse(lexer, &arenas.ast, &sess) {
+            files.push(file);
+        }
+    }
+
+    let result = if sess.failed() {
+        Err(())
+    } else {
+        let cx = svlog::GlobalContext::new(&sess, &arenas);
+        cx.add_files(files.iter());
+        let files = files.iter().collect();
+        Ok(f(&Design { cx, files }))
+    };
+    for diag in sess.take_diagnostics() {
+        sink.emit(diag);
+    }
+    result
+}
+
+/// A parsed design.
+pub struct Design<'gcx> {
+    cx: svlog::GlobalContext<'gcx>,
+    files: Vec<&'gcx ast::SourceFile<'gcx>>,
+}
+
+/// A top-level module elaborated for a specific parametrization.
+#[derive(Debug, Clone)]
+pub struct Elaboration {
+    /// The top-level module.
+    pub module: NodeId,
+    /// The parametrization of the top-level module.
+    pub env: ParamEnv,
+    /// The instance hierarchy below the top-level module.
+    pub hierarchy: Instance,
+}
+
+impl<'gcx> Design<'gcx> {
+    /// Get the compiler context, for queries not covered by this interface.
+    pub fn context(&self) -> &svlog::GlobalContext<'gcx> {
+        &self.cx
+    }
+
+    /// Get the parsed source files.
+    pub fn files(&self) -> &[&'gcx ast::SourceFile<'gcx>] {
+        &self.files
+    }
+
+    /// Check whether any errors were emitted so far.
+    pub fn failed(&self) -> bool {
+        self.cx.sess.failed()
+    }
+
+    /// Get the modules in the design, in the order they were parsed.
+    pub fn modules(&self) -> Vec<(Name, NodeId)> {
+        let mut modules: Vec<_> = self.cx.modules().collect();
+        modules.sort_by_key(|&(_, id)| id);
+        modules
+    }
+
+    /// Find a module by name.
+    pub fn module(&self, name: &str) -> Option<NodeId> {
+        self.cx
+            .modules()
+            .find(|&(n, _)| &*n.as_str() == name)
+            .map(|(_, id)| id)
+    }
+
+    /// Elaborate a top-level module, overriding some of its value parameters.
+    ///
+    /// Emits an error if there is no such module, or if a parameter cannot be
+    /// overridden.
+    pub fn elaborate(&self, top: &str, params: &[(&str, i64)]) -> Result<Elaboration, ()> {
+        let module = match self.module(top) {
+            Some(x) => x,
+            None => {
+                self.cx
+                    .emit(DiagBuilder2::error(format!("unknown module `{}`", top)));
+                return Err(());
+            }
+        };
+        let env = svlog::hierarchy::top_param_env(&self.cx, module, params)?;
+        let hierarchy = svlog::hierarchy::instance_tree_with_env(&self.cx, module, env).ok_or(())?;
+        Ok(Elaboration {
+            module,
+            env,
+            hierarchy,
+        })
+    }
+
+    /// Find a definition in a module by name, e.g. a parameter or variable.
+    pub fn lookup(&self, module: NodeId, name: &str) -> Option<NodeId> {
+        let module = match self.cx.hir_of(module) {
+            Ok(svlog::hir::HirNode::Module(x)) => x,
+            _ => return None,
+        };
+        let scope = self.cx.generated_scope(module.ast);
+        scope.defs.get(&Name::from(name)).map(|def| match def.node {
+            DefNode::Ast(node) => node.id(),
+            DefNode::IntPort(port) => port.id,
+        })
+    }
+
+    /// Determine the type of a node in a parametrization.
+    pub fn type_of(&self, node: NodeId, env: ParamEnv) -> Result<&'gcx UnpackedType<'gcx>, ()> {
+        self.cx.type_of(node, env)
+    }
+
+    /// Determine the constant value of a node in a parametrization, e.g. of a
+    /// parameter.
+    pub fn value_of(&self, node: NodeId, env: ParamEnv) -> Value<'gcx> {
+        self.cx.constant_value_of(node, env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_file<R>(text: &str, f: impl FnOnce(&Path) -> R) -> R {
+        let dir = std::env::temp_dir().join(format!("moore-design-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{:x}.sv", text.len()));
+        std::fs::write(&path, text).unwrap();
+        let result = f(&path);
+        std::fs::remove_file(&path).unwrap();
+        result
+    }
+
+    #[test]
+    fn elaborate() {
+        let text = "module top #(parameter int N = 2); foo #(.M(N)) a(); endmodule\n\
+                    module foo #(parameter int M = 1); endmodule\n";
+        let mut diags = vec![];
+        let result = with_file(text, |path| {
+            parse_files(&[path], &Options::default(), &mut diags, |design| {
+                let names: Vec<_> = design
+                    .modules()
+                    .iter()
+                    .map(|(name, _)| name.to_string())
+                    .collect();
+                assert_eq!(names, vec!["top", "foo"]);
+                let elab = design.elaborate("top", &[("N", 7)]).unwrap();
+                assert_eq!(elab.hierarchy.children[0].target_desc(), "foo #(M = 7)");
+                let paths: Vec<_> = elab.hierarchy.iter().map(|(path, _)| path).collect();
+                assert_eq!(paths, vec!["top", "top.a"]);
+                let inst = elab.hierarchy.find("top.a").unwrap();
+                assert_eq!(Some(inst.node), design.module("foo"));
+                assert!(elab.hierarchy.find("top.b").is_none());
+                let param = design.lookup(elab.module, "N").unwrap();
+                assert_eq!(design.type_of(param, elab.env).unwrap().to_string(), "int");
+                assert_eq!(design.value_of(param, elab.env).kind.to_string(), "7");
+                assert!(design.elaborate("bar", &[]).is_err());
+                assert!(design.elaborate("top", &[("X", 1)]).is_err());
+            })
+        });
+        assert_eq!(result, Ok(()));
+        let messages: Vec<_> = diags.iter().map(|d| d.get_message().clone()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "unknown module `bar`",
+                "module `top` has no value parameter `X`"
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let mut diags = vec![];
+        let result = with_file("module top endmodule\n", |path| {
+            parse_files(&[path], &Options::default(), &mut diags, |_| ())
+        });
+        assert_eq!(result, Err(()));
+        assert!(!diags.is_empty());
+    }
+}
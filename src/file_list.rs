@@ -0,0 +1,422 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Reading file lists passed with `-f` and `-F`.
+//!
+//! A file list contains the source files of a design, separated by whitespace,
+//! together with the options needed to compile them:
+//!
+//! - `+incdir+<dir>[+<dir>...]` and `-I <dir>` add include search paths.
+//! - `+define+<name>[=<value>][+...]` and `-D <name>[=<value>]` define macros.
+//! - `-y <dir>` adds a library directory, `-v <file>` a library file, and
+//!   `+libext+<ext>[+<ext>...]` the extensions of files in library directories.
+//! - `-f <file>` and `-F <file>` include another file list.
+//! - `//` and `#` start a comment that extends to the end of the line.
+//!
+//! References to environment variables of the form `$VAR`, `${VAR}`, and
+//! `$(VAR)` are expanded in every entry. Relative paths in a file list passed
+//! with `-f` are relative to the working directory, those in a file list passed
+//! with `-F` relative to the directory that contains the file list.
+
+use crate::common::errors::*;
+use crate::common::lints;
+use crate::common::source::{get_source_manager, Source, Span};
+use std::path::Path;
+
+/// The source files and options collected from file lists.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FileList {
+    /// The source files, in the order they were listed.
+    pub files: Vec<String>,
+    /// The include search paths.
+    pub include_dirs: Vec<String>,
+    /// The macro definitions, with their optional value.
+    pub defines: Vec<(String, Option<String>)>,
+    /// The library directories searched for missing modules.
+    pub library_dirs: Vec<String>,
+    /// The library files searched for missing modules.
+    pub library_files: Vec<String>,
+    /// The extensions of files in library directories.
+    pub library_extensions: Vec<String>,
+    /// The file lists currently being read, to detect cyclic inclusion.
+    stack: Vec<Source>,
+}
+
+impl FileList {
+    /// Create an empty file list.
+    pub fn new() -> FileList {
+        Default::default()
+    }
+
+    /// Open and read a file list.
+    ///
+    /// If `relative` is set, paths in the file list are resolved relative to
+    /// the directory that contains it, as for `-F`.
+    pub fn open(
+        &mut self,
+        path: &str,
+        relative: bool,
+        span: Option<Span>,
+        emitter: &dyn DiagEmitter,
+    ) -> Result<(), ()> {
+        let source = match get_source_manager().open(path) {
+            Some(source) => source,
+            None => {
+                let mut diag = DiagBuilder2::error(format!("unable to open file list `{}`", path));
+                if let Some(span) = span {
+                    diag = diag.span(span);
+                }
+                emitter.emit(diag);
+                return Err(());
+            }
+        };
+        let base = if relative {
+            Path::new(path).parent()
+        } else {
+            None
+        };
+        self.read(source, base, span, emitter)
+    }
+
+    /// Add an input file or plusarg option given on the command line.
+    ///
+    /// Plusargs such as `+incdir+<dir>`, `+define+<name>=<value>`, and
+    /// `+libext+<ext>` are accepted for compatibility with other compilers.
+    pub fn add_arg(&mut self, arg: &str, emitter: &dyn DiagEmitter) {
+        if self.add_plusarg(arg, None) {
+            return;
+        }
+        if arg.starts_with('+') {
+            emitter.emit(DiagBuilder2::lint(
+                lints::UNSUPPORTED_OPTION,
+                format!("ignoring unsupported option `{}`", arg),
+            ));
+        } else {
+            self.files.push(arg.to_string());
+        }
+    }
+
+    /// Apply a `+incdir+`, `+define+`, or `+libext+` option, resolving
+    /// relative include paths against `base` if it is given.
+    ///
+    /// Returns `false` if the argument is not one of these options.
+    fn add_plusarg(&mut self, arg: &str, base: Option<&Path>) -> bool {
+        if let Some(dirs) = strip_prefix(arg, "+incdir+") {
+            self.include_dirs.extend(
+                dirs.split('+')
+                    .filter(|d| !d.is_empty())
+                    .map(|d| resolve(base, d.to_string())),
+            );
+        } else if let Some(defines) = strip_prefix(arg, "+define+") {
+            self.defines.extend(
+                defines
+                    .split('+')
+                    .filter(|d| !d.is_empty())
+                    .map(parse_define),
+            );
+        } else if let Some(exts) = strip_prefix(arg, "+libext+") {
+            self.library_extensions
+                .extend(exts.split('+').filter(|e| !e.is_empty()).map(String::from));
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Read the entries of a file list.
+    ///
+    /// Relative paths are resolved against `base` if it is given.
+    pub fn read(
+        &mut self,
+        source: Source,
+        base: Option<&Path>,
+        span: Option<Span>,
+        emitter: &dyn DiagEmitter,
+    ) -> Result<(), ()> {
+        if self.stack.contains(&source) {
+            let mut diag =
+                DiagBuilder2::error(format!("file list `{}` includes itself", source.get_path()));
+            if let Some(span) = span {
+                diag = diag.span(span);
+            }
+            emitter.emit(diag);
+            return Err(());
+        }
+        self.stack.push(source);
+        let result = self.read_entries(source, base, emitter);
+        self.stack.pop();
+        result
+    }
+
+    fn read_entries(
+        &mut self,
+        source: Source,
+        base: Option<&Path>,
+        emitter: &dyn DiagEmitter,
+    ) -> Result<(), ()> {
+        let resolve = |path: String| resolve(base, path);
+        let mut failed = false;
+        let mut tokens = tokenize(source).into_iter();
+        while let Some(span) = tokens.next() {
+            let entry = match expand_env(&span.extract()) {
+                Ok(entry) => entry,
+                Err(var) => {
+                    emitter.emit(
+                        DiagBuilder2::error(format!("environment variable `{}` is not set", var))
+                            .span(span),
+                    );
+                    failed = true;
+                    continue;
+                }
+            };
+
+            // Options that take the next entry as their argument.
+            if let "-f" | "-F" | "-I" | "-D" | "-y" | "-v" = entry.as_str() {
+                let arg_span = match tokens.next() {
+                    Some(arg) => arg,
+                    None => {
+                        emitter.emit(
+                            DiagBuilder2::error(format!("`{}` requires an argument", entry))
+                                .span(span),
+                        );
+                        failed = true;
+                        break;
+                    }
+                };
+                let arg = match expand_env(&arg_span.extract()) {
+                    Ok(arg) => arg,
+                    Err(var) => {
+                        emitter.emit(
+                            DiagBuilder2::error(format!(
+                                "environment variable `{}` is not set",
+                                var
+                            ))
+                            .span(arg_span),
+                        );
+                        failed = true;
+                        continue;
+                    }
+                };
+                match entry.as_str() {
+                    "-f" => failed |= self.open(&arg, false, Some(arg_span), emitter).is_err(),
+                    "-F" => {
+                        failed |= self
+                            .open(&resolve(arg), true, Some(arg_span), emitter)
+                            .is_err()
+                    }
+                    "-I" => self.include_dirs.push(resolve(arg)),
+                    "-y" => self.library_dirs.push(resolve(arg)),
+                    "-v" => self.library_files.push(resolve(arg)),
+                    _ => self.defines.push(parse_define(&arg)),
+                }
+                continue;
+            }
+
+            if self.add_plusarg(&entry, base) {
+                continue;
+            } else if let Some(dir) = strip_prefix(&entry, "-I") {
+                self.include_dirs.push(resolve(dir.to_string()));
+            } else if let Some(define) = strip_prefix(&entry, "-D") {
+                self.defines.push(parse_define(define));
+            } else if entry.starts_with('+') || entry.starts_with('-') {
+                emitter.emit(
+                    DiagBuilder2::lint(
+                        lints::UNSUPPORTED_OPTION,
+                        format!("ignoring unsupported option `{}`", entry),
+                    )
+                    .span(span),
+                );
+            } else {
+                self.files.push(resolve(entry));
+            }
+        }
+        if failed {
+            Err(())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Split a file list into its entries, skipping whitespace and comments.
+fn tokenize(source: Source) -> Vec<Span> {
+    let content = source.get_content();
+    let mut tokens = vec![];
+    let mut chars = content.iter().peekable();
+    while let Some((begin, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let comment = c == '#' || (c == '/' && chars.peek().map(|x| x.1) == Some('/'));
+        let mut end = begin + c.len_utf8();
+        while let Some(&(offset, c)) = chars.peek() {
+            if c == '\n' || (!comment && c.is_whitespace()) {
+                break;
+            }
+            end = offset + c.len_utf8();
+            chars.next();
+        }
+        if !comment {
+            tokens.push(Span::new(source, begin, end));
+        }
+    }
+    tokens
+}
+
+/// Resolve a relative path against a base directory, if one is given.
+fn resolve(base: Option<&Path>, path: String) -> String {
+    match base {
+        Some(base) if Path::new(&path).is_relative() => {
+            base.join(path).to_string_lossy().into_owned()
+        }
+        _ => path,
+    }
+}
+
+/// Split a macro definition of the form `NAME[=VALUE]`.
+fn parse_define(define: &str) -> (String, Option<String>) {
+    match define.find('=') {
+        Some(index) => (
+            define[..index].to_string(),
+            Some(define[index + 1..].to_string()),
+        ),
+        None => (define.to_string(), None),
+    }
+}
+
+/// Remove a prefix from a string, if the string starts with it.
+fn strip_prefix<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    if text.starts_with(prefix) {
+        Some(&text[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+/// Expand references to environment variables in a string.
+///
+/// References may be of the form `$VAR`, `${VAR}`, or `$(VAR)`. Returns the
+/// name of the variable as an error if it is not set.
+pub fn expand_env(input: &str) -> Result<String, String> {
+    expand_vars(input, |name| std::env::var(name).ok())
+}
+
+/// Expand references to variables in a string, looking up their values with
+/// a function.
+fn expand_vars(input: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut output = String::new();
+    let mut rest = input;
+    while let Some(index) = rest.find('$') {
+        output.push_str(&rest[..index]);
+        rest = &rest[index + 1..];
+        let (name, len) = match rest.chars().next() {
+            Some(open @ '{') | Some(open @ '(') => {
+                let close = if open == '{' { '}' } else { ')' };
+                match rest.find(close) {
+                    Some(end) => (&rest[1..end], end + 1),
+                    None => return Err(rest.to_string()),
+                }
+            }
+            _ => {
+                let end = rest
+                    .find(|c: char| !c.is_alphanumeric() && c != '_')
+                    .unwrap_or(rest.len());
+                (&rest[..end], end)
+            }
+        };
+        if name.is_empty() {
+            output.push('$');
+            continue;
+        }
+        match lookup(name) {
+            Some(value) => output.push_str(&value),
+            None => return Err(name.to_string()),
+        }
+        rest = &rest[len..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Session;
+
+    #[test]
+    fn env_vars() {
+        let lookup = |name: &str| match name {
+            "HOME" => Some("/home/me".to_string()),
+            "IP" => Some("ip".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            expand_vars("$HOME/${IP}/$(IP)_pkg.sv", lookup),
+            Ok("/home/me/ip/ip_pkg.sv".to_string())
+        );
+        assert_eq!(expand_vars("a$", lookup), Ok("a$".to_string()));
+        assert_eq!(expand_vars("$NOPE/a.sv", lookup), Err("NOPE".to_string()));
+    }
+
+    #[test]
+    fn entries() {
+        let sm = get_source_manager();
+        let source = sm.add(
+            "lists/top.f",
+            "// The top-level design.\n\
+             +incdir+inc+common/inc\n\
+             +define+SYNTHESIS+WIDTH=8\n\
+             -I other -DFOO=1 # Some more options.\n\
+             -y cells -v /abs/prims.v +libext+.v+.vlib\n\
+             rtl/pkg.sv\n\
+             /abs/top.sv\n",
+        );
+        let sess = Session::new();
+        let mut list = FileList::new();
+        list.read(source, Some(Path::new("lists")), None, &sess)
+            .unwrap();
+        assert_eq!(list.files, vec!["lists/rtl/pkg.sv", "/abs/top.sv"]);
+        assert_eq!(
+            list.include_dirs,
+            vec!["lists/inc", "lists/common/inc", "lists/other"]
+        );
+        assert_eq!(
+            list.defines,
+            vec![
+                ("SYNTHESIS".to_string(), None),
+                ("WIDTH".to_string(), Some("8".to_string())),
+                ("FOO".to_string(), Some("1".to_string())),
+            ]
+        );
+        assert_eq!(list.library_dirs, vec!["lists/cells"]);
+        assert_eq!(list.library_files, vec!["/abs/prims.v"]);
+        assert_eq!(list.library_extensions, vec![".v", ".vlib"]);
+    }
+
+    #[test]
+    fn plusargs() {
+        let sess = Session::new();
+        let mut list = FileList::new();
+        list.add_arg("+incdir+a+b", &sess);
+        list.add_arg("+define+X+Y=1", &sess);
+        list.add_arg("top.sv", &sess);
+        assert_eq!(list.files, vec!["top.sv"]);
+        assert_eq!(list.include_dirs, vec!["a", "b"]);
+        assert_eq!(
+            list.defines,
+            vec![
+                ("X".to_string(), None),
+                ("Y".to_string(), Some("1".to_string()))
+            ]
+        );
+    }
+
+    #[test]
+    fn self_inclusion() {
+        let sm = get_source_manager();
+        let source = sm.add("lists/self.f", "a.sv\n-f lists/self.f\n");
+        let sess = Session::new();
+        let mut list = FileList::new();
+        assert!(list.read(source, None, None, &sess).is_err());
+        assert_eq!(list.files, vec!["a.sv"]);
+    }
+}
@@ -15,4 +15,9 @@ pub use moore_common::*;
 pub use moore_svlog as svlog;
 pub use moore_vhdl as vhdl;
 
+pub mod cache;
+pub mod config;
+pub mod design;
+pub mod file_list;
+pub mod lsp;
 pub mod score;
@@ -0,0 +1,500 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! A language server for editors.
+//!
+//! The server speaks the Language Server Protocol over standard input and
+//! output. Whenever a document is opened, changed, or closed, the open
+//! documents are parsed and the names in their modules resolved, and the
+//! diagnostics this produces are published. The server also looks up the
+//! definition of the name under the cursor, and describes it when hovering.
+//! Only SystemVerilog documents are supported.
+
+use crate::common::errors::*;
+use crate::common::source::{get_source_manager, Location, Span};
+use crate::common::Session;
+use crate::svlog;
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead, Write},
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+};
+
+/// A language server.
+#[derive(Debug, Default)]
+pub struct Server {
+    /// The include search paths.
+    pub include_dirs: Vec<String>,
+    /// The macro definitions, with their optional value.
+    pub defines: Vec<(String, Option<String>)>,
+    /// The text of the open documents, by URI.
+    documents: BTreeMap<String, String>,
+}
+
+impl Server {
+    /// Create a server without any open documents.
+    pub fn new(include_dirs: Vec<String>, defines: Vec<(String, Option<String>)>) -> Server {
+        Server {
+            include_dirs,
+            defines,
+            documents: Default::default(),
+        }
+    }
+
+    /// Answer the messages from a client until it asks the server to exit.
+    pub fn run(&mut self, input: &mut impl BufRead, output: &mut impl Write) -> io::Result<()> {
+        while let Some(message) = read_message(input)? {
+            if message["method"] == "exit" {
+                break;
+            }
+            for reply in self.handle(&message) {
+                write_message(output, &reply)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle a request or notification from the client.
+    ///
+    /// Returns the messages to send back, i.e. the response to a request, and
+    /// notifications with updated diagnostics.
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let params = &message["params"];
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+        let result = match message["method"].as_str().unwrap_or("") {
+            "initialize" => Some(json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "definitionProvider": true,
+                    "hoverProvider": true,
+                    "referencesProvider": true,
+                },
+                "serverInfo": {
+                    "name": "moore",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+            })),
+            "shutdown" => Some(Value::Null),
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or("");
+                self.documents.insert(uri.to_string(), text.to_string());
+                return self.publish_diagnostics();
+            }
+            "textDocument/didChange" => {
+                // Only full updates of the document are announced in the
+                // server's capabilities.
+                let changes = params["contentChanges"].as_array();
+                if let Some(text) = changes.and_then(|c| c.last()?["text"].as_str()) {
+                    self.documents.insert(uri.to_string(), text.to_string());
+                }
+                return self.publish_diagnostics();
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                let mut replies = self.publish_diagnostics();
+                replies.push(diagnostics_notification(uri, vec![]));
+                return replies;
+            }
+            "textDocument/definition" => Some(self.definition(params).unwrap_or(Value::Null)),
+            "textDocument/hover" => Some(self.hover(params).unwrap_or(Value::Null)),
+            "textDocument/references" => Some(self.references(params).unwrap_or(Value::Null)),
+            _ => None,
+        };
+
+        // Notifications carry no id and must not be answered.
+        let id = match message.get("id") {
+            Some(id) => id,
+            None => return vec![],
+        };
+        match result {
+            Some(result) => vec![json!({ "jsonrpc": "2.0", "id": id, "result": result })],
+            None => vec![json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {
+                    "code": -32601,
+                    "message": format!("method `{}` is not supported", message["method"]),
+                },
+            })],
+        }
+    }
+
+    /// Check the open documents and publish the diagnostics for each of them.
+    fn publish_diagnostics(&self) -> Vec<Value> {
+        let mut published: BTreeMap<&str, Vec<Value>> = self
+            .documents
+            .keys()
+            .map(|uri| (uri.as_str(), vec![]))
+            .collect();
+        let diags = self.analyze(|_, _| ()).map(|x| x.0).unwrap_or_default();
+        for diag in diags {
+            let span = match diag.get_segments().iter().find_map(|s| match *s {
+                DiagSegment::Span(sp) => Some(sp),
+                _ => None,
+            }) {
+                Some(span) => span,
+                None => continue,
+            };
+            let mut message = diag.get_message().clone();
+            for segment in diag.get_segments() {
+                if let DiagSegment::Note(ref note) = *segment {
+                    message.push('\n');
+                    message.push_str(note);
+                }
+            }
+            let severity = match diag.get_severity() {
+                Severity::Note => 3,
+                Severity::Warning => 2,
+                _ => 1,
+            };
+            let mut diagnostic = json!({
+                "range": range(span),
+                "severity": severity,
+                "source": "moore",
+                "message": message,
+            });
+            if let Some(code) = diag.get_code() {
+                diagnostic["code"] = json!(code.code);
+            }
+            if let Some(list) = published.get_mut(self.uri_of(span).as_str()) {
+                list.push(diagnostic);
+            }
+        }
+        published
+            .into_iter()
+            .map(|(uri, diagnostics)| diagnostics_notification(uri, diagnostics))
+            .collect()
+    }
+
+    /// Find the definition of the name at a position in a document.
+    fn definition(&self, params: &Value) -> Option<Value> {
+        let (uri, offset) = self.offset(params)?;
+        self.analyze(|cx, files| {
+            let file = files.iter().find(|(u, _)| *u == uri)?.1;
+            let reference = svlog::lookup::reference_at(cx, file, offset)?;
+            let span = reference.def.name.span;
+            Some(json!({ "uri": self.uri_of(span), "range": range(span) }))
+        })?
+        .1
+    }
+
+    /// Describe the name at a position in a document.
+    fn hover(&self, params: &Value) -> Option<Value> {
+        let (uri, offset) = self.offset(params)?;
+        self.analyze(|cx, files| {
+            let file = files.iter().find(|(u, _)| *u == uri)?.1;
+            let reference = svlog::lookup::reference_at(cx, file, offset)?;
+            Some(json!({
+                "contents": {
+                    "kind": "plaintext",
+                    "value": svlog::lookup::describe(cx, reference.def),
+                },
+                "range": range(reference.name.span),
+            }))
+        })?
+        .1
+    }
+
+    /// Find the names which refer to the definition at a position in a
+    /// document.
+    fn references(&self, params: &Value) -> Option<Value> {
+        let (uri, offset) = self.offset(params)?;
+        let include_declaration = params["context"]["includeDeclaration"] == true;
+        self.analyze(|cx, files| {
+            let file = files.iter().find(|(u, _)| *u == uri)?.1;
+            let def = svlog::lookup::definition_at(cx, file, offset)?;
+            let mut spans = svlog::lookup::references_to(cx, file, def);
+            if include_declaration {
+                spans.insert(0, def.name.span);
+            }
+            let locations: Vec<_> = spans
+                .into_iter()
+                .map(|span| json!({ "uri": self.uri_of(span), "range": range(span) }))
+                .collect();
+            Some(Value::from(locations))
+        })?
+        .1
+    }
+
+    /// Parse the open documents and resolve the names in their modules, then
+    /// call a function to look at the result.
+    ///
+    /// Returns the diagnostics produced along the way together with the result
+    /// of the function, or `None` if the compiler panicked.
+    fn analyze<R>(
+        &self,
+        f: impl for<'gcx> FnOnce(
+            &svlog::GlobalContext<'gcx>,
+            &[(&str, &'gcx svlog::ast::SourceFile<'gcx>)],
+        ) -> R,
+    ) -> Option<(Vec<DiagBuilder2>, R)> {
+        let sess = Session::new();
+        let include_dirs: Vec<_> = self.include_dirs.iter().map(Path::new).collect();
+        let defines: Vec<_> = self
+            .defines
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_ref().map(String::as_str)))
+            .collect();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let arenas = svlog::GlobalArenas::default();
+            let sm = get_source_manager();
+            let mut files = vec![];
+            for (uri, text) in &self.documents {
+                let source = sm.update(&uri_to_path(uri), text);
+                let preproc = svlog::preproc::Preprocessor::new(source, &include_dirs, &defines);
+                let lexer = svlog::lexer::Lexer::new(preproc);
+                if let Ok(file) = svlog::parser::parse(lexer, &arenas.ast, &sess) {
+                    files.push((uri.as_str(), file));
+                }
+            }
+            let cx = svlog::GlobalContext::new(&sess, &arenas);
+            cx.add_files(files.iter().map(|(_, file)| file));
+            let mut modules: Vec<_> = cx.modules().collect();
+            modules.sort_by_key(|&(_, id)| id);
+            for (_, id) in modules {
+                cx.check_module_names(id);
+            }
+            let files: Vec<_> = files.iter().map(|(uri, file)| (*uri, file)).collect();
+            f(&cx, &files)
+        }));
+        let diags = sess.take_diagnostics();
+        result.ok().map(|result| (diags, result))
+    }
+
+    /// Determine the document and byte offset a request refers to.
+    fn offset<'a>(&'a self, params: &Value) -> Option<(&'a str, usize)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let (uri, text) = self.documents.iter().find(|(k, _)| k.as_str() == uri)?;
+        let line = params["position"]["line"].as_u64()? as usize;
+        let character = params["position"]["character"].as_u64()? as usize;
+        let line_start = text
+            .split('\n')
+            .take(line)
+            .map(|l| l.len() + 1)
+            .sum::<usize>()
+            .min(text.len());
+        let line_text = text[line_start..].split('\n').next().unwrap_or("");
+        let column = line_text
+            .char_indices()
+            .nth(character)
+            .map(|(i, _)| i)
+            .unwrap_or(line_text.len());
+        Some((uri, line_start + column))
+    }
+
+    /// Determine the URI of the file a span points into, preferring the URI
+    /// under which the client opened the file.
+    fn uri_of(&self, span: Span) -> String {
+        let path = span.source.get_path();
+        self.documents
+            .keys()
+            .find(|uri| uri_to_path(uri) == &*path)
+            .cloned()
+            .unwrap_or_else(|| path_to_uri(&path))
+    }
+}
+
+/// Read a message from the client. Returns `None` at the end of the input.
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(colon) = line.find(':') {
+            if line[..colon].eq_ignore_ascii_case("Content-Length") {
+                length = line[colon + 1..].trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let length = length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "message without Content-Length")
+    })?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Write a message to the client.
+fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+fn diagnostics_notification(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+/// Convert a span to an LSP range with zero-based lines and columns.
+fn range(span: Span) -> Value {
+    json!({ "start": position(span.begin()), "end": position(span.end()) })
+}
+
+fn position(location: Location) -> Value {
+    let (line, column, _) = location.human();
+    json!({ "line": line - 1, "character": column - 1 })
+}
+
+/// Convert a `file://` URI to a path, decoding escaped characters.
+fn uri_to_path(uri: &str) -> String {
+    let path = if uri.starts_with("file://") {
+        &uri["file://".len()..]
+    } else {
+        uri
+    };
+    let path = path.as_bytes();
+    let mut decoded = Vec::with_capacity(path.len());
+    let mut i = 0;
+    while i < path.len() {
+        let escaped = path
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (path[i], escaped) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Convert a path to a `file://` URI, escaping characters as necessary.
+fn path_to_uri(path: &str) -> String {
+    let mut uri = String::from("file://");
+    for &byte in path.as_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open(server: &mut Server, uri: &str, text: &str) -> Vec<Value> {
+        server.handle(&json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": { "textDocument": { "uri": uri, "text": text } },
+        }))
+    }
+
+    fn request(server: &mut Server, method: &str, uri: &str, line: usize, col: usize) -> Value {
+        let mut replies = server.handle(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": {
+                "textDocument": { "uri": uri },
+                "position": { "line": line, "character": col },
+            },
+        }));
+        assert_eq!(replies.len(), 1);
+        replies.remove(0)["result"].take()
+    }
+
+    #[test]
+    fn uris() {
+        assert_eq!(uri_to_path("file:///a%20b/c.sv"), "/a b/c.sv");
+        assert_eq!(path_to_uri("/a b/c.sv"), "file:///a%20b/c.sv");
+    }
+
+    #[test]
+    fn diagnostics() {
+        let mut server = Server::default();
+        let replies = open(
+            &mut server,
+            "file:///lsp/diag.sv",
+            "module foo;\n  assign x = 1;\nendmodule\n",
+        );
+        assert_eq!(replies.len(), 1);
+        let params = &replies[0]["params"];
+        assert_eq!(params["uri"], "file:///lsp/diag.sv");
+        assert_eq!(params["diagnostics"][0]["message"], "`x` not found");
+        assert_eq!(params["diagnostics"][0]["range"]["start"]["line"], 1);
+        assert_eq!(params["diagnostics"][0]["range"]["start"]["character"], 9);
+    }
+
+    #[test]
+    fn definition_and_hover() {
+        let mut server = Server::default();
+        let uri = "file:///lsp/nav.sv";
+        let replies = open(
+            &mut server,
+            uri,
+            "module foo #(parameter int N = 4);\n  \
+             logic [N-1:0] x;\n  \
+             assign x = '0;\n\
+             endmodule\n",
+        );
+        assert_eq!(replies[0]["params"]["diagnostics"], json!([]));
+
+        let def = request(&mut server, "textDocument/definition", uri, 2, 9);
+        assert_eq!(def["uri"], uri);
+        assert_eq!(def["range"]["start"], json!({ "line": 1, "character": 16 }));
+
+        let hover = request(&mut server, "textDocument/hover", uri, 2, 9);
+        assert_eq!(hover["contents"]["value"], "variable `x`: logic [3:0]");
+        let hover = request(&mut server, "textDocument/hover", uri, 1, 9);
+        assert_eq!(hover["contents"]["value"], "value parameter `N`: int = 4");
+    }
+
+    #[test]
+    fn references() {
+        let mut server = Server::default();
+        let uri = "file:///lsp/refs.sv";
+        open(
+            &mut server,
+            uri,
+            "module foo;\n  \
+             logic x, y;\n  \
+             assign y = x;\n  \
+             assign x = 0;\n\
+             endmodule\n",
+        );
+        let lines = |refs: &Value| -> Vec<_> {
+            refs.as_array()
+                .unwrap()
+                .iter()
+                .map(|r| r["range"]["start"]["line"].as_u64().unwrap())
+                .collect()
+        };
+        let refs = request(&mut server, "textDocument/references", uri, 2, 13);
+        assert_eq!(lines(&refs), vec![2, 3]);
+        let refs = server.handle(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "textDocument/references",
+            "params": {
+                "textDocument": { "uri": uri },
+                "position": { "line": 1, "character": 8 },
+                "context": { "includeDeclaration": true },
+            },
+        }));
+        assert_eq!(lines(&refs[0]["result"]), vec![1, 2, 3]);
+    }
+}
@@ -89,6 +89,7 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
             global: self,
             sb: self.vhdl,
             lazy: self.vhdl_phases,
+            env: Default::default(),
         }
     }
 
@@ -196,18 +197,26 @@ impl<'lazy, 'sb, 'ast, 'ctx> NodeMaker<ScopeRef, &'ctx Defs>
                         None => [].iter(),
                     };
                     let svlog_defs = self.svlog.find_module(name.into());
-                    let both_defs: Vec<Spanned<Def>> = vhdl_defs
+                    let mut both_defs: Vec<Spanned<Def>> = vhdl_defs
                         .map(|d| Spanned::new(Def::Vhdl(d.value), d.span))
                         .chain(
                             svlog_defs.map(|id| Spanned::new(Def::Svlog(id), self.svlog.span(id))),
                         )
                         .collect();
 
+                    // Report duplicates the same way the frontends do, pointing
+                    // at the later declaration and noting the earlier ones in
+                    // the order the files were given.
                     if both_defs.len() > 1 {
+                        both_defs.sort_by_key(|d| d.span);
+                        let (last, earlier) = both_defs.split_last().unwrap();
                         let mut diag =
-                            DiagBuilder2::error(format!("`{}` declared multiple times", name));
-                        for def in both_defs {
-                            diag = diag.span(def.span);
+                            DiagBuilder2::error(format!("`{}` has already been declared", name))
+                                .span(last.span);
+                        for def in earlier {
+                            diag = diag
+                                .add_note("previous declaration was here:")
+                                .span(def.span);
                         }
                         self.sess.emit(diag);
                         had_dups = true;
@@ -10,6 +10,7 @@ use crate::crate_prelude::*;
 use std::cell::RefCell;
 use std::collections::HashMap;
 
+/// A mapping from node IDs to the AST nodes they were allocated for.
 #[derive(Default)]
 pub struct AstMap<'ast> {
     map: RefCell<HashMap<NodeId, AstNode<'ast>>>,
@@ -26,6 +27,71 @@ impl<'ast> AstMap<'ast> {
     pub fn get(&self, id: NodeId) -> Option<AstNode<'ast>> {
         self.map.borrow().get(&id).cloned()
     }
+
+    /// Find the ID of an AST node in the map.
+    ///
+    /// Most nodes are mapped to the ID of the underlying AST node, which is
+    /// checked first. Nodes without one, such as ports, are searched for.
+    pub fn find(&self, node: AstNode<'ast>) -> Option<NodeId> {
+        let map = self.map.borrow();
+        if let Some(any) = node.get_any() {
+            if map.get(&any.id()) == Some(&node) {
+                return Some(any.id());
+            }
+        }
+        map.iter()
+            .find(|&(_, &other)| other == node)
+            .map(|(&id, _)| id)
+    }
+
+    /// Get the number of nodes in the map.
+    pub fn len(&self) -> usize {
+        self.map.borrow().len()
+    }
+
+    /// Check whether the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.map.borrow().is_empty()
+    }
+
+    /// Get the nodes in the map, ordered by their ID.
+    pub fn entries(&self) -> Vec<(NodeId, AstNode<'ast>)> {
+        self.filter(|_| true)
+    }
+
+    /// Get the nodes in the map for which a predicate holds, ordered by their
+    /// ID.
+    ///
+    /// For example, a predicate that accepts `AstNode::Module(..)` lists the
+    /// modules.
+    pub fn filter(
+        &self,
+        predicate: impl Fn(AstNode<'ast>) -> bool,
+    ) -> Vec<(NodeId, AstNode<'ast>)> {
+        let mut entries: Vec<_> = self
+            .map
+            .borrow()
+            .iter()
+            .filter(|&(_, &node)| predicate(node))
+            .map(|(&id, &node)| (id, node))
+            .collect();
+        entries.sort_by_key(|&(id, _)| id);
+        entries
+    }
+
+    /// Count the nodes in the map by kind, e.g. *"module"*.
+    ///
+    /// The kinds are ordered by decreasing count, and by name among equal
+    /// counts.
+    pub fn stats(&self) -> Vec<(&'static str, usize)> {
+        let mut counts = HashMap::<_, usize>::new();
+        for node in self.map.borrow().values() {
+            *counts.entry(node.desc()).or_default() += 1;
+        }
+        let mut counts: Vec<_> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        counts
+    }
 }
 
 /// A reference to an AST node.
@@ -0,0 +1,120 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Microbenchmarks for the construction and interning of types.
+//!
+//! Run with `cargo bench -p moore-svlog --bench types`. Each benchmark prints
+//! the average time it takes to construct and intern one type, once for the
+//! first occurrence of a type and once for repeated occurrences, which is what
+//! dominates type checking of designs with many wide signals and structs.
+
+use moore_common::{name::get_name_table, source::INVALID_SPAN};
+use moore_svlog::{
+    ty::{
+        Domain, IntVecType, PackedCore, PackedDim, PackedType, Range, RangeDir, SbvType, Sign,
+        TypeStorage, UnpackedCore, UnpackedDim, UnpackedType,
+    },
+    Spanned,
+};
+use std::time::{Duration, Instant};
+
+/// The number of distinct types created by each benchmark.
+const DISTINCT: usize = 1000;
+
+/// The number of times each distinct type is created again.
+const REPEATS: usize = 100;
+
+fn main() {
+    bench("logic [N:0]", |st, n| {
+        PackedType::make_dims(
+            &st,
+            IntVecType::Logic,
+            vec![PackedDim::Range(Range {
+                size: n + 1,
+                dir: RangeDir::Down,
+                offset: 0,
+            })],
+        );
+    });
+    bench("simple bit vector", |st, n| {
+        PackedType::make_simple_bit_vector(
+            &st,
+            SbvType::new(Domain::FourValued, Sign::Unsigned, n + 1),
+        );
+    });
+    bench("logic [N:0][7:0]", |st, n| {
+        PackedType::make_dims(
+            &st,
+            IntVecType::Logic,
+            vec![
+                PackedDim::Range(Range {
+                    size: n + 1,
+                    dir: RangeDir::Down,
+                    offset: 0,
+                }),
+                PackedDim::Range(Range {
+                    size: 8,
+                    dir: RangeDir::Down,
+                    offset: 0,
+                }),
+            ],
+        );
+    });
+    bench("logic [7:0] x [N]", |st, n| {
+        let elem = PackedType::make_dims(
+            &st,
+            IntVecType::Logic,
+            vec![PackedDim::Range(Range {
+                size: 8,
+                dir: RangeDir::Down,
+                offset: 0,
+            })],
+        );
+        UnpackedType::make_dims(&st, elem, vec![UnpackedDim::Array(n + 1)]);
+    });
+    let name = get_name_table().intern("word_t", true);
+    bench("word_t [N:0]", |st, n| {
+        let word = PackedType::make(&st, IntVecType::Logic);
+        let ty = PackedType::make_dims(
+            &st,
+            PackedCore::Named {
+                name: Spanned::new(name, INVALID_SPAN),
+                ty: word,
+            },
+            vec![PackedDim::Range(Range {
+                size: n + 1,
+                dir: RangeDir::Down,
+                offset: 0,
+            })],
+        );
+        UnpackedType::make(&st, UnpackedCore::Packed(ty));
+    });
+}
+
+/// Measure the time it takes to create `DISTINCT` types with `make`, first
+/// each type once, and then each type `REPEATS` times.
+fn bench(name: &str, make: impl for<'a> Fn(&'a TypeStorage<'a>, usize)) {
+    let storage = TypeStorage::default();
+    let start = Instant::now();
+    for n in 0..DISTINCT {
+        make(&storage, n);
+    }
+    let first = start.elapsed();
+    let start = Instant::now();
+    for _ in 0..REPEATS {
+        for n in 0..DISTINCT {
+            make(&storage, n);
+        }
+    }
+    let repeated = start.elapsed();
+    println!(
+        "{:<20} first: {:>8}  repeated: {:>8}",
+        name,
+        per_type(first, DISTINCT),
+        per_type(repeated, DISTINCT * REPEATS)
+    );
+}
+
+/// Format the average time per type.
+fn per_type(duration: Duration, count: usize) -> String {
+    format!("{:.1} ns", duration.as_nanos() as f64 / count as f64)
+}
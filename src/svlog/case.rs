@@ -0,0 +1,271 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Case statement completeness and overlap checks.
+//!
+//! This module reports `case` statements which do not handle every value of
+//! their expression, and items which overlap with an earlier item. Cases
+//! without a `default` must list every member of an enum, or every value of a
+//! bit vector of up to 16 bits. The `unique`, `unique0`, and `priority`
+//! qualifiers state that the unhandled values never occur, so such cases are
+//! not checked for completeness. Overlapping items are legal in a `priority`
+//! case, where the first matching item wins, but make the later item partly
+//! unreachable in a plain case, and violate a `unique` or `unique0` case.
+//!
+//! The `z` and `?` bits in the items of a `casez`, and the `x` and `z` bits in
+//! the items of a `casex`, match any value.
+
+use crate::{
+    ast_map::AstNode,
+    crate_prelude::*,
+    hir::{HirNode, StmtKind, Visitor},
+    value::ValueKind,
+    ParamEnv,
+};
+use bit_vec::BitVec;
+use moore_common::lints;
+use num::{BigInt, One, Zero};
+
+/// Report incomplete case statements and overlapping case items in the
+/// processes of a module.
+///
+/// Only the processes directly within the module body are considered;
+/// processes nested in generate blocks are skipped.
+pub fn check_cases<'a>(cx: &impl Context<'a>, node_id: NodeId, env: ParamEnv) {
+    let hir = match cx.hir_of(node_id) {
+        Ok(HirNode::Module(x)) => x,
+        _ => return,
+    };
+    let mut checker = CaseChecker { cx, env };
+    for &proc_id in &hir.block.procs {
+        checker.visit_node_with_id(proc_id, false);
+    }
+}
+
+/// A constant case item.
+struct Item {
+    /// The label expression.
+    id: NodeId,
+    /// The index of the way the label belongs to.
+    way: usize,
+    /// The value of the label.
+    value: BigInt,
+    /// The bits of the label which have to match. The others are wildcards.
+    care: BigInt,
+}
+
+impl Item {
+    /// Check whether the item matches a value.
+    fn matches(&self, value: &BigInt) -> bool {
+        ((&self.value ^ value) & &self.care).is_zero()
+    }
+
+    /// Check whether the item matches some of the values another item matches.
+    fn overlaps(&self, other: &Item) -> bool {
+        ((&self.value ^ &other.value) & &self.care & &other.care).is_zero()
+    }
+}
+
+/// A visitor that checks the case statements in a process.
+struct CaseChecker<'c, C> {
+    cx: &'c C,
+    env: ParamEnv,
+}
+
+impl<'a, 'c, C: Context<'a>> CaseChecker<'c, C> {
+    /// Check a case statement.
+    fn check_case(
+        &self,
+        stmt: &hir::Stmt,
+        expr: NodeId,
+        ways: &[(Vec<NodeId>, NodeId)],
+        default: Option<NodeId>,
+        kind: ast::CaseKind,
+    ) {
+        // Find the `unique`, `unique0`, or `priority` qualifier, which is only
+        // recorded in the AST.
+        let up = match self.cx.ast_of(stmt.id) {
+            Ok(AstNode::Stmt(stmt)) => match stmt.kind {
+                ast::CaseStmt { up, .. } => up,
+                _ => None,
+            },
+            _ => None,
+        };
+        let ty = self.cx.mir_rvalue(expr, self.env).ty;
+        let width = match ty.get_bit_size() {
+            Some(w) => w,
+            None => return,
+        };
+        let all = (BigInt::one() << width) - BigInt::one();
+
+        // Evaluate the labels. Labels which are not constant prevent the
+        // completeness check.
+        let mut items = vec![];
+        let mut all_const = true;
+        for (way, (labels, _)) in ways.iter().enumerate() {
+            for &label in labels {
+                if !self.cx.mir_rvalue(label, self.env).is_const() {
+                    all_const = false;
+                    continue;
+                }
+                let value = self.cx.constant_value_of(label, self.env);
+                let (value, special, x) = match value.kind {
+                    ValueKind::Int(ref v, ref s, ref x) => (v, s, x),
+                    _ => {
+                        all_const = false;
+                        continue;
+                    }
+                };
+                let wildcard = match kind {
+                    // Items with `x` or `z` bits never match a two-state value.
+                    ast::CaseKind::Normal if special.any() => continue,
+                    ast::CaseKind::Normal => BigInt::zero(),
+                    ast::CaseKind::DontCareZ => {
+                        let mut mask = special.clone();
+                        mask.difference(x);
+                        to_int(&mask)
+                    }
+                    ast::CaseKind::DontCareXZ => to_int(special),
+                };
+                let care = &all & !wildcard;
+                items.push(Item {
+                    id: label,
+                    way,
+                    value: value & &care,
+                    care,
+                });
+            }
+        }
+
+        // Report items which overlap with an item of an earlier way.
+        if up != Some(ast::UniquePriority::Priority) {
+            for (i, item) in items.iter().enumerate() {
+                let earlier = match items[..i]
+                    .iter()
+                    .find(|e| e.way != item.way && e.overlaps(item))
+                {
+                    Some(x) => x,
+                    None => continue,
+                };
+                let note = match up {
+                    Some(ast::UniquePriority::Unique0) => {
+                        "Overlapping items violate the `unique0` qualifier of the case statement."
+                            .to_string()
+                    }
+                    Some(_) => {
+                        "Overlapping items violate the `unique` qualifier of the case statement."
+                            .to_string()
+                    }
+                    None => "The earlier item takes precedence, such that this item is never \
+                             taken for the values both items match."
+                        .to_string(),
+                };
+                self.cx.emit(
+                    DiagBuilder2::lint(
+                        lints::CASE_OVERLAP,
+                        format!(
+                            "case item `{}` overlaps with an earlier item",
+                            self.cx.span(item.id).extract()
+                        ),
+                    )
+                    .span(self.cx.span(item.id))
+                    .add_note("The earlier item is here:")
+                    .span(self.cx.span(earlier.id))
+                    .add_note(note),
+                );
+            }
+        }
+
+        // Report the values which are not handled.
+        if default.is_some() || up.is_some() || !all_const {
+            return;
+        }
+        let expr_text = self.cx.span(expr).extract();
+        let (msg, example) = if let Some(enum_ty) = ty.get_enum() {
+            let mut missing = vec![];
+            for (name, variant) in &enum_ty.variants {
+                let value = self.cx.constant_value_of(variant.id(), self.env);
+                if let Some(value) = value.get_int() {
+                    if !items.iter().any(|item| item.matches(value)) {
+                        missing.push(format!("`{}`", name.value));
+                    }
+                }
+            }
+            if missing.is_empty() {
+                return;
+            }
+            let msg = format!(
+                "case statement does not handle {} of `{}`",
+                join_names(&missing),
+                expr_text
+            );
+            (msg, None)
+        } else if width <= 16 {
+            let mut missing = (0..1u32 << width)
+                .map(BigInt::from)
+                .filter(|value| !items.iter().any(|item| item.matches(value)));
+            let first = match missing.next() {
+                Some(x) => x,
+                None => return,
+            };
+            let count = 1 + missing.count();
+            let msg = format!(
+                "case statement does not handle {} of the {} values of `{}`",
+                count,
+                1u32 << width,
+                expr_text
+            );
+            let example = format!("For example, `{}'h{:x}` is not handled.", width, first);
+            (msg, Some(example))
+        } else {
+            return;
+        };
+        let mut d = DiagBuilder2::lint(lints::CASE_INCOMPLETE, msg).span(self.cx.span(expr));
+        if let Some(example) = example {
+            d = d.add_note(example);
+        }
+        self.cx
+            .emit(d.add_note("Add the missing items or a `default` case."));
+    }
+}
+
+impl<'a, 'c, C: Context<'a>> Visitor<'a> for CaseChecker<'c, C> {
+    type Context = C;
+
+    fn context(&self) -> &C {
+        self.cx
+    }
+
+    fn visit_stmt(&mut self, stmt: &'a hir::Stmt) -> hir::Control {
+        if let StmtKind::Case {
+            expr,
+            ref ways,
+            default,
+            kind,
+        } = stmt.kind
+        {
+            self.check_case(stmt, expr, ways, default, kind);
+        }
+        hir::walk_stmt(self, stmt)
+    }
+}
+
+/// Convert a bit mask with the most significant bit first to an integer.
+fn to_int(bits: &BitVec) -> BigInt {
+    let mut value = BigInt::zero();
+    for bit in bits {
+        value <<= 1;
+        if bit {
+            value |= BigInt::one();
+        }
+    }
+    value
+}
+
+/// Join a list of names into `a`, `a and b`, or `a, b, and c`.
+fn join_names(names: &[String]) -> String {
+    match names.len() {
+        0 | 1 => names.concat(),
+        2 => format!("{} and {}", names[0], names[1]),
+        n => format!("{}, and {}", names[..n - 1].join(", "), names[n - 1]),
+    }
+}
@@ -0,0 +1,277 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Clock and reset inference.
+//!
+//! This module identifies the clock and asynchronous reset signals of
+//! edge-triggered processes. A process of the form
+//!
+//! ```text
+//! always_ff @(posedge clk or negedge rst_n)
+//!     if (!rst_n) ... else ...
+//! ```
+//!
+//! is recognized as being clocked by `clk` on the rising edge and reset
+//! asynchronously by `rst_n` when it is low. The signals in the sensitivity
+//! list which are tested by the leading `if` chain of the process body are
+//! considered resets; the remaining edge is the clock.
+
+use crate::{
+    crate_prelude::*,
+    hir::{HirNode, StmtKind, TimingControl},
+    ParamEnv,
+};
+use std::sync::Arc;
+
+/// The clock and resets of an edge-triggered process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcClocking {
+    /// The process.
+    pub proc: NodeId,
+    /// The clock of the process, if one could be identified.
+    pub clock: Option<ClockSignal>,
+    /// The asynchronous resets of the process.
+    pub resets: Vec<ResetSignal>,
+}
+
+/// A clock signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockSignal {
+    /// The variable or port carrying the clock.
+    pub signal: NodeId,
+    /// The name of the signal.
+    pub name: Name,
+    /// The edge on which the process is triggered.
+    pub edge: ast::EdgeIdent,
+}
+
+/// An asynchronous reset signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResetSignal {
+    /// The variable or port carrying the reset.
+    pub signal: NodeId,
+    /// The name of the signal.
+    pub name: Name,
+    /// The edge on which the process is triggered.
+    pub edge: ast::EdgeIdent,
+    /// Whether the reset is asserted when the signal is high.
+    pub active_high: bool,
+}
+
+/// The clocks and resets of a module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleClocking {
+    /// The clocking of each edge-triggered process in the module.
+    pub procs: Vec<Arc<ProcClocking>>,
+    /// The distinct clock signals used in the module.
+    pub clocks: Vec<ClockSignal>,
+    /// The distinct reset signals used in the module.
+    pub resets: Vec<ResetSignal>,
+}
+
+/// Determine the clock and asynchronous resets of a process.
+///
+/// Returns `None` if the process is not triggered by signal edges.
+#[moore_derive::query]
+pub(crate) fn proc_clocking<'a>(
+    cx: &impl Context<'a>,
+    node_id: NodeId,
+    env: ParamEnv,
+) -> Result<Option<Arc<ProcClocking>>> {
+    let prok = match cx.hir_of(node_id)? {
+        HirNode::Proc(x) => x,
+        x => bug_span!(x.span(), cx, "proc_clocking called on {}", x.desc_full()),
+    };
+
+    // Find the event control at the beginning of the process.
+    let (event_id, body) = match cx.hir_of(prok.stmt)? {
+        HirNode::Stmt(hir::Stmt {
+            kind:
+                StmtKind::Timed {
+                    control: TimingControl::ExplicitEvent(event_id),
+                    stmt,
+                },
+            ..
+        }) => (*event_id, *stmt),
+        _ => return Ok(None),
+    };
+    let event = match cx.hir_of(event_id)? {
+        HirNode::EventExpr(x) => x,
+        _ => unreachable!(),
+    };
+
+    // Collect the signals whose edges trigger the process.
+    let mut edges = vec![];
+    for event in &event.events {
+        if event.edge == ast::EdgeIdent::Implicit {
+            continue;
+        }
+        if let Some(signal) = signal_of(cx, event.expr, env) {
+            edges.push((signal, event.edge));
+        }
+    }
+    if edges.is_empty() {
+        return Ok(None);
+    }
+
+    // Every edge signal tested by the leading if chain is a reset.
+    let mut resets = vec![];
+    let mut next = Some(body);
+    while let Some(stmt_id) = next.take().and_then(|id| leading_stmt(cx, id)) {
+        let (cond, else_stmt) = match cx.hir_of(stmt_id)? {
+            HirNode::Stmt(hir::Stmt {
+                kind: StmtKind::If {
+                    cond, else_stmt, ..
+                },
+                ..
+            }) => (*cond, *else_stmt),
+            _ => break,
+        };
+        let (signal, active_high) = match reset_condition(cx, cx.mir_rvalue(cond, env)) {
+            Some(x) => x,
+            None => break,
+        };
+        let edge = match edges.iter().position(|&(s, _)| s == signal) {
+            Some(index) => edges.remove(index).1,
+            None => break,
+        };
+        resets.push(ResetSignal {
+            signal,
+            name: signal_name(cx, signal)?,
+            edge,
+            active_high,
+        });
+        next = else_stmt;
+    }
+
+    // The remaining edge is the clock.
+    if edges.len() > 1 {
+        debug!(
+            "{} has multiple clock candidates {:?}",
+            prok.desc_full(),
+            edges
+        );
+    }
+    let clock = match edges.first() {
+        Some(&(signal, edge)) => Some(ClockSignal {
+            signal,
+            name: signal_name(cx, signal)?,
+            edge,
+        }),
+        None => None,
+    };
+
+    Ok(Some(Arc::new(ProcClocking {
+        proc: node_id,
+        clock,
+        resets,
+    })))
+}
+
+/// Determine the clocks and asynchronous resets of a module.
+///
+/// Only the processes directly within the module body are considered;
+/// processes nested in generate blocks are skipped.
+#[moore_derive::query]
+pub(crate) fn module_clocking<'a>(
+    cx: &impl Context<'a>,
+    node_id: NodeId,
+    env: ParamEnv,
+) -> Result<Arc<ModuleClocking>> {
+    let hir = match cx.hir_of(node_id)? {
+        HirNode::Module(x) => x,
+        x => bug_span!(x.span(), cx, "module_clocking called on {}", x.desc_full()),
+    };
+    let mut clocking = ModuleClocking {
+        procs: vec![],
+        clocks: vec![],
+        resets: vec![],
+    };
+    for &proc_id in &hir.block.procs {
+        let prok = match cx.proc_clocking(proc_id, env)? {
+            Some(x) => x,
+            None => continue,
+        };
+        if let Some(clock) = prok.clock {
+            if !clocking.clocks.iter().any(|c| c.signal == clock.signal) {
+                clocking.clocks.push(clock);
+            }
+        }
+        for &reset in &prok.resets {
+            if !clocking.resets.iter().any(|r| r.signal == reset.signal) {
+                clocking.resets.push(reset);
+            }
+        }
+        clocking.procs.push(prok);
+    }
+    Ok(Arc::new(clocking))
+}
+
+/// Skip over blocks that only contain a single statement.
+fn leading_stmt<'a>(cx: &impl Context<'a>, stmt_id: NodeId) -> Option<NodeId> {
+    match cx.hir_of(stmt_id) {
+        Ok(HirNode::Stmt(hir::Stmt {
+            kind: StmtKind::Block(stmts),
+            ..
+        })) if stmts.len() == 1 => leading_stmt(cx, stmts[0]),
+        Ok(HirNode::Stmt(_)) => Some(stmt_id),
+        _ => None,
+    }
+}
+
+/// Determine the variable or port an expression refers to.
+fn signal_of<'a>(cx: &impl Context<'a>, expr_id: NodeId, env: ParamEnv) -> Option<NodeId> {
+    match cx.mir_rvalue(expr_id, env).strip_casts().kind {
+        mir::RvalueKind::Var(id) | mir::RvalueKind::Port(id) => Some(id),
+        _ => None,
+    }
+}
+
+/// Determine the signal tested by a reset condition, and whether the signal
+/// is active-high.
+///
+/// Recognizes `rst`, `!rst`, `~rst`, and comparisons of `rst` against a
+/// constant.
+fn reset_condition<'a>(cx: &impl Context<'a>, mir: &'a mir::Rvalue<'a>) -> Option<(NodeId, bool)> {
+    match mir.strip_casts().kind {
+        mir::RvalueKind::Var(id) | mir::RvalueKind::Port(id) => Some((id, true)),
+        mir::RvalueKind::CastToBool(arg) => reset_condition(cx, arg),
+        mir::RvalueKind::UnaryBitwise {
+            op: mir::UnaryBitwiseOp::Not,
+            arg,
+        } => reset_condition(cx, arg).map(|(id, high)| (id, !high)),
+        mir::RvalueKind::IntComp {
+            op: op @ mir::IntCompOp::Eq,
+            lhs,
+            rhs,
+            ..
+        }
+        | mir::RvalueKind::IntComp {
+            op: op @ mir::IntCompOp::Neq,
+            lhs,
+            rhs,
+            ..
+        } => {
+            let (signal, konst) = if rhs.is_const() {
+                (lhs, rhs)
+            } else {
+                (rhs, lhs)
+            };
+            if !konst.is_const() {
+                return None;
+            }
+            let (id, high) = reset_condition(cx, signal)?;
+            let zero = cx.const_mir_rvalue(konst.into()).is_false();
+            Some((id, high ^ zero ^ (op == mir::IntCompOp::Neq)))
+        }
+        _ => None,
+    }
+}
+
+/// Determine the name of a signal.
+fn signal_name<'a>(cx: &impl Context<'a>, id: NodeId) -> Result<Name> {
+    Ok(match cx.hir_of(id)? {
+        HirNode::VarDecl(x) => x.name.value,
+        HirNode::IntPort(x) => x.name.value,
+        x => bug_span!(x.span(), cx, "{} is not a signal", x.desc_full()),
+    })
+}
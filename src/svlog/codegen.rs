@@ -4,7 +4,8 @@
 
 use crate::{
     crate_prelude::*,
-    hir::{AccessedNode, HirNode},
+    dpi,
+    hir::{AccessedNode, HirNode, ModuleId, ProcId},
     port_list::PortList,
     resolver::InstTarget,
     ty::UnpackedType,
@@ -13,7 +14,7 @@ use crate::{
 };
 use num::{BigInt, One, ToPrimitive, Zero};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     iter::{once, repeat},
     ops::{Deref, DerefMut},
     rc::Rc,
@@ -29,6 +30,37 @@ pub struct CodeGenerator<'gcx, C> {
     into: llhd::ir::Module,
     /// Tables holding mappings and interned values.
     tables: Tables<'gcx>,
+    /// How modules are mapped to LLHD entities.
+    policy: EntityPolicy,
+}
+
+/// A policy for mapping module specializations to LLHD entities.
+///
+/// Every instantiation of a module with a distinct set of parameters, i.e. a
+/// distinct `ParamEnv`, is a separate specialization of that module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityPolicy {
+    /// Emit one entity for every specialization of a module.
+    ///
+    /// The entity for the default parameters is named after the module. All
+    /// other specializations are named `<module>.param<N>`, where `N`
+    /// identifies the parameter environment. This preserves a one-to-one
+    /// mapping between specializations and entities.
+    PerInstance,
+    /// Share one entity among the specializations of a module which produce
+    /// the same code.
+    ///
+    /// Specializations whose parameters do not affect the generated entity or
+    /// any of its processes are mapped to the entity emitted for the first of
+    /// them. This reduces the size of the output, at the cost of no longer
+    /// having one entity per specialization.
+    Shared,
+}
+
+impl Default for EntityPolicy {
+    fn default() -> Self {
+        EntityPolicy::PerInstance
+    }
 }
 
 impl<'gcx, C> CodeGenerator<'gcx, C> {
@@ -38,7 +70,53 @@ impl<'gcx, C> CodeGenerator<'gcx, C> {
             cx,
             into: llhd::ir::Module::new(),
             tables: Default::default(),
+            policy: Default::default(),
+        }
+    }
+
+    /// Change how modules are mapped to LLHD entities.
+    pub fn set_entity_policy(&mut self, policy: EntityPolicy) {
+        self.policy = policy;
+    }
+
+    /// Write a map from the emitted entity ports to the source ports.
+    ///
+    /// Each line describes one port of an emitted entity, listing the entity
+    /// name, port direction, position, LLHD name and type, and the port or
+    /// interface signal in the source text that the port corresponds to. See
+    /// `determine_module_ports` for how source ports are mapped.
+    pub fn write_port_map(&self, out: &mut impl std::io::Write) -> std::io::Result<()> {
+        let mut modules: Vec<_> = self
+            .tables
+            .module_defs
+            .values()
+            .flat_map(|m| m.as_ref().ok())
+            .map(|m| (self.into.unit(m.unit).name().to_string(), m))
+            .collect();
+        modules.sort_by(|a, b| a.0.cmp(&b.0));
+        modules.dedup_by(|a, b| a.0 == b.0);
+        for (name, module) in modules {
+            let sig = &module.ports.sig;
+            let inputs = module.ports.inputs.iter().zip(sig.inputs());
+            for (index, (port, arg)) in inputs.enumerate() {
+                write_port_map_line(out, &name, "input", index, port, &sig.arg_type(arg))?;
+            }
+            let outputs = module.ports.outputs.iter().zip(sig.outputs());
+            for (index, (port, arg)) in outputs.enumerate() {
+                write_port_map_line(out, &name, "output", index, port, &sig.arg_type(arg))?;
+            }
         }
+        Ok(())
+    }
+
+    /// Write a C header declaring the DPI functions and tasks of the emitted
+    /// modules.
+    ///
+    /// See `dpi::write_c_header` for the contents of the header.
+    pub fn write_dpi_header(&self, out: &mut impl std::io::Write) -> std::io::Result<()> {
+        let mut subroutines = self.tables.dpi_subroutines.clone();
+        subroutines.sort_by(|a, b| a.c_name.as_str().cmp(&b.c_name.as_str()));
+        dpi::write_c_header(out, &subroutines)
     }
 
     /// Finalize code generation and return the generated LLHD module.
@@ -52,6 +130,9 @@ struct Tables<'gcx> {
     module_defs: HashMap<NodeEnvId, Result<Rc<EmittedModule<'gcx>>>>,
     module_signatures: HashMap<NodeEnvId, (llhd::ir::UnitName, llhd::ir::Signature)>,
     interned_types: HashMap<&'gcx UnpackedType<'gcx>, Result<llhd::Type>>,
+    extern_decls: HashSet<String>,
+    dpi_subroutines: Vec<dpi::DpiSubroutine<'gcx>>,
+    shared_entities: HashMap<(NodeId, String), Rc<EmittedModule<'gcx>>>,
 }
 
 impl<'gcx, C> Deref for CodeGenerator<'gcx, C> {
@@ -65,7 +146,11 @@ impl<'gcx, C> Deref for CodeGenerator<'gcx, C> {
 impl<'a, 'gcx, C: Context<'gcx>> CodeGenerator<'gcx, &'a C> {
     /// Emit the code for a module and all its dependent modules.
     pub fn emit_module(&mut self, id: NodeId) -> Result<Rc<EmittedModule<'gcx>>> {
-        self.emit_module_with_env(id, self.default_param_env())
+        let name = ModuleId::from_id(self.cx, id)?.hir(self.cx).name;
+        self.sess().enter_inst(&name.value.as_str());
+        let result = self.emit_module_with_env(id, self.default_param_env());
+        self.sess().leave_inst();
+        result
     }
 
     /// Emit the code for a module and all its dependent modules.
@@ -77,11 +162,9 @@ impl<'a, 'gcx, C: Context<'gcx>> CodeGenerator<'gcx, &'a C> {
         if let Some(x) = self.tables.module_defs.get(&id.env(env)) {
             return x.clone();
         }
-        let hir = match self.hir_of(id)? {
-            HirNode::Module(m) => m,
-            _ => panic!("expected {:?} to be a module", id),
-        };
+        let hir = ModuleId::from_id(self.cx, id)?.hir(self.cx);
         info!("Emit module `{}` with {:?}", hir.name, env);
+        let _timer = self.sess().time_pass(format!("module `{}`", hir.name));
 
         // Emit detailed port information if requested.
         if self.sess().has_verbosity(Verbosity::PORTS) {
@@ -141,6 +224,18 @@ impl<'a, 'gcx, C: Context<'gcx>> CodeGenerator<'gcx, &'a C> {
         // Emit the actual contents of the entity.
         gen.emit_module_block(id, env, &hir.block, &entity_name)?;
 
+        // Emit the DPI functions and tasks declared in the module, or in the
+        // compilation unit the module is declared in.
+        gen.emit_dpi_subroutines(&hir.ast.items, env)?;
+        let mut parent = hir.ast.get_parent();
+        while let Some(node) = parent {
+            if let ast::AllNode::SourceFile(file) = node.as_all() {
+                gen.emit_dpi_subroutines(&file.items, env)?;
+                break;
+            }
+            parent = node.get_parent();
+        }
+
         // Assign default values to undriven output ports.
         for port in ports.outputs.iter() {
             let value = gen.values[&port.accnode];
@@ -172,11 +267,153 @@ impl<'a, 'gcx, C: Context<'gcx>> CodeGenerator<'gcx, &'a C> {
         }
 
         let unit = self.into.add_unit(ent);
-        let result = Ok(Rc::new(EmittedModule { unit, ports }));
+        let mut emitted = Rc::new(EmittedModule { unit, ports });
+        if self.policy == EntityPolicy::Shared {
+            emitted = self.share_entity(id, emitted);
+        }
+        let result = Ok(emitted);
         self.tables.module_defs.insert(id.env(env), result.clone());
         result
     }
 
+    /// Share an emitted entity with an equivalent specialization of the same
+    /// module.
+    ///
+    /// Two entities are considered equivalent if they and their processes
+    /// only differ in their names. If an equivalent entity has already been
+    /// emitted, the new entity and its processes are removed from the output
+    /// and the existing entity is returned instead.
+    fn share_entity(
+        &mut self,
+        id: NodeId,
+        emitted: Rc<EmittedModule<'gcx>>,
+    ) -> Rc<EmittedModule<'gcx>> {
+        let entity = self.into.unit(emitted.unit);
+
+        // Find the processes instantiated by the entity. These are the only
+        // local units the entity refers to.
+        let procs: Vec<_> = entity
+            .extern_units()
+            .filter(|(_, data)| data.name.is_local())
+            .flat_map(|(_, data)| self.into.units().find(|u| *u.name() == data.name))
+            .collect();
+
+        // Print the entity and its processes, and replace their names with
+        // placeholders such that specializations can be compared.
+        let mut renames = vec![(entity.name().to_string(), "@<entity>".to_string())];
+        let mut text = format!("{}\n", entity);
+        for (i, prok) in procs.iter().enumerate() {
+            renames.push((prok.name().to_string(), format!("%<proc{}>", i)));
+            text.push_str(&format!("{}\n", prok));
+        }
+        for (from, to) in renames {
+            text = text.replace(&format!("{} ", from), &format!("{} ", to));
+        }
+        let proc_ids: Vec<_> = procs.iter().map(|u| u.id()).collect();
+
+        // Reuse an existing equivalent entity if there is one.
+        if let Some(existing) = self.tables.shared_entities.get(&(id, text.clone())) {
+            debug!(
+                "Sharing entity {} for {}",
+                self.into.unit(existing.unit).name(),
+                self.into.unit(emitted.unit).name()
+            );
+            let existing = existing.clone();
+            for unit in proc_ids {
+                self.into.remove_unit(unit);
+            }
+            self.into.remove_unit(emitted.unit);
+            return existing;
+        }
+        self.tables
+            .shared_entities
+            .insert((id, text), emitted.clone());
+        emitted
+    }
+
+    /// Emit the DPI functions and tasks declared among a list of items.
+    ///
+    /// Imported subroutines are declared as external functions named after
+    /// their C identifier. Tasks return an `i32` which is always zero, as
+    /// prescribed for the C side. Output and `inout` arguments, as well as
+    /// arguments which C receives by reference, such as packed vectors, are
+    /// passed as pointers. Each C identifier is only emitted once.
+    ///
+    /// Exported subroutines are not supported, since functions and tasks are
+    /// not lowered to LLHD. They are ignored with a warning.
+    fn emit_dpi_subroutines(
+        &mut self,
+        items: &'gcx [ast::Item<'gcx>],
+        env: ParamEnv,
+    ) -> Result<()> {
+        for sub in dpi::dpi_subroutines(self.cx, items, env)? {
+            let c_name = llhd::ir::UnitName::global(sub.c_name.as_str());
+            if !self.tables.extern_decls.insert(c_name.to_string()) {
+                continue;
+            }
+            if !sub.import {
+                self.emit(
+                    DiagBuilder2::lint(
+                        lints::UNSUPPORTED,
+                        format!("unsupported: export of `{}` to C; ignored", sub.name),
+                    )
+                    .span(sub.name.span)
+                    .add_note("Functions and tasks are not lowered to LLHD"),
+                );
+                continue;
+            }
+
+            // Determine the signature.
+            let mut sig = llhd::ir::Signature::new();
+            for arg in &sub.args {
+                let ty = self.emit_dpi_type(arg.ty)?;
+                sig.add_input(if arg.is_output() || !dpi::is_small_value(arg.ty) {
+                    llhd::pointer_ty(ty)
+                } else {
+                    ty
+                });
+            }
+            sig.set_return_type(match (sub.kind, sub.retty) {
+                (ast::SubroutineKind::Task, _) => llhd::int_ty(32),
+                (_, Some(ty)) => self.emit_dpi_type(ty)?,
+                (_, None) => llhd::void_ty(),
+            });
+            self.into.declare(c_name, sig);
+            self.tables.dpi_subroutines.push(sub);
+        }
+        Ok(())
+    }
+
+    /// Map the type of a DPI argument or return value to an LLHD type.
+    ///
+    /// Strings and `chandle`s are passed as pointers to bytes.
+    fn emit_dpi_type(&mut self, ty: &'gcx UnpackedType<'gcx>) -> Result<llhd::Type> {
+        match ty.resolve_full().core {
+            ty::UnpackedCore::String | ty::UnpackedCore::Chandle => {
+                Ok(llhd::pointer_ty(llhd::int_ty(8)))
+            }
+            _ => self.emit_type(ty),
+        }
+    }
+
+    /// Determine the LLHD ports of a module.
+    ///
+    /// Ports are mapped to LLHD entity ports deterministically, in the order in
+    /// which they appear in the module's internal port list:
+    ///
+    /// - A regular port maps to exactly one LLHD port of the same name. Its
+    ///   type is mapped faithfully, such that unpacked arrays become LLHD
+    ///   arrays and unpacked structs become LLHD structs. Inputs and `ref`
+    ///   ports become entity inputs, outputs and `inout` ports become entity
+    ///   outputs.
+    /// - An interface port is flattened into one LLHD port per signal declared
+    ///   in the interface, named `<port>.<signal>` and ordered as the signals
+    ///   are declared in the interface. The direction of each signal is taken
+    ///   from the modport, if one is given; otherwise the signal becomes an
+    ///   output. The unpacked dimensions of an array of interfaces are applied
+    ///   to each of the signals.
+    ///
+    /// The resulting mapping can be written out with `write_port_map`.
     fn determine_module_ports(
         &mut self,
         ports: &'gcx [port_list::IntPort<'gcx>],
@@ -311,6 +548,21 @@ impl<'a, 'gcx, C: Context<'gcx>> CodeGenerator<'gcx, &'a C> {
         Ok(result)
     }
 
+    /// Map the type of a node accessed by a procedure to LLHD.
+    fn emit_accessed_node_type(&mut self, id: AccessedNode, env: ParamEnv) -> Result<llhd::Type> {
+        self.emit_type(match id {
+            AccessedNode::Regular(id) => self.type_of(id, env)?,
+            AccessedNode::Intf(intf, id) => {
+                let intf_ty = self.type_of(intf, env)?;
+                let intf_ty_inner = intf_ty.resolve_full().core.get_interface().unwrap();
+                let mut sig_ty = self.type_of(id, intf_ty_inner.env)?.clone();
+                sig_ty.dims.extend(&intf_ty.dims);
+                sig_ty.intern(self.cx)
+            }
+            AccessedNode::Cover(_) => return Ok(llhd::int_ty(COVER_COUNTER_WIDTH)),
+        })
+    }
+
     /// Emit the code for a procedure.
     fn emit_procedure(
         &mut self,
@@ -318,10 +570,7 @@ impl<'a, 'gcx, C: Context<'gcx>> CodeGenerator<'gcx, &'a C> {
         env: ParamEnv,
         name_prefix: &str,
     ) -> Result<EmittedProcedure> {
-        let hir = match self.hir_of(id)? {
-            HirNode::Proc(x) => x,
-            _ => unreachable!(),
-        };
+        let hir = ProcId::from_id(self.cx, id)?.hir(self.cx);
 
         // Find the accessed nodes.
         let acc = self.accessed_nodes(hir.stmt, env)?;
@@ -330,29 +579,11 @@ impl<'a, 'gcx, C: Context<'gcx>> CodeGenerator<'gcx, &'a C> {
         let mut inputs = vec![];
         let mut outputs = vec![];
         for &id in acc.read.iter().filter(|id| !acc.written.contains(id)) {
-            sig.add_input(llhd::signal_ty(self.emit_type(match id {
-                AccessedNode::Regular(id) => self.type_of(id, env)?,
-                AccessedNode::Intf(intf, id) => {
-                    let intf_ty = self.type_of(intf, env)?;
-                    let intf_ty_inner = intf_ty.resolve_full().core.get_interface().unwrap();
-                    let mut sig_ty = self.type_of(id, intf_ty_inner.env)?.clone();
-                    sig_ty.dims.extend(&intf_ty.dims);
-                    sig_ty.intern(self.cx)
-                }
-            })?));
+            sig.add_input(llhd::signal_ty(self.emit_accessed_node_type(id, env)?));
             inputs.push(id);
         }
         for &id in acc.written.iter() {
-            sig.add_output(llhd::signal_ty(self.emit_type(match id {
-                AccessedNode::Regular(id) => self.type_of(id, env)?,
-                AccessedNode::Intf(intf, id) => {
-                    let intf_ty = self.type_of(intf, env)?;
-                    let intf_ty_inner = intf_ty.resolve_full().core.get_interface().unwrap();
-                    let mut sig_ty = self.type_of(id, intf_ty_inner.env)?.clone();
-                    sig_ty.dims.extend(&intf_ty.dims);
-                    sig_ty.intern(self.cx)
-                }
-            })?));
+            sig.add_output(llhd::signal_ty(self.emit_accessed_node_type(id, env)?));
             outputs.push(id);
         }
         trace!("Process Inputs: {:?}", inputs);
@@ -386,6 +617,7 @@ impl<'a, 'gcx, C: Context<'gcx>> CodeGenerator<'gcx, &'a C> {
         let guess_name = |id| {
             let (prefix, id) = match id {
                 AccessedNode::Regular(id) => (None, id),
+                AccessedNode::Cover(id) => return Some(cover_counter_name(self.cx, id)),
                 AccessedNode::Intf(inst_id, id) => {
                     let inst_name = match self.hir_of(inst_id).ok()? {
                         HirNode::IntPort(x) => Some(x.name),
@@ -442,10 +674,8 @@ impl<'a, 'gcx, C: Context<'gcx>> CodeGenerator<'gcx, &'a C> {
 
         // Determine which values are both read and written. These require
         // shadow variables to emulate the expected behaviour under blocking
-        // assignments.
-        let input_set: HashSet<_> = acc.read.iter().cloned().collect();
-        let output_set: HashSet<_> = acc.written.iter().cloned().collect();
-        for &id in input_set.intersection(&output_set) {
+        // assignments. Iterate in a fixed order to keep the output stable.
+        for &id in acc.read.intersection(&acc.written) {
             let init = pg.builder.ins().prb(pg.values[&id.into()]);
             let shadow = pg.builder.ins().var(init);
             if let Some(name) = pg
@@ -550,6 +780,12 @@ impl<'a, 'gcx, C: Context<'gcx>> CodeGenerator<'gcx, &'a C> {
             return Ok(llhd::array_ty(size, self.emit_type(inner)?));
         }
 
+        // Handle real numbers, which are represented by the bit pattern of
+        // their IEEE 754 encoding.
+        if let Some(real) = ty.get_real() {
+            return Ok(llhd::int_ty(real.bit_size()));
+        }
+
         // Handle structs.
         if let Some(strukt) = ty.get_struct() {
             let mut types = vec![];
@@ -673,8 +909,8 @@ struct UnitGenerator<'a, 'gcx, C> {
     /// The MIR rvalues emitted into the unit.
     interned_rvalues: HashMap<NodeId, Result<llhd::ir::Value>>,
     /// The shadow variables introduced to handle signals which are both read
-    /// and written in a process.
-    shadows: HashMap<AccessedNode, llhd::ir::Value>,
+    /// and written in a process. Ordered to keep the output stable.
+    shadows: BTreeMap<AccessedNode, llhd::ir::Value>,
 }
 
 impl<'a, 'gcx, C> Deref for UnitGenerator<'a, 'gcx, C> {
@@ -700,10 +936,7 @@ where
         match self.values.get(&src) {
             Some(&v) => v,
             None => bug_span!(
-                self.span(match src {
-                    AccessedNode::Regular(id) => id,
-                    AccessedNode::Intf(_, id) => id,
-                }),
+                self.span(src.id()),
                 self.cx,
                 "no value emitted for {:?}",
                 src
@@ -725,6 +958,54 @@ where
         self.interned_rvalues.clear();
     }
 
+    /// Determine the constant values an initial block assigns to signals.
+    ///
+    /// Returns the value assigned to each signal if the process is an initial
+    /// block that only assigns constants to the given declarations through
+    /// blocking assignments. Returns `None` if the process does anything else
+    /// and has to be emitted as a process.
+    fn fold_initial_block(
+        &mut self,
+        proc_id: NodeId,
+        env: ParamEnv,
+        decls: &[NodeId],
+    ) -> Result<Option<Vec<(NodeId, Value<'gcx>)>>> {
+        let hir = ProcId::from_id(self.cx, proc_id)?.hir(self.cx);
+        if hir.kind != ast::ProcedureKind::Initial {
+            return Ok(None);
+        }
+        let mut values = vec![];
+        let mut todo = vec![hir.stmt];
+        while let Some(stmt_id) = todo.pop() {
+            let stmt = match self.hir_of(stmt_id)? {
+                HirNode::Stmt(x) => x,
+                _ => return Ok(None),
+            };
+            match stmt.kind {
+                hir::StmtKind::Null => (),
+                hir::StmtKind::Block(ref stmts) => todo.extend(stmts.iter().rev()),
+                hir::StmtKind::Assign {
+                    lhs,
+                    rhs,
+                    kind: hir::AssignKind::Block(ast::AssignOp::Identity),
+                } => {
+                    let lhs = self.mir_lvalue(lhs, env);
+                    let rhs = self.mir_rvalue(rhs, env);
+                    let decl_id = match lhs.kind {
+                        mir::LvalueKind::Var(id) if decls.contains(&id) => id,
+                        _ => return Ok(None),
+                    };
+                    if !rhs.is_const() || rhs.is_error() || rhs.ty != lhs.ty {
+                        return Ok(None);
+                    }
+                    values.push((decl_id, self.const_mir_rvalue(rhs.into())));
+                }
+                _ => return Ok(None),
+            }
+        }
+        Ok(Some(values))
+    }
+
     /// Emit the code for the contents of a module.
     fn emit_module_block(
         &mut self,
@@ -733,6 +1014,21 @@ where
         hir: &hir::ModuleBlock,
         name_prefix: &str,
     ) -> Result<()> {
+        // Fold initial blocks which only assign constants into the initial
+        // values of the signals. This is skipped at `-O0`, where every initial
+        // block is emitted as a process.
+        let mut folded_values = HashMap::new();
+        let mut folded_procs = HashSet::new();
+        if self.sess().opts.opt_level > 0 {
+            for &proc_id in &hir.procs {
+                if let Some(values) = self.fold_initial_block(proc_id, env, &hir.decls)? {
+                    debug!("Folding {:?} into signal initial values", proc_id);
+                    folded_values.extend(values);
+                    folded_procs.insert(proc_id);
+                }
+            }
+        }
+
         // Emit declarations.
         for &decl_id in &hir.decls {
             let hir = match self.hir_of(decl_id)? {
@@ -741,9 +1037,10 @@ where
             };
             let ty = self.type_of(decl_id, env)?;
             let init = self.emit_const(
-                match hir.init {
-                    Some(expr) => self.constant_value_of(expr, env),
-                    None => self.type_default_value(ty),
+                match (folded_values.remove(&decl_id), hir.init) {
+                    (Some(value), _) => value,
+                    (None, Some(expr)) => self.constant_value_of(expr, env),
+                    (None, None) => self.type_default_value(ty),
                 },
                 env,
                 self.span(hir.init.unwrap_or(decl_id)),
@@ -863,7 +1160,10 @@ where
             };
 
             // Emit the instantiated module.
-            let target = self.emit_module_with_env(target_module.id, inst.inner_env)?;
+            self.enter_inst(inst.hir);
+            let target = self.emit_module_with_env(target_module.id, inst.inner_env);
+            self.leave_inst();
+            let target = target?;
 
             // Prepare the port assignments.
             let (inputs, outputs) = self.emit_port_connections(
@@ -930,8 +1230,30 @@ where
             }
         }
 
+        // Emit the hit counters of the cover statements in the procedures.
+        if self.sess().opts.coverage {
+            for &proc_id in &hir.procs {
+                let stmt = match self.hir_of(proc_id)? {
+                    HirNode::Proc(x) => x.stmt,
+                    _ => unreachable!(),
+                };
+                for &acc in &self.accessed_nodes(stmt, env)?.written {
+                    if let AccessedNode::Cover(id) = acc {
+                        let zero = self.builder.ins().const_int((COVER_COUNTER_WIDTH, 0));
+                        let value = self.builder.ins().sig(zero);
+                        self.builder
+                            .set_name(value, cover_counter_name(self.cx, id));
+                        self.values.insert(acc, value);
+                    }
+                }
+            }
+        }
+
         // Emit and instantiate procedures.
         for &proc_id in &hir.procs {
+            if folded_procs.contains(&proc_id) {
+                continue;
+            }
             let prok = self.emit_procedure(proc_id, env, name_prefix)?;
             let lookup_value = |&id: &AccessedNode| match self.values.get(&id) {
                 Some(v) => v.clone(),
@@ -1001,6 +1323,7 @@ where
             if port_mapping_int.insert(int.id, mapping).is_some() {
                 self.emit(
                     DiagBuilder2::error(format!("port `{}` connected multiple times", int.name))
+                        .code(codes::SV0005)
                         .span(self.span(mapping.id())),
                 );
             }
@@ -1127,6 +1450,16 @@ where
                 .builder
                 .ins()
                 .const_time(llhd::value::TimeValue::new(k.clone(), 0, 0))),
+            ValueKind::Real(bits) => {
+                let k = match value.ty.get_real() {
+                    Some(ty::RealType::ShortReal) => {
+                        BigInt::from((f64::from_bits(bits) as f32).to_bits())
+                    }
+                    _ => BigInt::from(bits),
+                };
+                let size = value.ty.get_bit_size().unwrap();
+                Ok(self.builder.ins().const_int((size, k)))
+            }
             ValueKind::StructOrArray(ref v) => {
                 if let Some(_dim) = value.ty.outermost_dim() {
                     let fields: Result<Vec<_>> = v
@@ -1502,6 +1835,84 @@ where
                 })
             }
 
+            mir::RvalueKind::IntToReal(sign, value) => {
+                let arg = self.emit_mir_rvalue(value)?;
+                let op = match sign {
+                    ty::Sign::Signed => "sitofp",
+                    ty::Sign::Unsigned => "uitofp",
+                };
+                let name = format!("{}.{}.{}", op, self.llhd_type(arg), real_suffix(mir.ty));
+                let ret = self.emit_type(mir.ty)?;
+                Ok(self.emit_runtime_call(&name, vec![arg], ret))
+            }
+
+            mir::RvalueKind::RealToInt(value) => {
+                // Reals are rounded to the nearest integer, with ties away
+                // from zero, rather than truncated (IEEE 1800-2017 6.12.1).
+                let suffix = real_suffix(value.ty);
+                let arg = self.emit_mir_rvalue(value)?;
+                let real_ty = self.llhd_type(arg);
+                let arg = self.emit_runtime_call(&format!("round.{}", suffix), vec![arg], real_ty);
+                let ret = self.emit_type(mir.ty)?;
+                let name = format!("fptosi.{}.{}", suffix, ret);
+                Ok(self.emit_runtime_call(&name, vec![arg], ret))
+            }
+
+            mir::RvalueKind::RealToReal(value) => {
+                let arg = self.emit_mir_rvalue(value)?;
+                let from = real_suffix(value.ty);
+                let to = real_suffix(mir.ty);
+                if from == to {
+                    return Ok(arg);
+                }
+                let op = if to == "f64" { "fpext" } else { "fptrunc" };
+                let name = format!("{}.{}.{}", op, from, to);
+                let ret = self.emit_type(mir.ty)?;
+                Ok(self.emit_runtime_call(&name, vec![arg], ret))
+            }
+
+            mir::RvalueKind::RealUnaryArith { op, arg } => {
+                let arg = self.emit_mir_rvalue(arg)?;
+                let op = match op {
+                    mir::RealUnaryArithOp::Neg => "neg",
+                };
+                let name = format!("{}.{}", op, real_suffix(mir.ty));
+                let ret = self.emit_type(mir.ty)?;
+                Ok(self.emit_runtime_call(&name, vec![arg], ret))
+            }
+
+            mir::RvalueKind::RealBinaryArith { op, lhs, rhs } => {
+                let lhs = self.emit_mir_rvalue(lhs)?;
+                let rhs = self.emit_mir_rvalue(rhs)?;
+                let op = match op {
+                    mir::RealBinaryArithOp::Add => "add",
+                    mir::RealBinaryArithOp::Sub => "sub",
+                    mir::RealBinaryArithOp::Mul => "mul",
+                    mir::RealBinaryArithOp::Div => "div",
+                    mir::RealBinaryArithOp::Pow => "pow",
+                };
+                let name = format!("{}.{}", op, real_suffix(mir.ty));
+                let ret = self.emit_type(mir.ty)?;
+                Ok(self.emit_runtime_call(&name, vec![lhs, rhs], ret))
+            }
+
+            mir::RvalueKind::RealComp { op, lhs, rhs } => {
+                let suffix = real_suffix(lhs.ty);
+                let lhs = self.emit_mir_rvalue(lhs)?;
+                let rhs = self.emit_mir_rvalue(rhs)?;
+                let op = match op {
+                    mir::RealCompOp::Eq => "eq",
+                    mir::RealCompOp::Neq => "neq",
+                    mir::RealCompOp::Lt => "lt",
+                    mir::RealCompOp::Leq => "le",
+                    mir::RealCompOp::Gt => "gt",
+                    mir::RealCompOp::Geq => "ge",
+                };
+                let name = format!("{}.{}", op, suffix);
+                let ret = self.emit_type(mir.ty)?;
+                Ok(self.emit_runtime_call(&name, vec![lhs, rhs], ret))
+            }
+
             mir::RvalueKind::Concat(ref values) => {
                 let mut offset = 0;
                 let llty = self.emit_type(mir.ty)?;
@@ -1610,6 +2021,34 @@ where
         }
     }
 
+    /// Emit a call to a real number runtime function.
+    ///
+    /// LLHD has no floating point types or instructions. Real numbers are
+    /// carried around as the bit pattern of their IEEE 754 encoding, and all
+    /// operations on them are emitted as calls to external functions named
+    /// `@moore.real.<op>.<types>`, e.g. `@moore.real.add.f64`, which the
+    /// simulator is expected to provide. The functions are declared in the
+    /// module upon first use.
+    fn emit_runtime_call(
+        &mut self,
+        name: &str,
+        args: Vec<llhd::ir::Value>,
+        ret: llhd::Type,
+    ) -> llhd::ir::Value {
+        let mut sig = llhd::ir::Signature::new();
+        for &arg in &args {
+            sig.add_input(self.llhd_type(arg));
+        }
+        sig.set_return_type(ret);
+        let name = llhd::ir::UnitName::global(format!("moore.real.{}", name));
+        if self.tables.extern_decls.insert(name.to_string()) {
+            self.into.declare(name.clone(), sig.clone());
+        }
+        let ext_unit = self.builder.add_extern(name, sig);
+        let inst = self.builder.ins().call(ext_unit, args);
+        self.builder.inst_result(inst)
+    }
+
     fn emit_prb_or_var(&mut self, sig: llhd::ir::Value) -> llhd::ir::Value {
         match *self.llhd_type(sig) {
             llhd::SignalType(_) => {
@@ -1798,6 +2237,22 @@ where
             // signal or pointer to the respective subfield.
             mir::LvalueKind::Member { value, field } => {
                 let target = self.emit_mir_lvalue(value)?;
+
+                // All members of a packed union share the same bits, so the
+                // union itself is the member.
+                if value.ty.is_packed_union() {
+                    if !mir.ty.coalesces_to_llhd_scalar() {
+                        self.emit(
+                            DiagBuilder2::error(format!(
+                                "assignment to union member of type `{}` not supported",
+                                mir.ty
+                            ))
+                            .span(mir.span),
+                        );
+                        return Err(());
+                    }
+                    return Ok(target);
+                }
                 let value_real = self.builder.ins().ext_field(target.0, field);
                 let value_shadow = target
                     .1
@@ -1959,12 +2414,7 @@ where
     }
 
     /// Emit the code for a statement, given its HIR.
-    fn emit_stmt_regular(
-        &mut self,
-        _stmt_id: NodeId,
-        hir: &hir::Stmt,
-        env: ParamEnv,
-    ) -> Result<()> {
+    fn emit_stmt_regular(&mut self, stmt_id: NodeId, hir: &hir::Stmt, env: ParamEnv) -> Result<()> {
         debug!("Emit stmt `{}`", {
             let s = hir.span.extract();
             if s.len() > 40 {
@@ -2237,6 +2687,27 @@ where
                 };
                 self.builder.append_to(exit_blk);
             }
+            hir::StmtKind::Cover { expr, stmt } => {
+                // Without coverage, neither the condition nor the statement
+                // are emitted.
+                if self.sess().opts.coverage {
+                    let hit_blk = self.add_named_block("cover_hit");
+                    let exit_blk = self.add_named_block("cover_exit");
+                    let cond = self.emit_rvalue_bool(expr, env)?;
+                    self.builder.ins().br_cond(cond, exit_blk, hit_blk);
+                    self.builder.append_to(hit_blk);
+                    let counter = AccessedNode::Cover(stmt_id);
+                    let sig = self.emitted_value(counter);
+                    let shadow = self.shadows.get(&counter).cloned();
+                    let count = self.emit_prb_or_var(shadow.unwrap_or(sig));
+                    let one = self.builder.ins().const_int((COVER_COUNTER_WIDTH, 1));
+                    let count = self.builder.ins().add(count, one);
+                    self.emit_blocking_assign_llhd((sig, shadow), count)?;
+                    self.emit_stmt(stmt, env)?;
+                    self.builder.ins().br(exit_blk);
+                    self.builder.append_to(exit_blk);
+                }
+            }
             hir::StmtKind::InlineGroup { ref stmts, .. } => {
                 for &stmt in stmts {
                     self.emit_stmt(stmt, env)?;
@@ -2539,6 +3010,54 @@ fn emit_port_details<'gcx>(cx: &impl Context<'gcx>, hir: &hir::Module<'gcx>, env
     }
 }
 
+/// Determine the runtime function suffix for a real type.
+fn real_suffix(ty: &UnpackedType) -> &'static str {
+    match ty.get_real() {
+        Some(ty::RealType::ShortReal) => "f32",
+        _ => "f64",
+    }
+}
+
+/// Write a single line of the port map.
+fn write_port_map_line(
+    out: &mut impl std::io::Write,
+    entity: &str,
+    dir: &str,
+    index: usize,
+    port: &ModulePort,
+    llty: &llhd::Type,
+) -> std::io::Result<()> {
+    write!(
+        out,
+        "{}\t{}\t{}\t{}\t{}\t",
+        entity, dir, index, port.name, llty
+    )?;
+    match port.kind {
+        ModulePortKind::Port => writeln!(out, "port {} : {}", port.port.name, port.ty),
+        ModulePortKind::IntfSignal { intf, .. } => writeln!(
+            out,
+            "port {} : {} signal {} : {}",
+            port.port.name,
+            intf.name,
+            port.name.rsplit('.').next().unwrap_or(""),
+            port.ty
+        ),
+    }
+}
+
+/// The width of the hit counters of cover statements.
+const COVER_COUNTER_WIDTH: usize = 32;
+
+/// Determine the name of the hit counter of a cover statement.
+fn cover_counter_name<'a>(cx: &impl Context<'a>, stmt_id: NodeId) -> String {
+    match cx.hir_of(stmt_id) {
+        Ok(HirNode::Stmt(hir::Stmt {
+            label: Some(label), ..
+        })) => format!("cover.{}", label.value),
+        _ => "cover".to_string(),
+    }
+}
+
 /// Result of emitting a module.
 pub struct EmittedModule<'a> {
     /// The emitted LLHD unit.
@@ -0,0 +1,548 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Combinational loop detection.
+//!
+//! This module builds a dependency graph of the signals in a module from the
+//! MIR of its combinational processes and continuous assignments. A signal
+//! assigned by a continuous assignment depends on all signals the assignment
+//! reads. A signal assigned by a process depends on the signals read by the
+//! assignments to it, and on the conditions of the `if` and `case` statements
+//! enclosing those assignments. Signals read after the process has assigned
+//! them contribute the signals they were assigned from, such that a process
+//! may reuse a temporary, as in `t = a; x = t; t = b; y = t;`, without `y`
+//! depending on `a`. A cycle in this graph is a combinational loop, which never
+//! settles in simulation.
+//!
+//! A process which does not assign a signal on every path keeps the signal's
+//! previous value. This self-dependency is a latch rather than a loop, and is
+//! only considered for continuous assignments like `assign x = x + 1`.
+
+use crate::{
+    crate_prelude::*,
+    hir::{HirNode, LoopKind, StmtKind, TimingControl},
+    mir::WalkVisitor as _,
+    ParamEnv,
+};
+use moore_common::lints;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    sync::Arc,
+};
+
+/// A combinational loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CombLoop {
+    /// The signals along the loop. Each signal drives the next one, and the
+    /// last signal drives the first one.
+    pub signals: Vec<NodeId>,
+    /// The assignment through which each signal drives the next one.
+    pub drivers: Vec<NodeId>,
+}
+
+/// Find the combinational loops in a module.
+///
+/// Reports one loop for every group of signals which depend on each other.
+/// Only the processes and assignments directly within the module body are
+/// considered; generate blocks and paths through instances are skipped.
+#[moore_derive::query]
+pub(crate) fn comb_loops<'a>(
+    cx: &impl Context<'a>,
+    node_id: NodeId,
+    env: ParamEnv,
+) -> Result<Arc<Vec<CombLoop>>> {
+    let hir = match cx.hir_of(node_id)? {
+        HirNode::Module(x) => x,
+        x => bug_span!(x.span(), cx, "comb_loops called on {}", x.desc_full()),
+    };
+
+    // Build the dependency graph, mapping each signal to the signals it drives
+    // and the assignment through which it does so.
+    let mut graph: BTreeMap<NodeId, BTreeMap<NodeId, NodeId>> = BTreeMap::new();
+    for &proc_id in &hir.block.procs {
+        let prok = match cx.hir_of(proc_id)? {
+            HirNode::Proc(x) => x,
+            _ => unreachable!(),
+        };
+        if !is_combinational(cx, prok)? {
+            continue;
+        }
+        let mut state = Deps::new();
+        ProcDeps { cx, env }.stmt(prok.stmt, &mut state, &BTreeSet::new())?;
+        for (signal, inputs) in state {
+            // Variables declared within the process are not visible outside
+            // of it.
+            if cx.is_parent_of(proc_id, signal) {
+                continue;
+            }
+            for (input, driver) in inputs {
+                if input != signal && !cx.is_parent_of(proc_id, input) {
+                    graph
+                        .entry(input)
+                        .or_default()
+                        .entry(signal)
+                        .or_insert(driver);
+                }
+            }
+        }
+    }
+    for &assign_id in &hir.block.assigns {
+        let assign = match cx.hir_of(assign_id)? {
+            HirNode::Assign(x) => x,
+            _ => unreachable!(),
+        };
+        let mut access = Access::default();
+        cx.mir_lvalue(assign.lhs, env).walk(&mut access);
+        cx.mir_rvalue(assign.rhs, env).walk(&mut access);
+        for &from in &access.read {
+            for &to in &access.written {
+                graph
+                    .entry(from)
+                    .or_default()
+                    .entry(to)
+                    .or_insert(assign_id);
+            }
+        }
+    }
+
+    // Find one loop in each strongly connected component of the graph.
+    let mut loops = vec![];
+    for scc in strongly_connected(&graph) {
+        let start = scc[0];
+        if scc.len() == 1 && !graph.get(&start).map_or(false, |e| e.contains_key(&start)) {
+            continue;
+        }
+
+        // Search for the shortest path from the first signal back to itself.
+        let mut pred: HashMap<NodeId, NodeId> = HashMap::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+        'search: while let Some(from) = queue.pop_front() {
+            for &to in graph[&from].keys() {
+                if !scc.contains(&to) || pred.contains_key(&to) {
+                    continue;
+                }
+                pred.insert(to, from);
+                if to == start {
+                    break 'search;
+                }
+                queue.push_back(to);
+            }
+        }
+        let mut signals = vec![];
+        let mut at = start;
+        loop {
+            at = pred[&at];
+            signals.push(at);
+            if at == start {
+                break;
+            }
+        }
+        signals.reverse();
+        let drivers = signals
+            .iter()
+            .zip(signals.iter().cycle().skip(1))
+            .map(|(from, to)| graph[from][to])
+            .collect();
+        loops.push(CombLoop { signals, drivers });
+    }
+    Ok(Arc::new(loops))
+}
+
+/// The signals each signal assigned by a process depends on, and the
+/// assignment through which it depends on each of them.
+///
+/// A signal the process has not assigned yet is absent. A signal which keeps
+/// its previous value on some paths depends on itself.
+type Deps = BTreeMap<NodeId, BTreeMap<NodeId, NodeId>>;
+
+/// A helper to determine the dependencies of the signals assigned by a
+/// process.
+struct ProcDeps<'c, C> {
+    cx: &'c C,
+    env: ParamEnv,
+}
+
+impl<'a, 'c, C: Context<'a>> ProcDeps<'c, C> {
+    /// Update the dependencies with the assignments of a statement.
+    ///
+    /// `conds` are the signals the enclosing conditions depend on.
+    fn stmt(&self, stmt_id: NodeId, state: &mut Deps, conds: &BTreeSet<NodeId>) -> Result<()> {
+        let stmt = match self.cx.hir_of(stmt_id)? {
+            HirNode::Stmt(x) => x,
+            HirNode::VarDecl(decl) => {
+                if let Some(init) = decl.init {
+                    let mut access = Access::default();
+                    self.cx.mir_rvalue(init, self.env).walk(&mut access);
+                    let inputs = self.inputs(state, &access.read, conds);
+                    assign(state, decl.id, stmt_id, &inputs, true);
+                }
+                return Ok(());
+            }
+            _ => return Ok(()),
+        };
+        match stmt.kind {
+            StmtKind::Assign { lhs, rhs, kind } => {
+                let lvalue = self.cx.mir_lvalue(lhs, self.env);
+                let mut access = Access::default();
+                lvalue.walk(&mut access);
+                self.cx.mir_rvalue(rhs, self.env).walk(&mut access);
+                // Operators like `+=` also read the assigned signal.
+                if let hir::AssignKind::Block(op) = kind {
+                    if op != ast::AssignOp::Identity {
+                        access.read.extend(access.written.iter().cloned());
+                    }
+                }
+                let whole = match lvalue.kind {
+                    mir::LvalueKind::Var(_) | mir::LvalueKind::Port(_) => true,
+                    _ => false,
+                };
+                let inputs = self.inputs(state, &access.read, conds);
+                for &signal in &access.written {
+                    assign(state, signal, stmt_id, &inputs, whole);
+                }
+            }
+            StmtKind::Expr(expr) => {
+                let mut access = Access::default();
+                self.cx.mir_rvalue(expr, self.env).walk(&mut access);
+                let inputs = self.inputs(state, &access.read, conds);
+                for &signal in &access.written {
+                    assign(state, signal, stmt_id, &inputs, false);
+                }
+            }
+            StmtKind::Block(ref stmts) | StmtKind::InlineGroup { ref stmts, .. } => {
+                for &stmt in stmts {
+                    self.stmt(stmt, state, conds)?;
+                }
+            }
+            StmtKind::Timed { stmt, .. } | StmtKind::Cover { stmt, .. } => {
+                self.stmt(stmt, state, conds)?
+            }
+            StmtKind::If {
+                cond,
+                main_stmt,
+                else_stmt,
+            } => {
+                let conds = self.inputs(state, &self.reads(cond), conds);
+                let mut main = state.clone();
+                self.stmt(main_stmt, &mut main, &conds)?;
+                let mut other = state.clone();
+                if let Some(else_stmt) = else_stmt {
+                    self.stmt(else_stmt, &mut other, &conds)?;
+                }
+                *state = merge(vec![main, other], stmt_id);
+            }
+            StmtKind::Case {
+                expr,
+                ref ways,
+                default,
+                ..
+            } => {
+                let mut reads = self.reads(expr);
+                for &label in ways.iter().flat_map(|(labels, _)| labels) {
+                    reads.extend(self.reads(label));
+                }
+                let conds = self.inputs(state, &reads, conds);
+                let mut branches = vec![];
+                for &stmt in ways.iter().map(|(_, stmt)| stmt).chain(default.iter()) {
+                    let mut branch = state.clone();
+                    self.stmt(stmt, &mut branch, &conds)?;
+                    branches.push(branch);
+                }
+                if default.is_none() {
+                    branches.push(state.clone());
+                }
+                *state = merge(branches, stmt_id);
+            }
+            StmtKind::Loop { kind, body } => {
+                let (cond, step) = match kind {
+                    LoopKind::Forever => (None, None),
+                    LoopKind::Repeat(cond) | LoopKind::While(cond) | LoopKind::Do(cond) => {
+                        (Some(cond), None)
+                    }
+                    LoopKind::For(init, cond, step) => {
+                        self.stmt(init, state, conds)?;
+                        (Some(cond), Some(step))
+                    }
+                };
+                // Iterate until the signals carried from one iteration to the
+                // next stop gaining dependencies.
+                loop {
+                    let reads = cond.map(|cond| self.reads(cond)).unwrap_or_default();
+                    let conds = self.inputs(state, &reads, conds);
+                    let mut next = state.clone();
+                    self.stmt(body, &mut next, &conds)?;
+                    if let Some(step) = step {
+                        let mut access = Access::default();
+                        self.cx.mir_rvalue(step, self.env).walk(&mut access);
+                        let inputs = self.inputs(&next, &access.read, &conds);
+                        for &signal in &access.written {
+                            assign(&mut next, signal, step, &inputs, false);
+                        }
+                    }
+                    let next = merge(vec![state.clone(), next], stmt_id);
+                    if next == *state {
+                        break;
+                    }
+                    *state = next;
+                }
+            }
+            StmtKind::Null => (),
+        }
+        Ok(())
+    }
+
+    /// Determine the signals an expression reads.
+    fn reads(&self, expr_id: NodeId) -> BTreeSet<NodeId> {
+        let mut access = Access::default();
+        self.cx.mir_rvalue(expr_id, self.env).walk(&mut access);
+        access.read
+    }
+
+    /// Determine the signals a value depends on if it is computed from a set
+    /// of signals under a set of conditions.
+    ///
+    /// Signals which the process has assigned already are replaced by the
+    /// signals they depend on.
+    fn inputs(
+        &self,
+        state: &Deps,
+        reads: &BTreeSet<NodeId>,
+        conds: &BTreeSet<NodeId>,
+    ) -> BTreeSet<NodeId> {
+        let mut inputs = conds.clone();
+        for &read in reads {
+            match state.get(&read) {
+                Some(deps) => inputs.extend(deps.keys().cloned()),
+                None => {
+                    inputs.insert(read);
+                }
+            }
+        }
+        inputs
+    }
+}
+
+/// Record an assignment to a signal.
+///
+/// If the assignment only covers part of the signal, the signal keeps the
+/// dependencies of the other parts.
+fn assign(
+    state: &mut Deps,
+    signal: NodeId,
+    driver: NodeId,
+    inputs: &BTreeSet<NodeId>,
+    whole: bool,
+) {
+    let mut deps = if whole {
+        BTreeMap::new()
+    } else {
+        state.get(&signal).cloned().unwrap_or_else(|| {
+            let mut deps = BTreeMap::new();
+            deps.insert(signal, driver);
+            deps
+        })
+    };
+    for &input in inputs {
+        deps.entry(input).or_insert(driver);
+    }
+    state.insert(signal, deps);
+}
+
+/// Merge the dependencies of alternative paths through a statement.
+///
+/// A signal which is not assigned on one of the paths keeps its previous value
+/// there, and thus depends on itself.
+fn merge(branches: Vec<Deps>, stmt_id: NodeId) -> Deps {
+    let signals: BTreeSet<NodeId> = branches.iter().flat_map(|b| b.keys().cloned()).collect();
+    let mut merged = Deps::new();
+    for signal in signals {
+        let deps = merged.entry(signal).or_insert_with(BTreeMap::new);
+        for branch in &branches {
+            match branch.get(&signal) {
+                Some(branch_deps) => {
+                    for (&input, &driver) in branch_deps {
+                        deps.entry(input).or_insert(driver);
+                    }
+                }
+                None => {
+                    deps.entry(signal).or_insert(stmt_id);
+                }
+            }
+        }
+    }
+    merged
+}
+
+/// A visitor that collects the signals read and written by an MIR value.
+#[derive(Default)]
+struct Access {
+    read: BTreeSet<NodeId>,
+    written: BTreeSet<NodeId>,
+}
+
+impl<'a> mir::Visitor<'a> for Access {
+    fn pre_visit_lvalue(&mut self, mir: &mir::Lvalue) -> bool {
+        match mir.kind {
+            mir::LvalueKind::Var(id) | mir::LvalueKind::Port(id) => {
+                self.written.insert(id);
+                false
+            }
+            _ => true,
+        }
+    }
+
+    fn pre_visit_rvalue(&mut self, mir: &mir::Rvalue) -> bool {
+        match mir.kind {
+            mir::RvalueKind::Var(id) | mir::RvalueKind::Port(id) => {
+                self.read.insert(id);
+                false
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Report the combinational loops in a module.
+pub fn check_comb_loops<'a>(cx: &impl Context<'a>, node_id: NodeId, env: ParamEnv) {
+    let loops = match cx.comb_loops(node_id, env) {
+        Ok(x) => x,
+        Err(()) => return,
+    };
+    for comb_loop in loops.iter() {
+        let names: Vec<_> = match comb_loop
+            .signals
+            .iter()
+            .map(|&id| signal_name(cx, id))
+            .collect::<Result<_>>()
+        {
+            Ok(x) => x,
+            Err(()) => continue,
+        };
+        let mut path: Vec<_> = names
+            .iter()
+            .map(|name| format!("`{}`", name.value))
+            .collect();
+        path.push(path[0].clone());
+        let mut d = DiagBuilder2::lint(
+            lints::COMB_LOOP,
+            format!("combinational loop: {}", path.join(" -> ")),
+        )
+        .span(names[0].span);
+        for (i, &driver) in comb_loop.drivers.iter().enumerate() {
+            d = d
+                .add_note(format!(
+                    "`{}` drives `{}` here:",
+                    names[i].value,
+                    names[(i + 1) % names.len()].value
+                ))
+                .span(cx.span(driver));
+        }
+        cx.emit(d);
+    }
+}
+
+/// Check whether a process is combinational, i.e. an `always_comb` process or
+/// an `always` process which is triggered by any change of its inputs.
+pub(crate) fn is_combinational<'a>(cx: &impl Context<'a>, prok: &hir::Proc) -> Result<bool> {
+    Ok(match prok.kind {
+        ast::ProcedureKind::AlwaysComb => true,
+        ast::ProcedureKind::Always => match cx.hir_of(prok.stmt)? {
+            HirNode::Stmt(hir::Stmt {
+                kind:
+                    StmtKind::Timed {
+                        control: TimingControl::ImplicitEvent,
+                        ..
+                    },
+                ..
+            }) => true,
+            HirNode::Stmt(hir::Stmt {
+                kind:
+                    StmtKind::Timed {
+                        control: TimingControl::ExplicitEvent(event_id),
+                        ..
+                    },
+                ..
+            }) => match cx.hir_of(*event_id)? {
+                HirNode::EventExpr(event) => event
+                    .events
+                    .iter()
+                    .all(|e| e.edge == ast::EdgeIdent::Implicit && e.iff.is_empty()),
+                _ => false,
+            },
+            _ => false,
+        },
+        _ => false,
+    })
+}
+
+/// Determine the name of a signal, and where it is declared.
+pub(crate) fn signal_name<'a>(cx: &impl Context<'a>, id: NodeId) -> Result<Spanned<Name>> {
+    Ok(match cx.hir_of(id)? {
+        HirNode::VarDecl(x) => x.name,
+        HirNode::IntPort(x) => x.name,
+        x => bug_span!(x.span(), cx, "{} is not a signal", x.desc_full()),
+    })
+}
+
+/// Find the strongly connected components of a graph.
+///
+/// Returns the components in a deterministic order, each sorted by node.
+fn strongly_connected<T>(graph: &BTreeMap<NodeId, BTreeMap<NodeId, T>>) -> Vec<Vec<NodeId>> {
+    struct Tarjan<'g, T> {
+        graph: &'g BTreeMap<NodeId, BTreeMap<NodeId, T>>,
+        index: HashMap<NodeId, usize>,
+        lowlink: HashMap<NodeId, usize>,
+        stack: Vec<NodeId>,
+        on_stack: BTreeSet<NodeId>,
+        sccs: Vec<Vec<NodeId>>,
+    }
+
+    impl<'g, T> Tarjan<'g, T> {
+        fn visit(&mut self, node: NodeId) {
+            let index = self.index.len();
+            self.index.insert(node, index);
+            self.lowlink.insert(node, index);
+            self.stack.push(node);
+            self.on_stack.insert(node);
+            for &next in self.graph.get(&node).into_iter().flat_map(|e| e.keys()) {
+                if !self.index.contains_key(&next) {
+                    self.visit(next);
+                    let low = self.lowlink[&node].min(self.lowlink[&next]);
+                    self.lowlink.insert(node, low);
+                } else if self.on_stack.contains(&next) {
+                    let low = self.lowlink[&node].min(self.index[&next]);
+                    self.lowlink.insert(node, low);
+                }
+            }
+            if self.lowlink[&node] == self.index[&node] {
+                let mut scc = vec![];
+                loop {
+                    let n = self.stack.pop().unwrap();
+                    self.on_stack.remove(&n);
+                    scc.push(n);
+                    if n == node {
+                        break;
+                    }
+                }
+                scc.sort();
+                self.sccs.push(scc);
+            }
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        graph,
+        index: HashMap::new(),
+        lowlink: HashMap::new(),
+        stack: vec![],
+        on_stack: BTreeSet::new(),
+        sccs: vec![],
+    };
+    for &node in graph.keys() {
+        if !tarjan.index.contains_key(&node) {
+            tarjan.visit(node);
+        }
+    }
+    tarjan.sccs.sort();
+    tarjan.sccs
+}
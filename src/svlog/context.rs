@@ -21,21 +21,23 @@
 //! ```
 
 use crate::crate_prelude::*;
-use crate::salsa; // TODO(fschuiki): Remove this once salsa is regular dep again
 use crate::{
     ast::{self, Visitor},
     ast_map::{AstMap, AstNode},
-    common::{arenas::Alloc, arenas::TypedArena, Session},
+    common::{
+        arenas::{Alloc, AllocStats, TypedArena},
+        source::Source,
+        Session,
+    },
     hir::{self, HirNode},
     port_list::PortList,
-    resolver::{Scope, StructDef},
+    resolver::{AsScopedNode, Scope},
     value::{Value, ValueData, ValueKind},
-    ParamEnv, ParamEnvData, ParamEnvSource, QueryDatabase, QueryStorage,
+    ParamEnv, ParamEnvData, QueryDatabase, QueryStorage, QueryTag,
 };
 use std::{
     cell::RefCell,
     collections::{BTreeSet, HashMap, HashSet},
-    sync::Arc,
 };
 
 /// The central data structure of the compiler. It stores references to various
@@ -46,9 +48,7 @@ pub struct GlobalContext<'gcx> {
     pub sess: &'gcx Session,
     /// The arena that owns all references.
     pub arena: &'gcx GlobalArenas<'gcx>,
-    /// The underlying runtime for the query system.
-    runtime: salsa::Runtime<GlobalContext<'gcx>>,
-    /// The underlying storage for the new query system.
+    /// The underlying storage for the query system.
     storage: QueryStorage<'gcx>,
     /// The mapping of node IDs to abstract syntax tree nodes.
     ast_map: AstMap<'gcx>,
@@ -62,6 +62,8 @@ pub struct GlobalContext<'gcx> {
     interfaces: RefCell<HashMap<Name, NodeId>>,
     /// The global imports in the AST.
     imports: RefCell<Vec<NodeId>>,
+    /// The source files in the AST.
+    files: RefCell<Vec<&'gcx ast::SourceFile<'gcx>>>,
     /// A mapping from node ids to spans for diagnostics.
     node_id_to_span: RefCell<HashMap<NodeId, Span>>,
     /// The tables.
@@ -74,7 +76,6 @@ impl<'gcx> GlobalContext<'gcx> {
         GlobalContext {
             sess,
             arena,
-            runtime: Default::default(),
             storage: Default::default(),
             ast_map: Default::default(),
             ast_map2: Default::default(),
@@ -82,6 +83,7 @@ impl<'gcx> GlobalContext<'gcx> {
             packages: Default::default(),
             interfaces: Default::default(),
             imports: Default::default(),
+            files: Default::default(),
             node_id_to_span: Default::default(),
             tables: Default::default(),
         }
@@ -97,7 +99,9 @@ impl<'gcx> GlobalContext<'gcx> {
         root.link(None, &mut index);
         debug!("Linked {} nodes", index);
 
-        // Ensure there are no naming conflicts in the scopes.
+        // Ensure there are no naming conflicts in the scopes. The scopes
+        // within modules are only built once a module is needed; see
+        // `check_module_names`.
         debug!("Materializing scopes");
         crate::resolver::materialize_scope(self, root);
 
@@ -107,36 +111,111 @@ impl<'gcx> GlobalContext<'gcx> {
 
         // Resolve names for debugging purposes.
         debug!("Checking names");
-        self.nameck(root);
+        for file in &root.files {
+            self.check_item_names(file);
+        }
 
         // Keep track of some names for now.
         for file in &root.files {
-            for item in &file.items {
-                match &item.data {
-                    ast::ItemData::ModuleDecl(ref n) => {
-                        let id = self.map_ast(AstNode::Module(n));
-                        self.modules.borrow_mut().insert(n.name.value, id);
-                    }
-                    ast::ItemData::PackageDecl(ref n) => {
-                        let id = self.map_ast(AstNode::Package(n));
-                        self.packages.borrow_mut().insert(n.name.value, id);
-                    }
-                    ast::ItemData::InterfaceDecl(ref n) => {
-                        let id = self.map_ast(AstNode::Interface(n));
-                        self.interfaces.borrow_mut().insert(n.name.value, id);
-                    }
-                    ast::ItemData::ImportDecl(ref n) => {
-                        for item in &n.items {
-                            let id = self.map_ast(AstNode::Import(item));
-                            self.imports.borrow_mut().push(id);
-                        }
-                    }
-                    _ => (),
+            self.add_items(file);
+        }
+        self.files.borrow_mut().extend(root.files.iter().cloned());
+
+        // Queries that looked at the items of the design need to reconsider
+        // them in the light of the newly added ones.
+        self.invalidate_input(QueryInput::Items);
+
+        debug!("{:?} added", root);
+    }
+
+    /// Replace a source file with a new version of it.
+    ///
+    /// The new file takes the place of the one parsed from `old` and is linked
+    /// into a new AST root together with the other files. Only the queries
+    /// that looked at the old file, at the order of the files that follow it,
+    /// or at the items of the design are recomputed. All other results remain
+    /// cached.
+    pub fn update_file(&self, old: Source, file: &'gcx ast::SourceFile<'gcx>) {
+        debug!("Updating {:?} to {:?}", old, file);
+        let mut files = self.files.borrow().clone();
+        match files.iter().position(|f| f.span.source == old) {
+            Some(index) => files[index] = file,
+            None => files.push(file),
+        }
+        let orders: Vec<_> = files.iter().map(|f| f.order()).collect();
+        let root = ast::Root::new(
+            moore_common::source::INVALID_SPAN,
+            ast::RootData {
+                files: files.clone(),
+            },
+        );
+        let root = self.arena.alloc_ast_root(root);
+        root.link(None, &mut 0);
+
+        // Forget the items declared in the old file, and add the new ones.
+        let keep = |id: &NodeId| self.span(*id).source != old;
+        self.modules.borrow_mut().retain(|_, id| keep(id));
+        self.packages.borrow_mut().retain(|_, id| keep(id));
+        self.interfaces.borrow_mut().retain(|_, id| keep(id));
+        self.imports.borrow_mut().retain(keep);
+        self.add_items(file);
+        *self.files.borrow_mut() = files.clone();
+
+        // Discard the results that depend on the old file, or on the lexical
+        // order of a file that has shifted in the new root.
+        self.invalidate_input(QueryInput::Source(old));
+        for (f, order) in files.iter().zip(orders) {
+            if !std::ptr::eq(*f, file) && f.order() != order {
+                self.invalidate_input(QueryInput::Source(f.span.source));
+            }
+        }
+        self.invalidate_input(QueryInput::Items);
+
+        crate::resolver::materialize_scope(self, root);
+        self.register_ast(file);
+        self.check_item_names(file);
+    }
+
+    /// Resolve the names in the items of a source file, except for modules,
+    /// which are only checked once they are known to be needed; see
+    /// `check_module_names`.
+    fn check_item_names(&self, file: &'gcx ast::SourceFile<'gcx>) {
+        for item in file.items {
+            match &item.data {
+                ast::ItemData::ModuleDecl(..) => (),
+                _ => {
+                    self.nameck(item);
                 }
             }
         }
+    }
 
-        debug!("{:?} added", root);
+    /// Keep track of the modules, packages, interfaces, and imports declared
+    /// in a source file.
+    fn add_items(&self, file: &'gcx ast::SourceFile<'gcx>) {
+        for item in file.items {
+            match &item.data {
+                ast::ItemData::ModuleDecl(ref n) => {
+                    let id = self.map_ast(AstNode::Module(n));
+                    self.modules.borrow_mut().insert(n.name.value, id);
+                }
+                ast::ItemData::PackageDecl(ref n) => {
+                    let id = self.map_ast(AstNode::Package(n));
+                    self.packages.borrow_mut().insert(n.name.value, id);
+                }
+                ast::ItemData::InterfaceDecl(ref n) => {
+                    let id = self.map_ast(AstNode::Interface(n));
+                    self.interfaces.borrow_mut().insert(n.name.value, id);
+                }
+                ast::ItemData::ImportDecl(ref n) => {
+                    for item in &n.items {
+                        let id = self.map_ast(AstNode::Import(item));
+                        self.imports.borrow_mut().push(id);
+                    }
+                }
+                _ => (),
+            }
+        }
     }
 
     /// Add an AST root with a series of source files to the context for
@@ -152,23 +231,49 @@ impl<'gcx> GlobalContext<'gcx> {
         self.add_root(root);
     }
 
+    /// Get the mapping from node IDs to AST nodes.
+    pub fn ast_map(&self) -> &AstMap<'gcx> {
+        &self.ast_map
+    }
+
     /// Find a module in the AST.
     pub fn find_module(&self, name: Name) -> Option<NodeId> {
+        self.read_input(QueryInput::Items);
         self.modules.borrow().get(&name).cloned()
     }
 
     /// Get an iterator over all modules in the AST.
     pub fn modules(&self) -> impl Iterator<Item = (Name, NodeId)> {
+        self.read_input(QueryInput::Items);
         self.modules.borrow().clone().into_iter()
     }
 
+    /// Check name resolution within a module.
+    ///
+    /// Modules are neither scoped nor checked when they are added to the
+    /// context, such that modules which are never instantiated, e.g. the bulk
+    /// of a library searched with `-y`, do not incur any cost. Call this
+    /// function on every module that is elaborated before lowering it.
+    /// Returns `false` if any names could not be resolved.
+    pub fn check_module_names(&self, id: NodeId) -> bool {
+        match self.ast_of(id) {
+            Ok(AstNode::Module(x)) => {
+                crate::resolver::materialize_scope(self, x);
+                self.nameck(x)
+            }
+            _ => true,
+        }
+    }
+
     /// Find a package in the AST.
     pub fn find_package(&self, name: Name) -> Option<NodeId> {
+        self.read_input(QueryInput::Items);
         self.packages.borrow().get(&name).cloned()
     }
 
     /// Get an iterator over all root imports in the AST.
     pub fn imports(&self) -> impl Iterator<Item = NodeId> {
+        self.read_input(QueryInput::Items);
         self.imports.borrow().clone().into_iter()
     }
 }
@@ -181,31 +286,41 @@ impl DiagEmitter for GlobalContext<'_> {
         // the query stack it occurred.
         if sev >= Severity::Error {
             for query in self.storage().stack.borrow().iter().rev() {
-                match query {
-                    QueryTag::TypeOfExpr(query) => {
-                        diag = diag
-                            .add_note("Needed to compute the type of the expression:")
-                            .span(query.0.span());
-                    }
-                    QueryTag::TypeOfIntPort(query) => {
-                        diag = diag
-                            .add_note(format!(
-                                "Needed to compute the type of port `{}`:",
-                                query.0.name
-                            ))
-                            .span(query.0.span);
-                    }
-                    QueryTag::TypeOfVarDecl(query) => {
-                        diag = diag
-                            .add_note(format!(
-                                "Needed to compute the type of variable `{}`:",
-                                query.0.name
-                            ))
-                            .span(query.0.span);
-                    }
-                    _ => (),
+                if let Some((note, span)) = describe_query(query) {
+                    diag = diag.add_note(note).span(span);
                 }
             }
+
+            // Trace the instantiations that led to the error, such that the
+            // faulty parametrization can be identified.
+            for &inst_id in self.tables.inst_stack.borrow().iter().rev() {
+                let inst = match self.hir_of(inst_id) {
+                    Ok(HirNode::Inst(x)) => x,
+                    _ => continue,
+                };
+                let target = match self.hir_of(inst.target) {
+                    Ok(HirNode::InstTarget(x)) => x,
+                    _ => continue,
+                };
+                let params: Vec<_> = target
+                    .pos_params
+                    .iter()
+                    .map(|p| p.0)
+                    .chain(target.named_params.iter().map(|p| p.0))
+                    .map(|sp| sp.extract())
+                    .collect();
+                let params = if params.is_empty() {
+                    String::new()
+                } else {
+                    format!(" #({})", params.join(", "))
+                };
+                diag = diag
+                    .add_note(format!(
+                        "Required by instance `{}` of `{}{}`:",
+                        inst.name, target.target, params
+                    ))
+                    .span(inst.name.span);
+            }
         }
 
         // Emit the diagnostic.
@@ -226,9 +341,199 @@ impl DiagEmitter for GlobalContext<'_> {
     }
 }
 
-impl<'gcx> salsa::Database for GlobalContext<'gcx> {
-    fn salsa_runtime(&self) -> &salsa::Runtime<Self> {
-        &self.runtime
+impl Drop for GlobalContext<'_> {
+    fn drop(&mut self) {
+        // Preserve the queries and instances being processed for the crash
+        // report if the compiler panicked. Only data that is readily available
+        // is used, since another panic would abort the process.
+        if !std::thread::panicking() {
+            return;
+        }
+        let mut context = self.sess.crash_context.borrow_mut();
+        if let Ok(stack) = self.storage.stack.try_borrow() {
+            for query in stack.iter().rev() {
+                context.queries.push(format!("{:?}", query));
+                if let Some((note, span)) = describe_query(query) {
+                    context.spans.push((note, span));
+                }
+            }
+        }
+        if let (Ok(insts), Ok(spans)) = (
+            self.tables.inst_stack.try_borrow(),
+            self.node_id_to_span.try_borrow(),
+        ) {
+            for id in insts.iter().rev() {
+                if let Some(&span) = spans.get(id) {
+                    context
+                        .spans
+                        .push(("While elaborating the instance at".to_string(), span));
+                }
+            }
+        }
+    }
+}
+
+/// Find the closest parent of a node whose AST node satisfies a predicate.
+fn find_ancestor<'gcx, C>(
+    cx: &C,
+    node_id: NodeId,
+    predicate: impl Fn(AstNode<'gcx>) -> bool,
+) -> Option<NodeId>
+where
+    C: BaseContext<'gcx> + ?Sized,
+{
+    let mut next = cx.parent_node_id(node_id);
+    while let Some(id) = next {
+        if cx.get_ast(id).map(&predicate).unwrap_or(false) {
+            return Some(id);
+        }
+        next = cx.parent_node_id(id);
+    }
+    None
+}
+
+/// Describe why a query in the query stack was needed, if it refers to a
+/// location in the source code.
+fn describe_query(query: &QueryTag) -> Option<(String, Span)> {
+    match query {
+        QueryTag::TypeOfExpr(query) => Some((
+            "Needed to compute the type of the expression:".to_string(),
+            query.0.span(),
+        )),
+        QueryTag::TypeOfIntPort(query) => Some((
+            format!("Needed to compute the type of port `{}`:", query.0.name),
+            query.0.span,
+        )),
+        QueryTag::TypeOfVarDecl(query) => Some((
+            format!("Needed to compute the type of variable `{}`:", query.0.name),
+            query.0.span,
+        )),
+        _ => None,
+    }
+}
+
+/// Find the declaration that a query in a dependency cycle computes the type or
+/// value of, if any.
+fn query_decl<'gcx>(
+    cx: &GlobalContext<'gcx>,
+    query: &QueryTag<'gcx>,
+) -> Option<(NodeId, Spanned<Name>)> {
+    let id = match query {
+        QueryTag::HirOf(query) => query.0,
+        QueryTag::TypeOf(query) => query.0,
+        QueryTag::ConstantValueOf(query) => query.0,
+        QueryTag::MapToType(query) => match (query.0).as_all() {
+            ast::AllNode::ParamTypeDecl(decl) => return Some((decl.id(), decl.name)),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let name = match cx.ast_map.get(id)? {
+        AstNode::ValueParam(_, decl) => decl.name,
+        AstNode::TypeParam(_, decl) => decl.name,
+        AstNode::VarDecl(name, ..) | AstNode::NetDecl(name, ..) => {
+            Spanned::new(name.name, name.name_span)
+        }
+        _ => return None,
+    };
+    Some((id, name))
+}
+
+/// Describe a dependency cycle in terms of the declarations it passes through.
+///
+/// Each declaration is listed together with the place where it refers to the
+/// next one. Returns `None` if the cycle does not involve any declarations.
+fn cycle_decls<'gcx>(cx: &GlobalContext<'gcx>, cycle: &[QueryTag<'gcx>]) -> Option<DiagBuilder2> {
+    // Rotate the cycle such that it starts and ends at a declaration.
+    let cycle = &cycle[..cycle.len() - 1];
+    let first = cycle.iter().position(|q| query_decl(cx, q).is_some())?;
+    let rotated: Vec<_> = cycle[first..].iter().chain(&cycle[..=first]).collect();
+
+    let (first_id, first_name) = query_decl(cx, rotated[0])?;
+    let mut diag = DiagBuilder2::fatal(format!(
+        "cyclic dependency: `{}` depends on itself",
+        first_name.value
+    ))
+    .span(first_name.span);
+    let mut prev = (first_id, first_name.value);
+    for (i, query) in rotated.iter().enumerate().skip(1) {
+        let (id, name) = match query_decl(cx, query) {
+            Some(x) => x,
+            None => continue,
+        };
+        let completes = i == rotated.len() - 1;
+        if id == prev.0 && !completes {
+            continue;
+        }
+        diag = diag.add_note(if id == prev.0 {
+            format!("`{}` refers to itself here:", name.value)
+        } else {
+            format!(
+                "`{}` refers to `{}` here{}",
+                prev.1,
+                name.value,
+                if completes {
+                    ", completing the cycle:"
+                } else {
+                    ":"
+                }
+            )
+        });
+        if let Some(span) = query_span(cx, rotated[i - 1]) {
+            diag = diag.span(span);
+        }
+        prev = (id, name.value);
+    }
+    Some(diag)
+}
+
+/// Describe a dependency cycle in terms of the queries it consists of, outermost
+/// first, such that the user can follow the chain of dependencies.
+fn cycle_queries<'gcx>(cx: &GlobalContext<'gcx>, cycle: &[QueryTag<'gcx>]) -> DiagBuilder2 {
+    let repeated = &cycle[0];
+    let mut diag = DiagBuilder2::fatal(format!(
+        "cyclic dependency: `{}` depends on itself",
+        repeated.name()
+    ));
+    if let Some(span) = query_span(cx, repeated) {
+        diag = diag.span(span);
+    }
+    for pair in cycle.windows(2) {
+        let completes = pair[1] == *repeated;
+        diag = diag.add_note(format!(
+            "`{}` requires `{}`{}",
+            pair[0].name(),
+            pair[1].name(),
+            if completes {
+                ", completing the cycle"
+            } else {
+                ":"
+            }
+        ));
+        if !completes {
+            if let Some(span) = query_span(cx, &pair[1]) {
+                diag = diag.span(span);
+            }
+        }
+    }
+    diag
+}
+
+/// Find the location in the source code that a query in a dependency cycle
+/// refers to, if any.
+fn query_span<'gcx>(cx: &GlobalContext<'gcx>, query: &QueryTag<'gcx>) -> Option<Span> {
+    match query {
+        QueryTag::HirOf(query) => Some(cx.span(query.0)),
+        QueryTag::TypeOf(query) => Some(cx.span(query.0)),
+        QueryTag::CastType(query) => Some(cx.span(query.0)),
+        QueryTag::SelfDeterminedType(query) => Some(cx.span(query.0)),
+        QueryTag::ConstantValueOf(query) => Some(cx.span(query.0)),
+        QueryTag::MirLvalue(query) => Some(cx.span(query.0)),
+        QueryTag::MirRvalue(query) => Some(cx.span(query.0)),
+        QueryTag::MapToType(query) => Some(query.0.span()),
+        QueryTag::PackedTypeFromAst(query) => Some(query.0.span()),
+        QueryTag::UnpackedTypeFromAst(query) => Some(query.0.span()),
+        _ => describe_query(query).map(|(_, span)| span),
     }
 }
 
@@ -238,6 +543,19 @@ impl<'gcx> BaseContext<'gcx> for GlobalContext<'gcx> {
     }
 }
 
+/// An input of the compiler queries.
+///
+/// Queries record the inputs they read, such that a change to an input only
+/// discards the cached results of the queries that depend on it. See
+/// `QueryDatabase::invalidate_input`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QueryInput {
+    /// The AST nodes parsed from a source file, when looked up by node id.
+    Source(Source),
+    /// The modules, interfaces, packages, and root imports of the design.
+    Items,
+}
+
 impl<'gcx> QueryDatabase<'gcx> for GlobalContext<'gcx> {
     type Context = Self;
 
@@ -248,6 +566,40 @@ impl<'gcx> QueryDatabase<'gcx> for GlobalContext<'gcx> {
     fn storage(&self) -> &QueryStorage<'gcx> {
         &self.storage
     }
+
+    fn handle_cycle(&self) -> ! {
+        // The query that closed the cycle has just been pushed onto the stack.
+        let diag = {
+            let stack = self.storage.stack.borrow();
+            let repeated = stack.last().expect("cycle without queries");
+            let start = stack.iter().position(|q| q == repeated).unwrap_or(0);
+            let cycle = &stack[start..];
+            match cycle_decls(self, cycle) {
+                Some(diag) => diag,
+                None => cycle_queries(self, cycle),
+            }
+        };
+        self.sess.emit(diag);
+        crate::common::ice::abort()
+    }
+
+    fn before_query(&self, _tag: &QueryTag<'gcx>) {
+        if self.sess.opts.time_passes {
+            self.sess.timings.enter_query();
+        }
+    }
+
+    fn after_query(&self, tag: &QueryTag<'gcx>) {
+        if self.sess.opts.time_passes {
+            self.sess.timings.leave_query(tag.name());
+        }
+    }
+
+    fn cached_query(&self, tag: &QueryTag<'gcx>) {
+        if self.sess.opts.time_passes {
+            self.sess.timings.cached_query(tag.name());
+        }
+    }
 }
 
 impl<'gcx> ty::HasTypeStorage<'gcx> for GlobalContext<'gcx> {
@@ -335,6 +687,53 @@ impl<'t> GlobalArenas<'t> {
     pub fn alloc_ast_expr(&'t self, ast: ast::Expr<'t>) -> &'t ast::Expr {
         self.ast_exprs.alloc(ast)
     }
+
+    /// The number and size of the values allocated so far, grouped by what
+    /// they represent.
+    ///
+    /// The parsed AST in `ast` is not included, since it is complete once
+    /// parsing is done; see `ast::Arena::statistics`. Only the AST nodes
+    /// generated during lowering are listed.
+    pub fn statistics(&self) -> Vec<(&'static str, Vec<AllocStats>)> {
+        vec![
+            (
+                "ast",
+                vec![
+                    AllocStats {
+                        name: "Root",
+                        ..AllocStats::of(&self.ast_roots)
+                    },
+                    AllocStats {
+                        name: "Type",
+                        ..AllocStats::of(&self.ast_types)
+                    },
+                    AllocStats {
+                        name: "Expr",
+                        ..AllocStats::of(&self.ast_exprs)
+                    },
+                ],
+            ),
+            ("hir", self.hir.statistics()),
+            ("types", self.type_storage.statistics()),
+            ("param envs", vec![AllocStats::of(&self.param_envs)]),
+            (
+                "mir",
+                vec![
+                    AllocStats::of(&self.mir_lvalue),
+                    AllocStats::of(&self.mir_rvalue),
+                ],
+            ),
+            (
+                "scopes",
+                vec![
+                    AllocStats::of(&self.scopes),
+                    AllocStats::of(&self.ribs),
+                    AllocStats::of(&self.port_lists),
+                ],
+            ),
+            ("values", vec![AllocStats::of(&self.values)]),
+        ]
+    }
 }
 
 /// Allow AST nodes to be allocated into `GlobalArenas`.
@@ -355,6 +754,7 @@ pub struct GlobalTables<'t> {
     interned_param_envs: RefCell<HashMap<&'t ParamEnvData<'t>, ParamEnv>>,
     param_envs: RefCell<Vec<&'t ParamEnvData<'t>>>,
     param_env_contexts: RefCell<HashMap<ParamEnv, BTreeSet<NodeId>>>,
+    inst_stack: RefCell<Vec<NodeId>>,
     node_id_to_parent_node_id: RefCell<HashMap<NodeId, NodeId>>,
     interned_values: RefCell<HashSet<Value<'t>>>,
     lowering_hints: RefCell<HashMap<NodeId, hir::Hint>>,
@@ -366,9 +766,7 @@ pub struct GlobalTables<'t> {
 /// This trait represents the context within which most compiler operations take
 /// place. It is implemented by [`GlobalContext`] and also provides access to
 /// the global context via the `gcx()` method.
-pub trait BaseContext<'gcx>:
-    salsa::Database + DiagEmitter + QueryDatabase<'gcx> + ty::HasTypeStorage<'gcx>
-{
+pub trait BaseContext<'gcx>: DiagEmitter + QueryDatabase<'gcx> + ty::HasTypeStorage<'gcx> {
     /// Get the global context.
     fn gcx(&self) -> &GlobalContext<'gcx>;
 
@@ -467,9 +865,18 @@ pub trait BaseContext<'gcx>:
         id
     }
 
+    /// Obtain the AST node associated with a node id, if there is one.
+    ///
+    /// Unlike [`ast_of`], this does not report a missing node as a bug.
+    fn get_ast(&self, node_id: NodeId) -> Option<AstNode<'gcx>> {
+        let node = self.gcx().ast_map.get(node_id)?;
+        self.read_input(QueryInput::Source(node.human_span().source));
+        Some(node)
+    }
+
     /// Obtain the AST node associated with a node id.
     fn ast_of(&self, node_id: NodeId) -> Result<AstNode<'gcx>> {
-        match self.gcx().ast_map.get(node_id) {
+        match self.get_ast(node_id) {
             Some(node) => return Ok(node),
             None => (),
         }
@@ -503,10 +910,12 @@ pub trait BaseContext<'gcx>:
 
     /// Obtain an `ast::AnyNode` associated with a node id.
     fn ast_for_id(&self, node_id: NodeId) -> &'gcx dyn ast::AnyNode<'gcx> {
-        match self.gcx().ast_map2.borrow().get(&node_id) {
+        let node = match self.gcx().ast_map2.borrow().get(&node_id) {
             Some(&node) => node,
             None => panic!("no AST node for {:?} registered", node_id),
-        }
+        };
+        self.read_input(QueryInput::Source(node.span().source));
+        node
     }
 
     /// Internalize an HIR node.
@@ -598,6 +1007,21 @@ pub trait BaseContext<'gcx>:
             .unwrap_or_else(Default::default)
     }
 
+    /// Enter an instantiation while elaborating a design.
+    ///
+    /// Errors emitted until the matching `leave_inst` are annotated with the
+    /// chain of instantiations that led to them.
+    fn enter_inst(&self, inst: &hir::Inst) {
+        self.tables().inst_stack.borrow_mut().push(inst.id);
+        self.sess().enter_inst(&inst.name.value.as_str());
+    }
+
+    /// Leave the instantiation entered last.
+    fn leave_inst(&self) {
+        self.tables().inst_stack.borrow_mut().pop();
+        self.sess().leave_inst();
+    }
+
     /// Associate a parent with a node.
     ///
     /// Panics if `node_id` already has a parent assigned.
@@ -632,13 +1056,44 @@ pub trait BaseContext<'gcx>:
         }
     }
 
+    /// Find the module that contains a node.
+    ///
+    /// Returns `None` if the node is not part of a module, for example because
+    /// it is declared in a package.
+    fn enclosing_module(&self, node_id: NodeId) -> Option<NodeId> {
+        find_ancestor(self, node_id, |ast| match ast {
+            AstNode::Module(_) => true,
+            _ => false,
+        })
+    }
+
+    /// Find the procedure that contains a node.
+    fn enclosing_proc(&self, node_id: NodeId) -> Option<NodeId> {
+        find_ancestor(self, node_id, |ast| match ast {
+            AstNode::Proc(_) => true,
+            _ => false,
+        })
+    }
+
+    /// Find the innermost node that contains a node and generates a scope,
+    /// such as a module, a generate block, or a sequential block.
+    fn enclosing_scope(&self, node_id: NodeId) -> Option<NodeId> {
+        find_ancestor(self, node_id, |ast| {
+            ast.get_any()
+                .map(|node| node.as_all().is_scoped_node())
+                .unwrap_or(false)
+        })
+    }
+
     /// Resolve a name upwards or emit a diagnostic if nothing is found.
     fn resolve_upwards_or_error(&self, name: Spanned<Name>, start_at: NodeId) -> Result<NodeId> {
         match self.gcx().resolve_upwards(name.value, start_at)? {
             Some(id) => Ok(id),
             None => {
                 self.emit(
-                    DiagBuilder2::error(format!("`{}` not found", name.value)).span(name.span),
+                    DiagBuilder2::error(format!("`{}` not found", name.value))
+                        .code(codes::SV0001)
+                        .span(name.span),
                 );
                 Err(())
             }
@@ -656,6 +1111,7 @@ pub trait BaseContext<'gcx>:
                         name.value,
                         self.ast_of(start_at)?.desc_full()
                     ))
+                    .code(codes::SV0002)
                     .span(name.span),
                 );
                 Err(())
@@ -693,101 +1149,12 @@ pub trait BaseContext<'gcx>:
     }
 }
 
-/// The queries implemented by the compiler.
-pub(super) mod queries {
-    use super::*;
-
-    query_group! {
-        /// A collection of compiler queries.
-        pub trait Context<'a>: BaseContext<'a> {
-            /// Lower an AST node to HIR.
-            fn hir_of(node_id: NodeId) -> Result<HirNode<'a>> {
-                type HirOfQuery;
-                use fn hir::hir_of;
-            }
-
-            /// Compute the parameter bindings for an instantiation.
-            fn param_env(src: ParamEnvSource<'a>) -> Result<ParamEnv> {
-                type ParamEnvQuery;
-                use fn param_env::compute;
-            }
-
-            /// Determine the local rib that applies to a node.
-            fn local_rib(node_id: NodeId) -> Result<&'a Rib> {
-                type LocalRibQuery;
-                use fn resolver::local_rib;
-            }
-
-            /// Determine the hierarchical rib of a node.
-            fn hierarchical_rib(node_id: NodeId) -> Result<&'a Rib> {
-                type HierarchicalRibQuery;
-                use fn resolver::hierarchical_rib;
-            }
-
-            /// Resolve a name upwards through the ribs.
-            fn resolve_upwards(name: Name, start_at: NodeId) -> Result<Option<NodeId>> {
-                type ResolveUpwardsQuery;
-                use fn resolver::resolve_upwards;
-            }
-
-            /// Resolve a name downwards through the ribs.
-            fn resolve_downwards(name: Name, start_at: NodeId) -> Result<Option<NodeId>> {
-                type ResolveDownwardsQuery;
-                use fn resolver::resolve_downwards;
-            }
-
-            /// Resolve a node to its target.
-            fn resolve_node(node_id: NodeId, env: ParamEnv) -> Result<NodeId> {
-                type ResolveNodeQuery;
-                use fn resolver::resolve_node;
-            }
-
-            /// Obtain the details of a struct definition.
-            fn struct_def(node_id: NodeId) -> Result<Arc<StructDef>> {
-                type StructDefQuery;
-                use fn resolver::struct_def;
-            }
-
-            /// Lower an expression to an lvalue in the MIR.
-            fn mir_lvalue(
-                expr_id: NodeId,
-                env: ParamEnv,
-            ) -> &'a mir::Lvalue<'a> {
-                type MirLvalueQuery;
-                use fn mir::lower::lvalue::lower_expr;
-            }
-
-            /// Lower an expression to an rvalue in the MIR.
-            fn mir_rvalue(
-                expr_id: NodeId,
-                env: ParamEnv,
-            ) -> &'a mir::Rvalue<'a> {
-                type MirRvalueQuery;
-                use fn mir::lower::rvalue::lower_expr;
-            }
-        }
-    }
-
-    database_storage! {
-        /// The query result storage embedded in the global context.
-        pub struct GlobalStorage<'gcx> for GlobalContext<'gcx> {
-            impl Context<'gcx> {
-                fn hir_of() for HirOfQuery<'gcx>;
-                fn param_env() for ParamEnvQuery<'gcx>;
-                fn local_rib() for LocalRibQuery<'gcx>;
-                fn hierarchical_rib() for HierarchicalRibQuery<'gcx>;
-                fn resolve_upwards() for ResolveUpwardsQuery<'gcx>;
-                fn resolve_downwards() for ResolveDownwardsQuery<'gcx>;
-                fn resolve_node() for ResolveNodeQuery<'gcx>;
-                fn struct_def() for StructDefQuery<'gcx>;
-                fn mir_lvalue() for MirLvalueQuery<'gcx>;
-                fn mir_rvalue() for MirRvalueQuery<'gcx>;
-            }
-        }
-    }
-}
+/// The compiler context within which queries are executed.
+///
+/// The queries themselves are provided by the `QueryDatabase` supertrait.
+pub trait Context<'gcx>: BaseContext<'gcx> {}
 
-pub use self::queries::Context;
+impl<'gcx> Context<'gcx> for GlobalContext<'gcx> {}
 
 /// An ugly hack to get the new AST nodes to hook into the ID-based AST lookup
 /// during the transition phase.
@@ -820,3 +1187,105 @@ impl<'a, 'b> ast::Visitor<'a> for AstMapRegistrator<'a, 'b> {
         self.cx.canonicalize_ports(node);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{with_context, with_files};
+
+    #[test]
+    fn enclosing_nodes() {
+        with_context(
+            "package p; localparam int x = 1; endpackage\n\
+             module top; int a; initial begin int b; b = a; end endmodule\n",
+            |cx| {
+                let module_id = cx.find_module(Name::from("top")).unwrap();
+                let module = match cx.hir_of(module_id) {
+                    Ok(HirNode::Module(x)) => x,
+                    x => panic!("expected a module, got {:?}", x),
+                };
+                let prok = match cx.hir_of(module.block.procs[0]) {
+                    Ok(HirNode::Proc(x)) => x,
+                    x => panic!("expected a procedure, got {:?}", x),
+                };
+                let assign = match cx.hir_of(prok.stmt) {
+                    Ok(HirNode::Stmt(hir::Stmt {
+                        kind: hir::StmtKind::Block(stmts),
+                        ..
+                    })) => *stmts.last().unwrap(),
+                    x => panic!("expected a block, got {:?}", x),
+                };
+                let lhs = match cx.hir_of(assign) {
+                    Ok(HirNode::Stmt(hir::Stmt {
+                        kind: hir::StmtKind::Assign { lhs, .. },
+                        ..
+                    })) => *lhs,
+                    x => panic!("expected an assignment, got {:?}", x),
+                };
+
+                // A name in a block finds the block, procedure, and module.
+                assert_eq!(cx.enclosing_module(lhs), Some(module_id));
+                assert_eq!(cx.enclosing_proc(lhs), Some(prok.id));
+                assert_eq!(cx.enclosing_scope(lhs), Some(prok.stmt));
+                assert_eq!(cx.enclosing_scope(prok.stmt), Some(prok.id));
+                assert_eq!(cx.enclosing_scope(prok.id), Some(module_id));
+
+                // A variable outside of any procedure has no enclosing one.
+                let var = module.block.decls[0];
+                assert_eq!(cx.enclosing_module(var), Some(module_id));
+                assert_eq!(cx.enclosing_proc(var), None);
+
+                // A package item is in no module.
+                let package_id = cx.find_package(Name::from("p")).unwrap();
+                let package = match cx.hir_of(package_id) {
+                    Ok(HirNode::Package(x)) => x,
+                    x => panic!("expected a package, got {:?}", x),
+                };
+                let param = package.params[0];
+                assert_eq!(cx.enclosing_module(param), None);
+                assert_eq!(cx.enclosing_proc(param), None);
+                assert_eq!(cx.enclosing_scope(param), Some(package_id));
+            },
+        );
+    }
+
+    #[test]
+    fn update_file() {
+        with_files(
+            &[
+                "module a; int x; endmodule\n",
+                "module b; int y; endmodule\n",
+                "module b; int z; endmodule\n",
+            ],
+            |cx, files| {
+                cx.add_files(files[..2].iter().cloned());
+                let a = cx.find_module(Name::from("a")).unwrap();
+                let b = cx.find_module(Name::from("b")).unwrap();
+                cx.hir_of(a).unwrap();
+                cx.hir_of(b).unwrap();
+                let cached = |id| {
+                    cx.storage
+                        .cached_hir_of
+                        .borrow()
+                        .contains_key(&HirOfQueryKey(id))
+                };
+                assert!(cached(a) && cached(b));
+
+                // Only the queries that looked at the edited file re-run.
+                cx.update_file(files[1].span.source, files[2]);
+                assert!(cached(a));
+                assert!(!cached(b));
+                assert_eq!(cx.find_module(Name::from("a")), Some(a));
+                let b = cx.find_module(Name::from("b")).unwrap();
+                let decl = match cx.hir_of(b) {
+                    Ok(HirNode::Module(x)) => x.block.decls[0],
+                    x => panic!("expected a module, got {:?}", x),
+                };
+                match cx.hir_of(decl) {
+                    Ok(HirNode::VarDecl(x)) => assert_eq!(x.name.value, Name::from("z")),
+                    x => panic!("expected a variable, got {:?}", x),
+                }
+            },
+        );
+    }
+}
@@ -0,0 +1,163 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Dependencies between source files.
+//!
+//! A file depends on another if it instantiates a module or interface, imports
+//! a package, or refers to a package with a scoped name such as `pkg::T`, that
+//! the other file defines. Build systems which compile one file at a time use
+//! these dependencies to determine the order in which the files have to be
+//! compiled.
+
+use crate::{ast::AcceptVisitor, crate_prelude::*};
+
+/// The units a file defines, and the ones it refers to.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FileDeps {
+    /// The modules, interfaces, and packages defined in the file.
+    pub defined: Vec<Name>,
+    /// The modules, interfaces, and packages referred to in the file, in the
+    /// order in which they are first referred to.
+    pub used: Vec<Name>,
+}
+
+impl FileDeps {
+    /// Determine the units a file defines and refers to.
+    pub fn scan<'a>(file: &'a ast::SourceFile<'a>) -> Self {
+        let mut collector = DepCollector::default();
+        file.accept(&mut collector);
+        let mut deps = collector.deps;
+        let defined = deps.defined.clone();
+        deps.used.retain(|name| !defined.contains(name));
+        deps
+    }
+}
+
+/// The order in which a set of files has to be compiled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileOrder {
+    /// For each file, the indices of the files it depends on.
+    pub deps: Vec<Vec<usize>>,
+    /// The indices of the files, in the order in which they have to be
+    /// compiled.
+    pub order: Vec<usize>,
+    /// The indices of the files at which a dependency cycle was broken, i.e.
+    /// which are compiled before some of the files they depend on.
+    pub cyclic: Vec<usize>,
+}
+
+impl CompileOrder {
+    /// Determine the order in which files have to be compiled.
+    ///
+    /// Files are compiled after the files they depend on. Otherwise they keep
+    /// the order in which they are given. References to units which are not
+    /// defined in any of the files are ignored. Files in a dependency cycle
+    /// are compiled in the order in which they are given.
+    pub fn new(files: &[FileDeps]) -> Self {
+        let deps: Vec<Vec<usize>> = files
+            .iter()
+            .enumerate()
+            .map(|(index, file)| {
+                let mut deps: Vec<usize> = file
+                    .used
+                    .iter()
+                    .flat_map(|name| files.iter().position(|other| other.defined.contains(name)))
+                    .filter(|&dep| dep != index)
+                    .collect();
+                deps.sort();
+                deps.dedup();
+                deps
+            })
+            .collect();
+
+        let mut done = vec![false; files.len()];
+        let mut order = Vec::with_capacity(files.len());
+        let mut cyclic = vec![];
+        while order.len() < files.len() {
+            let ready = (0..files.len())
+                .find(|&index| !done[index] && deps[index].iter().all(|&dep| done[dep]));
+            let next = match ready {
+                Some(index) => index,
+                None => {
+                    // All remaining files wait for another one, so there is a
+                    // cycle. Break it at the first file that was given.
+                    let index = (0..files.len()).find(|&index| !done[index]).unwrap();
+                    cyclic.push(index);
+                    index
+                }
+            };
+            done[next] = true;
+            order.push(next);
+        }
+        cyclic.sort();
+        CompileOrder {
+            deps,
+            order,
+            cyclic,
+        }
+    }
+}
+
+/// A visitor that collects the units defined and referred to in a file.
+#[derive(Default)]
+struct DepCollector {
+    deps: FileDeps,
+}
+
+impl DepCollector {
+    fn define(&mut self, name: Name) {
+        if !self.deps.defined.contains(&name) {
+            self.deps.defined.push(name);
+        }
+    }
+
+    fn use_unit(&mut self, name: Name) {
+        if !self.deps.used.contains(&name) {
+            self.deps.used.push(name);
+        }
+    }
+}
+
+impl<'a> ast::Visitor<'a> for DepCollector {
+    fn pre_visit_module(&mut self, node: &'a ast::Module<'a>) -> bool {
+        self.define(node.name.value);
+        true
+    }
+
+    fn pre_visit_interface(&mut self, node: &'a ast::Interface<'a>) -> bool {
+        self.define(node.name.value);
+        true
+    }
+
+    fn pre_visit_package(&mut self, node: &'a ast::Package<'a>) -> bool {
+        self.define(node.name.value);
+        true
+    }
+
+    fn pre_visit_inst(&mut self, node: &'a ast::Inst<'a>) -> bool {
+        self.use_unit(node.target.value);
+        true
+    }
+
+    fn pre_visit_import_item(&mut self, node: &'a ast::ImportItem<'a>) -> bool {
+        self.use_unit(node.pkg.value);
+        true
+    }
+
+    fn pre_visit_expr(&mut self, node: &'a ast::Expr<'a>) -> bool {
+        if let ast::ScopeExpr(ref scope, _) = node.data {
+            if let ast::IdentExpr(name) = scope.data {
+                self.use_unit(name.value);
+            }
+        }
+        true
+    }
+
+    fn pre_visit_type(&mut self, node: &'a ast::Type<'a>) -> bool {
+        if let ast::ScopedType { ref ty, .. } = node.kind.data {
+            if let ast::NamedType(name) = ty.kind.data {
+                self.use_unit(name.value);
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,339 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Support for the Direct Programming Interface (DPI).
+//!
+//! This module collects the functions and tasks imported from and exported to
+//! C through `import "DPI-C"` and `export "DPI-C"` declarations, and maps their
+//! arguments to the C types prescribed by Annex H of IEEE 1800-2017.
+
+use crate::{
+    crate_prelude::*,
+    ty::{IntAtomType, PackedCore, RealType, Sign, UnpackedCore, UnpackedType},
+    ParamEnv,
+};
+use std::io::Write;
+
+/// A function or task imported from or exported to C.
+#[derive(Debug, Clone)]
+pub struct DpiSubroutine<'a> {
+    /// Whether the subroutine is implemented in C and imported into
+    /// SystemVerilog, as opposed to implemented in SystemVerilog and exported
+    /// to C.
+    pub import: bool,
+    /// Whether this is a function or a task.
+    pub kind: ast::SubroutineKind,
+    /// The name of the subroutine in SystemVerilog.
+    pub name: Spanned<Name>,
+    /// The name of the subroutine in C.
+    pub c_name: Name,
+    /// The arguments of the subroutine.
+    pub args: Vec<DpiArg<'a>>,
+    /// The return type of a function, or `None` for tasks and void functions.
+    pub retty: Option<&'a UnpackedType<'a>>,
+}
+
+/// An argument of a DPI function or task.
+#[derive(Debug, Clone)]
+pub struct DpiArg<'a> {
+    /// The name of the argument, if it has one.
+    pub name: Option<Name>,
+    /// The direction of the argument.
+    pub dir: ast::SubroutinePortDir,
+    /// The type of the argument.
+    pub ty: &'a UnpackedType<'a>,
+}
+
+impl DpiArg<'_> {
+    /// Check whether the argument is passed from C back to SystemVerilog.
+    pub fn is_output(&self) -> bool {
+        match self.dir {
+            ast::SubroutinePortDir::Output | ast::SubroutinePortDir::Inout => true,
+            _ => false,
+        }
+    }
+}
+
+/// Collect the DPI functions and tasks declared among a list of items.
+///
+/// Exported subroutines are looked up among the same items, since they must be
+/// declared in the same scope as the export declaration.
+pub fn dpi_subroutines<'a>(
+    cx: &impl Context<'a>,
+    items: &'a [ast::Item<'a>],
+    env: ParamEnv,
+) -> Result<Vec<DpiSubroutine<'a>>> {
+    let mut subroutines = vec![];
+    for item in items {
+        let decl = match item.data {
+            ast::ItemData::DpiDecl(ref x) => x,
+            _ => continue,
+        };
+        let sub = match decl.data {
+            ast::DpiDeclData::Import {
+                cident,
+                ref prototype,
+                ..
+            } => lower_prototype(cx, true, cident, prototype, env)?,
+            ast::DpiDeclData::Export { cident, name, .. } => {
+                let prototype = items
+                    .iter()
+                    .flat_map(|item| match item.data {
+                        ast::ItemData::SubroutineDecl(ref x) => Some(&x.prototype),
+                        _ => None,
+                    })
+                    .find(|prototype| prototype.name.value == name.value);
+                match prototype {
+                    Some(prototype) => lower_prototype(cx, false, cident, prototype, env)?,
+                    None => {
+                        cx.emit(
+                            DiagBuilder2::error(format!(
+                                "`{}` is not a function or task in this scope",
+                                name
+                            ))
+                            .code(codes::SV0016)
+                            .span(name.span)
+                            .add_note(
+                                "Only subroutines declared in the same scope can be exported",
+                            ),
+                        );
+                        return Err(());
+                    }
+                }
+            }
+        };
+        subroutines.push(sub);
+    }
+    Ok(subroutines)
+}
+
+/// Determine the arguments and return type of a DPI subroutine.
+fn lower_prototype<'a>(
+    cx: &impl Context<'a>,
+    import: bool,
+    cident: Option<Spanned<Name>>,
+    prototype: &'a ast::SubroutinePrototype<'a>,
+    env: ParamEnv,
+) -> Result<DpiSubroutine<'a>> {
+    let mut failed = false;
+
+    // Determine the arguments. Arguments without an explicit direction inherit
+    // the direction of the previous argument, or are inputs.
+    let mut args = vec![];
+    let mut dir = ast::SubroutinePortDir::Input;
+    for arg in &prototype.args {
+        dir = arg.dir.unwrap_or(dir);
+        let (name, dims) = match arg.name {
+            Some(ref name) => (Some(name.name.value), &name.dims[..]),
+            None => (None, &[][..]),
+        };
+        let ty = cx.unpacked_type_from_ast(
+            Ref(&arg.ty),
+            Ref(dims),
+            env,
+            Some(PackedCore::IntVec(ty::IntVecType::Logic)),
+        );
+        if ty.is_error() {
+            failed = true;
+            continue;
+        }
+        if c_type(ty).is_none() {
+            cx.emit(
+                DiagBuilder2::error(format!("`{}` cannot be passed through the DPI", ty))
+                    .code(codes::SV0015)
+                    .span(arg.span()),
+            );
+            failed = true;
+        }
+        args.push(DpiArg { name, dir, ty });
+    }
+
+    // Determine the return type.
+    let retty = match prototype.retty {
+        Some(ref ast_ty) => {
+            let ty = cx.packed_type_from_ast(Ref(ast_ty), env, None);
+            match ty.get_packed().map(|p| &p.resolve_full().core) {
+                Some(PackedCore::Void) => None,
+                _ if ty.is_error() => {
+                    failed = true;
+                    None
+                }
+                _ if !is_small_value(ty) => {
+                    cx.emit(
+                        DiagBuilder2::error(format!("`{}` cannot be returned through the DPI", ty))
+                            .code(codes::SV0015)
+                            .span(ast_ty.span())
+                            .add_note(
+                                "Only `void`, `byte`, `shortint`, `int`, `longint`, `real`, \
+                             `shortreal`, `chandle`, `string`, and scalar `bit` and `logic` \
+                             can be returned",
+                            ),
+                    );
+                    failed = true;
+                    None
+                }
+                _ => Some(ty),
+            }
+        }
+        None => None,
+    };
+
+    if failed {
+        return Err(());
+    }
+    Ok(DpiSubroutine {
+        import,
+        kind: prototype.kind,
+        name: prototype.name,
+        c_name: cident.unwrap_or(prototype.name).value,
+        args,
+        retty,
+    })
+}
+
+/// Check whether a type is passed by value through the DPI.
+///
+/// These are the types that may be returned from a DPI function.
+pub fn is_small_value(ty: &UnpackedType) -> bool {
+    let ty = ty.resolve_full();
+    match ty.core {
+        UnpackedCore::Real(_) | UnpackedCore::String | UnpackedCore::Chandle => ty.dims.is_empty(),
+        _ => match ty.get_packed().map(|p| p.resolve_full()) {
+            Some(p) if p.dims.is_empty() => match p.core {
+                PackedCore::IntAtom(IntAtomType::Integer)
+                | PackedCore::IntAtom(IntAtomType::Time) => false,
+                PackedCore::IntAtom(_) | PackedCore::IntVec(_) => true,
+                _ => false,
+            },
+            _ => false,
+        },
+    }
+}
+
+/// Map a type to the corresponding C type.
+///
+/// Types which are not passed by value map to the type of the elements of the
+/// canonical vector representation they are passed in. Returns `None` if the
+/// type cannot be passed through the DPI.
+pub fn c_type(ty: &UnpackedType) -> Option<&'static str> {
+    let ty = ty.resolve_full();
+    if !ty.dims.is_empty() {
+        return None;
+    }
+    match ty.core {
+        UnpackedCore::Real(RealType::ShortReal) => return Some("float"),
+        UnpackedCore::Real(_) => return Some("double"),
+        UnpackedCore::String => return Some("const char*"),
+        UnpackedCore::Chandle => return Some("void*"),
+        _ => (),
+    }
+    let packed = ty.get_packed()?.resolve_full();
+    let unsigned = packed.sign == Sign::Unsigned;
+    Some(match packed.core {
+        PackedCore::IntAtom(IntAtomType::Byte) if packed.dims.is_empty() => {
+            if unsigned {
+                "unsigned char"
+            } else {
+                "char"
+            }
+        }
+        PackedCore::IntAtom(IntAtomType::ShortInt) if packed.dims.is_empty() => {
+            if unsigned {
+                "unsigned short int"
+            } else {
+                "short int"
+            }
+        }
+        PackedCore::IntAtom(IntAtomType::Int) if packed.dims.is_empty() => {
+            if unsigned {
+                "unsigned int"
+            } else {
+                "int"
+            }
+        }
+        PackedCore::IntAtom(IntAtomType::LongInt) if packed.dims.is_empty() => {
+            if unsigned {
+                "unsigned long long"
+            } else {
+                "long long"
+            }
+        }
+        PackedCore::IntVec(_) if packed.dims.is_empty() => match packed.domain() {
+            ty::Domain::TwoValued => "svBit",
+            ty::Domain::FourValued => "svLogic",
+        },
+        PackedCore::Error | PackedCore::Void => return None,
+        _ => match packed.domain() {
+            ty::Domain::TwoValued => "svBitVecVal",
+            ty::Domain::FourValued => "svLogicVecVal",
+        },
+    })
+}
+
+/// Write the C prototype of a DPI subroutine.
+fn write_c_prototype(out: &mut impl Write, sub: &DpiSubroutine) -> std::io::Result<()> {
+    let retty = match (sub.kind, sub.retty) {
+        (ast::SubroutineKind::Task, _) => "int",
+        (_, Some(ty)) => c_type(ty).unwrap(),
+        (_, None) => "void",
+    };
+    write!(out, "{} {}(", retty, sub.c_name)?;
+    if sub.args.is_empty() {
+        write!(out, "void")?;
+    }
+    for (i, arg) in sub.args.iter().enumerate() {
+        if i > 0 {
+            write!(out, ", ")?;
+        }
+        let ty = c_type(arg.ty).unwrap();
+        if arg.is_output() {
+            write!(out, "{}*", ty)?;
+        } else if is_small_value(arg.ty) {
+            write!(out, "{}", ty)?;
+        } else {
+            write!(out, "const {}*", ty)?;
+        }
+        if let Some(name) = arg.name {
+            write!(out, " {}", name)?;
+        }
+    }
+    writeln!(out, ");")
+}
+
+/// Write a C header declaring a list of DPI subroutines.
+///
+/// Imported subroutines are to be implemented by the user in C. Exported
+/// subroutines are implemented in SystemVerilog and may be called from C.
+pub fn write_c_header(out: &mut impl Write, subroutines: &[DpiSubroutine]) -> std::io::Result<()> {
+    writeln!(
+        out,
+        "/* DPI declarations generated by moore. Do not edit. */"
+    )?;
+    writeln!(out, "#ifndef MOORE_DPI_H")?;
+    writeln!(out, "#define MOORE_DPI_H")?;
+    writeln!(out)?;
+    writeln!(out, "#include \"svdpi.h\"")?;
+    writeln!(out)?;
+    writeln!(out, "#ifdef __cplusplus")?;
+    writeln!(out, "extern \"C\" {{")?;
+    writeln!(out, "#endif")?;
+    for &(import, comment) in &[
+        (true, "Imported into SystemVerilog; implemented in C."),
+        (false, "Exported from SystemVerilog; callable from C."),
+    ] {
+        let mut subs = subroutines.iter().filter(|s| s.import == import).peekable();
+        if subs.peek().is_none() {
+            continue;
+        }
+        writeln!(out)?;
+        writeln!(out, "/* {} */", comment)?;
+        for sub in subs {
+            write_c_prototype(out, sub)?;
+        }
+    }
+    writeln!(out)?;
+    writeln!(out, "#ifdef __cplusplus")?;
+    writeln!(out, "}}")?;
+    writeln!(out, "#endif")?;
+    writeln!(out)?;
+    writeln!(out, "#endif /* MOORE_DPI_H */")
+}
@@ -0,0 +1,308 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Finding variables and nets with more than one driver.
+//!
+//! Every `always` procedure counts as one driver of the signals it assigns,
+//! and every continuous assignment counts as a driver of its own. A variable
+//! may only have one driver. A net may have several continuous drivers as long
+//! as they form a tri-state pattern, where all of them may release the net by
+//! driving `'z`. Drivers in different branches of an `if` generate construct
+//! exclude each other and never conflict.
+
+use crate::{
+    ast::AcceptVisitor,
+    crate_prelude::*,
+    lookup::SymbolKind,
+    resolver::{DefNode, ScopedNode},
+    syntax::token::Lit,
+};
+use moore_common::lints;
+use std::collections::HashMap;
+
+/// Report the variables and nets with multiple drivers in the modules of a
+/// source file.
+///
+/// The modules should have passed name resolution, since the drivers are
+/// associated with the signals through the names they assign to.
+pub fn check_drivers<'gcx>(cx: &GlobalContext<'gcx>, file: &'gcx ast::SourceFile<'gcx>) {
+    for item in file.items.iter() {
+        if let ast::ItemData::ModuleDecl(ref module) = item.data {
+            check_module(cx, module);
+        }
+    }
+}
+
+/// Report the signals with multiple drivers in a module.
+fn check_module<'gcx>(cx: &GlobalContext<'gcx>, module: &'gcx ast::Module<'gcx>) {
+    let mut finder = DriverFinder::default();
+    module.accept(&mut finder);
+    let table = cx.symbol_table(Ref(module as &dyn ScopedNode));
+    for symbol in &table.symbols {
+        let net = match net_type(symbol) {
+            Some(None) => false,
+            Some(Some(ast::NetType::Wire)) | Some(Some(ast::NetType::Uwire)) => true,
+            _ => continue,
+        };
+
+        // Gather the targets of the drivers of this signal, keeping only the
+        // first target of each driver for every part it drives.
+        let mut targets: Vec<&Target> = vec![];
+        for span in &symbol.references {
+            for target in finder.targets.get(span).into_iter().flatten() {
+                let driver = &finder.drivers[target.driver];
+                if net && driver.kind != DriverKind::Continuous {
+                    continue;
+                }
+                if !targets
+                    .iter()
+                    .any(|t| t.driver == target.driver && t.part == target.part)
+                {
+                    targets.push(target);
+                }
+            }
+        }
+
+        // Check whether any two drivers conflict.
+        let conflict = targets.iter().enumerate().any(|(i, a)| {
+            targets[i + 1..].iter().any(|b| {
+                let (da, db) = (&finder.drivers[a.driver], &finder.drivers[b.driver]);
+                a.driver != b.driver
+                    && (a.part.is_none() || b.part.is_none() || a.part == b.part)
+                    && !excluded(&da.branches, &db.branches)
+                    && !(net && da.tristate && db.tristate)
+            })
+        });
+        if !conflict {
+            continue;
+        }
+
+        let name = symbol.name();
+        let mut diag = DiagBuilder2::lint(
+            lints::MULTIPLE_DRIVERS,
+            format!("`{}` has multiple drivers", name.value),
+        )
+        .span(name.span);
+        for target in targets {
+            let driver = &finder.drivers[target.driver];
+            diag = diag
+                .add_note(match driver.kind {
+                    DriverKind::Continuous => "Driven by a continuous assignment:".to_string(),
+                    DriverKind::Procedure(kind) => {
+                        format!("Driven by an `{}` procedure:", procedure_keyword(kind))
+                    }
+                })
+                .span(target.span);
+        }
+        cx.emit(diag);
+    }
+}
+
+/// Determine whether a symbol is a net or a variable.
+///
+/// Returns `Some(None)` for variables, `Some(Some(..))` with the net type for
+/// nets, and `None` for all other symbols.
+fn net_type(symbol: &lookup::Symbol) -> Option<Option<ast::NetType>> {
+    match (symbol.kind, symbol.def.node) {
+        (SymbolKind::Variable, _) => Some(None),
+        (SymbolKind::Net, DefNode::Ast(node)) => match node.get_parent().map(|p| p.as_all()) {
+            Some(ast::AllNode::NetDecl(decl)) => Some(Some(decl.net_type)),
+            _ => None,
+        },
+        (SymbolKind::Port, DefNode::IntPort(port)) => match port.kind {
+            ast::PortKind::Var => Some(None),
+            ast::PortKind::Net(net_type) => Some(Some(net_type)),
+        },
+        _ => None,
+    }
+}
+
+/// Check whether two drivers sit in different branches of the same `if`
+/// generate construct, such that at most one of them exists.
+fn excluded(a: &[(NodeId, usize)], b: &[(NodeId, usize)]) -> bool {
+    a.iter()
+        .any(|&(ia, ba)| b.iter().any(|&(ib, bb)| ia == ib && ba != bb))
+}
+
+/// The keyword that introduces a procedure.
+fn procedure_keyword(kind: ast::ProcedureKind) -> &'static str {
+    match kind {
+        ast::ProcedureKind::Initial => "initial",
+        ast::ProcedureKind::Always => "always",
+        ast::ProcedureKind::AlwaysComb => "always_comb",
+        ast::ProcedureKind::AlwaysLatch => "always_latch",
+        ast::ProcedureKind::AlwaysFf => "always_ff",
+        ast::ProcedureKind::Final => "final",
+    }
+}
+
+/// Check whether an expression may drive a high-impedance value, like `'z` or
+/// `en ? a : 'z`.
+fn is_high_z(expr: &ast::Expr) -> bool {
+    match expr.data {
+        ast::LiteralExpr(Lit::UnbasedUnsized(c)) => c == 'z' || c == 'Z',
+        ast::LiteralExpr(Lit::BasedInteger(_, _, _, value)) => value
+            .as_str()
+            .chars()
+            .any(|c| c == 'z' || c == 'Z' || c == '?'),
+        ast::TernaryExpr {
+            ref true_expr,
+            ref false_expr,
+            ..
+        } => is_high_z(true_expr) || is_high_z(false_expr),
+        _ => false,
+    }
+}
+
+/// A driver of one or more signals.
+struct Driver {
+    /// Whether this is a procedure or a continuous assignment.
+    kind: DriverKind,
+    /// The enclosing `if` generate constructs, and the branch taken in each.
+    branches: Vec<(NodeId, usize)>,
+    /// Whether the driver may release its target by driving `'z`.
+    tristate: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DriverKind {
+    Continuous,
+    Procedure(ast::ProcedureKind),
+}
+
+/// A signal assigned by a driver.
+struct Target {
+    /// The index of the driver.
+    driver: usize,
+    /// The assigned expression, like `x` or `x[3:0]`.
+    span: Span,
+    /// The assigned part of the signal, or `None` if all of it is assigned.
+    part: Option<String>,
+}
+
+/// A visitor that collects the drivers in a module, and the names which they
+/// assign to.
+#[derive(Default)]
+struct DriverFinder {
+    drivers: Vec<Driver>,
+    /// The targets, indexed by the span of the assigned name.
+    targets: HashMap<Span, Vec<Target>>,
+    /// The enclosing `if` generate constructs.
+    branches: Vec<(NodeId, usize)>,
+    /// The procedure currently being visited.
+    procedure: Option<usize>,
+}
+
+impl DriverFinder {
+    /// Add a new driver.
+    fn add_driver(&mut self, kind: DriverKind, tristate: bool) -> usize {
+        self.drivers.push(Driver {
+            kind,
+            branches: self.branches.clone(),
+            tristate,
+        });
+        self.drivers.len() - 1
+    }
+
+    /// Add the names written by an assignment to an expression.
+    fn add_target(&mut self, driver: usize, expr: &ast::Expr) {
+        if let ast::ConcatExpr { exprs, .. } = expr.data {
+            for expr in exprs {
+                self.add_target(driver, expr);
+            }
+            return;
+        }
+        let mut name = expr;
+        loop {
+            name = match name.data {
+                ast::IndexExpr { ref indexee, .. } => indexee,
+                ast::MemberExpr { ref expr, .. } => expr,
+                _ => break,
+            };
+        }
+        if let ast::IdentExpr(ident) = name.data {
+            let part = if name.span == expr.span {
+                None
+            } else {
+                let mut part = expr.span.extract();
+                part.retain(|c| !c.is_whitespace());
+                Some(part)
+            };
+            self.targets.entry(ident.span).or_default().push(Target {
+                driver,
+                span: expr.span,
+                part,
+            });
+        }
+    }
+
+    /// Determine which branch of an `if` generate construct a block is.
+    fn generate_branch<'a>(node: &'a dyn ast::AnyNode<'a>) -> Option<(NodeId, usize)> {
+        match node.get_parent()?.as_all() {
+            ast::AllNode::GenerateIf(gen) => Some((
+                gen.id(),
+                if gen.main_block.id() == node.id() {
+                    0
+                } else {
+                    1
+                },
+            )),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> ast::Visitor<'a> for DriverFinder {
+    fn pre_visit_node(&mut self, node: &'a dyn ast::AnyNode<'a>) -> bool {
+        match node.as_all() {
+            ast::AllNode::GenerateBlock(_) => {
+                if let Some(branch) = Self::generate_branch(node) {
+                    self.branches.push(branch);
+                }
+            }
+            ast::AllNode::Procedure(proc) => match proc.kind {
+                ast::ProcedureKind::Initial | ast::ProcedureKind::Final => return false,
+                kind => {
+                    self.procedure = Some(self.add_driver(DriverKind::Procedure(kind), false));
+                }
+            },
+            ast::AllNode::SubroutineDecl(_) => return false,
+            ast::AllNode::ContAssign(assign) => {
+                for (lhs, rhs) in &assign.assignments {
+                    let driver = self.add_driver(DriverKind::Continuous, is_high_z(rhs));
+                    self.add_target(driver, lhs);
+                }
+            }
+            ast::AllNode::Stmt(stmt) => match stmt.kind {
+                ast::BlockingAssignStmt { ref lhs, .. }
+                | ast::NonblockingAssignStmt { ref lhs, .. } => {
+                    if let Some(driver) = self.procedure {
+                        self.add_target(driver, lhs);
+                    }
+                }
+                _ => (),
+            },
+            ast::AllNode::Expr(expr) => match expr.data {
+                ast::AssignExpr { ref lhs, .. } => {
+                    if let Some(driver) = self.procedure {
+                        self.add_target(driver, lhs);
+                    }
+                }
+                _ => (),
+            },
+            _ => (),
+        }
+        true
+    }
+
+    fn post_visit_node(&mut self, node: &'a dyn ast::AnyNode<'a>) {
+        match node.as_all() {
+            ast::AllNode::GenerateBlock(_) => {
+                if Self::generate_branch(node).is_some() {
+                    self.branches.pop();
+                }
+            }
+            ast::AllNode::Procedure(_) => self.procedure = None,
+            _ => (),
+        }
+    }
+}
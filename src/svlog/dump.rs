@@ -0,0 +1,408 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Textual dumps of the HIR and MIR of modules.
+//!
+//! A dump lists the contents of one module under one parametrization, one
+//! node per line and indented by nesting depth. Nodes are identified by their
+//! location in the source file rather than their node IDs, such that the dumps
+//! only change if the lowering does. In the HIR dump every expression is
+//! annotated with its type. In the MIR dump the expressions are replaced with
+//! the MIR trees they lower to, with every MIR node annotated with its type.
+
+use crate::{
+    crate_prelude::*,
+    hir::{Control, HirNode, Visitor},
+    mir::{Lvalue, LvalueKind, Rvalue, RvalueKind},
+    ParamEnv,
+};
+
+/// Render the HIR of a module under a parametrization.
+pub fn dump_hir<'gcx>(cx: &GlobalContext<'gcx>, module: NodeId, env: ParamEnv) -> String {
+    Dumper::new(cx, env, false).dump(module)
+}
+
+/// Render the MIR of the expressions in a module under a parametrization.
+pub fn dump_mir<'gcx>(cx: &GlobalContext<'gcx>, module: NodeId, env: ParamEnv) -> String {
+    Dumper::new(cx, env, true).dump(module)
+}
+
+/// A visitor that renders the nodes of a module.
+struct Dumper<'a, 'gcx> {
+    cx: &'a GlobalContext<'gcx>,
+    env: ParamEnv,
+    /// Whether to render expressions as MIR instead of HIR.
+    mir: bool,
+    depth: usize,
+    output: String,
+}
+
+impl<'a, 'gcx> Dumper<'a, 'gcx> {
+    fn new(cx: &'a GlobalContext<'gcx>, env: ParamEnv, mir: bool) -> Self {
+        Dumper {
+            cx,
+            env,
+            mir,
+            depth: 0,
+            output: String::new(),
+        }
+    }
+
+    fn dump(mut self, module: NodeId) -> String {
+        let hir = match self.cx.hir_of(module) {
+            Ok(HirNode::Module(x)) => x,
+            _ => return self.output,
+        };
+        self.line(format!("module `{}`", hir.ast.name));
+        self.depth += 1;
+        for &id in hir.params {
+            match self.cx.hir_of(id) {
+                Ok(HirNode::ValueParam(param)) => {
+                    let value = self.cx.constant_value_of(id, self.env);
+                    self.line(format!(
+                        "parameter `{}` = {}: {}",
+                        param.name, value.kind, value.ty
+                    ));
+                }
+                Ok(HirNode::TypeParam(param)) => {
+                    let ty = match self.cx.map_to_type(Ref(self.cx.ast_for_id(id)), self.env) {
+                        Some(ty) => ty.to_string(),
+                        None => "<error>".to_string(),
+                    };
+                    self.line(format!("type parameter `{}` = {}", param.name, ty));
+                }
+                _ => (),
+            }
+        }
+        for port in &hir.ports_new.int {
+            self.visit_node_with_id(port.id, false);
+        }
+        let block = &hir.block;
+        for &id in block.insts.iter().chain(&block.decls).chain(&block.procs) {
+            self.visit_node_with_id(id, false);
+        }
+        // Generate blocks are expanded during code generation, with a separate
+        // parametrization per iteration, so they are only listed.
+        for &id in &block.gens {
+            if let Ok(HirNode::Gen(x)) = self.cx.hir_of(id) {
+                self.node(x.span, x.desc_full());
+            }
+        }
+        for &id in &block.assigns {
+            self.visit_node_with_id(id, false);
+        }
+        self.depth -= 1;
+        self.output
+    }
+
+    /// Add a line at the current depth.
+    fn line(&mut self, text: impl AsRef<str>) {
+        for _ in 0..self.depth {
+            self.output.push_str("  ");
+        }
+        self.output.push_str(text.as_ref());
+        self.output.push('\n');
+    }
+
+    /// Add a line for a node at the current depth, followed by its location.
+    fn node(&mut self, span: Span, text: impl AsRef<str>) {
+        let begin = span.begin();
+        self.line(format!(
+            "{} @ {}:{}",
+            text.as_ref(),
+            begin.human_line(),
+            begin.human_column()
+        ));
+    }
+
+    /// Add a line for a node and render its children one level deeper.
+    fn nested<R>(
+        &mut self,
+        span: Span,
+        text: impl AsRef<str>,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        self.node(span, text);
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Resolve the declaration a MIR node refers to to its name.
+    fn name_of(&self, id: NodeId) -> String {
+        match self.cx.hir_of(id) {
+            Ok(HirNode::VarDecl(x)) => x.name.value.to_string(),
+            Ok(HirNode::IntPort(x)) => x.name.value.to_string(),
+            Ok(HirNode::Inst(x)) => x.ast.name.to_string(),
+            Ok(HirNode::GenvarDecl(x)) => x.name.value.to_string(),
+            _ => self.cx.span(id).extract(),
+        }
+    }
+
+    fn rvalue(&mut self, prefix: &str, mir: &Rvalue) {
+        let label = match mir.kind {
+            RvalueKind::CastValueDomain { from, to, .. } => {
+                format!("cast_value_domain {:?} -> {:?}", from, to)
+            }
+            RvalueKind::Transmute(..) => "transmute".to_string(),
+            RvalueKind::CastSign(sign, ..) => format!("cast_sign {}", sign),
+            RvalueKind::CastToBool(..) => "cast_to_bool".to_string(),
+            RvalueKind::Truncate(width, ..) => format!("truncate {}", width),
+            RvalueKind::ZeroExtend(width, ..) => format!("zero_extend {}", width),
+            RvalueKind::SignExtend(width, ..) => format!("sign_extend {}", width),
+            RvalueKind::IntToReal(sign, ..) => format!("int_to_real {}", sign),
+            RvalueKind::RealToInt(..) => "real_to_int".to_string(),
+            RvalueKind::RealToReal(..) => "real_to_real".to_string(),
+            RvalueKind::ConstructArray(..) => "construct_array".to_string(),
+            RvalueKind::ConstructStruct(..) => "construct_struct".to_string(),
+            RvalueKind::Const(value) => format!("const {}", value.kind),
+            RvalueKind::UnaryBitwise { op, .. } => format!("unary_bitwise {:?}", op),
+            RvalueKind::BinaryBitwise { op, .. } => format!("binary_bitwise {:?}", op),
+            RvalueKind::IntUnaryArith {
+                op, sign, domain, ..
+            } => format!("int_unary_arith {:?} {} {:?}", op, sign, domain),
+            RvalueKind::IntBinaryArith {
+                op, sign, domain, ..
+            } => format!("int_binary_arith {:?} {} {:?}", op, sign, domain),
+            RvalueKind::IntComp {
+                op, sign, domain, ..
+            } => format!("int_comp {:?} {} {:?}", op, sign, domain),
+            RvalueKind::RealUnaryArith { op, .. } => format!("real_unary_arith {:?}", op),
+            RvalueKind::RealBinaryArith { op, .. } => format!("real_binary_arith {:?}", op),
+            RvalueKind::RealComp { op, .. } => format!("real_comp {:?}", op),
+            RvalueKind::Concat(..) => "concat".to_string(),
+            RvalueKind::Repeat(count, ..) => format!("repeat {}", count),
+            RvalueKind::Var(id) => format!("var `{}`", self.name_of(id)),
+            RvalueKind::Port(id) => format!("port `{}`", self.name_of(id)),
+            RvalueKind::Intf(id) => format!("intf `{}`", self.name_of(id)),
+            RvalueKind::IntfSignal(_, id) => format!("intf_signal `{}`", self.name_of(id)),
+            RvalueKind::Index { length: 0, .. } => "index".to_string(),
+            RvalueKind::Index { length, .. } => format!("index length {}", length),
+            RvalueKind::Member { field, .. } => format!("member {}", field),
+            RvalueKind::Ternary { .. } => "ternary".to_string(),
+            RvalueKind::Shift { op, arith, .. } => {
+                format!("shift {:?}{}", op, if arith { " arith" } else { "" })
+            }
+            RvalueKind::Reduction { op, .. } => format!("reduction {:?}", op),
+            RvalueKind::Assignment { .. } => "assignment".to_string(),
+            RvalueKind::Error => "error".to_string(),
+        };
+        self.line(format!("{}{}: {}", prefix, label, mir.ty));
+        self.depth += 1;
+        match mir.kind {
+            RvalueKind::CastValueDomain { value, .. }
+            | RvalueKind::Transmute(value)
+            | RvalueKind::CastSign(_, value)
+            | RvalueKind::CastToBool(value)
+            | RvalueKind::Truncate(_, value)
+            | RvalueKind::ZeroExtend(_, value)
+            | RvalueKind::SignExtend(_, value)
+            | RvalueKind::IntToReal(_, value)
+            | RvalueKind::RealToInt(value)
+            | RvalueKind::RealToReal(value)
+            | RvalueKind::Repeat(_, value)
+            | RvalueKind::IntfSignal(value, _)
+            | RvalueKind::Member { value, .. }
+            | RvalueKind::UnaryBitwise { arg: value, .. }
+            | RvalueKind::IntUnaryArith { arg: value, .. }
+            | RvalueKind::RealUnaryArith { arg: value, .. }
+            | RvalueKind::Reduction { arg: value, .. } => self.rvalue("", value),
+            RvalueKind::BinaryBitwise { lhs, rhs, .. }
+            | RvalueKind::IntBinaryArith { lhs, rhs, .. }
+            | RvalueKind::IntComp { lhs, rhs, .. }
+            | RvalueKind::RealBinaryArith { lhs, rhs, .. }
+            | RvalueKind::RealComp { lhs, rhs, .. } => {
+                self.rvalue("", lhs);
+                self.rvalue("", rhs);
+            }
+            RvalueKind::ConstructArray(ref values) => {
+                let mut values: Vec<_> = values.iter().collect();
+                values.sort_by_key(|&(&index, _)| index);
+                for (index, value) in values {
+                    self.rvalue(&format!("[{}] ", index), value);
+                }
+            }
+            RvalueKind::ConstructStruct(ref values) | RvalueKind::Concat(ref values) => {
+                for value in values {
+                    self.rvalue("", value);
+                }
+            }
+            RvalueKind::Index { value, base, .. } => {
+                self.rvalue("", value);
+                self.rvalue("base ", base);
+            }
+            RvalueKind::Ternary {
+                cond,
+                true_value,
+                false_value,
+            } => {
+                self.rvalue("", cond);
+                self.rvalue("", true_value);
+                self.rvalue("", false_value);
+            }
+            RvalueKind::Shift { value, amount, .. } => {
+                self.rvalue("", value);
+                self.rvalue("amount ", amount);
+            }
+            RvalueKind::Assignment {
+                lvalue,
+                rvalue,
+                result,
+            } => {
+                self.lvalue("", lvalue);
+                self.rvalue("", rvalue);
+                self.rvalue("result ", result);
+            }
+            RvalueKind::Const(_)
+            | RvalueKind::Var(_)
+            | RvalueKind::Port(_)
+            | RvalueKind::Intf(_)
+            | RvalueKind::Error => (),
+        }
+        self.depth -= 1;
+    }
+
+    fn lvalue(&mut self, prefix: &str, mir: &Lvalue) {
+        let label = match mir.kind {
+            LvalueKind::DestructArray(..) => "destruct_array".to_string(),
+            LvalueKind::DestructStruct(..) => "destruct_struct".to_string(),
+            LvalueKind::Genvar(id) => format!("genvar `{}`", self.name_of(id)),
+            LvalueKind::Var(id) => format!("var `{}`", self.name_of(id)),
+            LvalueKind::Port(id) => format!("port `{}`", self.name_of(id)),
+            LvalueKind::Intf(id) => format!("intf `{}`", self.name_of(id)),
+            LvalueKind::IntfSignal(_, id) => format!("intf_signal `{}`", self.name_of(id)),
+            LvalueKind::Index { length: 0, .. } => "index".to_string(),
+            LvalueKind::Index { length, .. } => format!("index length {}", length),
+            LvalueKind::Member { field, .. } => format!("member {}", field),
+            LvalueKind::Error => "error".to_string(),
+        };
+        self.line(format!("{}lvalue {}: {}", prefix, label, mir.ty));
+        self.depth += 1;
+        match mir.kind {
+            LvalueKind::DestructArray(ref values) => {
+                let mut values: Vec<_> = values.iter().collect();
+                values.sort_by_key(|&(&index, _)| index);
+                for (index, value) in values {
+                    self.lvalue(&format!("[{}] ", index), value);
+                }
+            }
+            LvalueKind::DestructStruct(ref values) => {
+                for value in values {
+                    self.lvalue("", value);
+                }
+            }
+            LvalueKind::IntfSignal(value, _) | LvalueKind::Member { value, .. } => {
+                self.lvalue("", value)
+            }
+            LvalueKind::Index { value, base, .. } => {
+                self.lvalue("", value);
+                self.rvalue("base ", base);
+            }
+            LvalueKind::Genvar(_)
+            | LvalueKind::Var(_)
+            | LvalueKind::Port(_)
+            | LvalueKind::Intf(_)
+            | LvalueKind::Error => (),
+        }
+        self.depth -= 1;
+    }
+}
+
+impl<'a, 'gcx> Visitor<'gcx> for Dumper<'a, 'gcx> {
+    type Context = GlobalContext<'gcx>;
+
+    fn context(&self) -> &Self::Context {
+        self.cx
+    }
+
+    fn visit_proc(&mut self, prok: &'gcx hir::Proc) -> Control {
+        self.nested(prok.span, prok.desc_full(), |this| {
+            hir::walk_proc(this, prok)
+        })
+    }
+
+    fn visit_stmt(&mut self, stmt: &'gcx hir::Stmt) -> Control {
+        self.nested(stmt.span, stmt.desc_full(), |this| {
+            hir::walk_stmt(this, stmt)
+        })
+    }
+
+    fn visit_expr(&mut self, expr: &'gcx hir::Expr<'gcx>, lvalue: bool) -> Control {
+        let text = format!("{} `{}`", expr.desc(), expr.span.extract());
+        if !self.mir {
+            let text = match self.cx.type_of(expr.id, self.env) {
+                Ok(ty) => format!("{}: {}", text, ty),
+                Err(()) => text,
+            };
+            return self.nested(expr.span, text, |this| hir::walk_expr(this, expr, lvalue));
+        }
+        self.nested(expr.span, text, |this| {
+            if lvalue {
+                let mir = this.cx.mir_lvalue(expr.id, this.env);
+                this.lvalue("", mir);
+            } else {
+                let mir = this.cx.mir_rvalue(expr.id, this.env);
+                this.rvalue("", mir);
+            }
+        });
+        Control::SkipChildren
+    }
+
+    fn visit_event_expr(&mut self, expr: &'gcx hir::EventExpr) -> Control {
+        self.nested(expr.span, expr.desc_full(), |this| {
+            hir::walk_event_expr(this, expr)
+        })
+    }
+
+    fn visit_event(&mut self, event: &'gcx hir::Event) -> Control {
+        let text = match event.edge {
+            ast::EdgeIdent::Implicit => "event",
+            ast::EdgeIdent::Edge => "event edge",
+            ast::EdgeIdent::Posedge => "event posedge",
+            ast::EdgeIdent::Negedge => "event negedge",
+        };
+        self.nested(event.span, text, |this| hir::walk_event(this, event))
+    }
+
+    fn visit_typedef(&mut self, typedef: &'gcx hir::Typedef) -> Control {
+        self.node(typedef.span, typedef.desc_full());
+        Control::SkipChildren
+    }
+
+    fn visit_var_decl(&mut self, decl: &'gcx hir::VarDecl) -> Control {
+        let text = match self.cx.type_of(decl.id, self.env) {
+            Ok(ty) => format!("{}: {}", decl.desc_full(), ty),
+            Err(()) => decl.desc_full(),
+        };
+        self.nested(decl.span, text, |this| match decl.init {
+            Some(init) => this.visit_node_with_id(init, false),
+            None => Control::Continue,
+        })
+    }
+
+    fn visit_assign(&mut self, assign: &'gcx hir::Assign) -> Control {
+        self.nested(assign.span, assign.desc_full(), |this| {
+            hir::walk_assign(this, assign)
+        })
+    }
+
+    fn visit_int_port(&mut self, port: &'gcx hir::IntPort) -> Control {
+        let text = match self.cx.type_of(port.id, self.env) {
+            Ok(ty) => format!("{} {} `{}`: {}", port.dir, port.kind, port.name, ty),
+            Err(()) => format!("{} {} `{}`", port.dir, port.kind, port.name),
+        };
+        self.node(port.span, text);
+        Control::SkipChildren
+    }
+
+    fn visit_inst(&mut self, inst: &'gcx hir::Inst<'gcx>) -> Control {
+        self.nested(inst.span(), inst.desc_full(), |this| {
+            hir::walk_inst(this, inst)
+        })
+    }
+
+    fn visit_inst_target(&mut self, target: &'gcx hir::InstTarget<'gcx>) -> Control {
+        self.node(target.span(), target.desc_full());
+        Control::SkipChildren
+    }
+}
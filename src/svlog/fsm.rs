@@ -0,0 +1,461 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Finite state machine extraction.
+//!
+//! This module detects the classic state machine pattern in a module: a state
+//! register which is assigned constant values inside the ways of a `case`
+//! statement on that very register. Both the single-process form (the `case`
+//! sits inside the `always_ff` process) and the two-process form (an
+//! `always_comb` process computes `next_state`, which an `always_ff` process
+//! copies into `state`) are recognized. The recovered transition graph is
+//! intended for documentation and FSM-specific optimizations further down the
+//! line.
+
+use crate::{
+    crate_prelude::*,
+    hir::{HirNode, ModuleId, ProcId, StmtKind},
+    ParamEnv,
+};
+use num::BigInt;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+/// A state machine recovered from a module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fsm {
+    /// The module which contains the state machine.
+    pub module: NodeId,
+    /// The name of the module.
+    pub module_name: Name,
+    /// The parameter environment of the module.
+    pub env: ParamEnv,
+    /// The variable or port holding the current state.
+    pub state: NodeId,
+    /// The name of the state variable.
+    pub name: Name,
+    /// The `case` statement which decodes the current state.
+    pub case: NodeId,
+    /// The states of the machine, in order of appearance.
+    pub states: Vec<FsmState>,
+    /// The index of the state assumed upon reset, if one could be determined.
+    pub reset: Option<usize>,
+    /// The transitions between states.
+    pub transitions: Vec<FsmTransition>,
+}
+
+/// A single state of a state machine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FsmState {
+    /// The value of the state register in this state.
+    pub value: BigInt,
+    /// The name of the state, if it was referred to by a named constant.
+    pub name: Option<Name>,
+}
+
+impl std::fmt::Display for FsmState {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.name {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "{}", self.value),
+        }
+    }
+}
+
+/// A transition between two states of a state machine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FsmTransition {
+    /// The index of the source state.
+    pub from: usize,
+    /// The index of the destination state.
+    pub to: usize,
+    /// The conditions that must hold for the transition to occur, as they
+    /// appear in the source text.
+    pub conds: Vec<String>,
+}
+
+/// Extract the state machines of a module.
+///
+/// Only the procedures directly within the module body are considered;
+/// procedures nested in generate blocks are skipped.
+#[moore_derive::query]
+pub(crate) fn extract_fsms<'a>(
+    cx: &impl Context<'a>,
+    node_id: NodeId,
+    env: ParamEnv,
+) -> Result<Arc<Vec<Fsm>>> {
+    let hir = ModuleId::from_id(cx, node_id)?.hir(cx);
+
+    // Gather the raw assignments and case statements in all procedures.
+    let mut facts = FactCollector {
+        cx,
+        env,
+        conds: vec![],
+        stack: vec![],
+        cases: vec![],
+        assigns: vec![],
+    };
+    for &proc_id in &hir.block.procs {
+        let prok = ProcId::from_id(cx, proc_id)?.hir(cx);
+        facts.collect_stmt(prok.stmt)?;
+    }
+    let FactCollector { cases, assigns, .. } = facts;
+
+    // Assemble a state machine for every variable that is decoded by a case
+    // statement.
+    let mut fsms = vec![];
+    let mut seen = HashSet::new();
+    for (case_index, case) in cases.iter().enumerate() {
+        if !seen.insert(case.selector) {
+            continue;
+        }
+        let state = case.selector;
+        let mut builder = FsmBuilder::default();
+
+        // The values that are decoded by any case statement on the state
+        // register define the initial set of states.
+        let decoders: Vec<usize> = cases
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.selector == state)
+            .map(|(i, _)| i)
+            .collect();
+        for &i in &decoders {
+            for way in &cases[i].ways {
+                for (value, name) in way {
+                    builder.state(value, *name);
+                }
+            }
+        }
+
+        // Determine the signals whose value is copied into the state register.
+        // This covers the two-process form `state <= next_state`.
+        let mut next: HashSet<NodeId> = Some(state).into_iter().collect();
+        for assign in &assigns {
+            if assign.target == state {
+                if let AssignValue::Var(id) = assign.value {
+                    next.insert(id);
+                }
+            }
+        }
+
+        // Derive the transitions from assignments to the next state.
+        let mut reset = None;
+        for assign in &assigns {
+            if !next.contains(&assign.target) {
+                continue;
+            }
+            let (value, name) = match assign.value {
+                AssignValue::Const(ref value, name) => (value, name),
+                _ => continue,
+            };
+            let scope = assign
+                .stack
+                .iter()
+                .rev()
+                .find(|s| cases[s.case].selector == state);
+            let scope = match scope {
+                Some(s) => s,
+                None => {
+                    if assign.target == state && reset.is_none() {
+                        reset = Some(builder.state(value, name));
+                    }
+                    continue;
+                }
+            };
+            let to = builder.state(value, name);
+            let conds = assign.conds[scope.depth..].to_vec();
+            let case = &cases[scope.case];
+            let from: Vec<usize> = match scope.way {
+                Some(way) => case.ways[way]
+                    .iter()
+                    .map(|(v, n)| builder.state(v, *n))
+                    .collect(),
+                None => {
+                    // The default way covers all states not explicitly listed
+                    // in the case statement.
+                    let covered: HashSet<&BigInt> =
+                        case.ways.iter().flatten().map(|(v, _)| v).collect();
+                    builder
+                        .states
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, s)| !covered.contains(&s.value))
+                        .map(|(i, _)| i)
+                        .collect()
+                }
+            };
+            for from in from {
+                builder.transition(from, to, conds.clone());
+            }
+        }
+
+        if builder.states.len() < 2 || builder.transitions.is_empty() {
+            continue;
+        }
+        debug!(
+            "Extracted FSM on {:?} with {} states and {} transitions",
+            state,
+            builder.states.len(),
+            builder.transitions.len()
+        );
+        fsms.push(Fsm {
+            module: node_id,
+            module_name: hir.name.value,
+            env,
+            state,
+            name: state_name(cx, state)?,
+            case: cases[case_index].stmt,
+            states: builder.states,
+            reset,
+            transitions: builder.transitions,
+        });
+    }
+
+    Ok(Arc::new(fsms))
+}
+
+/// Determine the name of a state variable.
+fn state_name<'a>(cx: &impl Context<'a>, id: NodeId) -> Result<Name> {
+    Ok(match cx.hir_of(id)? {
+        HirNode::VarDecl(x) => x.name.value,
+        HirNode::IntPort(x) => x.name.value,
+        x => bug_span!(x.span(), cx, "{} is not a state variable", x.desc_full()),
+    })
+}
+
+/// A case statement that decodes a variable.
+struct CaseFact {
+    /// The case statement.
+    stmt: NodeId,
+    /// The variable being decoded.
+    selector: NodeId,
+    /// The constant values listed for each way.
+    ways: Vec<Vec<(BigInt, Option<Name>)>>,
+}
+
+/// A case way enclosing an assignment.
+struct CaseScope {
+    /// Index into the list of case facts.
+    case: usize,
+    /// The way within the case, or `None` for the default way.
+    way: Option<usize>,
+    /// The number of conditions active upon entering the case statement.
+    depth: usize,
+}
+
+/// An assignment to a variable.
+struct AssignFact {
+    /// The variable being assigned.
+    target: NodeId,
+    /// The value being assigned.
+    value: AssignValue,
+    /// The conditions under which the assignment occurs.
+    conds: Vec<String>,
+    /// The enclosing case ways, from outermost to innermost.
+    stack: Vec<CaseScope>,
+}
+
+/// The value assigned to a variable.
+enum AssignValue {
+    /// A constant, possibly named.
+    Const(BigInt, Option<Name>),
+    /// Another variable or port.
+    Var(NodeId),
+    /// Anything else.
+    Other,
+}
+
+/// A helper to gather the case statements and assignments of a procedure.
+struct FactCollector<'a, C> {
+    cx: &'a C,
+    env: ParamEnv,
+    conds: Vec<String>,
+    stack: Vec<(usize, Option<usize>, usize)>,
+    cases: Vec<CaseFact>,
+    assigns: Vec<AssignFact>,
+}
+
+impl<'a, 'gcx: 'a, C: Context<'gcx>> FactCollector<'a, C> {
+    fn collect_stmt(&mut self, stmt_id: NodeId) -> Result<()> {
+        let stmt = match self.cx.hir_of(stmt_id)? {
+            HirNode::Stmt(x) => x,
+            _ => return Ok(()),
+        };
+        match stmt.kind {
+            StmtKind::Block(ref stmts) | StmtKind::InlineGroup { ref stmts, .. } => {
+                for &id in stmts {
+                    self.collect_stmt(id)?;
+                }
+            }
+            StmtKind::Timed { stmt, .. } | StmtKind::Loop { body: stmt, .. } => {
+                self.collect_stmt(stmt)?;
+            }
+            StmtKind::Assign { lhs, rhs, .. } => {
+                let target = match self.cx.mir_lvalue(lhs, self.env).kind {
+                    mir::LvalueKind::Var(id) | mir::LvalueKind::Port(id) => id,
+                    _ => return Ok(()),
+                };
+                let value = self.assign_value(rhs)?;
+                self.assigns.push(AssignFact {
+                    target,
+                    value,
+                    conds: self.conds.clone(),
+                    stack: self
+                        .stack
+                        .iter()
+                        .map(|&(case, way, depth)| CaseScope { case, way, depth })
+                        .collect(),
+                });
+            }
+            StmtKind::If {
+                cond,
+                main_stmt,
+                else_stmt,
+            } => {
+                let text = self.cx.span(cond).extract();
+                self.conds.push(text.clone());
+                self.collect_stmt(main_stmt)?;
+                self.conds.pop();
+                if let Some(else_stmt) = else_stmt {
+                    self.conds.push(format!("!({})", text));
+                    self.collect_stmt(else_stmt)?;
+                    self.conds.pop();
+                }
+            }
+            StmtKind::Case {
+                expr,
+                ref ways,
+                default,
+                ..
+            } => {
+                // Only case statements on a plain variable are of interest;
+                // others merely contribute conditions.
+                let selector = match self.cx.mir_rvalue(expr, self.env).strip_casts().kind {
+                    mir::RvalueKind::Var(id) | mir::RvalueKind::Port(id) => Some(id),
+                    _ => None,
+                };
+                let case = match selector {
+                    Some(selector) => {
+                        let mut labels = vec![];
+                        for &(ref exprs, _) in ways {
+                            let mut values = vec![];
+                            for &expr in exprs {
+                                if let AssignValue::Const(v, n) = self.assign_value(expr)? {
+                                    values.push((v, n));
+                                }
+                            }
+                            labels.push(values);
+                        }
+                        self.cases.push(CaseFact {
+                            stmt: stmt_id,
+                            selector,
+                            ways: labels,
+                        });
+                        Some(self.cases.len() - 1)
+                    }
+                    None => None,
+                };
+                let expr_text = self.cx.span(expr).extract();
+                for (index, &(ref exprs, stmt)) in ways.iter().enumerate() {
+                    let labels: Vec<_> = exprs.iter().map(|&e| self.cx.span(e).extract()).collect();
+                    let cond = format!("{} == {}", expr_text, labels.join(" | "));
+                    self.with_way(case, Some(index), cond, stmt)?;
+                }
+                if let Some(default) = default {
+                    self.with_way(case, None, format!("{} == default", expr_text), default)?;
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Collect the statement in a case way.
+    ///
+    /// If the way belongs to a case on a variable, it is pushed onto the case
+    /// stack; otherwise the way's condition is tracked instead.
+    fn with_way(
+        &mut self,
+        case: Option<usize>,
+        way: Option<usize>,
+        cond: String,
+        stmt: NodeId,
+    ) -> Result<()> {
+        match case {
+            Some(case) => {
+                self.stack.push((case, way, self.conds.len()));
+                self.collect_stmt(stmt)?;
+                self.stack.pop();
+            }
+            None => {
+                self.conds.push(cond);
+                self.collect_stmt(stmt)?;
+                self.conds.pop();
+            }
+        }
+        Ok(())
+    }
+
+    /// Classify the value of an expression.
+    fn assign_value(&self, expr_id: NodeId) -> Result<AssignValue> {
+        let mir = self.cx.mir_rvalue(expr_id, self.env);
+        if mir.is_error() {
+            return Ok(AssignValue::Other);
+        }
+        if mir.is_const() {
+            let value = self.cx.constant_value_of(expr_id, self.env);
+            let name = match self.cx.hir_of(expr_id)? {
+                HirNode::Expr(hir::Expr {
+                    kind: hir::ExprKind::Ident(name),
+                    ..
+                }) => Some(name.value),
+                _ => None,
+            };
+            return Ok(match value.get_int() {
+                Some(v) => AssignValue::Const(v.clone(), name),
+                None => AssignValue::Other,
+            });
+        }
+        Ok(match mir.strip_casts().kind {
+            mir::RvalueKind::Var(id) | mir::RvalueKind::Port(id) => AssignValue::Var(id),
+            _ => AssignValue::Other,
+        })
+    }
+}
+
+/// A helper to assemble the states and transitions of a state machine.
+#[derive(Default)]
+struct FsmBuilder {
+    states: Vec<FsmState>,
+    indices: HashMap<BigInt, usize>,
+    transitions: Vec<FsmTransition>,
+}
+
+impl FsmBuilder {
+    /// Get the index of a state, creating it if it does not yet exist.
+    fn state(&mut self, value: &BigInt, name: Option<Name>) -> usize {
+        if let Some(&index) = self.indices.get(value) {
+            if self.states[index].name.is_none() {
+                self.states[index].name = name;
+            }
+            return index;
+        }
+        let index = self.states.len();
+        self.states.push(FsmState {
+            value: value.clone(),
+            name,
+        });
+        self.indices.insert(value.clone(), index);
+        index
+    }
+
+    /// Add a transition, unless an identical one already exists.
+    fn transition(&mut self, from: usize, to: usize, conds: Vec<String>) {
+        let t = FsmTransition { from, to, conds };
+        if !self.transitions.contains(&t) {
+            self.transitions.push(t);
+        }
+    }
+}
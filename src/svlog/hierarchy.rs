@@ -0,0 +1,261 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! The instance hierarchy of an elaborated design.
+//!
+//! Starting at a top-level module, the hierarchy lists every module and
+//! interface instance together with the values its parameters assume. Only the
+//! instances directly in a module body are considered; instances in generate
+//! blocks are expanded during code generation and are not listed.
+//!
+//! Each instance is identified by its path, which joins the names of the
+//! instances leading to it with dots, e.g. `top.core.alu`. The path only
+//! depends on the instance names in the source code, such that it stays the
+//! same across invocations.
+
+use crate::{crate_prelude::*, hir::HirNode, resolver::InstTarget, value, ParamEnv, ParamEnvData};
+
+/// An instance in the hierarchy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instance {
+    /// The name of the instance. For top-level modules this is the name of the
+    /// module.
+    pub name: Name,
+    /// The name of the instantiated module or interface.
+    pub target: Name,
+    /// Whether the instance is of an interface rather than a module.
+    pub interface: bool,
+    /// The instantiated module or interface.
+    pub node: NodeId,
+    /// The parametrization of the instantiated module or interface.
+    pub env: ParamEnv,
+    /// The parameters of the instantiated module, and their values.
+    pub params: Vec<(Name, String)>,
+    /// The instances within this instance.
+    pub children: Vec<Instance>,
+}
+
+impl Instance {
+    /// Describe the instantiated module and its parameters, e.g. `foo #(N = 4)`.
+    pub fn target_desc(&self) -> String {
+        if self.params.is_empty() {
+            return self.target.to_string();
+        }
+        let params: Vec<_> = self
+            .params
+            .iter()
+            .map(|(name, value)| format!("{} = {}", name, value))
+            .collect();
+        format!("{} #({})", self.target, params.join(", "))
+    }
+
+    /// Find an instance by its path, e.g. `top.core.alu`.
+    ///
+    /// The path starts with the name of this instance.
+    pub fn find(&self, path: &str) -> Option<&Instance> {
+        let mut names = path.split('.');
+        if names.next()? != &*self.name.as_str() {
+            return None;
+        }
+        names.try_fold(self, |inst, name| {
+            inst.children
+                .iter()
+                .find(|child| &*child.name.as_str() == name)
+        })
+    }
+
+    /// Iterate over this instance and the ones within it, together with their
+    /// path.
+    ///
+    /// Instances are visited before the ones within them, and in the order
+    /// they appear in the source code.
+    pub fn iter(&self) -> Iter {
+        Iter {
+            stack: vec![(self.name.to_string(), self)],
+        }
+    }
+}
+
+/// An iterator over the instances in a hierarchy.
+///
+/// See [`Instance::iter`].
+pub struct Iter<'a> {
+    stack: Vec<(String, &'a Instance)>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (String, &'a Instance);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, inst) = self.stack.pop()?;
+        self.stack.extend(
+            inst.children
+                .iter()
+                .rev()
+                .map(|child| (format!("{}.{}", path, child.name), child)),
+        );
+        Some((path, inst))
+    }
+}
+
+/// Determine the instance hierarchy below a top-level module.
+pub fn instance_tree<'gcx>(cx: &GlobalContext<'gcx>, top: NodeId) -> Option<Instance> {
+    instance_tree_with_env(cx, top, cx.default_param_env())
+}
+
+/// Determine the instance hierarchy below a top-level module, for a specific
+/// parametrization of that module.
+pub fn instance_tree_with_env<'gcx>(
+    cx: &GlobalContext<'gcx>,
+    top: NodeId,
+    env: ParamEnv,
+) -> Option<Instance> {
+    let hir = match cx.hir_of(top) {
+        Ok(HirNode::Module(x)) => x,
+        _ => return None,
+    };
+    let mut stack = vec![];
+    Some(module_instance(cx, hir, hir.name.value, env, &mut stack))
+}
+
+/// Determine the parametrization of a top-level module with some of its value
+/// parameters overridden by integers, e.g. from the command line.
+///
+/// Emits an error for each parameter which the module does not have, or which
+/// cannot hold an integer.
+pub fn top_param_env<'gcx>(
+    cx: &GlobalContext<'gcx>,
+    top: NodeId,
+    overrides: &[(&str, i64)],
+) -> Result<ParamEnv> {
+    let hir = match cx.hir_of(top)? {
+        HirNode::Module(x) => x,
+        _ => return Err(()),
+    };
+    let default_env = cx.default_param_env();
+    let mut env_data = ParamEnvData::default();
+    let mut failed = false;
+    for &(name, value) in overrides {
+        let param = hir.params.iter().find_map(|&id| match cx.hir_of(id) {
+            Ok(HirNode::ValueParam(param))
+                if !param.local && &*param.name.value.as_str() == name =>
+            {
+                Some(param)
+            }
+            _ => None,
+        });
+        let param = match param {
+            Some(x) => x,
+            None => {
+                cx.emit(
+                    DiagBuilder2::error(format!(
+                        "module `{}` has no value parameter `{}`",
+                        hir.name, name
+                    ))
+                    .span(hir.name.span),
+                );
+                failed = true;
+                continue;
+            }
+        };
+        let ty = cx.type_of(param.id, default_env)?;
+        if ty.get_simple_bit_vector().is_none() {
+            cx.emit(
+                DiagBuilder2::error(format!(
+                    "cannot assign integer to {} of type `{}`",
+                    param.desc_full(),
+                    ty
+                ))
+                .span(param.name.span),
+            );
+            failed = true;
+            continue;
+        }
+        let value = cx.intern_value(value::make_int(ty, value.into()));
+        env_data.set_value(param.id, value);
+    }
+    if failed {
+        return Err(());
+    }
+    Ok(cx.intern_param_env(env_data))
+}
+
+/// Render the values of a list of parameters in a parametrization.
+pub fn param_values<'gcx>(
+    cx: &GlobalContext<'gcx>,
+    params: &[NodeId],
+    env: ParamEnv,
+) -> Vec<(Name, String)> {
+    params
+        .iter()
+        .flat_map(|&id| match cx.hir_of(id) {
+            Ok(HirNode::ValueParam(param)) => {
+                let value = cx.constant_value_of(id, env);
+                Some((param.name.value, value.kind.to_string()))
+            }
+            Ok(HirNode::TypeParam(param)) => {
+                let ty = match cx.map_to_type(Ref(cx.ast_for_id(id)), env) {
+                    Some(ty) => ty.to_string(),
+                    None => "<error>".to_string(),
+                };
+                Some((param.name.value, ty))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn module_instance<'gcx>(
+    cx: &GlobalContext<'gcx>,
+    hir: &'gcx hir::Module<'gcx>,
+    name: Name,
+    env: ParamEnv,
+    stack: &mut Vec<(NodeId, ParamEnv)>,
+) -> Instance {
+    let mut instance = Instance {
+        name,
+        target: hir.name.value,
+        interface: false,
+        node: hir.ast.id(),
+        env,
+        params: param_values(cx, hir.params, env),
+        children: vec![],
+    };
+
+    // Guard against modules which instantiate themselves.
+    let key = (hir.ast.id(), env);
+    if stack.contains(&key) {
+        return instance;
+    }
+    stack.push(key);
+    for &inst_id in &hir.block.insts {
+        let inst = match cx.hir_of(inst_id) {
+            Ok(HirNode::Inst(x)) => x,
+            _ => continue,
+        };
+        let details = match cx.inst_details(Ref(inst), env) {
+            Ok(x) => x,
+            Err(()) => continue,
+        };
+        let name = inst.ast.name.value;
+        let child = match details.target.kind {
+            InstTarget::Module(ast) => match cx.hir_of(ast.id()) {
+                Ok(HirNode::Module(hir)) => {
+                    module_instance(cx, hir, name, details.inner_env, stack)
+                }
+                _ => continue,
+            },
+            InstTarget::Interface(ast) => Instance {
+                name,
+                target: ast.name.value,
+                interface: true,
+                node: ast.id(),
+                env: details.inner_env,
+                params: vec![],
+                children: vec![],
+            },
+        };
+        instance.children.push(child);
+    }
+    stack.pop();
+    instance
+}
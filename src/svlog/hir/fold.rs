@@ -0,0 +1,435 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! An implementation of the folder pattern for the HIR.
+//!
+//! This module defines the [`Folder`] trait, the counterpart of [`Visitor`]
+//! that rebuilds the procedures, statements, and expressions it visits. A pass
+//! overrides the methods for the nodes it wants to change and returns a new
+//! node built with [`rebuild_proc`], [`rebuild_stmt`], or [`rebuild_expr`]. The
+//! default methods fold the children of a node and only rebuild it if one of
+//! them changed, such that an unmodified tree is returned as it is.
+//!
+//! Rebuilt nodes receive a fresh node id and are interned into the context,
+//! such that `hir_of` returns them like any other node. They inherit the AST
+//! node, span, and parent of the node they replace, which makes names and types
+//! resolve the same way as in the original tree.
+//!
+//! [`Visitor`]: super::Visitor
+
+use super::{nodes::*, HirNode};
+use crate::{
+    ast::{self, AnyNode as _},
+    ast_map::AstNode,
+    common::{source::Span, NodeId},
+    Context, QueryDatabase,
+};
+
+/// A folder of the HIR.
+pub trait Folder<'a>: Sized {
+    /// The type of context that this folder uses.
+    type Context: Context<'a>;
+
+    /// Get the context to be used to resolve queries and allocate nodes.
+    fn context(&self) -> &Self::Context;
+
+    /// Fold the node with an id, returning the id of the folded node.
+    ///
+    /// Nodes other than procedures, statements, and expressions are returned
+    /// as they are.
+    fn fold_node_with_id(&mut self, node_id: NodeId, lvalue: bool) -> NodeId {
+        match self.context().hir_of(node_id) {
+            Ok(HirNode::Proc(x)) => self.fold_proc(x).id,
+            Ok(HirNode::Stmt(x)) => self.fold_stmt(x).id,
+            Ok(HirNode::Expr(x)) => self.fold_expr(x, lvalue).id,
+            _ => node_id,
+        }
+    }
+
+    fn fold_proc(&mut self, prok: &'a Proc) -> &'a Proc {
+        fold_proc_contents(self, prok)
+    }
+
+    fn fold_stmt(&mut self, stmt: &'a Stmt) -> &'a Stmt {
+        fold_stmt_contents(self, stmt)
+    }
+
+    fn fold_expr(&mut self, expr: &'a Expr<'a>, lvalue: bool) -> &'a Expr<'a> {
+        fold_expr_contents(self, expr, lvalue)
+    }
+}
+
+/// Fold the contents of a procedure.
+pub fn fold_proc_contents<'a>(folder: &mut impl Folder<'a>, prok: &'a Proc) -> &'a Proc {
+    let stmt = folder.fold_node_with_id(prok.stmt, false);
+    if stmt == prok.stmt {
+        prok
+    } else {
+        rebuild_proc(folder.context(), prok, stmt)
+    }
+}
+
+/// Fold the contents of a statement.
+pub fn fold_stmt_contents<'a>(folder: &mut impl Folder<'a>, stmt: &'a Stmt) -> &'a Stmt {
+    let mut fold = |id: NodeId| folder.fold_node_with_id(id, false);
+    let kind = match stmt.kind {
+        StmtKind::Null => StmtKind::Null,
+        StmtKind::Block(ref stmts) => StmtKind::Block(stmts.iter().map(|&id| fold(id)).collect()),
+        StmtKind::Assign { lhs, rhs, kind } => StmtKind::Assign {
+            lhs: folder.fold_node_with_id(lhs, true),
+            rhs: folder.fold_node_with_id(rhs, false),
+            kind: match kind {
+                AssignKind::NonblockDelay(id) => {
+                    AssignKind::NonblockDelay(folder.fold_node_with_id(id, false))
+                }
+                kind => kind,
+            },
+        },
+        StmtKind::Timed { control, stmt } => StmtKind::Timed {
+            control: match control {
+                TimingControl::Delay(id) => TimingControl::Delay(fold(id)),
+                TimingControl::ImplicitEvent => TimingControl::ImplicitEvent,
+                TimingControl::ExplicitEvent(id) => TimingControl::ExplicitEvent(fold(id)),
+            },
+            stmt: fold(stmt),
+        },
+        StmtKind::Expr(expr) => StmtKind::Expr(fold(expr)),
+        StmtKind::If {
+            cond,
+            main_stmt,
+            else_stmt,
+        } => StmtKind::If {
+            cond: fold(cond),
+            main_stmt: fold(main_stmt),
+            else_stmt: else_stmt.map(&mut fold),
+        },
+        StmtKind::Loop { kind, body } => StmtKind::Loop {
+            kind: match kind {
+                LoopKind::Forever => LoopKind::Forever,
+                LoopKind::Repeat(id) => LoopKind::Repeat(fold(id)),
+                LoopKind::While(id) => LoopKind::While(fold(id)),
+                LoopKind::Do(id) => LoopKind::Do(fold(id)),
+                LoopKind::For(init, cond, step) => {
+                    LoopKind::For(fold(init), fold(cond), fold(step))
+                }
+            },
+            body: fold(body),
+        },
+        StmtKind::Cover { expr, stmt } => StmtKind::Cover {
+            expr: fold(expr),
+            stmt: fold(stmt),
+        },
+        StmtKind::InlineGroup { ref stmts, rib } => StmtKind::InlineGroup {
+            stmts: stmts.iter().map(|&id| fold(id)).collect(),
+            rib,
+        },
+        StmtKind::Case {
+            expr,
+            ref ways,
+            default,
+            kind,
+        } => StmtKind::Case {
+            expr: fold(expr),
+            ways: ways
+                .iter()
+                .map(|&(ref exprs, stmt)| (exprs.iter().map(|&id| fold(id)).collect(), fold(stmt)))
+                .collect(),
+            default: default.map(&mut fold),
+            kind,
+        },
+    };
+    if kind == stmt.kind {
+        stmt
+    } else {
+        rebuild_stmt(folder.context(), stmt, kind)
+    }
+}
+
+/// Fold the contents of an expression.
+pub fn fold_expr_contents<'a>(
+    folder: &mut impl Folder<'a>,
+    expr: &'a Expr<'a>,
+    lvalue: bool,
+) -> &'a Expr<'a> {
+    let mut fold = |id: NodeId, lvalue: bool| folder.fold_node_with_id(id, lvalue);
+    let kind = match expr.kind {
+        ExprKind::Builtin(BuiltinCall::Unsupported)
+        | ExprKind::Builtin(BuiltinCall::Bits(_))
+        | ExprKind::IntConst { .. }
+        | ExprKind::UnsizedConst(_)
+        | ExprKind::TimeConst(_)
+        | ExprKind::RealConst(_)
+        | ExprKind::StringConst(_)
+        | ExprKind::Ident(_) => return expr,
+        ExprKind::Unary(op, arg) => {
+            let lvalue = match op {
+                UnaryOp::PreInc | UnaryOp::PreDec | UnaryOp::PostInc | UnaryOp::PostDec => true,
+                _ => false,
+            };
+            ExprKind::Unary(op, fold(arg, lvalue))
+        }
+        ExprKind::Binary(op, lhs, rhs) => ExprKind::Binary(op, fold(lhs, false), fold(rhs, false)),
+        ExprKind::Field(expr, name) => ExprKind::Field(fold(expr, lvalue), name),
+        ExprKind::Index(expr, mode) => ExprKind::Index(
+            fold(expr, lvalue),
+            match mode {
+                IndexMode::One(expr) => IndexMode::One(fold(expr, false)),
+                IndexMode::Many(mode, lhs, rhs) => {
+                    IndexMode::Many(mode, fold(lhs, false), fold(rhs, false))
+                }
+            },
+        ),
+        ExprKind::Builtin(BuiltinCall::Clog2(arg)) => {
+            ExprKind::Builtin(BuiltinCall::Clog2(fold(arg, false)))
+        }
+        ExprKind::Builtin(BuiltinCall::Signed(arg)) => {
+            ExprKind::Builtin(BuiltinCall::Signed(fold(arg, false)))
+        }
+        ExprKind::Builtin(BuiltinCall::Unsigned(arg)) => {
+            ExprKind::Builtin(BuiltinCall::Unsigned(fold(arg, false)))
+        }
+        ExprKind::Ternary(cond, true_expr, false_expr) => ExprKind::Ternary(
+            fold(cond, false),
+            fold(true_expr, lvalue),
+            fold(false_expr, lvalue),
+        ),
+        ExprKind::Scope(expr, name) => ExprKind::Scope(fold(expr, false), name),
+        ExprKind::PositionalPattern(ref exprs) => {
+            ExprKind::PositionalPattern(exprs.iter().map(|&id| fold(id, lvalue)).collect())
+        }
+        ExprKind::NamedPattern(ref mappings) => ExprKind::NamedPattern(
+            mappings
+                .iter()
+                .map(|&(key, value)| {
+                    let key = match key {
+                        PatternMapping::Type(ty) => PatternMapping::Type(fold(ty, false)),
+                        PatternMapping::Member(expr) => PatternMapping::Member(fold(expr, false)),
+                        PatternMapping::Default => PatternMapping::Default,
+                    };
+                    (key, fold(value, lvalue))
+                })
+                .collect(),
+        ),
+        ExprKind::RepeatPattern(count, ref exprs) => ExprKind::RepeatPattern(
+            fold(count, false),
+            exprs.iter().map(|&id| fold(id, lvalue)).collect(),
+        ),
+        ExprKind::Concat(repeat, ref exprs) => ExprKind::Concat(
+            repeat.map(|id| fold(id, false)),
+            exprs.iter().map(|&id| fold(id, lvalue)).collect(),
+        ),
+        ExprKind::Cast(ty, expr) => ExprKind::Cast(fold(ty, false), fold(expr, false)),
+        ExprKind::CastSign(sign, expr) => ExprKind::CastSign(sign, fold(expr, false)),
+        ExprKind::CastSize(size_expr, expr) => {
+            ExprKind::CastSize(fold(size_expr, false), fold(expr, false))
+        }
+        ExprKind::Inside(expr, ref ranges) => ExprKind::Inside(
+            fold(expr, false),
+            ranges
+                .iter()
+                .map(|r| {
+                    r.map(|range| match range {
+                        InsideRange::Single(expr) => InsideRange::Single(fold(expr, false)),
+                        InsideRange::Range(lo, hi) => {
+                            InsideRange::Range(fold(lo, false), fold(hi, false))
+                        }
+                    })
+                })
+                .collect(),
+        ),
+        ExprKind::FunctionCall(target, ref args) => ExprKind::FunctionCall(
+            target,
+            args.iter()
+                .map(|&arg| CallArg {
+                    expr: arg.expr.map(|id| fold(id, false)),
+                    ..arg
+                })
+                .collect(),
+        ),
+        ExprKind::Assign { op, lhs, rhs } => {
+            let lhs_id = folder.fold_node_with_id(lhs.id, true);
+            let rhs_id = folder.fold_node_with_id(rhs.id, false);
+            let cx = folder.context();
+            ExprKind::Assign {
+                op,
+                lhs: ast_of_expr(cx, lhs_id).unwrap_or(lhs),
+                rhs: ast_of_expr(cx, rhs_id).unwrap_or(rhs),
+            }
+        }
+    };
+    if kind == expr.kind {
+        expr
+    } else {
+        rebuild_expr(folder.context(), expr, kind)
+    }
+}
+
+/// Allocate a copy of a procedure with a different body.
+pub fn rebuild_proc<'a>(cx: &impl Context<'a>, prok: &'a Proc, stmt: NodeId) -> &'a Proc {
+    let id = inherit_id(cx, prok.id, prok.span);
+    let hir = cx.arena().alloc_hir(Proc {
+        id,
+        span: prok.span,
+        kind: prok.kind,
+        stmt,
+    });
+    cx.intern_hir(id, HirNode::Proc(hir));
+    hir
+}
+
+/// Allocate a copy of a statement with a different kind.
+pub fn rebuild_stmt<'a>(cx: &impl Context<'a>, stmt: &'a Stmt, kind: StmtKind) -> &'a Stmt {
+    let id = inherit_id(cx, stmt.id, stmt.span);
+    let hir = cx.arena().alloc_hir(Stmt {
+        id,
+        label: stmt.label,
+        span: stmt.span,
+        kind,
+    });
+    cx.intern_hir(id, HirNode::Stmt(hir));
+    hir
+}
+
+/// Allocate a copy of an expression with a different kind.
+///
+/// Since an expression takes its node id from its AST node, the copy is backed
+/// by a copy of that AST node.
+pub fn rebuild_expr<'a>(
+    cx: &impl Context<'a>,
+    expr: &'a Expr<'a>,
+    kind: ExprKind<'a>,
+) -> &'a Expr<'a> {
+    let ast = cx.arena().alloc_ast_expr(ast::Expr {
+        id: NodeId::alloc(),
+        ..expr.ast.clone()
+    });
+    if let Some(parent) = expr.ast.get_parent() {
+        ast.link_attach(parent, expr.ast.order());
+    }
+    let id = cx.map_ast(AstNode::Expr(ast));
+    if let Some(parent) = cx.parent_node_id(expr.id) {
+        cx.set_parent(id, parent);
+    }
+    let hir = cx.arena().alloc_hir(Expr { ast, kind });
+    cx.intern_hir(id, HirNode::Expr(hir));
+    hir
+}
+
+/// Allocate a node id that inherits the span, AST node, and parent of another.
+fn inherit_id<'a>(cx: &impl Context<'a>, node_id: NodeId, span: Span) -> NodeId {
+    let id = cx.alloc_id(span);
+    if let Ok(ast) = cx.ast_of(node_id) {
+        cx.set_ast(id, ast);
+    }
+    if let Some(parent) = cx.parent_node_id(node_id) {
+        cx.set_parent(id, parent);
+    }
+    id
+}
+
+/// Find the AST node of an expression.
+fn ast_of_expr<'a>(cx: &impl Context<'a>, node_id: NodeId) -> Option<&'a ast::Expr<'a>> {
+    match cx.hir_of(node_id) {
+        Ok(HirNode::Expr(expr)) => Some(expr.ast),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{with_context, BaseContext, GlobalContext};
+
+    /// A folder that swaps the operands of additions.
+    struct SwapAdd<'a, 'gcx> {
+        cx: &'a GlobalContext<'gcx>,
+        swapped: usize,
+    }
+
+    impl<'a, 'gcx> Folder<'gcx> for SwapAdd<'a, 'gcx> {
+        type Context = GlobalContext<'gcx>;
+
+        fn context(&self) -> &GlobalContext<'gcx> {
+            self.cx
+        }
+
+        fn fold_expr(&mut self, expr: &'gcx Expr<'gcx>, lvalue: bool) -> &'gcx Expr<'gcx> {
+            let expr = fold_expr_contents(self, expr, lvalue);
+            match expr.kind {
+                ExprKind::Binary(BinaryOp::Add, lhs, rhs) => {
+                    self.swapped += 1;
+                    rebuild_expr(self.cx, expr, ExprKind::Binary(BinaryOp::Add, rhs, lhs))
+                }
+                _ => expr,
+            }
+        }
+    }
+
+    /// A folder that leaves every node as it is.
+    struct Identity<'a, 'gcx>(&'a GlobalContext<'gcx>);
+
+    impl<'a, 'gcx> Folder<'gcx> for Identity<'a, 'gcx> {
+        type Context = GlobalContext<'gcx>;
+
+        fn context(&self) -> &GlobalContext<'gcx> {
+            self.0
+        }
+    }
+
+    /// Find the right-hand side of the assignment in an `initial` procedure.
+    fn assign_rhs<'gcx>(cx: &GlobalContext<'gcx>, prok: &'gcx Proc) -> NodeId {
+        match cx.hir_of(prok.stmt) {
+            Ok(HirNode::Stmt(Stmt {
+                kind: StmtKind::Assign { rhs, .. },
+                ..
+            })) => *rhs,
+            x => panic!("expected an assignment, got {:?}", x),
+        }
+    }
+
+    fn binary_operands<'gcx>(cx: &GlobalContext<'gcx>, id: NodeId) -> (NodeId, NodeId) {
+        match cx.hir_of(id) {
+            Ok(HirNode::Expr(Expr {
+                kind: ExprKind::Binary(BinaryOp::Add, lhs, rhs),
+                ..
+            })) => (*lhs, *rhs),
+            x => panic!("expected an addition, got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn swap_operands() {
+        with_context(
+            "module top; int a, b; initial a = b + (1 + b); endmodule\n",
+            |cx| {
+                let (_, module) = cx.modules().next().unwrap();
+                let module = match cx.hir_of(module) {
+                    Ok(HirNode::Module(x)) => x,
+                    x => panic!("expected a module, got {:?}", x),
+                };
+                let prok = match cx.hir_of(module.block.procs[0]) {
+                    Ok(HirNode::Proc(x)) => x,
+                    x => panic!("expected a procedure, got {:?}", x),
+                };
+
+                // A folder that changes nothing returns the original tree.
+                assert!(std::ptr::eq(Identity(cx).fold_proc(prok), prok));
+
+                // Both additions are swapped, and the original tree remains.
+                let mut folder = SwapAdd { cx, swapped: 0 };
+                let folded = folder.fold_proc(prok);
+                assert_eq!(folder.swapped, 2);
+                assert_ne!(folded.id, prok.id);
+                let (outer_lhs, outer_rhs) = binary_operands(cx, assign_rhs(cx, prok));
+                let (lhs, rhs) = binary_operands(cx, assign_rhs(cx, folded));
+                assert_eq!(rhs, outer_lhs);
+                assert_ne!(lhs, outer_rhs);
+                let (inner_lhs, inner_rhs) = binary_operands(cx, outer_rhs);
+                assert_eq!(binary_operands(cx, lhs), (inner_rhs, inner_lhs));
+
+                // The rebuilt nodes resolve like the ones they replace.
+                assert_eq!(cx.parent_node_id(lhs), cx.parent_node_id(outer_rhs));
+                assert_eq!(cx.span(folded.id), cx.span(prok.id));
+            },
+        );
+    }
+}
@@ -0,0 +1,91 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Node ids that are known to refer to a specific kind of HIR node.
+//!
+//! A typed id is created from a [`NodeId`] once, which checks the kind of node
+//! it refers to. Afterwards [`hir`] returns the node itself rather than a
+//! [`HirNode`], such that code which expects a module or a procedure does not
+//! have to match on the node kind and panic on anything else.
+//!
+//! [`hir`]: ModuleId::hir
+
+use super::{nodes::*, HirNode};
+use crate::crate_prelude::*;
+
+macro_rules! declare_ids {
+    ($($(#[$doc:meta])* $name:ident => $variant:ident($node:ty), $desc:expr;)*) => {$(
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub struct $name(NodeId);
+
+        impl $name {
+            /// Check that a node id refers to this kind of node.
+            ///
+            /// Emits a bug diagnostic if the node is of a different kind.
+            pub fn from_id<'a>(cx: &impl Context<'a>, id: NodeId) -> Result<Self> {
+                match cx.hir_of(id)? {
+                    HirNode::$variant(_) => Ok($name(id)),
+                    other => {
+                        cx.emit(
+                            DiagBuilder2::bug(format!(
+                                "expected {}, found {}",
+                                $desc,
+                                other.desc_full()
+                            ))
+                            .span(other.human_span()),
+                        );
+                        Err(())
+                    }
+                }
+            }
+
+            /// Get the node id.
+            pub fn id(self) -> NodeId {
+                self.0
+            }
+
+            /// Get the HIR node this id refers to.
+            pub fn hir<'a>(self, cx: &impl Context<'a>) -> &'a $node {
+                match cx.hir_of(self.0) {
+                    Ok(HirNode::$variant(x)) => x,
+                    _ => unreachable!("{:?} no longer refers to {}", self.0, $desc),
+                }
+            }
+        }
+
+        impl From<$name> for NodeId {
+            fn from(id: $name) -> NodeId {
+                id.0
+            }
+        }
+    )*};
+}
+
+declare_ids! {
+    /// The id of a module.
+    ModuleId => Module(Module<'a>), "a module";
+    /// The id of an interface.
+    InterfaceId => Interface(Interface<'a>), "an interface";
+    /// The id of a module or interface port, as seen from the inside.
+    PortId => IntPort(IntPort<'a>), "a port";
+    /// The id of an instantiation.
+    InstId => Inst(Inst<'a>), "an instantiation";
+    /// The id of a variable or net declaration.
+    VarDeclId => VarDecl(VarDecl), "a variable or net declaration";
+    /// The id of a procedure.
+    ProcId => Proc(Proc), "a procedure";
+    /// The id of a statement.
+    StmtId => Stmt(Stmt), "a statement";
+    /// The id of an expression.
+    ExprId => Expr(Expr<'a>), "an expression";
+    /// The id of an event expression.
+    EventExprId => EventExpr(EventExpr), "an event expression";
+    /// The id of a generate block.
+    GenId => Gen(Gen), "a generate block";
+    /// The id of a continuous assignment.
+    AssignId => Assign(Assign), "a continuous assignment";
+    /// The id of a package.
+    PackageId => Package(Package), "a package";
+    /// The id of a function or task.
+    SubroutineId => Subroutine(Subroutine), "a function or task";
+}
@@ -16,7 +16,9 @@ pub enum Hint {
     Expr,
 }
 
-pub(crate) fn hir_of<'gcx>(cx: &impl Context<'gcx>, node_id: NodeId) -> Result<HirNode<'gcx>> {
+/// Lower an AST node to HIR.
+#[moore_derive::query]
+pub(crate) fn hir_of<'a>(cx: &impl Context<'a>, node_id: NodeId) -> Result<HirNode<'a>> {
     if let Some(hir) = cx.get_interned_hir(node_id) {
         return Ok(hir);
     }
@@ -48,12 +50,15 @@ pub(crate) fn hir_of<'gcx>(cx: &impl Context<'gcx>, node_id: NodeId) -> Result<H
                 } else {
                     if !is_pos {
                         cx.emit(
-                            DiagBuilder2::warning("positional parameters must appear before named")
-                                .span(param.span)
-                                .add_note(format!(
-                                    "assuming this refers to argument #{}",
-                                    pos_params.len() + 1
-                                )),
+                            DiagBuilder2::lint(
+                                lints::ARG_ORDER,
+                                "positional parameters must appear before named",
+                            )
+                            .span(param.span)
+                            .add_note(format!(
+                                "assuming this refers to argument #{}",
+                                pos_params.len() + 1
+                            )),
                         );
                     }
                     pos_params.push((param.span, Some(value_id)));
@@ -71,7 +76,7 @@ pub(crate) fn hir_of<'gcx>(cx: &impl Context<'gcx>, node_id: NodeId) -> Result<H
             let mut pos_ports = vec![];
             let mut has_wildcard_port = false;
             let mut is_pos = true;
-            for port in &inst.conns {
+            for port in inst.conns {
                 match port.data {
                     ast::PortConnData::Auto => has_wildcard_port = true,
                     ast::PortConnData::Named(name, ref mode) => {
@@ -95,12 +100,15 @@ pub(crate) fn hir_of<'gcx>(cx: &impl Context<'gcx>, node_id: NodeId) -> Result<H
                     ast::PortConnData::Positional(ref expr) => {
                         if !is_pos {
                             cx.emit(
-                                DiagBuilder2::warning("positional port must appear before named")
-                                    .span(port.span)
-                                    .add_note(format!(
-                                        "assuming this refers to argument #{}",
-                                        pos_ports.len() + 1
-                                    )),
+                                DiagBuilder2::lint(
+                                    lints::ARG_ORDER,
+                                    "positional port must appear before named",
+                                )
+                                .span(port.span)
+                                .add_note(format!(
+                                    "assuming this refers to argument #{}",
+                                    pos_ports.len() + 1
+                                )),
                             );
                         }
                         let value_id = cx.map_ast_with_parent(AstNode::Expr(expr), node_id);
@@ -344,13 +352,29 @@ pub(crate) fn hir_of<'gcx>(cx: &impl Context<'gcx>, node_id: NodeId) -> Result<H
                         kind,
                     }
                 }
-                ast::AssertionStmt { .. } => {
-                    cx.emit(
-                        DiagBuilder2::warning("unsupported: immediate assertion; ignored")
+                ast::AssertionStmt(ref assertion) => match assertion.data {
+                    ast::AssertionData::Immediate(ast::BlockingAssertion::Cover(
+                        ref expr,
+                        ref stmt,
+                    ))
+                    | ast::AssertionData::Deferred(
+                        _,
+                        ast::BlockingAssertion::Cover(ref expr, ref stmt),
+                    ) => hir::StmtKind::Cover {
+                        expr: cx.map_ast_with_parent(AstNode::Expr(expr), node_id),
+                        stmt: cx.map_ast_with_parent(AstNode::Stmt(stmt), node_id),
+                    },
+                    _ => {
+                        cx.emit(
+                            DiagBuilder2::lint(
+                                lints::UNSUPPORTED,
+                                "unsupported: immediate assertion; ignored",
+                            )
                             .span(stmt.human_span()),
-                    );
-                    hir::StmtKind::Null
-                }
+                        );
+                        hir::StmtKind::Null
+                    }
+                },
                 _ => {
                     error!("{:#?}", stmt);
                     bug_span!(
@@ -381,12 +405,12 @@ pub(crate) fn hir_of<'gcx>(cx: &impl Context<'gcx>, node_id: NodeId) -> Result<H
         }
         AstNode::GenIf(gen) => {
             let cond = cx.map_ast_with_parent(AstNode::Expr(&gen.cond), node_id);
-            let main_body = lower_module_block(cx, node_id, &gen.main_block.items, false, false)?;
+            let main_body = lower_module_block(cx, node_id, gen.main_block.items, false, false)?;
             let else_body = match gen.else_block {
                 Some(ref else_block) => Some(lower_module_block(
                     cx,
                     node_id,
-                    &else_block.items,
+                    else_block.items,
                     false,
                     false,
                 )?),
@@ -408,7 +432,7 @@ pub(crate) fn hir_of<'gcx>(cx: &impl Context<'gcx>, node_id: NodeId) -> Result<H
             let rib = *init.last().unwrap();
             let cond = cx.map_ast_with_parent(AstNode::Expr(&gen.cond), rib);
             let step = cx.map_ast_with_parent(AstNode::Expr(&gen.step), rib);
-            let body = lower_module_block(cx, rib, &gen.block.items, false, false)?;
+            let body = lower_module_block(cx, rib, gen.block.items, false, false)?;
             let hir = hir::Gen {
                 id: node_id,
                 span: gen.span(),
@@ -531,7 +555,7 @@ pub(crate) fn hir_of_module<'a>(
     next_rib = ports_new.tail_rib;
 
     // Lower the module body.
-    let block = lower_module_block(cx, next_rib, &ast.items, true, false)?;
+    let block = lower_module_block(cx, next_rib, ast.items, true, false)?;
 
     // Create the HIR module.
     let hir = hir::Module {
@@ -564,7 +588,7 @@ pub(crate) fn hir_of_interface<'a>(
     let ports = cx.canonicalize_ports(ast);
 
     // Lower the interface body.
-    let block = lower_module_block(cx, ast.id(), &ast.items, true, true)?;
+    let block = lower_module_block(cx, ast.id(), ast.items, true, true)?;
 
     // Create the HIR node.
     let hir = hir::Interface { ast, ports, block };
@@ -613,15 +637,19 @@ fn lower_module_block<'gcx>(
                 // next_rib = id;
                 // procs.push(id);
                 cx.emit(
-                    DiagBuilder2::warning("unsupported: interface declaration; ignored")
-                        .span(decl.span),
+                    DiagBuilder2::lint(
+                        lints::UNSUPPORTED,
+                        "unsupported: interface declaration; ignored",
+                    )
+                    .span(decl.span),
                 );
             }
             ast::ItemData::ProgramDecl(ref _decl) => {
                 // let id = cx.map_ast_with_parent(AstNode::Program(decl), next_rib);
                 // next_rib = id;
                 // procs.push(id);
-                cx.emit(DiagBuilder2::warning(
+                cx.emit(DiagBuilder2::lint(
+                    lints::UNSUPPORTED,
                     "unsupported: program declaration; ignored",
                 ));
             }
@@ -629,7 +657,7 @@ fn lower_module_block<'gcx>(
                 let target_id = cx.map_ast_with_parent(AstNode::InstTarget(inst), next_rib);
                 next_rib = target_id;
                 trace!("instantiation target `{}` => {:?}", inst.target, target_id);
-                for inst in &inst.names {
+                for inst in inst.names {
                     let inst_id = cx.map_ast_with_parent(AstNode::Inst(inst, target_id), next_rib);
                     trace!("instantiation `{}` => {:?}", inst.name, inst_id);
                     next_rib = inst_id;
@@ -704,8 +732,11 @@ fn lower_module_block<'gcx>(
             }
             ast::ItemData::ClassDecl(ref decl) => {
                 cx.emit(
-                    DiagBuilder2::warning("unsupported: class declaration; ignored")
-                        .span(decl.span),
+                    DiagBuilder2::lint(
+                        lints::UNSUPPORTED,
+                        "unsupported: class declaration; ignored",
+                    )
+                    .span(decl.span),
                 );
             }
             ast::ItemData::SubroutineDecl(ref decl) => {
@@ -714,8 +745,11 @@ fn lower_module_block<'gcx>(
             }
             ast::ItemData::Assertion(ref assert) => {
                 cx.emit(
-                    DiagBuilder2::warning("unsupported: concurrent assertion; ignored")
-                        .span(assert.span),
+                    DiagBuilder2::lint(
+                        lints::UNSUPPORTED,
+                        "unsupported: concurrent assertion; ignored",
+                    )
+                    .span(assert.span),
                 );
             }
 
@@ -754,6 +788,9 @@ fn lower_type<'gcx>(
         ast::LongIntType => hir::TypeKind::Builtin(hir::BuiltinType::LongInt),
         ast::StringType => hir::TypeKind::Builtin(hir::BuiltinType::String),
         ast::TimeType => hir::TypeKind::Builtin(hir::BuiltinType::Time),
+        ast::ShortRealType => hir::TypeKind::Builtin(hir::BuiltinType::ShortReal),
+        ast::RealType => hir::TypeKind::Builtin(hir::BuiltinType::Real),
+        ast::RealtimeType => hir::TypeKind::Builtin(hir::BuiltinType::RealTime),
         ast::NamedType(name) => hir::TypeKind::Named(name),
         ast::StructType(ref def) => {
             let mut fields = vec![];
@@ -832,9 +869,6 @@ fn lower_type<'gcx>(
         | ast::MailboxType
         | ast::ImplicitSignedType
         | ast::ImplicitUnsignedType
-        | ast::ShortRealType
-        | ast::RealType
-        | ast::RealtimeType
         | ast::SpecializedType(..)
         | ast::ScopedType { .. } => {
             error!("{:#?}", ty);
@@ -911,6 +945,9 @@ fn lower_expr_inner<'gcx>(
                 return Err(());
             }
         },
+        ast::LiteralExpr(Lit::Number(int, Some(frac))) => {
+            hir::ExprKind::RealConst(parse_fixed_point_number(cx, expr.span, int, Some(frac))?)
+        }
         ast::LiteralExpr(Lit::UnbasedUnsized(c)) => hir::ExprKind::UnsizedConst(c),
 
         ast::LiteralExpr(Lit::BasedInteger(maybe_size, signed, base, value)) => {
@@ -970,13 +1007,16 @@ fn lower_expr_inner<'gcx>(
             };
             if size_needed > size {
                 cx.emit(
-                    DiagBuilder2::warning(format!("`{}` is too large", value,))
-                        .span(expr.span)
-                        .add_note(format!(
-                            "constant is {} bits wide, but the value `{}{}` needs {} bits to not \
+                    DiagBuilder2::lint(
+                        lints::LITERAL_OVERFLOW,
+                        format!("`{}` is too large", value,),
+                    )
+                    .span(expr.span)
+                    .add_note(format!(
+                        "constant is {} bits wide, but the value `{}{}` needs {} bits to not \
                              be truncated",
-                            size, base, value, size_needed
-                        )),
+                        size, base, value, size_needed
+                    )),
                 );
             }
 
@@ -1150,7 +1190,7 @@ fn lower_expr_inner<'gcx>(
         ast::CallExpr(ref callee, ref args) => match callee.data {
             ast::SysIdentExpr(ident) => {
                 let map_unary = || {
-                    Ok(match args.as_slice() {
+                    Ok(match args {
                         [ast::CallArg {
                             expr: Some(ref arg),
                             ..
@@ -1170,8 +1210,11 @@ fn lower_expr_inner<'gcx>(
                     "unsigned" => hir::BuiltinCall::Unsigned(map_unary()?),
                     _ => {
                         cx.emit(
-                            DiagBuilder2::warning(format!("`${}` not supported; ignored", ident))
-                                .span(expr.human_span()),
+                            DiagBuilder2::lint(
+                                lints::UNSUPPORTED,
+                                format!("`${}` not supported; ignored", ident),
+                            )
+                            .span(expr.human_span()),
                         );
                         hir::BuiltinCall::Unsupported
                     }
@@ -1190,10 +1233,13 @@ fn lower_expr_inner<'gcx>(
             _ => {
                 error!("{:#?}", callee);
                 cx.emit(
-                    DiagBuilder2::warning(format!(
-                        "`{}` is not something that can be called",
-                        expr.span().extract()
-                    ))
+                    DiagBuilder2::lint(
+                        lints::NOT_CALLABLE,
+                        format!(
+                            "`{}` is not something that can be called",
+                            expr.span().extract()
+                        ),
+                    )
                     .span(expr.human_span()),
                 );
                 return Err(());
@@ -1547,7 +1593,7 @@ fn alloc_var_decl<'gcx>(
 ) -> NodeId {
     let type_id = cx.map_ast_with_parent(AstNode::Type(&decl.ty), next_rib);
     next_rib = type_id;
-    for name in &decl.names {
+    for name in decl.names {
         let decl_id = cx.map_ast_with_parent(AstNode::VarDecl(name, decl, type_id), next_rib);
         next_rib = decl_id;
         into.push(decl_id);
@@ -1564,7 +1610,7 @@ fn alloc_net_decl<'gcx>(
 ) -> NodeId {
     let type_id = cx.map_ast_with_parent(AstNode::Type(&decl.ty), next_rib);
     next_rib = type_id;
-    for name in &decl.names {
+    for name in decl.names {
         let decl_id = cx.map_ast_with_parent(AstNode::NetDecl(name, decl, type_id), next_rib);
         next_rib = decl_id;
         into.push(decl_id);
@@ -1581,7 +1627,7 @@ fn alloc_struct_member<'gcx>(
 ) -> NodeId {
     let type_id = cx.map_ast_with_parent(AstNode::Type(&member.ty), next_rib);
     next_rib = type_id;
-    for name in &member.names {
+    for name in member.names {
         let member_id =
             cx.map_ast_with_parent(AstNode::StructMember(name, member, type_id), next_rib);
         next_rib = member_id;
@@ -1603,7 +1649,7 @@ fn lower_package<'gcx>(
     let mut names = Vec::new();
     let mut decls = Vec::new();
     let mut params = Vec::new();
-    for item in &ast.items {
+    for item in ast.items {
         match item.data {
             ast::ItemData::VarDecl(ref decl) => {
                 next_rib = alloc_var_decl(cx, decl, next_rib, &mut decls);
@@ -9,11 +9,14 @@ use crate::crate_prelude::*;
 use crate::mir::WalkVisitor as _;
 use std::{collections::BTreeSet, sync::Arc};
 
+mod fold;
+mod ids;
 pub(crate) mod lowering;
 mod nodes;
 mod visit;
 
-pub(crate) use self::lowering::hir_of;
+pub use self::fold::*;
+pub use self::ids::*;
 pub use self::lowering::Hint;
 pub use self::nodes::*;
 pub use self::visit::*;
@@ -86,13 +89,15 @@ pub enum AccessedNode {
     Regular(NodeId),
     /// An interface signal.
     Intf(NodeId, NodeId),
+    /// The hit counter of a cover statement.
+    Cover(NodeId),
 }
 
 impl AccessedNode {
     /// Get the ID of the accessed node, dropping any context information.
     pub fn id(&self) -> NodeId {
         match *self {
-            Self::Regular(id) | Self::Intf(_, id) => id,
+            Self::Regular(id) | Self::Intf(_, id) | Self::Cover(id) => id,
         }
     }
 }
@@ -119,12 +124,27 @@ where
         self.cx
     }
 
-    fn visit_expr(&mut self, expr: &'gcx Expr, lvalue: bool) {
+    fn visit_stmt(&mut self, stmt: &'gcx Stmt) -> Control {
+        // Cover statements are only emitted if coverage is enabled, in which
+        // case they increment their hit counter.
+        if let StmtKind::Cover { .. } = stmt.kind {
+            if !self.cx.sess().opts.coverage {
+                return Control::SkipChildren;
+            }
+            let counter = AccessedNode::Cover(stmt.id);
+            self.table.read.insert(counter);
+            self.table.written.insert(counter);
+        }
+        walk_stmt(self, stmt)
+    }
+
+    fn visit_expr(&mut self, expr: &'gcx Expr, lvalue: bool) -> Control {
         if lvalue {
             self.cx.mir_lvalue(expr.id, self.env).walk(self);
         } else {
             self.cx.mir_rvalue(expr.id, self.env).walk(self);
         }
+        Control::SkipChildren
     }
 }
 
@@ -566,6 +566,9 @@ pub enum BuiltinType {
     Integer,
     LongInt,
     Time,
+    ShortReal,
+    Real,
+    RealTime,
     String,
 }
 
@@ -602,6 +605,7 @@ impl HasDesc for Expr<'_> {
         match self.kind {
             ExprKind::IntConst { .. } => "integer constant",
             ExprKind::TimeConst(_) => "time constant",
+            ExprKind::RealConst(_) => "real constant",
             ExprKind::Ident(_) => "identifier",
             _ => "expression",
         }
@@ -612,6 +616,7 @@ impl HasDesc for Expr<'_> {
         match self.kind {
             ExprKind::IntConst { value: ref k, .. } => format!("{} `{}`", self.desc(), k),
             ExprKind::TimeConst(ref k) => format!("{} `{}`", self.desc(), k),
+            ExprKind::RealConst(_) => format!("{} `{}`", self.desc(), self.span().extract()),
             ExprKind::Ident(n) => format!("`{}`", n.value),
             ExprKind::PositionalPattern(..) => format!("positional pattern"),
             ExprKind::NamedPattern(..) => format!("named pattern"),
@@ -639,6 +644,8 @@ pub enum ExprKind<'a> {
     UnsizedConst(char),
     /// A time constant literal.
     TimeConst(BigRational),
+    /// A real constant literal such as `1.5`.
+    RealConst(BigRational),
     /// A string constant literal.
     StringConst(Spanned<Name>),
     /// An identifier.
@@ -953,6 +960,7 @@ impl HasDesc for Stmt {
             StmtKind::Null => "null statement",
             StmtKind::Block(_) => "block",
             StmtKind::Assign { .. } => "assign statement",
+            StmtKind::Cover { .. } => "cover statement",
             _ => "statement",
         }
     }
@@ -1004,6 +1012,12 @@ pub enum StmtKind {
         default: Option<NodeId>,
         kind: ast::CaseKind,
     },
+    /// An immediate cover statement.
+    ///
+    /// ```text
+    /// cover (<expr>) <stmt>
+    /// ```
+    Cover { expr: NodeId, stmt: NodeId },
 }
 
 /// The different forms an assignment can take.
@@ -10,7 +10,7 @@ use crate::{
     resolver::InstTarget,
     Context, ParamEnv, ParamEnvData, ParamEnvSource, PortMapping,
 };
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
 /// Instantiation details
 ///
@@ -195,10 +195,10 @@ impl<'a, 'gcx> hir::Visitor<'gcx> for InstVerbosityVisitor<'a, 'gcx> {
         self.cx
     }
 
-    fn visit_inst(&mut self, hir: &'gcx hir::Inst<'gcx>) {
+    fn visit_inst(&mut self, hir: &'gcx hir::Inst<'gcx>) -> hir::Control {
         let details = match self.cx.inst_details(Ref(hir), self.env) {
             Ok(x) => x,
-            Err(()) => return,
+            Err(()) => return hir::Control::SkipChildren,
         };
         self.cx.emit(
             DiagBuilder2::note("instantiation details")
@@ -209,6 +209,62 @@ impl<'a, 'gcx> hir::Visitor<'gcx> for InstVerbosityVisitor<'a, 'gcx> {
             cx: self.cx,
             env: details.inner_env,
         }
-        .visit_node_with_id(details.target.kind.as_any().id(), false);
+        .visit_node_with_id(details.target.kind.as_any().id(), false)
+    }
+}
+
+/// A visitor that collects the modules in a design hierarchy.
+///
+/// Starting at a module, the visitor descends into all modules instantiated
+/// directly in the module body and records each module and parametrization
+/// they are first encountered.
+pub struct ModuleCollector<'a, 'gcx> {
+    cx: &'a GlobalContext<'gcx>,
+    env: ParamEnv,
+    visited: HashSet<(NodeId, ParamEnv)>,
+    /// The modules found so far.
+    pub modules: Vec<(NodeId, ParamEnv)>,
+}
+
+impl<'a, 'gcx> ModuleCollector<'a, 'gcx> {
+    /// Create a new collector.
+    pub fn new(cx: &'a GlobalContext<'gcx>) -> Self {
+        Self {
+            cx,
+            env: cx.default_param_env(),
+            visited: Default::default(),
+            modules: vec![],
+        }
+    }
+}
+
+impl<'a, 'gcx> hir::Visitor<'gcx> for ModuleCollector<'a, 'gcx> {
+    type Context = GlobalContext<'gcx>;
+
+    fn context(&self) -> &Self::Context {
+        self.cx
+    }
+
+    fn visit_module(&mut self, module: &'gcx hir::Module) -> hir::Control {
+        let id = module.ast.id();
+        if !self.visited.insert((id, self.env)) {
+            return hir::Control::SkipChildren;
+        }
+        self.modules.push((id, self.env));
+        for &inst_id in &module.block.insts {
+            self.visit_node_with_id(inst_id, false);
+        }
+        hir::Control::SkipChildren
+    }
+
+    fn visit_inst(&mut self, hir: &'gcx hir::Inst<'gcx>) -> hir::Control {
+        let details = match self.cx.inst_details(Ref(hir), self.env) {
+            Ok(x) => x,
+            Err(()) => return hir::Control::SkipChildren,
+        };
+        let outer = std::mem::replace(&mut self.env, details.inner_env);
+        self.visit_node_with_id(details.target.kind.as_any().id(), false);
+        self.env = outer;
+        hir::Control::SkipChildren
     }
 }
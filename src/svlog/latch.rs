@@ -0,0 +1,274 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Inferred latch detection.
+//!
+//! A combinational process which does not assign a variable on every path
+//! through its body has to remember the variable's previous value on the other
+//! paths, which infers a latch. This module finds such variables and the
+//! branch where the assignment is missing, like an `if` without an `else`, or
+//! a `case` without a `default` that does not cover all values.
+//!
+//! Assigning to part of a variable, like `x[0] = a`, counts as assigning the
+//! whole variable. This avoids false alarms for variables which are assigned
+//! piece by piece, at the cost of missing latches on individual bits.
+
+use crate::{
+    comb_loop::{is_combinational, signal_name},
+    crate_prelude::*,
+    hir::{AccessedNode, HirNode, LoopKind, StmtKind},
+    ParamEnv,
+};
+use moore_common::lints;
+use num::BigInt;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Report the variables which infer a latch in the combinational processes of
+/// a module.
+///
+/// Only the processes directly within the module body are considered;
+/// processes nested in generate blocks are skipped.
+pub fn check_latches<'a>(cx: &impl Context<'a>, node_id: NodeId, env: ParamEnv) {
+    let _ = try_check_latches(cx, node_id, env);
+}
+
+fn try_check_latches<'a>(cx: &impl Context<'a>, node_id: NodeId, env: ParamEnv) -> Result<()> {
+    let hir = match cx.hir_of(node_id)? {
+        HirNode::Module(x) => x,
+        x => bug_span!(x.span(), cx, "check_latches called on {}", x.desc_full()),
+    };
+    for &proc_id in &hir.block.procs {
+        let prok = match cx.hir_of(proc_id)? {
+            HirNode::Proc(x) => x,
+            _ => unreachable!(),
+        };
+        if !is_combinational(cx, prok)? {
+            continue;
+        }
+        let mut finder = LatchFinder {
+            cx,
+            env,
+            assigned: HashMap::new(),
+        };
+        let written = finder.written(prok.stmt)?;
+        let assigned = finder.assigned(prok.stmt)?;
+        for &var in written.difference(&assigned) {
+            let missing = match finder.missing(prok.stmt, var)? {
+                Some(x) => x,
+                None => continue,
+            };
+            let name = signal_name(cx, var)?.value;
+            cx.emit(
+                DiagBuilder2::lint(
+                    lints::INFERRED_LATCH,
+                    format!(
+                        "`{}` is not assigned on every path, inferring a latch",
+                        name
+                    ),
+                )
+                .span(missing.0)
+                .add_note(format!(
+                    "`{}` keeps its previous value {}.",
+                    name, missing.1
+                ))
+                .add_note(format!(
+                    "Assign a default value to `{}` at the beginning of the process, or use \
+                     `always_latch` if the latch is intended.",
+                    name
+                )),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// A helper to determine which variables a statement assigns.
+struct LatchFinder<'c, C> {
+    cx: &'c C,
+    env: ParamEnv,
+    /// The variables assigned on every path through a statement.
+    assigned: HashMap<NodeId, BTreeSet<NodeId>>,
+}
+
+impl<'a, 'c, C: Context<'a>> LatchFinder<'c, C> {
+    /// Determine the variables assigned on any path through a statement.
+    fn written(&self, stmt_id: NodeId) -> Result<BTreeSet<NodeId>> {
+        Ok(self
+            .cx
+            .accessed_nodes(stmt_id, self.env)?
+            .written
+            .iter()
+            .flat_map(|acc| match *acc {
+                AccessedNode::Regular(id) => Some(id),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Determine the variables assigned on every path through a statement.
+    fn assigned(&mut self, stmt_id: NodeId) -> Result<BTreeSet<NodeId>> {
+        if let Some(assigned) = self.assigned.get(&stmt_id) {
+            return Ok(assigned.clone());
+        }
+        let stmt = match self.cx.hir_of(stmt_id)? {
+            HirNode::Stmt(x) => x,
+            _ => return Ok(BTreeSet::new()),
+        };
+        let assigned = match stmt.kind {
+            StmtKind::Assign { .. } | StmtKind::Expr(_) => self.written(stmt_id)?,
+            StmtKind::Block(ref stmts) | StmtKind::InlineGroup { ref stmts, .. } => {
+                let mut assigned = BTreeSet::new();
+                for &stmt in stmts {
+                    assigned.extend(self.assigned(stmt)?);
+                }
+                assigned
+            }
+            StmtKind::Timed { stmt, .. } => self.assigned(stmt)?,
+            StmtKind::Loop {
+                kind: LoopKind::Forever,
+                body,
+            }
+            | StmtKind::Loop {
+                kind: LoopKind::Do(_),
+                body,
+            } => self.assigned(body)?,
+            StmtKind::If {
+                main_stmt,
+                else_stmt: Some(else_stmt),
+                ..
+            } => {
+                let main = self.assigned(main_stmt)?;
+                let other = self.assigned(else_stmt)?;
+                main.intersection(&other).cloned().collect()
+            }
+            StmtKind::Case {
+                expr,
+                ref ways,
+                default,
+                kind,
+            } => {
+                let mut ways_assigned = None::<BTreeSet<NodeId>>;
+                for &stmt in ways.iter().map(|(_, stmt)| stmt).chain(default.iter()) {
+                    let assigned = self.assigned(stmt)?;
+                    ways_assigned = Some(match ways_assigned {
+                        Some(x) => x.intersection(&assigned).cloned().collect(),
+                        None => assigned,
+                    });
+                }
+                if default.is_some() || self.is_full_case(expr, ways, kind) {
+                    ways_assigned.unwrap_or_default()
+                } else {
+                    BTreeSet::new()
+                }
+            }
+            _ => BTreeSet::new(),
+        };
+        self.assigned.insert(stmt_id, assigned.clone());
+        Ok(assigned)
+    }
+
+    /// Find the location where a statement misses an assignment to a variable,
+    /// and describe under which condition that happens.
+    ///
+    /// Should only be called if the statement does not assign the variable on
+    /// every path.
+    fn missing(&mut self, stmt_id: NodeId, var: NodeId) -> Result<Option<(Span, String)>> {
+        let stmt = match self.cx.hir_of(stmt_id)? {
+            HirNode::Stmt(x) => x,
+            _ => return Ok(None),
+        };
+        let missing = match stmt.kind {
+            StmtKind::Block(ref stmts) | StmtKind::InlineGroup { ref stmts, .. } => {
+                for &stmt in stmts {
+                    if self.written(stmt)?.contains(&var) {
+                        return self.missing(stmt, var);
+                    }
+                }
+                None
+            }
+            StmtKind::Timed { stmt, .. } => return self.missing(stmt, var),
+            StmtKind::If {
+                cond,
+                main_stmt,
+                else_stmt,
+            } => {
+                if !self.assigned(main_stmt)?.contains(&var) {
+                    self.missing_in_branch(main_stmt, var)?
+                } else if let Some(else_stmt) = else_stmt {
+                    self.missing_in_branch(else_stmt, var)?
+                } else {
+                    Some((
+                        self.cx.span(cond),
+                        "if this condition is false, since there is no `else` branch".to_string(),
+                    ))
+                }
+            }
+            StmtKind::Case {
+                expr,
+                ref ways,
+                default,
+                ..
+            } => {
+                for &stmt in ways.iter().map(|(_, stmt)| stmt).chain(default.iter()) {
+                    if !self.assigned(stmt)?.contains(&var) {
+                        return self.missing_in_branch(stmt, var);
+                    }
+                }
+                Some((
+                    self.cx.span(expr),
+                    "if this expression matches none of the case items, since there is no \
+                     `default` case"
+                        .to_string(),
+                ))
+            }
+            StmtKind::Loop { .. } => Some((
+                stmt.span,
+                "if this loop does not execute its body".to_string(),
+            )),
+            _ => None,
+        };
+        Ok(missing)
+    }
+
+    /// Find the location where a branch misses an assignment to a variable.
+    fn missing_in_branch(
+        &mut self,
+        stmt_id: NodeId,
+        var: NodeId,
+    ) -> Result<Option<(Span, String)>> {
+        if self.written(stmt_id)?.contains(&var) {
+            self.missing(stmt_id, var)
+        } else {
+            Ok(Some((
+                self.cx.span(stmt_id),
+                "if this branch is taken".to_string(),
+            )))
+        }
+    }
+
+    /// Check whether the items of a case statement without `default` cover
+    /// every value the case expression can take.
+    fn is_full_case(
+        &self,
+        expr: NodeId,
+        ways: &[(Vec<NodeId>, NodeId)],
+        kind: ast::CaseKind,
+    ) -> bool {
+        if kind != ast::CaseKind::Normal {
+            return false;
+        }
+        let width = match self.cx.mir_rvalue(expr, self.env).ty.get_bit_size() {
+            Some(w) if w <= 16 => w,
+            _ => return false,
+        };
+        let mut values = HashSet::<BigInt>::new();
+        for &label in ways.iter().flat_map(|(labels, _)| labels) {
+            if !self.cx.mir_rvalue(label, self.env).is_const() {
+                return false;
+            }
+            if let Some(v) = self.cx.constant_value_of(label, self.env).get_int() {
+                values.insert(v.clone());
+            }
+        }
+        values.len() >= 1 << width
+    }
+}
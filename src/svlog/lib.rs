@@ -40,12 +40,6 @@ extern crate log;
 pub extern crate moore_svlog_syntax as syntax;
 pub(crate) use moore_common as common;
 
-// Inline the salsa crate as a module, since we use a very esoteric branch for
-// now.
-// TODO(fschuiki): Remove this once salsa is regular dep again
-#[macro_use]
-mod salsa;
-
 /// Assert that a condition holds, or emit a bug diagnostic and panic.
 #[macro_export]
 macro_rules! assert_span {
@@ -112,22 +106,37 @@ macro_rules! assert_type {
 }
 
 mod ast_map;
+pub mod case;
+pub mod clock;
 mod codegen;
+pub mod comb_loop;
 mod context;
+pub mod deps;
+pub mod dpi;
+pub mod drivers;
+pub mod dump;
+pub mod fsm;
+pub mod hierarchy;
 pub mod hir;
 mod inst_details;
+pub mod latch;
+pub mod library;
+pub mod lookup;
 pub mod mir;
 mod param_env;
 #[warn(missing_docs)]
 pub mod pattern_mapping;
 pub mod port_list;
 mod port_mapping;
+pub mod reachability;
 pub mod resolver;
 pub mod rst;
 #[warn(missing_docs)]
 pub mod ty;
 pub mod typeck;
+pub mod unused;
 pub mod value;
+pub mod width;
 
 pub use moore_common::{
     name::Name,
@@ -138,9 +147,10 @@ pub use moore_common::{
 pub type Result<T> = std::result::Result<T, ()>;
 
 pub use crate::{
-    codegen::CodeGenerator,
+    ast_map::{AstMap, AstNode},
+    codegen::{CodeGenerator, EntityPolicy},
     context::*,
-    inst_details::{InstDetails, InstTargetDetails, InstVerbosityVisitor},
+    inst_details::{InstDetails, InstTargetDetails, InstVerbosityVisitor, ModuleCollector},
     param_env::{
         IntoNodeEnvId, NodeEnvId, ParamEnv, ParamEnvBinding, ParamEnvData, ParamEnvSource,
     },
@@ -155,7 +165,9 @@ mod crate_prelude {
     pub(crate) use crate::{
         ast::{AnyNode, AnyNodeData},
         common::{
+            codes,
             errors::*,
+            lints,
             name::Name,
             score::Result,
             source::{Span, Spanned},
@@ -264,9 +276,17 @@ mod queries {
     use crate::crate_prelude::*;
     #[allow(deprecated)]
     use crate::{
+        clock::*,
+        comb_loop::*,
+        fsm::*,
         hir::lowering::*,
-        hir::{accessed_nodes, AccessTable},
+        hir::{accessed_nodes, AccessTable, HirNode},
         inst_details::*,
+        lookup::*,
+        mir::{
+            lower::{lvalue::*, rvalue::*},
+            Lvalue, Rvalue,
+        },
         param_env::*,
         pattern_mapping::*,
         port_list::{self, *},
@@ -288,3 +308,40 @@ mod queries {
     }
 }
 pub use crate::queries::*;
+
+/// Parse a source text and pass a context holding it to a function.
+///
+/// Used by the unit tests that need a real AST and HIR to work on.
+#[cfg(test)]
+pub(crate) fn with_context<R>(
+    text: &str,
+    f: impl for<'gcx> FnOnce(&GlobalContext<'gcx>) -> R,
+) -> R {
+    with_files(&[text], |cx, files| {
+        cx.add_files(files.iter().cloned());
+        f(cx)
+    })
+}
+
+/// Parse a few source texts and pass them to a function, together with an
+/// empty context.
+#[cfg(test)]
+pub(crate) fn with_files<R>(
+    texts: &[&str],
+    f: impl for<'gcx> FnOnce(&GlobalContext<'gcx>, &[&'gcx ast::SourceFile<'gcx>]) -> R,
+) -> R {
+    let sess = moore_common::Session::new();
+    let arenas = GlobalArenas::default();
+    let files: Vec<_> = texts
+        .iter()
+        .map(|&text| {
+            let source = moore_common::source::get_source_manager().add_anonymous(text);
+            let preproc = preproc::Preprocessor::new(source, &[], &[]);
+            parser::parse(lexer::Lexer::new(preproc), &arenas.ast, &sess)
+                .expect("test source should parse")
+        })
+        .collect();
+    let files: Vec<_> = files.iter().collect();
+    let cx = GlobalContext::new(&sess, &arenas);
+    f(&cx, &files)
+}
@@ -0,0 +1,135 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Libraries of modules that are compiled on demand.
+//!
+//! Verilog tools traditionally do not require every module of a design to be
+//! passed as an input file. Modules which are instantiated but not defined in
+//! any of the input files are looked up in library directories given with
+//! `-y`, which contain one file per module named after the module, with one of
+//! the extensions given with `+libext+`. Modules may also be looked up in
+//! library files given with `-v`, which may define any number of modules. Only
+//! the library files that define a missing module are compiled.
+
+use crate::crate_prelude::*;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// The file extensions tried in library directories if none are given.
+pub const DEFAULT_EXTENSIONS: &[&str] = &[".sv", ".v"];
+
+/// The places where modules missing from the input files are looked up.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Library {
+    /// The library directories, which contain one file per module.
+    pub dirs: Vec<PathBuf>,
+    /// The file extensions tried in the library directories, in order.
+    pub extensions: Vec<String>,
+    /// The library files, which may define any number of modules.
+    pub files: Vec<PathBuf>,
+}
+
+impl Library {
+    /// Check whether no library directories or files are given.
+    pub fn is_empty(&self) -> bool {
+        self.dirs.is_empty() && self.files.is_empty()
+    }
+
+    /// Find the file in the library directories that defines a module.
+    ///
+    /// The directories are searched in order, and in each directory the
+    /// extensions are tried in order. Returns the first file that exists.
+    pub fn find(&self, name: Name) -> Option<PathBuf> {
+        let extensions: Vec<&str> = if self.extensions.is_empty() {
+            DEFAULT_EXTENSIONS.to_vec()
+        } else {
+            self.extensions.iter().map(String::as_str).collect()
+        };
+        self.dirs
+            .iter()
+            .flat_map(|dir| {
+                extensions
+                    .iter()
+                    .map(move |ext| dir.join(format!("{}{}", name, ext)))
+            })
+            .find(|path| path.is_file())
+    }
+}
+
+/// The modules and interfaces defined and instantiated in source files.
+#[derive(Debug, Default)]
+pub struct Units {
+    /// The names of the defined modules and interfaces.
+    pub defined: HashSet<Name>,
+    /// The names of the instantiated modules and interfaces, in the order in
+    /// which they are first instantiated.
+    pub instantiated: Vec<Name>,
+}
+
+impl Units {
+    /// Add the modules and interfaces defined and instantiated in a file.
+    pub fn add_file(&mut self, file: &ast::SourceFile) {
+        let mut collector = UnitCollector::default();
+        collector.visit_items(&file.items);
+        self.defined.extend(collector.defined);
+        for name in collector.instantiated {
+            if !self.instantiated.contains(&name) {
+                self.instantiated.push(name);
+            }
+        }
+    }
+
+    /// The names of the instantiated modules and interfaces which are not
+    /// defined.
+    pub fn undefined(&self) -> Vec<Name> {
+        self.instantiated
+            .iter()
+            .cloned()
+            .filter(|name| !self.defined.contains(name))
+            .collect()
+    }
+}
+
+/// Determine the names of the modules and interfaces defined in a file.
+pub fn defined_units(file: &ast::SourceFile) -> Vec<Name> {
+    let mut collector = UnitCollector::default();
+    collector.visit_items(&file.items);
+    collector.defined
+}
+
+/// A walk over items that collects the modules and interfaces they define and
+/// instantiate.
+///
+/// Instantiations within all generate blocks are considered, regardless of
+/// whether they end up being enabled.
+#[derive(Default)]
+struct UnitCollector {
+    defined: Vec<Name>,
+    instantiated: Vec<Name>,
+}
+
+impl UnitCollector {
+    fn visit_items(&mut self, items: &[ast::Item]) {
+        for item in items {
+            match item.data {
+                ast::ItemData::ModuleDecl(ref decl) => {
+                    self.defined.push(decl.name.value);
+                    self.visit_items(&decl.items);
+                }
+                ast::ItemData::InterfaceDecl(ref decl) => {
+                    self.defined.push(decl.name.value);
+                    self.visit_items(&decl.items);
+                }
+                ast::ItemData::Inst(ref inst) => self.instantiated.push(inst.target.value),
+                ast::ItemData::GenerateRegion(_, ref items) => self.visit_items(items),
+                ast::ItemData::GenerateFor(ref gen) => self.visit_items(&gen.block.items),
+                ast::ItemData::GenerateIf(ref gen) => {
+                    self.visit_items(&gen.main_block.items);
+                    if let Some(ref block) = gen.else_block {
+                        self.visit_items(&block.items);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+}
@@ -0,0 +1,493 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Looking up what the names in a source file refer to.
+//!
+//! Editors use this to jump to the definition of a name and to show what is
+//! known about it, and code browsers to index the definitions and references
+//! in a design. Names are resolved in the scope they appear in. Types and
+//! parameter values are determined for the default parameters, since a module
+//! on its own is not instantiated with any other values.
+//!
+//! Positions in a file are mapped to nodes through a [`NodeIndex`], which is
+//! built once per file. The definitions in a module, interface, or package,
+//! and the places where they are referred to, are listed in a [`SymbolTable`].
+
+use crate::{
+    ast::AcceptVisitor,
+    crate_prelude::*,
+    hir::HirNode,
+    resolver::{Def, DefNode, ScopedNode},
+    ty::UnpackedType,
+};
+use std::{collections::HashMap, sync::Arc};
+
+/// A name in a source file together with the definition it refers to.
+#[derive(Debug, Clone, Copy)]
+pub struct Reference<'a> {
+    /// The name as it appears in the source file.
+    pub name: Spanned<Name>,
+    /// The definition the name refers to.
+    pub def: &'a Def<'a>,
+}
+
+/// Find the name at a byte offset into a source file and resolve it.
+///
+/// Returns `None` if there is no name at the offset, or if it does not refer
+/// to anything.
+pub fn reference_at<'gcx>(
+    cx: &GlobalContext<'gcx>,
+    file: &'gcx ast::SourceFile<'gcx>,
+    offset: usize,
+) -> Option<Reference<'gcx>> {
+    // Find the innermost node with a name at the offset.
+    let mut node = node_at(cx, file, offset)?;
+    loop {
+        match name_of(node) {
+            Some(name) if name.span.begin <= offset && offset <= name.span.end => break,
+            _ => node = node.get_parent()?,
+        }
+    }
+    resolve(cx, node)
+}
+
+/// Find the definition at a byte offset into a source file, either through a
+/// name which refers to it or through the name it defines.
+pub fn definition_at<'gcx>(
+    cx: &GlobalContext<'gcx>,
+    file: &'gcx ast::SourceFile<'gcx>,
+    offset: usize,
+) -> Option<&'gcx Def<'gcx>> {
+    if let Some(reference) = reference_at(cx, file, offset) {
+        return Some(reference.def);
+    }
+    definitions(cx, file)
+        .into_iter()
+        .find(|def| def.name.span.begin <= offset && offset <= def.name.span.end)
+}
+
+/// Find the names in the design of a source file which refer to a definition.
+pub fn references_to<'gcx>(
+    cx: &GlobalContext<'gcx>,
+    file: &'gcx ast::SourceFile<'gcx>,
+    def: &Def,
+) -> Vec<Span> {
+    match file.get_parent().map(|root| root.as_all()) {
+        Some(ast::AllNode::Root(root)) => cx
+            .reference_index(Ref(root))
+            .get(&def.name.span)
+            .cloned()
+            .unwrap_or_default(),
+        _ => vec![],
+    }
+}
+
+/// Find the innermost AST node that covers a byte offset into a source file.
+pub fn node_at<'gcx>(
+    cx: &GlobalContext<'gcx>,
+    file: &'gcx ast::SourceFile<'gcx>,
+    offset: usize,
+) -> Option<&'gcx dyn ast::AnyNode<'gcx>> {
+    cx.node_index(Ref(file)).node_covering(offset, offset)
+}
+
+/// Find the innermost HIR node that covers a byte offset into a source file.
+///
+/// This is the HIR node of the innermost AST node at the offset which is
+/// lowered on its own. For example, the name of a port maps to the port.
+pub fn hir_at<'gcx>(
+    cx: &GlobalContext<'gcx>,
+    file: &'gcx ast::SourceFile<'gcx>,
+    offset: usize,
+) -> Option<HirNode<'gcx>> {
+    let mut node = node_at(cx, file, offset)?;
+    while cx.get_ast(node.id()).is_none() {
+        node = node.get_parent()?;
+    }
+    cx.hir_of(node.id()).ok()
+}
+
+/// Find the definitions in a source file, in the order they appear.
+pub fn definitions<'gcx>(
+    cx: &GlobalContext<'gcx>,
+    file: &'gcx ast::SourceFile<'gcx>,
+) -> Vec<&'gcx Def<'gcx>> {
+    let mut defs: Vec<&Def> = vec![];
+    let mut scopes: Vec<&dyn ScopedNode> = vec![file];
+    while let Some(node) = scopes.pop() {
+        let scope = cx.generated_scope(node);
+        defs.extend(scope.defs.values());
+        scopes.extend(scope.subscopes.iter().cloned());
+    }
+
+    // Imports are listed as definitions of the names they import.
+    defs.retain(|def| match def.node {
+        DefNode::Ast(node) => node.as_all().get_import_item().is_none(),
+        DefNode::IntPort(..) => true,
+    });
+    defs.sort_by_key(|def| (def.name.span.begin, def.name.span.end));
+    defs.dedup_by_key(|def| def.name.span);
+    defs
+}
+
+/// Find the names in a source file which refer to a definition.
+pub fn references<'gcx>(
+    cx: &GlobalContext<'gcx>,
+    file: &'gcx ast::SourceFile<'gcx>,
+) -> Vec<Reference<'gcx>> {
+    let mut collector = NameCollector { nodes: vec![] };
+    file.accept(&mut collector);
+    collector
+        .nodes
+        .into_iter()
+        .flat_map(|node| resolve(cx, node))
+        .collect()
+}
+
+/// Resolve the name a node refers to.
+fn resolve<'gcx>(
+    cx: &GlobalContext<'gcx>,
+    node: &'gcx dyn ast::AnyNode<'gcx>,
+) -> Option<Reference<'gcx>> {
+    let name = name_of(node)?;
+    let def = cx
+        .resolve_local(name.value, cx.scope_location(node), false)
+        .ok()??;
+    Some(Reference { name, def })
+}
+
+/// Describe a definition, together with its type or value if it has one.
+///
+/// For example, a variable is described as ``variable `x`: logic [7:0]``, and
+/// a parameter as ``value parameter `N`: int = 4``.
+pub fn describe<'gcx>(cx: &GlobalContext<'gcx>, def: &'gcx Def<'gcx>) -> String {
+    let env = cx.default_param_env();
+    let node = match def.node {
+        DefNode::Ast(node) => node,
+        DefNode::IntPort(port) => {
+            return format!(
+                "{} `{}`: {}",
+                Kind(port.ast),
+                def.name,
+                cx.type_of_int_port(Ref(port), env)
+            )
+        }
+    };
+    let label = format!("{} `{}`", Kind(node), def.name);
+    match node.as_all() {
+        ast::AllNode::VarDeclName(..) => match type_of_def(cx, def) {
+            Some(ty) => format!("{}: {}", label, ty),
+            None => label,
+        },
+        ast::AllNode::ParamValueDecl(..) => match cx.hir_of(node.id()) {
+            Ok(HirNode::ValueParam(..)) => {
+                let value = cx.constant_value_of(node.id(), env);
+                format!("{}: {} = {}", label, value.ty, value.kind)
+            }
+            _ => label,
+        },
+        ast::AllNode::ParamTypeDecl(..) => match cx.map_to_type(Ref(node), env) {
+            Some(ty) => format!("{} = {}", label, ty),
+            None => label,
+        },
+        _ => label,
+    }
+}
+
+/// Determine the type of a variable, net, or port definition, for the default
+/// parameters.
+fn type_of_def<'gcx>(
+    cx: &GlobalContext<'gcx>,
+    def: &'gcx Def<'gcx>,
+) -> Option<&'gcx UnpackedType<'gcx>> {
+    let env = cx.default_param_env();
+    match def.node {
+        DefNode::IntPort(port) => Some(cx.type_of_int_port(Ref(port), env)),
+        DefNode::Ast(node) => match node.as_all() {
+            ast::AllNode::VarDeclName(..) => cx.type_of(node.id(), env).ok(),
+            _ => None,
+        },
+    }
+}
+
+/// The name that a node refers to, if any.
+fn name_of<'a>(node: &'a dyn ast::AnyNode<'a>) -> Option<Spanned<Name>> {
+    match node.as_all() {
+        ast::AllNode::Expr(expr) => match expr.data {
+            ast::IdentExpr(name) => Some(name),
+            _ => None,
+        },
+        ast::AllNode::Type(ty) => match ty.kind.data {
+            ast::NamedType(name) => Some(name),
+            _ => None,
+        },
+        ast::AllNode::Inst(inst) => Some(inst.target),
+        _ => None,
+    }
+}
+
+/// Formats the kind of a node, e.g. *"variable"*.
+struct Kind<'a>(&'a dyn ast::AnyNode<'a>);
+
+impl std::fmt::Display for Kind<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.0.fmt_indefinite(f)
+    }
+}
+
+/// The nodes of a source file, ordered by the span they cover.
+///
+/// The nodes are sorted by where they begin, and nodes which begin at the same
+/// offset from the outermost to the innermost. Since the spans of nodes nest,
+/// the innermost node covering a range is the first node before the range that
+/// also extends to its end.
+#[derive(Debug)]
+pub struct NodeIndex<'a> {
+    nodes: Vec<&'a dyn ast::AnyNode<'a>>,
+}
+
+impl<'a> NodeIndex<'a> {
+    /// Index the nodes of a source file.
+    ///
+    /// Nodes expanded from macros or included from other files are left out.
+    pub fn new(file: &'a ast::SourceFile<'a>) -> Self {
+        let mut collector = NodeCollector {
+            source: file.span().source,
+            nodes: vec![],
+        };
+        file.accept(&mut collector);
+        let mut nodes = collector.nodes;
+        nodes.sort_by_key(|node| {
+            let span = node.span();
+            (span.begin, std::cmp::Reverse(span.end))
+        });
+        NodeIndex { nodes }
+    }
+
+    /// Find the innermost node that covers the byte range `begin..end`.
+    pub fn node_covering(&self, begin: usize, end: usize) -> Option<&'a dyn ast::AnyNode<'a>> {
+        // Never reports equality, such that the search yields the index of the
+        // first node that begins after `begin`.
+        let len = self
+            .nodes
+            .binary_search_by(|node| {
+                if node.span().begin <= begin {
+                    std::cmp::Ordering::Less
+                } else {
+                    std::cmp::Ordering::Greater
+                }
+            })
+            .unwrap_err();
+        self.nodes[..len]
+            .iter()
+            .rev()
+            .find(|node| end <= node.span().end)
+            .cloned()
+    }
+}
+
+/// Build the index of the nodes in a source file.
+#[moore_derive::query]
+pub(crate) fn node_index<'a>(
+    _cx: &impl Context<'a>,
+    Ref(file): Ref<'a, ast::SourceFile<'a>>,
+) -> Arc<NodeIndex<'a>> {
+    Arc::new(NodeIndex::new(file))
+}
+
+/// A visitor that collects the nodes in a source file.
+struct NodeCollector<'a> {
+    source: moore_common::source::Source,
+    nodes: Vec<&'a dyn ast::AnyNode<'a>>,
+}
+
+impl<'a> ast::Visitor<'a> for NodeCollector<'a> {
+    fn pre_visit_node(&mut self, node: &'a dyn ast::AnyNode<'a>) -> bool {
+        if node.span().source == self.source {
+            self.nodes.push(node);
+        }
+        true
+    }
+}
+
+/// A visitor that collects the nodes which refer to a name.
+struct NameCollector<'a> {
+    nodes: Vec<&'a dyn ast::AnyNode<'a>>,
+}
+
+impl<'a> ast::Visitor<'a> for NameCollector<'a> {
+    fn pre_visit_node(&mut self, node: &'a dyn ast::AnyNode<'a>) -> bool {
+        if name_of(node).is_some() {
+            self.nodes.push(node);
+        }
+        true
+    }
+}
+
+/// The kind of a definition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    Module,
+    Interface,
+    Modport,
+    Package,
+    Net,
+    Variable,
+    Genvar,
+    ValueParam,
+    TypeParam,
+    EnumConst,
+    Typedef,
+    Function,
+    Task,
+    Port,
+    Instance,
+    Block,
+}
+
+impl SymbolKind {
+    /// Determine the kind of a definition.
+    pub fn of(def: &Def) -> Self {
+        let node = match def.node {
+            DefNode::Ast(node) => node,
+            DefNode::IntPort(_) => return SymbolKind::Port,
+        };
+        match node.as_all() {
+            ast::AllNode::Module(_) => SymbolKind::Module,
+            ast::AllNode::Interface(_) => SymbolKind::Interface,
+            ast::AllNode::ModportName(_) => SymbolKind::Modport,
+            ast::AllNode::Package(_) => SymbolKind::Package,
+            ast::AllNode::VarDeclName(_) => match node.get_parent().map(|p| p.as_all()) {
+                Some(ast::AllNode::NetDecl(_)) => SymbolKind::Net,
+                _ => SymbolKind::Variable,
+            },
+            ast::AllNode::GenvarDecl(_) => SymbolKind::Genvar,
+            ast::AllNode::ParamValueDecl(_) => SymbolKind::ValueParam,
+            ast::AllNode::ParamTypeDecl(_) => SymbolKind::TypeParam,
+            ast::AllNode::EnumName(_) => SymbolKind::EnumConst,
+            ast::AllNode::Typedef(_) => SymbolKind::Typedef,
+            ast::AllNode::SubroutineDecl(decl) => match decl.prototype.kind {
+                ast::SubroutineKind::Func => SymbolKind::Function,
+                ast::SubroutineKind::Task => SymbolKind::Task,
+            },
+            ast::AllNode::SubroutinePort(_) => SymbolKind::Port,
+            ast::AllNode::InstName(_) => SymbolKind::Instance,
+            _ => SymbolKind::Block,
+        }
+    }
+}
+
+/// A definition in a [`SymbolTable`].
+#[derive(Debug, Clone)]
+pub struct Symbol<'a> {
+    /// The definition.
+    pub def: &'a Def<'a>,
+    /// The kind of the definition.
+    pub kind: SymbolKind,
+    /// The names which refer to the definition, anywhere in the design.
+    pub references: Vec<Span>,
+}
+
+impl<'a> Symbol<'a> {
+    /// Get the name of the symbol where it is defined.
+    pub fn name(&self) -> Spanned<Name> {
+        self.def.name
+    }
+
+    /// Determine the type of the symbol for the default parameters, if it is a
+    /// variable, net, or port.
+    pub fn ty(&self, cx: &GlobalContext<'a>) -> Option<&'a UnpackedType<'a>> {
+        type_of_def(cx, self.def)
+    }
+}
+
+/// The definitions within a module, interface, or package.
+#[derive(Debug)]
+pub struct SymbolTable<'a> {
+    /// The symbols, in the order they are defined.
+    pub symbols: Vec<Symbol<'a>>,
+}
+
+/// List the definitions within a module, interface, or package, including the
+/// ones in nested scopes, together with the names that refer to them.
+#[moore_derive::query]
+pub(crate) fn symbol_table<'a>(
+    cx: &impl Context<'a>,
+    Ref(node): Ref<'a, dyn ScopedNode<'a>>,
+) -> Arc<SymbolTable<'a>> {
+    // Find the root of the design, since a definition may be referred to from
+    // other modules.
+    let mut root = node.as_any();
+    while let Some(parent) = root.get_parent() {
+        root = parent;
+    }
+    let references = match root.as_all() {
+        ast::AllNode::Root(root) => cx.reference_index(Ref(root)),
+        _ => Default::default(),
+    };
+
+    let mut defs: Vec<&Def> = vec![];
+    let mut scopes = vec![node];
+    while let Some(node) = scopes.pop() {
+        let scope = cx.generated_scope(node);
+        defs.extend(scope.defs.values());
+        scopes.extend(scope.subscopes.iter().cloned());
+    }
+    defs.retain(|def| match def.node {
+        DefNode::Ast(node) => node.as_all().get_import_item().is_none(),
+        DefNode::IntPort(..) => true,
+    });
+    defs.sort_by_key(|def| (def.name.span.begin, def.name.span.end));
+    defs.dedup_by_key(|def| def.name.span);
+
+    let symbols = defs
+        .into_iter()
+        .map(|def| Symbol {
+            def,
+            kind: SymbolKind::of(def),
+            references: references.get(&def.name.span).cloned().unwrap_or_default(),
+        })
+        .collect();
+    Arc::new(SymbolTable { symbols })
+}
+
+/// Find the names in a design which refer to a definition, grouped by the
+/// name of the definition they refer to.
+#[moore_derive::query]
+pub(crate) fn reference_index<'a>(
+    cx: &impl Context<'a>,
+    Ref(root): Ref<'a, ast::Root<'a>>,
+) -> Arc<HashMap<Span, Vec<Span>>> {
+    let mut collector = NameCollector { nodes: vec![] };
+    root.accept(&mut collector);
+    let mut index = HashMap::<_, Vec<_>>::new();
+    for node in collector.nodes {
+        let name = match name_of(node) {
+            Some(x) => x,
+            None => continue,
+        };
+        if let Ok(Some(def)) = cx.resolve_local(name.value, cx.scope_location(node), false) {
+            index.entry(def.name.span).or_default().push(name.span);
+        }
+    }
+    Arc::new(index)
+}
+
+/// Find the symbol tables of the modules, interfaces, and packages in a source
+/// file.
+pub fn symbol_tables<'gcx>(
+    cx: &GlobalContext<'gcx>,
+    file: &'gcx ast::SourceFile<'gcx>,
+) -> Vec<(Spanned<Name>, Arc<SymbolTable<'gcx>>)> {
+    file.items
+        .iter()
+        .flat_map(|item| -> Option<(_, &dyn ScopedNode)> {
+            match &item.data {
+                ast::ItemData::ModuleDecl(x) => Some((x.name, x)),
+                ast::ItemData::InterfaceDecl(x) => Some((x.name, x)),
+                ast::ItemData::PackageDecl(x) => Some((x.name, x)),
+                _ => None,
+            }
+        })
+        .map(|(name, node)| (name, cx.symbol_table(Ref(node))))
+        .collect()
+}
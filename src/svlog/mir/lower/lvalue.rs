@@ -52,11 +52,12 @@ impl<'a, C: Context<'a>> Builder<'_, C> {
 }
 
 /// Lower an expression to an lvalue in the MIR.
-pub fn lower_expr<'gcx>(
-    cx: &impl Context<'gcx>,
+#[moore_derive::query]
+pub(crate) fn mir_lvalue<'a>(
+    cx: &impl Context<'a>,
     expr_id: NodeId,
     env: ParamEnv,
-) -> &'gcx Lvalue<'gcx> {
+) -> &'a Lvalue<'a> {
     let span = cx.span(expr_id);
     let builder = Builder {
         cx,
@@ -123,9 +124,6 @@ fn try_lower_expr<'gcx>(
         }
 
         hir::ExprKind::Index(target, mode) => {
-            // Compute the indexing parameters.
-            let (base, length) = compute_indexing(cx, builder.expr, env, mode)?;
-
             // Lower the indexee and make sure it can be indexed into.
             let target = cx.mir_lvalue(target, env);
             assert_span!(
@@ -136,6 +134,10 @@ fn try_lower_expr<'gcx>(
                 target.ty
             );
 
+            // Compute the indexing parameters.
+            let (base, length) =
+                compute_indexing(cx, builder.expr, env, mode, target.ty.outermost_dim())?;
+
             // Build the cast lvalue.
             return Ok(builder.build(
                 ty,
@@ -165,6 +167,10 @@ fn try_lower_expr<'gcx>(
     // Show an error informing the user that the given expression cannot be
     // assigned to.
     error!("{:#?}", hir);
-    cx.emit(DiagBuilder2::error(format!("{} cannot be assigned to", hir.desc_full())).span(span));
+    cx.emit(
+        DiagBuilder2::error(format!("{} cannot be assigned to", hir.desc_full()))
+            .code(codes::SV0011)
+            .span(span),
+    );
     Err(())
 }
@@ -61,11 +61,12 @@ impl<'a, C: Context<'a>> Builder<'_, C> {
 }
 
 /// Lower an expression to an rvalue in the MIR.
-pub fn lower_expr<'gcx>(
-    cx: &impl Context<'gcx>,
+#[moore_derive::query]
+pub(crate) fn mir_rvalue<'a>(
+    cx: &impl Context<'a>,
     expr_id: NodeId,
     env: ParamEnv,
-) -> &'gcx Rvalue<'gcx> {
+) -> &'a Rvalue<'a> {
     let span = cx.span(expr_id);
     let builder = Builder {
         cx,
@@ -145,6 +146,13 @@ fn lower_expr_inner<'gcx>(
             bug_span!(span, cx, "unsized const with weird '{}' char", c)
         }
         hir::ExprKind::TimeConst(ref k) => Ok(builder.constant(value::make_time(k.clone()))),
+        hir::ExprKind::RealConst(ref k) => {
+            let value = match (k.numer().to_f64(), k.denom().to_f64()) {
+                (Some(n), Some(d)) => n / d,
+                _ => std::f64::INFINITY,
+            };
+            Ok(builder.constant(value::make_real(ty, value)))
+        }
         hir::ExprKind::StringConst(_) => Ok(builder.constant(value::make_array(
             // TODO(fschuiki): Actually assemble a real string here!
             ty::PackedType::make_dims(
@@ -295,8 +303,6 @@ fn lower_expr_inner<'gcx>(
         }
 
         hir::ExprKind::Index(target, mode) => {
-            let (base, length) = compute_indexing(cx, builder.expr, env, mode)?;
-
             // Cast the target to a simple bit vector type if needed.
             let target = cx.mir_rvalue(target, env);
 
@@ -308,6 +314,8 @@ fn lower_expr_inner<'gcx>(
                 "cannot index into `{}`; should be handled by typeck",
                 target.ty
             );
+            let (base, length) =
+                compute_indexing(cx, builder.expr, env, mode, target.ty.outermost_dim())?;
 
             // Build the cast rvalue.
             Ok(builder.build(
@@ -326,6 +334,10 @@ fn lower_expr_inner<'gcx>(
             if let Some(intf) = target_ty.and_then(|ty| ty.get_interface()) {
                 let def = cx.resolve_hierarchical_or_error(name, intf.ast)?.node.id();
                 Ok(builder.build(ty, RvalueKind::IntfSignal(value, def)))
+            } else if value.ty.is_packed_union() {
+                // All members of a packed union share the same bits.
+                let value = pack_simple_bit_vector(&builder, value);
+                Ok(unpack_simple_bit_vector(&builder, value, ty))
             } else {
                 let (field, _) = cx.resolve_field_access(expr_id, env)?;
                 Ok(builder.build(ty, RvalueKind::Member { value, field }))
@@ -420,13 +432,16 @@ fn lower_expr_inner<'gcx>(
 
 /// Compute the base and length of an indexing operation.
 ///
-/// Determine the index of the LSB and the width of the selection. Note that
-/// bit-selects are mapped to part-selects of length 0.
+/// Determine the position of the LSB and the width of the selection. Note
+/// that bit-selects are mapped to part-selects of length 0. Indices are mapped
+/// to positions within the outermost dimension `dim` of the indexed type, as
+/// described by `Dim::position`.
 pub(crate) fn compute_indexing<'gcx>(
     cx: &impl Context<'gcx>,
     origin: NodeId,
     env: ParamEnv,
     mode: hir::IndexMode,
+    dim: Option<ty::Dim<'gcx>>,
 ) -> Result<(&'gcx Rvalue<'gcx>, usize)> {
     let builder = Builder {
         cx,
@@ -434,41 +449,45 @@ pub(crate) fn compute_indexing<'gcx>(
         expr: origin,
         env,
     };
+    let dim = dim.filter(|dim| dim.get_range().is_some());
+    let reversed = dim.map(|dim| dim.is_reversed()).unwrap_or(false);
+    let const_length = |delta| -> Result<usize> {
+        Ok(builder
+            .cx
+            .constant_int_value_of(delta, env)?
+            .to_usize()
+            .unwrap())
+    };
     Ok(match mode {
-        hir::IndexMode::One(index) => (cx.mir_rvalue(index, env), 0),
-        hir::IndexMode::Many(ast::RangeMode::RelativeUp, base, delta) => (
-            cx.mir_rvalue(base, env),
-            builder
-                .cx
-                .constant_int_value_of(delta, env)?
-                .to_usize()
-                .unwrap(),
-        ),
+        hir::IndexMode::One(index) => {
+            let index = cx.mir_rvalue(index, env);
+            (index_position(&builder, index, dim, 0), 0)
+        }
+        // `[i +: w]` selects the indices `i` to `i+w-1`.
+        hir::IndexMode::Many(ast::RangeMode::RelativeUp, base, delta) => {
+            let base = cx.mir_rvalue(base, env);
+            let length = const_length(delta)?;
+            let lsb = if reversed { length as isize - 1 } else { 0 };
+            (index_position(&builder, base, dim, lsb), length)
+        }
+        // `[i -: w]` selects the indices `i-w+1` to `i`.
         hir::IndexMode::Many(ast::RangeMode::RelativeDown, base, delta) => {
             let base = cx.mir_rvalue(base, env);
-            let delta_rvalue = cx.mir_rvalue(delta, env);
-            assert_type!(delta_rvalue.ty, base.ty, delta_rvalue.span, builder.cx);
-            let base = builder.build(
-                base.ty,
-                RvalueKind::IntBinaryArith {
-                    op: IntBinaryArithOp::Sub,
-                    sign: base.ty.get_simple_bit_vector().unwrap().sign,
-                    domain: base.ty.domain(),
-                    lhs: base,
-                    rhs: delta_rvalue,
-                },
-            );
-            let length = builder
-                .cx
-                .constant_int_value_of(delta, env)?
-                .to_usize()
-                .unwrap();
-            (base, length)
+            let length = const_length(delta)?;
+            let lsb = if reversed { 0 } else { 1 - length as isize };
+            (index_position(&builder, base, dim, lsb), length)
         }
         hir::IndexMode::Many(ast::RangeMode::Absolute, lhs, rhs) => {
             let lhs_int = cx.constant_int_value_of(lhs, env)?;
             let rhs_int = cx.constant_int_value_of(rhs, env)?;
-            let base = std::cmp::min(lhs_int, rhs_int).clone();
+            let base = match dim {
+                Some(dim) => {
+                    let lhs = dim.position(lhs_int.to_isize().unwrap());
+                    let rhs = dim.position(rhs_int.to_isize().unwrap());
+                    BigInt::from(std::cmp::min(lhs, rhs))
+                }
+                None => std::cmp::min(lhs_int, rhs_int).clone(),
+            };
             let base_ty =
                 SbvType::new(ty::Domain::TwoValued, ty::Sign::Signed, max(base.bits(), 1))
                     .to_unpacked(builder.cx);
@@ -482,6 +501,77 @@ pub(crate) fn compute_indexing<'gcx>(
     })
 }
 
+/// Map the index `index + lsb` to its position within a dimension.
+///
+/// Indices into descending dimensions starting at zero, such as `[7:0]`, are
+/// their own positions and are returned unchanged. Otherwise the position is
+/// computed on an integer at least 32 bits wide, such that it does not wrap
+/// around for any valid index.
+fn index_position<'a>(
+    builder: &Builder<'_, impl Context<'a>>,
+    index: &'a Rvalue<'a>,
+    dim: Option<ty::Dim<'a>>,
+    lsb: isize,
+) -> &'a Rvalue<'a> {
+    if index.is_error() {
+        return index;
+    }
+
+    // Positions are computed as `index - offset`, or `offset - index` for
+    // reversed dimensions.
+    let reversed = dim.map(|dim| dim.is_reversed()).unwrap_or(false);
+    let offset = match dim.and_then(|dim| dim.get_range()) {
+        Some(r) if reversed => r.offset + r.size as isize - 1 - lsb,
+        Some(r) => r.offset - lsb,
+        None => -lsb,
+    };
+    if offset == 0 && !reversed {
+        return index;
+    }
+
+    // Widen the index such that the computation does not wrap around.
+    let sbvt = index.ty.simple_bit_vector(builder.cx, index.span);
+    let index = if sbvt.size < 32 {
+        let ty = sbvt.change_size(32).to_unpacked(builder.cx);
+        builder.build(
+            ty,
+            match sbvt.sign {
+                ty::Sign::Signed => RvalueKind::SignExtend(32, index),
+                ty::Sign::Unsigned => RvalueKind::ZeroExtend(32, index),
+            },
+        )
+    } else {
+        index
+    };
+    let ty = index.ty;
+    let width = max(sbvt.size, 32);
+
+    // Compute the position.
+    let mut offset = BigInt::from(offset);
+    if offset.is_negative() {
+        offset += BigInt::one() << width;
+    }
+    let offset = builder.build(
+        ty,
+        RvalueKind::Const(builder.cx.intern_value(value::make_int(ty, offset))),
+    );
+    let (lhs, rhs) = if reversed {
+        (offset, index)
+    } else {
+        (index, offset)
+    };
+    builder.build(
+        ty,
+        RvalueKind::IntBinaryArith {
+            op: IntBinaryArithOp::Sub,
+            sign: sbvt.sign,
+            domain: sbvt.domain,
+            lhs,
+            rhs,
+        },
+    )
+}
+
 /// Generate the nodes necessary for a cast operation.
 fn lower_cast<'gcx>(
     builder: &Builder<'_, impl Context<'gcx>>,
@@ -516,6 +606,17 @@ fn lower_cast<'gcx>(
     for &(op, to) in &to.casts {
         debug!("- {:?} from `{}` to `{}`", op, value.ty, to);
         match op {
+            CastOp::Bool if value.ty.is_real() => {
+                let zero = builder.constant(value::make_real(value.ty, 0.0));
+                value = builder.build(
+                    to,
+                    RvalueKind::RealComp {
+                        op: RealCompOp::Neq,
+                        lhs: value,
+                        rhs: zero,
+                    },
+                );
+            }
             CastOp::Bool => {
                 assert_span!(value.ty.is_simple_bit_vector(), value.span, builder.cx);
                 value = builder.build(to, RvalueKind::CastToBool(value));
@@ -559,6 +660,19 @@ fn lower_cast<'gcx>(
             CastOp::PickModport => {
                 value = builder.build(to, value.kind.clone());
             }
+            CastOp::IntToReal => {
+                let sign = value.ty.simple_bit_vector(builder.cx, value.span).sign;
+                value = builder.build(to, RvalueKind::IntToReal(sign, value));
+            }
+            CastOp::RealToInt => {
+                assert_span!(value.ty.is_real(), value.span, builder.cx);
+                assert_span!(to.is_simple_bit_vector(), value.span, builder.cx);
+                value = builder.build(to, RvalueKind::RealToInt(value));
+            }
+            CastOp::RealToReal => {
+                assert_span!(value.ty.is_real(), value.span, builder.cx);
+                value = builder.build(to, RvalueKind::RealToReal(value));
+            }
         }
         if !value.ty.is_identical(to) {
             error!(
@@ -612,6 +726,8 @@ fn pack_simple_bit_vector<'gcx>(
 }
 
 /// Pack a struct as a simple bit vector.
+///
+/// The first member of the struct occupies the most significant bits.
 fn pack_struct<'a>(
     builder: &Builder<'_, impl Context<'a>>,
     value: &'a Rvalue<'a>,
@@ -631,6 +747,8 @@ fn pack_struct<'a>(
 }
 
 /// Pack an array as a simple bit vector.
+///
+/// The leftmost element of the array occupies the most significant bits.
 fn pack_array<'a>(
     builder: &Builder<'_, impl Context<'a>>,
     value: &'a Rvalue<'a>,
@@ -659,11 +777,16 @@ fn pack_array<'a>(
         }
     }
 
-    // Cast each element.
+    // Cast each element, starting with the leftmost one.
     let mut packed_elements = vec![];
     let int_ty =
         SbvType::new(ty::Domain::TwoValued, ty::Sign::Unsigned, 32).to_unpacked(builder.cx);
-    for i in 0..length {
+    let positions: Vec<usize> = if dim.is_left_highest() {
+        (0..length).rev().collect()
+    } else {
+        (0..length).collect()
+    };
+    for i in positions {
         let i = builder.build(
             int_ty,
             RvalueKind::Const(builder.cx.intern_value(value::make_int(int_ty, i.into()))),
@@ -706,16 +829,18 @@ fn unpack_simple_bit_vector<'a>(
 }
 
 /// Unpack a struct from a simple bit vector.
+///
+/// The first member of the struct occupies the most significant bits.
 fn unpack_struct<'a>(
     builder: &Builder<'_, impl Context<'a>>,
     value: &'a Rvalue<'a>,
     to: &'a UnpackedType<'a>,
     strukt: &'a ty::StructType<'a>,
 ) -> &'a Rvalue<'a> {
-    // Unpack each of the fields.
+    // Unpack each of the fields, starting with the last one in the LSBs.
     let mut offset = 0;
     let mut unpacked_fields = vec![];
-    for field in &strukt.members {
+    for field in strukt.members.iter().rev() {
         let sbvt = field.ty.simple_bit_vector(builder.cx, value.span);
         let ty =
             SbvType::new(ty::Domain::TwoValued, ty::Sign::Unsigned, 32).to_unpacked(builder.cx);
@@ -736,12 +861,15 @@ fn unpack_struct<'a>(
         unpacked_fields.push(value);
         offset += w;
     }
+    unpacked_fields.reverse();
 
     // Construct the struct.
     builder.build(to, RvalueKind::ConstructStruct(unpacked_fields))
 }
 
 /// Unpack an array from a simple bit vector.
+///
+/// The leftmost element of the array occupies the most significant bits.
 fn unpack_array<'a>(
     builder: &Builder<'_, impl Context<'a>>,
     value: &'a Rvalue<'a>,
@@ -779,9 +907,14 @@ fn unpack_array<'a>(
     for i in 0..length {
         let ty =
             SbvType::new(ty::Domain::TwoValued, ty::Sign::Unsigned, 32).to_unpacked(builder.cx);
+        let offset = if dim.is_left_highest() {
+            i * w
+        } else {
+            (length - 1 - i) * w
+        };
         let base = builder.build(
             ty,
-            RvalueKind::Const(builder.cx.intern_value(value::make_int(ty, (i * w).into()))),
+            RvalueKind::Const(builder.cx.intern_value(value::make_int(ty, offset.into()))),
         );
         let elem = builder.build(
             sbvt.to_unpacked(builder.cx),
@@ -823,12 +956,13 @@ fn lower_pattern<'a>(
         })
         .collect();
 
-    // Construct the correct output value.
+    // Construct the correct output value. The fields are ordered by position,
+    // but concatenation expects the most significant field first.
     if ty.coalesces_to_llhd_scalar() {
         if values.len() == 1 {
             values[0]
         } else {
-            builder.build(ty, RvalueKind::Concat(values))
+            builder.build(ty, RvalueKind::Concat(values.into_iter().rev().collect()))
         }
     } else if ty.outermost_dim().is_some() {
         builder.build(
@@ -924,9 +1058,23 @@ fn lower_int_unary_arith<'gcx>(
     }
 
     // Check that the operand is of the right type.
-    let sbvt = result_ty.simple_bit_vector(builder.cx, builder.span);
     assert_type!(arg.ty, result_ty, builder.span, builder.cx);
 
+    // Handle real numbers.
+    if result_ty.is_real() {
+        return match op {
+            hir::UnaryOp::Pos => arg,
+            _ => builder.build(
+                result_ty,
+                RvalueKind::RealUnaryArith {
+                    op: RealUnaryArithOp::Neg,
+                    arg,
+                },
+            ),
+        };
+    }
+    let sbvt = result_ty.simple_bit_vector(builder.cx, builder.span);
+
     // Determine the operation.
     let op = match op {
         hir::UnaryOp::Pos => return arg,
@@ -966,6 +1114,26 @@ fn lower_int_binary_arith<'a>(
         return builder.error();
     }
 
+    // Handle real numbers.
+    if result_ty.is_real() {
+        assert_type!(lhs.ty, result_ty, builder.span, builder.cx);
+        assert_type!(rhs.ty, result_ty, builder.span, builder.cx);
+        let op = match op {
+            hir::BinaryOp::Add => RealBinaryArithOp::Add,
+            hir::BinaryOp::Sub => RealBinaryArithOp::Sub,
+            hir::BinaryOp::Mul => RealBinaryArithOp::Mul,
+            hir::BinaryOp::Div => RealBinaryArithOp::Div,
+            hir::BinaryOp::Pow => RealBinaryArithOp::Pow,
+            _ => bug_span!(
+                builder.span,
+                builder.cx,
+                "{:?} is not a real binary arithmetic operator",
+                op
+            ),
+        };
+        return builder.build(result_ty, RvalueKind::RealBinaryArith { op, lhs, rhs });
+    }
+
     // Check that the operands are of the right type.
     let sbvt = result_ty.simple_bit_vector(builder.cx, builder.span);
     assert_type!(lhs.ty, result_ty, builder.span, builder.cx);
@@ -1036,6 +1204,21 @@ fn lower_int_comparison<'a>(
         ),
     };
 
+    // Handle real numbers.
+    if ty.is_real() {
+        assert_type!(lhs.ty, ty, builder.span, builder.cx);
+        assert_type!(rhs.ty, ty, builder.span, builder.cx);
+        let op = match op {
+            IntCompOp::Eq => RealCompOp::Eq,
+            IntCompOp::Neq => RealCompOp::Neq,
+            IntCompOp::Lt => RealCompOp::Lt,
+            IntCompOp::Leq => RealCompOp::Leq,
+            IntCompOp::Gt => RealCompOp::Gt,
+            IntCompOp::Geq => RealCompOp::Geq,
+        };
+        return builder.build(result_ty, RvalueKind::RealComp { op, lhs, rhs });
+    }
+
     // Assemble the node.
     make_int_comparison(builder, op, result_ty, ty, lhs, rhs)
 }
@@ -1348,8 +1531,23 @@ fn lower_int_incdec<'gcx>(
     }
 
     // Compute the new value, depending on the operand type.
-    let sbvt = lv.ty.simple_bit_vector(builder.cx, builder.span);
-    let new = if sbvt.size == 1 {
+    let new = if lv.ty.is_real() {
+        // Real values add/subtract one.
+        let op = match op {
+            hir::UnaryOp::PreInc | hir::UnaryOp::PostInc => RealBinaryArithOp::Add,
+            hir::UnaryOp::PreDec | hir::UnaryOp::PostDec => RealBinaryArithOp::Sub,
+            _ => unreachable!(),
+        };
+        let one = builder.constant(value::make_real(lv.ty, 1.0));
+        builder.build(
+            lv.ty,
+            RvalueKind::RealBinaryArith {
+                op,
+                lhs: rv,
+                rhs: one,
+            },
+        )
+    } else if lv.ty.simple_bit_vector(builder.cx, builder.span).size == 1 {
         // Single bit values simply toggle the bit.
         builder.build(
             lv.ty,
@@ -1360,6 +1558,7 @@ fn lower_int_incdec<'gcx>(
         )
     } else {
         // Bit vector values add/subtract one.
+        let sbvt = lv.ty.simple_bit_vector(builder.cx, builder.span);
         let op = match op {
             hir::UnaryOp::PreInc | hir::UnaryOp::PostInc => IntBinaryArithOp::Add,
             hir::UnaryOp::PreDec | hir::UnaryOp::PostDec => IntBinaryArithOp::Sub,
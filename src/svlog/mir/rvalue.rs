@@ -47,6 +47,22 @@ impl<'a> Rvalue<'a> {
         self.konst
     }
 
+    /// Skip over casts that do not change the value of the rvalue.
+    ///
+    /// This looks through domain, sign, and width casts as well as transmutes,
+    /// which is useful to find the variable or port an expression refers to.
+    pub fn strip_casts(&'a self) -> &'a Rvalue<'a> {
+        match self.kind {
+            RvalueKind::CastValueDomain { value, .. }
+            | RvalueKind::Transmute(value)
+            | RvalueKind::CastSign(_, value)
+            | RvalueKind::Truncate(_, value)
+            | RvalueKind::ZeroExtend(_, value)
+            | RvalueKind::SignExtend(_, value) => value.strip_casts(),
+            _ => self,
+        }
+    }
+
     /// Get the `Intf` nested within `Index`, if one exists.
     pub fn get_intf(&self) -> Option<NodeId> {
         match self.kind {
@@ -98,6 +114,15 @@ pub enum RvalueKind<'a> {
     /// [7:0]` to `bit signed [31:0]`.
     // TODO: Add SBVT
     SignExtend(usize, &'a Rvalue<'a>),
+    /// A conversion from an integer to a real number. The sign indicates how
+    /// the integer is to be interpreted.
+    IntToReal(ty::Sign, &'a Rvalue<'a>),
+    /// A conversion from a real number to an integer, rounding to the nearest
+    /// integer. E.g. `real` to `int`.
+    RealToInt(&'a Rvalue<'a>),
+    /// A conversion between real numbers of different precision. E.g.
+    /// `shortreal` to `real`.
+    RealToReal(&'a Rvalue<'a>),
     /// Constructor for an array.
     ConstructArray(HashMap<usize, &'a Rvalue<'a>>),
     /// Constructor for a struct.
@@ -149,6 +174,23 @@ pub enum RvalueKind<'a> {
         lhs: &'a Rvalue<'a>,
         rhs: &'a Rvalue<'a>,
     },
+    /// A real unary arithmetic operator.
+    RealUnaryArith {
+        op: RealUnaryArithOp,
+        arg: &'a Rvalue<'a>,
+    },
+    /// A real binary arithmetic operator.
+    RealBinaryArith {
+        op: RealBinaryArithOp,
+        lhs: &'a Rvalue<'a>,
+        rhs: &'a Rvalue<'a>,
+    },
+    /// A real comparison operator.
+    RealComp {
+        op: RealCompOp,
+        lhs: &'a Rvalue<'a>,
+        rhs: &'a Rvalue<'a>,
+    },
     /// Concatenate multiple values.
     ///
     /// The values are cast to and treated as packed bit vectors, and the result
@@ -228,6 +270,9 @@ impl<'a> RvalueKind<'a> {
             | RvalueKind::Truncate(_, value)
             | RvalueKind::ZeroExtend(_, value)
             | RvalueKind::SignExtend(_, value)
+            | RvalueKind::IntToReal(_, value)
+            | RvalueKind::RealToInt(value)
+            | RvalueKind::RealToReal(value)
             | RvalueKind::Repeat(_, value)
             | RvalueKind::Member { value, .. } => value.is_const(),
             RvalueKind::ConstructArray(values) => values.values().all(|v| v.is_const()),
@@ -235,10 +280,13 @@ impl<'a> RvalueKind<'a> {
             RvalueKind::Const(_) => true,
             RvalueKind::UnaryBitwise { arg, .. }
             | RvalueKind::IntUnaryArith { arg, .. }
+            | RvalueKind::RealUnaryArith { arg, .. }
             | RvalueKind::Reduction { arg, .. } => arg.is_const(),
             RvalueKind::BinaryBitwise { lhs, rhs, .. }
             | RvalueKind::IntBinaryArith { lhs, rhs, .. }
-            | RvalueKind::IntComp { lhs, rhs, .. } => lhs.is_const() && rhs.is_const(),
+            | RvalueKind::IntComp { lhs, rhs, .. }
+            | RvalueKind::RealBinaryArith { lhs, rhs, .. }
+            | RvalueKind::RealComp { lhs, rhs, .. } => lhs.is_const() && rhs.is_const(),
             RvalueKind::Concat(values) => values.iter().all(|v| v.is_const()),
             RvalueKind::Var(_) => false,
             RvalueKind::Port(_) => false,
@@ -310,6 +358,39 @@ pub enum IntCompOp {
     Geq,
 }
 
+/// The real unary arithmetic operators.
+#[moore_derive::visit_without_foreach]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum RealUnaryArithOp {
+    Neg,
+}
+
+/// The real binary arithmetic operators.
+#[moore_derive::visit_without_foreach]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum RealBinaryArithOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+/// The real comparison operators.
+#[moore_derive::visit_without_foreach]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum RealCompOp {
+    Eq,
+    Neq,
+    Lt,
+    Leq,
+    Gt,
+    Geq,
+}
+
 /// The shift operators.
 #[moore_derive::visit_without_foreach]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -9,6 +9,7 @@ use crate::{
     ty::UnpackedType,
     value::Value,
 };
+use std::{collections::HashMap, sync::Arc};
 
 /// A parameter environment.
 ///
@@ -157,138 +158,156 @@ pub enum ParamEnvSource<'hir> {
     },
 }
 
-pub(crate) fn compute<'gcx>(
-    cx: &impl Context<'gcx>,
-    src: ParamEnvSource<'gcx>,
-) -> Result<ParamEnv> {
+/// Compute the parameter bindings for an instantiation.
+#[moore_derive::query]
+pub(crate) fn param_env<'a>(cx: &impl Context<'a>, src: ParamEnvSource<'a>) -> Result<ParamEnv> {
     match src {
         ParamEnvSource::ModuleInst {
             module,
             env,
             pos,
             named,
-        } => param_env_from_instance(
-            cx,
-            module.ast,
-            module
-                .params
-                .iter()
-                .cloned()
-                .chain(module.block.params.iter().cloned())
-                .collect(),
-            env,
-            pos,
-            named,
-        ),
+        } => param_env_from_instance(cx, module.ast, env, pos, named),
         ParamEnvSource::InterfaceInst {
             interface,
             env,
             pos,
             named,
-        } => param_env_from_instance(
-            cx,
-            interface.ast,
-            interface
-                .params
-                .iter()
-                .flat_map(|p| match &p.kind {
-                    ast::ParamKind::Type(x) => {
-                        x.iter().map(|d| d.id()).collect::<Vec<_>>().into_iter()
-                    }
-                    ast::ParamKind::Value(x) => {
-                        x.iter().map(|d| d.id()).collect::<Vec<_>>().into_iter()
-                    }
-                })
-                .chain(interface.block.params.iter().cloned())
-                .collect(),
-            env,
-            pos,
-            named,
-        ),
+        } => param_env_from_instance(cx, interface.ast, env, pos, named),
+    }
+}
+
+/// The parameters of a module or interface.
+///
+/// This is computed once per module, such that instantiations can look up
+/// their named parameter assignments without scanning the parameters.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParamList {
+    /// The parameters in declaration order, together with their name and
+    /// whether they are type or value parameters.
+    pub params: Vec<(NodeId, Name, hir::Hint)>,
+    /// The index of each parameter in `params`, by name.
+    pub by_name: HashMap<Name, usize>,
+}
+
+/// Determine the parameters of a module or interface.
+#[moore_derive::query]
+pub(crate) fn param_list<'a>(
+    cx: &impl Context<'a>,
+    Ref(node): Ref<'a, dyn ast::AnyNode<'a>>,
+) -> Result<Arc<ParamList>> {
+    let mut ids = vec![];
+    match node.as_all() {
+        ast::AllNode::Module(x) => {
+            let hir = cx.hir_of_module(x)?;
+            ids.extend(hir.params.iter().cloned());
+            ids.extend(hir.block.params.iter().cloned());
+        }
+        ast::AllNode::Interface(x) => {
+            let hir = cx.hir_of_interface(x)?;
+            for param in &x.params {
+                match &param.kind {
+                    ast::ParamKind::Type(x) => ids.extend(x.iter().map(|d| d.id())),
+                    ast::ParamKind::Value(x) => ids.extend(x.iter().map(|d| d.id())),
+                }
+            }
+            ids.extend(hir.block.params.iter().cloned());
+        }
+        _ => unreachable!("parameters of {:?}", node),
+    }
+    let mut list = ParamList {
+        params: Vec::with_capacity(ids.len()),
+        by_name: HashMap::with_capacity(ids.len()),
+    };
+    for id in ids {
+        let (name, hint) = match cx.ast_of(id)? {
+            AstNode::TypeParam(_, p) => (p.name.value, hir::Hint::Type),
+            AstNode::ValueParam(_, p) => (p.name.value, hir::Hint::Expr),
+            _ => unreachable!(),
+        };
+        list.by_name.entry(name).or_insert(list.params.len());
+        list.params.push((id, name, hint));
     }
+    Ok(Arc::new(list))
 }
 
 fn param_env_from_instance<'a>(
     cx: &impl Context<'a>,
     node: &'a dyn ast::AnyNode<'a>,
-    params: Vec<NodeId>,
     env: ParamEnv,
     pos: &[PosParam],
     named: &[NamedParam],
 ) -> Result<ParamEnv> {
+    let list = cx.param_list(Ref(node))?;
+
     // Associate the positional and named assignments with the actual
     // parameters of the module.
-    let param_iter = pos
+    let assignments = pos
         .iter()
         .enumerate()
-        .map(|(index, &(span, assign_id))| match params.get(index) {
-            Some(&param_id) => Ok((param_id, (assign_id, env))),
+        .map(|(index, &(span, assign_id))| match list.params.get(index) {
+            Some(param) => Some((param, assign_id)),
             None => {
                 cx.emit(
-                    DiagBuilder2::error(format!("{} only has {} parameter(s)", node, params.len()))
-                        .span(span),
+                    DiagBuilder2::error(format!(
+                        "{} only has {} parameter(s)",
+                        node,
+                        list.params.len()
+                    ))
+                    .code(codes::SV0007)
+                    .span(span),
                 );
-                Err(())
+                None
             }
         })
-        .chain(named.iter().map(|&(_span, name, assign_id)| {
-            let names: Vec<_> = params
-                .iter()
-                .flat_map(|&id| match cx.ast_of(id) {
-                    Ok(AstNode::TypeParam(_, p)) => Some((p.name.value, id)),
-                    Ok(AstNode::ValueParam(_, p)) => Some((p.name.value, id)),
-                    Ok(_) => unreachable!(),
-                    Err(()) => None,
-                })
-                .collect();
-            match names
-                .iter()
-                .find(|&(param_name, _)| *param_name == name.value)
-            {
-                Some(&(_, param_id)) => Ok((param_id, (assign_id, env))),
+        .chain(named.iter().map(
+            |&(_span, name, assign_id)| match list.by_name.get(&name.value) {
+                Some(&index) => Some((&list.params[index], assign_id)),
                 None => {
                     cx.emit(
                         DiagBuilder2::error(format!("no parameter `{}` in {}", name, node,))
+                            .code(codes::SV0006)
                             .span(name.span)
                             .add_note(format!(
                                 "declared parameters are {}",
-                                names
+                                list.params
                                     .iter()
-                                    .map(|&(n, _)| format!("`{}`", n))
+                                    .map(|&(_, n, _)| format!("`{}`", n))
                                     .collect::<Vec<_>>()
                                     .join(", ")
                             )),
                     );
-                    Err(())
+                    None
                 }
-            }
-        }));
-    let param_iter = param_iter
-        .collect::<Vec<_>>()
-        .into_iter()
-        .collect::<Result<Vec<_>>>()?
-        .into_iter();
+            },
+        ));
 
-    // Split up type and value parameters.
+    // Split up type and value parameters. All assignments are checked before
+    // giving up, such that every invalid one is reported.
     let mut types = vec![];
     let mut values = vec![];
-    for (param_id, assign_id) in param_iter {
+    let mut failed = false;
+    for assignment in assignments {
+        let (&(param_id, _, hint), assign_id) = match assignment {
+            Some(x) => x,
+            None => {
+                failed = true;
+                continue;
+            }
+        };
         let assign_id = match assign_id {
-            (Some(i), n) => i.env(n),
-            _ => continue,
+            Some(i) => i.env(env),
+            None => continue,
         };
-        match cx.ast_of(param_id)? {
-            AstNode::TypeParam(..) => {
-                cx.set_lowering_hint(assign_id.0, hir::Hint::Type);
-                types.push((param_id, ParamEnvBinding::Indirect(assign_id)))
-            }
-            AstNode::ValueParam(..) => {
-                cx.set_lowering_hint(assign_id.0, hir::Hint::Expr);
-                values.push((param_id, ParamEnvBinding::Indirect(assign_id)))
-            }
-            _ => unreachable!(),
+        cx.set_lowering_hint(assign_id.0, hint);
+        match hint {
+            hir::Hint::Type => types.push((param_id, ParamEnvBinding::Indirect(assign_id))),
+            hir::Hint::Expr => values.push((param_id, ParamEnvBinding::Indirect(assign_id))),
         }
     }
+    if failed {
+        return Err(());
+    }
 
     let env = cx.intern_param_env(ParamEnvData {
         module: Some(node.id()),
@@ -15,8 +15,10 @@ pub struct PatternMapping<'a> {
     pub hir: &'a hir::Expr<'a>,
     /// The type the pattern maps to.
     pub ty: &'a ty::UnpackedType<'a>,
-    /// The mapped expression for each field. The fields are in type order.
-    /// Multiple fields may be assigned the same expression.
+    /// The mapped expression for each field. The fields are in type order,
+    /// i.e. struct members in declaration order and array elements and bits by
+    /// ascending position as described by `ty::Dim::position`. Multiple fields
+    /// may be assigned the same expression.
     pub fields: Vec<(PatternField<'a>, &'a hir::Expr<'a>)>,
 }
 
@@ -128,12 +130,8 @@ fn map_named_array_pattern<'a>(
     span: Span,
     env: ParamEnv,
 ) -> Result<Vec<(PatternField<'a>, &'a hir::Expr<'a>)>> {
-    // Determine the length of the array and the offset of the indexes.
-    let (length, offset) = match dim
-        .get_range()
-        .map(|r| (r.size, r.offset))
-        .or_else(|| dim.get_size().map(|s| (s, 0)))
-    {
+    // Determine the length of the array.
+    let length = match dim.get_size() {
         Some(x) => x,
         None => bug_span!(
             span,
@@ -171,7 +169,10 @@ fn map_named_array_pattern<'a>(
                 let index = match || -> Result<usize> {
                     let index = cx.constant_value_of(member_id, env);
                     let index = match &index.kind {
-                        ValueKind::Int(i, ..) => i - num::BigInt::from(offset),
+                        ValueKind::Int(i, ..) => match i.to_isize() {
+                            Some(i) => num::BigInt::from(dim.position(i)),
+                            None => i.clone(),
+                        },
                         ValueKind::Error => return Err(()),
                         _ => {
                             cx.emit(
@@ -186,6 +187,7 @@ fn map_named_array_pattern<'a>(
                         _ => {
                             cx.emit(
                                 DiagBuilder2::error(format!("index `{}` out of bounds", index))
+                                    .code(codes::SV0013)
                                     .span(cx.span(member_id)),
                             );
                             return Err(());
@@ -204,12 +206,15 @@ fn map_named_array_pattern<'a>(
                 let entry = (PatternField::Array(elem_ty), to);
                 if let Some((_, prev)) = values.insert(index, entry) {
                     cx.emit(
-                        DiagBuilder2::warning(format!(
-                            "`{}` overwrites previous value `{}` at index {}",
-                            to.span.extract(),
-                            prev.span.extract(),
-                            index
-                        ))
+                        DiagBuilder2::lint(
+                            lints::PATTERN_OVERWRITE,
+                            format!(
+                                "`{}` overwrites previous value `{}` at index {}",
+                                to.span.extract(),
+                                prev.span.extract(),
+                                index
+                            ),
+                        )
                         .span(to.span)
                         .add_note("Previous value was here:")
                         .span(prev.span),
@@ -334,12 +339,15 @@ fn map_named_struct_pattern<'a>(
                     let entry = (PatternField::Struct(&strukt.members[index]), to);
                     if let Some((_, prev)) = values.insert(index, entry) {
                         cx.emit(
-                            DiagBuilder2::warning(format!(
-                                "`{}` overwrites previous value `{}` for member `{}`",
-                                to.span.extract(),
-                                prev.span.extract(),
-                                name
-                            ))
+                            DiagBuilder2::lint(
+                                lints::PATTERN_OVERWRITE,
+                                format!(
+                                    "`{}` overwrites previous value `{}` for member `{}`",
+                                    to.span.extract(),
+                                    prev.span.extract(),
+                                    name
+                                ),
+                            )
                             .span(to.span)
                             .add_note("Previous value was here:")
                             .span(prev.span),
@@ -470,6 +478,10 @@ fn map_positional_pattern<'a>(
         )
     } else if let Some(dim) = ty.outermost_dim() {
         let elem_ty = ty.pop_dim(cx).unwrap();
+        let mut values = values;
+        if dim.is_left_highest() {
+            values.reverse();
+        }
         match dim.get_size() {
             Some(size) => (
                 size,
@@ -645,7 +645,7 @@ fn lower_node_ports_nonansi<'a>(
             _ => continue,
         };
         // trace!("Found {:#?}", ast);
-        for name in &ast.names {
+        for name in ast.names {
             let data = PartialPort {
                 span: ast.span,
                 name: Spanned::new(name.name, name.name_span),
@@ -685,7 +685,7 @@ fn lower_node_ports_nonansi<'a>(
     for item in ast_items {
         match &item.data {
             ast::ItemData::VarDecl(vd) => {
-                for name in &vd.names {
+                for name in vd.names {
                     let index = match decl_names.get(&name.name) {
                         Some(&e) => e,
                         None => continue,
@@ -705,7 +705,7 @@ fn lower_node_ports_nonansi<'a>(
                 }
             }
             ast::ItemData::NetDecl(nd) => {
-                for name in &nd.names {
+                for name in nd.names {
                     let index = match decl_names.get(&name.name) {
                         Some(&e) => e,
                         None => continue,
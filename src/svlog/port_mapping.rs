@@ -89,7 +89,7 @@ pub(crate) fn port_mapping<'a>(
     });
 
     // Associate the named assignments with external ports.
-    let named_iter = named.iter().map(|&(_span, name, assign_id)| {
+    let named_iter = named.iter().map(|&(span, name, assign_id)| {
         let names = match port_list.ext_named.as_ref() {
             Some(x) => x,
             None => {
@@ -101,7 +101,13 @@ pub(crate) fn port_mapping<'a>(
                                  position.",
                             node
                         ))
-                        .add_note(format!("Remove `.{}(...)`", name)),
+                        .span_suggestion(
+                            span,
+                            format!("Remove `.{}(...)`", name),
+                            assign_id
+                                .map(|id| cx.span(id).extract())
+                                .unwrap_or_default(),
+                        ),
                 );
                 return Err(());
             }
@@ -111,6 +117,7 @@ pub(crate) fn port_mapping<'a>(
             None => {
                 cx.emit(
                     DiagBuilder2::error(format!("no port `{}` in {}", name, node,))
+                        .code(codes::SV0004)
                         .span(name.span)
                         .add_note(format!(
                             "Declared ports are {}",
@@ -0,0 +1,87 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Reachability of modules from the top-level modules of a design.
+//!
+//! Only the modules which are instantiated, directly or indirectly, by one of
+//! the top-level modules selected for elaboration need to be checked and
+//! emitted. All other modules in the input files can be pruned. If no
+//! top-level modules are selected, the modules which are not instantiated
+//! anywhere are used instead.
+
+use crate::{ast::AcceptVisitor, ast_map::AstNode, crate_prelude::*};
+use std::collections::HashSet;
+
+/// Determine the modules reachable from a set of top-level modules.
+///
+/// A module is reachable if it is one of the `tops`, or if it is instantiated
+/// in a reachable module. All instantiations within a module are considered,
+/// including the ones in generate blocks that may end up being disabled, such
+/// that the result does not depend on any parameters. The modules are returned
+/// in the order in which they are discovered.
+pub fn reachable_modules<'gcx>(cx: &GlobalContext<'gcx>, tops: &[NodeId]) -> Vec<NodeId> {
+    let mut seen: HashSet<NodeId> = tops.iter().cloned().collect();
+    let mut reachable: Vec<NodeId> = vec![];
+    let mut todo: Vec<NodeId> = tops.iter().rev().cloned().collect();
+    while let Some(id) = todo.pop() {
+        reachable.push(id);
+        let module = match cx.ast_of(id) {
+            Ok(AstNode::Module(x)) => x,
+            _ => continue,
+        };
+        let mut collector = InstCollector {
+            cx,
+            targets: vec![],
+        };
+        module.accept(&mut collector);
+        for target in collector.targets.into_iter().rev() {
+            if seen.insert(target) {
+                todo.push(target);
+            }
+        }
+    }
+    reachable
+}
+
+/// Determine the modules which are not instantiated in any other module.
+///
+/// These are the top-level modules of a design if none are selected
+/// explicitly. As in `reachable_modules`, the instantiations in all generate
+/// blocks are considered. A module which only instantiates itself is still
+/// uninstantiated. The modules are returned in the order in which they are
+/// declared.
+pub fn uninstantiated_modules<'gcx>(cx: &GlobalContext<'gcx>) -> Vec<NodeId> {
+    let mut modules: Vec<NodeId> = cx.modules().map(|(_, id)| id).collect();
+    modules.sort();
+    let mut instantiated = HashSet::new();
+    for &id in &modules {
+        let module = match cx.ast_of(id) {
+            Ok(AstNode::Module(x)) => x,
+            _ => continue,
+        };
+        let mut collector = InstCollector {
+            cx,
+            targets: vec![],
+        };
+        module.accept(&mut collector);
+        instantiated.extend(collector.targets.into_iter().filter(|&target| target != id));
+    }
+    modules.retain(|id| !instantiated.contains(id));
+    modules
+}
+
+/// A visitor that collects the modules instantiated in an AST node.
+struct InstCollector<'a, 'gcx> {
+    cx: &'a GlobalContext<'gcx>,
+    targets: Vec<NodeId>,
+}
+
+impl<'a, 'gcx> ast::Visitor<'gcx> for InstCollector<'a, 'gcx> {
+    fn pre_visit_inst(&mut self, node: &'gcx ast::Inst<'gcx>) -> bool {
+        // Instantiations of anything other than a module, e.g. interfaces,
+        // are ignored.
+        if let Some(id) = self.cx.find_module(node.target.value) {
+            self.targets.push(id);
+        }
+        false
+    }
+}
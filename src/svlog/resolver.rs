@@ -85,7 +85,8 @@ impl RibKind {
 ///
 /// This will return either the rib the node itself generates, or the next rib
 /// up the hierarchy.
-pub(crate) fn local_rib<'gcx>(cx: &impl Context<'gcx>, node_id: NodeId) -> Result<&'gcx Rib> {
+#[moore_derive::query]
+pub(crate) fn local_rib<'a>(cx: &impl Context<'a>, node_id: NodeId) -> Result<&'a Rib> {
     let ast = cx.ast_of(node_id)?;
     trace!("local_rib for {} ({:?})", ast.desc_full(), node_id);
     let mut parent = None;
@@ -125,6 +126,7 @@ pub(crate) fn local_rib<'gcx>(cx: &impl Context<'gcx>, node_id: NodeId) -> Resul
                 None => {
                     cx.emit(
                         DiagBuilder2::error(format!("`{}` not found", import.pkg.value))
+                            .code(codes::SV0001)
                             .span(import.pkg.span),
                     );
                     return Err(());
@@ -214,10 +216,8 @@ fn local_rib_kind_for_type<'gcx>(cx: &impl Context<'gcx>, kind: &hir::TypeKind)
 /// Determine the hierarchical rib of a node.
 ///
 /// This will return a rib containing the hierarchical names exposed by a node.
-pub(crate) fn hierarchical_rib<'gcx>(
-    cx: &impl Context<'gcx>,
-    node_id: NodeId,
-) -> Result<&'gcx Rib> {
+#[moore_derive::query]
+pub(crate) fn hierarchical_rib<'a>(cx: &impl Context<'a>, node_id: NodeId) -> Result<&'a Rib> {
     let hir = cx.hir_of(node_id)?;
     let mut names = HashMap::new();
     let mut rib_id = match hir {
@@ -251,8 +251,9 @@ pub(crate) fn hierarchical_rib<'gcx>(
 /// Resolve a name upwards through the ribs.
 ///
 /// This is equivalent to performing regular scoped namespace lookup.
-pub(crate) fn resolve_upwards<'gcx>(
-    cx: &impl Context<'gcx>,
+#[moore_derive::query]
+pub(crate) fn resolve_upwards<'a>(
+    cx: &impl Context<'a>,
     name: Name,
     start_at: NodeId,
 ) -> Result<Option<NodeId>> {
@@ -274,8 +275,9 @@ pub(crate) fn resolve_upwards<'gcx>(
 /// Resolve a name downwards.
 ///
 /// This is equivalent to performing a hierarchical name lookup.
-pub(crate) fn resolve_downwards<'gcx>(
-    cx: &impl Context<'gcx>,
+#[moore_derive::query]
+pub(crate) fn resolve_downwards<'a>(
+    cx: &impl Context<'a>,
     name: Name,
     start_at: NodeId,
 ) -> Result<Option<NodeId>> {
@@ -294,8 +296,9 @@ pub(crate) fn resolve_downwards<'gcx>(
 }
 
 /// Resolve a node to its target.
-pub(crate) fn resolve_node<'gcx>(
-    cx: &impl Context<'gcx>,
+#[moore_derive::query]
+pub(crate) fn resolve_node<'a>(
+    cx: &impl Context<'a>,
     node_id: NodeId,
     env: ParamEnv,
 ) -> Result<NodeId> {
@@ -346,7 +349,8 @@ pub struct StructField {
 }
 
 /// Obtain the details of a struct definition.
-pub(crate) fn struct_def<'gcx>(cx: &impl Context<'gcx>, node_id: NodeId) -> Result<Arc<StructDef>> {
+#[moore_derive::query]
+pub(crate) fn struct_def<'a>(cx: &impl Context<'a>, node_id: NodeId) -> Result<Arc<StructDef>> {
     let hir = cx.hir_of(node_id)?;
     let struct_fields = match hir {
         HirNode::Type(hir::Type {
@@ -419,6 +423,7 @@ pub(crate) fn resolve_field_access<'a>(
         None => {
             cx.emit(
                 DiagBuilder2::error(format!("value of type `{}` has no field `{}`", ty, name))
+                    .code(codes::SV0008)
                     .span(name.span())
                     .add_note(format!("`{}` was defined here:", ty))
                     .span(strukt.ast.span()),
@@ -521,6 +526,14 @@ pub(crate) fn generated_scope<'a>(
     cx: &impl Context<'a>,
     node: &'a dyn ScopedNode<'a>,
 ) -> &'a Scope<'a> {
+    // The scopes of the root and the files change as files are replaced.
+    match node.as_all() {
+        ast::AllNode::Root(_) | ast::AllNode::SourceFile(_) => {
+            cx.read_input(crate::QueryInput::Items)
+        }
+        _ => (),
+    }
+
     // Find the parent scope.
     let parent = node
         .get_parent()
@@ -749,6 +762,7 @@ impl<'a, 'c, C: Context<'a>> ScopeGenerator<'a, 'c, C> {
             };
             if !def.may_override {
                 let d = DiagBuilder2::error(format!("`{}` is defined multiple times", def.name))
+                    .code(codes::SV0003)
                     .span(def.name.span)
                     .add_note(format!("Previous definition of `{}` was here:", def.name))
                     .span(existing.name.span);
@@ -1059,6 +1073,9 @@ pub(crate) fn scope_location<'a>(
     while let Some(node) = next {
         if let Some(scoped) = node.as_all().get_scoped_node() {
             trace!(" - Found {:?}", node);
+            if let ast::AllNode::Root(_) = node.as_all() {
+                cx.read_input(crate::QueryInput::Items);
+            }
             return ScopeLocation {
                 scope: scoped,
                 order,
@@ -1179,7 +1196,11 @@ pub(crate) fn resolve_local_or_error<'a>(
             Ok(def)
         }
         None => {
-            cx.emit(DiagBuilder2::error(format!("`{}` not found", name.value)).span(name.span));
+            cx.emit(
+                DiagBuilder2::error(format!("`{}` not found", name.value))
+                    .code(codes::SV0001)
+                    .span(name.span),
+            );
             Err(())
         }
     }
@@ -1230,6 +1251,7 @@ pub(crate) fn resolve_namespace_or_error<'a>(
         None => {
             cx.emit(
                 DiagBuilder2::error(format!("`{}` not found in {}", name.value, inside))
+                    .code(codes::SV0002)
                     .span(name.span)
                     .add_note(format!("{} was defined here:", inside))
                     .span(inside.human_span()),
@@ -1284,6 +1306,7 @@ pub(crate) fn resolve_hierarchical_or_error<'a>(
         None => {
             cx.emit(
                 DiagBuilder2::error(format!("`{}` not found in {}", name.value, inside))
+                    .code(codes::SV0002)
                     .span(name.span)
                     .add_note(format!("{} was defined here:", inside))
                     .span(inside.human_span()),
@@ -1329,11 +1352,16 @@ pub(crate) fn resolve_imported_scope<'a>(
 /// diagnostics emitted.
 ///
 /// This function helps in triggering naming conflicts at a defined point in the
-/// compilation.
+/// compilation. The scopes of modules nested within `node` are skipped, since
+/// modules are only materialized once they are instantiated from one of the
+/// top-level modules; see `GlobalContext::check_module_names`.
 pub(crate) fn materialize_scope<'a>(cx: &impl Context<'a>, node: &'a dyn ScopedNode<'a>) {
     debug!("Materializing scope {:?}", node);
     let scope = cx.generated_scope(node);
     for &subscope in &scope.subscopes {
+        if let ast::AllNode::Module(_) = subscope.as_all() {
+            continue;
+        }
         materialize_scope(cx, subscope);
     }
 }
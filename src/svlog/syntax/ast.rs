@@ -17,6 +17,10 @@ use std::{
     hash::{Hash, Hasher},
 };
 
+pub use moore_common::assertion::{
+    DirectiveKind, PropBinOp, PropSeqBinOp, PropSeqOp, PropUnaryOp, SeqBinOp,
+};
+
 /// An AST node.
 pub trait AnyNode<'a>: BasicNode<'a> + AnyNodeData + std::fmt::Display + Send + Sync {
     /// Get this node's unique ID.
@@ -98,6 +102,12 @@ pub trait AnyNodeData {
         None
     }
 
+    /// Get the name of the variant of an enum node, e.g. *"BinaryExpr"*, or
+    /// `None` if the node is not an enum.
+    fn get_variant(&self) -> Option<&'static str> {
+        None
+    }
+
     /// Describe this node for diagnostics in indefinite form, e.g. *"entity"*.
     ///
     /// This should not include any node name. Generally, we want to describe
@@ -186,7 +196,7 @@ pub trait ForEachNode<'a> {
 
 impl<'a, T> ForEachNode<'a> for &'_ T
 where
-    T: ForEachNode<'a>,
+    T: ForEachNode<'a> + ?Sized,
 {
     fn for_each_node(&'a self, each: &mut dyn FnMut(&'a dyn AnyNode<'a>)) {
         (*self).for_each_node(each);
@@ -204,6 +214,17 @@ where
     }
 }
 
+impl<'a, T> ForEachNode<'a> for [T]
+where
+    T: ForEachNode<'a>,
+{
+    fn for_each_node(&'a self, each: &mut dyn FnMut(&'a dyn AnyNode<'a>)) {
+        for node in self {
+            node.for_each_node(each);
+        }
+    }
+}
+
 impl<'a, T> ForEachNode<'a> for Vec<T>
 where
     T: ForEachNode<'a>,
@@ -238,6 +259,10 @@ impl<'a> ForEachNode<'a> for Name {}
 impl<'a> ForEachNode<'a> for Identifier {}
 impl<'a> ForEachNode<'a> for Lit {}
 impl<'a> ForEachNode<'a> for Op {}
+impl<'a> ForEachNode<'a> for SeqBinOp {}
+impl<'a> ForEachNode<'a> for PropSeqOp {}
+impl<'a> ForEachNode<'a> for PropSeqBinOp {}
+impl<'a> ForEachNode<'a> for PropBinOp {}
 impl<'a> ForEachNode<'a> for bool {}
 impl<'a> ForEachNode<'a> for usize {}
 
@@ -329,6 +354,10 @@ where
         self.data.get_name()
     }
 
+    fn get_variant(&self) -> Option<&'static str> {
+        self.data.get_variant()
+    }
+
     fn fmt_indefinite(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
         self.data.fmt_indefinite(fmt)
     }
@@ -438,13 +467,24 @@ pub trait AcceptVisitor<'a> {
 
 impl<'a, T> AcceptVisitor<'a> for &'_ T
 where
-    T: AcceptVisitor<'a>,
+    T: AcceptVisitor<'a> + ?Sized,
 {
     fn accept(&'a self, visitor: &mut dyn Visitor<'a>) {
         (*self).accept(visitor);
     }
 }
 
+impl<'a, T> AcceptVisitor<'a> for [T]
+where
+    T: AcceptVisitor<'a>,
+{
+    fn accept(&'a self, visitor: &mut dyn Visitor<'a>) {
+        for c in self {
+            c.accept(visitor);
+        }
+    }
+}
+
 impl<'a, T> AcceptVisitor<'a> for Vec<T>
 where
     T: AcceptVisitor<'a>,
@@ -484,13 +524,24 @@ pub trait WalkVisitor<'a> {
 
 impl<'a, T> WalkVisitor<'a> for &'_ T
 where
-    T: WalkVisitor<'a>,
+    T: WalkVisitor<'a> + ?Sized,
 {
     fn walk(&'a self, visitor: &mut dyn Visitor<'a>) {
         (*self).walk(visitor);
     }
 }
 
+impl<'a, T> WalkVisitor<'a> for [T]
+where
+    T: WalkVisitor<'a>,
+{
+    fn walk(&'a self, visitor: &mut dyn Visitor<'a>) {
+        for c in self {
+            c.walk(visitor);
+        }
+    }
+}
+
 impl<'a, T> WalkVisitor<'a> for Vec<T>
 where
     T: WalkVisitor<'a>,
@@ -551,6 +602,22 @@ impl<'a> WalkVisitor<'a> for Op {
     fn walk(&'a self, visitor: &mut dyn Visitor<'a>) {}
 }
 
+impl<'a> WalkVisitor<'a> for SeqBinOp {
+    fn walk(&'a self, visitor: &mut dyn Visitor<'a>) {}
+}
+
+impl<'a> WalkVisitor<'a> for PropSeqOp {
+    fn walk(&'a self, visitor: &mut dyn Visitor<'a>) {}
+}
+
+impl<'a> WalkVisitor<'a> for PropSeqBinOp {
+    fn walk(&'a self, visitor: &mut dyn Visitor<'a>) {}
+}
+
+impl<'a> WalkVisitor<'a> for PropBinOp {
+    fn walk(&'a self, visitor: &mut dyn Visitor<'a>) {}
+}
+
 impl<'a> WalkVisitor<'a> for bool {
     fn walk(&'a self, visitor: &mut dyn Visitor<'a>) {}
 }
@@ -614,7 +681,7 @@ pub struct Root<'a> {
 #[derive(Debug)]
 pub struct SourceFile<'a> {
     pub timeunits: Timeunit,
-    pub items: Vec<Item<'a>>,
+    pub items: &'a [Item<'a>],
 }
 
 /// An item that may appear in a hierarchical scope.
@@ -647,7 +714,7 @@ pub enum Item<'a> {
     SubroutineDecl(#[forward] SubroutineDecl<'a>),
     ContAssign(#[forward] ContAssign<'a>),
     GenvarDecl(Vec<GenvarDecl<'a>>),
-    GenerateRegion(Span, Vec<Item<'a>>),
+    GenerateRegion(Span, &'a [Item<'a>]),
     GenerateFor(#[forward] GenerateFor<'a>),
     GenerateIf(#[forward] GenerateIf<'a>),
     GenerateCase(#[forward] GenerateCase<'a>),
@@ -669,7 +736,7 @@ pub struct Module<'a> {
     pub imports: Vec<ImportDecl<'a>>,
     pub params: Vec<ParamDecl<'a>>,
     pub ports: Vec<Port<'a>>,
-    pub items: Vec<Item<'a>>,
+    pub items: &'a [Item<'a>],
 }
 
 /// An interface.
@@ -683,7 +750,7 @@ pub struct Interface<'a> {
     pub name: Spanned<Name>,
     pub params: Vec<ParamDecl<'a>>,
     pub ports: Vec<Port<'a>>,
-    pub items: Vec<Item<'a>>,
+    pub items: &'a [Item<'a>],
 }
 
 /// A package.
@@ -696,7 +763,7 @@ pub struct Package<'a> {
     #[name]
     pub name: Spanned<Name>,
     pub timeunits: Timeunit,
-    pub items: Vec<Item<'a>>,
+    pub items: &'a [Item<'a>],
 }
 
 /// Lifetime specifier for variables, tasks, and functions. Defaults to static.
@@ -906,7 +973,7 @@ impl std::fmt::Display for StructKind {
 pub struct StructMember<'a> {
     pub rand_qualifier: Option<RandomQualifier>,
     pub ty: Box<Type<'a>>,
-    pub names: Vec<VarDeclName<'a>>,
+    pub names: &'a [VarDeclName<'a>],
 }
 
 /// A module or interface port as declared in the port list.
@@ -968,7 +1035,7 @@ pub struct PortDecl<'a> {
     pub dir: PortDir,
     pub kind: Option<PortKind>,
     pub ty: Type<'a>,
-    pub names: Vec<VarDeclName<'a>>,
+    pub names: &'a [VarDeclName<'a>],
 }
 
 #[moore_derive::visit]
@@ -1077,8 +1144,8 @@ pub struct Stmt<'a> {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum StmtKind<'a> {
     NullStmt,
-    SequentialBlock(Vec<Stmt<'a>>),
-    ParallelBlock(Vec<Stmt<'a>>, JoinKind),
+    SequentialBlock(&'a [Stmt<'a>]),
+    ParallelBlock(&'a [Stmt<'a>], JoinKind),
     IfStmt {
         up: Option<UniquePriority>,
         cond: Expr<'a>,
@@ -1226,6 +1293,27 @@ pub enum AssignOp {
     ArithShR,
 }
 
+impl std::fmt::Display for AssignOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let op = match self {
+            AssignOp::Identity => "=",
+            AssignOp::Add => "+=",
+            AssignOp::Sub => "-=",
+            AssignOp::Mul => "*=",
+            AssignOp::Div => "/=",
+            AssignOp::Mod => "%=",
+            AssignOp::BitAnd => "&=",
+            AssignOp::BitOr => "|=",
+            AssignOp::BitXor => "^=",
+            AssignOp::LogicShL => "<<=",
+            AssignOp::LogicShR => ">>=",
+            AssignOp::ArithShL => "<<<=",
+            AssignOp::ArithShR => ">>>=",
+        };
+        write!(f, "{}", op)
+    }
+}
+
 /// A variable declaration.
 ///
 /// For example `logic x, y, z`.
@@ -1237,7 +1325,7 @@ pub struct VarDecl<'a> {
     pub var: bool,
     pub lifetime: Option<Lifetime>,
     pub ty: Type<'a>,
-    pub names: Vec<VarDeclName<'a>>,
+    pub names: &'a [VarDeclName<'a>],
 }
 
 /// A variable or net declaration name.
@@ -1314,9 +1402,9 @@ pub enum Expr<'a> {
         lhs: Box<Expr<'a>>,
         rhs: Box<Expr<'a>>,
     },
-    CallExpr(Box<Expr<'a>>, Vec<CallArg<'a>>),
+    CallExpr(Box<Expr<'a>>, &'a [CallArg<'a>]),
     TypeExpr(Box<Type<'a>>), // TODO: Check if this is still needed, otherwise remove
-    ConstructorCallExpr(Vec<CallArg<'a>>),
+    ConstructorCallExpr(&'a [CallArg<'a>]),
     ClassNewExpr(Option<Box<Expr<'a>>>),
     ArrayNewExpr(Box<Expr<'a>>, Option<Box<Expr<'a>>>),
     EmptyQueueExpr,
@@ -1326,7 +1414,7 @@ pub enum Expr<'a> {
     },
     ConcatExpr {
         repeat: Option<Box<Expr<'a>>>,
-        exprs: Vec<Expr<'a>>,
+        exprs: &'a [Expr<'a>],
     },
     MinTypMaxExpr {
         min: Box<Expr<'a>>,
@@ -1476,6 +1564,7 @@ pub struct Identifier {
     pub name: Name,
 }
 
+#[moore_derive::arena]
 #[moore_derive::visit]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CallArg<'a> {
@@ -1711,7 +1800,7 @@ pub struct SubroutinePortDecl<'a> {
     pub dir: SubroutinePortDir,
     pub var: bool,
     pub ty: Type<'a>,
-    pub names: Vec<VarDeclName<'a>>,
+    pub names: &'a [VarDeclName<'a>],
 }
 
 #[moore_derive::visit]
@@ -1724,6 +1813,18 @@ pub enum SubroutinePortDir {
     ConstRef,
 }
 
+impl std::fmt::Display for SubroutinePortDir {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SubroutinePortDir::Input => write!(f, "input"),
+            SubroutinePortDir::Output => write!(f, "output"),
+            SubroutinePortDir::Inout => write!(f, "inout"),
+            SubroutinePortDir::Ref => write!(f, "ref"),
+            SubroutinePortDir::ConstRef => write!(f, "const ref"),
+        }
+    }
+}
+
 /// A net declaration.
 ///
 /// For example `wire x, y, z`.
@@ -1736,7 +1837,7 @@ pub struct NetDecl<'a> {
     pub kind: NetKind,
     pub ty: Type<'a>,
     pub delay: Option<DelayControl<'a>>,
-    pub names: Vec<VarDeclName<'a>>,
+    pub names: &'a [VarDeclName<'a>],
 }
 
 #[moore_derive::visit]
@@ -1854,6 +1955,21 @@ pub enum ConcurrentAssertion<'a> {
     RestrictProperty(PropSpec),
 }
 
+impl<'a> ConcurrentAssertion<'a> {
+    /// Get the kind of verification directive this assertion represents.
+    pub fn directive_kind(&self) -> DirectiveKind {
+        match self {
+            ConcurrentAssertion::AssertProperty(..) => DirectiveKind::Assert,
+            ConcurrentAssertion::AssumeProperty(..) => DirectiveKind::Assume,
+            ConcurrentAssertion::CoverProperty(..) | ConcurrentAssertion::CoverSequence => {
+                DirectiveKind::Cover
+            }
+            ConcurrentAssertion::ExpectProperty(..) => DirectiveKind::Expect,
+            ConcurrentAssertion::RestrictProperty(..) => DirectiveKind::Restrict,
+        }
+    }
+}
+
 #[moore_derive::visit]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AssertionActionBlock<'a> {
@@ -1888,15 +2004,6 @@ pub enum SeqRep<'a> {
     Goto(Expr<'a>),      // [-> expr]
 }
 
-#[moore_derive::visit]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum SeqBinOp {
-    Or,
-    And,
-    Intersect,
-    Within,
-}
-
 #[moore_derive::visit]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PropSpec;
@@ -1918,40 +2025,6 @@ pub enum PropExprData<'a> {
     Clocked(EventExpr<'a>, Box<PropExpr<'a>>),
 }
 
-#[moore_derive::visit]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum PropSeqOp {
-    None,
-    Weak,
-    Strong,
-}
-
-#[moore_derive::visit]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum PropSeqBinOp {
-    ImplOverlap,
-    ImplNonoverlap,
-    FollowOverlap,
-    FollowNonoverlap,
-}
-
-#[moore_derive::visit]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum PropBinOp {
-    Or,
-    And,
-    Until,
-    SUntil,
-    UntilWith,
-    SUntilWith,
-    Impl,
-    Iff,
-    SeqImplOl,
-    SeqImplNol,
-    SeqFollowOl,
-    SeqFollowNol,
-}
-
 /// An instantiation of a module.
 ///
 /// For example `foo u0(), u1();`.
@@ -1966,7 +2039,7 @@ pub struct Inst<'a> {
     /// The parameters in the module to be assigned.
     pub params: Vec<ParamAssignment<'a>>,
     /// The names and ports of the module instantiations.
-    pub names: Vec<InstName<'a>>,
+    pub names: &'a [InstName<'a>],
 }
 
 /// A single module instance.
@@ -1982,7 +2055,7 @@ pub struct InstName<'a> {
     /// The unpacked dimensions.
     pub dims: Vec<TypeDim<'a>>,
     /// The port connections.
-    pub conns: Vec<PortConn<'a>>,
+    pub conns: &'a [PortConn<'a>],
 }
 
 impl<'a> InstName<'a> {
@@ -2157,7 +2230,7 @@ pub struct GenerateCase {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GenerateBlock<'a> {
     pub label: Option<Spanned<Name>>,
-    pub items: Vec<Item<'a>>,
+    pub items: &'a [Item<'a>],
 }
 
 #[moore_derive::visit]
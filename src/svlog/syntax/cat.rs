@@ -142,9 +142,10 @@ impl<'a> Iterator for Cat<'a> {
 }
 
 /// Check whether the given character is considered a whitespace in
-/// SystemVerilog.
+/// SystemVerilog. A byte order mark is treated as whitespace, such that files
+/// saved with one by an editor lex as usual.
 fn is_whitespace(c: char) -> bool {
-    c == ' ' || c == '\t' || c == '\r' || c == (0xA0 as char)
+    c == ' ' || c == '\t' || c == '\r' || c == (0xA0 as char) || c == BYTE_ORDER_MARK
 }
 
 /// Check whether the given character is a digit.
@@ -207,4 +208,12 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn byte_order_mark() {
+        assert_eq!(
+            lex("\u{FEFF}module"),
+            vec![CatToken(Whitespace, 0, 3), CatToken(Text, 3, 9)]
+        );
+    }
 }
@@ -0,0 +1,518 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! A formatter for SystemVerilog source text.
+//!
+//! The formatter works on the tokens of the categorizing lexer, which retains
+//! all whitespace and comments. It indents every line according to the nesting
+//! of blocks and brackets, removes trailing whitespace and runs of blank lines,
+//! and breaks lines that are longer than the line width after a comma. The
+//! spacing within lines, comments, and the bodies of macro definitions are
+//! kept as they are. Since the input is not parsed, files with syntax errors
+//! are formatted as well.
+
+use crate::cat::*;
+
+/// The options that control the formatter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// The number of spaces per level of indentation.
+    pub indent: usize,
+    /// The number of columns after which lines are broken, if possible.
+    pub line_width: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> FormatOptions {
+        FormatOptions {
+            indent: 2,
+            line_width: 100,
+        }
+    }
+}
+
+/// Format SystemVerilog source text.
+pub fn format(input: &str, options: &FormatOptions) -> String {
+    let tokens = Cat::new(Box::new(input.char_indices()))
+        .map(|CatToken(kind, begin, end)| Token {
+            kind,
+            text: &input[begin..end],
+        })
+        .collect();
+    let mut formatter = Formatter {
+        options,
+        output: String::new(),
+        blocks: 0,
+        brackets: vec![],
+        pending: 0,
+        continued: false,
+        statement: vec![],
+        blank: false,
+        comments: vec![],
+    };
+    for line in split_lines(tokens) {
+        formatter.line(&line);
+    }
+    formatter.flush_comments(formatter.blocks);
+    formatter.output
+}
+
+/// The keywords which open a block right away.
+const OPENERS: &[&str] = &[
+    "begin", "fork", "case", "casex", "casez", "randcase", "generate", "specify", "table",
+];
+
+/// The keywords which open a block once their header, which ends at the next
+/// semicolon, is complete.
+const HEADER_OPENERS: &[&str] = &[
+    "module",
+    "macromodule",
+    "interface",
+    "package",
+    "program",
+    "checker",
+    "primitive",
+    "config",
+    "class",
+    "function",
+    "task",
+    "covergroup",
+    "clocking",
+    "property",
+    "sequence",
+];
+
+/// The keywords which close a block.
+const CLOSERS: &[&str] = &[
+    "end",
+    "join",
+    "join_any",
+    "join_none",
+    "endcase",
+    "endgenerate",
+    "endspecify",
+    "endtable",
+    "endmodule",
+    "endinterface",
+    "endpackage",
+    "endprogram",
+    "endchecker",
+    "endprimitive",
+    "endconfig",
+    "endclass",
+    "endfunction",
+    "endtask",
+    "endgroup",
+    "endclocking",
+    "endproperty",
+    "endsequence",
+];
+
+/// A token together with the text it covers.
+#[derive(Clone, Copy, Debug)]
+struct Token<'a> {
+    kind: CatTokenKind,
+    text: &'a str,
+}
+
+/// A token that is relevant to the structure of the source text.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Sig<'a> {
+    /// A keyword or identifier.
+    Word(&'a str),
+    /// A macro, system task, or escaped identifier, or a string literal.
+    Other,
+    /// A symbol.
+    Symbol(char),
+}
+
+impl Sig<'_> {
+    fn is_closer(self) -> bool {
+        match self {
+            Sig::Word(word) => CLOSERS.contains(&word),
+            Sig::Symbol(c) => c == ')' || c == ']' || c == '}',
+            Sig::Other => false,
+        }
+    }
+}
+
+/// Split the tokens into lines. Lines continued with a backslash, as in macro
+/// definitions and string literals, are kept together.
+fn split_lines(tokens: Vec<Token>) -> Vec<Vec<Token>> {
+    let mut lines = vec![vec![]];
+    let mut in_string = false;
+    let mut escaped = false;
+    for token in tokens {
+        let line = lines.last_mut().unwrap();
+        if token.kind == Newline {
+            let continued = line
+                .last()
+                .map_or(false, |t: &Token| t.kind == Symbol('\\'));
+            if !continued {
+                in_string = false;
+                escaped = false;
+                lines.push(vec![]);
+                continue;
+            }
+        }
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if token.kind == Symbol('\\') {
+                escaped = true;
+            } else if token.kind == Symbol('"') {
+                in_string = false;
+            }
+        } else if token.kind == Symbol('"') {
+            in_string = true;
+        }
+        line.push(token);
+    }
+    lines
+}
+
+/// Determine the tokens of a line that are relevant to its structure, together
+/// with their index into the line.
+fn significant<'a>(tokens: &[Token<'a>]) -> Vec<(usize, Sig<'a>)> {
+    let mut sigs = vec![];
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, token) in tokens.iter().enumerate() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if token.kind == Symbol('\\') {
+                escaped = true;
+            } else if token.kind == Symbol('"') {
+                in_string = false;
+                sigs.push((index, Sig::Other));
+            }
+            continue;
+        }
+        let prefixed = match index.checked_sub(1).map(|i| tokens[i].kind) {
+            Some(Symbol(c)) => c == '`' || c == '$' || c == '\\' || c == '.',
+            _ => false,
+        };
+        match token.kind {
+            Symbol('"') => in_string = true,
+            Symbol(c) => sigs.push((index, Sig::Symbol(c))),
+            Text | Digits if prefixed => sigs.push((index, Sig::Other)),
+            Text | Digits => sigs.push((index, Sig::Word(token.text))),
+            Newline | Whitespace | Comment | Eof => (),
+        }
+    }
+    sigs
+}
+
+struct Formatter<'o> {
+    options: &'o FormatOptions,
+    output: String,
+    /// The number of blocks currently open.
+    blocks: usize,
+    /// For each bracket currently open, the level of indentation of the line
+    /// which opened it.
+    brackets: Vec<usize>,
+    /// The number of blocks which open once the current header is complete.
+    pending: usize,
+    /// Whether the previous line ended in the middle of a statement.
+    continued: bool,
+    /// The words of the current statement so far.
+    statement: Vec<String>,
+    /// Whether a blank line precedes the current line.
+    blank: bool,
+    /// The lines which only hold comments and precede the current line, with
+    /// `None` for blank lines between them. They are indented like the next
+    /// line of code, which they usually describe.
+    comments: Vec<Option<String>>,
+}
+
+impl Formatter<'_> {
+    fn line(&mut self, tokens: &[Token]) {
+        // Trim the whitespace around the line, and collapse blank lines.
+        let tokens = match tokens.iter().position(|t| t.kind != Whitespace) {
+            Some(start) => {
+                let end = tokens.iter().rposition(|t| t.kind != Whitespace).unwrap();
+                &tokens[start..=end]
+            }
+            None => {
+                self.blank = !self.output.is_empty();
+                return;
+            }
+        };
+        if self.blank {
+            if self.comments.is_empty() {
+                self.output.push('\n');
+            } else {
+                self.comments.push(None);
+            }
+            self.blank = false;
+        }
+        let sigs = significant(tokens);
+        if sigs.is_empty() {
+            self.comments
+                .push(Some(tokens.iter().map(|t| t.text).collect()));
+            return;
+        }
+
+        // Directives are indented like the surrounding code, but do not
+        // affect the structure.
+        if let Some((0, Sig::Symbol('`'))) = sigs.first() {
+            let level = match self.brackets.last() {
+                Some(&level) => level + 1,
+                None => self.blocks,
+            };
+            self.flush_comments(level);
+            self.emit(tokens, level, false);
+            return;
+        }
+
+        // Lines within brackets are indented one level more than the line
+        // which opened the innermost bracket. Lines which start by closing
+        // blocks or brackets are indented like the line which opened them.
+        // Lines which continue a statement are indented one more level.
+        let mut brackets = self.brackets.clone();
+        let mut blocks = self.blocks;
+        let mut closed = None;
+        let mut closers = 0;
+        for &(_, sig) in sigs.iter().take_while(|(_, sig)| sig.is_closer()) {
+            closers += 1;
+            match sig {
+                Sig::Symbol(_) => closed = brackets.pop().or(closed),
+                _ => blocks = blocks.saturating_sub(1),
+            }
+        }
+        let level = match (brackets.last(), closed) {
+            (Some(&level), _) => level + 1,
+            (None, Some(level)) => level,
+            (None, None) => {
+                let continued = self.continued
+                    && closers == 0
+                    && sigs.first().map(|&(_, sig)| sig) != Some(Sig::Word("begin"));
+                blocks + continued as usize
+            }
+        };
+        self.flush_comments(level);
+        self.emit(tokens, level, true);
+
+        // Track the blocks and brackets opened and closed by the line.
+        let mut block = false;
+        for (index, &(_, sig)) in sigs.iter().enumerate() {
+            match sig {
+                Sig::Symbol('(') | Sig::Symbol('[') | Sig::Symbol('{') => {
+                    self.brackets.push(level);
+                }
+                Sig::Symbol(')') | Sig::Symbol(']') | Sig::Symbol('}') => {
+                    self.brackets.pop();
+                }
+                Sig::Symbol(';') => {
+                    self.statement.clear();
+                    if self.brackets.is_empty() {
+                        self.blocks += self.pending;
+                        self.pending = 0;
+                    }
+                }
+                Sig::Word(word) if CLOSERS.contains(&word) => {
+                    self.blocks = self.blocks.saturating_sub(1);
+                    self.statement.clear();
+                    block = true;
+                }
+                Sig::Word(word) if OPENERS.contains(&word) => {
+                    let previous = index.checked_sub(1).map(|i| sigs[i].1);
+                    let prefixed = previous == Some(Sig::Word("wait"))
+                        || previous == Some(Sig::Word("disable"));
+                    if !prefixed {
+                        self.blocks += 1;
+                        self.statement.clear();
+                        block = true;
+                    }
+                }
+                Sig::Word(word) if HEADER_OPENERS.contains(&word) => {
+                    if self.brackets.is_empty() && !self.is_declaration(word) {
+                        self.pending += 1;
+                        block = true;
+                    }
+                    self.statement.push(word.to_string());
+                }
+                Sig::Word(word) => self.statement.push(word.to_string()),
+                _ => (),
+            }
+        }
+
+        // Determine whether the next line continues the statement.
+        if let Some(&(index, last)) = sigs.last() {
+            let attribute =
+                last == Sig::Symbol(')') && index > 0 && tokens[index - 1].kind == Symbol('*');
+            self.continued = self.brackets.is_empty()
+                && !block
+                && !attribute
+                && self.pending == 0
+                && last != Sig::Symbol(';');
+        }
+    }
+
+    /// Check whether a keyword which usually opens a block is used in a
+    /// declaration or statement without a body instead, such as in `extern
+    /// function` or `assert property`.
+    fn is_declaration(&self, keyword: &str) -> bool {
+        let prefixes: &[&str] = match keyword {
+            "function" | "task" => &["extern", "import", "export", "pure"],
+            "class" => &["typedef"],
+            "interface" => &["virtual", "typedef"],
+            "property" | "sequence" => &["assert", "assume", "cover", "restrict", "expect"],
+            _ => &["extern"],
+        };
+        self.statement
+            .iter()
+            .any(|word| prefixes.contains(&word.as_str()))
+    }
+
+    /// Write a line at a level of indentation, breaking it after commas if it
+    /// is too long.
+    fn emit(&mut self, tokens: &[Token], level: usize, wrap: bool) {
+        let indent = level * self.options.indent;
+        let text: String = tokens.iter().map(|t| t.text).collect();
+        if !wrap || text.contains('\n') || indent + width(&text) <= self.options.line_width {
+            self.push_line(indent, &text);
+            return;
+        }
+
+        // Determine where the line can be broken. The parts after the first
+        // are indented one more level, as they would be when formatting the
+        // broken line again.
+        let breaks: Vec<_> = significant(tokens)
+            .into_iter()
+            .filter(|&(_, sig)| sig == Sig::Symbol(','))
+            .map(|(index, _)| index)
+            .collect();
+
+        // Break the line greedily, filling each part as far as possible.
+        let mut start = 0;
+        let mut part_indent = indent;
+        loop {
+            let rest: String = tokens[start..].iter().map(|t| t.text).collect();
+            let candidates: Vec<_> = breaks
+                .iter()
+                .cloned()
+                .filter(|&index| index >= start && has_code(&tokens[index + 1..]))
+                .collect();
+            if part_indent + width(rest.trim_start()) <= self.options.line_width
+                || candidates.is_empty()
+            {
+                self.push_line(part_indent, rest.trim_start());
+                return;
+            }
+            let fitting = candidates.iter().rev().find(|&&index| {
+                let part: String = tokens[start..=index].iter().map(|t| t.text).collect();
+                part_indent + width(part.trim_start()) <= self.options.line_width
+            });
+            let index = *fitting.unwrap_or(&candidates[0]);
+            let part: String = tokens[start..=index].iter().map(|t| t.text).collect();
+            self.push_line(part_indent, part.trim());
+            start = index + 1;
+            part_indent = indent + self.options.indent;
+        }
+    }
+
+    fn flush_comments(&mut self, level: usize) {
+        for comment in std::mem::replace(&mut self.comments, vec![]) {
+            match comment {
+                Some(text) => self.push_line(level * self.options.indent, &text),
+                None => self.output.push('\n'),
+            }
+        }
+    }
+
+    fn push_line(&mut self, indent: usize, text: &str) {
+        self.output.extend(std::iter::repeat(' ').take(indent));
+        self.output.push_str(text);
+        self.output.push('\n');
+    }
+}
+
+/// Check whether any tokens other than whitespace and comments follow.
+fn has_code(tokens: &[Token]) -> bool {
+    tokens.iter().any(|t| match t.kind {
+        Whitespace | Comment | Newline => false,
+        _ => true,
+    })
+}
+
+fn width(text: &str) -> usize {
+    text.chars().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fmt(input: &str) -> String {
+        format(input, &Default::default())
+    }
+
+    #[test]
+    fn indentation() {
+        assert_eq!(
+            fmt("module foo (\ninput a,\n    output b\n);\n\
+                 // Some logic.\n\
+                 always_comb begin\n\
+                 if (a)\n\
+                 b = 1;   \n\
+                 else begin\n\
+                 b = 0; /* none */\n\
+                 end\n\
+                 end\n\n\n\
+                 endmodule\n"),
+            "module foo (\n  input a,\n  output b\n);\n  \
+             // Some logic.\n  \
+             always_comb begin\n    \
+             if (a)\n      \
+             b = 1;\n    \
+             else begin\n      \
+             b = 0; /* none */\n    \
+             end\n  \
+             end\n\n\
+             endmodule\n"
+        );
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(
+            fmt("if (a) begin\nb = 1;\n// otherwise\n\n/* zero */\nend else begin\nb = 0;\nend\n// done\n"),
+            "if (a) begin\n  b = 1;\n// otherwise\n\n/* zero */\nend else begin\n  b = 0;\nend\n// done\n"
+        );
+    }
+
+    #[test]
+    fn declarations() {
+        assert_eq!(
+            fmt("package p;\nimport \"DPI-C\" function void f();\n\
+                 typedef class c;\nfunction void g;\nwait fork;\nendfunction\nendpackage\n"),
+            "package p;\n  import \"DPI-C\" function void f();\n  typedef class c;\n  \
+             function void g;\n    wait fork;\n  endfunction\nendpackage\n"
+        );
+    }
+
+    #[test]
+    fn macros_and_strings() {
+        assert_eq!(
+            fmt(
+                "module m;\n`define FOO(x) \\\n    begin x; \\\n    end\ninitial \
+                 $display(\"begin ( \\\"\");\nendmodule\n"
+            ),
+            "module m;\n  `define FOO(x) \\\n    begin x; \\\n    end\n  initial \
+             $display(\"begin ( \\\"\");\nendmodule\n"
+        );
+    }
+
+    #[test]
+    fn line_width() {
+        let options = FormatOptions {
+            indent: 4,
+            line_width: 30,
+        };
+        assert_eq!(
+            format("foo bar (.a(x), .b(y), .c(z), .d(w));\n", &options),
+            "foo bar (.a(x), .b(y), .c(z),\n    .d(w));\n"
+        );
+    }
+}
@@ -9,6 +9,7 @@ pub use crate::token::*;
 use moore_common::errors::*;
 use moore_common::name::*;
 use moore_common::source::*;
+use moore_common::timing::Stopwatch;
 
 type CatTokenAndSpan = (CatTokenKind, Span);
 pub type TokenAndSpan = (Token, Span);
@@ -17,6 +18,7 @@ pub type TokenAndSpan = (Token, Span);
 pub struct Lexer<'a> {
     input: Preprocessor<'a>,
     peek: [CatTokenAndSpan; 4],
+    stopwatch: Option<Stopwatch>,
 }
 
 impl<'a> Lexer<'a> {
@@ -24,6 +26,15 @@ impl<'a> Lexer<'a> {
         Lexer {
             input: input,
             peek: [(CatTokenKind::Eof, INVALID_SPAN); 4],
+            stopwatch: None,
+        }
+    }
+
+    /// Accumulate the time spent preprocessing and lexing in a stopwatch.
+    pub fn with_stopwatch(self, stopwatch: Stopwatch) -> Self {
+        Lexer {
+            stopwatch: Some(stopwatch),
+            ..self
         }
     }
 
@@ -41,6 +52,13 @@ impl<'a> Lexer<'a> {
     }
 
     pub fn next_token(&mut self) -> DiagResult2<TokenAndSpan> {
+        match self.stopwatch.clone() {
+            Some(stopwatch) => stopwatch.measure(|| self.lex_token()),
+            None => self.lex_token(),
+        }
+    }
+
+    fn lex_token(&mut self) -> DiagResult2<TokenAndSpan> {
         // Upon the first invocation the peek buffer is still empty. In that
         // case we need to load the first batch of tokens.
         if self.peek[0].0 == CatTokenKind::Eof {
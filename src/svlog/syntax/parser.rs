@@ -10,7 +10,15 @@ use crate::ast;
 use crate::ast::*;
 use crate::lexer::{Lexer, TokenAndSpan};
 use crate::token::*;
-use moore_common::{arenas::Alloc, errors::*, name::*, source::*, util::HasSpan};
+use moore_common::{
+    arenas::{Alloc, AllocSlice},
+    codes,
+    errors::*,
+    lints,
+    name::*,
+    source::*,
+    util::HasSpan,
+};
 use std;
 use std::collections::VecDeque;
 
@@ -237,6 +245,7 @@ struct Parser<'a, 'n> {
     severity: Severity,
     consumed: usize,
     arena: &'n ast::Arena<'n>,
+    emitter: &'a dyn DiagEmitter,
 }
 
 impl<'a, 'n> AbstractParser<'n> for Parser<'a, 'n> {
@@ -279,8 +288,7 @@ impl<'a, 'n> AbstractParser<'n> for Parser<'a, 'n> {
     }
 
     fn add_diag(&mut self, diag: DiagBuilder2) {
-        eprintln!("");
-        eprintln!("{}", diag);
+        self.emitter.emit(diag.clone());
 
         // Emit a backtrace for this diagnostic.
         if diag.get_severity() >= Severity::Warning {
@@ -304,7 +312,7 @@ impl<'a, 'n> AbstractParser<'n> for Parser<'a, 'n> {
 }
 
 impl<'a, 'n> Parser<'a, 'n> {
-    fn new(input: Lexer<'a>, arena: &'n ast::Arena<'n>) -> Self {
+    fn new(input: Lexer<'a>, arena: &'n ast::Arena<'n>, emitter: &'a dyn DiagEmitter) -> Self {
         Parser {
             input: input,
             queue: VecDeque::new(),
@@ -313,6 +321,7 @@ impl<'a, 'n> Parser<'a, 'n> {
             severity: Severity::Note,
             consumed: 0,
             arena,
+            emitter,
         }
     }
 
@@ -407,7 +416,12 @@ where
         } else if p.try_eat(Comma) {
             if term.matches(p) {
                 let q = p.last_span();
-                p.add_diag(DiagBuilder2::warning("superfluous trailing comma").span(q));
+                p.add_diag(
+                    DiagBuilder2::lint(lints::TRAILING_COMMA, "superfluous trailing comma")
+                        .code(codes::SV0014)
+                        .span(q)
+                        .span_suggestion(q, "Remove the comma", ""),
+                );
                 break;
             }
         } else {
@@ -622,8 +636,15 @@ impl<
     }
 }
 
-pub fn parse<'n>(input: Lexer, arena: &'n ast::Arena<'n>) -> Result<ast::SourceFile<'n>, ()> {
-    let mut p = Parser::new(input, arena);
+/// Parse a source file.
+///
+/// Diagnostics are emitted through `emitter` as they are encountered.
+pub fn parse<'a, 'n>(
+    input: Lexer<'a>,
+    arena: &'n ast::Arena<'n>,
+    emitter: &'a dyn DiagEmitter,
+) -> Result<ast::SourceFile<'n>, ()> {
+    let mut p = Parser::new(input, arena, emitter);
     let root = parse_source_text(&mut p);
     if p.is_error() {
         Err(())
@@ -639,7 +660,7 @@ fn parse_source_text<'n>(p: &mut dyn AbstractParser<'n>) -> ast::SourceFile<'n>
             unit: None,
             prec: None,
         },
-        items: Vec::new(),
+        items: &[],
     };
 
     // Parse the optional timeunits declaration.
@@ -649,12 +670,14 @@ fn parse_source_text<'n>(p: &mut dyn AbstractParser<'n>) -> ast::SourceFile<'n>
     }
 
     // Parse the descriptions in the source text.
+    let mut items = Vec::new();
     while !p.is_fatal() && p.peek(0).0 != Eof {
         match parse_item(p) {
-            Ok(item) => root.items.push(item),
+            Ok(item) => items.push(item),
             Err(()) => (), // parse_item handles recovery, so no need to do anything here
         }
     }
+    root.items = p.arena().alloc_slice(items);
 
     span.expand(p.last_span());
     ast::SourceFile::new(span, root)
@@ -771,7 +794,7 @@ fn parse_interface_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<In
                 name: Spanned::new(name, name_sp),
                 params: param_ports,
                 ports: ports,
-                items: items,
+                items: p.arena().alloc_slice(items),
             },
         ))
     });
@@ -937,7 +960,7 @@ fn parse_module_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Modul
                 imports,
                 params,
                 ports,
-                items,
+                items: p.arena().alloc_slice(items),
             },
         ))
     });
@@ -988,7 +1011,7 @@ fn parse_package_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Pack
                 lifetime: lifetime,
                 name: Spanned::new(name, name_span),
                 timeunits: timeunits,
-                items: items,
+                items: p.arena().alloc_slice(items),
             },
         ))
     });
@@ -1100,7 +1123,7 @@ fn parse_item_data<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ItemDat
             let items = repeat_until(p, Keyword(Kw::Endgenerate), parse_generate_item)?;
             p.require_reported(Keyword(Kw::Endgenerate))?;
             span.expand(p.last_span());
-            return Ok(ItemData::GenerateRegion(span, items));
+            return Ok(ItemData::GenerateRegion(span, p.arena().alloc_slice(items)));
         }
         Keyword(Kw::For) => return parse_generate_for(p).map(|x| ItemData::GenerateFor(x)),
         Keyword(Kw::If) => return parse_generate_if(p).map(|x| ItemData::GenerateIf(x)),
@@ -1173,7 +1196,9 @@ fn parse_elab_system_task<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<
     };
     p.recover_balanced(&[Semicolon], true);
     span.expand(p.last_span());
-    p.add_diag(DiagBuilder2::warning("unsupported elaboration system task").span(span));
+    p.add_diag(
+        DiagBuilder2::lint(lints::UNSUPPORTED, "unsupported elaboration system task").span(span),
+    );
     Ok(())
 }
 
@@ -1212,7 +1237,12 @@ fn parse_localparam_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<(
                 // gracefully.
                 if p.peek(0).0 == Semicolon {
                     // TODO: This should be an error in pedantic mode.
-                    p.add_diag(DiagBuilder2::warning("superfluous trailing comma").span(sp));
+                    p.add_diag(
+                        DiagBuilder2::lint(lints::TRAILING_COMMA, "superfluous trailing comma")
+                            .code(codes::SV0014)
+                            .span(sp)
+                            .span_suggestion(sp, "Remove the comma", ""),
+                    );
                     break;
                 }
             }
@@ -1824,7 +1854,7 @@ fn parse_struct_member<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Str
         ast::StructMemberData {
             rand_qualifier,
             ty: Box::new(ty),
-            names,
+            names: p.arena().alloc_slice(names),
         },
     ))
 }
@@ -1996,7 +2026,10 @@ fn parse_expr_prec<'n>(
         } else {
             if let Some(args) = try_flanked(p, Paren, parse_call_args)? {
                 span.expand(p.last_span());
-                return Ok(Expr::new(span, ConstructorCallExpr(args)));
+                return Ok(Expr::new(
+                    span,
+                    ConstructorCallExpr(p.arena().alloc_slice(args)),
+                ));
             } else {
                 // Parse the optional expression.
                 let mut bp = BranchParser::new(p);
@@ -2107,7 +2140,7 @@ fn parse_expr_suffix<'n>(
             let args = flanked(p, Paren, parse_call_args)?;
             let expr = Expr::new(
                 Span::union(prefix.span, p.last_span()),
-                CallExpr(Box::new(prefix), args),
+                CallExpr(Box::new(prefix), p.arena().alloc_slice(args)),
             );
             return parse_expr_suffix(p, expr, precedence);
         }
@@ -2552,7 +2585,7 @@ fn parse_concat_expr<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ExprD
         p.require_reported(CloseDelim(Brace))?;
         return Ok(ConcatExpr {
             repeat: Some(Box::new(first_expr)),
-            exprs: exprs,
+            exprs: p.arena().alloc_slice(exprs),
         });
     }
 
@@ -2563,7 +2596,11 @@ fn parse_concat_expr<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ExprD
     while p.try_eat(Comma) {
         if p.peek(0).0 == CloseDelim(Brace) {
             let q = p.peek(0).1;
-            p.add_diag(DiagBuilder2::warning("superfluous trailing comma").span(q));
+            p.add_diag(
+                DiagBuilder2::lint(lints::TRAILING_COMMA, "superfluous trailing comma")
+                    .code(codes::SV0014)
+                    .span(q),
+            );
             break;
         }
         exprs.push(parse_expr_prec(p, Precedence::Min)?);
@@ -2571,7 +2608,7 @@ fn parse_concat_expr<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ExprD
 
     Ok(ConcatExpr {
         repeat: None,
-        exprs: exprs,
+        exprs: p.arena().alloc_slice(exprs),
     })
 }
 
@@ -2584,7 +2621,12 @@ fn parse_expr_list<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Vec<Exp
             (Comma, sp) => {
                 p.bump();
                 if p.peek(0).0 == CloseDelim(Brace) {
-                    p.add_diag(DiagBuilder2::warning("superfluous trailing comma").span(sp));
+                    p.add_diag(
+                        DiagBuilder2::lint(lints::TRAILING_COMMA, "superfluous trailing comma")
+                            .code(codes::SV0014)
+                            .span(sp)
+                            .span_suggestion(sp, "Remove the comma", ""),
+                    );
                     break;
                 }
             }
@@ -2766,7 +2808,12 @@ fn parse_port_list<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Vec<Por
             (Comma, sp) => {
                 p.bump();
                 if p.peek(0).0 == CloseDelim(Paren) {
-                    p.add_diag(DiagBuilder2::warning("superfluous trailing comma").span(sp));
+                    p.add_diag(
+                        DiagBuilder2::lint(lints::TRAILING_COMMA, "superfluous trailing comma")
+                            .code(codes::SV0014)
+                            .span(sp)
+                            .span_suggestion(sp, "Remove the comma", ""),
+                    );
                     break;
                 }
             }
@@ -3392,7 +3439,7 @@ fn parse_subroutine_item<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<S
             dir: dir,
             var: var,
             ty: ty,
-            names: names,
+            names: p.arena().alloc_slice(names),
         }));
     }
 
@@ -3450,7 +3497,7 @@ fn parse_stmt_kind<'n>(
         OpenDelim(Bgend) => {
             p.bump();
             let (stmts, _) = parse_block(p, label, &[CloseDelim(Bgend)])?;
-            SequentialBlock(stmts)
+            SequentialBlock(p.arena().alloc_slice(stmts))
         }
 
         // Parallel blocks
@@ -3471,7 +3518,7 @@ fn parse_stmt_kind<'n>(
                 Keyword(Kw::JoinNone) => JoinKind::None,
                 x => panic!("Invalid parallel block terminator {:?}", x),
             };
-            ParallelBlock(stmts, join)
+            ParallelBlock(p.arena().alloc_slice(stmts), join)
         }
 
         // If and case statements
@@ -3685,7 +3732,8 @@ fn parse_block<'n>(
         if let Some(existing) = *label {
             if name == existing {
                 p.add_diag(
-                    DiagBuilder2::warning(format!("Block {} labelled twice", name)).span(name_span),
+                    DiagBuilder2::lint(lints::LABEL, format!("Block {} labelled twice", name))
+                        .span(name_span),
                 );
             } else {
                 p.add_diag(
@@ -3904,7 +3952,17 @@ fn parse_case<'n>(
                         p.bump();
                         if p.try_eat(Colon) {
                             p.add_diag(
-                                DiagBuilder2::warning("superfluous trailing comma").span(sp),
+                                DiagBuilder2::lint(
+                                    lints::TRAILING_COMMA,
+                                    "superfluous trailing comma",
+                                )
+                                .code(codes::SV0014)
+                                .span(sp)
+                                .span_suggestion(
+                                    sp,
+                                    "Remove the comma",
+                                    "",
+                                ),
                             );
                             break;
                         }
@@ -4257,7 +4315,12 @@ fn parse_call_args<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Vec<Cal
             (Comma, sp) => {
                 p.bump();
                 if p.try_eat(CloseDelim(Paren)) {
-                    p.add_diag(DiagBuilder2::warning("superfluous trailing comma").span(sp));
+                    p.add_diag(
+                        DiagBuilder2::lint(lints::TRAILING_COMMA, "superfluous trailing comma")
+                            .code(codes::SV0014)
+                            .span(sp)
+                            .span_suggestion(sp, "Remove the comma", ""),
+                    );
                     break;
                 }
             }
@@ -4422,7 +4485,7 @@ fn parse_generate_block<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Ge
             span,
             GenerateBlockData {
                 label: label,
-                items: vec![item],
+                items: p.arena().alloc_slice(Some(item)),
             },
         ));
     }
@@ -4433,8 +4496,11 @@ fn parse_generate_block<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Ge
         if let Some(existing) = label {
             if existing.value == n.value {
                 p.add_diag(
-                    DiagBuilder2::warning(format!("Generate block {} labelled twice", n))
-                        .span(n.span),
+                    DiagBuilder2::lint(
+                        lints::LABEL,
+                        format!("Generate block {} labelled twice", n),
+                    )
+                    .span(n.span),
                 );
             } else {
                 p.add_diag(
@@ -4471,10 +4537,13 @@ fn parse_generate_block<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Ge
             }
         } else {
             p.add_diag(
-                DiagBuilder2::warning(format!(
-                    "Generate block has trailing label {}, but is missing leading label",
-                    n
-                ))
+                DiagBuilder2::lint(
+                    lints::LABEL,
+                    format!(
+                        "Generate block has trailing label {}, but is missing leading label",
+                        n
+                    ),
+                )
                 .span(n.span),
             );
         }
@@ -4485,7 +4554,7 @@ fn parse_generate_block<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Ge
         span,
         GenerateBlockData {
             label: label,
-            items: items,
+            items: p.arena().alloc_slice(items),
         },
     ))
 }
@@ -4690,17 +4759,6 @@ fn parse_class_item_qualifiers<'n>(
     Ok(v)
 }
 
-fn parse_class_method<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ClassItem<'n>> {
-    println!("Parsing class method");
-    Err(())
-}
-
-fn parse_class_property<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ClassItem<'n>> {
-    println!("Parsing class property");
-    p.try_eat(Keyword(Kw::Rand));
-    Err(())
-}
-
 fn parse_constraint<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Constraint<'n>> {
     let mut span = p.peek(0).1;
 
@@ -5074,7 +5132,7 @@ fn parse_port_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<PortDec
             dir,
             kind,
             ty,
-            names,
+            names: p.arena().alloc_slice(names),
         },
     ))
 }
@@ -5168,7 +5226,7 @@ fn parse_net_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<NetDecl<
             kind,
             ty,
             delay,
-            names,
+            names: p.arena().alloc_slice(names),
         },
     ))
 }
@@ -5834,7 +5892,11 @@ fn parse_inst<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ast::Inst<'n
         span.expand(p.last_span());
         Ok(ast::InstName::new(
             span,
-            ast::InstNameData { name, dims, conns },
+            ast::InstNameData {
+                name,
+                dims,
+                conns: p.arena().alloc_slice(conns),
+            },
         ))
     })?;
 
@@ -5845,7 +5907,7 @@ fn parse_inst<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ast::Inst<'n
         ast::InstData {
             target,
             params,
-            names,
+            names: p.arena().alloc_slice(names),
         },
     ))
 }
@@ -5900,7 +5962,7 @@ fn parse_var_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ast::Var
             var: var,
             lifetime: lifetime,
             ty: ty,
-            names: names,
+            names: p.arena().alloc_slice(names),
         },
     ))
 }
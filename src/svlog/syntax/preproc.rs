@@ -5,9 +5,9 @@
 //! resolution.
 
 use crate::cat::*;
-use moore_common::errors::{DiagBuilder2, DiagResult2};
+use moore_common::errors::{DiagBuilder2, DiagEmitter, DiagResult2};
 use moore_common::source::*;
-use std::{collections::HashMap, fmt, path::Path, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, fmt, io::Write, path::Path, rc::Rc};
 
 use once_cell::sync::Lazy;
 
@@ -39,6 +39,27 @@ pub struct Preprocessor<'a> {
     defcond_stack: Vec<Defcond>,
     /// Currently enabled directives.
     dirs: Directives,
+    /// The span of the token last read from an input file, and the depth of
+    /// the include stack at that point.
+    origin: Option<(Span, usize)>,
+    /// The origin of the token last emitted.
+    location: Option<(Span, usize)>,
+    /// The record of included files, if any.
+    include_log: Option<IncludeLog>,
+}
+
+/// A record of the files included while preprocessing a file.
+///
+/// Clones share the same record, such that the files can be inspected after
+/// the preprocessor has been handed to the lexer.
+#[derive(Clone, Default)]
+pub struct IncludeLog(Rc<RefCell<Vec<Source>>>);
+
+impl IncludeLog {
+    /// The files included so far, in the order they were first included.
+    pub fn sources(&self) -> Vec<Source> {
+        self.0.borrow().clone()
+    }
 }
 
 impl<'a> Preprocessor<'a> {
@@ -88,9 +109,29 @@ impl<'a> Preprocessor<'a> {
             include_paths: include_paths,
             defcond_stack: Vec::new(),
             dirs: Default::default(),
+            origin: None,
+            location: None,
+            include_log: None,
+        }
+    }
+
+    /// Record the files included while preprocessing in a log.
+    pub fn with_include_log(self, include_log: IncludeLog) -> Self {
+        Preprocessor {
+            include_log: Some(include_log),
+            ..self
         }
     }
 
+    /// The location in the input files of the token emitted last, together
+    /// with the depth of the include stack at that point.
+    ///
+    /// Tokens produced by a macro expansion are located where the macro is
+    /// used rather than where it is defined.
+    pub fn location(&self) -> Option<(Span, usize)> {
+        self.location
+    }
+
     /// Advance to the next token in the input stream.
     fn bump(&mut self) {
         self.token = self.macro_stack.pop();
@@ -98,6 +139,7 @@ impl<'a> Preprocessor<'a> {
             return;
         }
         loop {
+            let depth = self.stack.len();
             self.token = match self.stack.last_mut() {
                 Some(stream) => stream
                     .iter
@@ -105,10 +147,11 @@ impl<'a> Preprocessor<'a> {
                     .map(|tkn| (tkn.0, Span::new(stream.source, tkn.1, tkn.2))),
                 None => return,
             };
-            if self.token.is_none() {
-                self.stack.pop();
-            } else {
+            if let Some((_, span)) = self.token {
+                self.origin = Some((span, depth));
                 break;
+            } else {
+                self.stack.pop();
             }
         }
     }
@@ -200,6 +243,12 @@ impl<'a> Preprocessor<'a> {
                     }
                 };
 
+                if let Some(ref log) = self.include_log {
+                    let mut sources = log.0.borrow_mut();
+                    if !sources.contains(&included_source) {
+                        sources.push(included_source);
+                    }
+                }
                 let content = included_source.get_content();
                 let content_unbound = unsafe { &*(content.as_ref() as *const dyn SourceContent) };
                 let iter = content_unbound.iter();
@@ -388,10 +437,20 @@ impl<'a> Preprocessor<'a> {
                     }
 
                     // Push the tokens of the macro onto the stack, potentially
-                    // substituting any macro parameters as necessary.
+                    // substituting any macro parameters as necessary. The
+                    // tokens of the body are marked as originating from this
+                    // expansion, such that diagnostics can point at the
+                    // invocation of the macro. Substituted arguments keep the
+                    // spans they had at the invocation.
+                    let expansion = get_source_manager().add_expansion(&makro.name, span);
                     if args.is_empty() {
-                        self.macro_stack
-                            .extend(makro.body.iter().rev().map(|&(tkn, sp)| (tkn, sp)));
+                        self.macro_stack.extend(
+                            makro
+                                .body
+                                .iter()
+                                .rev()
+                                .map(|&(tkn, sp)| (tkn, sp.with_expansion(expansion))),
+                        );
                     } else {
                         let mut replacement = Vec::<TokenAndSpan>::new();
                         // TODO: Make this work for argument names that contain
@@ -402,9 +461,9 @@ impl<'a> Preprocessor<'a> {
                                     Some(substitute) => {
                                         replacement.extend(substitute);
                                     }
-                                    None => replacement.push(*tkn),
+                                    None => replacement.push((Text, sp.with_expansion(expansion))),
                                 },
-                                x => replacement.push(x),
+                                (x, sp) => replacement.push((x, sp.with_expansion(expansion))),
                             }
                         }
                         self.macro_stack
@@ -1116,12 +1175,14 @@ impl<'a> Iterator for Preprocessor<'a> {
                         continue;
                     } else if let Some(tkn @ (Symbol('"'), _)) = self.token {
                         // emit the '"'
+                        self.location = self.origin;
                         self.bump();
                         if !self.is_inactive() {
                             return Some(Ok(tkn));
                         }
                     } else if let Some(tkn @ (Symbol('\\'), _)) = self.token {
                         // emit the '\'
+                        self.location = self.origin;
                         self.bump();
                         if !self.is_inactive() {
                             return Some(Ok(tkn));
@@ -1143,6 +1204,7 @@ impl<'a> Iterator for Preprocessor<'a> {
                         self.bump();
                     } else {
                         let tkn = self.token.map(|x| Ok(x));
+                        self.location = self.origin;
                         self.bump();
                         return tkn;
                     }
@@ -1152,6 +1214,126 @@ impl<'a> Iterator for Preprocessor<'a> {
     }
 }
 
+/// Write the output of a preprocessor.
+///
+/// If `line_markers` is set, `` `line`` directives are inserted wherever the
+/// output does not continue with the next line of the same input file, i.e. at
+/// the beginning, when entering and leaving included files, and after lines
+/// removed by directives. Paths within the working directory are given
+/// relative to it. Errors are emitted as diagnostics, and preprocessing
+/// continues after them.
+pub fn write_output(
+    preproc: Preprocessor,
+    line_markers: bool,
+    out: &mut dyn Write,
+    emitter: &dyn DiagEmitter,
+) -> Result<(), ()> {
+    let mut markers = LineMarkers::default();
+    let mut failed = false;
+    let mut preproc = preproc;
+    while let Some(token) = preproc.next() {
+        let span = match token {
+            Ok((_, span)) => span,
+            Err(diag) => {
+                emitter.emit(diag);
+                failed = true;
+                continue;
+            }
+        };
+        let text = span.extract();
+        let result = match preproc.location() {
+            Some(location) if line_markers => markers.write(out, location, &text),
+            _ => out.write_all(text.as_bytes()),
+        };
+        if let Err(e) = result {
+            emitter.emit(DiagBuilder2::error(format!(
+                "unable to write preprocessed output: {}",
+                e
+            )));
+            return Err(());
+        }
+    }
+    if failed {
+        Err(())
+    } else {
+        Ok(())
+    }
+}
+
+/// The bookkeeping needed to insert `` `line`` directives into the output of
+/// the preprocessor.
+#[derive(Default)]
+struct LineMarkers {
+    /// The file, offset, and line of the last token read at each level of the
+    /// include stack.
+    files: Vec<(Source, usize, usize)>,
+    /// The file and line that the current line of output corresponds to.
+    output: Option<(Source, usize)>,
+    /// Whether the output is at the beginning of a line.
+    line_start: bool,
+}
+
+impl LineMarkers {
+    /// Write a token located at `location`, preceded by a `` `line``
+    /// directive if needed.
+    fn write(
+        &mut self,
+        out: &mut dyn Write,
+        (span, depth): (Span, usize),
+        text: &str,
+    ) -> std::io::Result<()> {
+        // Determine the line of the token, counting the lines since the
+        // previous token in the same file.
+        let prev_depth = self.files.len();
+        self.files.truncate(depth);
+        if self.files.len() < depth || self.files[depth - 1].0 != span.source {
+            self.files.truncate(depth - 1);
+            self.files.push((span.source, 0, 1));
+        }
+        let file = &mut self.files[depth - 1];
+        if span.begin > file.1 {
+            let skipped = span.source.get_content().extract(file.1, span.begin);
+            file.2 += skipped.matches('\n').count();
+            file.1 = span.begin;
+        }
+        let line = file.2;
+
+        // Insert a directive if the output does not continue at that line.
+        if self.output != Some((span.source, line)) && (self.line_start || prev_depth != depth) {
+            if self.output.is_some() && !self.line_start {
+                out.write_all(b"\n")?;
+            }
+            let level = if self.output.is_none() || prev_depth == depth {
+                0
+            } else if prev_depth < depth {
+                1
+            } else {
+                2
+            };
+            writeln!(
+                out,
+                "`line {} \"{}\" {}",
+                line,
+                display_path(&span.source.get_path()),
+                level
+            )?;
+            self.output = Some((span.source, line));
+            self.line_start = true;
+        }
+
+        // Write the token and keep track of the line in the output.
+        out.write_all(text.as_bytes())?;
+        let newlines = text.matches('\n').count();
+        if let Some((_, ref mut line)) = self.output {
+            *line += newlines;
+        }
+        if !text.is_empty() {
+            self.line_start = text.ends_with('\n');
+        }
+        Ok(())
+    }
+}
+
 struct Stream<'a> {
     source: Source,
     iter: Cat<'a>,
@@ -1362,6 +1544,31 @@ mod tests {
         assert_eq!(actual, "// Hello\n/* World */\nbar\n42 something\n");
     }
 
+    #[test]
+    fn line_markers() {
+        let sm = get_source_manager();
+        sm.add("markers.svh", "`define W 8\nlocalparam X = `W;\n");
+        let source = sm.add(
+            "markers.sv",
+            "module top;\n`include \"markers.svh\"\n`ifdef NOPE\nwire a;\n`endif\nwire b;\n",
+        );
+        let pp = Preprocessor::new(source, &[], &[]);
+        let mut output = vec![];
+        write_output(pp, true, &mut output, &moore_common::Session::new()).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "`line 1 \"markers.sv\" 0\n\
+             module top;\n\
+             `line 2 \"markers.svh\" 1\n\
+             localparam X = 8;\n\
+             `line 2 \"markers.sv\" 2\n\
+             \n\
+             `line 5 \"markers.sv\" 0\n\
+             \n\
+             wire b;\n"
+        );
+    }
+
     #[test]
     #[should_panic(expected = "unknown compiler directive")]
     fn conditional_define() {
@@ -1395,4 +1602,28 @@ mod tests {
     fn macro_name_with_digits_and_underscores() {
         check_str("`define AXI_BUS21_SV 42\n`AXI_BUS21_SV", "42");
     }
+
+    /// Verify that expanded tokens record the chain of macro invocations, and
+    /// that substituted arguments keep their original span.
+    #[test]
+    fn macro_expansion_provenance() {
+        let pp = preproc("`define FOO(x) x+1\n`define BAR `FOO(a)\n`BAR\n");
+        let tokens: Vec<_> = pp.map(|x| x.unwrap().1).collect();
+        let chain = |sp: &Span| -> Vec<(String, String)> {
+            sp.expansion_chain()
+                .into_iter()
+                .map(|e| (e.name.to_string(), e.call_site.extract()))
+                .collect()
+        };
+        let a = tokens.iter().find(|sp| sp.extract() == "a").unwrap();
+        let one = tokens.iter().find(|sp| sp.extract() == "1").unwrap();
+        assert_eq!(chain(a), vec![("BAR".to_string(), "`BAR".to_string())]);
+        assert_eq!(
+            chain(one),
+            vec![
+                ("FOO".to_string(), "`FOO".to_string()),
+                ("BAR".to_string(), "`BAR".to_string())
+            ]
+        );
+    }
 }
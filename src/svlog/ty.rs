@@ -86,11 +86,15 @@
 //! which changes only their value domain.
 
 use crate::crate_prelude::*;
-use crate::{common::arenas::TypedArena, ParamEnv};
+use crate::{
+    common::arenas::{AllocStats, TypedArena},
+    ParamEnv,
+};
 use once_cell::sync::Lazy;
+use rustc_hash::FxHashSet;
+use smallvec::{smallvec, SmallVec};
 use std::{
     cell::RefCell,
-    collections::HashSet,
     fmt::{Debug, Display},
     hash::{Hash, Hasher},
 };
@@ -105,7 +109,7 @@ pub struct PackedType<'a> {
     /// Whether the sign was explicit in the source code.
     pub sign_explicit: bool,
     /// The packed dimensions.
-    pub dims: Vec<PackedDim>,
+    pub dims: PackedDims,
     /// This type with one level of name/reference resolved.
     resolved: Option<&'a Self>,
     /// This type with all names/references recursively resolved.
@@ -152,6 +156,12 @@ pub enum PackedDim {
     Unsized,
 }
 
+/// The packed dimensions of a type.
+///
+/// Few types have more than two packed dimensions, so these are stored inline
+/// to avoid allocating for the common `logic [N:0]` and `bit [M:0][N:0]`.
+pub type PackedDims = SmallVec<[PackedDim; 2]>;
+
 /// An integer vector type.
 ///
 /// These are the builtin single-bit integer types.
@@ -397,14 +407,14 @@ impl<'a> PackedType<'a> {
     /// This creates the type on the stack. In general you will want to use the
     /// `make_*` functions to create a type that is interned into a context.
     pub fn new(core: impl Into<PackedCore<'a>>) -> Self {
-        Self::with_dims(core, vec![])
+        Self::with_dims(core, smallvec![])
     }
 
     /// Create a new type with default sign and packed dimensions.
     ///
     /// This creates the type on the stack. In general you will want to use the
     /// `make_*` functions to create a type that is interned into a context.
-    pub fn with_dims(core: impl Into<PackedCore<'a>>, dims: Vec<PackedDim>) -> Self {
+    pub fn with_dims(core: impl Into<PackedCore<'a>>, dims: impl Into<PackedDims>) -> Self {
         let core = core.into();
         let sign = match &core {
             PackedCore::IntAtom(IntAtomType::Time)
@@ -425,7 +435,7 @@ impl<'a> PackedType<'a> {
     /// This creates the type on the stack. In general you will want to use the
     /// `make_*` functions to create a type that is interned into a context.
     pub fn with_sign(core: impl Into<PackedCore<'a>>, sign: Sign, sign_explicit: bool) -> Self {
-        Self::with_sign_and_dims(core, sign, sign_explicit, vec![])
+        Self::with_sign_and_dims(core, sign, sign_explicit, smallvec![])
     }
 
     /// Create a new type with packed dimensions.
@@ -436,13 +446,13 @@ impl<'a> PackedType<'a> {
         core: impl Into<PackedCore<'a>>,
         sign: Sign,
         sign_explicit: bool,
-        dims: Vec<PackedDim>,
+        dims: impl Into<PackedDims>,
     ) -> Self {
         Self {
             core: core.into(),
             sign,
             sign_explicit,
-            dims,
+            dims: dims.into(),
             resolved: None,
             resolved_full: None,
         }
@@ -468,9 +478,9 @@ impl<'a> PackedType<'a> {
                 sbv.sign,
                 sbv.sign_explicit,
                 if sbv.size > 1 || sbv.size_explicit {
-                    vec![PackedDim::Range(sbv.range())]
+                    smallvec![PackedDim::Range(sbv.range())]
                 } else {
-                    vec![]
+                    smallvec![]
                 },
             ),
         }
@@ -485,7 +495,7 @@ impl<'a> PackedType<'a> {
     pub fn make_dims(
         cx: &impl TypeContext<'a>,
         core: impl Into<PackedCore<'a>>,
-        dims: Vec<PackedDim>,
+        dims: impl Into<PackedDims>,
     ) -> &'a Self {
         Self::with_dims(core, dims).intern(cx)
     }
@@ -506,7 +516,7 @@ impl<'a> PackedType<'a> {
         core: impl Into<PackedCore<'a>>,
         sign: Sign,
         sign_explicit: bool,
-        dims: Vec<PackedDim>,
+        dims: impl Into<PackedDims>,
     ) -> &'a Self {
         Self::with_sign_and_dims(core, sign, sign_explicit, dims).intern(cx)
     }
@@ -553,7 +563,16 @@ impl<'a> PackedType<'a> {
     }
 
     /// Internalize this type in a context and resolve it.
-    pub fn intern(mut self, cx: &impl TypeContext<'a>) -> &'a Self {
+    ///
+    /// The names and type references are only resolved the first time a type
+    /// is interned.
+    pub fn intern(self, cx: &impl TypeContext<'a>) -> &'a Self {
+        cx.intern_packed(self)
+    }
+
+    /// Resolve the names and type references in a type that is interned for
+    /// the first time.
+    fn resolve_inner(&mut self, cx: &impl TypeContext<'a>) {
         let inner = match self.core {
             PackedCore::Named { ty, .. } => Some(ty),
             PackedCore::Ref { ty, .. } => Some(ty),
@@ -567,7 +586,6 @@ impl<'a> PackedType<'a> {
         if let Some(x) = self.resolved_full {
             trace!("Type `{}` fully resolves to `{}`", self, x);
         }
-        cx.intern_packed(self)
     }
 
     /// Apply the sign and dimensions to a core type that expanded to another
@@ -734,12 +752,18 @@ impl<'a> PackedType<'a> {
     }
 
     /// Check if this type will coalesce to a scalar type in LLHD, like `i42`.
+    ///
+    /// Packed unions coalesce to a scalar, since all their members share the
+    /// same bits.
     pub fn coalesces_to_llhd_scalar(&self) -> bool {
         if let Some(enm) = self.get_enum() {
             enm.base.coalesces_to_llhd_scalar()
         } else {
             !self.is_time()
-                && (self.is_integer_vec() || self.is_integer_atom() || self.is_single_bit())
+                && (self.is_integer_vec()
+                    || self.is_integer_atom()
+                    || self.is_single_bit()
+                    || self.is_union())
         }
     }
 
@@ -811,6 +835,13 @@ impl<'a> PackedType<'a> {
         }
     }
 
+    /// Check if this type is a packed union.
+    pub fn is_union(&self) -> bool {
+        self.get_struct()
+            .map(|s| s.kind == ast::StructKind::Union)
+            .unwrap_or(false)
+    }
+
     /// Get the underlying enum, or `None` if the type is no enum.
     pub fn get_enum(&self) -> Option<&EnumType<'a>> {
         let ty = self.resolve_full();
@@ -842,8 +873,6 @@ impl PartialEq for Intern<PackedType<'_>> {
             && self.0.sign == other.0.sign
             && self.0.sign_explicit == other.0.sign_explicit
             && self.0.dims == other.0.dims
-            && self.0.resolved == other.0.resolved
-            && self.0.resolved_full == other.0.resolved_full
     }
 }
 impl Hash for Intern<PackedType<'_>> {
@@ -852,8 +881,6 @@ impl Hash for Intern<PackedType<'_>> {
         self.0.sign.hash(h);
         self.0.sign_explicit.hash(h);
         self.0.dims.hash(h);
-        self.0.resolved.hash(h);
-        self.0.resolved_full.hash(h);
     }
 }
 
@@ -1229,8 +1256,27 @@ impl<'a> UnpackedType<'a> {
         unsafe { std::mem::transmute(ty) }
     }
 
+    /// Create a `real` type.
+    pub fn make_real() -> &'a Self {
+        static TYPE: Lazy<UnpackedType> =
+            Lazy::new(|| UnpackedType::new(UnpackedCore::Real(RealType::Real)));
+        let ty: &UnpackedType = &TYPE;
+        // SAFETY: This is safe since the cell which causes 'a to need to
+        // outlive 'static is actually never mutated after AST construction.
+        unsafe { std::mem::transmute(ty) }
+    }
+
     /// Internalize this type in a context and resolve it.
-    pub fn intern(mut self, cx: &impl TypeContext<'a>) -> &'a Self {
+    ///
+    /// The names and type references are only resolved the first time a type
+    /// is interned.
+    pub fn intern(self, cx: &impl TypeContext<'a>) -> &'a Self {
+        cx.intern_unpacked(self)
+    }
+
+    /// Resolve the names and type references in a type that is interned for
+    /// the first time.
+    fn resolve_inner(&mut self, cx: &impl TypeContext<'a>) {
         let inner = match self.core {
             UnpackedCore::Named { ty, .. } | UnpackedCore::Ref { ty, .. } => {
                 (Some(ty), Some(ty.resolve_full()))
@@ -1249,7 +1295,6 @@ impl<'a> UnpackedType<'a> {
         if let Some(x) = self.resolved_full {
             trace!("Type `{}` fully resolves to `{}`", self, x);
         }
-        cx.intern_unpacked(self)
     }
 
     /// Apply the sign and dimensions to a core type that expanded to another
@@ -1391,6 +1436,11 @@ impl<'a> UnpackedType<'a> {
             .unwrap_or(false)
     }
 
+    /// Check if this type is a packed union.
+    pub fn is_packed_union(&self) -> bool {
+        self.get_packed().map(|ty| ty.is_union()).unwrap_or(false)
+    }
+
     /// Check if this type will coalesce to a scalar type in LLHD, like `i42`.
     pub fn coalesces_to_llhd_scalar(&self) -> bool {
         self.get_packed()
@@ -1515,6 +1565,20 @@ impl<'a> UnpackedType<'a> {
         }
     }
 
+    /// Get the underlying real type, or `None` if the type is no real.
+    pub fn get_real(&self) -> Option<RealType> {
+        if self.dims.is_empty() {
+            self.resolve_full().core.get_real()
+        } else {
+            None
+        }
+    }
+
+    /// Check if this type is a `shortreal`, `real`, or `realtime`.
+    pub fn is_real(&self) -> bool {
+        self.get_real().is_some()
+    }
+
     /// Get the underlying module, or `None` if the type is not a module.
     pub fn get_module(&self) -> Option<&ModuleType<'a>> {
         if self.dims.is_empty() {
@@ -1570,18 +1634,13 @@ impl Hash for UnpackedType<'_> {
 impl Eq for Intern<UnpackedType<'_>> {}
 impl PartialEq for Intern<UnpackedType<'_>> {
     fn eq(&self, other: &Self) -> bool {
-        self.0.core == other.0.core
-            && self.0.dims == other.0.dims
-            && self.0.resolved == other.0.resolved
-            && self.0.resolved_full == other.0.resolved_full
+        self.0.core == other.0.core && self.0.dims == other.0.dims
     }
 }
 impl Hash for Intern<UnpackedType<'_>> {
     fn hash<H: Hasher>(&self, h: &mut H) {
         self.0.core.hash(h);
         self.0.dims.hash(h);
-        self.0.resolved.hash(h);
-        self.0.resolved_full.hash(h);
     }
 }
 
@@ -1660,6 +1719,15 @@ impl<'a> UnpackedCore<'a> {
         }
     }
 
+    /// Get the underlying real type, or `None` if the type is no real.
+    pub fn get_real(&self) -> Option<RealType> {
+        match *self {
+            UnpackedCore::Real(x) => Some(x),
+            UnpackedCore::Named { ty, .. } | UnpackedCore::Ref { ty, .. } => ty.get_real(),
+            _ => None,
+        }
+    }
+
     /// Get the underlying struct, or `None` if the type is no struct.
     pub fn get_struct(&self) -> Option<&StructType<'a>> {
         match *self {
@@ -1825,11 +1893,16 @@ impl<'a> StructType<'a> {
 
     /// Compute the size of this struct in bits.
     ///
-    /// Returns `None` if any member of the type has a `[]` dimension.
+    /// The size of a union is the size of its largest member. Returns `None`
+    /// if any member of the type has a `[]` dimension.
     pub fn get_bit_size(&self) -> Option<usize> {
         let mut size = 0;
         for m in &self.members {
-            size += m.ty.get_bit_size()?;
+            let member_size = m.ty.get_bit_size()?;
+            size = match self.kind {
+                ast::StructKind::Struct => size + member_size,
+                _ => std::cmp::max(size, member_size),
+            };
         }
         Some(size)
     }
@@ -2020,6 +2093,46 @@ impl Dim<'_> {
             Self::Unpacked(x) => x.get_size(),
         }
     }
+
+    /// Check whether positions grow as the index of the dimension decreases.
+    ///
+    /// The elements of a dimension are stored at positions `0` to `size-1`.
+    /// Packed dimensions store their rightmost index at position 0, such that
+    /// the leftmost index is the most significant (IEEE 1800-2017 §7.4.1).
+    /// For an ascending packed range like `[0:7]` this means that the index
+    /// `7` is at position 0 and the index `0` at position 7. All other
+    /// dimensions store their lowest index at position 0.
+    pub fn is_reversed(&self) -> bool {
+        match self {
+            Self::Packed(x) => x.get_range().map(|r| r.dir == RangeDir::Up) == Some(true),
+            Self::Unpacked(_) => false,
+        }
+    }
+
+    /// Check whether the leftmost index of the dimension is stored at the
+    /// highest position.
+    ///
+    /// This is the case for packed dimensions and descending unpacked ranges
+    /// like `[3:0]`, but not for ascending unpacked ranges like `[0:3]` or
+    /// C-style dimensions like `[4]`. Concatenations and bit-stream casts
+    /// place the leftmost element in the most significant bits.
+    pub fn is_left_highest(&self) -> bool {
+        match self {
+            Self::Packed(_) => true,
+            Self::Unpacked(x) => x.get_range().map(|r| r.dir == RangeDir::Down) == Some(true),
+        }
+    }
+
+    /// Map an index of the dimension to the position of its element.
+    ///
+    /// See `is_reversed` for how indices map to positions.
+    pub fn position(&self, index: isize) -> isize {
+        match self.get_range() {
+            Some(r) if self.is_reversed() => r.offset + r.size as isize - 1 - index,
+            Some(r) => index - r.offset,
+            None => index,
+        }
+    }
 }
 
 impl Display for Dim<'_> {
@@ -2041,12 +2154,33 @@ pub trait TypeContext<'a> {
 }
 
 /// An arena that can internalize type data.
+///
+/// Interned types compare and hash by address, which is only meaningful for
+/// types interned into the same storage. Unlike the name table, the storage
+/// cannot be shared across threads, since the underlying arenas are not
+/// synchronized.
 #[derive(Default)]
 pub struct TypeStorage<'a> {
     packed: TypedArena<Intern<PackedType<'a>>>,
     unpacked: TypedArena<Intern<UnpackedType<'a>>>,
-    cached_packed: RefCell<HashSet<&'a Intern<PackedType<'a>>>>,
-    cached_unpacked: RefCell<HashSet<&'a Intern<UnpackedType<'a>>>>,
+    cached_packed: RefCell<FxHashSet<&'a Intern<PackedType<'a>>>>,
+    cached_unpacked: RefCell<FxHashSet<&'a Intern<UnpackedType<'a>>>>,
+}
+
+impl<'a> TypeStorage<'a> {
+    /// The number and size of the types interned so far.
+    pub fn statistics(&self) -> Vec<AllocStats> {
+        vec![
+            AllocStats {
+                name: "PackedType",
+                ..AllocStats::of(&self.packed)
+            },
+            AllocStats {
+                name: "UnpackedType",
+                ..AllocStats::of(&self.unpacked)
+            },
+        ]
+    }
 }
 
 /// An object that has type storage.
@@ -2057,6 +2191,10 @@ pub trait HasTypeStorage<'a> {
 
 /// An wrapper around types that hash/compare by pointer, but must hash/compare
 /// by value for interning.
+///
+/// The resolved forms of a type follow from its core, sign, and dimensions,
+/// and are not compared. This allows a type to be looked up before they are
+/// computed.
 struct Intern<T>(T);
 
 impl<'a, T> TypeContext<'a> for T
@@ -2064,22 +2202,24 @@ where
     T: HasTypeStorage<'a>,
 {
     fn intern_packed(&self, ty: PackedType<'a>) -> &'a PackedType<'a> {
-        let ty = Intern(ty);
+        let mut ty = Intern(ty);
         let st = self.type_storage();
         if let Some(x) = st.cached_packed.borrow().get(&ty) {
             return &x.0;
         }
+        ty.0.resolve_inner(self);
         let ty = st.packed.alloc(ty);
         st.cached_packed.borrow_mut().insert(ty);
         &ty.0
     }
 
     fn intern_unpacked(&self, ty: UnpackedType<'a>) -> &'a UnpackedType<'a> {
-        let ty = Intern(ty);
+        let mut ty = Intern(ty);
         let st = self.type_storage();
         if let Some(x) = st.cached_unpacked.borrow().get(&ty) {
             return &x.0;
         }
+        ty.0.resolve_inner(self);
         let ty = st.unpacked.alloc(ty);
         st.cached_unpacked.borrow_mut().insert(ty);
         &ty.0
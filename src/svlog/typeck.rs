@@ -435,6 +435,7 @@ pub(crate) fn map_to_type<'a>(
                         None => {
                             cx.emit(
                                 DiagBuilder2::error(format!("{} is not a type", assigned_ast))
+                                    .code(codes::SV0009)
                                     .span(assigned_ast.human_span())
                                     .add_note(format!(
                                         "Assigned to type parameter `{}` here:",
@@ -493,6 +494,7 @@ pub(crate) fn map_to_type_or_error<'a>(
         None => {
             cx.emit(
                 DiagBuilder2::error(format!("`{}` is not a type", ast.span().extract()))
+                    .code(codes::SV0009)
                     .span(ast.span()),
             );
             error!("Offending node: {:#2?}", ast);
@@ -582,7 +584,7 @@ pub(crate) fn packed_type_from_ast<'a>(
 
             // Populate the members.
             for member in &strukt.members {
-                for name in &member.names {
+                for name in member.names {
                     // Depending on whether the struct is packed or unpacked, we
                     // admit packed or unpacked members types.
                     let ty = if strukt.packed {
@@ -600,6 +602,35 @@ pub(crate) fn packed_type_from_ast<'a>(
                 }
             }
 
+            // The members of a packed union must all have the same size, since
+            // they share the same bits.
+            if strukt.packed && strukt.kind == ast::StructKind::Union {
+                let mut sizes = def
+                    .members
+                    .iter()
+                    .filter(|m| !m.ty.is_error())
+                    .map(|m| (m, m.ty.get_bit_size()));
+                if let Some((first, size)) = sizes.next() {
+                    for (member, other) in sizes {
+                        if other != size {
+                            cx.emit(
+                                DiagBuilder2::error(format!(
+                                    "member `{}` of packed union has a different size than `{}`",
+                                    member.name, first.name
+                                ))
+                                .span(member.name.span)
+                                .add_note(format!(
+                                    "`{}` has type `{}`, but `{}` has type `{}`",
+                                    member.name, member.ty, first.name, first.ty
+                                ))
+                                .add_note("All members of a packed union must have the same size."),
+                            );
+                            failed = true;
+                        }
+                    }
+                }
+            }
+
             // Keep track of the sign, and complain if the packed type itself
             // has separate sign information.
             if ast_sign != ast::TypeSign::None {
@@ -888,6 +919,7 @@ fn packed_type_from_def<'a>(
         None => {
             cx.emit(
                 DiagBuilder2::error(format!("`{}` is not a type", def.name))
+                    .code(codes::SV0009)
                     .span(span)
                     .add_note(format!("`{}` was declared here:", def.name))
                     .span(def.node.span()),
@@ -1006,6 +1038,7 @@ pub(crate) fn type_of_expr<'a>(
         // These expressions are have a fully self-determined type.
         hir::ExprKind::IntConst { .. }
         | hir::ExprKind::TimeConst(..)
+        | hir::ExprKind::RealConst(..)
         | hir::ExprKind::StringConst(..)
         | hir::ExprKind::Ident(..)
         | hir::ExprKind::Scope(..)
@@ -1153,6 +1186,9 @@ pub(crate) fn cast_expr_type<'a>(
                     ty
                 ),
                 CastOp::PickModport => format!("implicitly picking modport `{}`", ty),
+                CastOp::IntToReal => format!("convert to real `{}`", ty),
+                CastOp::RealToInt => format!("round to integer `{}`", ty),
+                CastOp::RealToReal => format!("convert to real `{}`", ty),
             };
             d = d.add_note(msg);
         }
@@ -1238,6 +1274,11 @@ fn cast_expr_type_inner<'gcx>(
         }
     }
 
+    // Convert real numbers to the context type.
+    if inferred.is_real() {
+        return cast_real_type(cx, expr, cast, context);
+    }
+
     // Cast the expression to a simple bit vector type.
     let inferred_sbvt = match inferred.get_simple_bit_vector() {
         Some(ty) => {
@@ -1280,6 +1321,13 @@ fn cast_expr_type_inner<'gcx>(
         TypeContext::Type(ty) => ty,
     };
 
+    // Convert the SBVT to a real number.
+    if context.is_real() {
+        trace!("  Converting to real");
+        cast.add_cast(CastOp::IntToReal, context);
+        return cast;
+    }
+
     // Cast the context type to an SBVT.
     let context_sbvt = match context.get_simple_bit_vector() {
         Some(ty) => ty.forget(),
@@ -1408,6 +1456,56 @@ fn cast_expr_type_inner<'gcx>(
     ty::UnpackedType::make_error().into()
 }
 
+/// Complete a cast sequence that starts at a real number.
+///
+/// Reals are rounded to the nearest integer when cast to an integral type, and
+/// compared against zero when cast to a boolean. See §6.12.1 "Conversion".
+fn cast_real_type<'gcx>(
+    cx: &impl Context<'gcx>,
+    expr: &'gcx hir::Expr<'gcx>,
+    mut cast: CastType<'gcx>,
+    context: TypeContext<'gcx>,
+) -> CastType<'gcx> {
+    let context = match context {
+        TypeContext::Bool => {
+            trace!("  Casting real to bool ({})", context.ty());
+            cast.add_cast(CastOp::Bool, context.ty());
+            return cast;
+        }
+        TypeContext::Type(ty) => ty,
+    };
+    if context.is_real() {
+        trace!("  Converting real precision");
+        cast.add_cast(CastOp::RealToReal, context);
+        return cast;
+    }
+    match context.get_simple_bit_vector() {
+        Some(sbvt) => {
+            trace!("  Rounding real to SBVT `{}`", sbvt);
+            cast.add_cast(CastOp::RealToInt, sbvt.forget().to_unpacked(cx));
+            if !context.is_simple_bit_vector() {
+                trace!("  Unpacking SBVT");
+                cast.add_cast(CastOp::UnpackSBVT, context);
+            }
+            cast
+        }
+        None => {
+            cx.emit(
+                DiagBuilder2::error(format!(
+                    "cannot cast a value of type `{}` to `{}`",
+                    cast.init, context
+                ))
+                .span(expr.span)
+                .add_note(format!(
+                    "`{}` has no simple bit-vector type representation",
+                    context
+                )),
+            );
+            ty::UnpackedType::make_error().into()
+        }
+    }
+}
+
 /// Get the self-determined type of a node.
 #[moore_derive::query]
 pub(crate) fn self_determined_type<'a>(
@@ -1450,7 +1548,7 @@ pub(crate) fn need_self_determined_type<'a>(
                          used it does not provide such information.",
                         desc
                     ))
-                    .add_note(format!("Try a cast: `T'({})`", extract)),
+                    .span_suggestion(cx.span(node_id), "Try a cast", format!("T'({})", extract)),
             );
             UnpackedType::make_error()
         }
@@ -1492,6 +1590,9 @@ fn self_determined_expr_type<'gcx>(
         // Time constants are of time type.
         hir::ExprKind::TimeConst(_) => Some(UnpackedType::make_time()),
 
+        // Real constants are of real type.
+        hir::ExprKind::RealConst(_) => Some(UnpackedType::make_real()),
+
         // String literals are of string type.
         hir::ExprKind::StringConst(_) => Some(
             ty::PackedType::make_dims(
@@ -1904,7 +2005,18 @@ pub(crate) fn operation_type<'a>(
                 | hir::UnaryOp::PostDec => {
                     let tc = cx.type_context(node_id, env).map(|x| x.ty());
                     let targ = cx.self_determined_type(arg, env);
-                    unify_operator_types(cx, env, tc.into_iter().chain(targ.into_iter()))
+                    let treal = match op {
+                        hir::UnaryOp::Neg
+                        | hir::UnaryOp::Pos
+                        | hir::UnaryOp::PreInc
+                        | hir::UnaryOp::PreDec
+                        | hir::UnaryOp::PostInc
+                        | hir::UnaryOp::PostDec => real_operator_type(targ.into_iter()),
+                        _ => None,
+                    };
+                    treal.or_else(|| {
+                        unify_operator_types(cx, env, tc.into_iter().chain(targ.into_iter()))
+                    })
                 }
 
                 // Handle the self-determined cases.
@@ -1913,12 +2025,17 @@ pub(crate) fn operation_type<'a>(
             if ty.is_none() {
                 cx.emit(
                     DiagBuilder2::error(format!("type of {} cannot be inferred", expr.desc_full()))
+                        .code(codes::SV0010)
                         .span(expr.human_span())
                         .add_note(
                             "The operand does not have a self-determined type, and the type \
                              cannot be inferred from the context.",
                         )
-                        .add_note(format!("Try a cast: `T'({})`", expr.span().extract())),
+                        .span_suggestion(
+                            expr.span(),
+                            "Try a cast",
+                            format!("T'({})", expr.span().extract()),
+                        ),
                 );
                 Some(UnpackedType::make_error())
             } else {
@@ -1946,13 +2063,31 @@ pub(crate) fn operation_type<'a>(
                     let tc = cx.type_context(node_id, env).map(|x| x.ty());
                     let tlhs = cx.self_determined_type(lhs, env);
                     let trhs = cx.self_determined_type(rhs, env);
-                    unify_operator_types(
-                        cx,
-                        env,
-                        tc.into_iter()
-                            .chain(tlhs.into_iter())
-                            .chain(trhs.into_iter()),
-                    )
+                    match real_operator_type(tlhs.into_iter().chain(trhs.into_iter())) {
+                        Some(ty) => match op {
+                            hir::BinaryOp::Add
+                            | hir::BinaryOp::Sub
+                            | hir::BinaryOp::Mul
+                            | hir::BinaryOp::Div => Some(ty),
+                            _ => {
+                                cx.emit(
+                                    DiagBuilder2::error(format!(
+                                        "{} cannot operate on real numbers",
+                                        expr.desc_full()
+                                    ))
+                                    .span(expr.human_span()),
+                                );
+                                Some(UnpackedType::make_error())
+                            }
+                        },
+                        None => unify_operator_types(
+                            cx,
+                            env,
+                            tc.into_iter()
+                                .chain(tlhs.into_iter())
+                                .chain(trhs.into_iter()),
+                        ),
+                    }
                 }
 
                 // Comparison operations do not consider their type context, but
@@ -1965,7 +2100,9 @@ pub(crate) fn operation_type<'a>(
                 | hir::BinaryOp::Geq => {
                     let tlhs = cx.self_determined_type(lhs, env);
                     let trhs = cx.self_determined_type(rhs, env);
-                    unify_operator_types(cx, env, tlhs.into_iter().chain(trhs.into_iter()))
+                    real_operator_type(tlhs.into_iter().chain(trhs.into_iter())).or_else(|| {
+                        unify_operator_types(cx, env, tlhs.into_iter().chain(trhs.into_iter()))
+                    })
                 }
 
                 // The boolean logic operators simply operate on bits.
@@ -1973,6 +2110,18 @@ pub(crate) fn operation_type<'a>(
                     Some(UnpackedType::make_logic())
                 }
 
+                // Exponentiation with a real operand operates on reals.
+                hir::BinaryOp::Pow
+                    if real_operator_type(
+                        cx.self_determined_type(lhs, env)
+                            .into_iter()
+                            .chain(cx.self_determined_type(rhs, env).into_iter()),
+                    )
+                    .is_some() =>
+                {
+                    Some(UnpackedType::make_real())
+                }
+
                 // Exponentiation and shifts operate on the left-hand side type.
                 hir::BinaryOp::Pow
                 | hir::BinaryOp::LogicShL
@@ -1987,12 +2136,17 @@ pub(crate) fn operation_type<'a>(
             if ty.is_none() {
                 cx.emit(
                     DiagBuilder2::error(format!("type of {} cannot be inferred", expr.desc_full()))
+                        .code(codes::SV0010)
                         .span(expr.human_span())
                         .add_note(
                             "Neither of the operands has a self-determined type, and the type \
                              cannot be inferred from the context.",
                         )
-                        .add_note(format!("Try a cast: `T'({})`", expr.span().extract())),
+                        .span_suggestion(
+                            expr.span(),
+                            "Try a cast",
+                            format!("T'({})", expr.span().extract()),
+                        ),
                 );
                 Some(UnpackedType::make_error())
             } else {
@@ -2006,13 +2160,15 @@ pub(crate) fn operation_type<'a>(
             let tc = cx.type_context(node_id, env).map(|x| x.ty());
             let tlhs = cx.self_determined_type(lhs, env);
             let trhs = cx.self_determined_type(rhs, env);
-            unify_operator_types(
-                cx,
-                env,
-                tc.into_iter()
-                    .chain(tlhs.into_iter())
-                    .chain(trhs.into_iter()),
-            )
+            real_operator_type(tlhs.into_iter().chain(trhs.into_iter())).or_else(|| {
+                unify_operator_types(
+                    cx,
+                    env,
+                    tc.into_iter()
+                        .chain(tlhs.into_iter())
+                        .chain(trhs.into_iter()),
+                )
+            })
         }
 
         // The inside expression uses an operation type for its comparisons. It
@@ -2101,6 +2257,26 @@ fn unify_operator_types<'gcx>(
     width.map(|w| SbvType::nice(domain, sign, w).to_unpacked(cx))
 }
 
+/// Determine the real type an operator operates on, if any of its operands is
+/// a real number.
+///
+/// The type context is deliberately not considered, such that an integer
+/// expression assigned to a real is still evaluated as an integer. See §11.8.1
+/// "Rules for expression types".
+fn real_operator_type<'gcx>(
+    types: impl Iterator<Item = &'gcx UnpackedType<'gcx>>,
+) -> Option<&'gcx UnpackedType<'gcx>> {
+    let mut result = None;
+    for ty in types {
+        match ty.get_real() {
+            Some(RealType::ShortReal) => result = result.or(Some(ty)),
+            Some(_) => return Some(UnpackedType::make_real()),
+            None => (),
+        }
+    }
+    result
+}
+
 /// Require a node to have an operation type.
 ///
 /// Emits an error if the node has no operation type.
@@ -2221,13 +2397,14 @@ pub(crate) fn need_type_context<'a>(
                 .unwrap_or_else(|_| format!("`{}`", extract));
             cx.emit(
                 DiagBuilder2::error(format!("type of {} cannot be inferred from context", desc))
+                    .code(codes::SV0010)
                     .span(cx.span(node_id))
                     .add_note(format!(
                         "The type of {} must be inferred from context, but the location where you \
                          used it does not provide such information.",
                         desc
                     ))
-                    .add_note(format!("Try a cast: `T'({})`", extract)),
+                    .span_suggestion(cx.span(node_id), "Try a cast", format!("T'({})", extract)),
             );
             UnpackedType::make_error().into()
         }
@@ -2318,6 +2495,11 @@ fn type_context_imposed_by_expr<'gcx>(
             // The logic operators require boolean arguments.
             hir::BinaryOp::LogicAnd | hir::BinaryOp::LogicOr => Some(TypeContext::Bool),
 
+            // Exponentiation on reals imposes a type context on both sides.
+            hir::BinaryOp::Pow if cx.need_operation_type(expr.id, env).is_real() => {
+                Some(cx.need_operation_type(expr.id, env).into())
+            }
+
             // Exponentiation and shifts impose a type context on their left
             // hand side.
             hir::BinaryOp::Pow
@@ -2505,8 +2687,9 @@ fn type_context_imposed_by_stmt<'gcx>(
             }
         }
 
-        // If statements and do/while loops require a boolean condition.
+        // If and cover statements require a boolean condition.
         hir::StmtKind::If { cond, .. } if onto == cond => Some(TypeContext::Bool),
+        hir::StmtKind::Cover { expr, .. } if onto == expr => Some(TypeContext::Bool),
 
         // Do/while loops require a boolean condition.
         hir::StmtKind::Loop { kind, .. } => {
@@ -2568,6 +2751,12 @@ pub enum CastOp {
     Domain(ty::Domain),
     /// Pick an interface's modport.
     PickModport,
+    /// Convert an SBVT to a real number.
+    IntToReal,
+    /// Round a real number to an SBVT.
+    RealToInt,
+    /// Convert a real number to a different precision.
+    RealToReal,
 }
 
 impl<'a> CastType<'a> {
@@ -0,0 +1,140 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Finding signals, parameters, and ports that are never used.
+//!
+//! This builds on the symbol tables of the `lookup` module, which list the
+//! definitions in a module together with the names that refer to them. A
+//! signal that is only ever assigned to counts as unused, since its value has
+//! no effect on the design. Definitions whose name contains `_unused` are not
+//! reported, such that deliberately unused signals and ports can be marked as
+//! such in the source text.
+
+use crate::{
+    ast::AcceptVisitor,
+    crate_prelude::*,
+    lookup::SymbolKind,
+    resolver::{DefNode, ScopedNode},
+};
+use moore_common::lints;
+use std::collections::HashSet;
+
+/// Report the unused signals, parameters, and ports in the modules of a source
+/// file.
+///
+/// The modules should have passed name resolution, since definitions appear
+/// unused if the names referring to them cannot be resolved.
+pub fn check_unused<'gcx>(cx: &GlobalContext<'gcx>, file: &'gcx ast::SourceFile<'gcx>) {
+    for item in file.items.iter() {
+        if let ast::ItemData::ModuleDecl(ref module) = item.data {
+            check_module(cx, module);
+        }
+    }
+}
+
+/// Report the unused definitions in a module.
+fn check_module<'gcx>(cx: &GlobalContext<'gcx>, module: &'gcx ast::Module<'gcx>) {
+    let mut targets = AssignTargets::default();
+    module.accept(&mut targets);
+    let table = cx.symbol_table(Ref(module as &dyn ScopedNode));
+    for symbol in &table.symbols {
+        let name = symbol.name();
+        if name.value.as_str().contains("_unused") {
+            continue;
+        }
+        let unused = symbol.references.is_empty();
+        let diag = match symbol.kind {
+            SymbolKind::Net | SymbolKind::Variable => {
+                if symbol
+                    .references
+                    .iter()
+                    .any(|sp| !targets.spans.contains(sp))
+                {
+                    continue;
+                }
+                DiagBuilder2::lint(
+                    lints::UNUSED_SIGNAL,
+                    format!("signal `{}` is never read", name.value),
+                )
+            }
+            SymbolKind::ValueParam | SymbolKind::TypeParam if unused => DiagBuilder2::lint(
+                lints::UNUSED_PARAM,
+                format!("parameter `{}` is never used", name.value),
+            ),
+            SymbolKind::Port if unused => match symbol.def.node {
+                DefNode::IntPort(..) => DiagBuilder2::lint(
+                    lints::UNUSED_PORT,
+                    format!(
+                        "port `{}` is never used inside module `{}`",
+                        name.value, module.name.value
+                    ),
+                ),
+                DefNode::Ast(..) => continue,
+            },
+            _ => continue,
+        };
+        cx.emit(
+            diag.span(name.span)
+                .add_note("Add `_unused` to the name if this is intentional."),
+        );
+    }
+}
+
+/// A visitor that collects the names which are assigned to, like the `x` in
+/// `x = a` or `x[i] <= a`.
+///
+/// Compound assignments like `x += a` also read their target and are not
+/// collected.
+#[derive(Default)]
+struct AssignTargets {
+    spans: HashSet<Span>,
+}
+
+impl AssignTargets {
+    /// Collect the names written by an assignment to an expression.
+    fn add(&mut self, expr: &ast::Expr) {
+        match expr.data {
+            ast::IdentExpr(name) => {
+                self.spans.insert(name.span);
+            }
+            ast::IndexExpr { ref indexee, .. } => self.add(indexee),
+            ast::MemberExpr { ref expr, .. } => self.add(expr),
+            ast::ConcatExpr { exprs, .. } => {
+                for expr in exprs {
+                    self.add(expr);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+impl<'a> ast::Visitor<'a> for AssignTargets {
+    fn pre_visit_node(&mut self, node: &'a dyn ast::AnyNode<'a>) -> bool {
+        match node.as_all() {
+            ast::AllNode::ContAssign(assign) => {
+                for (lhs, _) in &assign.assignments {
+                    self.add(lhs);
+                }
+            }
+            ast::AllNode::Stmt(stmt) => match stmt.kind {
+                ast::BlockingAssignStmt {
+                    ref lhs,
+                    op: ast::AssignOp::Identity,
+                    ..
+                }
+                | ast::NonblockingAssignStmt { ref lhs, .. } => self.add(lhs),
+                _ => (),
+            },
+            ast::AllNode::Expr(expr) => match expr.data {
+                ast::AssignExpr {
+                    ref lhs,
+                    op: ast::AssignOp::Identity,
+                    ..
+                } => self.add(lhs),
+                _ => (),
+            },
+            _ => (),
+        }
+        true
+    }
+}
@@ -19,7 +19,7 @@ use crate::{
 };
 use bit_vec::BitVec;
 use itertools::Itertools;
-use num::{BigInt, BigRational, Integer, One, ToPrimitive, Zero};
+use num::{BigInt, BigRational, FromPrimitive, Integer, One, Signed, ToPrimitive, Zero};
 
 /// A verilog value.
 pub type Value<'t> = &'t ValueData<'t>;
@@ -50,6 +50,7 @@ impl<'t> ValueData<'t> {
             ValueKind::Void => true,
             ValueKind::Int(ref v, ..) => v.is_zero(),
             ValueKind::Time(ref v) => v.is_zero(),
+            ValueKind::Real(v) => f64::from_bits(v) == 0.0,
             ValueKind::StructOrArray(_) => false,
             ValueKind::Error => true,
         }
@@ -62,6 +63,14 @@ impl<'t> ValueData<'t> {
             _ => None,
         }
     }
+
+    /// Convert the value to a real number.
+    pub fn get_real(&self) -> Option<f64> {
+        match self.kind {
+            ValueKind::Real(v) => Some(f64::from_bits(v)),
+            _ => None,
+        }
+    }
 }
 
 /// The different forms a value can assume.
@@ -76,6 +85,11 @@ pub enum ValueKind<'t> {
     Int(BigInt, BitVec, BitVec),
     /// An arbitrary precision time interval.
     Time(BigRational),
+    /// A real number.
+    ///
+    /// Stored as the bit pattern of its IEEE 754 double precision
+    /// representation, such that the value can be hashed and compared.
+    Real(u64),
     /// A struct.
     StructOrArray(Vec<Value<'t>>),
     /// An error occurred during value computation.
@@ -98,6 +112,7 @@ impl std::fmt::Display for ValueKind<'_> {
             ValueKind::Void => write!(f, "void"),
             ValueKind::Int(v, ..) => write!(f, "{}", v),
             ValueKind::Time(v) => write!(f, "{}", v),
+            ValueKind::Real(v) => write!(f, "{:?}", f64::from_bits(*v)),
             ValueKind::StructOrArray(v) => {
                 write!(f, "{{ {} }}", v.iter().map(|v| &v.kind).format(", "))
             }
@@ -154,6 +169,22 @@ pub fn make_time<'a>(value: BigRational) -> ValueData<'a> {
     }
 }
 
+/// Create a new real value.
+///
+/// Panics if `ty` is not a real type. Rounds the value to single precision if
+/// `ty` is a `shortreal`.
+pub fn make_real<'a>(ty: &'a UnpackedType<'a>, value: f64) -> ValueData<'a> {
+    let value = match ty.get_real() {
+        Some(ty::RealType::ShortReal) => value as f32 as f64,
+        Some(_) => value,
+        None => panic!("make_real got non-real type `{}`", ty),
+    };
+    ValueData {
+        ty,
+        kind: ValueKind::Real(value.to_bits()),
+    }
+}
+
 /// Create a new struct value.
 pub fn make_struct<'a>(ty: &'a UnpackedType<'a>, fields: Vec<Value<'a>>) -> ValueData<'a> {
     assert!(ty.dims().next().is_none() && ty.get_struct().is_some());
@@ -349,6 +380,45 @@ fn const_mir_rvalue_inner<'a>(cx: &impl Context<'a>, mir: &'a mir::Rvalue<'a>) -
             cx.intern_value(make_int(mir.ty, (value.is_true() as usize).into()))
         }
 
+        mir::RvalueKind::IntToReal(sign, value) => {
+            let value_const = cx.const_mir_rvalue(value.into());
+            if value_const.is_error() {
+                return cx.intern_value(make_error(mir.ty));
+            }
+            let sbvt = value.ty.simple_bit_vector(cx, value.span);
+            let mut int = value_const
+                .get_int()
+                .expect("int-to-real non-integer")
+                .clone();
+            let modulus = BigInt::one() << sbvt.size;
+            if int.is_negative() {
+                int += &modulus;
+            }
+            if sign == ty::Sign::Signed && int >= (BigInt::one() << (sbvt.size - 1)) {
+                int -= &modulus;
+            }
+            cx.intern_value(make_real(mir.ty, int.to_f64().unwrap_or(std::f64::NAN)))
+        }
+
+        mir::RvalueKind::RealToInt(value) => {
+            let value_const = cx.const_mir_rvalue(value.into());
+            if value_const.is_error() {
+                return cx.intern_value(make_error(mir.ty));
+            }
+            let real = value_const.get_real().expect("real-to-int non-real");
+            let int = BigInt::from_f64(real.round()).unwrap_or_else(BigInt::zero);
+            cx.intern_value(make_int(mir.ty, int))
+        }
+
+        mir::RvalueKind::RealToReal(value) => {
+            let value_const = cx.const_mir_rvalue(value.into());
+            if value_const.is_error() {
+                return cx.intern_value(make_error(mir.ty));
+            }
+            let real = value_const.get_real().expect("real-to-real non-real");
+            cx.intern_value(make_real(mir.ty, real))
+        }
+
         mir::RvalueKind::ConstructArray(ref values) => cx.intern_value(make_array(
             mir.ty,
             (0..values.len())
@@ -468,6 +538,40 @@ fn const_mir_rvalue_inner<'a>(cx: &impl Context<'a>, mir: &'a mir::Rvalue<'a>) -
             }
         }
 
+        mir::RvalueKind::RealUnaryArith { op, arg } => {
+            let arg_val = cx.const_mir_rvalue(arg.into());
+            if arg_val.is_error() {
+                return cx.intern_value(make_error(mir.ty));
+            }
+            let arg = arg_val.get_real().expect("real arith on non-real");
+            cx.intern_value(make_real(mir.ty, const_unary_arith_real(op, arg)))
+        }
+
+        mir::RvalueKind::RealBinaryArith { op, lhs, rhs } => {
+            let lhs_val = cx.const_mir_rvalue(lhs.into());
+            let rhs_val = cx.const_mir_rvalue(rhs.into());
+            if lhs_val.is_error() || rhs_val.is_error() {
+                return cx.intern_value(make_error(mir.ty));
+            }
+            let lhs = lhs_val.get_real().expect("real arith on non-real");
+            let rhs = rhs_val.get_real().expect("real arith on non-real");
+            cx.intern_value(make_real(mir.ty, const_binary_arith_real(op, lhs, rhs)))
+        }
+
+        mir::RvalueKind::RealComp { op, lhs, rhs } => {
+            let lhs_val = cx.const_mir_rvalue(lhs.into());
+            let rhs_val = cx.const_mir_rvalue(rhs.into());
+            if lhs_val.is_error() || rhs_val.is_error() {
+                return cx.intern_value(make_error(mir.ty));
+            }
+            let lhs = lhs_val.get_real().expect("real comparison on non-real");
+            let rhs = rhs_val.get_real().expect("real comparison on non-real");
+            cx.intern_value(make_int(
+                mir.ty,
+                (const_comp_real(op, lhs, rhs) as usize).into(),
+            ))
+        }
+
         mir::RvalueKind::Concat(ref values) => {
             let mut result = BigInt::zero();
             for &value in values {
@@ -499,7 +603,11 @@ fn const_mir_rvalue_inner<'a>(cx: &impl Context<'a>, mir: &'a mir::Rvalue<'a>) -
         | mir::RvalueKind::Port(_)
         | mir::RvalueKind::IntfSignal(..)
         | mir::RvalueKind::Intf(..) => {
-            cx.emit(DiagBuilder2::error("value is not constant").span(mir.span));
+            cx.emit(
+                DiagBuilder2::error("value is not constant")
+                    .code(codes::SV0012)
+                    .span(mir.span),
+            );
             cx.intern_value(make_error(mir.ty))
         }
 
@@ -723,6 +831,33 @@ fn const_comp_int<'gcx>(
     }
 }
 
+fn const_unary_arith_real(op: mir::RealUnaryArithOp, arg: f64) -> f64 {
+    match op {
+        mir::RealUnaryArithOp::Neg => -arg,
+    }
+}
+
+fn const_binary_arith_real(op: mir::RealBinaryArithOp, lhs: f64, rhs: f64) -> f64 {
+    match op {
+        mir::RealBinaryArithOp::Add => lhs + rhs,
+        mir::RealBinaryArithOp::Sub => lhs - rhs,
+        mir::RealBinaryArithOp::Mul => lhs * rhs,
+        mir::RealBinaryArithOp::Div => lhs / rhs,
+        mir::RealBinaryArithOp::Pow => lhs.powf(rhs),
+    }
+}
+
+fn const_comp_real(op: mir::RealCompOp, lhs: f64, rhs: f64) -> bool {
+    match op {
+        mir::RealCompOp::Eq => lhs == rhs,
+        mir::RealCompOp::Neq => lhs != rhs,
+        mir::RealCompOp::Lt => lhs < rhs,
+        mir::RealCompOp::Leq => lhs <= rhs,
+        mir::RealCompOp::Gt => lhs > rhs,
+        mir::RealCompOp::Geq => lhs >= rhs,
+    }
+}
+
 fn const_shift_int<'gcx>(
     _cx: &impl Context<'gcx>,
     _ty: SbvType,
@@ -790,6 +925,11 @@ pub(crate) fn type_default_value<'a>(cx: &impl Context<'a>, ty: &'a UnpackedType
         });
     }
 
+    // Handle packed unions, which are represented as a single integer.
+    if ty.is_packed_union() {
+        return cx.intern_value(make_int(ty, Zero::zero()));
+    }
+
     // Handle structs.
     if let Some(strukt) = ty.get_struct() {
         let fields = strukt
@@ -836,6 +976,11 @@ pub(crate) fn type_default_value<'a>(cx: &impl Context<'a>, ty: &'a UnpackedType
         ty
     );
 
+    // Handle real types.
+    if ty.is_real() {
+        return cx.intern_value(make_real(ty, 0.0));
+    }
+
     // Handle unpacked types.
     let packed = match ty.core {
         ty::UnpackedCore::Packed(p) => p,
@@ -0,0 +1,238 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Width mismatch detection.
+//!
+//! This module reports assignments whose value is wider or narrower than the
+//! assigned target, and comparisons between operands of different widths.
+//! Both are legal SystemVerilog, where the value is silently truncated or
+//! extended, but are a common source of bugs.
+//!
+//! Constants adapt to the width of their context and are not reported, such
+//! that `x = 0` or `x + 1` do not warn about the 32 bits of the literal.
+//! Arithmetic and bitwise operators take the width of their widest operand
+//! which is not a constant.
+
+use crate::{
+    crate_prelude::*,
+    hir::{BinaryOp, ExprKind, HirNode, StmtKind, UnaryOp, Visitor},
+    ParamEnv,
+};
+use moore_common::lints;
+
+/// Report the width mismatches in the processes and continuous assignments of
+/// a module.
+///
+/// Only the processes and assignments directly within the module body are
+/// considered; generate blocks are skipped.
+pub fn check_widths<'a>(cx: &impl Context<'a>, node_id: NodeId, env: ParamEnv) {
+    let hir = match cx.hir_of(node_id) {
+        Ok(HirNode::Module(x)) => x,
+        _ => return,
+    };
+    let mut checker = WidthChecker { cx, env };
+    for &proc_id in &hir.block.procs {
+        checker.visit_node_with_id(proc_id, false);
+    }
+    for &assign_id in &hir.block.assigns {
+        if let Ok(HirNode::Assign(assign)) = cx.hir_of(assign_id) {
+            checker.check_assign(assign.lhs, assign.rhs);
+        }
+        checker.visit_node_with_id(assign_id, false);
+    }
+}
+
+/// A visitor that checks the widths of assignments and comparisons.
+struct WidthChecker<'c, C> {
+    cx: &'c C,
+    env: ParamEnv,
+}
+
+impl<'a, 'c, C: Context<'a>> WidthChecker<'c, C> {
+    /// Determine the width of an expression, or `None` if it is a constant or
+    /// has no simple width.
+    fn width(&self, expr_id: NodeId) -> Option<usize> {
+        if self.cx.mir_rvalue(expr_id, self.env).is_const() {
+            return None;
+        }
+        let expr = match self.cx.hir_of(expr_id) {
+            Ok(HirNode::Expr(x)) => x,
+            _ => return None,
+        };
+        match expr.kind {
+            ExprKind::Binary(op, lhs, rhs) if is_context_determined(op) => self.widest(lhs, rhs),
+            ExprKind::Unary(UnaryOp::Pos, arg)
+            | ExprKind::Unary(UnaryOp::Neg, arg)
+            | ExprKind::Unary(UnaryOp::BitNot, arg) => self.width(arg),
+            ExprKind::Ternary(_, true_expr, false_expr) => self.widest(true_expr, false_expr),
+            _ => self
+                .cx
+                .self_determined_type(expr_id, self.env)
+                .and_then(|ty| ty.get_bit_size()),
+        }
+    }
+
+    /// Determine the width of the wider of two expressions.
+    fn widest(&self, a: NodeId, b: NodeId) -> Option<usize> {
+        match (self.width(a), self.width(b)) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Describe how a value is extended, i.e. whether it is sign-extended or
+    /// zero-extended.
+    fn extension(&self, expr_id: NodeId) -> &'static str {
+        let signed = self
+            .cx
+            .self_determined_type(expr_id, self.env)
+            .and_then(|ty| ty.get_simple_bit_vector())
+            .map_or(false, |sbv| sbv.is_signed());
+        if signed {
+            "sign-extended"
+        } else {
+            "zero-extended"
+        }
+    }
+
+    /// Check the widths of an assignment.
+    fn check_assign(&self, lhs: NodeId, rhs: NodeId) {
+        let lhs_width = match self.cx.mir_lvalue(lhs, self.env).ty.get_bit_size() {
+            Some(w) => w,
+            None => return,
+        };
+        let rhs_width = match self.width(rhs) {
+            Some(w) => w,
+            None => return,
+        };
+        if lhs_width == rhs_width {
+            return;
+        }
+        let note = if rhs_width > lhs_width {
+            format!(
+                "The value is truncated to {} bits, dropping its upper {} bits.",
+                lhs_width,
+                rhs_width - lhs_width
+            )
+        } else {
+            format!(
+                "The value is {} to {} bits.",
+                self.extension(rhs),
+                lhs_width
+            )
+        };
+        self.cx.emit(
+            DiagBuilder2::lint(
+                lints::WIDTH_MISMATCH,
+                format!(
+                    "{}-bit value assigned to {}-bit `{}`",
+                    rhs_width,
+                    lhs_width,
+                    self.cx.span(lhs).extract()
+                ),
+            )
+            .span(self.cx.span(rhs))
+            .add_note(note),
+        );
+    }
+
+    /// Check the widths of the operands of a comparison.
+    fn check_comparison(&self, expr_id: NodeId, lhs: NodeId, rhs: NodeId) {
+        let (lhs_width, rhs_width) = match (self.width(lhs), self.width(rhs)) {
+            (Some(a), Some(b)) if a != b => (a, b),
+            _ => return,
+        };
+        let (narrow, wide_width) = if lhs_width < rhs_width {
+            (lhs, rhs_width)
+        } else {
+            (rhs, lhs_width)
+        };
+        self.cx.emit(
+            DiagBuilder2::lint(
+                lints::WIDTH_MISMATCH,
+                format!(
+                    "comparison between {}-bit and {}-bit operands",
+                    lhs_width, rhs_width
+                ),
+            )
+            .span(self.cx.span(expr_id))
+            .add_note(format!(
+                "`{}` is {} to {} bits.",
+                self.cx.span(narrow).extract(),
+                self.extension(narrow),
+                wide_width
+            )),
+        );
+    }
+}
+
+impl<'a, 'c, C: Context<'a>> Visitor<'a> for WidthChecker<'c, C> {
+    type Context = C;
+
+    fn context(&self) -> &C {
+        self.cx
+    }
+
+    fn visit_stmt(&mut self, stmt: &'a hir::Stmt) -> hir::Control {
+        match stmt.kind {
+            StmtKind::Assign {
+                lhs,
+                rhs,
+                kind: hir::AssignKind::Block(ast::AssignOp::Identity),
+            }
+            | StmtKind::Assign {
+                lhs,
+                rhs,
+                kind: hir::AssignKind::Nonblock,
+            }
+            | StmtKind::Assign {
+                lhs,
+                rhs,
+                kind: hir::AssignKind::NonblockDelay(_),
+            } => self.check_assign(lhs, rhs),
+            _ => (),
+        }
+        hir::walk_stmt(self, stmt)
+    }
+
+    fn visit_expr(&mut self, expr: &'a hir::Expr<'a>, lvalue: bool) -> hir::Control {
+        match expr.kind {
+            ExprKind::Binary(op, lhs, rhs) if is_comparison(op) => {
+                self.check_comparison(expr.id, lhs, rhs)
+            }
+            _ => (),
+        }
+        hir::walk_expr(self, expr, lvalue)
+    }
+}
+
+/// Check whether a binary operator extends its operands to the width of its
+/// context.
+fn is_context_determined(op: BinaryOp) -> bool {
+    match op {
+        BinaryOp::Add
+        | BinaryOp::Sub
+        | BinaryOp::Mul
+        | BinaryOp::Div
+        | BinaryOp::Mod
+        | BinaryOp::BitAnd
+        | BinaryOp::BitNand
+        | BinaryOp::BitOr
+        | BinaryOp::BitNor
+        | BinaryOp::BitXor
+        | BinaryOp::BitXnor => true,
+        _ => false,
+    }
+}
+
+/// Check whether a binary operator compares its operands.
+fn is_comparison(op: BinaryOp) -> bool {
+    match op {
+        BinaryOp::Eq
+        | BinaryOp::Neq
+        | BinaryOp::Lt
+        | BinaryOp::Leq
+        | BinaryOp::Gt
+        | BinaryOp::Geq => true,
+        _ => false,
+    }
+}
@@ -13,6 +13,7 @@ use crate::common::name::*;
 use crate::common::score::NodeRef;
 use crate::common::source::*;
 
+use crate::generic_env::GenericEnv;
 use crate::op::*;
 use crate::scope::Scope;
 use crate::score::{
@@ -230,8 +231,13 @@ fn wrapup_type_builtin(bt: &BuiltinType) -> (Builtin, Vec<Builtin>) {
     let bi = Builtin::new(Def::Type(bt.id), bt.name).ty(bt.ty.clone());
     let mut aux = Vec::new();
 
-    // Add the usual predefined operators that all types get.
+    // Add the usual predefined operators that all types get. The subtypes of
+    // INTEGER and TIME share their operators, rather than declaring their own.
+    let is_int_subtype = bt.id == NATURAL_TYPE.id || bt.id == POSITIVE_TYPE.id;
+    let is_time_subtype = bt.id == DELAY_LENGTH_TYPE.id;
     match bt.ty {
+        Ty::Int(_) if is_int_subtype => (),
+        Ty::Physical(_) if is_time_subtype => (),
         Ty::Enum(_) => enum_type_builtins(&bt.named_ty(), &mut aux),
         Ty::Int(_) => integer_type_builtins(&bt.named_ty(), &mut aux),
         Ty::Physical(_) => physical_type_builtins(&bt.named_ty(), &mut aux),
@@ -419,12 +425,30 @@ pub fn register_builtins<'ast, 'ctx>(sb: &ScoreBoard<'ast, 'ctx>) {
             .iter()
             .flat_map(|&(ref bi, ref aux)| once(bi).chain(aux.iter()))
             .filter_map(|bi| match bi.ty {
-                Some(ref ty) => Some((bi.def.into(), Ok(sb.intern_ty(ty.clone())))),
+                Some(ref ty) => Some((
+                    (bi.def.into(), GenericEnv::default()),
+                    Ok(sb.intern_ty(ty.clone())),
+                )),
                 None => None,
             }),
     );
 }
 
+/// Determine the number of literals of a builtin enum type.
+///
+/// Returns `None` if `id` is not a builtin enum type.
+pub fn builtin_enum_len(id: TypeDeclRef) -> Option<usize> {
+    (*STANDARD_PKG_SCOPE)
+        .defs
+        .values()
+        .flat_map(|defs| defs.iter())
+        .filter_map(|def| match def.value {
+            Def::Enum(EnumRef(ty, index)) if ty == id => Some(index + 1),
+            _ => None,
+        })
+        .max()
+}
+
 /// Create a physical type with time units.
 fn make_time_type(decl: TypeDeclRef, base: IntTy) -> PhysicalTy {
     PhysicalTy::new(
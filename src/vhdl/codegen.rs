@@ -2,14 +2,20 @@
 
 //! LLHD code generation for VHDL.
 
+use crate::builtin::{BOOLEAN_TYPE, INTEGER_TYPE};
+use crate::generic_env::GenericEnvData;
 use crate::hir;
 use crate::konst::*;
+use crate::op::*;
 use crate::score::*;
 use crate::ty::*;
-use llhd;
+use llhd::{self, Value};
 use moore_common::errors::*;
+use moore_common::name::Name;
 use moore_common::score::Result;
-use num::{Signed, ToPrimitive, Zero};
+use moore_common::source::Span;
+use num::{BigInt, BigRational, Signed};
+use std::collections::HashMap;
 
 /// Generates LLHD code.
 pub trait Codegen<I, C> {
@@ -60,13 +66,7 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
                     llhd::int_ty(diff.bits())
                 }
             }
-            Ty::Enum(ref ty) => {
-                let hir = self.lazy_hir(ty.decl)?;
-                match hir.data.as_ref().unwrap().value {
-                    hir::TypeData::Enum(ref lits) => llhd::enum_ty(lits.len()),
-                    _ => unreachable!(),
-                }
-            }
+            Ty::Enum(ref ty) => llhd::int_ty(self.enum_width(ty.decl)?),
             Ty::Physical(ref ty) => {
                 self.emit(DiagBuilder2::error(format!(
                     "cannot generate code for physical type `{}`",
@@ -78,51 +78,9 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
             Ty::Array(ref ty) => {
                 let mut llty = self.map_type(&ty.element)?;
                 for index in ty.indices.iter().rev() {
-                    match *index {
-                        ArrayIndex::Unbounded(_) => {
-                            self.emit(
-                                DiagBuilder2::error(format!("type `{}` is unbounded", ty)), // TODO: What span should we use here?
-                            );
-                            return Err(());
-                        }
-                        ArrayIndex::Constrained(ref ty) => {
-                            let num = match **ty {
-                                Ty::Int(ref ty) => {
-                                    let l = ty.len();
-                                    if l.is_negative() || l.is_zero() {
-                                        return Ok(llhd::void_ty());
-                                    }
-                                    match l.to_usize() {
-                                        Some(l) => l,
-                                        None => {
-                                            self.emit(
-                                                DiagBuilder2::error(format!(
-                                                    "array index `{}` is too large; {} elements",
-                                                    ty, l
-                                                )), // TODO: What span should we use here?
-                                            );
-                                            return Err(());
-                                        }
-                                    }
-                                }
-                                Ty::Enum(ref ty) => {
-                                    match self.lazy_hir(ty.decl)?.data.as_ref().unwrap().value {
-                                        hir::TypeData::Enum(ref lits) => lits.len(),
-                                        _ => unreachable!(),
-                                    }
-                                }
-                                _ => {
-                                    self.emit(
-                                        DiagBuilder2::error(format!(
-                                            "`{}` is an invalid array index type",
-                                            ty
-                                        )), // TODO: What span should we use here?
-                                    );
-                                    return Err(());
-                                }
-                            };
-                            llty = llhd::array_ty(num, llty);
-                        }
+                    match self.array_index_len(ty, index)? {
+                        0 => return Ok(llhd::void_ty()),
+                        len => llty = llhd::array_ty(len, llty),
                     }
                 }
                 llty
@@ -137,6 +95,13 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
                 llhd::struct_ty(fields)
             }
             Ty::Subprog(..) => unimplemented!(),
+            Ty::Protected(_) => {
+                self.emit(DiagBuilder2::bug(format!(
+                    "protected type `{}` cannot be mapped to LLHD",
+                    ty
+                )));
+                return Err(());
+            }
             // Unbounded integers cannot be mapped to LLHD. All cases where
             // such an int can leak through to codegen should actually be caught
             // beforehand in the type check.
@@ -144,23 +109,80 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
         })
     }
 
+    /// Determine the number of bits needed to encode the literals of an enum.
+    ///
+    /// Enums are mapped to integers, since LLHD has no constants of its enum
+    /// types. This makes `BIT` and `BOOLEAN` single-bit values, such that they
+    /// can be used as branch conditions directly.
+    pub fn enum_width(&self, decl: TypeDeclRef) -> Result<usize> {
+        let len = self.enum_len(decl)?;
+        Ok(std::cmp::max(BigInt::from(len.saturating_sub(1)).bits(), 1))
+    }
+
     /// Map a constant value to the LLHD counterpart.
     pub fn map_const(&self, konst: &Const) -> Result<llhd::ValueRef> {
         Ok(match *konst {
             // TODO: Map this to llhd::const_void once available.
-            Const::Null => llhd::const_int(0, 0.into()),
-            Const::Int(ref k) => llhd::const_int(999, k.value.clone()),
-            Const::Enum(ref k) => {
-                let size = match self.lazy_hir(k.decl)?.data.as_ref().unwrap().value {
-                    hir::TypeData::Enum(ref lits) => lits.len(),
-                    _ => unreachable!(),
+            Const::Null => llhd::const_int(0, 0.into()).into(),
+            Const::Int(ref k) => {
+                let width = match k.ty {
+                    Some(ref ty) => {
+                        let ty = self.map_type(&Ty::Int(ty.clone()))?;
+                        if ty.is_int() {
+                            ty.unwrap_int()
+                        } else {
+                            0
+                        }
+                    }
+                    None => 999,
                 };
-                llhd::const_int(size, k.index.into())
+                llhd::const_int(width, k.value.clone()).into()
             }
+            Const::Enum(ref k) => llhd::const_int(self.enum_width(k.decl)?, k.index.into()).into(),
             Const::Float(ref _k) => panic!("cannot map float constant"),
             Const::IntRange(_) | Const::FloatRange(_) => panic!("cannot map range constant"),
+            Const::Array(ref k) => {
+                let elements = k
+                    .elements
+                    .iter()
+                    .map(|k| self.map_const(k))
+                    .collect::<Result<Vec<_>>>()?;
+                let element_ty = match elements[0] {
+                    llhd::ValueRef::Const(ref k) => k.ty(),
+                    llhd::ValueRef::Aggregate(ref k) => k.ty(),
+                    _ => unreachable!(),
+                };
+                llhd::const_array(element_ty, elements)
+            }
+            Const::Record(ref k) => llhd::const_struct(
+                k.fields
+                    .iter()
+                    .map(|k| self.map_const(k))
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+        })
+    }
+
+    /// Map a constant of type `TIME` to an LLHD time value.
+    ///
+    /// Time constants are counted in femtoseconds, the primary unit of `TIME`,
+    /// whereas LLHD expects the physical time in seconds.
+    pub fn map_time_const(&self, konst: &Const) -> Result<llhd::ValueRef> {
+        match *konst {
+            Const::Int(ref k) => Ok(llhd::const_time(
+                BigRational::new(k.value.clone(), num::pow(BigInt::from(10), 15)),
+                0,
+                0,
+            )
+            .into()),
+            _ => {
+                self.emit(DiagBuilder2::bug(format!(
+                    "{:?} is not a valid time constant",
+                    konst
+                )));
+                Err(())
+            }
         }
-        .into())
     }
 }
 
@@ -190,10 +212,24 @@ impl_codegen!(self, id: DeclInBlockRef, ctx: &mut llhd::Entity => {
 });
 
 impl_codegen!(self, id: ConstDeclRef, _ctx: &mut llhd::Entity => {
-    unimp!(self, id);
+    // Constants are substituted where they are used, so there is nothing to
+    // emit here other than checking that the value can be calculated.
+    let hir = self.lazy_hir(id)?;
+    if let Some(init_id) = hir.decl.init {
+        self.const_value(init_id)?;
+    }
+    Ok(())
 });
 
 impl_codegen!(self, id: VarDeclRef, _ctx: &mut llhd::Entity => {
+    let hir = self.lazy_hir(id)?;
+    if hir.decl.shared {
+        self.emit(
+            DiagBuilder2::error(format!("shared variable `{}` is not supported", hir.name.value))
+            .span(hir.name.span)
+        );
+        return Err(());
+    }
     unimp!(self, id);
 });
 
@@ -205,7 +241,7 @@ impl_codegen!(self, id: SignalDeclRef, ctx: &mut llhd::Entity => {
     // Calculate the initial value for the signal, either from the provided
     // expression or implicitly.
     let init = if let Some(init_id) = hir.decl.init {
-        self.const_value(init_id)?
+        self.cast_const(self.const_value(init_id)?, ty)?
     } else {
         self.default_value_for_type(&ty)?
     };
@@ -216,7 +252,8 @@ impl_codegen!(self, id: SignalDeclRef, ctx: &mut llhd::Entity => {
         Some(hir.name.value.into()),
         llhd::SignalInst(self.map_type(ty)?, Some(self.map_const(init)?))
     );
-    ctx.add_inst(inst, llhd::InstPosition::End);
+    let inst = ctx.add_inst(inst, llhd::InstPosition::End);
+    self.set_llsig(id, inst.into());
     Ok(())
 });
 
@@ -244,37 +281,229 @@ impl_codegen!(self, id: BlockStmtRef, _ctx: &mut llhd::Entity => {
 
 impl_codegen!(self, id: ProcessStmtRef, ctx: &mut llhd::Entity => {
     let hir = self.hir(id)?;
+    let span = self.ast(id).1.span;
     let name = match hir.label {
         Some(n) => format!("{}_{}", ctx.name(), n.value),
         None => format!("{}_proc", ctx.name()),
     };
     debugln!("generating process `{}`", name);
-    // TODO: Check which signals are actually read and written.
-    let ty = llhd::entity_ty(vec![], vec![]);
+
+    // The signals the process reads become its inputs, the signals it drives
+    // its outputs.
+    let sensitivity = self.process_sensitivity(id)?;
+    let mut inputs = sensitivity.clone();
+    for read in self.process_reads(id)? {
+        if !inputs.contains(&read.signal) {
+            inputs.push(read.signal);
+        }
+    }
+    let mut outputs = vec![];
+    self.process_signals(&hir.stmts, &mut inputs, &mut outputs)?;
+    let in_tys = inputs
+        .iter()
+        .map(|&sig| self.map_type(self.ty(sig)?))
+        .collect::<Result<Vec<_>>>()?;
+    let out_tys = outputs
+        .iter()
+        .map(|&sig| self.map_type(self.ty(sig)?))
+        .collect::<Result<Vec<_>>>()?;
+    let ty = llhd::entity_ty(in_tys, out_tys);
     let mut prok = llhd::Process::new(name, ty.clone());
-    // TODO: define the process as a local name
-    // TOOD: codegen declarations
-    // TOOD: codegen statements
+    for (arg, &sig) in prok.inputs_mut().iter_mut().zip(inputs.iter()) {
+        arg.set_name(self.signal_name(sig)?.as_str().to_owned());
+    }
+    for (arg, &sig) in prok.outputs_mut().iter_mut().zip(outputs.iter()) {
+        arg.set_name(self.signal_name(sig)?.as_str().to_owned());
+    }
+    let input_args: HashMap<_, _> = inputs
+        .iter()
+        .enumerate()
+        .map(|(i, &sig)| (sig, prok.input(i).into()))
+        .collect();
+    let output_args: HashMap<_, _> = outputs
+        .iter()
+        .enumerate()
+        .map(|(i, &sig)| (sig, prok.output(i).into()))
+        .collect();
+
     {
         let body = prok.body_mut();
         let entry_blk = body.add_block(llhd::Block::new(Some("entry".into())), llhd::BlockPosition::End);
-        let mut builder = InstBuilder::new(body, entry_blk);
+        let body_blk = body.add_block(llhd::Block::new(Some("body".into())), llhd::BlockPosition::End);
+        let mut builder = ProcessBuilder::new(body, entry_blk, input_args, output_args);
+
+        // Declarations are elaborated once when the process starts.
+        for &decl in &hir.decls {
+            self.codegen(decl, &mut builder)?;
+        }
+
+        // Processes execute their statements in an infinite loop. A process
+        // with a sensitivity list suspends at the end of each iteration until
+        // one of the signals in the list changes.
+        builder.inst.set_block(body_blk);
         for &stmt in &hir.stmts {
             self.codegen(stmt, &mut builder)?;
         }
+        if sensitivity.is_empty() {
+            builder.inst.add_inst(llhd::Inst::new(
+                None,
+                llhd::BranchInst(llhd::BranchKind::Uncond(body_blk)),
+            ));
+        } else {
+            let signals = sensitivity
+                .iter()
+                .map(|&sig| self.process_input(&builder, sig, span))
+                .collect::<Result<Vec<_>>>()?;
+            let wait = builder.inst.add_inst(llhd::Inst::new(
+                None,
+                llhd::WaitInst(body_blk, None, signals),
+            ));
+            builder.waits.push(wait);
+        }
+        builder.finish(body_blk);
     }
-    // TODO: codegen wait statements implied by sensitivity list
     let prok_ref = self.sb.llmod.borrow_mut().add_process(prok);
-    // TODO: wire instantiation with signals in the process' port.
+
+    // Instantiate the process with the signals in its ports.
+    let mut ins = vec![];
+    for &sig in &inputs {
+        ins.push(self.process_llsig(sig, span)?);
+    }
+    let mut outs = vec![];
+    for &sig in &outputs {
+        outs.push(self.process_llsig(sig, span)?);
+    }
     ctx.add_inst(
         llhd::Inst::new(hir.label.map(|l| l.value.into()), llhd::InstKind::InstanceInst(
-            ty, prok_ref.into(), vec![], vec![]
+            ty, prok_ref.into(), ins, outs
         )),
         llhd::InstPosition::End
     );
     Ok(())
 });
 
+impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
+    /// Find the signals a process waits on and drives.
+    ///
+    /// The signals in the sensitivity clauses of wait statements are added to
+    /// `inputs`, the targets of signal assignments to `outputs`.
+    fn process_signals(
+        &self,
+        stmts: &[SeqStmtRef],
+        inputs: &mut Vec<SignalRef>,
+        outputs: &mut Vec<SignalRef>,
+    ) -> Result<()> {
+        for &stmt in stmts {
+            match stmt {
+                SeqStmtRef::Wait(id) => {
+                    if let Some(ref sens) = self.lazy_hir(id)?.stmt.sens {
+                        for sig in &sens.value {
+                            if !inputs.contains(&sig.value) {
+                                inputs.push(sig.value);
+                            }
+                        }
+                    }
+                }
+                SeqStmtRef::SigAssign(id) => {
+                    if let hir::SigAssignTarget::Name(sig) = self.hir(id)?.target {
+                        if !outputs.contains(&sig) {
+                            outputs.push(sig);
+                        }
+                    }
+                }
+                SeqStmtRef::If(id) => {
+                    let hir = &self.lazy_hir(id)?.stmt;
+                    for &(_, ref stmts) in &hir.branches {
+                        self.process_signals(stmts, inputs, outputs)?;
+                    }
+                    if let Some(ref stmts) = hir.otherwise {
+                        self.process_signals(stmts, inputs, outputs)?;
+                    }
+                }
+                SeqStmtRef::Case(id) => {
+                    for &(_, ref stmts) in &self.lazy_hir(id)?.stmt.cases {
+                        self.process_signals(stmts, inputs, outputs)?;
+                    }
+                }
+                SeqStmtRef::Loop(id) => {
+                    self.process_signals(&self.lazy_hir(id)?.stmt.stmts, inputs, outputs)?;
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Determine the name of a signal.
+    fn signal_name(&self, sig: SignalRef) -> Result<Name> {
+        Ok(match sig {
+            SignalRef::Intf(id) => self.hir(id)?.name.value,
+            SignalRef::Decl(id) => self.lazy_hir(id)?.name.value,
+        })
+    }
+
+    /// Obtain the LLHD value of a signal connected to a process.
+    fn process_llsig(&self, sig: SignalRef, span: Span) -> Result<llhd::ValueRef> {
+        match self.llsig(sig) {
+            Some(value) => Ok(value),
+            None => {
+                self.emit(
+                    DiagBuilder2::bug(format!(
+                        "no signal generated for `{}`",
+                        self.signal_name(sig)?
+                    ))
+                    .span(span),
+                );
+                Err(())
+            }
+        }
+    }
+
+    /// Obtain the process input through which a signal is read.
+    fn process_input(
+        &self,
+        ctx: &ProcessBuilder,
+        sig: SignalRef,
+        span: Span,
+    ) -> Result<llhd::ValueRef> {
+        match ctx.inputs.get(&sig) {
+            Some(value) => Ok(value.clone()),
+            None => {
+                self.emit(
+                    DiagBuilder2::bug(format!(
+                        "`{}` is not an input of the process",
+                        self.signal_name(sig)?
+                    ))
+                    .span(span),
+                );
+                Err(())
+            }
+        }
+    }
+
+    /// Obtain the process output through which a signal is driven.
+    fn process_output(
+        &self,
+        ctx: &ProcessBuilder,
+        sig: SignalRef,
+        span: Span,
+    ) -> Result<llhd::ValueRef> {
+        match ctx.outputs.get(&sig) {
+            Some(value) => Ok(value.clone()),
+            None => {
+                self.emit(
+                    DiagBuilder2::bug(format!(
+                        "`{}` is not an output of the process",
+                        self.signal_name(sig)?
+                    ))
+                    .span(span),
+                );
+                Err(())
+            }
+        }
+    }
+}
+
 impl_codegen!(self, id: ConcCallStmtRef, _ctx: &mut llhd::Entity => {
     unimp!(self, id);
 });
@@ -287,26 +516,686 @@ impl_codegen!(self, id: ConcSigAssignStmtRef, _ctx: &mut llhd::Entity => {
     unimp!(self, id);
 });
 
-impl_codegen!(self, id: CompInstStmtRef, _ctx: &mut llhd::Entity => {
-    unimp!(self, id);
+impl_codegen!(self, id: CompInstStmtRef, ctx: &mut llhd::Entity => {
+    let hir = self.lazy_hir(id)?;
+
+    // Determine the entity and architecture to instantiate. Instances that are
+    // left unbound generate nothing.
+    let binding = match self.inst_binding(id)? {
+        Some(b) => b,
+        None => return Ok(()),
+    };
+    let entity = self.hir(binding.entity)?;
+    let arch = binding.arch;
+    let bound_assocs;
+    let (generics, ports) = match hir.stmt.unit {
+        hir::InstUnit::Comp(comp) => {
+            bound_assocs = self.bind_comp_assocs(comp, &hir.stmt, binding.entity)?;
+            (&bound_assocs.0, &bound_assocs.1)
+        }
+        _ => (&hir.stmt.generics, &hir.stmt.ports),
+    };
+
+    // Calculate the values of the generics, which form the environment the
+    // instantiated architecture is elaborated in.
+    let mut values = Vec::new();
+    for &(generic, actual) in generics {
+        let ty = self.lazy_typeval(generic)?;
+        let value = self.cast_const(self.const_value(actual)?, ty)?;
+        if !self.const_in_range(value, ty)? {
+            let span = self.span(actual).unwrap();
+            self.emit(
+                DiagBuilder2::error(format!(
+                    "value {} of `{}` is out of range for generic `{}` of type {}",
+                    value,
+                    span.extract(),
+                    self.lazy_hir(generic)?.name.value,
+                    ty
+                ))
+                .span(span),
+            );
+            return Err(());
+        }
+        values.push((generic.into(), value));
+    }
+    let env = self.intern_generic_env(GenericEnvData {
+        parent: None,
+        values: values,
+        cfg: binding.cfg,
+    });
+    let inner = self.with_env(env);
+    let unit = inner.lldef(arch)?;
+
+    // Connect the ports of the instance. Ports that are left unconnected are
+    // driven by a fresh signal carrying their default value.
+    let mut in_tys = Vec::new();
+    let mut out_tys = Vec::new();
+    let mut ins = Vec::new();
+    let mut outs = Vec::new();
+    for &port in &entity.ports {
+        let port_hir = self.hir(port)?;
+        let ty = inner.ty(port_hir.ty)?;
+        let llty = self.map_type(ty)?;
+        let actual = ports.iter().find(|&&(p, _)| p == port).map(|&(_, a)| a);
+        let value = match actual {
+            Some(actual) => {
+                let span = self.span(actual).unwrap();
+                let sig = match self.lazy_hir(actual)?.data {
+                    hir::ExprData::SignalName(sig) => sig,
+                    _ => {
+                        self.emit(
+                            DiagBuilder2::error(format!(
+                                "`{}` cannot be connected to port `{}`; only signals are supported",
+                                span.extract(),
+                                port_hir.name.value
+                            ))
+                            .span(span),
+                        );
+                        return Err(());
+                    }
+                };
+                let sig_ty = self.ty(sig)?;
+                if self.map_type(sig_ty)? != llty {
+                    self.emit(
+                        DiagBuilder2::error(format!(
+                            "port `{}` has type {}, but `{}` has type {}",
+                            port_hir.name.value,
+                            ty,
+                            span.extract(),
+                            sig_ty
+                        ))
+                        .span(span),
+                    );
+                    return Err(());
+                }
+                match self.llsig(sig) {
+                    Some(value) => value,
+                    None => {
+                        self.emit(
+                            DiagBuilder2::bug(format!("no signal generated for `{}`", span.extract()))
+                                .span(span),
+                        );
+                        return Err(());
+                    }
+                }
+            }
+            None => {
+                let init = match port_hir.init {
+                    Some(init) => inner.cast_const(inner.const_value(init)?, ty)?,
+                    None => inner.default_value_for_type(ty)?,
+                };
+                let name = match hir.label {
+                    Some(label) => format!("{}_{}", label.value, port_hir.name.value),
+                    None => port_hir.name.value.to_string(),
+                };
+                let inst = llhd::Inst::new(
+                    Some(name),
+                    llhd::SignalInst(llty.clone(), Some(self.map_const(init)?))
+                );
+                ctx.add_inst(inst, llhd::InstPosition::End).into()
+            }
+        };
+        let (input, output) = port_dirs(port_hir.mode);
+        if input {
+            in_tys.push(llty.clone());
+            ins.push(value.clone());
+        }
+        if output {
+            out_tys.push(llty);
+            outs.push(value);
+        }
+    }
+
+    let inst = llhd::Inst::new(
+        hir.label.map(|l| l.value.into()),
+        llhd::InstKind::InstanceInst(llhd::entity_ty(in_tys, out_tys), unit, ins, outs)
+    );
+    ctx.add_inst(inst, llhd::InstPosition::End);
+    Ok(())
 });
 
-impl_codegen!(self, id: ForGenStmtRef, _ctx: &mut llhd::Entity => {
-    unimp!(self, id);
+impl_codegen!(self, id: ForGenStmtRef, ctx: &mut llhd::Entity => {
+    // Generate the body once for every value of the parameter.
+    let hir = self.lazy_hir(id)?;
+    for value in self.for_gen_values(id)? {
+        let env = self.intern_generic_env(GenericEnvData {
+            parent: Some(self.env),
+            values: vec![(id.into(), value)],
+            cfg: None,
+        });
+        self.with_env(env).codegen(hir.stmt.body, ctx)?;
+    }
+    Ok(())
 });
 
-impl_codegen!(self, id: IfGenStmtRef, _ctx: &mut llhd::Entity => {
-    unimp!(self, id);
+impl_codegen!(self, id: IfGenStmtRef, ctx: &mut llhd::Entity => {
+    // Generate the body of the first branch whose condition holds.
+    let hir = self.lazy_hir(id)?;
+    for &(cond, body) in &hir.stmt.branches {
+        match *self.const_value(cond)? {
+            Const::Enum(ref k) if k.decl == BOOLEAN_TYPE.id => {
+                if k.index != 0 {
+                    return self.codegen(body, ctx);
+                }
+            }
+            ref k => {
+                self.emit(
+                    DiagBuilder2::bug(format!("condition evaluated to non-boolean {}", k))
+                        .span(self.span(cond).unwrap()),
+                );
+                return Err(());
+            }
+        }
+    }
+    match hir.stmt.otherwise {
+        Some(body) => self.codegen(body, ctx),
+        None => Ok(()),
+    }
+});
+
+impl_codegen!(self, id: GenBodyRef, ctx: &mut llhd::Entity => {
+    let hir = self.lazy_hir(id)?;
+    for &decl_id in &hir.decls {
+        self.codegen(decl_id, ctx)?;
+    }
+    for &stmt_id in &hir.stmts {
+        self.codegen(stmt_id, ctx)?;
+    }
+    Ok(())
 });
 
 impl_codegen!(self, id: CaseGenStmtRef, _ctx: &mut llhd::Entity => {
     unimp!(self, id);
 });
 
-impl_codegen!(self, id: SeqStmtRef, _ctx: &'a mut InstBuilder<'a> => {
-    unimp!(self, id);
+impl_codegen!(self, id: DeclInProcRef, ctx: &'a mut ProcessBuilder<'a> => {
+    match id {
+        DeclInProcRef::Type(_id)      => Ok(()),
+        DeclInProcRef::Subtype(_id)   => Ok(()),
+        DeclInProcRef::Const(id)      => {
+            // Constants are substituted where they are used.
+            if let Some(init_id) = self.lazy_hir(id)?.decl.init {
+                self.const_value(init_id)?;
+            }
+            Ok(())
+        }
+        DeclInProcRef::Var(id)        => self.codegen(id, ctx),
+        DeclInProcRef::Alias(_id)     => Ok(()),
+        DeclInProcRef::Attr(_id)      => Ok(()),
+        DeclInProcRef::AttrSpec(_id)  => Ok(()),
+        DeclInProcRef::GroupTemp(_id) => Ok(()),
+        DeclInProcRef::Group(_id)     => Ok(()),
+        _ => unimp!(self, id),
+    }
+});
+
+impl_codegen!(self, id: VarDeclRef, ctx: &'a mut ProcessBuilder<'a> => {
+    let hir = self.lazy_hir(id)?;
+    let ty = self.lazy_typeval(id)?;
+    let init = if let Some(init_id) = hir.decl.init {
+        self.cast_const(self.const_value(init_id)?, ty)?
+    } else {
+        self.default_value_for_type(&ty)?
+    };
+    let llty = self.map_type(ty)?;
+    let var = ctx.inst.add_inst(llhd::Inst::new(
+        Some(hir.name.value.into()),
+        llhd::VariableInst(llty.clone()),
+    ));
+    ctx.inst.add_inst(llhd::Inst::new(
+        None,
+        llhd::StoreInst(llty, var.into(), self.map_const(init)?),
+    ));
+    ctx.vars.insert(id, var.into());
+    Ok(())
 });
 
+impl_codegen!(self, id: SeqStmtRef, ctx: &'a mut ProcessBuilder<'a> => {
+    match id {
+        SeqStmtRef::Wait(id) => self.codegen(id, ctx),
+        SeqStmtRef::SigAssign(id) => self.codegen(id, ctx),
+        SeqStmtRef::VarAssign(id) => self.codegen(id, ctx),
+        SeqStmtRef::If(id) => self.codegen(id, ctx),
+        SeqStmtRef::Loop(id) => self.codegen(id, ctx),
+        SeqStmtRef::Null(_) => Ok(()),
+        _ => unimp!(self, id),
+    }
+});
+
+impl_codegen!(self, id: WaitStmtRef, ctx: &'a mut ProcessBuilder<'a> => {
+    let hir: &hir::Stmt<hir::WaitStmt> = self.lazy_hir(id)?;
+
+    // Without a sensitivity clause, the process is sensitive to the signals
+    // read by the condition. See IEEE 1076-2008 section 10.2.
+    let sens = match (&hir.stmt.sens, hir.stmt.cond) {
+        (&Some(ref sens), _) => sens.value.iter().map(|s| s.value).collect(),
+        (&None, Some(cond)) => self.expr_signals(cond)?,
+        (&None, None) => vec![],
+    };
+    let time = match hir.stmt.timeout {
+        Some(timeout) => Some(self.map_time_const(self.const_value(timeout)?)?),
+        None => None,
+    };
+
+    // A plain `wait;` suspends the process forever. Any statements that
+    // follow it are unreachable and go into a block of their own.
+    if sens.is_empty() && time.is_none() {
+        ctx.inst.add_inst(llhd::Inst::new(None, llhd::HaltInst));
+        let dead_blk = ctx.inst.add_block(llhd::Block::new(None));
+        ctx.inst.set_block(dead_blk);
+        return Ok(());
+    }
+
+    // TODO: Keep track of the time waited such that a timeout can be combined
+    // with a condition.
+    if hir.stmt.cond.is_some() && time.is_some() {
+        unimp!(self, id);
+    }
+    let signals = sens
+        .into_iter()
+        .map(|sig| self.process_input(ctx, sig, hir.span))
+        .collect::<Result<Vec<_>>>()?;
+
+    // A condition is checked every time the process resumes. The process
+    // keeps waiting as long as it does not hold.
+    let wait_blk = if hir.stmt.cond.is_some() {
+        let blk = ctx.inst.add_block(llhd::Block::new(None));
+        ctx.inst.add_inst(llhd::Inst::new(None, llhd::BranchInst(llhd::BranchKind::Uncond(blk))));
+        ctx.inst.set_block(blk);
+        Some(blk)
+    } else {
+        None
+    };
+    let resume_blk = ctx.inst.add_block(llhd::Block::new(None));
+    let wait = ctx.inst.add_inst(llhd::Inst::new(
+        None,
+        llhd::WaitInst(resume_blk, time, signals),
+    ));
+    ctx.waits.push(wait);
+    ctx.inst.set_block(resume_blk);
+    if let (Some(cond), Some(wait_blk)) = (hir.stmt.cond, wait_blk) {
+        let cond = self.codegen_expr(cond, self.intern_ty(BOOLEAN_TYPE.named_ty()), ctx)?;
+        let cont_blk = ctx.inst.add_block(llhd::Block::new(None));
+        ctx.inst.add_inst(llhd::Inst::new(
+            None,
+            llhd::BranchInst(llhd::BranchKind::Cond(cond, cont_blk, wait_blk)),
+        ));
+        ctx.inst.set_block(cont_blk);
+    }
+    Ok(())
+});
+
+impl_codegen!(self, id: SigAssignStmtRef, ctx: &'a mut ProcessBuilder<'a> => {
+    let hir = self.hir(id)?;
+    let sig = match hir.target {
+        hir::SigAssignTarget::Name(sig) => sig,
+        hir::SigAssignTarget::Aggregate => unimp!(self, id),
+    };
+    let ty = self.ty(sig)?;
+    let output = self.process_output(ctx, sig, hir.target_span)?;
+    match hir.kind {
+        // TODO: Distinguish the transport and inertial delay mechanisms once
+        // LLHD can express pulse rejection.
+        hir::SigAssignKind::SimpleWave(_, ref wave) => {
+            for elem in wave {
+                let value = match elem.value {
+                    Some(value) => self.codegen_expr(value, ty, ctx)?,
+                    None => unimp!(self, id),
+                };
+                // Without an explicit delay, the new value takes effect in the
+                // next delta cycle.
+                let delay = match elem.after {
+                    Some(after) => self.map_time_const(self.const_value(after)?)?,
+                    None => llhd::const_time(BigRational::from_integer(0.into()), 1, 0).into(),
+                };
+                ctx.inst.add_inst(llhd::Inst::new(
+                    None,
+                    llhd::DriveInst(output.clone(), value, Some(delay)),
+                ));
+            }
+            Ok(())
+        }
+        _ => unimp!(self, id),
+    }
+});
+
+impl_codegen!(self, id: VarAssignStmtRef, ctx: &'a mut ProcessBuilder<'a> => {
+    let hir = &self.lazy_hir(id)?.stmt;
+    let var = match hir.target.value {
+        hir::Target::Name(expr) => match self.lazy_hir(expr)?.data {
+            hir::ExprData::VarName(var) => var,
+            _ => unimp!(self, id),
+        },
+        hir::Target::Aggregate(_) => unimp!(self, id),
+    };
+    let ptr = match ctx.vars.get(&var) {
+        Some(ptr) => ptr.clone(),
+        None => unimp!(self, id),
+    };
+    let ty = self.lazy_typeval(var)?;
+    match hir.kind {
+        hir::VarAssignKind::Simple(expr) => {
+            let value = self.codegen_expr(expr, ty, ctx)?;
+            ctx.inst.add_inst(llhd::Inst::new(
+                None,
+                llhd::StoreInst(self.map_type(ty)?, ptr, value),
+            ));
+            Ok(())
+        }
+        _ => unimp!(self, id),
+    }
+});
+
+impl_codegen!(self, id: IfStmtRef, ctx: &'a mut ProcessBuilder<'a> => {
+    let hir = &self.lazy_hir(id)?.stmt;
+    let boolean = self.intern_ty(BOOLEAN_TYPE.named_ty());
+    let exit_blk = ctx.inst.add_block(llhd::Block::new(None));
+    for &(cond, ref stmts) in &hir.branches {
+        let cond = self.codegen_expr(cond, boolean, ctx)?;
+        let else_blk = ctx.inst.add_block(llhd::Block::new(None));
+        let then_blk = ctx.inst.add_block(llhd::Block::new(None));
+        ctx.inst.add_inst(llhd::Inst::new(
+            None,
+            llhd::BranchInst(llhd::BranchKind::Cond(cond, then_blk, else_blk)),
+        ));
+        ctx.inst.set_block(then_blk);
+        for &stmt in stmts {
+            self.codegen(stmt, ctx)?;
+        }
+        ctx.inst.add_inst(llhd::Inst::new(None, llhd::BranchInst(llhd::BranchKind::Uncond(exit_blk))));
+        ctx.inst.set_block(else_blk);
+    }
+    if let Some(ref stmts) = hir.otherwise {
+        for &stmt in stmts {
+            self.codegen(stmt, ctx)?;
+        }
+    }
+    ctx.inst.add_inst(llhd::Inst::new(None, llhd::BranchInst(llhd::BranchKind::Uncond(exit_blk))));
+    ctx.inst.set_block(exit_blk);
+    Ok(())
+});
+
+impl_codegen!(self, id: LoopStmtRef, ctx: &'a mut ProcessBuilder<'a> => {
+    let hir = &self.lazy_hir(id)?.stmt;
+    let cond = match hir.scheme {
+        hir::LoopScheme::Loop => None,
+        hir::LoopScheme::While(cond) => Some(cond),
+        hir::LoopScheme::For(..) => unimp!(self, id),
+    };
+    let exit_blk = ctx.inst.add_block(llhd::Block::new(None));
+    let head_blk = ctx.inst.add_block(llhd::Block::new(None));
+    ctx.inst.add_inst(llhd::Inst::new(None, llhd::BranchInst(llhd::BranchKind::Uncond(head_blk))));
+    ctx.inst.set_block(head_blk);
+    if let Some(cond) = cond {
+        let cond = self.codegen_expr(cond, self.intern_ty(BOOLEAN_TYPE.named_ty()), ctx)?;
+        let body_blk = ctx.inst.add_block(llhd::Block::new(None));
+        ctx.inst.add_inst(llhd::Inst::new(
+            None,
+            llhd::BranchInst(llhd::BranchKind::Cond(cond, body_blk, exit_blk)),
+        ));
+        ctx.inst.set_block(body_blk);
+    }
+    for &stmt in &hir.stmts {
+        self.codegen(stmt, ctx)?;
+    }
+    ctx.inst.add_inst(llhd::Inst::new(None, llhd::BranchInst(llhd::BranchKind::Uncond(head_blk))));
+    ctx.inst.set_block(exit_blk);
+    Ok(())
+});
+
+impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
+    /// Generate the code for an expression within a process.
+    ///
+    /// The expression is expected to be of type `ty`, which is used to pick
+    /// overloaded enum literals and to give integer literals their width.
+    /// Operators are assumed to be the builtin ones.
+    pub fn codegen_expr(
+        &self,
+        id: ExprRef,
+        ty: &'ctx Ty,
+        ctx: &mut ProcessBuilder,
+    ) -> Result<llhd::ValueRef> {
+        let hir = self.lazy_hir(id)?;
+        Ok(match hir.data {
+            hir::ExprData::SignalName(sig) | hir::ExprData::Name(Def::Signal(sig), _) => {
+                let input = self.process_input(ctx, sig, hir.span)?;
+                let llty = self.map_type(self.ty(sig)?)?;
+                ctx.inst
+                    .add_inst(llhd::Inst::new(None, llhd::ProbeInst(llty, input)))
+                    .into()
+            }
+            hir::ExprData::VarName(var) => {
+                let ptr = match ctx.vars.get(&var) {
+                    Some(ptr) => ptr.clone(),
+                    None => return self.unimp_expr(hir),
+                };
+                let llty = self.map_type(self.lazy_typeval(var)?)?;
+                ctx.inst
+                    .add_inst(llhd::Inst::new(None, llhd::LoadInst(llty, ptr)))
+                    .into()
+            }
+            hir::ExprData::EnumName(ref defs) => {
+                let decl = match *self.deref_named_type(ty)? {
+                    Ty::Enum(ref ty) => Some(ty.decl),
+                    _ => None,
+                };
+                let def = match defs.iter().find(|d| Some(d.value.0) == decl) {
+                    Some(def) => def.value,
+                    None if defs.len() == 1 => defs[0].value,
+                    None => {
+                        self.emit(
+                            DiagBuilder2::error(format!("`{}` is ambiguous", hir.span.extract()))
+                                .span(hir.span),
+                        );
+                        return Err(());
+                    }
+                };
+                self.map_const(self.intern_const(ConstEnum::new(def.0, def.1)))?
+            }
+            hir::ExprData::Qualified(ref tm, expr) => {
+                let ty = self.intern_ty(Ty::Named(tm.span.into(), tm.value));
+                self.codegen_expr(expr, ty, ctx)?
+            }
+            hir::ExprData::Attr(prefix, _) => {
+                // The `'event` attribute compares the signal against its value
+                // before the process last suspended.
+                let sig = match self.lazy_hir(prefix)?.data {
+                    hir::ExprData::SignalName(sig) => sig,
+                    _ => return self.unimp_expr(hir),
+                };
+                let input = self.process_input(ctx, sig, hir.span)?;
+                let llty = self.map_type(self.ty(sig)?)?;
+                let prev = ctx.event_var(sig, input.clone(), llty.clone());
+                let value = ctx
+                    .inst
+                    .add_inst(llhd::Inst::new(None, llhd::ProbeInst(llty.clone(), input)));
+                let old = ctx
+                    .inst
+                    .add_inst(llhd::Inst::new(None, llhd::LoadInst(llty.clone(), prev)));
+                ctx.inst
+                    .add_inst(llhd::Inst::new(
+                        None,
+                        llhd::CompareInst(llhd::CompareOp::Neq, llty, value.into(), old.into()),
+                    ))
+                    .into()
+            }
+            hir::ExprData::Unary(op, _, arg) => {
+                let value = self.codegen_expr(arg, ty, ctx)?;
+                let llty = self.map_type(ty)?;
+                match op.value {
+                    UnaryOp::Not => ctx.inst.add_inst(llhd::Inst::new(
+                        None,
+                        llhd::UnaryInst(llhd::UnaryOp::Not, llty, value),
+                    )),
+                    UnaryOp::Pos => return Ok(value),
+                    UnaryOp::Neg => ctx.inst.add_inst(llhd::Inst::new(
+                        None,
+                        llhd::BinaryInst(
+                            llhd::BinaryOp::Sub,
+                            llty.clone(),
+                            llhd::const_zero(&llty),
+                            value,
+                        ),
+                    )),
+                    _ => return self.unimp_expr(hir),
+                }
+                .into()
+            }
+            hir::ExprData::Binary(op, _, lhs, rhs) => match op.value {
+                BinaryOp::Logical(op) => {
+                    let llty = self.map_type(ty)?;
+                    let lhs = self.codegen_expr(lhs, ty, ctx)?;
+                    let rhs = self.codegen_expr(rhs, ty, ctx)?;
+                    let (binop, negate) = match op {
+                        LogicalOp::And => (llhd::BinaryOp::And, false),
+                        LogicalOp::Or => (llhd::BinaryOp::Or, false),
+                        LogicalOp::Xor => (llhd::BinaryOp::Xor, false),
+                        LogicalOp::Nand => (llhd::BinaryOp::And, true),
+                        LogicalOp::Nor => (llhd::BinaryOp::Or, true),
+                        LogicalOp::Xnor => (llhd::BinaryOp::Xor, true),
+                    };
+                    let value = ctx.inst.add_inst(llhd::Inst::new(
+                        None,
+                        llhd::BinaryInst(binop, llty.clone(), lhs, rhs),
+                    ));
+                    if negate {
+                        ctx.inst
+                            .add_inst(llhd::Inst::new(
+                                None,
+                                llhd::UnaryInst(llhd::UnaryOp::Not, llty, value.into()),
+                            ))
+                            .into()
+                    } else {
+                        value.into()
+                    }
+                }
+                BinaryOp::Rel(op) => {
+                    let operand_ty = self.operand_type(lhs, rhs)?;
+                    let llty = self.map_type(operand_ty)?;
+                    let signed = match *self.deref_named_type(operand_ty)? {
+                        Ty::Int(_) => true,
+                        _ => false,
+                    };
+                    let lhs = self.codegen_expr(lhs, operand_ty, ctx)?;
+                    let rhs = self.codegen_expr(rhs, operand_ty, ctx)?;
+                    let cmpop = match (op, signed) {
+                        (RelationalOp::Eq, _) => llhd::CompareOp::Eq,
+                        (RelationalOp::Neq, _) => llhd::CompareOp::Neq,
+                        (RelationalOp::Lt, true) => llhd::CompareOp::Slt,
+                        (RelationalOp::Leq, true) => llhd::CompareOp::Sle,
+                        (RelationalOp::Gt, true) => llhd::CompareOp::Sgt,
+                        (RelationalOp::Geq, true) => llhd::CompareOp::Sge,
+                        (RelationalOp::Lt, false) => llhd::CompareOp::Ult,
+                        (RelationalOp::Leq, false) => llhd::CompareOp::Ule,
+                        (RelationalOp::Gt, false) => llhd::CompareOp::Ugt,
+                        (RelationalOp::Geq, false) => llhd::CompareOp::Uge,
+                    };
+                    ctx.inst
+                        .add_inst(llhd::Inst::new(
+                            None,
+                            llhd::CompareInst(cmpop, llty, lhs, rhs),
+                        ))
+                        .into()
+                }
+                BinaryOp::Add
+                | BinaryOp::Sub
+                | BinaryOp::Mul
+                | BinaryOp::Div
+                | BinaryOp::Mod
+                | BinaryOp::Rem => {
+                    let llty = self.map_type(ty)?;
+                    let lhs = self.codegen_expr(lhs, ty, ctx)?;
+                    let rhs = self.codegen_expr(rhs, ty, ctx)?;
+                    let binop = match op.value {
+                        BinaryOp::Add => llhd::BinaryOp::Add,
+                        BinaryOp::Sub => llhd::BinaryOp::Sub,
+                        BinaryOp::Mul => llhd::BinaryOp::Mul,
+                        BinaryOp::Div => llhd::BinaryOp::Div,
+                        BinaryOp::Mod => llhd::BinaryOp::Mod,
+                        _ => llhd::BinaryOp::Rem,
+                    };
+                    ctx.inst
+                        .add_inst(llhd::Inst::new(
+                            None,
+                            llhd::BinaryInst(binop, llty, lhs, rhs),
+                        ))
+                        .into()
+                }
+                _ => return self.unimp_expr(hir),
+            },
+            // Everything else must be a constant.
+            _ => self.map_const(self.cast_const(self.const_value(id)?, ty)?)?,
+        })
+    }
+
+    /// Determine the type of the operands of a relational operator.
+    ///
+    /// The operands of the builtin operators have the same type, which is
+    /// taken from whichever operand determines it without a type context.
+    fn operand_type(&self, lhs: ExprRef, rhs: ExprRef) -> Result<&'ctx Ty> {
+        if let Some(ty) = self.self_determined_type(lhs)? {
+            return Ok(ty);
+        }
+        if let Some(ty) = self.self_determined_type(rhs)? {
+            return Ok(ty);
+        }
+        Ok(self.intern_ty(INTEGER_TYPE.named_ty()))
+    }
+
+    /// Determine the type of an expression that does not depend on context.
+    ///
+    /// Returns `None` for literals and operators whose type can only be
+    /// inferred from their context.
+    fn self_determined_type(&self, id: ExprRef) -> Result<Option<&'ctx Ty>> {
+        let hir = self.lazy_hir(id)?;
+        Ok(match hir.data {
+            hir::ExprData::SignalName(sig) | hir::ExprData::Name(Def::Signal(sig), _) => {
+                Some(self.ty(sig)?)
+            }
+            hir::ExprData::VarName(id) => Some(self.lazy_typeval(id)?),
+            hir::ExprData::ConstName(id) => Some(self.lazy_typeval(id)?),
+            hir::ExprData::GenericName(id) => Some(self.lazy_typeval(id)?),
+            hir::ExprData::Qualified(ref tm, _) => {
+                Some(self.intern_ty(Ty::Named(tm.span.into(), tm.value)))
+            }
+            hir::ExprData::EnumName(ref defs) if defs.len() == 1 => {
+                Some(self.intern_ty(EnumTy::new(defs[0].value.0)))
+            }
+            hir::ExprData::Unary(op, _, arg) => match op.value {
+                UnaryOp::Not | UnaryOp::Pos | UnaryOp::Neg => self.self_determined_type(arg)?,
+                _ => None,
+            },
+            hir::ExprData::Binary(op, _, lhs, rhs) => match op.value {
+                BinaryOp::Logical(_)
+                | BinaryOp::Add
+                | BinaryOp::Sub
+                | BinaryOp::Mul
+                | BinaryOp::Div
+                | BinaryOp::Mod
+                | BinaryOp::Rem => match self.self_determined_type(lhs)? {
+                    Some(ty) => Some(ty),
+                    None => self.self_determined_type(rhs)?,
+                },
+                BinaryOp::Rel(_) => Some(self.intern_ty(BOOLEAN_TYPE.named_ty())),
+                _ => None,
+            },
+            _ => None,
+        })
+    }
+
+    /// Report an expression that cannot be generated yet.
+    fn unimp_expr<T>(&self, hir: &hir::Expr) -> Result<T> {
+        self.emit(
+            DiagBuilder2::bug(format!(
+                "code generation for expression `{}` not implemented",
+                hir.span.extract()
+            ))
+            .span(hir.span),
+        );
+        Err(())
+    }
+}
+
 impl_codegen!(self, id: SubprogDeclRef, _ctx: &mut () => {
     unimp!(self, id);
 });
@@ -331,8 +1220,9 @@ impl_codegen!(self, id: PkgInstRef, _ctx: &mut () => {
     unimp!(self, id);
 });
 
-impl_codegen!(self, id: CompDeclRef, _ctx: &mut () => {
-    unimp!(self, id);
+impl_codegen!(self, _id: CompDeclRef, _ctx: &mut () => {
+    // Components are bound to entities where they are instantiated.
+    Ok(())
 });
 
 /// An helper to build sequences of instructions.
@@ -367,3 +1257,96 @@ impl<'ctx> InstBuilder<'ctx> {
         self.block = block
     }
 }
+
+/// A helper to generate the body of a process.
+pub struct ProcessBuilder<'ctx> {
+    /// The builder for the instructions of the process.
+    pub inst: InstBuilder<'ctx>,
+    /// The block that is executed once when the process starts.
+    pub entry: llhd::BlockRef,
+    /// The process inputs through which signals are read.
+    pub inputs: HashMap<SignalRef, llhd::ValueRef>,
+    /// The process outputs through which signals are driven.
+    pub outputs: HashMap<SignalRef, llhd::ValueRef>,
+    /// The storage allocated for each variable.
+    pub vars: HashMap<VarDeclRef, llhd::ValueRef>,
+    /// The wait instructions emitted so far.
+    pub waits: Vec<llhd::InstRef>,
+    /// The signals whose `'event` attribute is used, together with the input
+    /// they are read from and the variable holding their previous value.
+    events: Vec<(SignalRef, llhd::ValueRef, llhd::ValueRef, llhd::Type)>,
+}
+
+impl<'ctx> ProcessBuilder<'ctx> {
+    /// Create a new process builder that starts in the entry block.
+    pub fn new(
+        body: &'ctx mut llhd::SeqBody,
+        entry: llhd::BlockRef,
+        inputs: HashMap<SignalRef, llhd::ValueRef>,
+        outputs: HashMap<SignalRef, llhd::ValueRef>,
+    ) -> ProcessBuilder<'ctx> {
+        ProcessBuilder {
+            inst: InstBuilder::new(body, entry),
+            entry: entry,
+            inputs: inputs,
+            outputs: outputs,
+            vars: HashMap::new(),
+            waits: vec![],
+            events: vec![],
+        }
+    }
+
+    /// Get the variable that holds the value a signal had before the process
+    /// last suspended.
+    ///
+    /// The variable is created in the entry block upon first use.
+    pub fn event_var(
+        &mut self,
+        sig: SignalRef,
+        input: llhd::ValueRef,
+        ty: llhd::Type,
+    ) -> llhd::ValueRef {
+        if let Some(&(_, _, ref var, _)) = self.events.iter().find(|e| e.0 == sig) {
+            return var.clone();
+        }
+        let pos = llhd::InstPosition::BlockEnd(self.entry);
+        let body = &mut self.inst.body;
+        let var = body.add_inst(llhd::Inst::new(None, llhd::VariableInst(ty.clone())), pos);
+        let value = body.add_inst(
+            llhd::Inst::new(None, llhd::ProbeInst(ty.clone(), input.clone())),
+            pos,
+        );
+        body.add_inst(
+            llhd::Inst::new(None, llhd::StoreInst(ty.clone(), var.into(), value.into())),
+            pos,
+        );
+        self.events.push((sig, input, var.into(), ty));
+        var.into()
+    }
+
+    /// Finish the process.
+    ///
+    /// Records the value of the signals whose `'event` attribute is used
+    /// before every wait, and enters the body of the process after the entry
+    /// block.
+    pub fn finish(self, body_blk: llhd::BlockRef) {
+        let body = self.inst.body;
+        for &wait in &self.waits {
+            for &(_, ref input, ref var, ref ty) in &self.events {
+                let pos = llhd::InstPosition::Before(wait);
+                let value = body.add_inst(
+                    llhd::Inst::new(None, llhd::ProbeInst(ty.clone(), input.clone())),
+                    pos,
+                );
+                body.add_inst(
+                    llhd::Inst::new(None, llhd::StoreInst(ty.clone(), var.clone(), value.into())),
+                    pos,
+                );
+            }
+        }
+        body.add_inst(
+            llhd::Inst::new(None, llhd::BranchInst(llhd::BranchKind::Uncond(body_blk))),
+            llhd::InstPosition::BlockEnd(self.entry),
+        );
+    }
+}
@@ -5,6 +5,7 @@
 use crate::hir;
 use crate::score::*;
 use crate::syntax::ast;
+use moore_common::codes;
 use moore_common::errors::*;
 use moore_common::score::Result;
 use moore_common::source::*;
@@ -63,8 +64,8 @@ impl<'sbc, 'lazy, 'sb, 'ast, 'ctx> DefsContext<'sbc, 'lazy, 'sb, 'ast, 'ctx> {
             debugln!("[SB][VHDL][SCOPE] declaring `{}` as {:?}", name.value, def);
         }
         match def {
-            // Handle overloadable cases.
-            Def::Enum(_) => {
+            // Handle overloadable cases. See IEEE 1076-2008 section 4.5.1.
+            Def::Enum(_) | Def::Subprog(_) | Def::SubprogInst(_) => {
                 self.defs
                     .entry(name.value)
                     .or_insert_with(|| Vec::new())
@@ -79,6 +80,7 @@ impl<'sbc, 'lazy, 'sb, 'ast, 'ctx> DefsContext<'sbc, 'lazy, 'sb, 'ast, 'ctx> {
                 if let Some(existing) = ins {
                     self.emit(
                         DiagBuilder2::error(format!("`{}` has already been declared", name.value))
+                            .code(codes::VH0003)
                             .span(name.span)
                             .add_note("previous declaration was here:")
                             .span(existing.last().unwrap().span),
@@ -111,6 +113,15 @@ impl<'sbc, 'lazy, 'sb, 'ast, 'ctx> DefsContext<'sbc, 'lazy, 'sb, 'ast, 'ctx> {
     /// Handle type declarations.
     pub fn declare_type(&mut self, id: TypeDeclRef) {
         let ast = self.ctx.ast(id).1;
+        // A protected type body completes an earlier protected type
+        // declaration rather than declaring a new name.
+        if let Some(Spanned {
+            value: ast::ProtectedBody(..),
+            ..
+        }) = ast.data
+        {
+            return;
+        }
         self.declare(ast.name.map_into(), Def::Type(id));
         // This is a rather hacky way of declaring the variant names for enum
         // literals, but it does not require the HIR to be constructed, which is
@@ -364,15 +375,61 @@ impl<'sbc, 'lazy, 'sb, 'ast, 'ctx> DefsContext<'sbc, 'lazy, 'sb, 'ast, 'ctx> {
     ///
     /// These are mainly subprogram parameters and entity ports.
     pub fn declare_intf_objs(&mut self, ids: &[IntfObjRef]) {
-        if !ids.is_empty() {
-            unimplemented!();
+        for &id in ids {
+            match id {
+                IntfObjRef::Signal(id) => {
+                    let hir = match self.ctx.hir(id) {
+                        Ok(h) => h,
+                        Err(()) => {
+                            self.failed = true;
+                            continue;
+                        }
+                    };
+                    self.declare(hir.name.map_into(), Def::Signal(id.into()))
+                }
+                _ => {
+                    self.emit(DiagBuilder2::bug(format!(
+                        "declaration of interface object {:?} not implemented",
+                        id
+                    )));
+                }
+            }
         }
     }
 
     /// Handle generics.
     pub fn declare_generics(&mut self, ids: &[GenericRef]) {
-        if !ids.is_empty() {
-            unimplemented!();
+        for &id in ids {
+            match id {
+                GenericRef::Const(id) => {
+                    let hir = match self.ctx.lazy_hir(id) {
+                        Ok(h) => h,
+                        Err(()) => {
+                            self.failed = true;
+                            continue;
+                        }
+                    };
+                    self.declare(hir.name.map_into(), Def::Generic(id))
+                }
+                _ => {
+                    self.emit(DiagBuilder2::bug(format!(
+                        "declaration of generic {:?} not implemented",
+                        id
+                    )));
+                }
+            }
         }
     }
+
+    /// Handle the parameter of a for-generate statement.
+    pub fn declare_gen_param(&mut self, id: ForGenStmtRef) {
+        let hir = match self.ctx.lazy_hir(id) {
+            Ok(h) => h,
+            Err(()) => {
+                self.failed = true;
+                return;
+            }
+        };
+        self.declare(hir.stmt.param.map_into(), Def::GenParam(id))
+    }
 }
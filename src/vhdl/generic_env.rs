@@ -0,0 +1,59 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! A generic environment generated by an instantiation or a generate statement.
+//!
+//! The types and constant values of nodes may depend on the values assigned to
+//! the generics of an entity, or on the parameter of a for-generate statement.
+//! The scoreboard therefore keys these results by the node and the environment
+//! it is evaluated in.
+
+use crate::hir;
+use crate::konst::Const;
+use moore_common::NodeId;
+
+/// A generic environment.
+///
+/// This is merely an handle that is cheap to copy and pass around. Use the
+/// [`ScoreContext`] to resolve this to the actual [`GenericEnvData`]. The
+/// default environment assigns no values, such that generics assume their
+/// default value.
+///
+/// [`ScoreContext`]: crate::score::ScoreContext
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct GenericEnv(pub(crate) u32);
+
+impl std::fmt::Display for GenericEnv {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "g{}", self.0)
+    }
+}
+
+impl std::fmt::Debug for GenericEnv {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+/// The values assigned by a generic environment.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GenericEnvData<'ctx> {
+    /// The environment this one is nested in. Generate statements extend the
+    /// environment of their surroundings, whereas instantiations start afresh.
+    pub parent: Option<GenericEnv>,
+    /// The values assigned to generics and generate parameters.
+    pub values: Vec<(NodeId, &'ctx Const)>,
+    /// The block configuration that applies to the architecture elaborated in
+    /// this environment. Generate statements inherit the configuration of
+    /// their surroundings.
+    pub cfg: Option<&'ctx hir::BlockCfg>,
+}
+
+impl<'ctx> GenericEnvData<'ctx> {
+    /// Find the value assigned to a node, without looking at the parent.
+    pub fn find(&self, id: NodeId) -> Option<&'ctx Const> {
+        self.values
+            .iter()
+            .find(|&&(node, _)| node == id)
+            .map(|&(_, value)| value)
+    }
+}
@@ -22,7 +22,11 @@ fn apply_use_clause(clause: &ast::CompoundName, context: AllocContext) -> Result
     let mut lookup = context.resolve(pn.value, true);
     let mut lookup_name = pn;
     if lookup.is_empty() {
-        context.emit(DiagBuilder2::error(format!("`{}` is unknown", pn.value)).span(pn.span));
+        context.emit(
+            DiagBuilder2::error(format!("`{}` is unknown", pn.value))
+                .code(codes::VH0001)
+                .span(pn.span),
+        );
         return Err(());
     }
     // debugln!("`{}` resolved to {:?}", pn.value, lookup);
@@ -32,6 +36,7 @@ fn apply_use_clause(clause: &ast::CompoundName, context: AllocContext) -> Result
         // Ensure the name is unique.
         if lookup.len() > 1 {
             let mut d = DiagBuilder2::error(format!("`{}` is ambiguous", lookup_name.value))
+                .code(codes::VH0002)
                 .span(lookup_name.span)
                 .add_note("Refers to the following:");
             for l in lookup {
@@ -62,6 +67,7 @@ fn apply_use_clause(clause: &ast::CompoundName, context: AllocContext) -> Result
                 if lookup.is_empty() {
                     context.emit(
                         DiagBuilder2::error(format!("`{}` is unknown", lookup_name.value))
+                            .code(codes::VH0001)
                             .span(lookup_name.span),
                     );
                     return Err(());
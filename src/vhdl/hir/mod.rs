@@ -2,7 +2,7 @@
 
 //! The High-level Intermediate Representation of a VHDL design.
 
-use num::BigInt;
+use num::{BigInt, BigRational};
 
 use crate::common::name::*;
 use crate::common::source::*;
@@ -45,7 +45,11 @@ make_arenas!(
         lib: Lib,
         entity: Entity,
         arch: Arch,
+        component: Component,
+        cfg: Cfg,
+        cfg_spec: CfgSpec,
         intf_sig: IntfSignal,
+        intf_const: IntfConst,
         subtype_ind: SubtypeInd,
         package: Package,
         package_body: PackageBody,
@@ -60,6 +64,10 @@ make_arenas!(
         file_decl: Decl<FileDecl>,
         type_decl2: Decl<Option<TypeData>>,
         process_stmt: ProcessStmt,
+        comp_inst_stmt: Stmt<CompInstStmt>,
+        for_gen_stmt: Stmt<ForGenStmt>,
+        if_gen_stmt: Stmt<IfGenStmt>,
+        gen_body: GenBody,
         sig_assign_stmt: SigAssignStmt,
         array_type_index: Spanned<ArrayTypeIndex>,
         subprog: Subprog,
@@ -134,6 +142,104 @@ pub struct Arch {
     pub stmts: Vec<ConcStmtRef>,
 }
 
+/// A component declaration.
+///
+/// See IEEE 1076-2008 section 6.8.
+#[derive(Debug)]
+pub struct Component {
+    /// The scope within which the component is declared.
+    pub parent: ScopeRef,
+    /// The component name.
+    pub name: Spanned<Name>,
+    /// The list of generics that the component declares.
+    pub generics: Vec<GenericRef>,
+    /// The list of ports that the component declares.
+    pub ports: Vec<IntfSignalRef>,
+}
+
+/// A configuration declaration.
+///
+/// See IEEE 1076-2008 section 3.4.
+#[derive(Debug)]
+pub struct Cfg {
+    /// The configuration name.
+    pub name: Spanned<Name>,
+    /// The configured entity.
+    pub entity: Spanned<EntityRef>,
+    /// The configuration of the architecture of the entity.
+    pub block: BlockCfg,
+}
+
+/// A block configuration of an architecture.
+///
+/// See IEEE 1076-2008 section 3.4.2.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BlockCfg {
+    /// The configured architecture.
+    pub arch: Spanned<ArchRef>,
+    /// The configurations of the component instances in the architecture.
+    pub comps: Vec<CompCfg>,
+}
+
+/// A component configuration.
+///
+/// See IEEE 1076-2008 section 3.4.3.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CompCfg {
+    /// The component instances the configuration applies to.
+    pub spec: InstSpec,
+    /// The entity the instances are bound to, if one is given.
+    pub binding: Option<EntityAspect>,
+    /// The configuration of the bound architecture.
+    pub block: Option<BlockCfg>,
+}
+
+/// A configuration specification.
+///
+/// See IEEE 1076-2008 section 7.3.
+#[derive(Debug)]
+pub struct CfgSpec {
+    /// The component instances the specification applies to.
+    pub spec: InstSpec,
+    /// The entity the instances are bound to.
+    pub binding: EntityAspect,
+}
+
+/// A set of component instances, as named in a configuration.
+///
+/// See IEEE 1076-2008 section 7.3.2.
+#[derive(Debug, PartialEq, Eq)]
+pub struct InstSpec {
+    /// The labels of the instances.
+    pub list: InstList,
+    /// The instantiated component.
+    pub comp: Spanned<CompDeclRef>,
+}
+
+/// The instances a configuration applies to.
+#[derive(Debug, PartialEq, Eq)]
+pub enum InstList {
+    /// The instances with the given labels.
+    Labels(Vec<Spanned<Name>>),
+    /// All instances not covered by another configuration.
+    Others,
+    /// All instances.
+    All,
+}
+
+/// The entity aspect of a binding indication.
+///
+/// See IEEE 1076-2008 section 7.3.2.2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityAspect {
+    /// An entity and an optional architecture name.
+    Entity(Spanned<EntityRef>, Option<Spanned<Name>>),
+    /// A configuration.
+    Cfg(Spanned<CfgRef>),
+    /// The instances are left unbound.
+    Open,
+}
+
 #[derive(Debug)]
 pub struct IntfSignal {
     /// The name of this signal.
@@ -148,6 +254,16 @@ pub struct IntfSignal {
     pub init: Option<ExprRef>,
 }
 
+#[derive(Debug)]
+pub struct IntfConst {
+    /// The name of this constant.
+    pub name: Spanned<Name>,
+    /// The type of this constant.
+    pub ty: SubtypeIndRef,
+    /// The expression determining the default value of this constant.
+    pub init: Option<ExprRef>,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum IntfSignalMode {
     In,
@@ -388,6 +504,10 @@ pub enum TypeData {
     File(Spanned<TypeMarkRef>),
     /// A record type.
     Record(Vec<(Spanned<Name>, SubtypeIndRef)>),
+    /// A protected type. Its declarative items are not lowered yet.
+    Protected,
+    /// A protected type body. Its declarative items are not lowered yet.
+    ProtectedBody,
 }
 
 /// An enumeration literal as listed in a type declaration.
@@ -448,6 +568,10 @@ pub enum ExprData {
     Name(Def, Span),
     /// A resolved constant name.
     ConstName(ConstDeclRef),
+    /// A resolved generic name.
+    GenericName(IntfConstRef),
+    /// A resolved generate parameter name.
+    GenParamName(ForGenStmtRef),
     /// A resolved signal name.
     SignalName(SignalRef),
     /// A resolved variable name.
@@ -469,6 +593,9 @@ pub enum ExprData {
     IntegerLiteral(ConstInt),
     /// A float literal.
     FloatLiteral(ConstFloat),
+    /// A physical literal, e.g. `10 ns`. Consists of the abstract literal and
+    /// the unit it is scaled by.
+    PhysicalLiteral(BigRational, Spanned<UnitRef>),
     /// A unary operator expression.
     Unary(Spanned<UnaryOp>, Vec<Spanned<Def>>, ExprRef),
     /// A binary operator expression.
@@ -589,7 +716,70 @@ pub enum ProcessSensitivity {
     /// The `all` sensitivity list.
     All,
     /// Explicitly enumerated signals.
-    List(Vec<Def>),
+    List(SensitivityList),
+}
+
+/// A component instantiation statement.
+///
+/// See IEEE 1076-2008 section 11.7.
+#[derive(Debug)]
+pub struct CompInstStmt {
+    /// The instantiated unit.
+    pub unit: InstUnit,
+    /// The actuals associated with the generics of the unit.
+    pub generics: Vec<(IntfConstRef, ExprRef)>,
+    /// The actuals associated with the ports of the unit.
+    pub ports: Vec<(IntfSignalRef, ExprRef)>,
+}
+
+/// The unit instantiated by a component instantiation statement.
+#[derive(Debug, Clone, Copy)]
+pub enum InstUnit {
+    /// A component, which is bound to an entity during elaboration.
+    Comp(Spanned<CompDeclRef>),
+    /// An entity and an optional architecture name.
+    Entity(Spanned<EntityRef>, Option<Spanned<Name>>),
+    /// A configuration.
+    Cfg(Spanned<CfgRef>),
+}
+
+/// A for-generate statement.
+///
+/// See IEEE 1076-2008 section 11.8.
+#[derive(Debug)]
+pub struct ForGenStmt {
+    /// The name of the generate parameter.
+    pub param: Spanned<Name>,
+    /// The range of values the parameter assumes.
+    pub range: Spanned<DiscreteRange>,
+    /// The body generated for every value of the parameter.
+    pub body: GenBodyRef,
+}
+
+/// An if-generate statement.
+///
+/// See IEEE 1076-2008 section 11.8.
+#[derive(Debug)]
+pub struct IfGenStmt {
+    /// The conditions and the bodies generated if they hold.
+    pub branches: Vec<(ExprRef, GenBodyRef)>,
+    /// The body generated if none of the conditions hold.
+    pub otherwise: Option<GenBodyRef>,
+}
+
+/// The body of a generate statement.
+///
+/// See IEEE 1076-2008 section 11.8.
+#[derive(Debug)]
+pub struct GenBody {
+    /// The scope within which the body is declared.
+    pub parent: ScopeRef,
+    /// The optional alternative label.
+    pub label: Option<Spanned<Name>>,
+    /// The declarations made before the `begin` keyword.
+    pub decls: Vec<DeclInBlockRef>,
+    /// The statements inside the body.
+    pub stmts: Vec<ConcStmtRef>,
 }
 
 /// A sequential signal assignment.
@@ -1,5 +1,6 @@
 // Copyright (c) 2016-2020 Fabian Schuiki
 
+pub use crate::common::codes;
 pub use crate::common::errors::*;
 pub use crate::common::name::Name;
 pub use crate::common::score::Result;
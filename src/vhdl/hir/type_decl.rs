@@ -353,6 +353,16 @@ fn unpack_units<'t>(
                         return Err(());
                     }
                 };
+                if !value.is_integer() {
+                    ctx.emit(
+                        DiagBuilder2::error(format!(
+                            "`{}` is not an integer multiple of a unit",
+                            term.span.extract()
+                        ))
+                        .span(term.span),
+                    );
+                    return Err(());
+                }
                 // TODO: Find a way to enable this again!
                 // if unit.value.0 != id {
                 //     ctx.emit(
@@ -366,7 +376,7 @@ fn unpack_units<'t>(
                 //             .span(unit.span),
                 //     );
                 // }
-                Some((value, unit.value.unwrap_new().1))
+                Some((value.to_integer(), unit.value.unwrap_new().1))
             } else {
                 None
             };
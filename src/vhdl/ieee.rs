@@ -0,0 +1,39 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! The IEEE library bundled with the compiler.
+//!
+//! This provides a subset of the `std_logic_1164` and `numeric_std` packages,
+//! such that designs that use them can be compiled without pointing the
+//! compiler at a separate copy of the library.
+
+use crate::common::errors::DiagEmitter;
+use crate::common::source::get_source_manager;
+use crate::syntax::{self, ast};
+
+/// The name of the library the packages are compiled into.
+pub const LIB_NAME: &str = "ieee";
+
+/// The packages of the library, in dependency order.
+pub static PACKAGES: &[(&str, &str)] = &[
+    (
+        "<ieee>/std_logic_1164.vhd",
+        include_str!("ieee/std_logic_1164.vhd"),
+    ),
+    (
+        "<ieee>/numeric_std.vhd",
+        include_str!("ieee/numeric_std.vhd"),
+    ),
+];
+
+/// Parse the packages of the library.
+pub fn parse(emitter: &dyn DiagEmitter) -> Result<Vec<ast::DesignUnit>, ()> {
+    let sm = get_source_manager();
+    let mut units = vec![];
+    for &(filename, content) in PACKAGES {
+        let source = sm
+            .find(filename)
+            .unwrap_or_else(|| sm.add(filename, content));
+        units.extend(syntax::parse(source, emitter)?);
+    }
+    Ok(units)
+}
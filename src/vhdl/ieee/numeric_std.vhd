@@ -0,0 +1,16 @@
+-- Copyright (c) 2016-2020 Fabian Schuiki
+
+-- The types of the IEEE numeric standard.
+--
+-- This is a subset of the package defined in IEEE 1076-2008 section 16.8. The
+-- arithmetic operators and conversion functions are omitted.
+
+library IEEE;
+use IEEE.STD_LOGIC_1164.all;
+
+package NUMERIC_STD is
+
+	type UNSIGNED is array (NATURAL range <>) of STD_LOGIC;
+	type SIGNED is array (NATURAL range <>) of STD_LOGIC;
+
+end package;
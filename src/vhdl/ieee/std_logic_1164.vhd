@@ -0,0 +1,29 @@
+-- Copyright (c) 2016-2020 Fabian Schuiki
+
+-- The types of the IEEE 1164 multi-value logic system.
+--
+-- This is a subset of the package defined in IEEE 1076-2008 section 16.7. The
+-- resolution function, the subtypes X01 through UX01Z, and the overloaded
+-- operators are omitted, such that STD_LOGIC is an alias of STD_ULOGIC for now.
+
+package STD_LOGIC_1164 is
+
+	-- logic state system (unresolved)
+	type STD_ULOGIC is (
+		'U', -- Uninitialized
+		'X', -- Forcing  Unknown
+		'0', -- Forcing  0
+		'1', -- Forcing  1
+		'Z', -- High Impedance
+		'W', -- Weak     Unknown
+		'L', -- Weak     0
+		'H', -- Weak     1
+		'-'  -- Don't care
+	);
+	type STD_ULOGIC_VECTOR is array (NATURAL range <>) of STD_ULOGIC;
+
+	-- industry standard logic type
+	subtype STD_LOGIC is STD_ULOGIC;
+	type STD_LOGIC_VECTOR is array (NATURAL range <>) of STD_LOGIC;
+
+end package;
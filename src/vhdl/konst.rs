@@ -17,6 +17,8 @@ pub enum Const {
     Enum(ConstEnum),
     IntRange(ConstIntRange),
     FloatRange(ConstFloatRange),
+    Array(ConstArray),
+    Record(ConstRecord),
 }
 
 impl Const {
@@ -28,6 +30,8 @@ impl Const {
             Const::Enum(_) => panic!("cannot negate enumeration literal"),
             Const::IntRange(_) => panic!("cannot negate integer range"),
             Const::FloatRange(_) => panic!("cannot negate float range"),
+            Const::Array(_) => panic!("cannot negate array"),
+            Const::Record(_) => panic!("cannot negate record"),
         }
     }
 
@@ -40,6 +44,8 @@ impl Const {
             Const::Enum(_) => "enumeration literal",
             Const::IntRange(_) => "integer range",
             Const::FloatRange(_) => "float range",
+            Const::Array(_) => "array",
+            Const::Record(_) => "record",
         }
     }
 }
@@ -74,6 +80,18 @@ impl From<ConstFloatRange> for Const {
     }
 }
 
+impl From<ConstArray> for Const {
+    fn from(k: ConstArray) -> Const {
+        Const::Array(k)
+    }
+}
+
+impl From<ConstRecord> for Const {
+    fn from(k: ConstRecord) -> Const {
+        Const::Record(k)
+    }
+}
+
 /// A constant integer value.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ConstInt {
@@ -152,6 +170,37 @@ where
 pub type ConstIntRange = ConstRange<ConstInt>;
 pub type ConstFloatRange = ConstRange<ConstFloat>;
 
+/// A constant array value.
+///
+/// Arrays with multiple dimensions are represented as arrays of arrays.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstArray {
+    /// The elements of the array, in index order. At least one.
+    pub elements: Vec<Const>,
+}
+
+impl ConstArray {
+    /// Create a new constant array.
+    pub fn new(elements: Vec<Const>) -> ConstArray {
+        assert!(!elements.is_empty());
+        ConstArray { elements: elements }
+    }
+}
+
+/// A constant record value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstRecord {
+    /// The values of the fields, in declaration order.
+    pub fields: Vec<Const>,
+}
+
+impl ConstRecord {
+    /// Create a new constant record.
+    pub fn new(fields: Vec<Const>) -> ConstRecord {
+        ConstRecord { fields: fields }
+    }
+}
+
 // ----- FORMATTING ------------------------------------------------------------
 
 impl fmt::Display for Const {
@@ -163,6 +212,8 @@ impl fmt::Display for Const {
             Const::Enum(ref k) => k.fmt(f),
             Const::IntRange(ref k) => k.fmt(f),
             Const::FloatRange(ref k) => k.fmt(f),
+            Const::Array(ref k) => k.fmt(f),
+            Const::Record(ref k) => k.fmt(f),
         }
     }
 }
@@ -193,3 +244,29 @@ where
         write!(f, "{} {} {}", self.left_bound, self.dir, self.right_bound)
     }
 }
+
+impl fmt::Display for ConstArray {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "(")?;
+        for (i, element) in self.elements.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            element.fmt(f)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl fmt::Display for ConstRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "(")?;
+        for (i, field) in self.fields.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            field.fmt(f)?;
+        }
+        write!(f, ")")
+    }
+}
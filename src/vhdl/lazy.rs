@@ -122,6 +122,7 @@ pub type LazyTypeval<'sb, 'ast, 'ctx> =
 node_storage!(LazyHirTable<'sb, 'ast, 'ctx> where ('ast: 'sb, 'ctx: 'sb):
     // Miscellaneous
     subtype_inds:     SubtypeIndRef    => LazyNode<LazyHir<'sb, 'ast, 'ctx, hir::SubtypeInd>>,
+    intf_consts:      IntfConstRef     => LazyNode<LazyHir<'sb, 'ast, 'ctx, hir::IntfConst>>,
 
     // Expressions
     exprs:            ExprRef          => LazyNode<LazyHir<'sb, 'ast, 'ctx, hir::Expr>>,
@@ -147,6 +148,12 @@ node_storage!(LazyHirTable<'sb, 'ast, 'ctx> where ('ast: 'sb, 'ctx: 'sb):
     nexit_stmt:       NexitStmtRef     => LazyNode<LazyHir<'sb, 'ast, 'ctx, hir::Stmt<hir::NexitStmt>>>,
     return_stmt:      ReturnStmtRef    => LazyNode<LazyHir<'sb, 'ast, 'ctx, hir::Stmt<hir::ReturnStmt>>>,
     null_stmt:        NullStmtRef      => LazyNode<LazyHir<'sb, 'ast, 'ctx, hir::Stmt<hir::NullStmt>>>,
+
+    // Concurrent statements
+    comp_inst_stmts:  CompInstStmtRef  => LazyNode<LazyHir<'sb, 'ast, 'ctx, hir::Stmt<hir::CompInstStmt>>>,
+    for_gen_stmts:    ForGenStmtRef    => LazyNode<LazyHir<'sb, 'ast, 'ctx, hir::Stmt<hir::ForGenStmt>>>,
+    if_gen_stmts:     IfGenStmtRef     => LazyNode<LazyHir<'sb, 'ast, 'ctx, hir::Stmt<hir::IfGenStmt>>>,
+    gen_bodies:       GenBodyRef       => LazyNode<LazyHir<'sb, 'ast, 'ctx, hir::GenBody>>,
 );
 
 impl<'sb, 'ast, 'ctx> Default for LazyHirTable<'sb, 'ast, 'ctx> {
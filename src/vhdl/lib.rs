@@ -24,7 +24,9 @@ pub mod builtin;
 pub mod codegen;
 pub mod debug;
 pub mod defs;
+pub mod generic_env;
 pub mod hir;
+pub mod ieee;
 pub mod konst;
 pub mod konst2;
 pub mod lazy;
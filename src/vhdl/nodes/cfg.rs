@@ -0,0 +1,579 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Configurations
+
+use crate::common::errors::*;
+use crate::common::name::{get_name_table, Name};
+use crate::common::score::Result;
+use crate::common::source::{Span, Spanned, INVALID_SPAN};
+use crate::common::util::{HasDesc, HasSpan};
+
+use crate::add_ctx::AddContext;
+use crate::hir;
+use crate::score::*;
+use crate::syntax::ast;
+
+impl<'sbc, 'lazy, 'sb, 'ast, 'ctx> AddContext<'sbc, 'lazy, 'sb, 'ast, 'ctx> {
+    /// Resolve a name to the single definition it refers to.
+    ///
+    /// Returns `None` if the name is ambiguous or does not denote a
+    /// definition, for the caller to report. The parts of the name that were
+    /// not resolved are returned as well.
+    pub fn resolve_unit_name(
+        &self,
+        name: &'ast ast::CompoundName,
+    ) -> Result<(Option<Def>, Span, &'ast [ast::NamePart])> {
+        let (_, defs, span, tail) = self.ctx.resolve_compound_name(name, self.scope, false)?;
+        let def = match defs.as_slice() {
+            [def] => Some(def.value),
+            _ => None,
+        };
+        Ok((def, span, tail))
+    }
+
+    /// Resolve the name of a component.
+    pub fn add_comp_name(&self, name: &'ast ast::CompoundName) -> Result<Spanned<CompDeclRef>> {
+        match self.resolve_unit_name(name)? {
+            (Some(Def::Comp(id)), span, []) => Ok(Spanned::new(id, span)),
+            _ => {
+                self.emit(
+                    DiagBuilder2::error(format!("`{}` is not a component", name.span.extract()))
+                        .span(name.span),
+                );
+                Err(())
+            }
+        }
+    }
+
+    /// Resolve the name of a configuration.
+    pub fn add_cfg_name(&self, name: &'ast ast::CompoundName) -> Result<Spanned<CfgRef>> {
+        match self.resolve_unit_name(name)? {
+            (Some(Def::Cfg(id)), span, []) => Ok(Spanned::new(id, span)),
+            _ => {
+                self.emit(
+                    DiagBuilder2::error(format!(
+                        "`{}` is not a configuration",
+                        name.span.extract()
+                    ))
+                    .span(name.span),
+                );
+                Err(())
+            }
+        }
+    }
+
+    /// Add the entity aspect of a binding indication.
+    ///
+    /// Returns `None` if the binding indication does not name an entity.
+    /// Generic and port maps in binding indications are not supported; the
+    /// ports and generics of the component are associated with the ports and
+    /// generics of the entity by name. See IEEE 1076-2008 section 7.3.2.
+    pub fn add_binding_ind(
+        &self,
+        bind: &'ast ast::BindingInd,
+    ) -> Result<Option<hir::EntityAspect>> {
+        if let Some(ref elems) = bind.generics.as_ref().or(bind.ports.as_ref()) {
+            self.emit(
+                DiagBuilder2::error("generic and port maps in binding indications not supported")
+                    .span(elems.span),
+            );
+            return Err(());
+        }
+        Ok(match bind.entity {
+            Some(ast::EntityAspect::Entity(ref name)) => {
+                let (entity, arch) = self.add_entity_name(name)?;
+                Some(hir::EntityAspect::Entity(entity, arch))
+            }
+            Some(ast::EntityAspect::Cfg(ref name)) => {
+                Some(hir::EntityAspect::Cfg(self.add_cfg_name(name)?))
+            }
+            Some(ast::EntityAspect::Open) => Some(hir::EntityAspect::Open),
+            None => None,
+        })
+    }
+
+    /// Add the component instances named in a configuration.
+    ///
+    /// The component name is resolved in the scope of the context, which must
+    /// be the architecture containing the instances.
+    pub fn add_inst_spec(&self, spec: &'ast Spanned<ast::BlockCompSpec>) -> Result<hir::InstSpec> {
+        let (list, comp) = match spec.value {
+            ast::BlockCompSpec::CompNames(ref labels, ref comp) => (
+                hir::InstList::Labels(
+                    labels
+                        .iter()
+                        .map(|l| Spanned::new(l.name, l.span))
+                        .collect(),
+                ),
+                comp,
+            ),
+            ast::BlockCompSpec::CompOthers(ref comp) => (hir::InstList::Others, comp),
+            ast::BlockCompSpec::CompAll(ref comp) => (hir::InstList::All, comp),
+            ast::BlockCompSpec::Block(..) => {
+                self.emit(
+                    DiagBuilder2::error(format!(
+                        "`{}` does not name component instances",
+                        spec.span.extract()
+                    ))
+                    .span(spec.span),
+                );
+                return Err(());
+            }
+        };
+        Ok(hir::InstSpec {
+            list: list,
+            comp: self.add_comp_name(comp)?,
+        })
+    }
+
+    /// Add a block configuration for an architecture of an entity.
+    ///
+    /// Only the configuration of component instances directly within the
+    /// architecture is supported. See IEEE 1076-2008 section 3.4.2.
+    pub fn add_block_cfg(
+        &self,
+        entity: EntityRef,
+        cfg: &'ast ast::BlockCompCfg,
+    ) -> Result<hir::BlockCfg> {
+        // Find the configured architecture.
+        let name = match cfg.spec.value {
+            ast::BlockCompSpec::Block(ref name) if name.parts.is_empty() => name,
+            _ => {
+                self.emit(
+                    DiagBuilder2::error(format!(
+                        "`{}` is not an architecture name",
+                        cfg.spec.span.extract()
+                    ))
+                    .span(cfg.spec.span),
+                );
+                return Err(());
+            }
+        };
+        let arch_name = match name.primary.kind {
+            ast::PrimaryNameKind::Ident(n) => n,
+            _ => {
+                self.emit(
+                    DiagBuilder2::error(format!(
+                        "`{}` is not an architecture name",
+                        name.span.extract()
+                    ))
+                    .span(name.span),
+                );
+                return Err(());
+            }
+        };
+        let entity_hir = self.ctx.hir(entity)?;
+        let arch = self
+            .ctx
+            .archs(entity_hir.lib)?
+            .by_entity
+            .get(&entity)
+            .and_then(|t| t.by_name.get(&arch_name));
+        let arch = match arch {
+            Some(&arch) => Spanned::new(arch, name.span),
+            None => {
+                self.emit(
+                    DiagBuilder2::error(format!(
+                        "entity `{}` has no architecture `{}`",
+                        entity_hir.name.value, arch_name
+                    ))
+                    .span(name.span),
+                );
+                return Err(());
+            }
+        };
+        if let Some(ref entity) = cfg.bind.entity {
+            let span = match *entity {
+                ast::EntityAspect::Entity(ref n) | ast::EntityAspect::Cfg(ref n) => n.span,
+                ast::EntityAspect::Open => cfg.bind.span,
+            };
+            self.emit(
+                DiagBuilder2::error("a block configuration cannot have a binding indication")
+                    .span(span),
+            );
+            return Err(());
+        }
+
+        // Add the component configurations. Component names are resolved in
+        // the architecture, entity names in the configuration itself.
+        let arch_ctx = self.with_scope(arch.value.into());
+        let mut comps = Vec::new();
+        let mut had_fails = false;
+        for decl in &cfg.decls {
+            let comp = match *decl {
+                ast::DeclItem::BlockCompCfg(ref comp) => comp,
+                ref wrong => {
+                    self.emit(
+                        DiagBuilder2::error(format!(
+                            "{} in a block configuration not supported",
+                            wrong.desc()
+                        ))
+                        .span(wrong.human_span()),
+                    );
+                    had_fails = true;
+                    continue;
+                }
+            };
+            match self.add_comp_cfg(arch_ctx, comp) {
+                Ok(c) => comps.push(c),
+                Err(()) => had_fails = true,
+            }
+        }
+        if had_fails {
+            return Err(());
+        }
+        Ok(hir::BlockCfg {
+            arch: arch,
+            comps: comps,
+        })
+    }
+
+    /// Add a component configuration.
+    ///
+    /// The instances are resolved in `arch_ctx`, everything else in the
+    /// current context. See IEEE 1076-2008 section 3.4.3.
+    fn add_comp_cfg(
+        &self,
+        arch_ctx: AddContext<'sbc, 'lazy, 'sb, 'ast, 'ctx>,
+        cfg: &'ast ast::BlockCompCfg,
+    ) -> Result<hir::CompCfg> {
+        let spec = arch_ctx.add_inst_spec(&cfg.spec)?;
+        let binding = self.add_binding_ind(&cfg.bind)?;
+        let block = match cfg.decls.as_slice() {
+            [] => None,
+            [ast::DeclItem::BlockCompCfg(ref block)] => match binding {
+                Some(hir::EntityAspect::Entity(entity, _)) => {
+                    Some(self.add_block_cfg(entity.value, block)?)
+                }
+                _ => {
+                    self.emit(
+                        DiagBuilder2::error(
+                            "a block configuration requires the instances to be bound to an \
+                             entity",
+                        )
+                        .span(block.span)
+                        .add_note("Bind the instances with `use entity <name>` before.")
+                        .span(cfg.spec.span),
+                    );
+                    return Err(());
+                }
+            },
+            [ref wrong, ..] => {
+                self.emit(
+                    DiagBuilder2::error(format!(
+                        "{} in a component configuration not supported",
+                        wrong.desc()
+                    ))
+                    .span(wrong.human_span()),
+                );
+                return Err(());
+            }
+        };
+        Ok(hir::CompCfg {
+            spec: spec,
+            binding: binding,
+            block: block,
+        })
+    }
+}
+
+/// The design entity a component instance is bound to.
+#[derive(Debug, Clone, Copy)]
+pub struct InstBinding<'ctx> {
+    /// The bound entity.
+    pub entity: EntityRef,
+    /// The bound architecture of the entity.
+    pub arch: ArchRef,
+    /// The configuration of the architecture, if any.
+    pub cfg: Option<&'ctx hir::BlockCfg>,
+}
+
+impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
+    /// Determine the design entity a component instance is bound to.
+    ///
+    /// A component is bound by the configuration in effect, by a configuration
+    /// specification in the surrounding declarative region, or by default to
+    /// the entity of the same name in the work library, in this order. Returns
+    /// `None` if the instance is left unbound. See IEEE 1076-2008 section 7.3.
+    pub fn inst_binding(&self, id: CompInstStmtRef) -> Result<Option<InstBinding<'ctx>>> {
+        let hir = self.lazy_hir(id)?;
+        let (aspect, block) = match hir.stmt.unit {
+            hir::InstUnit::Entity(entity, arch) => (hir::EntityAspect::Entity(entity, arch), None),
+            hir::InstUnit::Cfg(cfg) => (hir::EntityAspect::Cfg(cfg), None),
+            hir::InstUnit::Comp(comp) => {
+                let label = hir.label.map(|l| l.value);
+                match self.comp_cfg_binding(hir.parent, label, comp.value)? {
+                    Some(x) => x,
+                    None => match self.default_binding(hir.parent, comp.value)? {
+                        Some(aspect) => (aspect, None),
+                        None => {
+                            self.emit(
+                                DiagBuilder2::warning(format!(
+                                    "no entity `{}` to bind the instance to; the instance is \
+                                     left unbound",
+                                    self.hir(comp.value)?.name.value
+                                ))
+                                .span(hir.span),
+                            );
+                            (hir::EntityAspect::Open, None)
+                        }
+                    },
+                }
+            }
+        };
+        Ok(match aspect {
+            hir::EntityAspect::Entity(entity, arch) => {
+                let arch = match block {
+                    Some(block) => block.arch.value,
+                    None => self.select_arch(entity, arch)?,
+                };
+                Some(InstBinding {
+                    entity: entity.value,
+                    arch: arch,
+                    cfg: block,
+                })
+            }
+            hir::EntityAspect::Cfg(cfg) => {
+                let cfg = self.hir(cfg.value)?;
+                Some(InstBinding {
+                    entity: cfg.entity.value,
+                    arch: cfg.block.arch.value,
+                    cfg: Some(&cfg.block),
+                })
+            }
+            hir::EntityAspect::Open => None,
+        })
+    }
+
+    /// Find the binding of a component instance given by the configuration
+    /// in effect or a configuration specification.
+    fn comp_cfg_binding(
+        &self,
+        parent: ScopeRef,
+        label: Option<Name>,
+        comp: CompDeclRef,
+    ) -> Result<Option<(hir::EntityAspect, Option<&'ctx hir::BlockCfg>)>> {
+        // Component configurations without a binding indication leave the
+        // binding to the configuration specification or the default.
+        if let Some(cfg) = self.block_cfg() {
+            let specs: Vec<_> = cfg.comps.iter().map(|c| (&c.spec, c)).collect();
+            if let Some(comp_cfg) = select_inst_spec(&specs, label, comp) {
+                if let Some(binding) = comp_cfg.binding {
+                    return Ok(Some((binding, comp_cfg.block.as_ref())));
+                }
+            }
+        }
+
+        // Configuration specifications apply to the instances in the same
+        // declarative region.
+        let decls = match parent {
+            ScopeRef::Arch(id) => &self.hir(id)?.decls,
+            ScopeRef::GenBody(id) => &self.lazy_hir(id)?.decls,
+            _ => return Ok(None),
+        };
+        let mut specs = Vec::new();
+        for &decl in decls {
+            if let DeclInBlockRef::CfgSpec(id) = decl {
+                let spec = self.hir(id)?;
+                specs.push((&spec.spec, spec));
+            }
+        }
+        Ok(select_inst_spec(&specs, label, comp).map(|spec| (spec.binding, None)))
+    }
+
+    /// Find the entity a component is bound to by default.
+    ///
+    /// This is the entity of the same name as the component in the work
+    /// library. See IEEE 1076-2008 section 7.3.3.
+    fn default_binding(
+        &self,
+        parent: ScopeRef,
+        comp: CompDeclRef,
+    ) -> Result<Option<hir::EntityAspect>> {
+        let work = get_name_table().intern("work", false);
+        let lib = self
+            .resolve_name(Spanned::new(work.into(), INVALID_SPAN), parent, false, true)?
+            .into_iter()
+            .filter_map(|d| match d.value {
+                Def::Lib(id) => Some(id),
+                _ => None,
+            })
+            .next();
+        let lib = match lib {
+            Some(lib) => lib,
+            None => return Ok(None),
+        };
+        let name = self.hir(comp)?.name;
+        let defs = self.resolve_name(name.map_into(), lib.into(), true, true)?;
+        Ok(defs
+            .into_iter()
+            .filter_map(|d| match d.value {
+                Def::Entity(id) => {
+                    Some(hir::EntityAspect::Entity(Spanned::new(id, name.span), None))
+                }
+                _ => None,
+            })
+            .next())
+    }
+
+    /// Find the architecture of an entity to instantiate.
+    ///
+    /// Without an explicit name, the most recently analyzed architecture of
+    /// the entity is used.
+    pub fn select_arch(
+        &self,
+        entity: Spanned<EntityRef>,
+        name: Option<Spanned<Name>>,
+    ) -> Result<ArchRef> {
+        let entity_hir = self.hir(entity.value)?;
+        let archs = self.archs(entity_hir.lib)?.by_entity.get(&entity.value);
+        match name {
+            Some(name) => match archs.and_then(|t| t.by_name.get(&name.value)) {
+                Some(&arch) => Ok(arch),
+                None => {
+                    self.emit(
+                        DiagBuilder2::error(format!(
+                            "entity `{}` has no architecture `{}`",
+                            entity_hir.name.value, name.value
+                        ))
+                        .span(name.span),
+                    );
+                    Err(())
+                }
+            },
+            None => match archs.and_then(|t| t.ordered.last()) {
+                Some(&arch) => Ok(arch),
+                None => {
+                    self.emit(
+                        DiagBuilder2::error(format!(
+                            "entity `{}` has no architecture",
+                            entity_hir.name.value
+                        ))
+                        .span(entity.span),
+                    );
+                    Err(())
+                }
+            },
+        }
+    }
+
+    /// Associate the generics and ports of a component instance with those of
+    /// the entity the component is bound to.
+    ///
+    /// The generics and ports are associated by name. Generics of the
+    /// component that are not associated in the instance contribute their
+    /// default value.
+    pub fn bind_comp_assocs(
+        &self,
+        comp: Spanned<CompDeclRef>,
+        stmt: &hir::CompInstStmt,
+        entity: EntityRef,
+    ) -> Result<(Vec<(IntfConstRef, ExprRef)>, Vec<(IntfSignalRef, ExprRef)>)> {
+        let comp_hir = self.hir(comp.value)?;
+        let entity_hir = self.hir(entity)?;
+        let mut had_fails = false;
+
+        let mut generics = Vec::new();
+        for &generic in &comp_hir.generics {
+            let generic = match generic {
+                GenericRef::Const(id) => id,
+                _ => continue,
+            };
+            let generic_hir = self.lazy_hir(generic)?;
+            let actual = stmt
+                .generics
+                .iter()
+                .find(|&&(g, _)| g == generic)
+                .map(|&(_, a)| a)
+                .or(generic_hir.init);
+            let actual = match actual {
+                Some(a) => a,
+                None => continue,
+            };
+            let mut formal = None;
+            for &g in &entity_hir.generics {
+                if let GenericRef::Const(id) = g {
+                    if self.lazy_hir(id)?.name.value == generic_hir.name.value {
+                        formal = Some(id);
+                    }
+                }
+            }
+            match formal {
+                Some(formal) => generics.push((formal, actual)),
+                None => {
+                    self.emit(
+                        DiagBuilder2::error(format!(
+                            "entity `{}` has no generic `{}`",
+                            entity_hir.name.value, generic_hir.name.value
+                        ))
+                        .span(generic_hir.name.span)
+                        .add_note(format!(
+                            "Component `{}` is bound to the entity here:",
+                            comp_hir.name.value
+                        ))
+                        .span(comp.span),
+                    );
+                    had_fails = true;
+                }
+            }
+        }
+
+        let mut ports = Vec::new();
+        for &(port, actual) in &stmt.ports {
+            let port_hir = self.hir(port)?;
+            let mut formal = None;
+            for &p in &entity_hir.ports {
+                if self.hir(p)?.name.value == port_hir.name.value {
+                    formal = Some(p);
+                }
+            }
+            match formal {
+                Some(formal) => ports.push((formal, actual)),
+                None => {
+                    self.emit(
+                        DiagBuilder2::error(format!(
+                            "entity `{}` has no port `{}`",
+                            entity_hir.name.value, port_hir.name.value
+                        ))
+                        .span(port_hir.name.span)
+                        .add_note(format!(
+                            "Component `{}` is bound to the entity here:",
+                            comp_hir.name.value
+                        ))
+                        .span(comp.span),
+                    );
+                    had_fails = true;
+                }
+            }
+        }
+
+        if had_fails {
+            Err(())
+        } else {
+            Ok((generics, ports))
+        }
+    }
+}
+
+/// Select the configuration that applies to a component instance.
+///
+/// Configurations naming the instance by label take precedence over those
+/// applying to `all` or `others` instances of the component.
+fn select_inst_spec<T: Copy>(
+    specs: &[(&hir::InstSpec, T)],
+    label: Option<Name>,
+    comp: CompDeclRef,
+) -> Option<T> {
+    let find = |f: &dyn Fn(&hir::InstList) -> bool| {
+        specs
+            .iter()
+            .find(|&&(spec, _)| spec.comp.value == comp && f(&spec.list))
+            .map(|&(_, x)| x)
+    };
+    find(&|list| match *list {
+        hir::InstList::Labels(ref labels) => labels.iter().any(|l| Some(l.value) == label),
+        _ => false,
+    })
+    .or_else(|| find(&|list| *list == hir::InstList::All))
+    .or_else(|| find(&|list| *list == hir::InstList::Others))
+}
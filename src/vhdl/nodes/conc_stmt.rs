@@ -0,0 +1,487 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! Concurrent statements
+
+use crate::common::errors::*;
+use crate::common::name::Name;
+use crate::common::score::Result;
+use crate::common::source::{Span, Spanned};
+
+use num::{BigInt, One};
+
+use crate::add_ctx::AddContext;
+use crate::builtin::{BOOLEAN_TYPE, INTEGER_TYPE};
+use crate::generic_env::GenericEnvData;
+use crate::hir;
+use crate::konst::{Const, ConstInt};
+use crate::score::*;
+use crate::syntax::ast;
+use crate::ty::*;
+use crate::typeck::TypeckContext;
+
+impl<'sbc, 'lazy, 'sb, 'ast, 'ctx> AddContext<'sbc, 'lazy, 'sb, 'ast, 'ctx> {
+    /// Add a component instantiation statement.
+    pub fn add_comp_inst_stmt(&self, stmt: &'ast ast::Stmt) -> Result<CompInstStmtRef> {
+        let (target, name, generics, ports) = match stmt.data {
+            ast::InstOrCallStmt {
+                target,
+                ref name,
+                ref generics,
+                ref ports,
+            } => (target, name, generics, ports),
+            _ => unreachable!(),
+        };
+        let (mk, id, scope) = self.make::<CompInstStmtRef>(stmt.span);
+        mk.lower_to_hir(Box::new(move |sbc| {
+            let ctx = AddContext::new(sbc, scope);
+            let unit = match target {
+                Some(ast::InstTarget::Entity) => {
+                    let (entity, arch) = ctx.add_entity_name(name)?;
+                    hir::InstUnit::Entity(entity, arch)
+                }
+                Some(ast::InstTarget::Cfg) => hir::InstUnit::Cfg(ctx.add_cfg_name(name)?),
+                Some(ast::InstTarget::Comp) => hir::InstUnit::Comp(ctx.add_comp_name(name)?),
+                // Without a keyword this may also be a concurrent procedure
+                // call, which is not supported.
+                None => match ctx.resolve_unit_name(name)? {
+                    (Some(Def::Comp(id)), span, []) => hir::InstUnit::Comp(Spanned::new(id, span)),
+                    _ => return ctx.unimp(stmt),
+                },
+            };
+            let (unit_span, unit_generics, unit_ports) = match unit {
+                hir::InstUnit::Comp(comp) => {
+                    let hir = sbc.hir(comp.value)?;
+                    (comp.span, &hir.generics, &hir.ports)
+                }
+                hir::InstUnit::Entity(entity, _) => {
+                    let hir = sbc.hir(entity.value)?;
+                    (entity.span, &hir.generics, &hir.ports)
+                }
+                hir::InstUnit::Cfg(cfg) => {
+                    let hir = sbc.hir(sbc.hir(cfg.value)?.entity.value)?;
+                    (cfg.span, &hir.generics, &hir.ports)
+                }
+            };
+
+            // Associate the generic map with the generics of the unit.
+            let mut formals = Vec::new();
+            for &generic in unit_generics {
+                if let GenericRef::Const(id) = generic {
+                    formals.push((sbc.lazy_hir(id)?.name.value, id));
+                }
+            }
+            let generics = match *generics {
+                Some(ref elems) => ctx.add_assoc_list(elems, &formals, "generic", unit_span)?,
+                None => vec![],
+            };
+            for &(generic, actual) in &generics {
+                let ty = sbc.lazy_hir(generic)?.ty;
+                sbc.set_type_context(actual, TypeCtx::TypeOf(ty.into()));
+            }
+
+            // Associate the port map with the ports of the unit. The types of
+            // the ports may depend on the generics, so no type context is
+            // provided for the actuals.
+            let mut formals = Vec::new();
+            for &port in unit_ports {
+                formals.push((sbc.hir(port)?.name.value, port));
+            }
+            let ports = match *ports {
+                Some(ref elems) => ctx.add_assoc_list(elems, &formals, "port", unit_span)?,
+                None => vec![],
+            };
+
+            Ok(hir::Stmt {
+                parent: scope,
+                span: stmt.span,
+                label: stmt.label,
+                stmt: hir::CompInstStmt {
+                    unit: unit,
+                    generics: generics,
+                    ports: ports,
+                },
+            })
+        }));
+        mk.typeck(Box::new(move |tyc| {
+            let hir = tyc.ctx.lazy_hir(id)?;
+            for &(generic, actual) in &hir.stmt.generics {
+                let ty = tyc.lazy_typeval(generic)?;
+                let actual_ty = tyc.lazy_typeval(actual)?;
+                tyc.must_match(ty, actual_ty, tyc.ctx.span(actual).unwrap());
+            }
+            // The port types are checked during elaboration, once the values
+            // of the generics are known.
+            for &(_, actual) in &hir.stmt.ports {
+                tyc.lazy_typeval(actual)?;
+            }
+            Ok(())
+        }));
+        Ok(mk.finish())
+    }
+
+    /// Add the name of an instantiated entity.
+    ///
+    /// Resolves a name such as `work.foo(rtl)` to the entity and the optional
+    /// name of the architecture.
+    pub fn add_entity_name(
+        &self,
+        name: &'ast ast::CompoundName,
+    ) -> Result<(Spanned<EntityRef>, Option<Spanned<Name>>)> {
+        let (def, span, tail) = self.resolve_unit_name(name)?;
+        let entity = match def {
+            Some(Def::Entity(id)) => Spanned::new(id, span),
+            _ => {
+                self.emit(
+                    DiagBuilder2::error(format!("`{}` is not an entity", span.extract()))
+                        .span(span),
+                );
+                return Err(());
+            }
+        };
+        let arch = match tail {
+            [] => None,
+            [ast::NamePart::Call(ref elems)] if elems.value.len() == 1 => {
+                match simple_name(&elems.value[0].expr) {
+                    Some(arch) if elems.value[0].choices.value.is_empty() => Some(arch),
+                    _ => {
+                        self.emit(
+                            DiagBuilder2::error(format!(
+                                "`{}` is not an architecture name",
+                                elems.span.extract()
+                            ))
+                            .span(elems.span),
+                        );
+                        return Err(());
+                    }
+                }
+            }
+            _ => {
+                let span = Span::union(span.end().into(), name.span.end());
+                self.emit(DiagBuilder2::error("invalid name suffix").span(span));
+                return Err(());
+            }
+        };
+        Ok((entity, arch))
+    }
+
+    /// Add a generic or port map.
+    ///
+    /// Associates each element of the map with a formal, either by name or by
+    /// position. Elements whose actual is `open` are omitted. See IEEE
+    /// 1076-2008 section 6.5.7.
+    fn add_assoc_list<F>(
+        &self,
+        elems: &'ast ast::ParenElems,
+        formals: &[(Name, F)],
+        kind: &str,
+        unit: Span,
+    ) -> Result<Vec<(F, ExprRef)>>
+    where
+        F: Copy + PartialEq,
+    {
+        let mut assocs = Vec::new();
+        let mut seen = Vec::new();
+        let mut named = false;
+        let mut had_fails = false;
+        for (index, elem) in elems.value.iter().enumerate() {
+            // Determine the formal the element is associated with.
+            let (name, formal) = if elem.choices.value.is_empty() {
+                if named {
+                    self.emit(
+                        DiagBuilder2::error(format!(
+                            "positional {} association cannot follow named association",
+                            kind
+                        ))
+                        .span(elem.span),
+                    );
+                    had_fails = true;
+                    continue;
+                }
+                match formals.get(index) {
+                    Some(&formal) => formal,
+                    None => {
+                        self.emit(
+                            DiagBuilder2::error(format!(
+                                "`{}` has only {} {}s",
+                                unit.extract(),
+                                formals.len(),
+                                kind
+                            ))
+                            .span(elem.span),
+                        );
+                        had_fails = true;
+                        continue;
+                    }
+                }
+            } else {
+                named = true;
+                let name = match elem.choices.value.as_slice() {
+                    [ref choice] => simple_name(choice),
+                    _ => None,
+                };
+                let name = match name {
+                    Some(n) => n,
+                    None => {
+                        self.emit(
+                            DiagBuilder2::error(format!(
+                                "`{}` is not a {} name",
+                                elem.choices.span.extract(),
+                                kind
+                            ))
+                            .span(elem.choices.span),
+                        );
+                        had_fails = true;
+                        continue;
+                    }
+                };
+                match formals.iter().find(|&&(n, _)| n == name.value) {
+                    Some(&formal) => formal,
+                    None => {
+                        self.emit(
+                            DiagBuilder2::error(format!(
+                                "`{}` has no {} `{}`",
+                                unit.extract(),
+                                kind,
+                                name.value
+                            ))
+                            .span(name.span),
+                        );
+                        had_fails = true;
+                        continue;
+                    }
+                }
+            };
+
+            // Make sure every formal is only associated once.
+            if seen.contains(&formal) {
+                self.emit(
+                    DiagBuilder2::error(format!(
+                        "{} `{}` is associated more than once",
+                        kind, name
+                    ))
+                    .span(elem.span),
+                );
+                had_fails = true;
+                continue;
+            }
+            seen.push(formal);
+
+            // Add the actual, unless the formal is left open.
+            if elem.expr.data == ast::OpenExpr {
+                continue;
+            }
+            match self.add_expr(&elem.expr) {
+                Ok(actual) => assocs.push((formal, actual)),
+                Err(()) => had_fails = true,
+            }
+        }
+        if had_fails {
+            Err(())
+        } else {
+            Ok(assocs)
+        }
+    }
+
+    /// Add a for-generate statement.
+    pub fn add_for_gen_stmt(&self, stmt: &'ast ast::Stmt) -> Result<ForGenStmtRef> {
+        let (mk, id, scope) = self.make::<ForGenStmtRef>(stmt.span);
+        let (param, range, body) = match stmt.data {
+            ast::ForGenStmt {
+                param,
+                ref range,
+                ref body,
+            } => (param, range, body),
+            _ => unreachable!(),
+        };
+        mk.lower_to_hir(Box::new(move |sbc| {
+            let range = AddContext::new(sbc, scope).add_discrete_range(range);
+            let body = AddContext::new(sbc, id.into()).add_gen_body(body);
+            Ok(hir::Stmt {
+                parent: scope,
+                span: stmt.span,
+                label: stmt.label,
+                stmt: hir::ForGenStmt {
+                    param: param,
+                    range: range?,
+                    body: body?,
+                },
+            })
+        }));
+        mk.typeval(Box::new(move |tyc| {
+            // The parameter assumes the type of the range. Ranges of integer
+            // literals are of type `integer`.
+            let hir = tyc.ctx.lazy_hir(id)?;
+            match hir.stmt.range.value {
+                hir::DiscreteRange::Subtype(id) => tyc.lazy_typeval(id),
+                hir::DiscreteRange::Range(hir::Range::Immediate(_, lb, rb)) => {
+                    let lb = tyc.lazy_typeval(lb)?;
+                    let rb = tyc.lazy_typeval(rb)?;
+                    match (lb, rb) {
+                        (&Ty::UniversalInt, &Ty::UniversalInt) => {
+                            Ok(tyc.ctx.intern_ty(INTEGER_TYPE.named_ty()))
+                        }
+                        (&Ty::UniversalInt, ty) | (ty, _) => Ok(ty),
+                    }
+                }
+            }
+        }));
+        mk.typeck(Box::new(move |tyc| {
+            // Check the body as it is generated for the first value of the
+            // parameter. Nothing is generated for a null range.
+            let hir = tyc.ctx.lazy_hir(id)?;
+            let values = tyc.ctx.for_gen_values(id)?;
+            if let Some(&first) = values.first() {
+                let env = tyc.ctx.intern_generic_env(GenericEnvData {
+                    parent: Some(tyc.ctx.env),
+                    values: vec![(id.into(), first)],
+                    cfg: None,
+                });
+                let sbc = tyc.ctx.with_env(env);
+                let body_tyc = TypeckContext::new(&sbc);
+                body_tyc.lazy_typeck(hir.stmt.body);
+                if !body_tyc.finish() {
+                    return Err(());
+                }
+            }
+            Ok(())
+        }));
+        Ok(mk.finish())
+    }
+
+    /// Add an if-generate statement.
+    pub fn add_if_gen_stmt(&self, stmt: &'ast ast::Stmt) -> Result<IfGenStmtRef> {
+        let (mk, id, scope) = self.make::<IfGenStmtRef>(stmt.span);
+        let (branches, otherwise) = match stmt.data {
+            ast::IfGenStmt { ref conds, ref alt } => (conds, alt),
+            _ => unreachable!(),
+        };
+        mk.lower_to_hir(Box::new(move |sbc| {
+            let ctx = AddContext::new(sbc, scope);
+            let branches = branches
+                .iter()
+                .map(|&(ref cond, ref body)| Ok((ctx.add_expr(cond)?, ctx.add_gen_body(body)?)))
+                .collect::<Vec<Result<_>>>()
+                .into_iter()
+                .collect::<Result<Vec<_>>>();
+            let otherwise = ctx.add_optional(otherwise, AddContext::add_gen_body);
+            let (branches, otherwise) = (branches?, otherwise?);
+            let boolean = sbc.intern_ty(BOOLEAN_TYPE.named_ty());
+            for &(cond, _) in &branches {
+                sbc.set_type_context(cond, boolean);
+            }
+            Ok(hir::Stmt {
+                parent: scope,
+                span: stmt.span,
+                label: stmt.label,
+                stmt: hir::IfGenStmt {
+                    branches: branches,
+                    otherwise: otherwise,
+                },
+            })
+        }));
+        mk.typeck(Box::new(move |tyc| {
+            let hir = tyc.ctx.lazy_hir(id)?;
+            let boolean = tyc.ctx.intern_ty(BOOLEAN_TYPE.named_ty());
+            for &(cond, body) in &hir.stmt.branches {
+                let ty = tyc.lazy_typeval(cond)?;
+                tyc.must_match(boolean, ty, tyc.ctx.span(cond).unwrap());
+                tyc.lazy_typeck(body);
+            }
+            if let Some(body) = hir.stmt.otherwise {
+                tyc.lazy_typeck(body);
+            }
+            Ok(())
+        }));
+        Ok(mk.finish())
+    }
+
+    /// Add the body of a generate statement.
+    pub fn add_gen_body(&self, body: &'ast ast::GenBody) -> Result<GenBodyRef> {
+        let (mk, id, scope) = self.make::<GenBodyRef>(body.span);
+        mk.lower_to_hir(Box::new(move |sbc| {
+            let decls = sbc.unpack_block_decls(id.into(), &body.decls, "a generate statement");
+            let stmts = sbc.unpack_concurrent_stmts(id.into(), &body.stmts, "a generate statement");
+            Ok(hir::GenBody {
+                parent: scope,
+                label: body.label,
+                decls: decls?,
+                stmts: stmts?,
+            })
+        }));
+        mk.typeck(Box::new(move |tyc| {
+            let hir = tyc.ctx.lazy_hir(id)?;
+            tyc.typeck_slice(&hir.decls);
+            tyc.typeck_slice(&hir.stmts);
+            Ok(())
+        }));
+        Ok(mk.finish())
+    }
+}
+
+impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
+    /// Determine the values the parameter of a for-generate statement assumes,
+    /// in the order in which they are generated.
+    pub fn for_gen_values(&self, id: ForGenStmtRef) -> Result<Vec<&'ctx Const>> {
+        let hir = self.lazy_hir(id)?;
+        let range = &hir.stmt.range;
+        let tyc = TypeckContext::new(self);
+        let range_ty = tyc.type_from_discrete_range(Spanned::new(&range.value, range.span));
+        if !tyc.finish() {
+            return Err(());
+        }
+        let param_ty = self.lazy_typeval(id)?;
+        match (
+            self.deref_named_type(range_ty?)?,
+            self.deref_named_type(param_ty)?,
+        ) {
+            (&Ty::Int(ref range_ty), &Ty::Int(ref param_ty)) => {
+                let mut values = Vec::new();
+                let mut value = range_ty.left_bound.clone();
+                loop {
+                    let done = match range_ty.dir {
+                        Dir::To => value > range_ty.right_bound,
+                        Dir::Downto => value < range_ty.right_bound,
+                    };
+                    if done {
+                        break;
+                    }
+                    values.push(
+                        self.intern_const(ConstInt::new(Some(param_ty.clone()), value.clone())),
+                    );
+                    match range_ty.dir {
+                        Dir::To => value = value + BigInt::one(),
+                        Dir::Downto => value = value - BigInt::one(),
+                    }
+                }
+                Ok(values)
+            }
+            (range_ty, _) => {
+                self.emit(
+                    DiagBuilder2::error(format!(
+                        "cannot generate over `{}` of type {}; only integer ranges are supported",
+                        range.span.extract(),
+                        range_ty
+                    ))
+                    .span(range.span),
+                );
+                Err(())
+            }
+        }
+    }
+}
+
+/// Extract a simple name from an expression, e.g. the formal in a generic map.
+fn simple_name(expr: &ast::Expr) -> Option<Spanned<Name>> {
+    match expr.data {
+        ast::NameExpr(ast::CompoundName {
+            primary:
+                ast::PrimaryName {
+                    kind: ast::PrimaryNameKind::Ident(name),
+                    ..
+                },
+            ref parts,
+            ..
+        }) if parts.is_empty() => Some(Spanned::new(name, expr.span)),
+        _ => None,
+    }
+}
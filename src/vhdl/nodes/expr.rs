@@ -4,6 +4,7 @@
 
 use std::collections::HashMap;
 
+use crate::common::codes;
 use crate::common::errors::*;
 use crate::common::name::Name;
 use crate::common::score::Result;
@@ -11,6 +12,7 @@ use crate::common::source::{Span, Spanned};
 use crate::common::Verbosity;
 
 use crate::add_ctx::AddContext;
+use crate::builtin::{BOOLEAN_TYPE, TIME_TYPE};
 use crate::hir;
 use crate::make_ctx::MakeContext;
 use crate::overload_resolver::*;
@@ -187,6 +189,8 @@ pub fn typeval_expr<'sbc, 'lazy: 'sbc, 'sb: 'lazy, 'ast: 'sb, 'ctx: 'sb>(
 ) -> Result<&'ctx Ty> {
     match hir.data {
         hir::ExprData::ConstName(id) => tyc.ctx.lazy_typeval(id),
+        hir::ExprData::GenericName(id) => tyc.ctx.lazy_typeval(id),
+        hir::ExprData::GenParamName(id) => tyc.ctx.lazy_typeval(id),
         hir::ExprData::SignalName(id) => tyc.ctx.ty(id),
         hir::ExprData::VarName(id) => tyc.ctx.lazy_typeval(id),
         hir::ExprData::FileName(id) => tyc.ctx.lazy_typeval(id),
@@ -218,6 +222,7 @@ pub fn typeval_expr<'sbc, 'lazy: 'sbc, 'sb: 'lazy, 'ast: 'sb, 'ctx: 'sb>(
                 if filtered.len() != 1 {
                     tyc.emit(
                         DiagBuilder2::error(format!("`{}` is ambiguous", hir.span.extract()))
+                            .code(codes::VH0002)
                             .span(hir.span), // TODO: Show which definitions are available.
                     );
                     Err(())
@@ -231,15 +236,8 @@ pub fn typeval_expr<'sbc, 'lazy: 'sbc, 'sb: 'lazy, 'ast: 'sb, 'ctx: 'sb>(
             // resolution code can probably be shared, but I'm too lazy to do
             // that now.
             assert!(!defs.is_empty());
-            if defs.len() == 1 {
-                let index_ty = IntTy::new(Dir::To, 0.into(), (defs[0].1.len() - 1).into()).into();
-                let index = ArrayIndex::Constrained(Box::new(index_ty));
-                Ok(tyc.ctx.intern_ty(ArrayTy::new(
-                    vec![index],
-                    Box::new(EnumTy::new(defs[0].0).into()),
-                )))
-            } else {
-                let (index_ty, filtered): (Option<_>, Vec<_>) = if let Some(tyctx) = tyctx {
+            let (index_ty, element_ty, filtered): (Option<_>, Option<_>, Vec<_>) =
+                if let Some(tyctx) = tyctx {
                     let tyctx_flat = tyc.ctx.deref_named_type(tyctx)?;
                     match *tyctx_flat {
                         Ty::Array(ref at) if at.indices.len() == 1 => {
@@ -247,9 +245,10 @@ pub fn typeval_expr<'sbc, 'lazy: 'sbc, 'sb: 'lazy, 'ast: 'sb, 'ctx: 'sb>(
                                 Ty::Int(ref it) => Some(it.clone()),
                                 _ => None,
                             };
-                            match *at.element.as_ref() {
+                            match *tyc.ctx.deref_named_type(at.element.as_ref())? {
                                 Ty::Enum(ref et) => (
                                     index_ty,
+                                    Some(at.element.as_ref().clone()),
                                     defs.iter()
                                         .filter_map(|def| {
                                             if def.0 == et.decl {
@@ -260,30 +259,38 @@ pub fn typeval_expr<'sbc, 'lazy: 'sbc, 'sb: 'lazy, 'ast: 'sb, 'ctx: 'sb>(
                                         })
                                         .collect(),
                                 ),
-                                _ => (index_ty, vec![]),
+                                _ => (index_ty, None, vec![]),
                             }
                         }
-                        _ => (None, vec![]),
+                        _ => (None, None, vec![]),
                     }
                 } else {
-                    (None, vec![])
+                    (None, None, vec![])
                 };
-                let index_ty = index_ty
-                    .unwrap_or_else(|| IntTy::new(Dir::To, 0.into(), (defs[0].1.len() - 1).into()))
-                    .into();
-                if filtered.len() != 1 {
-                    tyc.emit(
-                        DiagBuilder2::error(format!("`{}` is ambiguous", hir.span.extract()))
-                            .span(hir.span), // TODO: Show which definitions are available.
-                    );
-                    return Err(());
-                } else {
-                    let index = ArrayIndex::Constrained(Box::new(index_ty));
-                    Ok(tyc.ctx.intern_ty(ArrayTy::new(
-                        vec![index],
-                        Box::new(EnumTy::new(filtered[0]).into()),
-                    )))
-                }
+            // Without a type context that applies, the literal is only
+            // unambiguous if there is a single candidate. Its index then
+            // counts up from zero.
+            let (index_ty, element_ty, filtered) = if filtered.is_empty() && defs.len() == 1 {
+                (None, None, vec![defs[0].0])
+            } else {
+                (index_ty, element_ty, filtered)
+            };
+            let index_ty = index_ty
+                .unwrap_or_else(|| IntTy::new(Dir::To, 0.into(), (defs[0].1.len() - 1).into()))
+                .into();
+            if filtered.len() != 1 {
+                tyc.emit(
+                    DiagBuilder2::error(format!("`{}` is ambiguous", hir.span.extract()))
+                        .code(codes::VH0002)
+                        .span(hir.span), // TODO: Show which definitions are available.
+                );
+                Err(())
+            } else {
+                let index = ArrayIndex::Constrained(Box::new(index_ty));
+                Ok(tyc.ctx.intern_ty(ArrayTy::new(
+                    vec![index],
+                    Box::new(element_ty.unwrap_or_else(|| EnumTy::new(filtered[0]).into())),
+                )))
             }
         }
         hir::ExprData::IntegerLiteral(ref value) => {
@@ -303,6 +310,7 @@ pub fn typeval_expr<'sbc, 'lazy: 'sbc, 'sb: 'lazy, 'ast: 'sb, 'ctx: 'sb>(
             // );
             // Err(())
         }
+        hir::ExprData::PhysicalLiteral(_, unit) => physical_unit_type(tyc, unit.value.0),
         hir::ExprData::Qualified(ref tm, expr) => {
             let ty = tyc.ctx.intern_ty(Ty::Named(tm.span.into(), tm.value));
             let expr_ty = tyc.lazy_typeval(expr)?;
@@ -343,16 +351,7 @@ pub fn typeval_expr<'sbc, 'lazy: 'sbc, 'sb: 'lazy, 'ast: 'sb, 'ctx: 'sb>(
             // Resolve the overload.
             let def = resolve_overloads(tyc.ctx, defs, &req, hir.span)?;
             debugln!("unary operator `{}` resolved to {:?}", op.value, def);
-
-            tyc.emit(
-                DiagBuilder2::bug(format!(
-                    "typeval for unary operation `{}` not implemented",
-                    hir.span.extract()
-                ))
-                .span(hir.span),
-            );
-            debugln!("Defs are {:?}", defs);
-            Err(())
+            operator_return_type(tyc, def.value, hir.span)
         }
         hir::ExprData::Binary(op, ref defs, lhs, rhs) => {
             // Assemble an overload resolution requirement based on the
@@ -372,26 +371,71 @@ pub fn typeval_expr<'sbc, 'lazy: 'sbc, 'sb: 'lazy, 'ast: 'sb, 'ctx: 'sb>(
             // Resolve the overload.
             let def = resolve_overloads(tyc.ctx, defs, &req, hir.span)?;
             debugln!("binary operator `{}` resolved to {:?}", op.value, def);
-
+            operator_return_type(tyc, def.value, hir.span)
+        }
+        hir::ExprData::Attr(prefix, name) => {
+            // The only predefined attribute supported is `'event`, which
+            // yields whether the signal it is applied to changed.
+            match tyc.ctx.lazy_hir(prefix)?.data {
+                hir::ExprData::SignalName(_) => Ok(tyc.ctx.intern_ty(BOOLEAN_TYPE.named_ty())),
+                _ => {
+                    tyc.emit(
+                        DiagBuilder2::error(format!(
+                            "attribute `{}` can only be applied to a signal",
+                            name.value
+                        ))
+                        .span(hir.span),
+                    );
+                    Err(())
+                }
+            }
+        }
+        _ => {
             tyc.emit(
                 DiagBuilder2::bug(format!(
-                    "typeval for binary operation `{}` not implemented",
+                    "typeval for expression `{}` not implemented",
                     hir.span.extract()
                 ))
                 .span(hir.span),
             );
-            debugln!("Defs are {:?}", defs);
+            debugln!("It is a {:#?}", hir.data);
             Err(())
         }
+    }
+}
+
+/// Determine the physical type a unit belongs to.
+fn physical_unit_type<'sbc, 'lazy: 'sbc, 'sb: 'lazy, 'ast: 'sb, 'ctx: 'sb>(
+    tyc: &TypeckContext<'sbc, 'lazy, 'sb, 'ast, 'ctx>,
+    decl: TypeDeclRef,
+) -> Result<&'ctx Ty> {
+    if decl == TIME_TYPE.id {
+        return Ok(tyc.ctx.intern_ty(TIME_TYPE.named_ty()));
+    }
+    let name = tyc.ctx.lazy_hir(decl)?.name;
+    Ok(tyc.ctx.intern_ty(Ty::Named(name.value.into(), decl.into())))
+}
+
+/// Determine the type an operator returns.
+fn operator_return_type<'sbc, 'lazy: 'sbc, 'sb: 'lazy, 'ast: 'sb, 'ctx: 'sb>(
+    tyc: &TypeckContext<'sbc, 'lazy, 'sb, 'ast, 'ctx>,
+    def: Def,
+    span: Span,
+) -> Result<&'ctx Ty> {
+    let ty = match def {
+        Def::BuiltinOp(id) => tyc.ctx.lazy_typeval(id)?,
+        Def::Subprog(id) => tyc.ctx.lazy_typeval(id)?,
+        _ => unreachable!(),
+    };
+    match *ty {
+        Ty::Subprog(SubprogTy {
+            ret: Some(ref ret), ..
+        }) => Ok(ret.as_ref()),
         _ => {
             tyc.emit(
-                DiagBuilder2::bug(format!(
-                    "typeval for expression `{}` not implemented",
-                    hir.span.extract()
-                ))
-                .span(hir.span),
+                DiagBuilder2::error(format!("`{}` does not return a value", span.extract()))
+                    .span(span),
             );
-            debugln!("It is a {:#?}", hir.data);
             Err(())
         }
     }
@@ -4,6 +4,8 @@
 
 #![deny(missing_docs)]
 
+mod cfg;
+mod conc_stmt;
 mod expr;
 mod misc;
 mod obj_decl;
@@ -204,4 +204,38 @@ impl<'sbc, 'lazy, 'sb, 'ast, 'ctx> AddContext<'sbc, 'lazy, 'sb, 'ast, 'ctx> {
             })
             .collect()
     }
+
+    /// Add a constant interface declaration, i.e. a generic.
+    pub fn add_intf_const_decl<I>(&self, decl: &'ast ast::IntfObjDecl) -> Result<Vec<I>>
+    where
+        I: From<IntfConstRef>,
+    {
+        let ty = self.add_subtype_ind(&decl.ty)?;
+        let init = self.add_optional(&decl.default, AddContext::add_expr)?;
+        self.ctx
+            .set_type_context_optional(init, TypeCtx::TypeOf(ty.into()));
+        decl.names
+            .iter()
+            .map(|name| {
+                let (mk, id, _) = self.make::<IntfConstRef>(name.span);
+                mk.lower_to_hir(Box::new(move |_sbc| {
+                    Ok(hir::IntfConst {
+                        name: Spanned::new(name.name, name.span),
+                        ty: ty,
+                        init: init,
+                    })
+                }));
+                mk.typeval(Box::new(move |tyc| {
+                    let hir = tyc.ctx.lazy_hir(id)?;
+                    let ty = tyc.lazy_typeval(hir.ty)?;
+                    if let Some(init) = hir.init {
+                        let init_ty = tyc.lazy_typeval(init)?;
+                        tyc.must_match(ty, init_ty, tyc.ctx.span(init).unwrap());
+                    }
+                    Ok(ty)
+                }));
+                Ok(mk.finish().into())
+            })
+            .collect()
+    }
 }
@@ -6,7 +6,7 @@ use std::iter::FromIterator;
 
 use crate::common::errors::*;
 use crate::common::name::Name;
-use crate::common::score::Result;
+use crate::common::score::{NodeRef, Result};
 use crate::common::source::Spanned;
 use crate::common::util::{HasDesc, HasSpan};
 
@@ -124,9 +124,16 @@ impl<'sbc, 'lazy, 'sb, 'ast, 'ctx> AddContext<'sbc, 'lazy, 'sb, 'ast, 'ctx> {
             })
         }));
         mk.typeck(Box::new(move |tyc| {
-            let _hir = tyc.ctx.lazy_hir(id)?;
+            let hir: &hir::Stmt<hir::WaitStmt> = tyc.ctx.lazy_hir(id)?;
             // hir.stmt.cond.map(|id| tyc.must_match(tyc.ctx.lazy_typeval(id), tyc.ctx.builtin_boolean_type()));
-            // hir.stmt.timeout.map(|id| tyc.must_match(tyc.ctx.lazy_typeval(id), tyc.ctx.builtin_time_type()));
+            if let Some(timeout) = hir.stmt.timeout {
+                let ty = tyc.lazy_typeval(timeout)?;
+                tyc.must_match(
+                    tyc.ctx.builtin_time_type(),
+                    ty,
+                    tyc.ctx.span(timeout).unwrap(),
+                );
+            }
             Ok(())
         }));
         Ok(mk.finish())
@@ -210,8 +217,17 @@ impl<'sbc, 'lazy, 'sb, 'ast, 'ctx> AddContext<'sbc, 'lazy, 'sb, 'ast, 'ctx> {
     }
 
     /// Add a sig_assign statement.
+    ///
+    /// Simple assignments are lowered to HIR by the scoreboard.
     pub fn add_sig_assign_stmt(&self, stmt: &'ast ast::Stmt) -> Result<SigAssignStmtRef> {
-        self.unimp(stmt)
+        match stmt.data {
+            ast::AssignStmt { .. } => {
+                let id = SigAssignStmtRef::alloc();
+                self.ctx.set_ast(id, (self.scope, stmt));
+                Ok(id)
+            }
+            _ => self.unimp(stmt),
+        }
     }
 
     /// Add a var_assign statement.
@@ -635,6 +651,12 @@ impl<'sbc, 'lazy, 'sb, 'ast, 'ctx> AddContext<'sbc, 'lazy, 'sb, 'ast, 'ctx> {
                     let term = ctx.termify_paren_elems(agg)?;
                     hir::Target::Aggregate(ctx.term_to_aggregate(term)?.value)
                 }
+                ast::AssignTarget::External(ref name) => {
+                    self.emit(
+                        DiagBuilder2::error("external names not implemented").span(name.span),
+                    );
+                    return Err(());
+                }
             },
             ast.span,
         ))
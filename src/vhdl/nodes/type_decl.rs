@@ -19,8 +19,7 @@ impl<'sbc, 'lazy, 'sb, 'ast, 'ctx> AddContext<'sbc, 'lazy, 'sb, 'ast, 'ctx> {
     /// Add a type declaration.
     pub fn add_type_decl(&self, decl: &'ast ast::TypeDecl) -> Result<TypeDeclRef> {
         let (mk, id, scope) = self.make(decl.span);
-        self.ctx
-            .define(scope, decl.name.map_into(), Def::Type(id))?;
+        self.ctx.set_ast(id, (scope, decl));
         mk.lower_to_hir(Box::new(move |sbc| {
             let ctx = AddContext::new(sbc, scope);
             Ok(hir::TypeDecl {
@@ -137,6 +136,16 @@ impl<'sbc, 'lazy, 'sb, 'ast, 'ctx> AddContext<'sbc, 'lazy, 'sb, 'ast, 'ctx> {
                                         return Err(());
                                     }
                                 };
+                                if !value.is_integer() {
+                                    self.emit(
+                                        DiagBuilder2::error(format!(
+                                            "`{}` is not an integer multiple of a unit",
+                                            term.span.extract()
+                                        ))
+                                        .span(term.span),
+                                    );
+                                    return Err(());
+                                }
                                 if unit.value.unwrap_old().0 != id {
                                     self.emit(
                                         DiagBuilder2::error(format!(
@@ -152,7 +161,7 @@ impl<'sbc, 'lazy, 'sb, 'ast, 'ctx> AddContext<'sbc, 'lazy, 'sb, 'ast, 'ctx> {
                                         .span(unit.span),
                                     );
                                 }
-                                Some((value, unit.value.unwrap_old().1))
+                                Some((value.to_integer(), unit.value.unwrap_old().1))
                             } else {
                                 None
                             };
@@ -272,10 +281,8 @@ impl<'sbc, 'lazy, 'sb, 'ast, 'ctx> AddContext<'sbc, 'lazy, 'sb, 'ast, 'ctx> {
                 hir::TypeData::Record(fields)
             }
 
-            ast::ProtectedType(..) => {
-                self.emit(DiagBuilder2::fatal("protected types not implemented").span(name.span));
-                return Err(());
-            }
+            ast::ProtectedType(..) => hir::TypeData::Protected,
+            ast::ProtectedBody(..) => hir::TypeData::ProtectedBody,
         };
         Ok(Spanned::new(td, data.span))
     }
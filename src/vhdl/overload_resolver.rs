@@ -6,12 +6,14 @@
 
 use std::collections::{HashMap, HashSet};
 
+use crate::common::codes;
 use crate::common::errors::*;
 use crate::common::name::Name;
 use crate::common::score::Result;
 use crate::common::source::{Span, Spanned};
 
-use crate::score::{Def, ScoreContext};
+use crate::builtin::{DELAY_LENGTH_TYPE, INTEGER_TYPE, NATURAL_TYPE, POSITIVE_TYPE, TIME_TYPE};
+use crate::score::{Def, ScoreContext, TypeMarkRef};
 use crate::ty::Ty;
 
 /// A type requirement on an overloaded entity.
@@ -158,13 +160,38 @@ impl<'ctx> Default for TypeReq<'ctx> {
 }
 
 /// Check if two types match.
-fn are_types_matching(a: &Ty, b: &Ty) -> bool {
+pub(crate) fn are_types_matching(a: &Ty, b: &Ty) -> bool {
     match (a, b) {
+        // The predefined integer subtypes share the base type `integer`, and
+        // integer literals implicitly convert to them.
+        (a, b) if is_predefined_integer(a) && is_predefined_integer(b) => true,
+        // Likewise for `time` and its subtype `delay_length`.
+        (a, b) if is_predefined_time(a) && is_predefined_time(b) => true,
         (&Ty::Named(_, ia), &Ty::Named(_, ib)) => ia == ib,
         (a, b) => a == b,
     }
 }
 
+/// Check if a type is `integer`, one of its predefined subtypes, or a
+/// universal integer.
+fn is_predefined_integer(ty: &Ty) -> bool {
+    match *ty {
+        Ty::UniversalInt => true,
+        Ty::Named(_, TypeMarkRef::Type(id)) => {
+            id == INTEGER_TYPE.id || id == NATURAL_TYPE.id || id == POSITIVE_TYPE.id
+        }
+        _ => false,
+    }
+}
+
+/// Check if a type is `time` or its predefined subtype `delay_length`.
+fn is_predefined_time(ty: &Ty) -> bool {
+    match *ty {
+        Ty::Named(_, TypeMarkRef::Type(id)) => id == TIME_TYPE.id || id == DELAY_LENGTH_TYPE.id,
+        _ => false,
+    }
+}
+
 /// Reduce overloaded definitions.
 pub fn reduce_overloads(
     ctx: &ScoreContext,
@@ -233,7 +260,9 @@ pub fn resolve_overloads(
         Err(())
     } else if reduced.len() > 1 {
         ctx.emit(
-            DiagBuilder2::error(format!("`{}` is ambiguous", span.extract())).span(span), // TODO: Show implementations that matched.
+            DiagBuilder2::error(format!("`{}` is ambiguous", span.extract()))
+                .code(codes::VH0002)
+                .span(span), // TODO: Show implementations that matched.
         );
         debugln!("matching definitions: {:#?}", reduced);
         Err(())
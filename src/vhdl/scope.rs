@@ -6,6 +6,7 @@
 
 use std::collections::{HashMap, HashSet};
 
+use crate::common::codes;
 use crate::common::errors::*;
 use crate::common::score::Result;
 use crate::common::source::Spanned;
@@ -75,8 +76,8 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
             debugln!("define `{}` as {:?} in scope {:?}", name.value, def, scope);
         }
         self.with_scope(scope, |scope| match def {
-            // Handle overloadable cases.
-            Def::Enum(_) => {
+            // Handle overloadable cases. See IEEE 1076-2008 section 4.5.1.
+            Def::Enum(_) | Def::Subprog(_) | Def::SubprogInst(_) => {
                 scope
                     .defs
                     .entry(name.value)
@@ -93,6 +94,7 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
                 if let Some(existing) = ins {
                     self.emit(
                         DiagBuilder2::error(format!("`{}` has already been declared", name.value))
+                            .code(codes::VH0003)
                             .span(name.span)
                             .add_note("Previous declaration was here:")
                             .span(existing.last().unwrap().span),
@@ -11,6 +11,7 @@ use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::hash::{Hash, Hasher};
 
+use crate::common::codes;
 use crate::common::errors::*;
 use crate::common::score::Result;
 use crate::common::source::Spanned;
@@ -148,6 +149,7 @@ impl<'t> ScopeData<'t> {
                 if let Some(existing) = ins {
                     ctx.emit(
                         DiagBuilder2::error(format!("`{}` has already been declared", name.value))
+                            .code(codes::VH0003)
                             .span(name.span)
                             .add_note("Previous declaration was here:")
                             .span(existing.last().unwrap().span),
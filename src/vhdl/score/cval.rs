@@ -3,6 +3,7 @@
 //! This module implements constant value computation.
 
 use crate::score::*;
+use num::{BigRational, Zero};
 
 // Calculate the constant value of an expression.
 impl_make!(self, id: ExprRef => &Const {
@@ -14,6 +15,22 @@ impl_make!(self, id: ExprRef => &Const {
         // Float literals.
         hir::ExprData::FloatLiteral(ref c) => self.intern_const(c.clone()),
 
+        // Physical literals, which are counted in the primary unit of their
+        // type. Fractional values are rounded to the nearest multiple of the
+        // primary unit.
+        hir::ExprData::PhysicalLiteral(ref value, unit) => {
+            let UnitRef(decl, index) = unit.value;
+            match *self.deref_named_type(self.ty(decl)?)? {
+                Ty::Physical(ref ty) => self.intern_const(ConstInt::new(
+                    Some(ty.base.clone()),
+                    (value * BigRational::from_integer(ty.units[index].abs.clone()))
+                        .round()
+                        .to_integer(),
+                )),
+                _ => unreachable!(),
+            }
+        }
+
         // Unary operators.
         hir::ExprData::Unary(op, _, arg_id) => {
             let arg = self.const_value(arg_id)?;
@@ -32,6 +49,13 @@ impl_make!(self, id: ExprRef => &Const {
             }
         }
 
+        // Binary operators.
+        hir::ExprData::Binary(op, _, lhs_id, rhs_id) => {
+            let lhs = self.const_value(lhs_id)?;
+            let rhs = self.const_value(rhs_id)?;
+            self.const_binary(op, lhs, rhs, hir.span)?
+        }
+
         // Ranges.
         hir::ExprData::Range(dir, lb_id, rb_id) => {
             // TODO: Determine the type of ourself, then make sure the const
@@ -58,6 +82,107 @@ impl_make!(self, id: ExprRef => &Const {
         // Names.
         hir::ExprData::Name(Def::Enum(EnumRef(decl, index)), _) => self.intern_const(ConstEnum::new(decl, index)),
 
+        // Enum literals, which are disambiguated by the type of the expression.
+        hir::ExprData::EnumName(ref defs) => {
+            let ty = self.deref_named_type(self.lazy_typeval(id)?)?;
+            let found = match *ty {
+                Ty::Enum(ref ty) => defs.iter().find(|d| d.value.0 == ty.decl),
+                _ => None,
+            };
+            match found {
+                Some(d) => self.intern_const(ConstEnum::new(d.value.0, d.value.1)),
+                None => {
+                    self.emit(
+                        DiagBuilder2::error(format!("`{}` is not a literal of type `{}`", hir.span.extract(), ty))
+                        .span(hir.span)
+                    );
+                    return Err(());
+                }
+            }
+        }
+
+        // Constants.
+        hir::ExprData::ConstName(const_id) => {
+            let decl = self.lazy_hir(const_id)?;
+            match decl.decl.init {
+                Some(init) => self.const_value(init)?,
+                None => {
+                    self.emit(
+                        DiagBuilder2::error(format!("value of deferred constant `{}` is not known", decl.name.value))
+                        .span(hir.span)
+                    );
+                    return Err(());
+                }
+            }
+        }
+
+        // Generics, which take their value from the generic environment or
+        // fall back to their default value.
+        hir::ExprData::GenericName(generic_id) => {
+            let decl = self.lazy_hir(generic_id)?;
+            match (self.generic_value(generic_id), decl.init) {
+                (Some(value), _) => value,
+                (None, Some(init)) => {
+                    let ty = self.lazy_typeval(generic_id)?;
+                    self.cast_const(self.const_value(init)?, ty)?
+                }
+                (None, None) => {
+                    self.emit(
+                        DiagBuilder2::error(format!("generic `{}` has no value", decl.name.value))
+                        .span(hir.span)
+                        .add_note("Assign a value in a generic map, or declare a default value:")
+                        .span(decl.name.span)
+                    );
+                    return Err(());
+                }
+            }
+        }
+
+        // Generate parameters, which are only known during elaboration.
+        hir::ExprData::GenParamName(gen_id) => match self.generic_value(gen_id) {
+            Some(value) => value,
+            None => {
+                self.emit(
+                    DiagBuilder2::error(format!("value of generate parameter `{}` is not known", hir.span.extract()))
+                    .span(hir.span)
+                );
+                return Err(());
+            }
+        },
+
+        // String literals, which are disambiguated by the element type of the
+        // expression.
+        hir::ExprData::StringLiteral(ref defs) => {
+            let ty = self.deref_named_type(self.lazy_typeval(id)?)?;
+            let element_ty = match *ty {
+                Ty::Array(ref ty) => Some(self.deref_named_type(&ty.element)?),
+                _ => None,
+            };
+            let found = match element_ty {
+                Some(&Ty::Enum(ref ty)) => defs.iter().find(|d| d.0 == ty.decl),
+                _ => None,
+            };
+            match found {
+                Some(&(_, ref indices)) if indices.is_empty() => self.intern_const(Const::Null),
+                Some(&(decl, ref indices)) => self.intern_const(ConstArray::new(
+                    indices.iter().map(|&index| ConstEnum::new(decl, index).into()).collect()
+                )),
+                None => {
+                    self.emit(
+                        DiagBuilder2::error(format!("`{}` is not a literal of type `{}`", hir.span.extract(), ty))
+                        .span(hir.span)
+                    );
+                    return Err(());
+                }
+            }
+        }
+
+        // Aggregates.
+        hir::ExprData::Aggregate(agg_id) => {
+            let ty = self.lazy_typeval(id)?;
+            self.const_aggregate(agg_id, ty)?
+        }
+
         // All other expressions cannot be turned into a constant value.
         _ => {
             self.emit(
@@ -68,3 +193,285 @@ impl_make!(self, id: ExprRef => &Const {
         }
     })
 });
+
+impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
+    /// Cast a constant to the type of the object it is assigned to.
+    ///
+    /// This gives integer literals the bounds of their target type, which is
+    /// needed to map them to LLHD.
+    pub fn cast_const(&self, konst: &'ctx Const, ty: &Ty) -> Result<&'ctx Const> {
+        Ok(match (konst, self.deref_named_type(ty)?) {
+            (&Const::Int(ref k), &Ty::Int(ref ty)) if k.ty.as_ref() != Some(ty) => {
+                self.intern_const(ConstInt::new(Some(ty.clone()), k.value.clone()))
+            }
+            (&Const::Int(ref k), &Ty::Physical(ref ty)) if k.ty.as_ref() != Some(&ty.base) => {
+                self.intern_const(ConstInt::new(Some(ty.base.clone()), k.value.clone()))
+            }
+            _ => konst,
+        })
+    }
+
+    /// Check whether a constant lies within the range of a type.
+    ///
+    /// Constants other than integers and physical values are considered to be
+    /// in range.
+    pub fn const_in_range(&self, konst: &Const, ty: &Ty) -> Result<bool> {
+        Ok(match (konst, self.deref_named_type(ty)?) {
+            (&Const::Int(ref k), &Ty::Int(ref ty))
+            | (&Const::Int(ref k), &Ty::Physical(PhysicalTy { base: ref ty, .. })) => {
+                let (lo, hi) = match ty.dir {
+                    Dir::To => (&ty.left_bound, &ty.right_bound),
+                    Dir::Downto => (&ty.right_bound, &ty.left_bound),
+                };
+                &k.value >= lo && &k.value <= hi
+            }
+            _ => true,
+        })
+    }
+
+    /// Apply a binary operator to two constant values.
+    pub fn const_binary(
+        &self,
+        op: Spanned<BinaryOp>,
+        lhs: &Const,
+        rhs: &Const,
+        span: Span,
+    ) -> Result<&'ctx Const> {
+        let boolean = |value: bool| ConstEnum::new(BOOLEAN_TYPE.id, value as usize);
+        Ok(match (op.value, lhs, rhs) {
+            (BinaryOp::Rel(rel), &Const::Int(ref a), &Const::Int(ref b)) => {
+                self.intern_const(boolean(compare_rel(rel, a.value.cmp(&b.value))))
+            }
+            (BinaryOp::Rel(rel), &Const::Enum(ref a), &Const::Enum(ref b)) if a.decl == b.decl => {
+                self.intern_const(boolean(compare_rel(rel, a.index.cmp(&b.index))))
+            }
+            (BinaryOp::Logical(log), &Const::Enum(ref a), &Const::Enum(ref b))
+                if a.decl == b.decl && (a.decl == BOOLEAN_TYPE.id || a.decl == BIT_TYPE.id) =>
+            {
+                let decl = a.decl;
+                let (a, b) = (a.index != 0, b.index != 0);
+                let value = match log {
+                    LogicalOp::And => a && b,
+                    LogicalOp::Or => a || b,
+                    LogicalOp::Nand => !(a && b),
+                    LogicalOp::Nor => !(a || b),
+                    LogicalOp::Xor => a != b,
+                    LogicalOp::Xnor => a == b,
+                };
+                self.intern_const(ConstEnum::new(decl, value as usize))
+            }
+            (_, &Const::Int(ref a), &Const::Int(ref b)) => {
+                let (a, b) = (&a.value, &b.value);
+                let value = match op.value {
+                    BinaryOp::Add => a + b,
+                    BinaryOp::Sub => a - b,
+                    BinaryOp::Mul => a * b,
+                    BinaryOp::Div | BinaryOp::Mod | BinaryOp::Rem if b.is_zero() => {
+                        self.emit(
+                            DiagBuilder2::error(format!("`{}` divides by zero", span.extract()))
+                                .span(span),
+                        );
+                        return Err(());
+                    }
+                    BinaryOp::Div => a / b,
+                    BinaryOp::Rem => a % b,
+                    BinaryOp::Mod => ((a % b) + b) % b,
+                    BinaryOp::Pow => match b.to_usize() {
+                        Some(b) => num::pow(a.clone(), b),
+                        None => {
+                            self.emit(
+                                DiagBuilder2::error(format!(
+                                    "exponent in `{}` must be a natural number",
+                                    span.extract()
+                                ))
+                                .span(span),
+                            );
+                            return Err(());
+                        }
+                    },
+                    _ => return self.unsupported_const_binary(op, span),
+                };
+                self.intern_const(ConstInt::new(None, value))
+            }
+            _ => return self.unsupported_const_binary(op, span),
+        })
+    }
+
+    fn unsupported_const_binary(&self, op: Spanned<BinaryOp>, span: Span) -> Result<&'ctx Const> {
+        self.emit(
+            DiagBuilder2::error(format!(
+                "operator `{}` not supported in constant expression `{}`",
+                op.value,
+                span.extract()
+            ))
+            .span(op.span),
+        );
+        Err(())
+    }
+
+    /// Calculate the constant value of an aggregate.
+    ///
+    /// See IEEE 1076-2008 section 9.3.3.
+    pub fn const_aggregate(&self, id: AggregateRef, ty: &'ctx Ty) -> Result<&'ctx Const> {
+        let hir = self.lazy_hir(id)?;
+        let others = hir.others.map(|o| o.value);
+        match *self.deref_named_type(ty)? {
+            Ty::Record(ref ty) => {
+                let mut fields = Vec::new();
+                for (index, &(name, ref field_ty)) in ty.fields.iter().enumerate() {
+                    let named = match hir.named {
+                        hir::AggregateKind::Record(ref named) => named
+                            .iter()
+                            .find(|f| f.value.0.iter().any(|c| c.value == name))
+                            .map(|f| f.value.1.value),
+                        _ => None,
+                    };
+                    let expr = hir
+                        .positional
+                        .get(index)
+                        .map(|p| p.value)
+                        .or(named)
+                        .or(others);
+                    match expr {
+                        Some(expr) => {
+                            fields.push(self.cast_const(self.const_value(expr)?, field_ty)?.clone())
+                        }
+                        None => {
+                            self.emit(
+                                DiagBuilder2::error(format!(
+                                    "aggregate `{}` has no value for field `{}`",
+                                    hir.span.extract(),
+                                    name
+                                ))
+                                .span(hir.span),
+                            );
+                            return Err(());
+                        }
+                    }
+                }
+                Ok(self.intern_const(ConstRecord::new(fields)))
+            }
+            Ty::Array(ref ty) => {
+                let element_ty: &Ty = if ty.indices.len() > 1 {
+                    self.intern_ty(ArrayTy::new(ty.indices[1..].to_vec(), ty.element.clone()))
+                } else {
+                    &ty.element
+                };
+
+                // Unconstrained arrays take their length from the aggregate.
+                let len = match (&ty.indices[0], &hir.named, others) {
+                    (&ArrayIndex::Unbounded(_), &hir::AggregateKind::Both, None) => {
+                        hir.positional.len()
+                    }
+                    (index, ..) => self.array_index_len(ty, index)?,
+                };
+                if hir.positional.len() > len {
+                    self.emit(
+                        DiagBuilder2::error(format!(
+                            "aggregate `{}` has {} elements, but the array only has {}",
+                            hir.span.extract(),
+                            hir.positional.len(),
+                            len
+                        ))
+                        .span(hir.span),
+                    );
+                    return Err(());
+                }
+                let mut elements = vec![None; len];
+                for (index, pos) in hir.positional.iter().enumerate() {
+                    elements[index] = Some(pos.value);
+                }
+                if let hir::AggregateKind::Array(ref named) = hir.named {
+                    for field in named {
+                        for choice in &field.value.0 {
+                            let index = match choice.value {
+                                hir::ArrayChoice::Expr(index) => self.const_value(index)?,
+                                hir::ArrayChoice::DiscreteRange(..) => {
+                                    self.emit(
+                                        DiagBuilder2::error("ranges in aggregates not implemented")
+                                            .span(choice.span),
+                                    );
+                                    return Err(());
+                                }
+                            };
+                            match self.const_array_offset(ty.indices[0].ty(), index)? {
+                                Some(offset) if offset < len => {
+                                    elements[offset] = Some(field.value.1.value)
+                                }
+                                _ => {
+                                    self.emit(
+                                        DiagBuilder2::error(format!(
+                                            "index `{}` is out of bounds",
+                                            choice.span.extract()
+                                        ))
+                                        .span(choice.span),
+                                    );
+                                    return Err(());
+                                }
+                            }
+                        }
+                    }
+                }
+                let mut values = Vec::new();
+                for expr in elements {
+                    match expr.or(others) {
+                        Some(expr) => values.push(
+                            self.cast_const(self.const_value(expr)?, element_ty)?
+                                .clone(),
+                        ),
+                        None => {
+                            self.emit(
+                                DiagBuilder2::error(format!(
+                                    "aggregate `{}` does not cover all {} elements of the array",
+                                    hir.span.extract(),
+                                    len
+                                ))
+                                .span(hir.span),
+                            );
+                            return Err(());
+                        }
+                    }
+                }
+                if values.is_empty() {
+                    Ok(self.intern_const(Const::Null))
+                } else {
+                    Ok(self.intern_const(ConstArray::new(values)))
+                }
+            }
+            _ => {
+                self.emit(
+                    DiagBuilder2::error("expression does not have a constant value").span(hir.span),
+                );
+                Err(())
+            }
+        }
+    }
+
+    /// Calculate the position of an index within an array.
+    ///
+    /// Returns `None` if the index lies before the start of the index type.
+    fn const_array_offset(&self, index_ty: &Ty, index: &Const) -> Result<Option<usize>> {
+        Ok(match (self.deref_named_type(index_ty)?, index) {
+            (&Ty::Int(ref ty), &Const::Int(ref k)) => match ty.dir {
+                Dir::To => &k.value - &ty.left_bound,
+                Dir::Downto => &ty.left_bound - &k.value,
+            }
+            .to_usize(),
+            (&Ty::Enum(_), &Const::Enum(ref k)) => Some(k.index),
+            _ => None,
+        })
+    }
+}
+
+/// Check whether an ordering satisfies a relational operator.
+fn compare_rel(op: RelationalOp, ord: std::cmp::Ordering) -> bool {
+    use std::cmp::Ordering::*;
+    match op {
+        RelationalOp::Eq => ord == Equal,
+        RelationalOp::Neq => ord != Equal,
+        RelationalOp::Lt => ord == Less,
+        RelationalOp::Leq => ord != Greater,
+        RelationalOp::Gt => ord == Greater,
+        RelationalOp::Geq => ord != Less,
+    }
+}
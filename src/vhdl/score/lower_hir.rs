@@ -7,7 +7,9 @@
 //! lowering to HIR.
 
 use crate::add_ctx::AddContext;
+use crate::common::lints;
 use crate::common::score::NodeRef;
+use crate::defs::DefsContext;
 use crate::make_ctx::MakeContext;
 use crate::op::*;
 use crate::score::*;
@@ -50,9 +52,8 @@ macro_rules! unimp_msg {
 impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
     /// Unpack an AST expression.
     pub fn unpack_expr(&self, ast: &'ast ast::Expr, scope_id: ScopeRef) -> Result<ExprRef> {
-        let id = ExprRef::new(NodeId::alloc());
-        self.set_ast(id, (scope_id, ast));
-        Ok(id)
+        let ctx = AddContext::new(self, scope_id);
+        ctx.add_expr(ast)
     }
 
     /// Unpack an AST subtype indication.
@@ -154,9 +155,7 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
                     refs.push(subid.into());
                 }
                 ast::DeclItem::TypeDecl(ref decl) => {
-                    let subid = TypeDeclRef(NodeId::alloc());
-                    self.set_ast(subid, (scope_id, decl));
-                    refs.push(subid.into());
+                    refs.push(ctx.add_type_decl(decl)?.into());
                 }
                 ast::DeclItem::SubtypeDecl(ref decl) => {
                     let subid = SubtypeDeclRef(NodeId::alloc());
@@ -232,6 +231,7 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
                     }
                 },
                 ast::DeclItem::UseClause(..) => (),
+                ast::DeclItem::PslDecl(ref decl) => self.emit_psl_decl_ignored(decl),
                 ref wrong => {
                     self.emit(
                         DiagBuilder2::error(format!(
@@ -302,9 +302,7 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
                     refs.push(subid.into());
                 }
                 ast::DeclItem::TypeDecl(ref decl) => {
-                    let subid = TypeDeclRef(NodeId::alloc());
-                    self.set_ast(subid, (scope_id, decl));
-                    refs.push(subid.into());
+                    refs.push(ctx.add_type_decl(decl)?.into());
                 }
                 ast::DeclItem::SubtypeDecl(ref decl) => {
                     let subid = SubtypeDeclRef(NodeId::alloc());
@@ -441,9 +439,7 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
                     refs.push(subid.into());
                 }
                 ast::DeclItem::TypeDecl(ref decl) => {
-                    let subid = TypeDeclRef(NodeId::alloc());
-                    self.set_ast(subid, (scope_id, decl));
-                    refs.push(subid.into());
+                    refs.push(ctx.add_type_decl(decl)?.into());
                 }
                 ast::DeclItem::SubtypeDecl(ref decl) => {
                     let subid = SubtypeDeclRef(NodeId::alloc());
@@ -466,7 +462,7 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
                     ast::ObjKind::SharedVar => {
                         self.emit(
                             DiagBuilder2::error(
-                                "not a variable; shared variables may not appear in a package body",
+                                "not a variable; shared variables may not appear in a subprogram",
                             )
                             .span(decl.human_span()),
                         );
@@ -545,16 +541,17 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
                 DiagBuilder2::bug(format!("{} not implemented", s.desc())).span(s.human_span()),
             )
         };
+        let ctx = AddContext::new(self, scope_id);
         for stmt in stmts {
             match stmt.data {
                 ast::BlockStmt { .. } => {
                     unimp(stmt);
                     had_fails = true;
                 }
-                ast::InstOrCallStmt { .. } => {
-                    unimp(stmt);
-                    had_fails = true;
-                }
+                ast::InstOrCallStmt { .. } => match ctx.add_comp_inst_stmt(stmt) {
+                    Ok(id) => refs.push(id.into()),
+                    Err(()) => had_fails = true,
+                },
                 ast::AssertStmt { .. } => {
                     unimp(stmt);
                     had_fails = true;
@@ -567,18 +564,18 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
                     unimp(stmt);
                     had_fails = true;
                 }
-                ast::IfGenStmt { .. } => {
-                    unimp(stmt);
-                    had_fails = true;
-                }
+                ast::IfGenStmt { .. } => match ctx.add_if_gen_stmt(stmt) {
+                    Ok(id) => refs.push(id.into()),
+                    Err(()) => had_fails = true,
+                },
                 ast::CaseGenStmt { .. } => {
                     unimp(stmt);
                     had_fails = true;
                 }
-                ast::ForGenStmt { .. } => {
-                    unimp(stmt);
-                    had_fails = true;
-                }
+                ast::ForGenStmt { .. } => match ctx.add_for_gen_stmt(stmt) {
+                    Ok(id) => refs.push(id.into()),
+                    Err(()) => had_fails = true,
+                },
 
                 ast::ProcStmt { .. } => {
                     let id = ProcessStmtRef(NodeId::alloc());
@@ -586,6 +583,16 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
                     refs.push(id.into());
                 }
 
+                ast::PslDirective { ref kind, .. } => {
+                    self.emit(
+                        DiagBuilder2::lint(
+                            lints::UNSUPPORTED,
+                            format!("PSL `{}` directive is not checked", kind.value.keyword()),
+                        )
+                        .span(kind.span),
+                    );
+                }
+
                 ref wrong => {
                     self.emit(
                         DiagBuilder2::error(format!(
@@ -611,6 +618,17 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
         }
     }
 
+    /// Warn about a PSL declaration, which is parsed but otherwise ignored.
+    fn emit_psl_decl_ignored(&self, decl: &ast::PslDecl) {
+        self.emit(
+            DiagBuilder2::lint(
+                lints::UNSUPPORTED,
+                format!("PSL {} is ignored", decl.data.desc()),
+            )
+            .span(decl.human_span()),
+        );
+    }
+
     /// Unpack a slice of sequential statements.
     ///
     /// See IEEE 1076-2008 section 10.
@@ -665,6 +683,7 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
                 if !defs.is_empty() {
                     self.emit(
                         DiagBuilder2::error(format!("`{}` is ambiguous", res_span.extract()))
+                            .code(codes::VH0002)
                             .span(res_span),
                     );
                     return Err(());
@@ -678,6 +697,10 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
                 );
                 Err(())
             }
+            ast::AssignTarget::External(ref name) => {
+                self.emit(DiagBuilder2::error("external names not implemented").span(name.span));
+                Err(())
+            }
         }
     }
 
@@ -738,6 +761,7 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
                 ast::DelayMech::Inertial => hir::DelayMechanism::Inertial,
                 ast::DelayMech::InertialReject(ref expr) => {
                     let expr = self.unpack_expr(expr, scope_id)?;
+                    self.set_type_context(expr, self.builtin_time_type());
                     hir::DelayMechanism::RejectInertial(expr)
                 }
             })
@@ -794,8 +818,7 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
                     after: match *after {
                         Some(ref expr) => {
                             let expr = self.unpack_expr(expr, scope_id)?;
-                            // TODO: Set the type context for the expression.
-                            // self.set_type_context(expr, TypeCtx::Type(/* somehow refer to builtin `time` type */));
+                            self.set_type_context(expr, self.builtin_time_type());
                             Some(expr)
                         }
                         None => None,
@@ -966,22 +989,12 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
                     into.push(id.into());
                 }
                 ast::IntfDecl::ObjDecl(
-                    ref
-                    decl
-                    @
-                    ast::IntfObjDecl {
+                    ref decl @ ast::IntfObjDecl {
                         kind: ast::IntfObjKind::Const,
                         ..
                     },
                 ) => {
-                    let ty = ctx.add_subtype_ind(&decl.ty)?;
-                    // let ty = SubtypeIndRef(NodeId::alloc());
-                    // self.set_ast(ty, (scope_id, &decl.ty));
-                    for name in &decl.names {
-                        let id = IntfConstRef(NodeId::alloc());
-                        self.set_ast(id, (scope_id, decl, ty, name));
-                        into.push(id.into());
-                    }
+                    into.extend(ctx.add_intf_const_decl::<GenericRef>(decl)?);
                 }
                 ref wrong => {
                     self.emit(
@@ -1002,6 +1015,46 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
         }
     }
 
+    /// Unpack the signals of a port clause.
+    ///
+    /// Declarations other than signals are reported and skipped.
+    pub fn unpack_ports(
+        &self,
+        scope_id: ScopeRef,
+        decls: &'ast [ast::IntfDecl],
+        into: &mut Vec<IntfSignalRef>,
+    ) -> Result<()> {
+        let ctx = AddContext::new(self, scope_id);
+        for decl in decls {
+            match *decl {
+                ast::IntfDecl::ObjDecl(
+                    ref decl @ ast::IntfObjDecl {
+                        kind: ast::IntfObjKind::Signal,
+                        ..
+                    },
+                ) => {
+                    let ty = ctx.add_subtype_ind(&decl.ty)?;
+                    for name in &decl.names {
+                        let subid = IntfSignalRef(NodeId::alloc());
+                        self.set_ast(subid, (scope_id, decl, ty, name));
+                        into.push(subid);
+                    }
+                }
+                ref wrong => {
+                    self.emit(
+                        DiagBuilder2::error(format!(
+                            "a {} cannot appear in a port clause",
+                            wrong.desc()
+                        ))
+                        .span(wrong.human_span()),
+                    );
+                    continue;
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Unpack a generic map from a parenthesized list of elements.
     ///
     /// See IEEE 1076-2008 section 6.5.7.2.
@@ -1029,34 +1082,13 @@ impl_make!(self, id: EntityRef => &hir::Entity {
     };
     let mut port_spans = Vec::new();
     let mut generic_spans = Vec::new();
-    let ctx = AddContext::new(self, id.into());
     for decl in &ast.decls {
         match *decl {
             // Port clauses
             ast::DeclItem::PortgenClause(_, Spanned{ value: ast::PortgenKind::Port, span }, ref decls) => {
                 // For ports only signal interface declarations are allowed.
                 port_spans.push(span);
-                for decl in &decls.value {
-                    match *decl {
-                        ast::IntfDecl::ObjDecl(ref decl @ ast::IntfObjDecl{ kind: ast::IntfObjKind::Signal, .. }) => {
-                            let ty = ctx.add_subtype_ind(&decl.ty)?;
-                            // let ty = SubtypeIndRef(NodeId::alloc());
-                            // self.set_ast(ty, (id.into(), &decl.ty));
-                            for name in &decl.names {
-                                let subid = IntfSignalRef(NodeId::alloc());
-                                self.set_ast(subid, (id.into(), decl, ty, name));
-                                entity.ports.push(subid);
-                            }
-                        }
-                        ref wrong => {
-                            self.emit(
-                                DiagBuilder2::error(format!("a {} cannot appear in a port clause", wrong.desc()))
-                                .span(wrong.human_span())
-                            );
-                            continue;
-                        }
-                    }
-                }
+                self.unpack_ports(id.into(), &decls.value, &mut entity.ports)?;
             }
 
             // Generic clauses
@@ -1080,6 +1112,99 @@ impl_make!(self, id: EntityRef => &hir::Entity {
     Ok(self.sb.arenas.hir.entity.alloc(entity))
 });
 
+// Lower a component declaration to HIR.
+impl_make!(self, id: CompDeclRef => &hir::Component {
+    let (parent, ast) = self.ast(id);
+    let mut comp = hir::Component {
+        parent: parent,
+        name: ast.name,
+        generics: Vec::new(),
+        ports: Vec::new(),
+    };
+    if let Some(ref generics) = ast.generics {
+        self.unpack_generics(id.into(), &generics.value, &mut comp.generics)?;
+    }
+    if let Some(ref ports) = ast.ports {
+        self.unpack_ports(id.into(), &ports.value, &mut comp.ports)?;
+    }
+    Ok(self.sb.arenas.hir.component.alloc(comp))
+});
+
+// Lower a configuration declaration to HIR.
+impl_make!(self, id: CfgRef => &hir::Cfg {
+    let (lib, ctx_id, ast) = self.ast(id);
+    let ctx = AddContext::new(self, self.make_ctx_items_scope(ctx_id, None)?.into());
+
+    // The configured entity is declared in the same library as the
+    // configuration. See IEEE 1076-2008 section 3.4.1.
+    let entity = match AddContext::new(self, lib.into()).resolve_unit_name(&ast.target)? {
+        (Some(Def::Entity(entity)), span, []) => Spanned::new(entity, span),
+        _ => {
+            self.emit(
+                DiagBuilder2::error(format!("`{}` is not an entity", ast.target.span.extract()))
+                .span(ast.target.span)
+            );
+            return Err(());
+        }
+    };
+
+    // A configuration declaration contains exactly one block configuration,
+    // for the configured architecture. See IEEE 1076-2008 section 3.4.1.
+    let mut blocks = ast.decls.iter().filter_map(|decl| match *decl {
+        ast::DeclItem::BlockCompCfg(ref cfg) => Some(cfg),
+        _ => None,
+    });
+    for decl in &ast.decls {
+        match *decl {
+            ast::DeclItem::BlockCompCfg(..) => (),
+            ref wrong => {
+                self.emit(
+                    DiagBuilder2::error(format!("{} in a configuration declaration not supported", wrong.desc()))
+                    .span(wrong.human_span())
+                );
+                return Err(());
+            }
+        }
+    }
+    let block = match (blocks.next(), blocks.next()) {
+        (Some(block), None) => ctx.add_block_cfg(entity.value, block)?,
+        _ => {
+            self.emit(
+                DiagBuilder2::error(format!("configuration `{}` must contain exactly one block configuration", ast.name.value))
+                .span(ast.name.span)
+            );
+            return Err(());
+        }
+    };
+    Ok(self.sb.arenas.hir.cfg.alloc(hir::Cfg {
+        name: ast.name,
+        entity: entity,
+        block: block,
+    }))
+});
+
+// Lower a configuration specification to HIR.
+impl_make!(self, id: CfgSpecRef => &hir::CfgSpec {
+    let (scope_id, ast) = self.ast(id);
+    let ctx = AddContext::new(self, scope_id);
+    let spec = ctx.add_inst_spec(&ast.spec)?;
+    let binding = match ctx.add_binding_ind(&ast.bind)? {
+        Some(binding) => binding,
+        None => {
+            self.emit(
+                DiagBuilder2::error("configuration specification must bind the instances")
+                .span(ast.span)
+                .add_note("Use `use entity <name>`, `use configuration <name>`, or `use open`.")
+            );
+            return Err(());
+        }
+    };
+    Ok(self.sb.arenas.hir.cfg_spec.alloc(hir::CfgSpec {
+        spec: spec,
+        binding: binding,
+    }))
+});
+
 // Lower an interface signal to HIR.
 impl_make!(self, id: IntfSignalRef => &hir::IntfSignal {
     let (scope_id, decl, subty_id, ident) = self.ast(id);
@@ -1121,6 +1246,7 @@ impl_make!(self, id: PkgDeclRef => &hir::Package {
     // support, and separate the generic clauses and maps.
     let ctx = AddContext::new(self, scope);
     for decl in &ast.decls {
+        let first = decls.len();
         match *decl {
             ast::DeclItem::SubprogDecl(ref decl) => {
                 match decl.data {
@@ -1157,9 +1283,6 @@ impl_make!(self, id: PkgDeclRef => &hir::Package {
             }
             ast::DeclItem::TypeDecl(ref decl) => {
                 decls.push(ctx.add_type_decl(decl)?.into());
-                // let subid = TypeDeclRef(NodeId::alloc());
-                // self.set_ast(subid, (scope, decl));
-                // decls.push(subid.into());
             }
             ast::DeclItem::SubtypeDecl(ref decl) => {
                 let subid = SubtypeDeclRef(NodeId::alloc());
@@ -1175,15 +1298,15 @@ impl_make!(self, id: PkgDeclRef => &hir::Package {
                         decls.extend(ctx.add_signal_decl::<DeclInPkgRef>(decl)?);
                     }
                     ast::ObjKind::SharedVar => {
+                        decls.extend(ctx.add_var_decl::<DeclInPkgRef>(decl)?);
+                    }
+                    ast::ObjKind::Var => {
                         self.emit(
-                            DiagBuilder2::error("not a variable; shared variables may not appear in a package declaration")
+                            DiagBuilder2::error("not a shared variable; variables in a package declaration must be shared")
                             .span(decl.human_span())
                         );
                         had_fails = true;
                     }
-                    ast::ObjKind::Var => {
-                        decls.extend(ctx.add_var_decl::<DeclInPkgRef>(decl)?);
-                    }
                     ast::ObjKind::File => {
                         decls.extend(ctx.add_file_decl::<DeclInPkgRef>(decl)?);
                     }
@@ -1233,6 +1356,7 @@ impl_make!(self, id: PkgDeclRef => &hir::Package {
                 }
             }
             ast::DeclItem::UseClause(..) => (),
+            ast::DeclItem::PslDecl(ref decl) => self.emit_psl_decl_ignored(decl),
             ref wrong => {
                 self.emit(
                     DiagBuilder2::error(format!("a {} cannot appear in a package declaration", wrong.desc()))
@@ -1242,6 +1366,18 @@ impl_make!(self, id: PkgDeclRef => &hir::Package {
                 continue;
             }
         }
+
+        // Make the declarations visible in the package right away, such that
+        // subsequent declarations may refer to them.
+        let mut defs = DefsContext::new(self);
+        for &decl in &decls[first..] {
+            defs.declare_any_in_pkg(decl);
+        }
+        for (name, defs) in defs.finish()? {
+            for def in defs {
+                self.define(scope, Spanned::new(name, def.span), def.value)?;
+            }
+        }
     }
 
     if had_fails {
@@ -1300,9 +1436,7 @@ impl_make!(self, id: PkgBodyRef => &hir::PackageBody {
                 decls.push(subid.into());
             }
             ast::DeclItem::TypeDecl(ref decl) => {
-                let subid = TypeDeclRef(NodeId::alloc());
-                self.set_ast(subid, (id.into(), decl));
-                decls.push(subid.into());
+                decls.push(ctx.with_scope(id.into()).add_type_decl(decl)?.into());
             }
             ast::DeclItem::SubtypeDecl(ref decl) => {
                 let subid = SubtypeDeclRef(NodeId::alloc());
@@ -1322,15 +1456,15 @@ impl_make!(self, id: PkgBodyRef => &hir::PackageBody {
                         had_fails = true;
                     }
                     ast::ObjKind::SharedVar => {
+                        decls.extend(ctx.add_var_decl::<DeclInPkgBodyRef>(decl)?);
+                    }
+                    ast::ObjKind::Var => {
                         self.emit(
-                            DiagBuilder2::error("not a variable; shared variables may not appear in a package body")
+                            DiagBuilder2::error("not a shared variable; variables in a package body must be shared")
                             .span(decl.human_span())
                         );
                         had_fails = true;
                     }
-                    ast::ObjKind::Var => {
-                        decls.extend(ctx.add_var_decl::<DeclInPkgBodyRef>(decl)?);
-                    }
                     ast::ObjKind::File => {
                         decls.extend(ctx.add_file_decl::<DeclInPkgBodyRef>(decl)?);
                     }
@@ -1841,20 +1975,31 @@ impl_make!(self, id: ProcessStmtRef => &hir::ProcessStmt {
     let (scope_id, ast) = self.ast(id);
     match ast.data {
         ast::ProcStmt {
-            // ref sensitivity,
+            ref sensitivity,
             ref decls,
             ref stmts,
             postponed,
-            ..
         } => {
-            // TODO: map sensititivty
+            let sensitivity = match *sensitivity {
+                None => hir::ProcessSensitivity::None,
+                Some(ast::Sensitivity::All) => hir::ProcessSensitivity::All,
+                Some(ast::Sensitivity::List(ref names)) => {
+                    let span = match (names.first(), names.last()) {
+                        (Some(first), Some(last)) => Span::union(first.span, last.span),
+                        _ => ast.span,
+                    };
+                    let ctx = AddContext::new(self, scope_id);
+                    let list = ctx.add_sensitivity_list(Spanned::new(names.iter(), span))?;
+                    hir::ProcessSensitivity::List(list.value)
+                }
+            };
             let decls = self.unpack_process_decls(id.into(), decls, "a process")?;
             let stmts = self.unpack_sequential_stmts(id.into(), stmts, "a process")?;
             Ok(self.sb.arenas.hir.process_stmt.alloc(hir::ProcessStmt {
                 parent: scope_id,
                 label: ast.label,
                 postponed: postponed,
-                sensitivity: hir::ProcessSensitivity::None,
+                sensitivity: sensitivity,
                 decls: decls,
                 stmts: stmts,
             }))
@@ -1880,7 +2025,7 @@ impl_make!(self, id: SigAssignStmtRef => &hir::SigAssignStmt {
             let kind = self.unpack_signal_assign_mode(scope_id, mode, &tyctx)?;
             if guarded {
                 self.emit(
-                    DiagBuilder2::warning("sequential signal assignment cannot be guarded")
+                    DiagBuilder2::lint(lints::GUARD, "sequential signal assignment cannot be guarded")
                     .span(ast.human_span())
                     .add_note("Only concurrent signal assignments can be guarded. See IEEE 1076-2008 section 11.6.")
                 );
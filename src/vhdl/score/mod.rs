@@ -10,6 +10,7 @@ use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::fmt::Debug;
 
+use moore_common::codes;
 use moore_common::errors::*;
 use moore_common::name::*;
 use moore_common::score::{GenericContext, NodeMaker, NodeStorage, Result};
@@ -19,13 +20,14 @@ use moore_common::NodeId;
 use moore_common::{Session, Verbosity};
 
 use llhd;
-use num::{BigInt, Signed};
+use num::{BigInt, Signed, ToPrimitive};
 use typed_arena::Arena;
 
 use crate::arenas::Alloc;
 use crate::builtin;
 pub use crate::builtin::*;
 use crate::codegen::Codegen;
+use crate::generic_env::{GenericEnv, GenericEnvData};
 use crate::hir;
 use crate::konst::*;
 use crate::lazy::*;
@@ -52,6 +54,7 @@ macro_rules! impl_make {
 mod cval;
 mod lower_hir;
 mod scope;
+mod sensitivity;
 
 /// The VHDL context which holds information about the language scoreboard and
 /// the global scoreboard in its language-agnostic generic form. All useful
@@ -67,6 +70,8 @@ pub struct ScoreContext<'lazy, 'sb: 'lazy, 'ast: 'sb, 'ctx: 'sb> {
     pub sb: &'sb ScoreBoard<'ast, 'ctx>,
     /// The table of scheduled operations.
     pub lazy: &'lazy LazyPhaseTable<'sb, 'ast, 'ctx>,
+    /// The generic environment in which types and values are determined.
+    pub env: GenericEnv,
 }
 
 /// The VHDL scoreboard that keeps track of compilation results.
@@ -97,21 +102,25 @@ pub struct ScoreBoard<'ast, 'ctx> {
     /// its interior.
     lldecl_table: RefCell<HashMap<NodeId, llhd::ValueRef>>,
     /// A table of LLHD definitions.
-    lldef_table: RefCell<HashMap<NodeId, llhd::ValueRef>>,
+    lldef_table: RefCell<HashMap<(NodeId, GenericEnv), llhd::ValueRef>>,
+    /// A table of the LLHD values of signals and ports.
+    llsig_table: RefCell<HashMap<(NodeId, GenericEnv), llhd::ValueRef>>,
     /// A table of types.
-    pub ty_table: RefCell<HashMap<NodeId, &'ctx Ty>>,
+    pub ty_table: RefCell<HashMap<(NodeId, GenericEnv), &'ctx Ty>>,
     /// A table of scopes.
     scope_table: RefCell<HashMap<ScopeRef, &'ctx Scope>>,
     /// A table of nodes' constant values.
-    const_table: RefCell<HashMap<NodeId, &'ctx Const>>,
+    const_table: RefCell<HashMap<(NodeId, GenericEnv), &'ctx Const>>,
     /// A table of type contexts for expressions.
     tyctx_table: RefCell<HashMap<NodeId, TypeCtx<'ctx>>>,
     /// A table of typeck results.
     pub typeck_table: RefCell<HashMap<NodeId, Result<()>>>,
     /// A table of typeval results.
-    pub typeval_table: RefCell<HashMap<NodeId, Result<&'ctx Ty>>>,
+    pub typeval_table: RefCell<HashMap<(NodeId, GenericEnv), Result<&'ctx Ty>>>,
     /// A table of scopes. Revised; will replace `scope_table` and `def_table`.
     pub scope2_table: RefCell<HashMap<ScopeRef, crate::scope::Scope>>,
+    /// The generic environments, indexed by `GenericEnv`.
+    generic_envs: RefCell<Vec<GenericEnvData<'ctx>>>,
 }
 
 impl<'ast, 'ctx> ScoreBoard<'ast, 'ctx> {
@@ -129,6 +138,7 @@ impl<'ast, 'ctx> ScoreBoard<'ast, 'ctx> {
             llmod: RefCell::new(llhd::Module::new()),
             lldecl_table: RefCell::new(HashMap::new()),
             lldef_table: RefCell::new(HashMap::new()),
+            llsig_table: RefCell::new(HashMap::new()),
             ty_table: RefCell::new(HashMap::new()),
             scope_table: RefCell::new(HashMap::new()),
             const_table: RefCell::new(HashMap::new()),
@@ -136,11 +146,17 @@ impl<'ast, 'ctx> ScoreBoard<'ast, 'ctx> {
             typeck_table: RefCell::new(HashMap::new()),
             typeval_table: RefCell::new(HashMap::new()),
             scope2_table: RefCell::new(HashMap::new()),
+            generic_envs: RefCell::new(vec![GenericEnvData::default()]),
         };
         builtin::register_builtins(&sb);
         sb
     }
 
+    /// Write the LLHD code generated so far as human-readable assembly.
+    pub fn write_llhd(&self, sink: &mut impl std::io::Write) {
+        llhd::assembly::write(sink, &self.llmod.borrow());
+    }
+
     /// Internalize a constant.
     ///
     /// Returns a reference to the constant whose lifetime is bound to that of
@@ -206,6 +222,59 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
         self.emit(d);
     }
 
+    /// Create a context which determines types and values in a different
+    /// generic environment.
+    pub fn with_env(&self, env: GenericEnv) -> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
+        ScoreContext { env, ..*self }
+    }
+
+    /// Internalize a generic environment.
+    pub fn intern_generic_env(&self, data: GenericEnvData<'ctx>) -> GenericEnv {
+        let mut envs = self.sb.generic_envs.borrow_mut();
+        if let Some(index) = envs.iter().position(|e| *e == data) {
+            return GenericEnv(index as u32);
+        }
+        envs.push(data);
+        GenericEnv(envs.len() as u32 - 1)
+    }
+
+    /// Get the data associated with a generic environment.
+    pub fn generic_env_data(&self, env: GenericEnv) -> GenericEnvData<'ctx> {
+        self.sb.generic_envs.borrow()[env.0 as usize].clone()
+    }
+
+    /// Find the value assigned to a generic or generate parameter in the
+    /// current environment or one of its parents.
+    pub fn generic_value<I>(&self, id: I) -> Option<&'ctx Const>
+    where
+        I: Into<NodeId>,
+    {
+        let id = id.into();
+        let mut env = Some(self.env);
+        while let Some(e) = env {
+            let data = self.generic_env_data(e);
+            if let Some(value) = data.find(id) {
+                return Some(value);
+            }
+            env = data.parent;
+        }
+        None
+    }
+
+    /// Find the block configuration that applies in the current environment or
+    /// one of its parents.
+    pub fn block_cfg(&self) -> Option<&'ctx hir::BlockCfg> {
+        let mut env = Some(self.env);
+        while let Some(e) = env {
+            let data = self.generic_env_data(e);
+            if data.cfg.is_some() {
+                return data.cfg;
+            }
+            env = data.parent;
+        }
+        None
+    }
+
     /// Obtain the AST node corresponding to a node reference. The AST node must
     /// have previously been added to the `ast_table`, otherwise this function
     /// panics.
@@ -353,7 +422,13 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
         if let Some(node) = self.sb.lldecl_table.borrow().get(&id.into()).cloned() {
             return Ok(node);
         }
-        if let Some(node) = self.sb.lldef_table.borrow().get(&id.into()).cloned() {
+        if let Some(node) = self
+            .sb
+            .lldef_table
+            .borrow()
+            .get(&(id.into(), self.env))
+            .cloned()
+        {
             return Ok(node);
         }
         if self.sess.opts.trace_scoreboard {
@@ -380,21 +455,32 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
         I: 'ctx + Copy + Debug + Into<NodeId>,
         ScoreContext<'lazy, 'sb, 'ast, 'ctx>: NodeMaker<I, DefValueRef>,
     {
-        if let Some(node) = self.sb.lldef_table.borrow().get(&id.into()).cloned() {
+        if let Some(node) = self
+            .sb
+            .lldef_table
+            .borrow()
+            .get(&(id.into(), self.env))
+            .cloned()
+        {
             return Ok(node);
         }
         if self.sess.opts.trace_scoreboard {
-            debugln!("[SB][VHDL] make lldef for {:?}", id);
+            debugln!("[SB][VHDL] make lldef for {:?} in {}", id, self.env);
         }
         let node = self.make(id)?.0;
         if self.sess.opts.trace_scoreboard {
-            debugln!("[SB][VHDL] lldef for {:?} is {:?}", id, node);
+            debugln!(
+                "[SB][VHDL] lldef for {:?} in {} is {:?}",
+                id,
+                self.env,
+                node
+            );
         }
         if self
             .sb
             .lldef_table
             .borrow_mut()
-            .insert(id.into(), node.clone())
+            .insert((id.into(), self.env), node.clone())
             .is_some()
         {
             panic!("node should not exist");
@@ -402,6 +488,36 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
         Ok(node)
     }
 
+    /// Obtain the LLHD value of a signal or port.
+    ///
+    /// Looks for the value in the current environment and its parents, since
+    /// generate statements may refer to the signals around them.
+    pub fn llsig<I>(&self, id: I) -> Option<llhd::ValueRef>
+    where
+        I: Into<NodeId>,
+    {
+        let id = id.into();
+        let mut env = Some(self.env);
+        while let Some(e) = env {
+            if let Some(value) = self.sb.llsig_table.borrow().get(&(id, e)) {
+                return Some(value.clone());
+            }
+            env = self.generic_env_data(e).parent;
+        }
+        None
+    }
+
+    /// Store the LLHD value of a signal or port in the current environment.
+    pub fn set_llsig<I>(&self, id: I, value: llhd::ValueRef)
+    where
+        I: Into<NodeId>,
+    {
+        self.sb
+            .llsig_table
+            .borrow_mut()
+            .insert((id.into(), self.env), value);
+    }
+
     /// Determine the type of a node.
     ///
     /// If called for the first time with the given `id`, calculates the type by
@@ -411,12 +527,21 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
         I: 'ctx + Copy + Debug + Into<NodeId>,
         ScoreContext<'lazy, 'sb, 'ast, 'ctx>: NodeMaker<I, &'ctx Ty>,
     {
-        if let Some(node) = self.sb.ty_table.borrow().get(&id.into()).cloned() {
+        let key = (id.into(), self.env);
+        if let Some(node) = self.sb.ty_table.borrow().get(&key).cloned() {
             return Ok(node);
         }
-        if let Some(&node) = self.sb.typeval_table.borrow().get(&id.into()) {
+        if let Some(&node) = self.sb.typeval_table.borrow().get(&key) {
             return node;
         }
+        // Nodes without a scheduled task, such as the builtin types, have the
+        // same type in every generic environment.
+        if self.env != GenericEnv::default() && !self.lazy.typeval.borrow().contains_key(&key.0) {
+            let default_key = (key.0, GenericEnv::default());
+            if let Some(&node) = self.sb.typeval_table.borrow().get(&default_key) {
+                return node;
+            }
+        }
         if self.sess.opts.trace_scoreboard {
             debugln!("[SB][VHDL] make ty for {:?}", id);
         }
@@ -424,13 +549,7 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
         if self.sess.opts.trace_scoreboard {
             debugln!("[SB][VHDL] ty for {:?} is {:?}", id, node);
         }
-        if self
-            .sb
-            .ty_table
-            .borrow_mut()
-            .insert(id.into(), node)
-            .is_some()
-        {
+        if self.sb.ty_table.borrow_mut().insert(key, node).is_some() {
             self.emit(DiagBuilder2::bug(format!(
                 "type for {:?} already in the scoreboard",
                 id
@@ -497,7 +616,8 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
         I: 'ctx + Copy + Debug + Into<NodeId>,
         ScoreContext<'lazy, 'sb, 'ast, 'ctx>: NodeMaker<I, &'ctx Const>,
     {
-        if let Some(node) = self.sb.const_table.borrow().get(&id.into()).cloned() {
+        let key = (id.into(), self.env);
+        if let Some(node) = self.sb.const_table.borrow().get(&key).cloned() {
             return Ok(node);
         }
         if self.sess.opts.trace_scoreboard {
@@ -507,13 +627,7 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
         if self.sess.opts.trace_scoreboard {
             debugln!("[SB][VHDL] const for {:?} is {:?}", id, node);
         }
-        if self
-            .sb
-            .const_table
-            .borrow_mut()
-            .insert(id.into(), node)
-            .is_some()
-        {
+        if self.sb.const_table.borrow_mut().insert(key, node).is_some() {
             panic!("node should not exist");
         }
         Ok(node)
@@ -664,7 +778,7 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
                 scope_id
             );
         }
-        let mut found_defs = Vec::new();
+        let mut found_defs: Vec<Spanned<Def>> = Vec::new();
         let parent_id = if !(*BUILTIN_SCOPE_REFS).contains(&scope_id) {
             if only_defs {
                 let defs = self.defs(scope_id)?;
@@ -679,6 +793,13 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
                     if let Some(d) = defs.get(&name.value) {
                         found_defs.extend(d);
                     }
+                    // Packages declare their contents in the revised scoping
+                    // mechanism, so look there as well.
+                    if let Some(scope) = self.sb.scope2_table.borrow().get(&defs_id) {
+                        if let Some(d) = scope.defs.get(&name.value) {
+                            found_defs.extend(d);
+                        }
+                    }
                 }
                 if let Some(d) = scope.explicit_defs.get(&name.value) {
                     found_defs.extend(d.iter());
@@ -710,6 +831,19 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
             }
         }
 
+        // Enumeration literals are overloadable and do not hide the literals
+        // of the same name declared in outer scopes, such as `'1'` of BIT and
+        // STD_ULOGIC. See IEEE 1076-2008 section 12.3.
+        if !found_defs.is_empty() && found_defs.iter().all(|d| is_enum_def(d.value)) {
+            if let Some(parent_id) = parent_id {
+                for def in self.resolve_name(name, parent_id, only_defs, true)? {
+                    if is_enum_def(def.value) && !found_defs.contains(&def) {
+                        found_defs.push(def);
+                    }
+                }
+            }
+        }
+
         // If nothing matched the definition, try to escalate to the parent
         // scope. If there is no parent scope, i.e. we're the parent, fail with
         // a diagnostic.
@@ -769,6 +903,7 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
                     } else {
                         let span_str = seen_span.extract();
                         let mut d = DiagBuilder2::error(format!("`{}` is ambiguous", span_str))
+                            .code(codes::VH0002)
                             .span(seen_span)
                             .add_note(format!(
                                 "`{}` refers to the following {} items:",
@@ -788,7 +923,7 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
                     let scope = match def.value {
                         Def::Lib(id) => id.into(),
                         Def::Pkg(id) => id.into(),
-                        // Def::PkgInst(id) => id.into(),
+                        Def::PkgInst(id) => self.instantiated_pkg(id, def.span)?.into(),
                         Def::BuiltinPkg(id) => id.into(),
                         d => {
                             self.emit(
@@ -818,6 +953,44 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
         Ok((res_name.value, defs, seen_span, &[]))
     }
 
+    /// Determine the package declaration a package instantiation refers to.
+    ///
+    /// Selecting into an instance currently yields the declarations of the
+    /// uninstantiated package; the generic map is not applied.
+    pub fn instantiated_pkg(&self, id: PkgInstRef, span: Span) -> Result<PkgDeclRef> {
+        let (scope, ast) = self.ast(id);
+        let (_, defs, target_span, tail) = self.resolve_compound_name(&ast.target, scope, true)?;
+        if !tail.is_empty() {
+            self.emit(
+                DiagBuilder2::error(format!("`{}` is not a package", ast.target.span.extract()))
+                    .span(ast.target.span),
+            );
+            return Err(());
+        }
+        match defs.as_slice() {
+            [Spanned {
+                value: Def::Pkg(id),
+                ..
+            }] => Ok(*id),
+            [Spanned {
+                value: Def::PkgInst(id),
+                span,
+            }] => self.instantiated_pkg(*id, *span),
+            _ => {
+                self.emit(
+                    DiagBuilder2::error(format!(
+                        "`{}` is not an uninstantiated package",
+                        target_span.extract()
+                    ))
+                    .span(target_span)
+                    .add_note("Referenced by the package instantiation here:")
+                    .span(span),
+                );
+                Err(())
+            }
+        }
+    }
+
     /// Get the builtin type `standard.boolean`.
     pub fn builtin_boolean_type(&self) -> &'ctx Ty {
         self.intern_ty(Ty::Null)
@@ -825,7 +998,7 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
 
     /// Get the builtin type `standard.time`.
     pub fn builtin_time_type(&self) -> &'ctx Ty {
-        self.intern_ty(Ty::Null)
+        self.intern_ty(builtin::TIME_TYPE.named_ty())
     }
 
     /// Get the builtin type `standard.string`.
@@ -958,30 +1131,26 @@ impl<'lazy, 'sb, 'ast, 'ctx> NodeMaker<ArchRef, DefValueRef>
         for &port in &entity.ports {
             let hir = self.hir(port)?;
             let ty = self.map_type(self.ty(hir.ty)?)?;
-            // let ty = llhd::void_ty();
-            match hir.mode {
-                hir::IntfSignalMode::In
-                | hir::IntfSignalMode::Inout
-                | hir::IntfSignalMode::Linkage => {
-                    in_tys.push(ty.clone());
-                    in_names.push(hir.name.value);
-                }
-                _ => (),
+            let (input, output) = port_dirs(hir.mode);
+            if input {
+                in_tys.push(ty.clone());
+                in_names.push(hir.name.value);
             }
-            match hir.mode {
-                hir::IntfSignalMode::Out
-                | hir::IntfSignalMode::Inout
-                | hir::IntfSignalMode::Buffer => {
-                    out_tys.push(ty.clone());
-                    out_names.push(hir.name.value);
-                }
-                _ => (),
+            if output {
+                out_tys.push(ty.clone());
+                out_names.push(hir.name.value);
             }
         }
         let ty = llhd::entity_ty(in_tys, out_tys);
 
         // Create a new entity into which we will generate all the code.
-        let name = format!("{}_{}", entity.name.value, hir.name.value);
+        // Entities elaborated with generics get a distinct name per generic
+        // environment.
+        let mut name = format!("{}_{}", entity.name.value, hir.name.value);
+        if self.env != GenericEnv::default() {
+            name = format!("{}_{}", name, self.env);
+        }
+        let ports = &entity.ports;
         let mut entity = llhd::Entity::new(name, ty);
 
         // Assign names to the arguments. This is merely cosmetic, but makes the
@@ -993,6 +1162,23 @@ impl<'lazy, 'sb, 'ast, 'ctx> NodeMaker<ArchRef, DefValueRef>
             arg.set_name(name.as_str().to_owned());
         }
 
+        // Make the ports available as signals within the architecture. Ports
+        // that are both read and driven are referred to by their input.
+        let (mut num_inputs, mut num_outputs) = (0, 0);
+        for &port in ports {
+            let (input, output) = port_dirs(self.hir(port)?.mode);
+            if input {
+                self.set_llsig(port, entity.input(num_inputs).into());
+                num_inputs += 1;
+            }
+            if output {
+                if !input {
+                    self.set_llsig(port, entity.output(num_outputs).into());
+                }
+                num_outputs += 1;
+            }
+        }
+
         // Generate the code for the declarations in the architecture.
         for &decl_id in &hir.decls {
             self.codegen(decl_id, &mut entity)?;
@@ -1010,16 +1196,31 @@ impl<'lazy, 'sb, 'ast, 'ctx> NodeMaker<ArchRef, DefValueRef>
     }
 }
 
+/// Check whether a definition is an enumeration literal.
+fn is_enum_def(def: Def) -> bool {
+    match def {
+        Def::Enum(_) => true,
+        _ => false,
+    }
+}
+
+/// Determine whether a port of the given mode is an input and/or an output of
+/// the entity it is generated as.
+pub(crate) fn port_dirs(mode: hir::IntfSignalMode) -> (bool, bool) {
+    match mode {
+        hir::IntfSignalMode::In | hir::IntfSignalMode::Linkage => (true, false),
+        hir::IntfSignalMode::Out | hir::IntfSignalMode::Buffer => (false, true),
+        hir::IntfSignalMode::Inout => (true, true),
+    }
+}
+
 impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
     /// Calculate the implicit default value for a type.
     pub fn default_value_for_type(&self, ty: &Ty) -> Result<&'ctx Const> {
         match *ty {
             Ty::Named(_, ty) => self.default_value_for_type(self.ty(ty)?),
             Ty::Null => Ok(self.intern_const(Const::Null)),
-            Ty::Enum(ref _ty) => {
-                // TODO: Replace with the first literal in the enum.
-                Ok(self.intern_const(Const::Null))
-            }
+            Ty::Enum(ref ty) => Ok(self.intern_const(ConstEnum::new(ty.decl, 0))),
             Ty::Physical(ref ty) => Ok(self.intern_const(ConstInt::new(
                 Some(ty.base.clone()),
                 ty.base.left_bound.clone(),
@@ -1030,14 +1231,24 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
             Ty::UniversalInt => panic!("universal integer has no default value"),
             Ty::UnboundedInt => panic!("unbounded integer has no default value"),
             Ty::Subprog(..) => panic!("subprogram type has no default value"),
-            Ty::Access(_) => Ok(self.intern_const(Const::Null)),
-            Ty::Array(ref ty) => {
+            Ty::Protected(_) => {
                 self.emit(DiagBuilder2::bug(format!(
                     "default value for type `{}` not implemented",
                     ty
                 )));
-                // TODO: Use the correct default value.
-                Ok(self.intern_const(Const::Null))
+                Err(())
+            }
+            Ty::Access(_) => Ok(self.intern_const(Const::Null)),
+            Ty::Array(ref ty) => {
+                let mut value = self.default_value_for_type(&ty.element)?.clone();
+                for index in ty.indices.iter().rev() {
+                    let len = self.array_index_len(ty, index)?;
+                    if len == 0 {
+                        return Ok(self.intern_const(Const::Null));
+                    }
+                    value = ConstArray::new(vec![value; len]).into();
+                }
+                Ok(self.intern_const(value))
             }
             Ty::File(ref ty) => {
                 self.emit(DiagBuilder2::bug(format!(
@@ -1048,12 +1259,64 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
                 Ok(self.intern_const(Const::Null))
             }
             Ty::Record(ref ty) => {
-                self.emit(DiagBuilder2::bug(format!(
-                    "default value for type `{}` not implemented",
+                let fields = ty
+                    .fields
+                    .iter()
+                    .map(|&(_, ref ty)| Ok(self.default_value_for_type(ty)?.clone()))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(self.intern_const(ConstRecord::new(fields)))
+            }
+        }
+    }
+
+    /// Determine the number of literals of an enum type.
+    pub fn enum_len(&self, decl: TypeDeclRef) -> Result<usize> {
+        if let Some(len) = builtin_enum_len(decl) {
+            return Ok(len);
+        }
+        match self.lazy_hir(decl)?.data.as_ref().unwrap().value {
+            hir::TypeData::Enum(ref lits) => Ok(lits.len()),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Calculate the number of elements along one index of an array type.
+    pub fn array_index_len(&self, array: &ArrayTy, index: &ArrayIndex) -> Result<usize> {
+        let ty = match *index {
+            ArrayIndex::Unbounded(_) => {
+                self.emit(DiagBuilder2::error(format!(
+                    "type `{}` is unbounded",
+                    array
+                )));
+                return Err(());
+            }
+            ArrayIndex::Constrained(ref ty) => self.deref_named_type(ty)?,
+        };
+        match *ty {
+            Ty::Int(ref ty) => {
+                let len = ty.len();
+                if len.is_negative() {
+                    return Ok(0);
+                }
+                match len.to_usize() {
+                    Some(len) => Ok(len),
+                    None => {
+                        self.emit(DiagBuilder2::error(format!(
+                            "array index `{}` is too large; {} elements",
+                            ty, len
+                        )));
+                        Err(())
+                    }
+                }
+            }
+            Ty::Enum(ref ty) => self.enum_len(ty.decl),
+            Ty::Null => Ok(0),
+            _ => {
+                self.emit(DiagBuilder2::error(format!(
+                    "`{}` is an invalid array index type",
                     ty
                 )));
-                // TODO: Use the correct default value.
-                Ok(self.intern_const(Const::Null))
+                Err(())
             }
         }
     }
@@ -1322,6 +1585,7 @@ node_ref!(CompInstStmtRef);
 node_ref!(ForGenStmtRef);
 node_ref!(IfGenStmtRef);
 node_ref!(CaseGenStmtRef);
+node_ref!(GenBodyRef);
 node_ref!(ConstDeclRef);
 node_ref!(SignalDeclRef);
 node_ref!(VarDeclRef);
@@ -1380,6 +1644,8 @@ node_ref_group!(
     Enum(EnumRef),
     Unit(UnitRef),
     Const(ConstDeclRef),
+    Generic(IntfConstRef),
+    GenParam(ForGenStmtRef),
     Signal(SignalRef),
     File(FileDeclRef),
     Var(VarDeclRef),
@@ -1402,8 +1668,11 @@ node_ref_group!(
     PkgBody(PkgBodyRef),
     Arch(ArchRef),
     Process(ProcessStmtRef),
+    ForGen(ForGenStmtRef),
+    GenBody(GenBodyRef),
     Subprog(SubprogDeclRef),
     SubprogBody(SubprogBodyRef),
+    Comp(CompDeclRef),
 );
 
 node_ref_group!(
@@ -1734,7 +2003,6 @@ node_storage!(AstTable<'ast>:
     intf_types:      IntfTypeRef        => (ScopeRef, &'ast ast::TypeDecl),
     intf_subprogs:   IntfSubprogRef     => (ScopeRef, &'ast ast::IntfSubprogDecl),
     intf_pkgs:       IntfPkgRef         => (ScopeRef, &'ast ast::PkgInst),
-    intf_consts:     IntfConstRef       => (ScopeRef, &'ast ast::IntfObjDecl, SubtypeIndRef, &'ast ast::Ident),
 
     // Declarations
     type_decls:            TypeDeclRef           => (ScopeRef, &'ast ast::TypeDecl),
@@ -1768,7 +2036,11 @@ node_storage!(HirTable<'ctx>:
     libs:                  LibRef                => &'ctx hir::Lib,
     entities:              EntityRef             => &'ctx hir::Entity,
     archs:                 ArchRef               => &'ctx hir::Arch,
+    comps:                 CompDeclRef           => &'ctx hir::Component,
+    cfgs:                  CfgRef                => &'ctx hir::Cfg,
+    cfg_specs:             CfgSpecRef            => &'ctx hir::CfgSpec,
     intf_sigs:             IntfSignalRef         => &'ctx hir::IntfSignal,
+    intf_consts:           IntfConstRef          => &'ctx hir::IntfConst,
     subtype_inds:          SubtypeIndRef         => &'ctx hir::SubtypeInd,
     pkgs:                  PkgDeclRef            => &'ctx hir::Package,
     pkg_bodies:            PkgBodyRef            => &'ctx hir::PackageBody,
@@ -1784,6 +2056,10 @@ node_storage!(HirTable<'ctx>:
     variable_decls:        VarDeclRef            => &'ctx hir::Decl<hir::VarDecl>,
     file_decls:            FileDeclRef           => &'ctx hir::Decl<hir::FileDecl>,
     process_stmts:         ProcessStmtRef        => &'ctx hir::ProcessStmt,
+    comp_inst_stmts:       CompInstStmtRef       => &'ctx hir::Stmt<hir::CompInstStmt>,
+    for_gen_stmts:         ForGenStmtRef         => &'ctx hir::Stmt<hir::ForGenStmt>,
+    if_gen_stmts:          IfGenStmtRef          => &'ctx hir::Stmt<hir::IfGenStmt>,
+    gen_bodies:            GenBodyRef            => &'ctx hir::GenBody,
     sig_assign_stmts:      SigAssignStmtRef      => &'ctx hir::SigAssignStmt,
     array_type_indices:    ArrayTypeIndexRef     => &'ctx Spanned<hir::ArrayTypeIndex>,
     subprogs:              SubprogDeclRef        => &'ctx hir::Subprog,
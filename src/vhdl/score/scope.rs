@@ -27,8 +27,11 @@ impl_make_defs!(self, id: ScopeRef => {
         ScopeRef::PkgBody(id)     => self.make(id),
         ScopeRef::Arch(id)        => self.make(id),
         ScopeRef::Process(id)     => self.make(id),
+        ScopeRef::ForGen(id)      => self.make(id),
+        ScopeRef::GenBody(id)     => self.make(id),
         ScopeRef::Subprog(id)     => self.make(id),
         ScopeRef::SubprogBody(id) => self.make(id),
+        ScopeRef::Comp(id)        => self.make(id),
     }
 });
 
@@ -42,8 +45,11 @@ impl_make_scope!(self, id: ScopeRef => {
         ScopeRef::PkgBody(id)     => self.make(id),
         ScopeRef::Arch(id)        => self.make(id),
         ScopeRef::Process(id)     => self.make(id),
+        ScopeRef::ForGen(id)      => self.make(id),
+        ScopeRef::GenBody(id)     => self.make(id),
         ScopeRef::Subprog(id)     => self.make(id),
         ScopeRef::SubprogBody(id) => self.make(id),
+        ScopeRef::Comp(id)        => self.make(id),
     }
 });
 
@@ -97,20 +103,46 @@ impl_make_defs!(self, id: LibRef => {
 
 // Definitions made by the context items that appear before design units.
 impl_make_defs!(self, id: CtxItemsRef => {
-    let (_, ast) = self.ast(id);
+    let (scope, ast) = self.ast(id);
     let mut defs = HashMap::new();
     let mut has_fails = false;
+
+    // Every design unit implicitly starts with the library clause `library
+    // STD, WORK;`, where WORK denotes the library the unit is analyzed into.
+    // See IEEE 1076-2008 section 13.2.
+    let work_name = get_name_table().intern("work", false);
+    let std_name = get_name_table().intern("std", false);
+    let work = match scope {
+        ScopeRef::Lib(lib) => Some(lib),
+        _ => None,
+    };
+    defs.insert(std_name.into(), vec![Spanned::new(Def::Lib(*STD_LIB_REF), INVALID_SPAN)]);
+    if let Some(lib) = work {
+        defs.insert(work_name.into(), vec![Spanned::new(Def::Lib(lib), INVALID_SPAN)]);
+    }
+
     for item in ast {
         // For each name in a library clause, find the corresponding library
         // and create a definition for it.
         match *item {
             ast::CtxItem::LibClause(Spanned{ value: ref names, .. }) => {
                 for ident in names {
-                    if let Some(&lib_id) = self.sb.lib_names.borrow().get(&ident.name) {
+                    let lib_id = if ident.name == work_name {
+                        work
+                    } else if ident.name == std_name {
+                        Some(*STD_LIB_REF)
+                    } else {
+                        self.sb.lib_names.borrow().get(&ident.name).cloned()
+                    };
+                    if let Some(lib_id) = lib_id {
                         let defs = defs.entry(ident.name.into()).or_insert_with(||vec![]);
+                        // Naming the same library again has no effect.
+                        if defs.iter().any(|d| d.value == Def::Lib(lib_id)) {
+                            continue;
+                        }
                         if !defs.is_empty() {
                             self.emit(
-                                DiagBuilder2::error(format!("`{}` has already been declared", ident.name))
+                                DiagBuilder2::error(format!("`{}` has already been declared", ident.name)).code(codes::VH0003)
                                 .span(ident.span)
                                 // TODO: Show previous declarations
                             );
@@ -147,9 +179,13 @@ impl_make_scope!(self, _id: CtxItemsRef => {
 });
 
 // Definitions in an entity.
-impl_make_defs!(self, _id: EntityRef => {
-    // TODO: Implement this.
-    Ok(self.sb.arenas.defs.alloc(HashMap::new()))
+impl_make_defs!(self, id: EntityRef => {
+    let mut ctx = DefsContext::new(self);
+    let hir = self.hir(id)?;
+    ctx.declare_generics(&hir.generics);
+    let ports: Vec<IntfObjRef> = hir.ports.iter().map(|&id| id.into()).collect();
+    ctx.declare_intf_objs(&ports);
+    Ok(self.sb.arenas.defs.alloc(ctx.finish()?))
 });
 
 // Definitions in an architecture.
@@ -162,6 +198,16 @@ impl_make_defs!(self, id: ArchRef => {
     Ok(self.sb.arenas.defs.alloc(ctx.finish()?))
 });
 
+// Definitions in a component declaration.
+impl_make_defs!(self, id: CompDeclRef => {
+    let mut ctx = DefsContext::new(self);
+    let hir = self.hir(id)?;
+    ctx.declare_generics(&hir.generics);
+    let ports: Vec<IntfObjRef> = hir.ports.iter().map(|&id| id.into()).collect();
+    ctx.declare_intf_objs(&ports);
+    Ok(self.sb.arenas.defs.alloc(ctx.finish()?))
+});
+
 // Definitions in a package declaration.
 impl_make_defs!(self, _id: PkgDeclRef => {
     let ctx = DefsContext::new(self);
@@ -242,9 +288,28 @@ impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
                                 Some(Spanned {
                                     value: Def::Pkg(id),
                                     ..
+                                }) => {
+                                    // Packages declare their contents upon
+                                    // lowering, which may not have happened
+                                    // yet if they live in another library.
+                                    if self.hir(id).is_err() {
+                                        continue;
+                                    }
+                                    defs.push(id.into());
+                                }
+                                Some(Spanned {
+                                    value: Def::BuiltinPkg(id),
+                                    ..
                                 }) => {
                                     defs.push(id.into());
                                 }
+                                Some(Spanned {
+                                    value: Def::PkgInst(id),
+                                    span,
+                                }) => match self.instantiated_pkg(id, span) {
+                                    Ok(id) if self.hir(id).is_ok() => defs.push(id.into()),
+                                    _ => continue,
+                                },
                                 Some(_) => {
                                     self.emit(
                                         DiagBuilder2::error(format!(
@@ -313,6 +378,18 @@ impl_make_scope!(self, id: ArchRef => {
     }))
 });
 
+// Populate the scope of a component declaration.
+impl_make_scope!(self, id: CompDeclRef => {
+    let hir = self.hir(id)?;
+    let mut defs = Vec::new();
+    defs.push(id.into());
+    Ok(self.sb.arenas.scope.alloc(Scope{
+        parent: Some(hir.parent),
+        defs: defs,
+        explicit_defs: HashMap::new(),
+    }))
+});
+
 // Populate the scope of a package declaration.
 impl_make_scope!(self, id: PkgDeclRef => {
     let hir = self.hir(id)?;
@@ -397,6 +474,47 @@ impl_make_scope!(self, id: ProcessStmtRef => {
     }))
 });
 
+// Definitions in a for-generate statement.
+impl_make_defs!(self, id: ForGenStmtRef => {
+    let mut ctx = DefsContext::new(self);
+    ctx.declare_gen_param(id);
+    Ok(self.sb.arenas.defs.alloc(ctx.finish()?))
+});
+
+// Populate the scope of a for-generate statement.
+impl_make_scope!(self, id: ForGenStmtRef => {
+    let hir = self.lazy_hir(id)?;
+    let mut defs = Vec::new();
+    defs.push(id.into());
+    Ok(self.sb.arenas.scope.alloc(Scope {
+        parent: Some(hir.parent),
+        defs: defs,
+        explicit_defs: HashMap::new(),
+    }))
+});
+
+// Definitions in the body of a generate statement.
+impl_make_defs!(self, id: GenBodyRef => {
+    let mut ctx = DefsContext::new(self);
+    let hir = self.lazy_hir(id)?;
+    for &decl in &hir.decls {
+        ctx.declare_any_in_block(decl);
+    }
+    Ok(self.sb.arenas.defs.alloc(ctx.finish()?))
+});
+
+// Populate the scope of the body of a generate statement.
+impl_make_scope!(self, id: GenBodyRef => {
+    let hir = self.lazy_hir(id)?;
+    let mut defs = Vec::new();
+    defs.push(id.into());
+    Ok(self.sb.arenas.scope.alloc(Scope {
+        parent: Some(hir.parent),
+        defs: defs,
+        explicit_defs: HashMap::new(),
+    }))
+});
+
 // DeclInPkgRef::Pkg(id) => vec![(self.ast(id).1.name.map_into(), Def::Pkg(id))],
 // DeclInPkgRef::PkgInst(id) => vec![(self.ast(id).1.name.map_into(), Def::PkgInst(id))],
 // DeclInPkgRef::Type(id) => {
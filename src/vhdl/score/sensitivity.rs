@@ -0,0 +1,378 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! This module implements the sensitivity analysis of processes.
+//!
+//! A process with the `all` sensitivity list is sensitive to every signal it
+//! reads. A process with an explicit sensitivity list is checked for
+//! completeness: signals that are read by the process but are missing from the
+//! list are reported, since simulation and synthesis will disagree on the
+//! behaviour of the process. Signals that are only read under an edge
+//! condition such as `clk'event` do not need to be in the list.
+
+use crate::common::lints;
+use crate::score::*;
+
+/// A signal read by a process.
+#[derive(Copy, Clone, Debug)]
+pub struct SignalRead {
+    /// The signal being read.
+    pub signal: SignalRef,
+    /// The location of the name that reads the signal.
+    pub span: Span,
+    /// Whether the read only happens under an edge condition.
+    pub clocked: bool,
+}
+
+impl<'lazy, 'sb, 'ast, 'ctx> ScoreContext<'lazy, 'sb, 'ast, 'ctx> {
+    /// Determine the signals a process is sensitive to.
+    ///
+    /// This is the explicit sensitivity list of the process, or every signal
+    /// read by the process if it has the `all` sensitivity list. See IEEE
+    /// 1076-2008 section 11.3.
+    pub fn process_sensitivity(&self, id: ProcessStmtRef) -> Result<Vec<SignalRef>> {
+        let hir = self.hir(id)?;
+        Ok(match hir.sensitivity {
+            hir::ProcessSensitivity::None => vec![],
+            hir::ProcessSensitivity::List(ref list) => dedup(list.iter().map(|s| s.value)),
+            hir::ProcessSensitivity::All => {
+                dedup(self.process_reads(id)?.into_iter().map(|r| r.signal))
+            }
+        })
+    }
+
+    /// Determine the signals read by the statements of a process.
+    pub fn process_reads(&self, id: ProcessStmtRef) -> Result<Vec<SignalRead>> {
+        let hir = self.hir(id)?;
+        let mut reads = SignalReads {
+            ctx: self,
+            reads: vec![],
+            clocked: false,
+        };
+        reads.stmts(&hir.stmts)?;
+        Ok(reads.reads)
+    }
+
+    /// Determine the signals read by an expression.
+    ///
+    /// A `wait until` statement without a sensitivity clause is sensitive to
+    /// these. See IEEE 1076-2008 section 10.2.
+    pub fn expr_signals(&self, id: ExprRef) -> Result<Vec<SignalRef>> {
+        let mut reads = SignalReads {
+            ctx: self,
+            reads: vec![],
+            clocked: false,
+        };
+        reads.expr(id)?;
+        Ok(dedup(reads.reads.into_iter().map(|r| r.signal)))
+    }
+
+    /// Check the sensitivity list of a process.
+    ///
+    /// Reports explicit wait statements in processes with a sensitivity list,
+    /// and signals that are read but missing from an explicit list.
+    pub fn check_process_sensitivity(&self, id: ProcessStmtRef) -> Result<()> {
+        let hir = self.hir(id)?;
+        let list = match hir.sensitivity {
+            hir::ProcessSensitivity::None => return Ok(()),
+            hir::ProcessSensitivity::All => None,
+            hir::ProcessSensitivity::List(ref list) => Some(list),
+        };
+        let mut failed = false;
+        for span in self.wait_stmts(&hir.stmts)? {
+            self.emit(
+                DiagBuilder2::error("process with a sensitivity list cannot contain a wait statement")
+                    .span(span)
+                    .add_note("The sensitivity list implies a wait statement at the end of the process. See IEEE 1076-2008 section 11.3."),
+            );
+            failed = true;
+        }
+        if failed {
+            return Err(());
+        }
+        let list = match list {
+            Some(list) => list,
+            None => return Ok(()),
+        };
+        let mut missing: Vec<SignalRef> = vec![];
+        for read in self.process_reads(id)? {
+            if read.clocked
+                || list.iter().any(|s| s.value == read.signal)
+                || missing.contains(&read.signal)
+            {
+                continue;
+            }
+            missing.push(read.signal);
+            self.emit(
+                DiagBuilder2::lint(
+                    lints::SENSITIVITY,
+                    format!(
+                        "`{}` is read by the process but missing from its sensitivity list",
+                        read.span.extract()
+                    ),
+                )
+                .span(read.span)
+                .add_note("Add the signal to the sensitivity list, or use `process (all)`."),
+            );
+        }
+        Ok(())
+    }
+
+    /// Find the wait statements among a list of sequential statements.
+    fn wait_stmts(&self, stmts: &[SeqStmtRef]) -> Result<Vec<Span>> {
+        let mut spans = vec![];
+        for &stmt in stmts {
+            match stmt {
+                SeqStmtRef::Wait(id) => spans.push(self.lazy_hir(id)?.span),
+                SeqStmtRef::If(id) => {
+                    let hir = self.lazy_hir(id)?;
+                    for &(_, ref stmts) in &hir.stmt.branches {
+                        spans.extend(self.wait_stmts(stmts)?);
+                    }
+                    if let Some(ref stmts) = hir.stmt.otherwise {
+                        spans.extend(self.wait_stmts(stmts)?);
+                    }
+                }
+                SeqStmtRef::Case(id) => {
+                    for &(_, ref stmts) in &self.lazy_hir(id)?.stmt.cases {
+                        spans.extend(self.wait_stmts(stmts)?);
+                    }
+                }
+                SeqStmtRef::Loop(id) => {
+                    spans.extend(self.wait_stmts(&self.lazy_hir(id)?.stmt.stmts)?);
+                }
+                _ => (),
+            }
+        }
+        Ok(spans)
+    }
+}
+
+/// Remove duplicates from a list of signals, keeping the first occurrence.
+fn dedup<I: IntoIterator<Item = SignalRef>>(signals: I) -> Vec<SignalRef> {
+    let mut result = vec![];
+    for signal in signals {
+        if !result.contains(&signal) {
+            result.push(signal);
+        }
+    }
+    result
+}
+
+/// A collector for the signals read by sequential statements.
+struct SignalReads<'a, 'lazy: 'a, 'sb: 'lazy, 'ast: 'sb, 'ctx: 'sb> {
+    ctx: &'a ScoreContext<'lazy, 'sb, 'ast, 'ctx>,
+    reads: Vec<SignalRead>,
+    clocked: bool,
+}
+
+impl<'a, 'lazy, 'sb, 'ast, 'ctx> SignalReads<'a, 'lazy, 'sb, 'ast, 'ctx> {
+    fn stmts(&mut self, stmts: &[SeqStmtRef]) -> Result<()> {
+        for &stmt in stmts {
+            self.stmt(stmt)?;
+        }
+        Ok(())
+    }
+
+    fn stmt(&mut self, stmt: SeqStmtRef) -> Result<()> {
+        match stmt {
+            SeqStmtRef::Wait(id) => {
+                let hir = &self.ctx.lazy_hir(id)?.stmt;
+                self.opt_expr(hir.cond)?;
+                self.opt_expr(hir.timeout)?;
+            }
+            SeqStmtRef::Assert(id) => {
+                let hir = &self.ctx.lazy_hir(id)?.stmt;
+                self.expr(hir.cond)?;
+                self.opt_expr(hir.report)?;
+                self.opt_expr(hir.severity)?;
+            }
+            SeqStmtRef::Report(id) => {
+                let hir = &self.ctx.lazy_hir(id)?.stmt;
+                self.expr(hir.report)?;
+                self.opt_expr(hir.severity)?;
+            }
+            SeqStmtRef::SigAssign(id) => {
+                let hir = self.ctx.hir(id)?;
+                match hir.kind {
+                    hir::SigAssignKind::SimpleWave(_, ref wave) => self.wave(wave)?,
+                    hir::SigAssignKind::SimpleForce(_, expr) => self.expr(expr)?,
+                    hir::SigAssignKind::SimpleRelease(_) => (),
+                    hir::SigAssignKind::CondWave(_, ref cond) => {
+                        for &(ref wave, cond) in &cond.when {
+                            self.expr(cond)?;
+                            self.wave(wave)?;
+                        }
+                        if let Some(ref wave) = cond.other {
+                            self.wave(wave)?;
+                        }
+                    }
+                    hir::SigAssignKind::CondForce(_, ref cond) => self.cond(cond)?,
+                    hir::SigAssignKind::SelWave(_, ref sel) => {
+                        self.expr(sel.disc)?;
+                        for &(ref wave, _) in &sel.when {
+                            self.wave(wave)?;
+                        }
+                    }
+                    hir::SigAssignKind::SelForce(_, ref sel) => self.sel(sel)?,
+                }
+            }
+            SeqStmtRef::VarAssign(id) => {
+                let hir = &self.ctx.lazy_hir(id)?.stmt;
+                match hir.kind {
+                    hir::VarAssignKind::Simple(expr) => self.expr(expr)?,
+                    hir::VarAssignKind::Cond(ref cond) => self.cond(cond)?,
+                    hir::VarAssignKind::Sel(ref sel) => self.sel(sel)?,
+                }
+            }
+            SeqStmtRef::ProcCall(_) => (),
+            SeqStmtRef::If(id) => {
+                let hir = &self.ctx.lazy_hir(id)?.stmt;
+                let outer = self.clocked;
+                for &(cond, ref stmts) in &hir.branches {
+                    self.expr(cond)?;
+                    let clocked = self.is_edge(cond)?;
+                    self.clocked = outer || clocked;
+                    self.stmts(stmts)?;
+                    self.clocked = outer;
+                }
+                if let Some(ref stmts) = hir.otherwise {
+                    self.stmts(stmts)?;
+                }
+            }
+            SeqStmtRef::Case(id) => {
+                let hir = &self.ctx.lazy_hir(id)?.stmt;
+                self.expr(hir.switch)?;
+                for &(_, ref stmts) in &hir.cases {
+                    self.stmts(stmts)?;
+                }
+            }
+            SeqStmtRef::Loop(id) => {
+                let hir = &self.ctx.lazy_hir(id)?.stmt;
+                match hir.scheme {
+                    hir::LoopScheme::While(cond) => self.expr(cond)?,
+                    hir::LoopScheme::For(_, ref range) => match range.value {
+                        hir::DiscreteRange::Range(hir::Range::Immediate(_, lo, hi)) => {
+                            self.expr(lo)?;
+                            self.expr(hi)?;
+                        }
+                        hir::DiscreteRange::Subtype(_) => (),
+                    },
+                    hir::LoopScheme::Loop => (),
+                }
+                self.stmts(&hir.stmts)?;
+            }
+            SeqStmtRef::Nexit(id) => {
+                self.opt_expr(self.ctx.lazy_hir(id)?.stmt.cond)?;
+            }
+            SeqStmtRef::Return(id) => {
+                self.opt_expr(self.ctx.lazy_hir(id)?.stmt.expr)?;
+            }
+            SeqStmtRef::Null(_) => (),
+        }
+        Ok(())
+    }
+
+    fn wave(&mut self, wave: &hir::Waveform) -> Result<()> {
+        for elem in wave {
+            self.opt_expr(elem.value)?;
+        }
+        Ok(())
+    }
+
+    fn cond(&mut self, cond: &hir::Cond<ExprRef>) -> Result<()> {
+        for &(value, cond) in &cond.when {
+            self.expr(cond)?;
+            self.expr(value)?;
+        }
+        self.opt_expr(cond.other)
+    }
+
+    fn sel(&mut self, sel: &hir::Sel<ExprRef>) -> Result<()> {
+        self.expr(sel.disc)?;
+        for &(value, _) in &sel.when {
+            self.expr(value)?;
+        }
+        Ok(())
+    }
+
+    fn opt_expr(&mut self, expr: Option<ExprRef>) -> Result<()> {
+        match expr {
+            Some(expr) => self.expr(expr),
+            None => Ok(()),
+        }
+    }
+
+    fn expr(&mut self, id: ExprRef) -> Result<()> {
+        let hir = self.ctx.lazy_hir(id)?;
+        match hir.data {
+            hir::ExprData::SignalName(signal) | hir::ExprData::Name(Def::Signal(signal), _) => {
+                self.reads.push(SignalRead {
+                    signal: signal,
+                    span: hir.span,
+                    clocked: self.clocked,
+                });
+            }
+            hir::ExprData::Select(expr, _)
+            | hir::ExprData::Attr(expr, _)
+            | hir::ExprData::Unary(_, _, expr)
+            | hir::ExprData::Qualified(_, expr)
+            | hir::ExprData::Allocator(_, Some(expr))
+            | hir::ExprData::Cast(_, expr) => self.expr(expr)?,
+            hir::ExprData::Binary(_, _, lhs, rhs) | hir::ExprData::Range(_, lhs, rhs) => {
+                self.expr(lhs)?;
+                self.expr(rhs)?;
+            }
+            hir::ExprData::Aggregate(id) => {
+                let hir = self.ctx.lazy_hir(id)?;
+                for expr in &hir.positional {
+                    self.expr(expr.value)?;
+                }
+                match hir.named {
+                    hir::AggregateKind::Both => (),
+                    hir::AggregateKind::Record(ref fields) => {
+                        for field in fields {
+                            self.expr(field.value.1.value)?;
+                        }
+                    }
+                    hir::AggregateKind::Array(ref fields) => {
+                        for field in fields {
+                            self.expr(field.value.1.value)?;
+                        }
+                    }
+                }
+                if let Some(expr) = hir.others {
+                    self.expr(expr.value)?;
+                }
+            }
+            hir::ExprData::Call(callee, ref args) => {
+                self.expr(callee)?;
+                for arg in &args.value {
+                    match arg.actual.value {
+                        hir::AssocActual::Expr(expr) | hir::AssocActual::InertialExpr(expr) => {
+                            self.expr(expr)?
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Check whether a condition detects a signal edge, as in `clk'event`.
+    fn is_edge(&self, id: ExprRef) -> Result<bool> {
+        let hir = self.ctx.lazy_hir(id)?;
+        Ok(match hir.data {
+            hir::ExprData::Attr(_, name) => match name.value {
+                ResolvableName::Ident(name) => {
+                    let name = name.as_str();
+                    name.eq_ignore_ascii_case("event")
+                }
+                _ => false,
+            },
+            hir::ExprData::Binary(_, _, lhs, rhs) => self.is_edge(lhs)? || self.is_edge(rhs)?,
+            _ => false,
+        })
+    }
+}
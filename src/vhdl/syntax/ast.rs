@@ -7,6 +7,9 @@ use std;
 use std::fmt;
 
 use crate::lexer::token::Literal;
+pub use moore_common::assertion::{
+    DirectiveKind, PropBinOp, PropSeqBinOp, PropSeqOp, PropUnaryOp, SeqBinOp,
+};
 use moore_common::name::Name;
 use moore_common::source::{Span, Spanned};
 use moore_common::util::{HasDesc, HasSpan};
@@ -354,6 +357,7 @@ pub enum DeclItem {
     GroupDecl(GroupDecl),
     VunitBindInd(()),
     BlockCompCfg(BlockCompCfg),
+    PslDecl(PslDecl),
 }
 
 impl HasSpan for DeclItem {
@@ -377,6 +381,7 @@ impl HasSpan for DeclItem {
             DeclItem::GroupDecl(ref n) => n.span,
             DeclItem::VunitBindInd(_) => unimplemented!(),
             DeclItem::BlockCompCfg(ref n) => n.span,
+            DeclItem::PslDecl(ref n) => n.span,
         }
     }
 
@@ -392,6 +397,7 @@ impl HasSpan for DeclItem {
             DeclItem::SubprogDecl(ref n) => n.human_span(),
             DeclItem::PortgenMap(_, Spanned { span, .. }, _) => span,
             DeclItem::PortgenClause(_, Spanned { span, .. }, _) => span,
+            DeclItem::PslDecl(ref n) => n.human_span(),
             _ => self.span(),
         }
     }
@@ -448,6 +454,7 @@ impl HasDesc for DeclItem {
             DeclItem::GroupDecl(..) => "group declaration",
             DeclItem::VunitBindInd(..) => "vunit binding indication",
             DeclItem::BlockCompCfg(..) => "block component configuration",
+            DeclItem::PslDecl(ref n) => n.data.desc(),
         }
     }
 }
@@ -569,7 +576,8 @@ pub struct AliasDecl {
     pub span: Span,
     pub name: PrimaryName,
     pub subtype: Option<SubtypeInd>,
-    pub target: CompoundName,
+    /// The aliased object, which is either a name or an external name.
+    pub target: Expr,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -755,6 +763,109 @@ pub enum GroupData {
     Temp(Vec<(EntityClass, bool)>),
 }
 
+/// A PSL declaration. See IEEE 1076-2008 section 11.9 and IEEE 1850.
+///
+/// ```text
+/// property ident "is" prop ";"
+/// sequence ident "is" seq ";"
+/// "default" "clock" "is" expr ";"
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PslDecl {
+    pub id: NodeId,
+    pub span: Span,
+    /// The declared name. `None` for the default clock declaration.
+    pub name: Option<Spanned<Name>>,
+    pub data: PslDeclData,
+}
+
+impl HasSpan for PslDecl {
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn human_span(&self) -> Span {
+        match self.name {
+            Some(Spanned { span, .. }) => span,
+            None => self.span,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PslDeclData {
+    Property(PslProp),
+    Sequence(PslSeq),
+    DefaultClock(Expr),
+}
+
+impl HasDesc for PslDeclData {
+    fn desc(&self) -> &'static str {
+        match *self {
+            PslDeclData::Property(..) => "property declaration",
+            PslDeclData::Sequence(..) => "sequence declaration",
+            PslDeclData::DefaultClock(..) => "default clock declaration",
+        }
+    }
+}
+
+/// A PSL property. Mirrors the SystemVerilog `PropExpr`, but with VHDL
+/// expressions as the boolean layer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PslProp {
+    pub span: Span,
+    pub data: PslPropData,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PslPropData {
+    /// A sequence or boolean used as a property, e.g. `a` or `{a; b}!`.
+    SeqOp(PropSeqOp, PslSeq),
+    /// A suffix implication, e.g. `{a; b} |=> c`.
+    SeqBinOp(PropSeqBinOp, PropSeqOp, PslSeq, Box<PslProp>),
+    /// A temporal prefix operator, e.g. `always p`.
+    UnaryOp(PropUnaryOp, Box<PslProp>),
+    /// A binary operator, e.g. `a -> next b` or `a until b`.
+    BinOp(PropBinOp, Box<PslProp>, Box<PslProp>),
+    /// A property evaluated at a clock, e.g. `p @ rising_edge(clk)`.
+    Clocked(Expr, Box<PslProp>),
+    /// A property aborted by a condition, e.g. `p abort rst`.
+    Abort(Box<PslProp>, Expr),
+}
+
+/// A PSL sequential extended regular expression (SERE). Mirrors the
+/// SystemVerilog `SeqExpr`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PslSeq {
+    pub span: Span,
+    pub data: PslSeqData,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PslSeqData {
+    /// A boolean, e.g. `a = '1'`.
+    Expr(Expr),
+    /// A repeated sequence, e.g. `a[*3]`.
+    Rep(Box<PslSeq>, PslRep),
+    /// A sequence composition, e.g. `a; b` or `{a} && {b}`.
+    BinOp(SeqBinOp, Box<PslSeq>, Box<PslSeq>),
+}
+
+/// A PSL repetition operator.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PslRep {
+    /// `[* expr]`
+    Consec(Expr),
+    /// `[*]`
+    ConsecStar,
+    /// `[+]`
+    ConsecPlus,
+    /// `[= expr]`
+    Nonconsec(Expr),
+    /// `[-> expr]` or `[->]`
+    Goto(Option<Expr>),
+}
+
 /// A parenthesized expression element. A parenthesized expression contains
 /// elements of which each may either be a simple `<expr>`, or an association of
 /// the form `<choices> => <expr>`.
@@ -805,6 +916,7 @@ pub enum ExprData {
     NameExpr(CompoundName),
     UnaryExpr(Spanned<UnaryOp>, Box<Expr>),
     BinaryExpr(Spanned<BinaryOp>, Box<Expr>, Box<Expr>),
+    ExternalExpr(Box<ExternalName>),
 }
 
 impl HasDesc for ExprData {
@@ -813,11 +925,38 @@ impl HasDesc for ExprData {
             NullExpr => "null expression",
             UnaryExpr(..) => "unary expression",
             BinaryExpr(..) => "binary expression",
+            ExternalExpr(..) => "external name",
             _ => "expression",
         }
     }
 }
 
+/// An external name, e.g. `<<signal .top.dut.x : bit>>`. See IEEE 1076-2008
+/// section 8.7.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExternalName {
+    pub span: Span,
+    /// The class of the object, which is a constant, signal, or variable.
+    pub kind: Spanned<ObjKind>,
+    /// Where the path starts.
+    pub root: ExternalRoot,
+    /// The path to the object.
+    pub path: CompoundName,
+    /// The subtype of the object.
+    pub subtype: SubtypeInd,
+}
+
+/// Where the path of an external name starts.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExternalRoot {
+    /// A package path, e.g. `@lib.pkg.x`.
+    Package,
+    /// An absolute path from the root of the design, e.g. `.top.dut.x`.
+    Absolute,
+    /// A relative path which goes up a number of levels first, e.g. `^.^.x`.
+    Relative(usize),
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum UnaryOp {
     Not,
@@ -973,6 +1112,7 @@ pub enum TypeData {
     AccessType(SubtypeInd),
     FileType(CompoundName),
     ProtectedType(Vec<DeclItem>),
+    ProtectedBody(Vec<DeclItem>),
 }
 
 impl HasDesc for TypeData {
@@ -985,6 +1125,7 @@ impl HasDesc for TypeData {
             TypeData::AccessType(..) => "access type declaration",
             TypeData::FileType(..) => "file type declaration",
             TypeData::ProtectedType(..) => "protected type declaration",
+            TypeData::ProtectedBody(..) => "protected type body",
         }
     }
 }
@@ -1097,6 +1238,13 @@ pub enum StmtData {
         generics: Option<ParenElems>,
         ports: Option<ParenElems>,
     },
+    /// A PSL verification directive. See IEEE 1076-2008 section 11.9.
+    PslDirective {
+        kind: Spanned<DirectiveKind>,
+        prop: PslProp,
+        report: Option<Expr>,
+        severity: Option<Expr>,
+    },
 }
 
 impl HasDesc for StmtData {
@@ -1126,6 +1274,7 @@ impl HasDesc for StmtData {
             StmtData::AssignStmt { .. } => "assign statement",
             StmtData::SelectAssignStmt { .. } => "assign statement",
             StmtData::InstOrCallStmt { .. } => "instantiation or call statement",
+            StmtData::PslDirective { .. } => "PSL directive",
         }
     }
 }
@@ -1169,6 +1318,7 @@ pub enum Sensitivity {
 pub enum AssignTarget {
     Name(CompoundName),
     Aggregate(ParenElems),
+    External(ExternalName),
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -26,6 +26,36 @@ impl<T: Grinder> Bundler<T> {
     }
 }
 
+impl<T> Bundler<T>
+where
+    T: Grinder<Item = Option<(usize, char, u8, Category)>, Error = DiagBuilder2>,
+{
+    /// Check whether the characters after a `--` form the `psl` keyword
+    /// followed by whitespace. Returns the number of characters up to and
+    /// including the keyword.
+    fn psl_comment_prefix(&mut self) -> Option<usize> {
+        let mut i = 0;
+        while let Some((_, c, _, _)) = *self.inner.lookahead(i) {
+            if c == ' ' || c == '\t' {
+                i += 1;
+            } else {
+                break;
+            }
+        }
+        for (k, expected) in "psl".chars().enumerate() {
+            match *self.inner.lookahead(i + k) {
+                Some((_, c, _, _)) if c.to_ascii_lowercase() == expected => (),
+                _ => return None,
+            }
+        }
+        match *self.inner.lookahead(i + 3) {
+            Some((_, '\n', _, _)) => None,
+            Some((_, _, _, Category::Space)) => Some(i + 3),
+            _ => None,
+        }
+    }
+}
+
 impl<T> Grinder for Bundler<T>
 where
     T: Grinder<Item = Option<(usize, char, u8, Category)>, Error = DiagBuilder2>,
@@ -48,6 +78,19 @@ where
         if c == '-' {
             if let Some((_, '-', _, _)) = *self.inner.lookahead(0) {
                 self.inner.next();
+
+                // PSL directives may be embedded in comments of the form
+                // `-- psl <directive>`. Drop the prefix and lex the remainder
+                // of the line as regular source text.
+                if let Some(len) = self.psl_comment_prefix() {
+                    for _ in 0..len {
+                        if let Some((offset, _, sz, _)) = self.inner.next() {
+                            sp.end = offset + sz as usize;
+                        }
+                    }
+                    return Some(Spanned::new(Bundle::Space, sp));
+                }
+
                 while let &Some((offset, d, sz, _)) = self.inner.lookahead(0) {
                     if d == '\n' {
                         break;
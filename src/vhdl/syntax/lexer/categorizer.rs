@@ -2,6 +2,7 @@
 
 use moore_common::errors::DiagBuilder2;
 use moore_common::grind::Grinder;
+use moore_common::source::BYTE_ORDER_MARK;
 
 /// A grinder that categorizes characters into different groups as per the VHDL
 /// language standard.
@@ -37,10 +38,12 @@ where
         };
         let cat = match c {
             '"' | '#' | '&' | '\'' | '(' | ')' | '*' | '+' | ',' | '-' | '.' | '/' | ':' | ';'
-            | '<' | '=' | '>' | '?' | '@' | '[' | ']' | '_' | '`' | '|' => Category::Special,
+            | '<' | '=' | '>' | '?' | '@' | '[' | ']' | '^' | '_' | '`' | '|' | '!' | '{' | '}' => {
+                Category::Special
+            }
             c if c.is_alphabetic() => Category::Letter,
             c if c.is_digit(10) => Category::Digit,
-            c if c.is_whitespace() => Category::Space,
+            c if c.is_whitespace() || c == BYTE_ORDER_MARK => Category::Space,
             _ => Category::Other,
         };
         Some((offset, c, sz, cat))
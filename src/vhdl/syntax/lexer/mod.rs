@@ -578,4 +578,39 @@ mod test {
             ],
         );
     }
+
+    #[test]
+    fn psl_comments() {
+        check(
+            "
+            -- psl assert a -> b;
+            --PSL cover {a}!;
+            -- pslx
+            -- psl
+            a <-> b |-> c |=> d && e
+        ",
+            &[
+                Keyword(Kw::Assert),
+                Ident(name("a")),
+                PslImpl,
+                Ident(name("b")),
+                Semicolon,
+                Keyword(Kw::Cover),
+                OpenDelim(Brace),
+                Ident(name("a")),
+                CloseDelim(Brace),
+                Excl,
+                Semicolon,
+                Ident(name("a")),
+                PslIff,
+                Ident(name("b")),
+                PslSeqImplOl,
+                Ident(name("c")),
+                PslSeqImplNol,
+                Ident(name("d")),
+                PslAnd,
+                Ident(name("e")),
+            ],
+        );
+    }
 }
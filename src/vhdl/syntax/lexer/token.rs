@@ -51,6 +51,19 @@ pub enum Token {
     Pow,
     Pipe,
     Qmark,
+    At,
+    Caret,
+    Excl,
+    /// The PSL logical implication `->`.
+    PslImpl,
+    /// The PSL logical equivalence `<->`.
+    PslIff,
+    /// The PSL overlapping suffix implication `|->`.
+    PslSeqImplOl,
+    /// The PSL non-overlapping suffix implication `|=>`.
+    PslSeqImplNol,
+    /// The PSL length-matching sequence conjunction `&&`.
+    PslAnd,
 
     /// The end of the input file.
     Eof,
@@ -65,6 +78,8 @@ impl Token {
             CloseDelim(Paren) => ")",
             OpenDelim(Brack) => "[",
             CloseDelim(Brack) => "]",
+            OpenDelim(Brace) => "{",
+            CloseDelim(Brace) => "}",
             Keyword(kw) => kw.as_str(),
 
             Period => ".",
@@ -98,6 +113,14 @@ impl Token {
             Pow => "**",
             Pipe => "|",
             Qmark => "?",
+            At => "@",
+            Caret => "^",
+            Excl => "!",
+            PslImpl => "->",
+            PslIff => "<->",
+            PslSeqImplOl => "|->",
+            PslSeqImplNol => "|=>",
+            PslAnd => "&&",
 
             Eof => "end of file",
         }
@@ -162,6 +185,7 @@ impl Literal {
 pub enum DelimToken {
     Paren,
     Brack,
+    Brace,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
@@ -13,13 +13,16 @@ use moore_common::errors::*;
 use moore_common::grind::{self, Grinder};
 use moore_common::source::*;
 
-pub fn parse(src: Source) -> Result<Vec<ast::DesignUnit>, ()> {
+/// Parse a source file.
+///
+/// Diagnostics are emitted through `emitter` as they are encountered.
+pub fn parse(src: Source, emitter: &dyn DiagEmitter) -> Result<Vec<ast::DesignUnit>, ()> {
     use self::parser::token_stream::TokenStream;
 
     // Get a grinder on the bytes of the source file.
     let content = src.get_content();
     let bytes = grind::from_iter(content.bytes().iter().map(|x| *x))
-        .vent(|err: DiagBuilder2| eprintln!("{}", err));
+        .vent(|err: DiagBuilder2| emitter.emit(err));
 
     // Perform lexical analysis on the bytes.
     let tokens = lexer::Lexer::new(bytes, src);
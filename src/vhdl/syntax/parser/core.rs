@@ -3,6 +3,7 @@
 use crate::lexer::token::*;
 use crate::parser::rules::{Parser, Recovered, RecoveredResult, Reported, ReportedResult};
 use moore_common::errors::*;
+use moore_common::lints;
 use moore_common::name::*;
 use moore_common::source::*;
 use std;
@@ -297,7 +298,13 @@ where
         } else if accept(p, sep) {
             if term.matches(p) {
                 let q = p.last_span();
-                p.emit(DiagBuilder2::warning(format!("Superfluous trailing {}", sep)).span(q));
+                p.emit(
+                    DiagBuilder2::lint(
+                        lints::TRAILING_COMMA,
+                        format!("Superfluous trailing {}", sep),
+                    )
+                    .span(q),
+                );
                 break;
             }
         } else {
@@ -8,6 +8,7 @@ pub mod token_stream;
 #[macro_use]
 mod core;
 pub mod basic;
+pub mod psl;
 pub mod rules;
 
 #[cfg(test)]
@@ -0,0 +1,437 @@
+// Copyright (c) 2016-2020 Fabian Schuiki
+
+//! This module implements the parser for the Property Specification Language
+//! (PSL) embedded in VHDL. See IEEE 1076-2008 section 11.9 and IEEE 1850.
+//!
+//! PSL directives may appear as concurrent statements, either directly in the
+//! source text or in comments of the form `-- psl <directive>`, which the
+//! lexer unpacks into regular tokens. The boolean layer of PSL consists of
+//! VHDL expressions; the temporal layer is parsed into the nodes of the AST
+//! that mirror the SystemVerilog assertions. The grammar is simplified as
+//! follows, in order of increasing precedence:
+//!
+//! ```text
+//! prop := prop ("->" | "<->") prop
+//!       | seq ("|->" | "|=>") prop
+//!       | prop ("until" | "until!" | "before" | "before!") prop
+//!       | prop "abort" expr
+//!       | ("always" | "never" | "eventually!" | "next" | "next!") prop
+//!       | "(" prop ")" ["@" primary_expr]
+//!       | seq ["!"] ["@" primary_expr]
+//! seq  := seq ("|" | "&&" | "within") seq
+//!       | seq (";" | ":") seq
+//!       | seq "[" ("*" [expr] | "+" | "=" expr | "->" [expr]) "]"
+//!       | "{" seq "}"
+//!       | expr
+//! ```
+
+use crate::ast;
+use crate::lexer::token::*;
+use crate::parser::core::*;
+use crate::parser::rules::{parse_expr, parse_primary_expr, Parser, Reported, ReportedResult};
+use moore_common::errors::*;
+use moore_common::source::*;
+
+/// Check whether a token is a PSL keyword which is not reserved in VHDL and is
+/// therefore lexed as an identifier.
+fn is_psl_kw(tkn: Token, kw: &str) -> bool {
+    match tkn {
+        Ident(n) => n.as_str().eq_ignore_ascii_case(kw),
+        _ => false,
+    }
+}
+
+/// Check whether a token may end a VHDL expression.
+fn ends_expr(tkn: Token) -> bool {
+    match tkn {
+        Ident(_) | Lit(_) | CloseDelim(_) => true,
+        _ => false,
+    }
+}
+
+/// Check whether the token at `offset` only occurs in PSL. The token before
+/// it is used to tell infix PSL keywords apart from VHDL names.
+fn is_psl_token<P: Parser>(p: &mut P, offset: usize) -> bool {
+    let tkn = p.peek(offset).value;
+    match tkn {
+        PslImpl | PslIff | PslSeqImplOl | PslSeqImplNol | PslAnd | Excl => true,
+        OpenDelim(Brace) | Keyword(Kw::Next) | Keyword(Kw::Until) => true,
+        At => offset > 0 && ends_expr(p.peek(offset - 1).value),
+        _ if is_psl_kw(tkn, "abort") || is_psl_kw(tkn, "before") || is_psl_kw(tkn, "within") => {
+            offset > 0 && ends_expr(p.peek(offset - 1).value)
+        }
+        _ if is_psl_kw(tkn, "always") || is_psl_kw(tkn, "never") => {
+            match p.peek(offset + 1).value {
+                Ident(_) | Lit(_) | OpenDelim(_) | Keyword(Kw::Next) | Keyword(Kw::Not) => true,
+                _ => false,
+            }
+        }
+        _ => is_psl_kw(tkn, "eventually") && p.peek(offset + 1).value == Excl,
+    }
+}
+
+/// Check whether the `assert` keyword at the current position introduces a
+/// PSL assertion directive rather than a VHDL assertion statement.
+pub fn is_psl_assert<P: Parser>(p: &mut P) -> bool {
+    for i in 1.. {
+        match p.peek(i).value {
+            Semicolon | Eof => return false,
+            _ if is_psl_token(p, i) => return true,
+            _ => (),
+        }
+    }
+    unreachable!();
+}
+
+/// Check whether the parenthesized expression at the current position
+/// contains a PSL property rather than a VHDL expression.
+fn is_paren_prop<P: Parser>(p: &mut P) -> bool {
+    let mut depth = 0;
+    for i in 0.. {
+        match p.peek(i).value {
+            OpenDelim(Paren) => depth += 1,
+            CloseDelim(Paren) => {
+                depth -= 1;
+                if depth == 0 {
+                    return false;
+                }
+            }
+            Semicolon | Eof => return false,
+            _ if is_psl_token(p, i) => return true,
+            _ => (),
+        }
+    }
+    unreachable!();
+}
+
+/// Parse a PSL verification directive.
+///
+/// ```text
+/// psl_directive := ("assert"|"assume"|"cover"|"restrict") prop ["report" expr] ["severity" expr] ";"
+/// ```
+pub fn parse_psl_directive<P: Parser>(p: &mut P) -> ReportedResult<ast::StmtData> {
+    let Spanned { value, span } = p.peek(0);
+    let kind = match value {
+        Keyword(Kw::Assert) => ast::DirectiveKind::Assert,
+        Keyword(Kw::Assume) => ast::DirectiveKind::Assume,
+        Keyword(Kw::Cover) => ast::DirectiveKind::Cover,
+        Keyword(Kw::Restrict) => ast::DirectiveKind::Restrict,
+        wrong => {
+            p.emit(
+                DiagBuilder2::error(format!(
+                    "Expected PSL directive, but found {} instead",
+                    wrong
+                ))
+                .span(span),
+            );
+            return Err(Reported);
+        }
+    };
+    p.bump();
+    let prop = parse_psl_prop(p)?;
+
+    // Parse the optional "report" part.
+    let report = if accept(p, Keyword(Kw::Report)) {
+        Some(parse_expr(p)?)
+    } else {
+        None
+    };
+
+    // Parse the optional "severity" part.
+    let severity = if accept(p, Keyword(Kw::Severity)) {
+        Some(parse_expr(p)?)
+    } else {
+        None
+    };
+
+    require(p, Semicolon)?;
+    Ok(ast::PslDirective {
+        kind: Spanned::new(kind, span),
+        prop: prop,
+        report: report,
+        severity: severity,
+    })
+}
+
+/// Check whether the current position starts a PSL declaration.
+pub fn is_psl_decl<P: Parser>(p: &mut P) -> bool {
+    match p.peek(0).value {
+        Keyword(Kw::Property) | Keyword(Kw::Sequence) => true,
+        Keyword(Kw::Default) => is_psl_kw(p.peek(1).value, "clock"),
+        _ => false,
+    }
+}
+
+/// Parse a PSL declaration.
+///
+/// ```text
+/// psl_decl
+///   := "property" ident "is" prop ";"
+///   := "sequence" ident "is" seq ";"
+///   := "default" "clock" "is" expr ";"
+/// ```
+pub fn parse_psl_decl<P: Parser>(p: &mut P) -> ReportedResult<ast::PslDecl> {
+    let mut span = p.peek(0).span;
+    let (name, data) = if accept(p, Keyword(Kw::Property)) {
+        let name = parse_ident(p, "property name")?;
+        require(p, Keyword(Kw::Is))?;
+        (Some(name), ast::PslDeclData::Property(parse_psl_prop(p)?))
+    } else if accept(p, Keyword(Kw::Sequence)) {
+        let name = parse_ident(p, "sequence name")?;
+        require(p, Keyword(Kw::Is))?;
+        (Some(name), ast::PslDeclData::Sequence(parse_psl_seq(p)?))
+    } else {
+        require(p, Keyword(Kw::Default))?;
+        parse_ident(p, "`clock`")?;
+        require(p, Keyword(Kw::Is))?;
+        (None, ast::PslDeclData::DefaultClock(parse_expr(p)?))
+    };
+    require(p, Semicolon)?;
+    span.expand(p.last_span());
+    Ok(ast::PslDecl {
+        id: Default::default(),
+        span: span,
+        name: name,
+        data: data,
+    })
+}
+
+/// Parse a PSL property.
+pub fn parse_psl_prop<P: Parser>(p: &mut P) -> ReportedResult<ast::PslProp> {
+    let lhs = parse_prop_suffix(p)?;
+    let op = match p.peek(0).value {
+        PslImpl => ast::PropBinOp::Impl,
+        PslIff => ast::PropBinOp::Iff,
+        _ => return Ok(lhs),
+    };
+    p.bump();
+    let rhs = parse_psl_prop(p)?;
+    Ok(ast::PslProp {
+        span: Span::union(lhs.span, rhs.span),
+        data: ast::PslPropData::BinOp(op, Box::new(lhs), Box::new(rhs)),
+    })
+}
+
+/// Parse a PSL property with an optional suffix implication.
+fn parse_prop_suffix<P: Parser>(p: &mut P) -> ReportedResult<ast::PslProp> {
+    let lhs = parse_prop_bound(p)?;
+    let Spanned { value, span } = p.peek(0);
+    let op = match value {
+        PslSeqImplOl => ast::PropSeqBinOp::ImplOverlap,
+        PslSeqImplNol => ast::PropSeqBinOp::ImplNonoverlap,
+        _ => return Ok(lhs),
+    };
+    let (seqop, seq) = match lhs.data {
+        ast::PslPropData::SeqOp(seqop, seq) => (seqop, seq),
+        _ => {
+            p.emit(
+                DiagBuilder2::error(format!(
+                    "Expected a sequence before `{}`, but found a property",
+                    value.as_str()
+                ))
+                .span(lhs.span)
+                .add_note("Enclose the sequence in braces, e.g. `{a; b}`"),
+            );
+            return Err(Reported);
+        }
+    };
+    p.bump();
+    let rhs = parse_prop_suffix(p)?;
+    Ok(ast::PslProp {
+        span: Span::union(lhs.span, rhs.span),
+        data: ast::PslPropData::SeqBinOp(op, seqop, seq, Box::new(rhs)),
+    })
+}
+
+/// Parse a PSL property with optional bounding and abort operators.
+fn parse_prop_bound<P: Parser>(p: &mut P) -> ReportedResult<ast::PslProp> {
+    let mut lhs = parse_prop_unary(p)?;
+    loop {
+        let tkn = p.peek(0).value;
+        let (weak, strong) = if tkn == Keyword(Kw::Until) {
+            (ast::PropBinOp::Until, ast::PropBinOp::SUntil)
+        } else if is_psl_kw(tkn, "before") {
+            (ast::PropBinOp::Before, ast::PropBinOp::SBefore)
+        } else if is_psl_kw(tkn, "abort") {
+            p.bump();
+            let cond = parse_expr(p)?;
+            lhs = ast::PslProp {
+                span: Span::union(lhs.span, cond.span),
+                data: ast::PslPropData::Abort(Box::new(lhs), cond),
+            };
+            continue;
+        } else {
+            return Ok(lhs);
+        };
+        p.bump();
+        let op = if accept(p, Excl) { strong } else { weak };
+        let rhs = parse_prop_unary(p)?;
+        lhs = ast::PslProp {
+            span: Span::union(lhs.span, rhs.span),
+            data: ast::PslPropData::BinOp(op, Box::new(lhs), Box::new(rhs)),
+        };
+    }
+}
+
+/// Parse a PSL property with an optional temporal prefix operator. The
+/// operand of the prefix operator extends as far to the right as possible.
+fn parse_prop_unary<P: Parser>(p: &mut P) -> ReportedResult<ast::PslProp> {
+    let Spanned {
+        value: tkn,
+        mut span,
+    } = p.peek(0);
+    let op = if is_psl_kw(tkn, "always") {
+        p.bump();
+        ast::PropUnaryOp::Always
+    } else if is_psl_kw(tkn, "never") {
+        p.bump();
+        ast::PropUnaryOp::Never
+    } else if is_psl_kw(tkn, "eventually") {
+        p.bump();
+        require(p, Excl)?;
+        ast::PropUnaryOp::SEventually
+    } else if tkn == Keyword(Kw::Next) {
+        p.bump();
+        if accept(p, Excl) {
+            ast::PropUnaryOp::SNext
+        } else {
+            ast::PropUnaryOp::Next
+        }
+    } else {
+        return parse_prop_primary(p);
+    };
+    let arg = parse_psl_prop(p)?;
+    span.expand(arg.span);
+    Ok(ast::PslProp {
+        span: span,
+        data: ast::PslPropData::UnaryOp(op, Box::new(arg)),
+    })
+}
+
+/// Parse a parenthesized PSL property or a sequence, followed by an optional
+/// clocking operator.
+fn parse_prop_primary<P: Parser>(p: &mut P) -> ReportedResult<ast::PslProp> {
+    let mut span = p.peek(0).span;
+    let mut prop = if p.peek(0).value == OpenDelim(Paren) && is_paren_prop(p) {
+        let mut prop = flanked(p, Paren, parse_psl_prop)?;
+        span.expand(p.last_span());
+        prop.span = span;
+        prop
+    } else {
+        let seq = parse_psl_seq(p)?;
+        let seqop = if accept(p, Excl) {
+            ast::PropSeqOp::Strong
+        } else {
+            ast::PropSeqOp::None
+        };
+        span.expand(p.last_span());
+        ast::PslProp {
+            span: span,
+            data: ast::PslPropData::SeqOp(seqop, seq),
+        }
+    };
+    while accept(p, At) {
+        let clock = parse_primary_expr(p)?;
+        prop = ast::PslProp {
+            span: Span::union(prop.span, clock.span),
+            data: ast::PslPropData::Clocked(clock, Box::new(prop)),
+        };
+    }
+    Ok(prop)
+}
+
+/// Parse a PSL sequence, i.e. a braced SERE or a boolean, each with optional
+/// repetitions.
+pub fn parse_psl_seq<P: Parser>(p: &mut P) -> ReportedResult<ast::PslSeq> {
+    let mut span = p.peek(0).span;
+    let mut seq = if p.peek(0).value == OpenDelim(Brace) {
+        let mut seq = flanked(p, Brace, parse_sere)?;
+        span.expand(p.last_span());
+        seq.span = span;
+        seq
+    } else {
+        let expr = parse_expr(p)?;
+        ast::PslSeq {
+            span: expr.span,
+            data: ast::PslSeqData::Expr(expr),
+        }
+    };
+    while p.peek(0).value == OpenDelim(Brack) {
+        let rep = flanked(p, Brack, parse_rep)?;
+        span.expand(p.last_span());
+        seq = ast::PslSeq {
+            span: span,
+            data: ast::PslSeqData::Rep(Box::new(seq), rep),
+        };
+    }
+    Ok(seq)
+}
+
+/// Parse a sequential extended regular expression, i.e. the contents of a
+/// braced sequence.
+fn parse_sere<P: Parser>(p: &mut P) -> ReportedResult<ast::PslSeq> {
+    let mut lhs = parse_sere_concat(p)?;
+    loop {
+        let tkn = p.peek(0).value;
+        let op = match tkn {
+            Pipe => ast::SeqBinOp::Or,
+            PslAnd => ast::SeqBinOp::Intersect,
+            _ if is_psl_kw(tkn, "within") => ast::SeqBinOp::Within,
+            _ => return Ok(lhs),
+        };
+        p.bump();
+        let rhs = parse_sere_concat(p)?;
+        lhs = ast::PslSeq {
+            span: Span::union(lhs.span, rhs.span),
+            data: ast::PslSeqData::BinOp(op, Box::new(lhs), Box::new(rhs)),
+        };
+    }
+}
+
+/// Parse a concatenation or fusion of sequences.
+fn parse_sere_concat<P: Parser>(p: &mut P) -> ReportedResult<ast::PslSeq> {
+    let mut lhs = parse_psl_seq(p)?;
+    loop {
+        let op = match p.peek(0).value {
+            Semicolon => ast::SeqBinOp::Concat,
+            Colon => ast::SeqBinOp::Fusion,
+            _ => return Ok(lhs),
+        };
+        p.bump();
+        let rhs = parse_psl_seq(p)?;
+        lhs = ast::PslSeq {
+            span: Span::union(lhs.span, rhs.span),
+            data: ast::PslSeqData::BinOp(op, Box::new(lhs), Box::new(rhs)),
+        };
+    }
+}
+
+/// Parse the contents of a repetition operator.
+///
+/// ```text
+/// rep := "*" [expr] | "+" | "=" expr | "->" [expr]
+/// ```
+fn parse_rep<P: Parser>(p: &mut P) -> ReportedResult<ast::PslRep> {
+    let Spanned { value, span } = p.peek(0);
+    p.bump();
+    let has_count = p.peek(0).value != CloseDelim(Brack);
+    Ok(match value {
+        Mul if has_count => ast::PslRep::Consec(parse_expr(p)?),
+        Mul => ast::PslRep::ConsecStar,
+        Add => ast::PslRep::ConsecPlus,
+        Eq => ast::PslRep::Nonconsec(parse_expr(p)?),
+        PslImpl if has_count => ast::PslRep::Goto(Some(parse_expr(p)?)),
+        PslImpl => ast::PslRep::Goto(None),
+        wrong => {
+            p.emit(
+                DiagBuilder2::error(format!(
+                    "Expected repetition operator `[*`, `[+`, `[=`, or `[->`, but found {} \
+                     instead",
+                    wrong
+                ))
+                .span(span),
+            );
+            return Err(Reported);
+        }
+    })
+}
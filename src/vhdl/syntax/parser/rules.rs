@@ -48,8 +48,10 @@
 use crate::ast;
 use crate::lexer::token::*;
 use crate::parser::core::*;
+use crate::parser::psl::*;
 use crate::parser::TokenStream;
 use moore_common::errors::*;
+use moore_common::lints;
 use moore_common::name::*;
 use moore_common::source::*;
 use std::fmt::Display;
@@ -120,6 +122,8 @@ pub fn parse_design_unit<P: Parser>(p: &mut P) -> RecoveredResult<ast::DesignUni
         Keyword(Kw::Package) => {
             if p.peek(1).value == Keyword(Kw::Body) {
                 parse_package_body(p).map(|d| ast::DesignUnitData::PkgBody(d))
+            } else if p.peek(2).value == Keyword(Kw::Is) && p.peek(3).value == Keyword(Kw::New) {
+                parse_package_inst(p, true).map(|d| ast::DesignUnitData::PkgInst(d))
             } else {
                 parse_package_decl(p).map(|d| ast::DesignUnitData::PkgDecl(d))
             }
@@ -325,7 +329,15 @@ pub fn parse_name_suffix<P: Parser>(
         return parse_name_suffix(p, name);
     }
 
-    // Try to parse a signature.
+    // Try to parse a signature. Brackets followed by `*`, `+`, `=`, or `->`
+    // are PSL repetition operators instead.
+    let is_psl_rep = match p.peek(1).value {
+        Mul | Add | Eq | PslImpl => true,
+        _ => false,
+    };
+    if p.peek(0).value == OpenDelim(Brack) && is_psl_rep {
+        return Ok(name);
+    }
     if let Some(sig) = try_flanked(p, Brack, parse_signature)? {
         name.span.expand(p.last_span());
         name.parts.push(ast::NamePart::Signature(sig));
@@ -385,20 +397,23 @@ where
         if let Some(name) = name.into() {
             if n.value != name.value {
                 p.emit(
-                    DiagBuilder2::warning(format!(
-                        "`{}` does not match {} name `{}`",
-                        n.value, msg, name.value
-                    ))
+                    DiagBuilder2::lint(
+                        lints::LABEL,
+                        format!("`{}` does not match {} name `{}`", n.value, msg, name.value),
+                    )
                     .span(n.span)
                     .add_note(format!("see IEEE 1076-2008 {}", sec)),
                 );
             }
         } else {
             p.emit(
-                DiagBuilder2::warning(format!(
-                    "Label `{}` is given at the end of {}, but not at the beginning",
-                    n.value, msg
-                ))
+                DiagBuilder2::lint(
+                    lints::LABEL,
+                    format!(
+                        "Label `{}` is given at the end of {}, but not at the beginning",
+                        n.value, msg
+                    ),
+                )
                 .span(n.span)
                 .add_note(format!("see IEEE 1076-2008 {}", sec)),
             );
@@ -839,6 +854,10 @@ pub fn try_decl_item<P: Parser>(p: &mut P) -> ReportedResult<Option<ast::DeclIte
         }
         // group_decl := "group" ...
         Keyword(Kw::Group) => Some(ast::DeclItem::GroupDecl(parse_group_decl(p)?)),
+        // psl_decl := "property" ...
+        //          := "sequence" ...
+        //          := "default" "clock" ...
+        _ if is_psl_decl(p) => Some(ast::DeclItem::PslDecl(parse_psl_decl(p)?)),
         _ => None,
     })
 }
@@ -1077,6 +1096,7 @@ pub fn parse_primary_expr<P: Parser>(p: &mut P) -> ReportedResult<ast::Expr> {
             p.bump();
             Some(ast::BoxExpr)
         }
+        Lshift => Some(ast::ExternalExpr(Box::new(parse_external_name(p)?))),
 
         Keyword(Kw::New) => {
             p.bump();
@@ -1162,13 +1182,73 @@ pub fn parse_primary_expr<P: Parser>(p: &mut P) -> ReportedResult<ast::Expr> {
         .span(q)
         .add_note(
             "A primary expression is either an abstract, bit string, character, or string \
-             literal; a name; a parenthesized expression `( ... )`; an allocation `new ...`; or \
-             the constants `null`, `open`, or `others`.",
+             literal; a name; an external name `<< ... >>`; a parenthesized expression \
+             `( ... )`; an allocation `new ...`; or the constants `null`, `open`, or `others`.",
         ),
     );
     Err(Reported)
 }
 
+/// Parse an external name. See IEEE 1076-2008 section 8.7.
+///
+/// ```text
+/// external_name := "<<" ("constant"|"signal"|"variable") external_path ":" subtype_ind ">>"
+/// external_path
+///   := "@" name
+///   := "." name
+///   := {"^" "."} name
+/// ```
+pub fn parse_external_name<P: Parser>(p: &mut P) -> ReportedResult<ast::ExternalName> {
+    let mut span = p.peek(0).span;
+    require(p, Lshift)?;
+    let Spanned {
+        value: tkn,
+        span: kind_span,
+    } = p.peek(0);
+    let kind = match tkn {
+        Keyword(Kw::Constant) => ast::ObjKind::Const,
+        Keyword(Kw::Signal) => ast::ObjKind::Signal,
+        Keyword(Kw::Variable) => ast::ObjKind::Var,
+        wrong => {
+            p.emit(
+                DiagBuilder2::error(format!(
+                    "Expected `constant`, `signal`, or `variable` in external name, found {} \
+                     instead",
+                    wrong
+                ))
+                .span(kind_span)
+                .add_note("see IEEE 1076-2008 section 8.7"),
+            );
+            return Err(Reported);
+        }
+    };
+    p.bump();
+    let root = if accept(p, At) {
+        ast::ExternalRoot::Package
+    } else if accept(p, Period) {
+        ast::ExternalRoot::Absolute
+    } else {
+        let mut levels = 0;
+        while accept(p, Caret) {
+            require(p, Period)?;
+            levels += 1;
+        }
+        ast::ExternalRoot::Relative(levels)
+    };
+    let path = parse_name(p)?;
+    require(p, Colon)?;
+    let subtype = parse_subtype_ind(p)?;
+    require(p, Rshift)?;
+    span.expand(p.last_span());
+    Ok(ast::ExternalName {
+        span: span,
+        kind: Spanned::new(kind, kind_span),
+        root: root,
+        path: path,
+        subtype: subtype,
+    })
+}
+
 pub fn try_name_or_qualified_primary_expr<P: Parser>(
     p: &mut P,
 ) -> ReportedResult<Option<ast::Expr>> {
@@ -1643,7 +1723,11 @@ pub fn parse_type_decl<P: Parser>(
                     require(p, Keyword(Kw::Body))?;
                 }
                 parse_optional_matching_ident(p, name, "type", "section 5.6");
-                ast::ProtectedType(decl_items)
+                if body {
+                    ast::ProtectedBody(decl_items)
+                } else {
+                    ast::ProtectedType(decl_items)
+                }
             }
 
             // Emit an error for anything else.
@@ -1731,7 +1815,15 @@ pub fn parse_alias_decl<P: Parser>(p: &mut P) -> ReportedResult<ast::AliasDecl>
         None
     };
     require(p, Keyword(Kw::Is))?;
-    let target = parse_name(p)?;
+    let target = if p.peek(0).value == Lshift {
+        parse_primary_expr(p)?
+    } else {
+        let name = parse_name(p)?;
+        ast::Expr {
+            span: name.span,
+            data: ast::NameExpr(name),
+        }
+    };
     require(p, Semicolon)?;
     span.expand(p.last_span());
     Ok(ast::AliasDecl {
@@ -2503,7 +2595,9 @@ pub fn parse_stmt<P: Parser>(p: &mut P) -> ReportedResult<ast::Stmt> {
     // by a name or parenthesized expression (aggregate).
     let data = match p.peek(0).value {
         Keyword(Kw::Wait) => parse_wait_stmt(p)?,
+        Keyword(Kw::Assert) if is_psl_assert(p) => parse_psl_directive(p)?,
         Keyword(Kw::Assert) => parse_assert_stmt(p)?,
+        Keyword(Kw::Assume) | Keyword(Kw::Cover) | Keyword(Kw::Restrict) => parse_psl_directive(p)?,
         Keyword(Kw::Report) => parse_report_stmt(p)?,
 
         // For the if statement, check if the `generate` or the `then` keyword
@@ -2564,6 +2658,12 @@ pub fn parse_stmt<P: Parser>(p: &mut P) -> ReportedResult<ast::Stmt> {
             parse_inst_or_call_tail(p, Some(target), name)?
         }
 
+        Lshift => {
+            let name = parse_external_name(p)?;
+            let span = name.span;
+            parse_assign_tail(p, Spanned::new(ast::AssignTarget::External(name), span))?
+        }
+
         wrong => {
             if let Some(name) = try_name(p)? {
                 // Try to parse a statement that begins with a name.
@@ -97,6 +97,10 @@ fn design_unit() {
     // parse!("configuration foo is begin end;", parse_design_unit);
     parse!("package foo is end;", parse_design_unit);
     parse!("context foo is end;", parse_design_unit);
+    parse!(
+        "package foo is new bar generic map (N => 8);",
+        parse_design_unit
+    );
 }
 
 #[test]
@@ -428,6 +432,10 @@ fn alias_decl() {
         parse_alias_decl
     );
     parse!("alias STD_BIT is STD.STANDARD.BIT;", parse_alias_decl);
+    parse!(
+        "alias x is <<signal .top.dut.x : std_logic>>;",
+        parse_alias_decl
+    );
     // parse!("alias '0' is STD.STANDARD.'0' [return STD.STANDARD.BIT];", parse_alias_decl);
     // parse!("alias '1' is STD.STANDARD.'1' [return STD.STANDARD.BIT];", parse_alias_decl);
 }
@@ -489,6 +497,18 @@ fn expr() {
     parse!("others", parse_expr);
 }
 
+#[test]
+fn external_name() {
+    parse!("<<signal .top.dut.x : bit>>", parse_expr);
+    parse!("<<constant @work.pkg.C : integer>>", parse_expr);
+    parse!(
+        "<<variable ^.^.gen(3).v : bit_vector(7 downto 0)>>",
+        parse_expr
+    );
+    parse!("<<signal dut.x : bit>> and y", parse_expr);
+    parse!("<<signal .top.x : bit>> <= '1';", parse_stmt);
+}
+
 #[test]
 fn subtype_decl() {
     parse!("subtype foo is integer;", parse_subtype_decl);
@@ -538,3 +558,28 @@ fn config_decl() {
         parse_config_decl
     );
 }
+
+#[test]
+fn psl_directive() {
+    parse!("assert always a -> next b;", parse_stmt);
+    parse!("assert a = '0' or b = '1' report \"no b\";", parse_stmt);
+    parse!(
+        "a0: assert always {a; b[*2]} |=> {c[->1]} abort rst;",
+        parse_stmt
+    );
+    parse!("assume never (a and b);", parse_stmt);
+    parse!(
+        "cover {a[+]; b[=2]; c[*]} ! report \"covered\";",
+        parse_stmt
+    );
+    parse!("restrict {a; b | c && d};", parse_stmt);
+    parse!("assert (always a until! b) @ rising_edge(clk);", parse_stmt);
+    parse!("assert always eventually! a severity warning;", parse_stmt);
+}
+
+#[test]
+fn psl_decl() {
+    parse!("default clock is rising_edge(clk);", try_decl_item);
+    parse!("property p is always a -> next b;", try_decl_item);
+    parse!("sequence s is {a; b : c};", try_decl_item);
+}
@@ -9,6 +9,7 @@ use std::marker::PhantomData;
 
 use num::{BigInt, BigRational};
 
+use crate::common::codes;
 use crate::common::errors::*;
 use crate::common::name::Name;
 use crate::common::score::Result;
@@ -48,7 +49,7 @@ pub enum Term<'t> {
     /// A floating point literal.
     FloatLit(BigRational),
     /// A physical literal.
-    PhysLit(BigInt, Spanned<EitherUnit<'t>>),
+    PhysLit(BigRational, Spanned<EitherUnit<'t>>),
     /// A bit string literal.
     StrLit(Name),
     /// An unresolved name.
@@ -94,6 +95,8 @@ pub enum Term<'t> {
     Binary(Spanned<BinaryOp>, Subterm<'t>, Subterm<'t>),
     /// A term of the form `T'T`.
     Qual(Subterm<'t>, Subterm<'t>),
+    /// A term of the form `T'<attr>` for a predefined attribute.
+    Attr(Subterm<'t>, Spanned<ResolvableName>),
     /// A term of the form `new T`.
     New(Subterm<'t>),
 }
@@ -297,7 +300,11 @@ where
     pub fn ensure_resolved<'t>(&self, term: Spanned<Term<'t>>) -> Result<Spanned<Term<'t>>> {
         match term.value {
             Term::Unresolved(name) => {
-                self.emit(DiagBuilder2::error(format!("`{}` is unknown", name)).span(term.span));
+                self.emit(
+                    DiagBuilder2::error(format!("`{}` is unknown", name))
+                        .code(codes::VH0001)
+                        .span(term.span),
+                );
                 Err(())
             }
             _ => Ok(term),
@@ -341,6 +348,13 @@ where
                     );
                     return Err(());
                 }
+                ast::NamePart::Attribute(ident) if is_predefined_attr(ident.name) => {
+                    let sp = Span::union(term.span, ident.span);
+                    Spanned::new(
+                        Term::Attr(Box::new(term), Spanned::new(ident.name.into(), ident.span)),
+                        sp,
+                    )
+                }
                 ast::NamePart::Attribute(ident) => {
                     let attr = self.termify_name(Spanned::new(ident.name.into(), ident.span))?;
                     match attr.value {
@@ -483,7 +497,8 @@ where
                         }
                     };
                     let lit = match lit.value {
-                        Term::IntLit(v) => v,
+                        Term::IntLit(v) => BigRational::from_integer(v),
+                        Term::FloatLit(v) => v,
                         _ => {
                             self.emit(
                                 DiagBuilder2::error(format!(
@@ -533,6 +548,10 @@ where
                 self.termify_paren_elems(arg)?.into(),
             ),
             ast::NewExpr(ref expr) => Term::New(self.termify_expr(expr)?.into()),
+            ast::ExternalExpr(_) => {
+                self.emit(DiagBuilder2::error("external names not implemented").span(ast.span));
+                return Err(());
+            }
             ref wrong => {
                 self.emit(
                     DiagBuilder2::bug(format!(
@@ -597,8 +616,9 @@ impl<'t, C: DiagEmitter, S> DefSpecificTermContext<'t, Def> for TermContext<C, S
             }
             // TODO: Handle the function case.
             _ if !defs.is_empty() => {
-                let mut d =
-                    DiagBuilder2::error(format!("`{}` is ambiguous", name.value)).span(name.span);
+                let mut d = DiagBuilder2::error(format!("`{}` is ambiguous", name.value))
+                    .code(codes::VH0002)
+                    .span(name.span);
                 d = d.add_note("Found the following definitions:");
                 if first_def.span() != INVALID_SPAN {
                     d = d.span(first_def.span());
@@ -653,8 +673,9 @@ impl<'t, C: DiagEmitter, S> DefSpecificTermContext<'t, Def2<'t>> for TermContext
             }
             // TODO: Handle the function case.
             _ if !defs.is_empty() => {
-                let mut d =
-                    DiagBuilder2::error(format!("`{}` is ambiguous", name.value)).span(name.span);
+                let mut d = DiagBuilder2::error(format!("`{}` is ambiguous", name.value))
+                    .code(codes::VH0002)
+                    .span(name.span);
                 d = d.add_note("Found the following definitions:");
                 if first_def.span() != INVALID_SPAN {
                     d = d.span(first_def.span());
@@ -788,7 +809,11 @@ where
         let term_span = term.span;
         let data = match term.value {
             Term::Unresolved(name) => {
-                self.emit(DiagBuilder2::error(format!("`{}` is unknown", name)).span(term.span));
+                self.emit(
+                    DiagBuilder2::error(format!("`{}` is unknown", name))
+                        .code(codes::VH0001)
+                        .span(term.span),
+                );
                 return Err(());
             }
             Term::IntLit(value) => hir::ExprData::IntegerLiteral(ConstInt::new(None, value)),
@@ -810,6 +835,7 @@ where
                             Term::Unresolved(name) => {
                                 self.emit(
                                     DiagBuilder2::error(format!("`{}` is unknown", name))
+                                        .code(codes::VH0001)
                                         .span(term.span),
                                 );
                                 Err(())
@@ -892,9 +918,15 @@ where
             }
             Term::Ident(def) => match def.value {
                 Def::Const(id) => hir::ExprData::ConstName(id),
+                Def::Generic(id) => hir::ExprData::GenericName(id),
+                Def::GenParam(id) => hir::ExprData::GenParamName(id),
                 Def::Signal(id) => hir::ExprData::SignalName(id),
                 Def::Var(id) => hir::ExprData::VarName(id),
                 Def::File(id) => hir::ExprData::FileName(id),
+                Def::Unit(unit) => hir::ExprData::PhysicalLiteral(
+                    BigRational::from_integer(BigInt::from(1)),
+                    Spanned::new(unit, def.span),
+                ),
                 _ => {
                     self.emit(
                         DiagBuilder2::error(format!(
@@ -908,8 +940,24 @@ where
                     return Err(());
                 }
             },
+            Term::PhysLit(value, unit) => match unit.value {
+                EitherUnit::Old(u) => {
+                    hir::ExprData::PhysicalLiteral(value, Spanned::new(u, unit.span))
+                }
+                EitherUnit::New(_) => {
+                    self.emit(
+                        DiagBuilder2::bug(format!(
+                            "physical literal `{}` not supported in expressions",
+                            term_span.extract()
+                        ))
+                        .span(term_span),
+                    );
+                    return Err(());
+                }
+            },
             Term::Enum(defs) => hir::ExprData::EnumName(defs),
             Term::Select(term, name) => hir::ExprData::Select(self.term_to_expr(*term)?, name),
+            Term::Attr(term, name) => hir::ExprData::Attr(self.term_to_expr(*term)?, name),
             Term::Paren(subterm) => {
                 // A parenthesis with only one element is just a parenthesized
                 // expression. If there's more than one element, this is a
@@ -1055,7 +1103,11 @@ where
     pub fn fold_term_as_type<'t>(&self, term: Spanned<Term<'t>>) -> Result<Spanned<Term<'t>>> {
         let (new, new_term) = match term.value {
             Term::Unresolved(name) => {
-                self.emit(DiagBuilder2::error(format!("`{}` is unknown", name)).span(term.span));
+                self.emit(
+                    DiagBuilder2::error(format!("`{}` is unknown", name))
+                        .code(codes::VH0001)
+                        .span(term.span),
+                );
                 return Err(());
             }
             Term::RangeSuffix(subterm, range) => {
@@ -1426,7 +1478,11 @@ where
     pub fn term_to_ident(&self, term: Spanned<Term>) -> Result<Spanned<Def>> {
         Ok(match term.value {
             Term::Unresolved(name) => {
-                self.emit(DiagBuilder2::error(format!("`{}` is unknown", name)).span(term.span));
+                self.emit(
+                    DiagBuilder2::error(format!("`{}` is unknown", name))
+                        .code(codes::VH0001)
+                        .span(term.span),
+                );
                 return Err(());
             }
             Term::Ident(def) => def,
@@ -1438,6 +1494,7 @@ where
                 } else {
                     self.emit(
                         DiagBuilder2::error(format!("`{}` is ambiguous", term.span.extract()))
+                            .code(codes::VH0002)
                             .span(term.span),
                     );
                     debugln!("Its definitions are {:#?}", defs);
@@ -1896,7 +1953,11 @@ where
 {
     match term.value {
         Term::Unresolved(name) => {
-            ctx.emit(DiagBuilder2::error(format!("`{}` is unknown", name)).span(term.span));
+            ctx.emit(
+                DiagBuilder2::error(format!("`{}` is unknown", name))
+                    .code(codes::VH0001)
+                    .span(term.span),
+            );
             Err(())
         }
         Term::IntLit(value) => Ok(ctx.alloc(hir::LitExpr::new_integer(term.span, value))),
@@ -1915,3 +1976,11 @@ where
         }
     }
 }
+
+/// Check whether a name refers to a predefined attribute that is supported.
+///
+/// Only the signal attribute `'event` is handled at the moment. See IEEE
+/// 1076-2008 section 16.2.4.
+fn is_predefined_attr(name: Name) -> bool {
+    name.as_str().eq_ignore_ascii_case("event")
+}
@@ -46,6 +46,8 @@ pub enum Ty {
     Record(RecordTy),
     /// A subprogram type.
     Subprog(SubprogTy),
+    /// A protected type.
+    Protected(TypeDeclRef),
 }
 
 impl Ty {
@@ -64,6 +66,7 @@ impl Ty {
             Ty::File(..) => "file type",
             Ty::Record(_) => "record type",
             Ty::Subprog(_) => "subprogram type",
+            Ty::Protected(_) => "protected type",
         }
     }
 
@@ -134,6 +137,7 @@ impl fmt::Display for Ty {
             Ty::File(ref ty) => write!(f, "file of {}", ty),
             Ty::Record(ref ty) => write!(f, "{}", ty),
             Ty::Subprog(ref ty) => write!(f, "{}", ty),
+            Ty::Protected(_) => write!(f, "protected"),
         }
     }
 }
@@ -193,8 +197,8 @@ impl IntTy {
     /// if the range has a negative or zero length.
     pub fn maybe_null(self) -> Ty {
         match self.dir {
-            Dir::To if self.left_bound >= self.right_bound => Ty::Null,
-            Dir::Downto if self.left_bound <= self.right_bound => Ty::Null,
+            Dir::To if self.left_bound > self.right_bound => Ty::Null,
+            Dir::Downto if self.left_bound < self.right_bound => Ty::Null,
             _ => self.into(),
         }
     }
@@ -202,8 +206,8 @@ impl IntTy {
     /// The length of the range.
     pub fn len(&self) -> BigInt {
         match self.dir {
-            Dir::To => &self.left_bound + BigInt::one() - &self.right_bound,
-            Dir::Downto => &self.right_bound + BigInt::one() - &self.left_bound,
+            Dir::To => &self.right_bound + BigInt::one() - &self.left_bound,
+            Dir::Downto => &self.left_bound + BigInt::one() - &self.right_bound,
         }
     }
 }
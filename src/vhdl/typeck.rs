@@ -10,9 +10,11 @@ use crate::common::errors::*;
 use crate::common::score::{NodeMaker, NodeStorage, Result};
 use crate::common::source::{Span, Spanned, INVALID_SPAN};
 use crate::common::{NodeId, Verbosity};
+use crate::generic_env::GenericEnv;
 use crate::hir;
 use crate::konst::*;
 use crate::lazy::LazyNode;
+use crate::overload_resolver::are_types_matching;
 use crate::score::*;
 use crate::ty::*;
 
@@ -99,14 +101,27 @@ impl<'sbc, 'lazy, 'sb, 'ast, 'ctx> TypeckContext<'sbc, 'lazy, 'sb, 'ast, 'ctx> {
         I: Into<NodeId>,
     {
         let id = id.into();
+        let key = (id, self.ctx.env);
 
         // If the typeval has already been performed, return its result.
-        if let Some(&node) = self.ctx.sb.typeval_table.borrow().get(&id) {
+        if let Some(&node) = self.ctx.sb.typeval_table.borrow().get(&key) {
             return node;
         }
 
+        // Nodes without a scheduled task, such as the builtin types, have the
+        // same type in every generic environment.
+        if self.ctx.env != GenericEnv::default()
+            && !self.ctx.lazy.typeval.borrow().contains_key(&id)
+        {
+            let default_key = (id, GenericEnv::default());
+            if let Some(&node) = self.ctx.sb.typeval_table.borrow().get(&default_key) {
+                return node;
+            }
+        }
+
         // Otherwise run the task scheduled in the lazy typeval table, then store
-        // the result.
+        // the result. The task is kept around, since the node may have to be
+        // evaluated again in a different generic environment.
         let task = self
             .ctx
             .lazy
@@ -114,7 +129,15 @@ impl<'sbc, 'lazy, 'sb, 'ast, 'ctx> TypeckContext<'sbc, 'lazy, 'sb, 'ast, 'ctx> {
             .borrow_mut()
             .set(id, LazyNode::Running);
         let result = match task {
-            Some(LazyNode::Pending(f)) => f(self),
+            Some(LazyNode::Pending(f)) => {
+                let result = f(self);
+                self.ctx
+                    .lazy
+                    .typeval
+                    .borrow_mut()
+                    .insert(id, LazyNode::Pending(f));
+                result
+            }
             Some(LazyNode::Running) => {
                 self.ctx
                     .bug(id, format!("recursion on typeval of {:?}", id));
@@ -144,7 +167,7 @@ impl<'sbc, 'lazy, 'sb, 'ast, 'ctx> TypeckContext<'sbc, 'lazy, 'sb, 'ast, 'ctx> {
             }
         }
 
-        self.ctx.sb.typeval_table.borrow_mut().insert(id, result);
+        self.ctx.sb.typeval_table.borrow_mut().insert(key, result);
         result
     }
 
@@ -157,7 +180,7 @@ impl<'sbc, 'lazy, 'sb, 'ast, 'ctx> TypeckContext<'sbc, 'lazy, 'sb, 'ast, 'ctx> {
                     .span(span),
             );
         }
-        if exp == act {
+        if exp == act || are_types_matching(exp, act) {
             return true;
         }
         let (exp_flat, act_flat) = match (
@@ -193,8 +216,18 @@ impl<'sbc, 'lazy, 'sb, 'ast, 'ctx> TypeckContext<'sbc, 'lazy, 'sb, 'ast, 'ctx> {
     }
 
     /// Type check the time expression in a delay mechanism.
-    pub fn typeck_delay_mechanism(&self, _node: &'ctx hir::DelayMechanism) {
-        // TODO: implement this
+    pub fn typeck_delay_mechanism(&self, node: &'ctx hir::DelayMechanism) {
+        if let hir::DelayMechanism::RejectInertial(expr) = *node {
+            self.typeck_time_expr(expr);
+        }
+    }
+
+    /// Type check an expression that must be of type `TIME`.
+    pub fn typeck_time_expr(&self, expr: ExprRef) {
+        if let Ok(ty) = self.lazy_typeval(expr) {
+            let span = self.ctx.span(expr).unwrap();
+            self.must_match(self.ctx.builtin_time_type(), ty, span);
+        }
     }
 
     /// Type check a waveform.
@@ -212,9 +245,8 @@ impl<'sbc, 'lazy, 'sb, 'ast, 'ctx> TypeckContext<'sbc, 'lazy, 'sb, 'ast, 'ctx> {
             // let ty = self.lazy_typeval(value);
             // self.must_match(exp, ty, node.span);
         }
-        if let Some(_after) = node.after {
-            // TODO: type check time expression
-            // self.typeck_node(after, /* time type */);
+        if let Some(after) = node.after {
+            self.typeck_time_expr(after);
         }
     }
 
@@ -483,31 +515,15 @@ impl<'sbc, 'lazy, 'sb, 'ast, 'ctx> TypeckContext<'sbc, 'lazy, 'sb, 'ast, 'ctx> {
         match (deref, self.ctx.deref_named_type(subty.value)?) {
             (&Ty::Int(ref ty), &Ty::Int(ref subty)) => {
                 use std::cmp::{max, min};
-                if ty.dir != subty.dir {
-                    self.emit(
-                        DiagBuilder2::error(format!(
-                            "directions disagree; `{}` and `{}`",
-                            subty, ty
-                        ))
-                        .span(span),
-                    );
-                    return Err(());
-                }
-                let (ty_lo, ty_hi, subty_lo, subty_hi) = match ty.dir {
-                    Dir::To => (
-                        &ty.left_bound,
-                        &ty.right_bound,
-                        &subty.left_bound,
-                        &subty.right_bound,
-                    ),
-                    Dir::Downto => (
-                        &ty.right_bound,
-                        &ty.left_bound,
-                        &subty.right_bound,
-                        &subty.left_bound,
-                    ),
+                // The subtype takes its direction from the constraint, which
+                // need not agree with the direction of the constrained type.
+                let bounds = |ty: &IntTy| match ty.dir {
+                    Dir::To => (ty.left_bound.clone(), ty.right_bound.clone()),
+                    Dir::Downto => (ty.right_bound.clone(), ty.left_bound.clone()),
                 };
-                if ty_lo > subty_lo || ty_hi < subty_hi {
+                let (ty_lo, ty_hi) = bounds(ty);
+                let (subty_lo, subty_hi) = bounds(subty);
+                if subty_lo <= subty_hi && (ty_lo > subty_lo || ty_hi < subty_hi) {
                     self.emit(
                         DiagBuilder2::error(format!("`{}` is not a subrange of `{}`", subty, ty))
                             .span(span)
@@ -519,11 +535,11 @@ impl<'sbc, 'lazy, 'sb, 'ast, 'ctx> TypeckContext<'sbc, 'lazy, 'sb, 'ast, 'ctx> {
                 }
                 let lo = max(ty_lo, subty_lo);
                 let hi = min(ty_hi, subty_hi);
-                let (lb, rb) = match ty.dir {
+                let (lb, rb) = match subty.dir {
                     Dir::To => (lo, hi),
                     Dir::Downto => (hi, lo),
                 };
-                let new_ty: Ty = IntTy::new(ty.dir, lb.clone(), rb.clone()).into();
+                let new_ty: Ty = IntTy::new(subty.dir, lb, rb).into();
                 if &new_ty == deref {
                     Ok(orig_ty)
                 } else {
@@ -719,8 +735,10 @@ impl_typeck!(self, id: CtxRef => {
     unimp!(self, id)
 });
 
-impl_typeck!(self, id: CfgRef => {
-    unimp!(self, id)
+impl_typeck_err!(self, id: CfgRef => {
+    let hir = self.ctx.hir(id)?;
+    self.typeck(hir.entity.value);
+    Ok(())
 });
 
 impl_typeck_err!(self, id: EntityRef => {
@@ -775,8 +793,7 @@ impl_typeck!(self, id: IntfPkgRef => {
 });
 
 impl_make!(self, id: IntfConstRef => &Ty {
-    // TODO: Implement this.
-    unimp_err!(self, id)
+    self.lazy_typeval(id)
 });
 
 impl_make!(self, id: IntfVarRef => &Ty {
@@ -923,7 +940,7 @@ impl_typeck!(self, id: SeqStmtRef => {
         SeqStmtRef::Wait(id)      => self.lazy_typeck(id),
         SeqStmtRef::Assert(id)    => self.lazy_typeck(id),
         SeqStmtRef::Report(id)    => self.lazy_typeck(id),
-        SeqStmtRef::SigAssign(id) => self.lazy_typeck(id),
+        SeqStmtRef::SigAssign(id) => self.typeck(id),
         SeqStmtRef::VarAssign(id) => self.lazy_typeck(id),
         SeqStmtRef::ProcCall(id)  => self.lazy_typeck(id),
         SeqStmtRef::If(id)        => self.lazy_typeck(id),
@@ -979,8 +996,15 @@ impl_typeck!(self, id: AliasDeclRef => {
     unimp!(self, id)
 });
 
-impl_typeck!(self, id: CompDeclRef => {
-    unimp!(self, id)
+impl_typeck_err!(self, id: CompDeclRef => {
+    let hir = self.ctx.hir(id)?;
+    for &generic in &hir.generics {
+        self.typeck(generic);
+    }
+    for &port in &hir.ports {
+        self.typeck(port);
+    }
+    Ok(())
 });
 
 impl_typeck!(self, id: AttrDeclRef => {
@@ -991,8 +1015,9 @@ impl_typeck!(self, id: AttrSpecRef => {
     unimp!(self, id)
 });
 
-impl_typeck!(self, id: CfgSpecRef => {
-    unimp!(self, id)
+impl_typeck_err!(self, id: CfgSpecRef => {
+    self.ctx.hir(id)?;
+    Ok(())
 });
 
 impl_typeck!(self, id: DisconSpecRef => {
@@ -1016,8 +1041,12 @@ impl_typeck_err!(self, id: ProcessStmtRef => {
     for &decl in &hir.decls {
         self.typeck(decl);
     }
-    for &stmt in &hir.stmts {
-        self.typeck(stmt);
+    // The sensitivity check revisits the statements, which is only possible
+    // if they were lowered successfully.
+    let stmts = TypeckContext::new(self.ctx);
+    stmts.typeck_slice(&hir.stmts);
+    if !stmts.finish() || self.ctx.check_process_sensitivity(id).is_err() {
+        self.failed.set(true);
     }
     Ok(())
 });
@@ -1035,15 +1064,15 @@ impl_typeck!(self, id: ConcSigAssignStmtRef => {
 });
 
 impl_typeck!(self, id: CompInstStmtRef => {
-    unimp!(self, id)
+    self.lazy_typeck(id)
 });
 
 impl_typeck!(self, id: ForGenStmtRef => {
-    unimp!(self, id)
+    self.lazy_typeck(id)
 });
 
 impl_typeck!(self, id: IfGenStmtRef => {
-    unimp!(self, id)
+    self.lazy_typeck(id)
 });
 
 impl_typeck!(self, id: CaseGenStmtRef => {
@@ -1239,6 +1268,10 @@ impl_make!(self, id: TypeDeclRef => &Ty {
             }
             Ok(self.intern_ty(RecordTy::new(mapped_fields)))
         }
+
+        hir::TypeData::Protected | hir::TypeData::ProtectedBody => {
+            Ok(self.intern_ty(Ty::Protected(id)))
+        }
     }
 });
 
@@ -0,0 +1,25 @@
+// RUN: moore %s -e top --emit clocks
+
+module top (input logic clk, input logic rst_n, input logic arst, input logic [7:0] d, output logic [7:0] q, output logic [7:0] r);
+	logic [7:0] x;
+
+	always_ff @(posedge clk or negedge rst_n) begin
+		if (!rst_n) x <= 0;
+		else x <= d;
+	end
+
+	always_ff @(negedge clk) r <= x;
+
+	always_ff @(posedge clk, negedge rst_n, posedge arst)
+		if (rst_n == 0) q <= 0;
+		else if (arst) q <= 1;
+		else q <= x;
+endmodule
+
+// CHECK: module top:
+// CHECK:     clock clk (posedge)
+// CHECK:     reset rst_n (negedge, active low)
+// CHECK:     reset arst (posedge, active high)
+// CHECK:     process at line 6: clock clk (posedge) reset rst_n
+// CHECK:     process at line 11: clock clk (negedge)
+// CHECK:     process at line 13: clock clk (posedge) reset rst_n reset arst
@@ -0,0 +1,46 @@
+// RUN: moore %s -e foo --dpi-header %t/dpi.h
+// RUN: cat %t/dpi.h
+
+import "DPI-C" function int c_add(input int a, input int b);
+
+module foo;
+    import "DPI-C" context function void c_dump(input logic [7:0] data, output bit done);
+    import "DPI-C" c_sqrt = function real sqrt(input real x);
+    export "DPI-C" function sv_count;
+    export "DPI-C" sv_run = task run;
+
+    function int sv_count(input byte unsigned x);
+        return x;
+    endfunction
+
+    task run(inout longint y);
+    endtask
+endmodule
+
+// CHECK: entity @foo () -> () {
+// CHECK:     halt
+// CHECK: }
+// CHECK: declare @c_dump (i8*, i1*) void
+// CHECK: declare @c_sqrt (i64) i64
+// CHECK: declare @c_add (i32, i32) i32
+// CHECK: /* DPI declarations generated by moore. Do not edit. */
+// CHECK: #ifndef MOORE_DPI_H
+// CHECK: #define MOORE_DPI_H
+// CHECK: #include "svdpi.h"
+// CHECK: #ifdef __cplusplus
+// CHECK: extern "C" {
+// CHECK: #endif
+// CHECK: /* Imported into SystemVerilog; implemented in C. */
+// CHECK: int c_add(int a, int b);
+// CHECK: void c_dump(const svLogicVecVal* data, svBit* done);
+// CHECK: double c_sqrt(double x);
+// CHECK: #ifdef __cplusplus
+// CHECK: }
+// CHECK: #endif
+// CHECK: #endif /* MOORE_DPI_H */
+// CHECK: warning: unsupported: export of `sv_count` to C; ignored [-Wunsupported]
+// CHECK: |     function int sv_count(input byte unsigned x);
+// CHECK: = note: Functions and tasks are not lowered to LLHD
+// CHECK: warning: unsupported: export of `run` to C; ignored [-Wunsupported]
+// CHECK: |     task run(inout longint y);
+// CHECK: = note: Functions and tasks are not lowered to LLHD
@@ -0,0 +1,60 @@
+// RUN: moore %s -e foo -O0
+
+// Array patterns list the elements from the leftmost to the rightmost index.
+// The LLHD array holds the elements by position, and the leftmost index of
+// packed and descending unpacked dimensions is stored at the highest position.
+module foo;
+    int [3:0] pd = '{1, 2, 3, 4};
+    int [0:3] pa = '{1, 2, 3, 4};
+    int ud [3:0] = '{1, 2, 3, 4};
+    int ua [0:3] = '{1, 2, 3, 4};
+    int uc [4] = '{1, 2, 3, 4};
+    int [3:0] nd = '{0: 1, default: 0};
+    int [0:3] na = '{0: 1, default: 0};
+endmodule
+
+// CHECK: entity @foo () -> () {
+// CHECK:     %0 = const i32 4
+// CHECK:     %1 = const i32 3
+// CHECK:     %2 = const i32 2
+// CHECK:     %3 = const i32 1
+// CHECK:     %4 = [i32 %0, %1, %2, %3]
+// CHECK:     %pd = sig [4 x i32] %4
+// CHECK:     %5 = const i32 4
+// CHECK:     %6 = const i32 3
+// CHECK:     %7 = const i32 2
+// CHECK:     %8 = const i32 1
+// CHECK:     %9 = [i32 %5, %6, %7, %8]
+// CHECK:     %pa = sig [4 x i32] %9
+// CHECK:     %10 = const i32 4
+// CHECK:     %11 = const i32 3
+// CHECK:     %12 = const i32 2
+// CHECK:     %13 = const i32 1
+// CHECK:     %14 = [i32 %10, %11, %12, %13]
+// CHECK:     %ud = sig [4 x i32] %14
+// CHECK:     %15 = const i32 1
+// CHECK:     %16 = const i32 2
+// CHECK:     %17 = const i32 3
+// CHECK:     %18 = const i32 4
+// CHECK:     %19 = [i32 %15, %16, %17, %18]
+// CHECK:     %ua = sig [4 x i32] %19
+// CHECK:     %20 = const i32 1
+// CHECK:     %21 = const i32 2
+// CHECK:     %22 = const i32 3
+// CHECK:     %23 = const i32 4
+// CHECK:     %24 = [i32 %20, %21, %22, %23]
+// CHECK:     %uc = sig [4 x i32] %24
+// CHECK:     %25 = const i32 1
+// CHECK:     %26 = const i32 0
+// CHECK:     %27 = const i32 0
+// CHECK:     %28 = const i32 0
+// CHECK:     %29 = [i32 %25, %26, %27, %28]
+// CHECK:     %nd = sig [4 x i32] %29
+// CHECK:     %30 = const i32 0
+// CHECK:     %31 = const i32 0
+// CHECK:     %32 = const i32 0
+// CHECK:     %33 = const i32 1
+// CHECK:     %34 = [i32 %30, %31, %32, %33]
+// CHECK:     %na = sig [4 x i32] %34
+// CHECK:     halt
+// CHECK: }
@@ -0,0 +1,32 @@
+// RUN: moore %s -e fsm --emit fsm
+
+module fsm (input logic clk, input logic rst_n, input logic go, output logic busy);
+	typedef enum logic [1:0] { IDLE, RUN, DONE } state_t;
+	state_t state, next;
+
+	always_ff @(posedge clk or negedge rst_n) begin
+		if (!rst_n) state <= IDLE;
+		else state <= next;
+	end
+
+	always_comb begin
+		next = state;
+		case (state)
+			IDLE: if (go) next = RUN;
+			RUN: next = DONE;
+			default: next = IDLE;
+		endcase
+	end
+
+	assign busy = state == RUN;
+endmodule
+
+// CHECK: subgraph cluster_0 {
+// CHECK:     label = "fsm.state";
+// CHECK:     s0_0 [label="IDLE", shape=doublecircle];
+// CHECK:     s0_1 [label="RUN"];
+// CHECK:     s0_2 [label="DONE"];
+// CHECK:     s0_0 -> s0_1 [label="go"];
+// CHECK:     s0_1 -> s0_2 [label=""];
+// CHECK:     s0_2 -> s0_0 [label=""];
+// CHECK: }
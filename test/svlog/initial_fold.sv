@@ -0,0 +1,31 @@
+// RUN: moore %s -e foo -O1
+
+module foo;
+    int a, b, c;
+    initial begin
+        a = 42;
+        b = 9001;
+        a = 43;
+    end
+    initial c = a + b;
+endmodule
+
+// CHECK: proc %foo.initial.56.0 (i32$ %a, i32$ %b) -> (i32$ %c) {
+// CHECK: 0:
+// CHECK:     %a.prb = prb i32$ %a
+// CHECK:     %b.prb = prb i32$ %b
+// CHECK:     %1 = add i32 %a.prb, %b.prb
+// CHECK:     %2 = const time 0s 1e
+// CHECK:     drv i32$ %c, %1, %2
+// CHECK:     halt
+// CHECK: }
+// CHECK: entity @foo () -> () {
+// CHECK:     %0 = const i32 43
+// CHECK:     %a = sig i32 %0
+// CHECK:     %1 = const i32 9001
+// CHECK:     %b = sig i32 %1
+// CHECK:     %2 = const i32 0
+// CHECK:     %c = sig i32 %2
+// CHECK:     inst %foo.initial.56.0 (i32$ %a, i32$ %b) -> (i32$ %c)
+// CHECK:     halt
+// CHECK: }
@@ -0,0 +1,58 @@
+// RUN: moore %s -e top --entity-policy shared
+
+module top;
+    int a, b, c, d;
+    leaf #(.W(1)) l1(a, b);
+    leaf #(.W(2)) l2(a, c);
+    leaf #(.W(3), .K(2)) l3(a, d);
+endmodule
+
+module leaf #(parameter int W = 0, parameter int K = 1) (input int x, output int y);
+    always_comb y = x + K;
+endmodule
+
+// CHECK: proc %leaf.param1.always_comb.106.1 (i32$ %x) -> (i32$ %y) {
+// CHECK: body:
+// CHECK:     %x.prb = prb i32$ %x
+// CHECK:     %0 = const i32 1
+// CHECK:     %1 = add i32 %x.prb, %0
+// CHECK:     %2 = const time 0s 1e
+// CHECK:     drv i32$ %y, %1, %2
+// CHECK:     wait %body, %x
+// CHECK: }
+// CHECK: entity @leaf.param1 (i32$ %x) -> (i32$ %y) {
+// CHECK:     inst %leaf.param1.always_comb.106.1 (i32$ %x) -> (i32$ %y)
+// CHECK:     halt
+// CHECK: }
+// CHECK: proc %leaf.param5.always_comb.106.5 (i32$ %x) -> (i32$ %y) {
+// CHECK: body:
+// CHECK:     %x.prb = prb i32$ %x
+// CHECK:     %0 = const i32 2
+// CHECK:     %1 = add i32 %x.prb, %0
+// CHECK:     %2 = const time 0s 1e
+// CHECK:     drv i32$ %y, %1, %2
+// CHECK:     wait %body, %x
+// CHECK: }
+// CHECK: entity @leaf.param5 (i32$ %x) -> (i32$ %y) {
+// CHECK:     inst %leaf.param5.always_comb.106.5 (i32$ %x) -> (i32$ %y)
+// CHECK:     halt
+// CHECK: }
+// CHECK: entity @top () -> () {
+// CHECK:     %0 = const i32 0
+// CHECK:     %a = sig i32 %0
+// CHECK:     %b = sig i32 %0
+// CHECK:     %c = sig i32 %0
+// CHECK:     %d = sig i32 %0
+// CHECK:     %a.prb = prb i32$ %a
+// CHECK:     %1 = sig i32 %0
+// CHECK:     %2 = const time 0s 1e
+// CHECK:     drv i32$ %1, %a.prb, %2
+// CHECK:     inst @leaf.param1 (i32$ %1) -> (i32$ %b)
+// CHECK:     %3 = sig i32 %0
+// CHECK:     drv i32$ %3, %a.prb, %2
+// CHECK:     inst @leaf.param1 (i32$ %3) -> (i32$ %c)
+// CHECK:     %4 = sig i32 %0
+// CHECK:     drv i32$ %4, %a.prb, %2
+// CHECK:     inst @leaf.param5 (i32$ %4) -> (i32$ %d)
+// CHECK:     halt
+// CHECK: }
@@ -0,0 +1,26 @@
+// RUN: moore %s -e top --emit hierarchy
+// The instance tree is printed with the parameter values of every instance.
+
+interface bus;
+    logic x;
+endinterface
+
+module top;
+    bus b();
+    mid #(.W(8)) m0();
+    mid m1();
+endmodule
+
+module mid #(parameter int W = 4, parameter type T = logic);
+    leaf #(.N(W*2)) l();
+endmodule
+
+module leaf #(parameter int N = 1);
+endmodule
+
+// CHECK: top
+// CHECK:   b: interface bus
+// CHECK:   m0: mid #(W = 8, T = logic)
+// CHECK:     l: leaf #(N = 16)
+// CHECK:   m1: mid #(W = 4, T = logic)
+// CHECK:     l: leaf #(N = 8)
@@ -0,0 +1,22 @@
+// RUN: moore %s -e top
+// FAIL
+module leaf #(parameter int N = 1);
+  int a [N];
+  initial a = '{0: 1, 1: 2, 2: 3};
+  // CHECK: error: `default:` missing in non-exhaustive array pattern
+  // CHECK: |   initial a = '{0: 1, 1: 2, 2: 3};
+  // CHECK: = note: Array patterns must assign a value to every index.
+  // CHECK: = note: Required by instance `l` of `leaf #(.N(M+1))`:
+  // CHECK: |   leaf #(.N(M+1)) l ();
+  // CHECK: = note: Required by instance `m` of `mid #(3)`:
+  // CHECK: |   mid #(3) m ();
+endmodule
+
+module mid #(parameter int M = 1);
+  leaf #(.N(M+1)) l ();
+endmodule
+
+module top;
+  mid #(2) ok ();
+  mid #(3) m ();
+endmodule
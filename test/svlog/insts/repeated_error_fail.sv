@@ -0,0 +1,19 @@
+// RUN: moore %s -e top
+// FAIL
+module leaf #(parameter int N = 1);
+  int y, z [2];
+  assign y = z;
+  // CHECK: error: cannot cast a value of type `int $ [2]` to `int`
+  // CHECK: |   assign y = z;
+  // CHECK: = note: `int $ [2]` has no simple bit-vector type representation
+  // CHECK: = note: Required by instance `u0` of `leaf #(1)`:
+  // CHECK: = note: Reported for 5 instantiations: `top.u0`, `top.u1`, `top.u2`, and 2 more
+endmodule
+
+module top;
+  leaf #(1) u0 ();
+  leaf #(2) u1 ();
+  leaf #(3) u2 ();
+  leaf #(4) u3 ();
+  leaf #(5) u4 ();
+endmodule
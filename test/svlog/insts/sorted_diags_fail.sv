@@ -0,0 +1,11 @@
+// RUN: moore %s -e top
+// FAIL
+module sub;
+  int a = x;
+endmodule
+module top;
+  int b = y;
+  sub s();
+  // CHECK: error[SV0001]: `x` not found
+  // CHECK: error[SV0001]: `y` not found
+endmodule
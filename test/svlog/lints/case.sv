@@ -0,0 +1,78 @@
+// RUN: moore %s --elaborate-only
+
+module foo (
+    input logic clk,
+    input logic [1:0] op,
+    input logic [3:0] sel,
+    input logic [7:0] a,
+    output logic [7:0] q,
+    output logic [7:0] r,
+    output logic [7:0] s,
+    output logic [7:0] t,
+    output logic [7:0] u
+);
+    typedef enum logic [1:0] { IDLE, BUSY, DONE, ERROR } state_t;
+    state_t state;
+    always_ff @(posedge clk) begin
+        case (state)
+            IDLE: state <= BUSY;
+            BUSY: state <= DONE;
+        endcase
+    end
+    always_comb begin
+        q = '0;
+        case (op)
+            2'd0: q = a;
+            2'd1: q = ~a;
+            2'd2: q = a << 1;
+        endcase
+    end
+    always_comb begin
+        r = '0;
+        casez (sel)
+            4'b1???: r = 1;
+            4'b01??: r = 2;
+            4'b11??: r = 3;
+            4'b00??: r = 4;
+        endcase
+    end
+    always_comb begin
+        s = '0;
+        unique case (op)
+            2'd0: s = a;
+            2'd0: s = ~a;
+        endcase
+    end
+    always_comb begin
+        t = '0;
+        priority casez (sel)
+            4'b1???: t = 1;
+            4'b11??: t = 2;
+        endcase
+    end
+    always_comb begin
+        case (op)
+            2'd0, 2'd1: u = a;
+            default: u = '0;
+        endcase
+    end
+endmodule
+
+// CHECK: warning: case statement does not handle `DONE` and `ERROR` of `state` [-Wcase-incomplete]
+// CHECK: |         case (state)
+// CHECK: = note: Add the missing items or a `default` case.
+// CHECK: warning: case statement does not handle 1 of the 4 values of `op` [-Wcase-incomplete]
+// CHECK: |         case (op)
+// CHECK: = note: For example, `2'h3` is not handled.
+// CHECK: = note: Add the missing items or a `default` case.
+// CHECK: warning: case item `4'b11??` overlaps with an earlier item [-Wcase-overlap]
+// CHECK: |             4'b11??: r = 3;
+// CHECK: = note: The earlier item is here:
+// CHECK: |             4'b1???: r = 1;
+// CHECK: = note: The earlier item takes precedence, such that this item is never taken for the values both items match.
+// CHECK: warning: case item `2'd0` overlaps with an earlier item [-Wcase-overlap]
+// CHECK: |             2'd0: s = ~a;
+// CHECK: = note: The earlier item is here:
+// CHECK: |             2'd0: s = a;
+// CHECK: = note: Overlapping items violate the `unique` qualifier of the case statement.
+// CHECK: moore: 0 errors, 4 warnings (case-incomplete: 2, case-overlap: 2)
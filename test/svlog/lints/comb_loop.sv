@@ -0,0 +1,78 @@
+// RUN: moore %s --elaborate-only
+
+module foo (
+    input logic clk,
+    input logic a,
+    input logic c,
+    output logic q,
+    output logic r
+);
+    logic x, y, z, s, t;
+    assign x = y & a;
+    always_comb y = x | z;
+    always_ff @(posedge clk) z <= y;
+    always_comb begin
+        s = a;
+        t = s;
+    end
+    wire w;
+    assign w = w;
+    assign q = x ^ t ^ w;
+
+    // Each assignment only depends on its own right-hand side and the
+    // enclosing conditions.
+    logic x2, y2, z2;
+    always_comb begin
+        x2 = c;
+        y2 = c;
+        z2 = 0;
+        if (x2 && y2) z2 = 1;
+    end
+
+    // A reused temporary does not carry the first value to the second use.
+    logic tmp, u, v, u2;
+    always_comb begin
+        tmp = a;
+        u = tmp;
+        tmp = v;
+        u2 = tmp;
+    end
+    assign v = u;
+
+    // Loops may pass through ports and conditions.
+    logic p, e, f;
+    always_comb begin
+        r = 0;
+        if (p) r = a;
+    end
+    assign p = r;
+    always_comb begin
+        e = 0;
+        if (f) e = 1;
+    end
+    assign f = e;
+endmodule
+
+// CHECK: warning: combinational loop: `r` -> `p` -> `r` [-Wcomb-loop]
+// CHECK: |     output logic r
+// CHECK: = note: `r` drives `p` here:
+// CHECK: |     assign p = r;
+// CHECK: = note: `p` drives `r` here:
+// CHECK: |         if (p) r = a;
+// CHECK: warning: combinational loop: `x` -> `y` -> `x` [-Wcomb-loop]
+// CHECK: |     logic x, y, z, s, t;
+// CHECK: = note: `x` drives `y` here:
+// CHECK: |     always_comb y = x | z;
+// CHECK: = note: `y` drives `x` here:
+// CHECK: |     assign x = y & a;
+// CHECK: warning: combinational loop: `w` -> `w` [-Wcomb-loop]
+// CHECK: |     wire w;
+// CHECK: = note: `w` drives `w` here:
+// CHECK: |     assign w = w;
+// CHECK: warning: combinational loop: `e` -> `f` -> `e` [-Wcomb-loop]
+// CHECK: |     logic p, e, f;
+// CHECK: = note: `e` drives `f` here:
+// CHECK: |     assign f = e;
+// CHECK: = note: `f` drives `e` here:
+// CHECK: |         if (f) e = 1;
+// CHECK: moore: 0 errors, 4 warnings (comb-loop: 4)
@@ -0,0 +1,68 @@
+// RUN: moore %s --elaborate-only
+
+module foo (
+    input logic en,
+    input logic sel,
+    input logic [1:0] op,
+    input logic [7:0] a,
+    input logic [7:0] b,
+    output logic [7:0] q,
+    output logic [7:0] r,
+    output logic [7:0] s,
+    output logic [7:0] t,
+    output logic [7:0] u,
+    output logic [7:0] v
+);
+    always_comb begin
+        if (en)
+            q = a;
+    end
+    always_comb begin
+        r = '0;
+        if (en)
+            r = a;
+    end
+    always @* begin
+        case (op)
+            2'd0: s = a;
+            2'd1: s = b;
+            2'd2: s = a & b;
+        endcase
+    end
+    always_comb begin
+        case (op)
+            2'd0: t = a;
+            2'd1: t = b;
+            2'd2: t = a & b;
+            2'd3: t = a | b;
+        endcase
+    end
+    always_comb begin
+        if (en) begin
+            u = a;
+        end else if (sel) begin
+            u = b;
+        end else begin
+            v = a;
+        end
+        v = b;
+    end
+endmodule
+
+// CHECK: warning: `q` is not assigned on every path, inferring a latch [-Winferred-latch]
+// CHECK: |         if (en)
+// CHECK: = note: `q` keeps its previous value if this condition is false, since there is no `else` branch.
+// CHECK: = note: Assign a default value to `q` at the beginning of the process, or use `always_latch` if the latch is intended.
+// CHECK: warning: `s` is not assigned on every path, inferring a latch [-Winferred-latch]
+// CHECK: |         case (op)
+// CHECK: = note: `s` keeps its previous value if this expression matches none of the case items, since there is no `default` case.
+// CHECK: = note: Assign a default value to `s` at the beginning of the process, or use `always_latch` if the latch is intended.
+// CHECK: warning: case statement does not handle 1 of the 4 values of `op` [-Wcase-incomplete]
+// CHECK: |         case (op)
+// CHECK: = note: For example, `2'h3` is not handled.
+// CHECK: = note: Add the missing items or a `default` case.
+// CHECK: warning: `u` is not assigned on every path, inferring a latch [-Winferred-latch]
+// CHECK: |         end else begin
+// CHECK: = note: `u` keeps its previous value if this branch is taken.
+// CHECK: = note: Assign a default value to `u` at the beginning of the process, or use `always_latch` if the latch is intended.
+// CHECK: moore: 0 errors, 4 warnings (case-incomplete: 1, inferred-latch: 3)
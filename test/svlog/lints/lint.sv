@@ -0,0 +1,15 @@
+// RUN: moore lint -Werror=label %s
+// FAIL
+// The lint subcommand checks every module and fails if anything is reported.
+
+module foo (input a,);
+    initial blk: begin : blk end
+endmodule
+
+// CHECK: warning[SV0014]: superfluous trailing comma [-Wtrailing-comma]
+// CHECK: | module foo (input a,);
+// CHECK: = help: Remove the comma
+// CHECK: | module foo (input a);
+// CHECK: error: Block blk labelled twice [-Werror=label]
+// CHECK: |     initial blk: begin : blk end
+// CHECK: moore: 1 error, 1 warning (label: 1, trailing-comma: 1)
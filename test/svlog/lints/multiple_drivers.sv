@@ -0,0 +1,59 @@
+// RUN: moore lint %s
+// FAIL
+
+module foo (
+    input logic clk,
+    input logic en1,
+    input logic en2,
+    input logic [7:0] a,
+    input logic [7:0] b,
+    output logic [7:0] q
+);
+    logic [7:0] x, y, z, v;
+    wire [7:0] w, bus;
+    tri [7:0] t;
+    always_ff @(posedge clk) x <= a;
+    always_comb x = b;
+    always_ff @(posedge clk) begin
+        y[3:0] <= a[3:0];
+        y[3:0] <= b[3:0];
+    end
+    always_comb y[7:4] = b[7:4];
+    always_comb v[0] = a[0];
+    always_comb v[0] = b[0];
+    always_comb v[7:1] = a[7:1];
+    assign w = a;
+    assign w = b;
+    assign bus = en1 ? a : 'z;
+    assign bus = en2 ? b : 8'bz;
+    assign t = a;
+    assign t = b;
+    if (1) begin
+        assign z = a;
+    end else begin
+        assign z = b;
+    end
+    assign q = x ^ y ^ z ^ w ^ bus ^ t ^ v;
+endmodule
+
+// CHECK: warning: `x` has multiple drivers [-Wmultiple-drivers]
+// CHECK: |     logic [7:0] x, y, z, v;
+// CHECK: = note: Driven by an `always_ff` procedure:
+// CHECK: |     always_ff @(posedge clk) x <= a;
+// CHECK: = note: Driven by an `always_comb` procedure:
+// CHECK: |     always_comb x = b;
+// CHECK: warning: `v` has multiple drivers [-Wmultiple-drivers]
+// CHECK: |     logic [7:0] x, y, z, v;
+// CHECK: = note: Driven by an `always_comb` procedure:
+// CHECK: |     always_comb v[0] = a[0];
+// CHECK: = note: Driven by an `always_comb` procedure:
+// CHECK: |     always_comb v[0] = b[0];
+// CHECK: = note: Driven by an `always_comb` procedure:
+// CHECK: |     always_comb v[7:1] = a[7:1];
+// CHECK: warning: `w` has multiple drivers [-Wmultiple-drivers]
+// CHECK: |     wire [7:0] w, bus;
+// CHECK: = note: Driven by a continuous assignment:
+// CHECK: |     assign w = a;
+// CHECK: = note: Driven by a continuous assignment:
+// CHECK: |     assign w = b;
+// CHECK: moore: 0 errors, 3 warnings (multiple-drivers: 3)
@@ -0,0 +1,16 @@
+// RUN: moore %s -Werror -e foo
+
+// moore lint_off TRAILING_COMMA
+module foo (input a, input b,);
+// moore lint_on TRAILING_COMMA
+    bar i_bar (a, b,); // moore lint_ignore trailing-comma
+    // moore lint_ignore label
+    initial blk: begin : blk end
+endmodule
+
+module bar (
+    input a,
+    // moore lint_ignore
+    input b,
+);
+endmodule
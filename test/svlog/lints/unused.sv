@@ -0,0 +1,29 @@
+// RUN: moore lint %s
+// FAIL
+
+module foo #(
+    parameter int N = 4,
+    parameter int M = 2,
+    parameter int P_unused = 1
+) (
+    input logic clk,
+    input logic [N-1:0] a,
+    input logic b,
+    input logic debug_unused,
+    output logic [N-1:0] q
+);
+    logic [N-1:0] r, s;
+    logic [7:0] cnt;
+    logic tmp_unused;
+    assign tmp_unused = a[0];
+    always_ff @(posedge clk) begin
+        r <= a;
+        cnt += 1;
+        q <= s;
+    end
+endmodule
+
+// CHECK: warning: parameter `M` is never used [-Wunused-param]
+// CHECK: warning: port `b` is never used inside module `foo` [-Wunused-port]
+// CHECK: warning: signal `r` is never read [-Wunused-signal]
+// CHECK: moore: 0 errors, 3 warnings (unused-param: 1, unused-port: 1, unused-signal: 1)
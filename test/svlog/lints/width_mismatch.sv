@@ -0,0 +1,39 @@
+// RUN: moore %s --elaborate-only -Wwidth-mismatch
+
+module foo (
+    input logic clk,
+    input logic [7:0] a,
+    input logic [3:0] b,
+    input logic signed [3:0] c,
+    output logic [7:0] q,
+    output logic [3:0] r,
+    output logic [7:0] s,
+    output logic t,
+    output logic [7:0] u
+);
+    logic [7:0] cnt;
+    assign q = b;
+    assign r = a;
+    assign s = c;
+    assign t = (a == b);
+    assign u = a + b;
+    always_ff @(posedge clk) begin
+        cnt <= cnt + 1;
+        if (cnt == 0)
+            cnt <= '0;
+    end
+endmodule
+
+// CHECK: warning: 4-bit value assigned to 8-bit `q` [-Wwidth-mismatch]
+// CHECK: |     assign q = b;
+// CHECK: = note: The value is zero-extended to 8 bits.
+// CHECK: warning: 8-bit value assigned to 4-bit `r` [-Wwidth-mismatch]
+// CHECK: |     assign r = a;
+// CHECK: = note: The value is truncated to 4 bits, dropping its upper 4 bits.
+// CHECK: warning: 4-bit value assigned to 8-bit `s` [-Wwidth-mismatch]
+// CHECK: |     assign s = c;
+// CHECK: = note: The value is sign-extended to 8 bits.
+// CHECK: warning: comparison between 8-bit and 4-bit operands [-Wwidth-mismatch]
+// CHECK: |     assign t = (a == b);
+// CHECK: = note: `b` is zero-extended to 8 bits.
+// CHECK: moore: 0 errors, 4 warnings (width-mismatch: 4)
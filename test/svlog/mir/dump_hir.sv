@@ -0,0 +1,19 @@
+// RUN: moore %s -e top --emit hir
+// The HIR of every module is dumped once per parametrization, with the type
+// of every expression.
+
+module top (input logic clk, input logic [7:0] a);
+    logic [7:0] q;
+    always_ff @(posedge clk) q <= a + 8'd1;
+endmodule
+
+// CHECK: module `top`
+// CHECK:   input wire `clk`: logic @ 5:13
+// CHECK:   variable `q`: logic [7:0] @ 6:5
+// CHECK:   `always_ff` procedure @ 7:5
+// CHECK:       event expression @ 7:17
+// CHECK:         event posedge @ 7:17
+// CHECK:           identifier `clk`: logic @ 7:25
+// CHECK:       assign statement @ 7:30
+// CHECK:         expression `a + 8'd1`: logic [7:0] @ 7:35
+// CHECK:           integer constant `8'd1`: logic [7:0] @ 7:39
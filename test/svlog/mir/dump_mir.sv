@@ -0,0 +1,25 @@
+// RUN: moore %s -e top --emit mir
+// The MIR of every module is dumped once per parametrization.
+
+module top (input logic [7:0] a, output logic [7:0] z);
+    sub #(.N(4)) i_sub (.x(a[3:0]));
+    assign z = a + 8'd1;
+endmodule
+
+module sub #(parameter int N = 2) (input logic [N-1:0] x);
+    logic [N-1:0] y;
+    assign y = ~x;
+endmodule
+
+// CHECK: module `top`
+// CHECK:   assignment @ 6:12
+// CHECK:     identifier `z` @ 6:12
+// CHECK:       lvalue port `z`: logic [7:0]
+// CHECK:     expression `a + 8'd1` @ 6:16
+// CHECK:       int_binary_arith Add unsigned FourValued: logic [7:0]
+// CHECK:         port `a`: logic [7:0]
+// CHECK:         cast_value_domain TwoValued -> FourValued: logic [7:0]
+// CHECK: module `sub`
+// CHECK:   parameter `N` = 4: int
+// CHECK:       unary_bitwise Not: logic [3:0]
+// CHECK:         port `x`: logic [3:0]
@@ -0,0 +1,54 @@
+// RUN: moore %s --emit ast-json
+// The parsed AST can be dumped as JSON.
+
+module foo (input logic [3:0] a, output logic b);
+    assign b = a[0] & 1'b1;
+endmodule
+
+// CHECK: "kind": "TypeKind",
+// CHECK: "file": "test/svlog/modules/ast_json.sv",
+// CHECK: "variant": "LogicType"
+// CHECK: "kind": "Expr",
+// CHECK: "text": "3",
+// CHECK: "variant": "LiteralExpr"
+// CHECK: "kind": "Expr",
+// CHECK: "text": "0",
+// CHECK: "variant": "LiteralExpr"
+// CHECK: "kind": "Type",
+// CHECK: "dir": "input",
+// CHECK: "kind": "Port",
+// CHECK: "name": "a",
+// CHECK: "variant": "Named"
+// CHECK: "kind": "TypeKind",
+// CHECK: "variant": "LogicType"
+// CHECK: "kind": "Type",
+// CHECK: "dir": "output",
+// CHECK: "kind": "Port",
+// CHECK: "name": "b",
+// CHECK: "variant": "Named"
+// CHECK: "kind": "Expr",
+// CHECK: "text": "b",
+// CHECK: "variant": "IdentExpr"
+// CHECK: "kind": "Expr",
+// CHECK: "text": "a",
+// CHECK: "variant": "IdentExpr"
+// CHECK: "kind": "Expr",
+// CHECK: "text": "0",
+// CHECK: "variant": "LiteralExpr"
+// CHECK: "kind": "Expr",
+// CHECK: "variant": "IndexExpr"
+// CHECK: "kind": "Expr",
+// CHECK: "text": "1'b1",
+// CHECK: "variant": "LiteralExpr"
+// CHECK: "kind": "Expr",
+// CHECK: "op": "&",
+// CHECK: "variant": "BinaryExpr"
+// CHECK: "kind": "ContAssign",
+// CHECK: "kind": "Item",
+// CHECK: "variant": "ContAssign"
+// CHECK: "kind": "Module",
+// CHECK: "name": "foo",
+// CHECK: "kind": "Item",
+// CHECK: "name": "foo",
+// CHECK: "variant": "ModuleDecl"
+// CHECK: "kind": "SourceFile",
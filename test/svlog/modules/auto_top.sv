@@ -0,0 +1,18 @@
+// RUN: moore %s --emit llhd
+// Without a top module given, the modules which are not instantiated anywhere
+// are elaborated.
+
+module a;
+    b x();
+endmodule
+
+module b;
+endmodule
+
+module c;
+endmodule
+
+// CHECK: entity @b.param1 () -> () {
+// CHECK: entity @a () -> () {
+// CHECK: entity @c () -> () {
+// CHECK: note: no top module given; elaborating `a`, `c`
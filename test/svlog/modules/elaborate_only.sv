@@ -0,0 +1,14 @@
+// RUN: moore %s --elaborate-only
+// The design is elaborated and checked, but no LLHD is emitted.
+
+module a;
+    b x();
+endmodule
+
+module b;
+    logic [7:0] y;
+    initial y = 8'd42;
+endmodule
+
+// CHECK: note: no top module given; elaborating `a`
+// CHECK: moore: 0 errors, 0 warnings
@@ -0,0 +1,11 @@
+// RUN: moore %s -y library +libext+.vlib -e top
+// Modules which are not defined in the input files are looked up in library
+// directories.
+
+module top;
+    leaf l();
+endmodule
+
+// CHECK: entity @leaf.param1 () -> () {
+// CHECK: entity @top () -> () {
+// CHECK:     inst @leaf.param1 () -> ()
@@ -0,0 +1,14 @@
+// RUN: moore %s -e top
+// FAIL
+
+// Names that cannot be resolved in a reachable module stop the compilation
+// before any code is generated, even if the construct is never lowered.
+
+module top;
+    function int f;
+        return missing;
+    endfunction
+endmodule
+
+// CHECK: error[SV0001]: `missing` not found
+// CHECK: |         return missing;
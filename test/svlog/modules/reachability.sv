@@ -0,0 +1,24 @@
+// RUN: moore %s -e top --emit reachability
+
+module top;
+    mid m();
+    if (0) begin : g
+        leaf l();
+    end
+endmodule
+
+module mid;
+    leaf l();
+endmodule
+
+module leaf;
+endmodule
+
+module unused;
+    leaf l();
+endmodule
+
+// CHECK: reachable top
+// CHECK: reachable mid
+// CHECK: reachable leaf
+// CHECK: pruned unused
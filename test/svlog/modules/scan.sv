@@ -0,0 +1,12 @@
+// RUN: moore scan %s scan_files/alu.svh scan_files/pkg.svh
+// The files are printed in the order in which they have to be compiled.
+
+module top;
+    import pkg::*;
+    byte_t x, y, z;
+    alu i_alu (x, y, z);
+endmodule
+
+// CHECK: test/svlog/modules/scan_files/pkg.svh
+// CHECK: test/svlog/modules/scan_files/alu.svh
+// CHECK: test/svlog/modules/scan.sv
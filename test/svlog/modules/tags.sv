@@ -0,0 +1,33 @@
+// RUN: moore tags --format json %s
+// The definitions are listed in the order they appear, followed by the names
+// which refer to them.
+
+module alu #(parameter int N = 4) (input logic [N-1:0] a);
+endmodule
+
+module top;
+    logic [3:0] x;
+    alu i_alu (x);
+endmodule
+
+// CHECK: "definitions": [
+// CHECK: "kind": "module",
+// CHECK: "name": "alu"
+// CHECK: "kind": "constant",
+// CHECK: "name": "N"
+// CHECK: "kind": "port",
+// CHECK: "name": "a"
+// CHECK: "kind": "module",
+// CHECK: "name": "top"
+// CHECK: "kind": "register",
+// CHECK: "name": "x"
+// CHECK: "kind": "instance",
+// CHECK: "name": "i_alu"
+// CHECK: "references": [
+// CHECK: "line": 5,
+// CHECK: "name": "N"
+// CHECK: "line": 10,
+// CHECK: "name": "alu"
+// CHECK: "line": 9
+// CHECK: "line": 10,
+// CHECK: "name": "x"
@@ -0,0 +1,185 @@
+// RUN: moore %s -e foo -O0
+
+module foo;
+    typedef struct packed {
+        logic [3:0] a;
+        logic [7:0] b;
+    } s_t;
+    typedef struct packed {
+        logic x;
+        s_t s;
+        logic [2:0] y;
+    } t_t;
+    typedef union packed {
+        logic [11:0] raw;
+        s_t s;
+    } u_t;
+
+    s_t s = 12'hABC;
+    t_t t = 16'b1_1010_10111100_011;
+    s_t [1:0] sd = 24'h123456;
+    s_t [0:1] sa = 24'h123456;
+    u_t u = 12'hABC;
+    logic [3:0] ua;
+    logic [3:0] pd = '{1, 0, 0, 0};
+    logic [0:3] pa = '{1, 0, 0, 0};
+    logic [3:0] nd = '{3: 1, default: 0};
+    logic [0:3] na = '{3: 1, default: 0};
+
+    logic [7:0] v;
+    logic [0:7] w;
+    logic [11:4] o;
+    logic q0, q1, q2;
+    logic [3:0] p0, p1, p2, p3;
+    initial begin
+        ua = u.s.a;
+        q0 = w[0];
+        q1 = o[4];
+        q2 = o[11];
+        p0 = w[2:5];
+        p1 = w[2 +: 4];
+        p2 = v[5 -: 4];
+        p3 = o[7:4];
+    end
+endmodule
+
+// Indices are converted to bit positions, with the rightmost index at
+// position 0.
+// CHECK: proc %foo.initial.328.0 (i12$ %u, i8$ %v, i8$ %w, i8$ %o) -> (i4$ %ua, i1$ %q0, i1$ %q1, i1$ %q2, i4$ %p0, i4$ %p1, i4$ %p2, i4$ %p3) {
+// CHECK: 0:
+// CHECK:     %u.prb = prb i12$ %u
+// CHECK:     %1 = const i32 8
+// CHECK:     %2 = const i12 0
+// CHECK:     %3 = shr i12 %u.prb, i12 %2, i32 %1
+// CHECK:     %4 = exts i4, i12 %3, 0, 4
+// CHECK:     %5 = const i32 0
+// CHECK:     %6 = const i12 0
+// CHECK:     %7 = shr i12 %u.prb, i12 %6, i32 %5
+// CHECK:     %8 = exts i8, i12 %7, 0, 8
+// CHECK:     %9 = {i4 %4, i8 %8}
+// CHECK:     %10 = extf i4, {i4, i8} %9, 0
+// CHECK:     %11 = const time 0s 1e
+// CHECK:     drv i4$ %ua, %10, %11
+// CHECK:     %w.prb = prb i8$ %w
+// CHECK:     %12 = const i32 7
+// CHECK:     %13 = const i8 0
+// CHECK:     %14 = shr i8 %w.prb, i8 %13, i32 %12
+// CHECK:     %15 = exts i1, i8 %14, 0, 1
+// CHECK:     %16 = const time 0s 1e
+// CHECK:     drv i1$ %q0, %15, %16
+// CHECK:     %o.prb = prb i8$ %o
+// CHECK:     %17 = const i32 0
+// CHECK:     %18 = const i8 0
+// CHECK:     %19 = shr i8 %o.prb, i8 %18, i32 %17
+// CHECK:     %20 = exts i1, i8 %19, 0, 1
+// CHECK:     %21 = const time 0s 1e
+// CHECK:     drv i1$ %q1, %20, %21
+// CHECK:     %o.prb1 = prb i8$ %o
+// CHECK:     %22 = const i32 7
+// CHECK:     %23 = const i8 0
+// CHECK:     %24 = shr i8 %o.prb1, i8 %23, i32 %22
+// CHECK:     %25 = exts i1, i8 %24, 0, 1
+// CHECK:     %26 = const time 0s 1e
+// CHECK:     drv i1$ %q2, %25, %26
+// CHECK:     %w.prb1 = prb i8$ %w
+// CHECK:     %27 = const i2 2
+// CHECK:     %28 = const i8 0
+// CHECK:     %29 = shr i8 %w.prb1, i8 %28, i2 %27
+// CHECK:     %30 = exts i4, i8 %29, 0, 4
+// CHECK:     %31 = const time 0s 1e
+// CHECK:     drv i4$ %p0, %30, %31
+// CHECK:     %w.prb2 = prb i8$ %w
+// CHECK:     %32 = const i32 2
+// CHECK:     %33 = const i8 0
+// CHECK:     %34 = shr i8 %w.prb2, i8 %33, i32 %32
+// CHECK:     %35 = exts i4, i8 %34, 0, 4
+// CHECK:     %36 = const time 0s 1e
+// CHECK:     drv i4$ %p1, %35, %36
+// CHECK:     %v.prb = prb i8$ %v
+// CHECK:     %37 = const i32 2
+// CHECK:     %38 = const i8 0
+// CHECK:     %39 = shr i8 %v.prb, i8 %38, i32 %37
+// CHECK:     %40 = exts i4, i8 %39, 0, 4
+// CHECK:     %41 = const time 0s 1e
+// CHECK:     drv i4$ %p2, %40, %41
+// CHECK:     %o.prb2 = prb i8$ %o
+// CHECK:     %42 = const i1 0
+// CHECK:     %43 = const i8 0
+// CHECK:     %44 = shr i8 %o.prb2, i8 %43, i1 %42
+// CHECK:     %45 = exts i4, i8 %44, 0, 4
+// CHECK:     %46 = const time 0s 1e
+// CHECK:     drv i4$ %p3, %45, %46
+// CHECK:     halt
+// CHECK: }
+
+// The first struct member occupies the MSBs.
+// CHECK: entity @foo () -> () {
+// CHECK:     %0 = const i4 10
+// CHECK:     %1 = const i8 188
+// CHECK:     %2 = {i4 %0, i8 %1}
+// CHECK:     %s = sig {i4, i8} %2
+// CHECK:     %3 = const i1 1
+// CHECK:     %4 = const i4 10
+// CHECK:     %5 = const i8 188
+// CHECK:     %6 = {i4 %4, i8 %5}
+// CHECK:     %7 = const i3 3
+// CHECK:     %8 = {i1 %3, {i4, i8} %6, i3 %7}
+// CHECK:     %t = sig {i1, {i4, i8}, i3} %8
+
+// Packed array elements are stored by position, with the rightmost index at
+// position 0.
+// CHECK:     %9 = const i4 4
+// CHECK:     %10 = const i8 86
+// CHECK:     %11 = {i4 %9, i8 %10}
+// CHECK:     %12 = const i4 1
+// CHECK:     %13 = const i8 35
+// CHECK:     %14 = {i4 %12, i8 %13}
+// CHECK:     %15 = [{i4, i8} %11, %14]
+// CHECK:     %sd = sig [2 x {i4, i8}] %15
+// CHECK:     %16 = const i4 4
+// CHECK:     %17 = const i8 86
+// CHECK:     %18 = {i4 %16, i8 %17}
+// CHECK:     %19 = const i4 1
+// CHECK:     %20 = const i8 35
+// CHECK:     %21 = {i4 %19, i8 %20}
+// CHECK:     %22 = [{i4, i8} %18, %21]
+// CHECK:     %sa = sig [2 x {i4, i8}] %22
+
+// Packed unions are plain bit vectors.
+// CHECK:     %23 = const i12 2748
+// CHECK:     %u = sig i12 %23
+// CHECK:     %24 = const i4 0
+// CHECK:     %ua = sig i4 %24
+
+// The leftmost index is the MSB regardless of the range direction.
+// CHECK:     %25 = const i4 8
+// CHECK:     %pd = sig i4 %25
+// CHECK:     %26 = const i4 8
+// CHECK:     %pa = sig i4 %26
+// CHECK:     %27 = const i4 8
+// CHECK:     %nd = sig i4 %27
+// CHECK:     %28 = const i4 1
+// CHECK:     %na = sig i4 %28
+// CHECK:     %29 = const i8 0
+// CHECK:     %v = sig i8 %29
+// CHECK:     %30 = const i8 0
+// CHECK:     %w = sig i8 %30
+// CHECK:     %31 = const i8 0
+// CHECK:     %o = sig i8 %31
+// CHECK:     %32 = const i1 0
+// CHECK:     %q0 = sig i1 %32
+// CHECK:     %33 = const i1 0
+// CHECK:     %q1 = sig i1 %33
+// CHECK:     %34 = const i1 0
+// CHECK:     %q2 = sig i1 %34
+// CHECK:     %35 = const i4 0
+// CHECK:     %p0 = sig i4 %35
+// CHECK:     %36 = const i4 0
+// CHECK:     %p1 = sig i4 %36
+// CHECK:     %37 = const i4 0
+// CHECK:     %p2 = sig i4 %37
+// CHECK:     %38 = const i4 0
+// CHECK:     %p3 = sig i4 %38
+// CHECK:     inst %foo.initial.328.0 (i12$ %u, i8$ %v, i8$ %w, i8$ %o) -> (i4$ %ua, i1$ %q0, i1$ %q1, i1$ %q2, i4$ %p0, i4$ %p1, i4$ %p2, i4$ %p3)
+// CHECK:     halt
+// CHECK: }
@@ -0,0 +1,11 @@
+// RUN: moore %s -E --line-markers
+// Line markers map the preprocessed output back to the input lines.
+
+`ifdef NOPE
+logic a;
+`endif
+logic b;
+
+// CHECK: `line 1 "test/svlog/parser/line_markers.sv" 0
+// CHECK: `line 6 "test/svlog/parser/line_markers.sv" 0
+// CHECK: logic b;
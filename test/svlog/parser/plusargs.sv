@@ -0,0 +1,7 @@
+// RUN: moore %s -E +define+WIDTH=8+SIGNED
+// Defines given as plusargs, for compatibility with other compilers.
+
+`ifdef SIGNED
+logic signed [`WIDTH-1:0] x;
+`endif
+// CHECK: logic signed [8-1:0] x;
@@ -0,0 +1,8 @@
+// RUN: moore %s --syntax-only -e top
+// The input is only parsed, so the undefined module is not noticed.
+
+module top;
+    missing x();
+endmodule
+
+// CHECK: moore: 0 errors, 0 warnings
@@ -0,0 +1,30 @@
+// RUN: moore %s -e top --elaborate-only --port-map %t/ports.map
+// RUN: cat %t/ports.map
+
+interface bus;
+    logic [7:0] data;
+    logic valid;
+    modport src (output data, output valid);
+endinterface
+
+module leaf (
+    input logic clk,
+    input logic [3:0] a [2],
+    bus.src b,
+    output struct packed { logic x; logic [2:0] y; } s
+);
+endmodule
+
+module top;
+    logic clk;
+    logic [3:0] a [2];
+    struct packed { logic x; logic [2:0] y; } s;
+    bus b();
+    leaf l (.clk, .a, .b, .s);
+endmodule
+
+// CHECK: @leaf.param3	input	0	clk	i1$	port clk : logic
+// CHECK: @leaf.param3	input	1	a	[2 x i4]$	port a : logic [3:0] $ [2]
+// CHECK: @leaf.param3	output	0	b.data	i8$	port b : bus signal data : logic [7:0]
+// CHECK: @leaf.param3	output	1	b.valid	i1$	port b : bus signal valid : logic
+// CHECK: @leaf.param3	output	2	s	{i1, i3}$	port s : struct packed { logic x; logic [2:0] y; }
@@ -0,0 +1,66 @@
+// RUN: moore %s -e top
+
+module top;
+    real a, b;
+    shortreal s;
+    int i, j, k;
+    initial begin
+        a = 1.5;
+        b = a * 2.0 + 0.25;
+        s = b;
+        i = a;
+        j = 2.5;
+        k = -2.5;
+    end
+endmodule
+
+// Conversions to integers round to the nearest integer, with ties away from
+// zero, both at runtime and when folded.
+
+// CHECK: proc %top.initial.90.0 () -> (i64$ %a, i64$ %b, i32$ %s, i32$ %i, i32$ %j, i32$ %k) {
+// CHECK: 0:
+// CHECK:     %1 = prb i64$ %a
+// CHECK:     %a.shadow = var i64 %1
+// CHECK:     %2 = prb i64$ %b
+// CHECK:     %b.shadow = var i64 %2
+// CHECK:     %3 = const i64 4609434218613702656
+// CHECK:     %4 = const time 0s 1e
+// CHECK:     drv i64$ %a, %3, %4
+// CHECK:     st i64* %a.shadow, %3
+// CHECK:     %a.shadow.ld = ld i64* %a.shadow
+// CHECK:     %5 = const i64 4611686018427387904
+// CHECK:     %6 = call i64 @moore.real.mul.f64 (i64 %a.shadow.ld, i64 %5)
+// CHECK:     %7 = const i64 4598175219545276416
+// CHECK:     %8 = call i64 @moore.real.add.f64 (i64 %6, i64 %7)
+// CHECK:     drv i64$ %b, %8, %4
+// CHECK:     st i64* %b.shadow, %8
+// CHECK:     %b.shadow.ld = ld i64* %b.shadow
+// CHECK:     %9 = call i32 @moore.real.fptrunc.f64.f32 (i64 %b.shadow.ld)
+// CHECK:     drv i32$ %s, %9, %4
+// CHECK:     %a.shadow.ld1 = ld i64* %a.shadow
+// CHECK:     %10 = call i64 @moore.real.round.f64 (i64 %a.shadow.ld1)
+// CHECK:     %11 = call i32 @moore.real.fptosi.f64.i32 (i64 %10)
+// CHECK:     drv i32$ %i, %11, %4
+// CHECK:     %12 = const i32 3
+// CHECK:     drv i32$ %j, %12, %4
+// CHECK:     %13 = const i32 4294967293
+// CHECK:     drv i32$ %k, %13, %4
+// CHECK:     halt
+// CHECK: }
+// CHECK: entity @top () -> () {
+// CHECK:     %0 = const i64 0
+// CHECK:     %a = sig i64 %0
+// CHECK:     %b = sig i64 %0
+// CHECK:     %1 = const i32 0
+// CHECK:     %s = sig i32 %1
+// CHECK:     %i = sig i32 %1
+// CHECK:     %j = sig i32 %1
+// CHECK:     %k = sig i32 %1
+// CHECK:     inst %top.initial.90.0 () -> (i64$ %a, i64$ %b, i32$ %s, i32$ %i, i32$ %j, i32$ %k)
+// CHECK:     halt
+// CHECK: }
+// CHECK: declare @moore.real.mul.f64 (i64, i64) i64
+// CHECK: declare @moore.real.add.f64 (i64, i64) i64
+// CHECK: declare @moore.real.fptrunc.f64.f32 (i64) i32
+// CHECK: declare @moore.real.round.f64 (i64) i64
+// CHECK: declare @moore.real.fptosi.f64.i32 (i64) i32
@@ -3,5 +3,5 @@
 module foo;
   int a;
   int a;
-  // CHECK: error: `a` is defined multiple times
+  // CHECK: error[SV0003]: `a` is defined multiple times
 endmodule
@@ -0,0 +1,11 @@
+// RUN: moore %s -e foo --error-limit 2
+// FAIL
+module foo;
+  int a = x;
+  int b = y;
+  int c = z;
+  // CHECK: error[SV0001]: `x` not found
+  // CHECK: error[SV0001]: `y` not found
+  // CHECK: note: too many errors, stopping after 2
+  // CHECK: moore: 2 errors, 0 warnings (SV0001: 2)
+endmodule
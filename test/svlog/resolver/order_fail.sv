@@ -3,5 +3,5 @@
 module foo;
   int b = a;
   int a;
-  // CHECK: error: `a` not found
+  // CHECK: error[SV0001]: `a` not found
 endmodule
@@ -0,0 +1,62 @@
+// RUN: moore %s -e foo --coverage
+
+module foo (input logic clk, input logic [3:0] x);
+    always_ff @(posedge clk) begin
+        cover (x == 4'd3);
+        five: cover (x == 4'd5);
+    end
+endmodule
+
+// CHECK: proc %foo.always_ff.31.0 (i1$ %clk, i4$ %x) -> (i32$ %cover, i32$ %cover.five) {
+// CHECK: 0:
+// CHECK:     %1 = prb i32$ %cover
+// CHECK:     %cover.shadow = var i32 %1
+// CHECK:     %2 = prb i32$ %cover.five
+// CHECK:     %cover.five.shadow = var i32 %2
+// CHECK:     br %init
+// CHECK: init:
+// CHECK:     %clk.prb = prb i1$ %clk
+// CHECK:     wait %check, %clk
+// CHECK: check:
+// CHECK:     %3 = prb i32$ %cover
+// CHECK:     st i32* %cover.shadow, %3
+// CHECK:     %4 = prb i32$ %cover.five
+// CHECK:     st i32* %cover.five.shadow, %4
+// CHECK:     %5 = const i1 0
+// CHECK:     %6 = eq i1 %clk.prb, %5
+// CHECK:     %7 = neq i1 %clk.prb, %5
+// CHECK:     %posedge = and i1 %6, %7
+// CHECK:     br %posedge, %init, %event
+// CHECK: event:
+// CHECK:     %x.prb = prb i4$ %x
+// CHECK:     %8 = const i4 3
+// CHECK:     %9 = eq i4 %x.prb, %8
+// CHECK:     %10 = neq i1 %9, %5
+// CHECK:     %11 = const i32 1
+// CHECK:     %12 = const time 0s 1e
+// CHECK:     br %10, %cover_exit, %cover_hit
+// CHECK: cover_hit:
+// CHECK:     %cover.shadow.ld = ld i32* %cover.shadow
+// CHECK:     %13 = add i32 %cover.shadow.ld, %11
+// CHECK:     drv i32$ %cover, %13, %12
+// CHECK:     st i32* %cover.shadow, %13
+// CHECK:     br %cover_exit
+// CHECK: cover_exit:
+// CHECK:     %14 = const i4 5
+// CHECK:     %15 = eq i4 %x.prb, %14
+// CHECK:     %16 = neq i1 %15, %5
+// CHECK:     br %16, %0, %cover_hit1
+// CHECK: cover_hit1:
+// CHECK:     %cover.five.shadow.ld = ld i32* %cover.five.shadow
+// CHECK:     %17 = add i32 %cover.five.shadow.ld, %11
+// CHECK:     drv i32$ %cover.five, %17, %12
+// CHECK:     st i32* %cover.five.shadow, %17
+// CHECK:     br %0
+// CHECK: }
+// CHECK: entity @foo (i1$ %clk, i4$ %x) -> () {
+// CHECK:     %0 = const i32 0
+// CHECK:     %cover = sig i32 %0
+// CHECK:     %cover.five = sig i32 %0
+// CHECK:     inst %foo.always_ff.31.0 (i1$ %clk, i4$ %x) -> (i32$ %cover, i32$ %cover.five)
+// CHECK:     halt
+// CHECK: }
@@ -0,0 +1,61 @@
+// RUN: moore %s -e foo --cache-dir %t
+// RUN: moore %s -e foo --cache-dir %t --coverage
+// RUN: moore %s -e foo --cache-dir %t
+
+// The coverage instrumentation is part of the key of the cached output.
+
+module foo (input logic clk, input logic x);
+    always_ff @(posedge clk) cover (x);
+endmodule
+
+// CHECK: proc %foo.always_ff.19.0 (i1$ %clk) -> () {
+// CHECK: check:
+// CHECK:     wait %check, %clk
+// CHECK: }
+// CHECK: entity @foo (i1$ %clk, i1$ %x) -> () {
+// CHECK:     inst %foo.always_ff.19.0 (i1$ %clk) -> ()
+// CHECK:     halt
+// CHECK: }
+// CHECK: proc %foo.always_ff.19.0 (i1$ %clk, i1$ %x) -> (i32$ %cover) {
+// CHECK: 0:
+// CHECK:     %1 = prb i32$ %cover
+// CHECK:     %cover.shadow = var i32 %1
+// CHECK:     br %init
+// CHECK: init:
+// CHECK:     %clk.prb = prb i1$ %clk
+// CHECK:     wait %check, %clk
+// CHECK: check:
+// CHECK:     %2 = prb i32$ %cover
+// CHECK:     st i32* %cover.shadow, %2
+// CHECK:     %3 = const i1 0
+// CHECK:     %4 = eq i1 %clk.prb, %3
+// CHECK:     %5 = neq i1 %clk.prb, %3
+// CHECK:     %posedge = and i1 %4, %5
+// CHECK:     br %posedge, %init, %event
+// CHECK: event:
+// CHECK:     %x.prb = prb i1$ %x
+// CHECK:     %6 = neq i1 %x.prb, %3
+// CHECK:     br %6, %0, %cover_hit
+// CHECK: cover_hit:
+// CHECK:     %cover.shadow.ld = ld i32* %cover.shadow
+// CHECK:     %7 = const i32 1
+// CHECK:     %8 = add i32 %cover.shadow.ld, %7
+// CHECK:     %9 = const time 0s 1e
+// CHECK:     drv i32$ %cover, %8, %9
+// CHECK:     st i32* %cover.shadow, %8
+// CHECK:     br %0
+// CHECK: }
+// CHECK: entity @foo (i1$ %clk, i1$ %x) -> () {
+// CHECK:     %0 = const i32 0
+// CHECK:     %cover = sig i32 %0
+// CHECK:     inst %foo.always_ff.19.0 (i1$ %clk, i1$ %x) -> (i32$ %cover)
+// CHECK:     halt
+// CHECK: }
+// CHECK: proc %foo.always_ff.19.0 (i1$ %clk) -> () {
+// CHECK: check:
+// CHECK:     wait %check, %clk
+// CHECK: }
+// CHECK: entity @foo (i1$ %clk, i1$ %x) -> () {
+// CHECK:     inst %foo.always_ff.19.0 (i1$ %clk) -> ()
+// CHECK:     halt
+// CHECK: }
@@ -0,0 +1,10 @@
+// RUN: moore %s -e top
+// FAIL
+module top;
+  localparam int A = A + 1;
+  logic [A:0] x;
+  // CHECK: fatal: cyclic dependency: `A` depends on itself
+  // CHECK: |   localparam int A = A + 1;
+  // CHECK: = note: `A` refers to itself here:
+  // CHECK: |   localparam int A = A + 1;
+endmodule
@@ -0,0 +1,20 @@
+// RUN: moore %s -e top
+// FAIL
+package p;
+  localparam int A = q::B + 1;
+endpackage
+
+package q;
+  localparam int B = p::A;
+endpackage
+
+module top;
+  logic [p::A:0] x;
+endmodule
+
+// CHECK: fatal: cyclic dependency: `A` depends on itself
+// CHECK: |   localparam int A = q::B + 1;
+// CHECK: = note: `A` refers to `B` here:
+// CHECK: |   localparam int A = q::B + 1;
+// CHECK: = note: `B` refers to `A` here, completing the cycle:
+// CHECK: |   localparam int B = p::A;
@@ -0,0 +1,51 @@
+entity leaf is
+	generic (W : integer := 1);
+	port (a : in bit);
+end;
+architecture x of leaf is begin end;
+architecture y of leaf is begin end;
+
+entity mid is
+	port (a : in bit);
+end;
+architecture rtl of mid is
+	component leaf
+		generic (W : integer := 2);
+		port (a : in bit);
+	end component;
+	for l1: leaf use entity work.leaf(y);
+begin
+	l0: leaf port map (a);
+	l1: component leaf generic map (W => 3) port map (a => a);
+end;
+
+entity top is end;
+architecture s of top is
+	component mid
+		port (a : in bit);
+	end component;
+	signal q : bit;
+begin
+	m0: mid port map (q);
+	m1: configuration work.midcfg port map (a => q);
+end;
+
+configuration midcfg of mid is
+	for rtl
+		for l0: leaf use entity work.leaf(x); end for;
+	end for;
+end;
+
+configuration topcfg of top is
+	for s
+		for m0: mid
+			use entity work.mid;
+			for rtl
+				for all: leaf use entity work.leaf(y); end for;
+			end for;
+		end for;
+	end for;
+end;
+
+-- @elab top(s)
+-- @elab topcfg
@@ -0,0 +1,37 @@
+/*
+// RUN: moore %s -e cfg
+// Elaborating a configuration emits the configured architecture.
+// CHECK: entity @leaf_x (i1 %a) () {
+// CHECK: }
+// CHECK: entity @leaf_y (i1 %a) () {
+// CHECK: }
+// CHECK: entity @top_rtl_g1 () () {
+// CHECK:     %q = sig i1 0
+// CHECK:     %l0 = inst @leaf_x (%q) ()
+// CHECK:     %l1 = inst @leaf_y (%q) ()
+// CHECK: }
+*/
+
+entity leaf is
+	port (a : in bit);
+end;
+architecture x of leaf is begin end;
+architecture y of leaf is begin end;
+
+entity top is end;
+architecture rtl of top is
+	component leaf
+		port (a : in bit);
+	end component;
+	signal q : bit;
+begin
+	l0: leaf port map (q);
+	l1: leaf port map (q);
+end;
+
+configuration cfg of top is
+	for rtl
+		for l0: leaf use entity work.leaf(x); end for;
+		for l1: leaf use entity work.leaf(y); end for;
+	end for;
+end;
@@ -0,0 +1,37 @@
+package pkg is
+	type REC is record
+		A : BIT;
+		B : INTEGER range 0 to 15;
+	end record;
+	type ARR is array (0 to 3) of BIT;
+	type UARR is array (NATURAL range <>) of INTEGER;
+	subtype CARR is UARR (0 to 2);
+	type MAT is array (0 to 1, 0 to 2) of BIT;
+
+	constant C0 : ARR := ('0', '1', '0', '1');
+	constant C1 : ARR := (1 => '1', others => '0');
+	constant C2 : REC := (A => '1', B => 4);
+	constant C3 : CARR := (others => 3);
+	constant C4 : MAT := (('0', '1', '0'), others => (others => '1'));
+end;
+
+library work;
+use work.pkg.all;
+entity foo is end;
+
+library work;
+use work.pkg.all;
+architecture bar of foo is
+	type LOCAL is array (0 to 1) of REC;
+	constant K : ARR := C0;
+	signal s0 : ARR;
+	signal s1 : ARR := C1;
+	signal s2 : REC := C2;
+	signal s3 : REC := (B => 7, others => '0');
+	signal s4 : CARR := C3;
+	signal s5 : MAT := C4;
+	signal s6 : LOCAL;
+	signal s7 : ARR := K;
+begin end;
+
+-- @elab foo(bar)
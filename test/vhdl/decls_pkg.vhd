@@ -29,7 +29,7 @@ package pkg is
 	--signal sig_a : BIT;
 
 	-- variable_declaration
-	--variable shvar_a : BIT;
+	shared variable shvar_a : BIT;
 
 	-- file_declaration
 	--file file_a : BIT;
@@ -0,0 +1,45 @@
+entity leaf is
+	generic (W : NATURAL := 2; INIT : BIT := '0');
+	port (a : in BIT_VECTOR(W-1 downto 0); b : out BIT_VECTOR(W-1 downto 0));
+end;
+
+architecture rtl of leaf is
+	constant N : INTEGER := W * 2;
+	signal s : BIT_VECTOR(N-1 downto 0);
+begin end;
+
+library work;
+entity foo is end;
+
+architecture bar of foo is
+	constant N : INTEGER := 3;
+	signal x : BIT_VECTOR(3 downto 0);
+	signal y : BIT_VECTOR(3 downto 0);
+begin
+	i0: entity work.leaf(rtl) generic map (W => 4, INIT => '1') port map (a => x, b => y);
+	i1: entity work.leaf generic map (4) port map (x, open);
+	i2: entity work.leaf;
+
+	g0: for i in 0 to N-1 generate
+		signal t : BIT_VECTOR(i downto 0);
+	begin
+		l: entity work.leaf generic map (W => i+1) port map (a => t);
+	end generate;
+
+	g1: for i in N-1 downto N generate
+		signal t : BIT;
+	begin end generate;
+
+	g2: if N > 4 generate
+		signal u : BIT;
+	begin
+	elsif N = 3 generate
+		signal v : BIT;
+	begin
+	else generate
+		signal w : BIT;
+	begin
+	end generate;
+end;
+
+-- @elab foo(bar)
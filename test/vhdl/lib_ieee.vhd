@@ -0,0 +1,24 @@
+package pkg is
+	constant WIDTH : integer := 4;
+end;
+
+library ieee;
+use ieee.std_logic_1164.all;
+use IEEE.NUMERIC_STD.all;
+use work.pkg.all;
+use std.standard.all;
+
+entity foo is
+	port (q : out std_logic_vector(WIDTH-1 downto 0));
+end;
+
+architecture bar of foo is
+	signal a : std_ulogic := 'Z';
+	signal b : std_logic_vector(3 downto 0) := "01ZX";
+	signal c : unsigned(WIDTH-1 downto 0) := "0000";
+	signal d : ieee.numeric_std.signed(1 downto 0) := "1-";
+	signal e : bit_vector(1 downto 0);
+begin
+end;
+
+-- @elab foo(bar)
@@ -0,0 +1,29 @@
+entity foo is end;
+
+architecture bar of foo is
+	type DISTANCE is range 0 to 1E16 units
+		um;
+		mm = 1000 um;
+		cm = 10 mm;
+		m  = 100 cm;
+	end units;
+
+	constant d0 : DISTANCE := 5 cm;
+	constant d1 : DISTANCE := 2.5 mm;
+	constant d2 : DISTANCE := m;
+
+	constant t0 : TIME := 10 ns;
+	constant t1 : TIME := 2 * t0;
+	constant t2 : TIME := 1 ms + 5 us;
+	constant t3 : DELAY_LENGTH := 1.5 ns;
+begin
+	p : process
+	begin
+		wait for t0;
+		wait for t1 - 5 ns;
+		wait for t3;
+		wait;
+	end process;
+end;
+
+-- @elab foo
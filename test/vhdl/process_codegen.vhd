@@ -0,0 +1,54 @@
+library ieee;
+use ieee.std_logic_1164.all;
+
+entity foo is
+	port (
+		clk : in std_logic;
+		rst : in bit;
+		d : in integer;
+		q : out integer
+	);
+end;
+
+architecture bar of foo is
+	signal count : integer := 0;
+	signal en : boolean;
+	signal s : std_logic;
+begin
+	comb : process (all)
+		variable tmp : integer;
+	begin
+		tmp := d + 1;
+		if tmp > 10 and not en then
+			q <= tmp - 10;
+		elsif tmp = 5 then
+			q <= 0 after 2 ns;
+		else
+			q <= tmp;
+		end if;
+	end process;
+
+	seq : process (clk)
+	begin
+		if clk'event and clk = '1' then
+			if rst = '1' then
+				count <= 0;
+			else
+				count <= count + 1;
+			end if;
+		end if;
+	end process;
+
+	stim : process
+	begin
+		en <= false;
+		s <= 'Z';
+		wait for 10 ns;
+		en <= true, false after 5 ns;
+		wait on clk;
+		wait until clk = '1';
+		wait;
+	end process;
+end;
+
+-- @elab foo
@@ -0,0 +1,24 @@
+entity foo is
+	port (
+		clk : in bit;
+		d : in bit
+	);
+end;
+
+architecture bar of foo is
+	signal q : bit;
+begin
+	p0 : process (all)
+	begin
+	end process;
+
+	p1 : process (clk, d, q)
+	begin
+	end process;
+
+	p2 : process (clk)
+	begin
+	end process;
+end;
+
+-- @elab foo
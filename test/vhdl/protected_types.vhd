@@ -0,0 +1,32 @@
+package pkg is
+	type SharedCounter is protected
+		procedure increment (N : INTEGER);
+		impure function value return INTEGER;
+	end protected SharedCounter;
+
+	shared variable counter : SharedCounter;
+end package;
+
+package body pkg is
+	type SharedCounter is protected body
+		variable count : INTEGER := 0;
+
+		procedure increment (N : INTEGER) is
+		begin
+			count := count + N;
+		end procedure increment;
+
+		impure function value return INTEGER is
+		begin
+			return count;
+		end function value;
+	end protected body SharedCounter;
+end package body;
+
+use work.pkg.all;
+
+entity foo is end;
+architecture bar of foo is
+begin end;
+
+-- @elab foo
@@ -0,0 +1,24 @@
+entity foo is
+	port (
+		clk : in BIT;
+		req : in BIT;
+		ack : in BIT
+	);
+end;
+
+architecture bar of foo is
+	-- psl default clock is clk'event and clk = '1';
+	property handshake is always (req = '1' -> next ack = '1');
+	sequence burst is {req = '1'; req = '1'[*2]; ack = '1'};
+begin
+	-- psl assert always req = '1' -> next ack = '1';
+	-- psl cover {req = '1'; ack = '0'[*]; ack = '1'};
+	a0: assert always {req = '1'} |=> {ack = '0'[->1]} abort clk = '0';
+	assume never (req = '1' and ack = '1');
+	cover {req = '1' [+] ; ack = '1'}! report "covered";
+	restrict {req = '0'; req = '1'};
+	assert (always req = '1' until! ack = '1') @ (clk'event and clk = '1');
+	assert always eventually! ack = '1' severity warning;
+end;
+
+-- @elab foo
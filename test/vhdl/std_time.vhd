@@ -2,7 +2,7 @@ entity foo is end;
 architecture bar of foo is
 
 	constant xA : std.standard.TIME;
-	--constant xB : TIME := 10 ns;
+	constant xB : TIME := 10 ns;
 
 	constant yA: std.standard.DELAY_LENGTH;
 